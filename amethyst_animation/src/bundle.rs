@@ -3,13 +3,16 @@ use std::marker;
 use amethyst_assets::AssetStorage;
 use amethyst_core::{ECSBundle, Result};
 use amethyst_renderer::JointTransforms;
+use shrev::EventChannel;
 use specs::{Component, DispatcherBuilder, World};
 
-use resources::{Animation, AnimationControlSet, AnimationHierarchy, AnimationSampling,
-                AnimationSet, Sampler, SamplerControlSet};
+use ik::{TwoBoneIk, TwoBoneIkSystem};
+use resources::{Animation, AnimationControlSet, AnimationEvent, AnimationHierarchy,
+                AnimationSampling, AnimationSet, AnimationTimeScale, Sampler, SamplerControlSet};
 use skinning::{Joint, Skin, VertexSkinningSystem};
-use systems::{AnimationControlSystem, AnimationProcessor, SamplerInterpolationSystem,
-              SamplerProcessor};
+use state_machine::AnimationStateMachine;
+use systems::{AnimationControlSystem, AnimationProcessor, AnimationStateMachineSystem,
+              SamplerInterpolationSystem, SamplerProcessor};
 
 /// Bundle for vertex skinning
 ///
@@ -50,10 +53,44 @@ impl<'a, 'b, 'c> ECSBundle<'a, 'b> for VertexSkinningBundle<'c> {
     }
 }
 
+/// Bundle for two bone inverse kinematics
+///
+/// This registers `TwoBoneIkSystem`.
+/// Note that the user must make sure this system runs after the sampling systems and before
+/// `TransformSystem`
+#[derive(Default)]
+pub struct TwoBoneIkBundle<'a> {
+    dep: &'a [&'a str],
+}
+
+impl<'a> TwoBoneIkBundle<'a> {
+    /// Create a new inverse kinematics bundle
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set dependencies for the `TwoBoneIkSystem`
+    pub fn with_dep(mut self, dep: &'a [&'a str]) -> Self {
+        self.dep = dep;
+        self
+    }
+}
+
+impl<'a, 'b, 'c> ECSBundle<'a, 'b> for TwoBoneIkBundle<'c> {
+    fn build(
+        self,
+        world: &mut World,
+        builder: DispatcherBuilder<'a, 'b>,
+    ) -> Result<DispatcherBuilder<'a, 'b>> {
+        world.register::<TwoBoneIk>();
+        Ok(builder.add(TwoBoneIkSystem::new(), "two_bone_ik_system", self.dep))
+    }
+}
+
 /// Bundle for only the sampler interpolation.
 ///
 /// Will add `SamplerInterpolationSystem<T>` with the given name.
-/// Will also add `SamplerProcessor<T::Primitive>`.
+/// Will also add `SamplerProcessor<T::Primitive>`, and the `AnimationTimeScale` resource.
 ///
 /// ### Type parameters:
 ///
@@ -99,6 +136,7 @@ where
             .res
             .entry()
             .or_insert_with(AssetStorage::<Sampler<T::Primitive>>::new);
+        world.res.entry().or_insert_with(AnimationTimeScale::default);
         world.register::<SamplerControlSet<T>>();
         Ok(builder
             .add(SamplerProcessor::<T::Primitive>::new(), "", &[])
@@ -111,7 +149,10 @@ where
 /// This will also add `SamplingBundle`, because it is a dependency of this bundle.
 ///
 /// Will add `AnimationControlSystem<T>` with the given name.
-/// Will also add `AnimationProcessor<T>`.
+/// Will also add `AnimationProcessor<T>`, and `AnimationStateMachineSystem<I, T>` with the name
+/// of the `AnimationControlSystem<T>` followed by `_state_machine`, which the
+/// `AnimationControlSystem<T>` depends on.
+/// Will also add the `EventChannel<AnimationEvent<I>>` resource the marker events are sent through.
 ///
 /// ### Type parameters:
 ///
@@ -163,11 +204,26 @@ where
         world.register::<AnimationControlSet<I, T>>();
         world.register::<AnimationHierarchy<T>>();
         world.register::<AnimationSet<T>>();
-        builder = builder.add(AnimationProcessor::<T>::new(), "", &[]).add(
-            AnimationControlSystem::<I, T>::new(),
-            self.animation_name,
-            self.dep,
-        );
+        world.register::<AnimationStateMachine<I, T>>();
+        world
+            .res
+            .entry()
+            .or_insert_with(EventChannel::<AnimationEvent<I>>::new);
+        let state_machine_name = format!("{}_state_machine", self.animation_name);
+        let mut control_dep: Vec<&str> = self.dep.to_vec();
+        control_dep.push(state_machine_name.as_str());
+        builder = builder
+            .add(AnimationProcessor::<T>::new(), "", &[])
+            .add(
+                AnimationStateMachineSystem::<I, T>::new(),
+                &state_machine_name,
+                self.dep,
+            )
+            .add(
+                AnimationControlSystem::<I, T>::new(),
+                self.animation_name,
+                &control_dep,
+            );
         SamplingBundle::<T>::new(self.sampling_name)
             .with_dep(&[self.animation_name])
             .build(world, builder)
@@ -0,0 +1,77 @@
+use amethyst_core::cgmath::Rad;
+use amethyst_renderer::{Camera, Projection};
+
+use resources::{AnimationSampling, BlendMethod};
+use util::SamplerPrimitive;
+
+/// Channels that can be animated on `Camera`
+///
+/// Channels that don't apply to the projection of the camera are ignored, like the field of view
+/// of an orthographic camera. Note that `AutoFov` overrides the field of view of the cameras it is
+/// attached to.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CameraChannel {
+    /// Vertical field of view of a perspective camera in radians, sampled as `Scalar`
+    Fov,
+    /// Distance of the near plane, sampled as `Scalar`
+    Near,
+    /// Distance of the far plane, sampled as `Scalar`
+    Far,
+    /// Left, right, top and bottom planes of an orthographic camera, sampled as `Vec4`
+    Extents,
+}
+
+impl AnimationSampling for Camera {
+    type Channel = CameraChannel;
+    type Primitive = SamplerPrimitive<f32>;
+
+    fn apply_sample(&mut self, channel: &Self::Channel, data: &SamplerPrimitive<f32>) {
+        use self::CameraChannel::*;
+        use util::SamplerPrimitive::*;
+        let mut projection = self.projection();
+        match (channel, &mut projection, *data) {
+            (&Fov, &mut Projection::Perspective(ref mut p), Scalar(d)) => p.fovy = Rad(d),
+            (&Fov, &mut Projection::Orthographic(_), Scalar(_)) => {}
+            (&Near, &mut Projection::Perspective(ref mut p), Scalar(d)) => p.near = d,
+            (&Near, &mut Projection::Orthographic(ref mut o), Scalar(d)) => o.near = d,
+            (&Far, &mut Projection::Perspective(ref mut p), Scalar(d)) => p.far = d,
+            (&Far, &mut Projection::Orthographic(ref mut o), Scalar(d)) => o.far = d,
+            (&Extents, &mut Projection::Orthographic(ref mut o), Vec4(d)) => {
+                o.left = d[0];
+                o.right = d[1];
+                o.top = d[2];
+                o.bottom = d[3];
+            }
+            (&Extents, &mut Projection::Perspective(_), Vec4(_)) => {}
+            _ => panic!("Attempt to apply invalid sample to Camera"),
+        }
+        self.proj = projection.into();
+    }
+
+    fn current_sample(&self, channel: &Self::Channel) -> SamplerPrimitive<f32> {
+        use self::CameraChannel::*;
+        match (channel, self.projection()) {
+            (&Fov, Projection::Perspective(p)) => SamplerPrimitive::Scalar(p.fovy.0),
+            (&Near, Projection::Perspective(p)) => SamplerPrimitive::Scalar(p.near),
+            (&Near, Projection::Orthographic(o)) => SamplerPrimitive::Scalar(o.near),
+            (&Far, Projection::Perspective(p)) => SamplerPrimitive::Scalar(p.far),
+            (&Far, Projection::Orthographic(o)) => SamplerPrimitive::Scalar(o.far),
+            (&Extents, Projection::Orthographic(o)) => {
+                SamplerPrimitive::Vec4([o.left, o.right, o.top, o.bottom])
+            }
+            _ => Self::default_primitive(channel),
+        }
+    }
+
+    fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
+        use self::CameraChannel::*;
+        match channel {
+            &Fov | &Near | &Far => SamplerPrimitive::Scalar(0.),
+            &Extents => SamplerPrimitive::Vec4([0.; 4]),
+        }
+    }
+
+    fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
+        Some(BlendMethod::Linear)
+    }
+}
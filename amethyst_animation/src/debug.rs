@@ -0,0 +1,77 @@
+use amethyst_core::{GlobalTransform, Parent, Transform};
+use amethyst_renderer::{DebugLines, Rgba};
+use hibitset::BitSet;
+use specs::{Entities, FetchMut, Join, ReadStorage, System};
+
+use resources::AnimationHierarchy;
+use skinning::Skin;
+
+/// System drawing skeletons with `DebugLines`, to diagnose skinning and animation problems.
+///
+/// Draws the joints of each `Skin` and the nodes of each `AnimationHierarchy<Transform>` as small
+/// axes, and the bones between the nodes and their parent nodes as lines.
+///
+/// Needs to run after global transforms have been updated for the current frame, and the
+/// `DebugLines` resource to be drawn by a `DrawDebugLines` pass.
+pub struct SkeletonDebugSystem {
+    /// Length of the axes drawn at each node
+    pub axis_length: f32,
+    /// Color of the bones
+    pub bone_color: Rgba,
+    /// Scratch space, used to collect the nodes to draw.
+    nodes: BitSet,
+}
+
+impl SkeletonDebugSystem {
+    /// Create a new system drawing the axes of the nodes with the given length
+    pub fn new(axis_length: f32) -> Self {
+        SkeletonDebugSystem {
+            axis_length,
+            bone_color: Rgba::white(),
+            nodes: BitSet::new(),
+        }
+    }
+}
+
+impl<'a> System<'a> for SkeletonDebugSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Skin>,
+        ReadStorage<'a, AnimationHierarchy<Transform>>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, GlobalTransform>,
+        FetchMut<'a, DebugLines>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, skins, hierarchies, parents, globals, mut lines): Self::SystemData,
+    ) {
+        self.nodes.clear();
+        for skin in skins.join() {
+            for joint in &skin.joints {
+                self.nodes.add(joint.id());
+            }
+        }
+        for hierarchy in hierarchies.join() {
+            for node in hierarchy.nodes.values() {
+                self.nodes.add(node.id());
+            }
+        }
+
+        for (_, entity, global) in (&self.nodes, &*entities, &globals).join() {
+            lines.add_axes(global, self.axis_length);
+            let parent = match parents.get(entity) {
+                Some(parent) if self.nodes.contains(parent.entity.id()) => parent.entity,
+                _ => continue,
+            };
+            if let Some(parent) = globals.get(parent) {
+                lines.add_line(position(parent), position(global), self.bone_color);
+            }
+        }
+    }
+}
+
+fn position(global: &GlobalTransform) -> [f32; 3] {
+    global.0.w.truncate().into()
+}
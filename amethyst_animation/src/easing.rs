@@ -0,0 +1,269 @@
+//! Easing curves between key frames, for tween-style animations authored with few key frames.
+//!
+//! The functions match the signature of `InterpolationFunction::Function`, for example
+//! `InterpolationFunction::Function(ease_in_out_cubic)`. Custom curves are defined by a function
+//! calling `cubic_bezier` with the control points of the curve:
+//!
+//! ```rust,ignore
+//! fn ease(input: f32, inputs: &[f32], outputs: &[SamplerPrimitive<f32>], normalize: bool)
+//!     -> SamplerPrimitive<f32>
+//! {
+//!     cubic_bezier([0.25, 0.1, 0.25, 1.0], input, inputs, outputs, normalize)
+//! }
+//! ```
+
+use minterpolate::{get_input_index, InterpolationPrimitive};
+
+/// Iterations of Newton's method when solving a bezier curve
+const NEWTON_ITERATIONS: usize = 8;
+
+/// Precision of the solution of a bezier curve
+const EPSILON: f32 = 1e-6;
+
+/// Quadratic ease in, starting slowly
+pub fn ease_in_quad<T>(input: f32, inputs: &[f32], outputs: &[T], normalize: bool) -> T
+where
+    T: InterpolationPrimitive + Clone,
+{
+    ease(input, inputs, outputs, normalize, |d| d * d)
+}
+
+/// Quadratic ease out, ending slowly
+pub fn ease_out_quad<T>(input: f32, inputs: &[f32], outputs: &[T], normalize: bool) -> T
+where
+    T: InterpolationPrimitive + Clone,
+{
+    ease(input, inputs, outputs, normalize, |d| d * (2. - d))
+}
+
+/// Quadratic ease in and out, starting and ending slowly
+pub fn ease_in_out_quad<T>(input: f32, inputs: &[f32], outputs: &[T], normalize: bool) -> T
+where
+    T: InterpolationPrimitive + Clone,
+{
+    ease(input, inputs, outputs, normalize, |d| {
+        if d < 0.5 {
+            2. * d * d
+        } else {
+            1. - 2. * (1. - d) * (1. - d)
+        }
+    })
+}
+
+/// Cubic ease in, starting slowly
+pub fn ease_in_cubic<T>(input: f32, inputs: &[f32], outputs: &[T], normalize: bool) -> T
+where
+    T: InterpolationPrimitive + Clone,
+{
+    ease(input, inputs, outputs, normalize, |d| d * d * d)
+}
+
+/// Cubic ease out, ending slowly
+pub fn ease_out_cubic<T>(input: f32, inputs: &[f32], outputs: &[T], normalize: bool) -> T
+where
+    T: InterpolationPrimitive + Clone,
+{
+    ease(input, inputs, outputs, normalize, |d| 1. - (1. - d).powi(3))
+}
+
+/// Cubic ease in and out, starting and ending slowly
+pub fn ease_in_out_cubic<T>(input: f32, inputs: &[f32], outputs: &[T], normalize: bool) -> T
+where
+    T: InterpolationPrimitive + Clone,
+{
+    ease(input, inputs, outputs, normalize, |d| {
+        if d < 0.5 {
+            4. * d * d * d
+        } else {
+            1. - 4. * (1. - d).powi(3)
+        }
+    })
+}
+
+/// Ease along a cubic bezier curve from (0, 0) to (1, 1), like the CSS `cubic-bezier` timing
+/// function.
+///
+/// ## Parameters:
+///
+/// - `points`: the control points of the curve, `[x1, y1, x2, y2]`, the x coordinates must be
+///             between 0 and 1
+/// - `input`: the input value to the function
+/// - `inputs`: list of discrete input values for each keyframe
+/// - `outputs`: list of output values to interpolate between, the same size as `inputs`
+/// - `normalize`: if true, normalize the interpolated value before returning it
+pub fn cubic_bezier<T>(
+    points: [f32; 4],
+    input: f32,
+    inputs: &[f32],
+    outputs: &[T],
+    normalize: bool,
+) -> T
+where
+    T: InterpolationPrimitive + Clone,
+{
+    ease(input, inputs, outputs, normalize, |d| {
+        let t = solve_bezier(points[0], points[2], d);
+        bezier(points[1], points[3], t)
+    })
+}
+
+/// Interpolate between the key frames around the input, with the factor given by the curve.
+fn ease<T, F>(input: f32, inputs: &[f32], outputs: &[T], normalize: bool, curve: F) -> T
+where
+    T: InterpolationPrimitive + Clone,
+    F: Fn(f32) -> f32,
+{
+    let index = match get_input_index(input, inputs) {
+        Some(index) => index,
+        None => return outputs[0].clone(),
+    };
+    if index >= inputs.len() - 1 {
+        return outputs[outputs.len() - 1].clone();
+    }
+    let d = (input - inputs[index]) / (inputs[index + 1] - inputs[index]);
+    let left = &outputs[index];
+    let right = &outputs[index + 1];
+    let v = left.add(&right.sub(left).mul(curve(d)));
+    if normalize {
+        v.normalize()
+    } else {
+        v
+    }
+}
+
+/// Coordinate of a bezier curve from 0 to 1 with the given control coordinates.
+fn bezier(p1: f32, p2: f32, t: f32) -> f32 {
+    let u = 1. - t;
+    3. * u * u * t * p1 + 3. * u * t * t * p2 + t * t * t
+}
+
+fn bezier_slope(p1: f32, p2: f32, t: f32) -> f32 {
+    let u = 1. - t;
+    3. * u * u * p1 + 6. * u * t * (p2 - p1) + 3. * t * t * (1. - p2)
+}
+
+/// Find the parameter of the curve at the given x coordinate, with Newton's method, falling back
+/// to bisection where the curve is too flat.
+fn solve_bezier(x1: f32, x2: f32, x: f32) -> f32 {
+    solve_bezier_newton(x1, x2, x).unwrap_or_else(|| solve_bezier_bisection(x1, x2, x))
+}
+
+/// Newton's method, `None` if it doesn't converge or the slope of the curve is too flat.
+fn solve_bezier_newton(x1: f32, x2: f32, x: f32) -> Option<f32> {
+    let mut t = x;
+    for _ in 0..NEWTON_ITERATIONS {
+        let error = bezier(x1, x2, t) - x;
+        if error.abs() < EPSILON {
+            return Some(t);
+        }
+        let slope = bezier_slope(x1, x2, t);
+        if slope.abs() < EPSILON {
+            return None;
+        }
+        t -= error / slope;
+    }
+    None
+}
+
+fn solve_bezier_bisection(x1: f32, x2: f32, x: f32) -> f32 {
+    let (mut low, mut high) = (0., 1.);
+    let mut t = x;
+    while high - low > EPSILON {
+        let value = bezier(x1, x2, t);
+        if (value - x).abs() < EPSILON {
+            break;
+        }
+        if value < x {
+            low = t;
+        } else {
+            high = t;
+        }
+        t = (low + high) / 2.;
+    }
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bezier, cubic_bezier, ease_in_cubic, ease_in_out_cubic, ease_in_out_quad,
+                ease_in_quad, ease_out_cubic, ease_out_quad, solve_bezier, solve_bezier_bisection,
+                solve_bezier_newton};
+    use util::SamplerPrimitive;
+
+    type Curve = fn(f32, &[f32], &[SamplerPrimitive<f32>], bool) -> SamplerPrimitive<f32>;
+
+    fn bezier_ease(
+        input: f32,
+        inputs: &[f32],
+        outputs: &[SamplerPrimitive<f32>],
+        normalize: bool,
+    ) -> SamplerPrimitive<f32> {
+        cubic_bezier([0.25, 0.1, 0.25, 1.0], input, inputs, outputs, normalize)
+    }
+
+    fn bezier_linear(
+        input: f32,
+        inputs: &[f32],
+        outputs: &[SamplerPrimitive<f32>],
+        normalize: bool,
+    ) -> SamplerPrimitive<f32> {
+        cubic_bezier([0., 0., 1., 1.], input, inputs, outputs, normalize)
+    }
+
+    fn bezier_flat(
+        input: f32,
+        inputs: &[f32],
+        outputs: &[SamplerPrimitive<f32>],
+        normalize: bool,
+    ) -> SamplerPrimitive<f32> {
+        cubic_bezier([0., 0., 0., 1.], input, inputs, outputs, normalize)
+    }
+
+    const CURVES: [Curve; 9] = [
+        ease_in_quad,
+        ease_out_quad,
+        ease_in_out_quad,
+        ease_in_cubic,
+        ease_out_cubic,
+        ease_in_out_cubic,
+        bezier_ease,
+        bezier_linear,
+        bezier_flat,
+    ];
+
+    fn sample(curve: Curve, input: f32) -> f32 {
+        let outputs = [SamplerPrimitive::Scalar(0.), SamplerPrimitive::Scalar(1.)];
+        match curve(input, &[0., 1.], &outputs, false) {
+            SamplerPrimitive::Scalar(value) => value,
+            _ => panic!("Easing changed the primitive"),
+        }
+    }
+
+    #[test]
+    fn curves_hit_ends() {
+        for &curve in &CURVES {
+            assert!(sample(curve, 0.).abs() < 1e-5);
+            assert!((sample(curve, 1. - 1e-6) - 1.).abs() < 1e-4);
+            assert!((sample(curve, 1.) - 1.).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn linear_bezier_matches_linear_interpolation() {
+        for i in 0..21 {
+            let input = i as f32 / 20.;
+            assert!((sample(bezier_linear, input) - input).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn flat_bezier_falls_back_to_bisection() {
+        // With x1 = x2 = 0 the curve is x = t³, too flat near 0 for Newton's method
+        let x: f32 = 1e-4;
+        assert_eq!(solve_bezier_newton(0., 0., x), None);
+        let t = solve_bezier(0., 0., x);
+        assert_eq!(t, solve_bezier_bisection(0., 0., x));
+        assert!((bezier(0., 0., t) - x).abs() < 1e-5);
+        assert!((t - x.cbrt()).abs() < 1e-3);
+    }
+}
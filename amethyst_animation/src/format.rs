@@ -0,0 +1,117 @@
+use std::marker;
+use std::str::from_utf8;
+
+use amethyst_assets::{Handle, Result, ResultExt, SimpleFormat};
+use fnv::FnvHashMap;
+use minterpolate::InterpolationPrimitive;
+use ron::de::from_str;
+use serde::de::DeserializeOwned;
+
+use resources::{Animation, AnimationMarker, AnimationSampling, QuantizeRotation, Sampler};
+
+/// Options of `SamplerFormat`, compressing the samplers when they are loaded.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SamplerCompression {
+    /// The tolerance the key frames are compressed with, see `Sampler::compress`, `None` to keep
+    /// all the key frames
+    pub tolerance: Option<f32>,
+    /// Quantize the rotations before compressing the key frames, see
+    /// `Sampler::quantize_rotations`. Only for rotation samplers, as every `Vec4` is taken for a
+    /// quaternion.
+    pub quantize_rotations: bool,
+}
+
+/// Allows loading samplers from RON files containing a `Sampler`.
+///
+/// The options are a `SamplerCompression`, which keeps the samplers as they are by default.
+///
+/// Samplers using `InterpolationFunction::Function` can't be loaded, as functions can't be
+/// serialized.
+#[derive(Clone, Debug, Default)]
+pub struct SamplerFormat;
+
+impl<T> SimpleFormat<Sampler<T>> for SamplerFormat
+where
+    T: InterpolationPrimitive + QuantizeRotation + DeserializeOwned + Send + Sync + 'static,
+{
+    const NAME: &'static str = "SAMPLER";
+
+    type Options = SamplerCompression;
+
+    fn import(&self, bytes: Vec<u8>, compression: SamplerCompression) -> Result<Sampler<T>> {
+        let mut sampler: Sampler<T> =
+            from_str(from_utf8(&bytes)?).chain_err(|| "Failed to parse sampler")?;
+        if compression.quantize_rotations {
+            sampler.quantize_rotations();
+        }
+        if let Some(tolerance) = compression.tolerance {
+            sampler.compress(tolerance);
+        }
+        Ok(sampler)
+    }
+}
+
+/// Contents of an `AnimationFormat` file.
+///
+/// ### Type parameters:
+///
+/// - `C`: the channel type of the component the animation applies to
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AnimationDefinition<C> {
+    /// The node index in the `AnimationHierarchy`, the channel and the name of the sampler of
+    /// each node
+    pub nodes: Vec<(usize, C, String)>,
+    /// Event markers
+    #[serde(default)]
+    pub events: Vec<AnimationMarker>,
+}
+
+/// Allows loading animations from RON files containing an `AnimationDefinition`.
+///
+/// The options are the handles of the samplers the nodes refer to, by name, usually loaded with
+/// `SamplerFormat`.
+///
+/// ### Type parameters:
+///
+/// - `T`: the component type that the animation should be applied to
+pub struct AnimationFormat<T>(marker::PhantomData<T>);
+
+impl<T> AnimationFormat<T> {
+    /// Create a new animation format
+    pub fn new() -> Self {
+        AnimationFormat(marker::PhantomData)
+    }
+}
+
+impl<T> Clone for AnimationFormat<T> {
+    fn clone(&self) -> Self {
+        AnimationFormat::new()
+    }
+}
+
+impl<T> SimpleFormat<Animation<T>> for AnimationFormat<T>
+where
+    T: AnimationSampling,
+    T::Channel: DeserializeOwned,
+{
+    const NAME: &'static str = "ANIMATION";
+
+    type Options = FnvHashMap<String, Handle<Sampler<T::Primitive>>>;
+
+    fn import(&self, bytes: Vec<u8>, samplers: Self::Options) -> Result<Animation<T>> {
+        let definition: AnimationDefinition<T::Channel> =
+            from_str(from_utf8(&bytes)?).chain_err(|| "Failed to parse animation")?;
+        let nodes = definition
+            .nodes
+            .into_iter()
+            .map(|(node_index, channel, name)| match samplers.get(&name) {
+                Some(sampler) => Ok((node_index, channel, sampler.clone())),
+                None => Err(format!("Unknown sampler {:?}", name).into()),
+            })
+            .collect::<Result<_>>()?;
+        Ok(Animation {
+            nodes,
+            events: definition.events,
+        })
+    }
+}
@@ -0,0 +1,5 @@
+pub use self::resources::*;
+pub use self::systems::*;
+
+mod resources;
+mod systems;
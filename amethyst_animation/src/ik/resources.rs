@@ -0,0 +1,57 @@
+use amethyst_core::cgmath::Vector3;
+use specs::{Component, DenseVecStorage, Entity};
+
+/// Two bone inverse kinematics chain, for example a leg placing its foot on the ground or an arm
+/// reaching for an object. Can be attached to any entity, usually the root of the hierarchy.
+///
+/// The `TwoBoneIkSystem` rotates the `root` and `middle` nodes so that the `end` node reaches the
+/// position of the `target` entity, after the animations were sampled. The nodes must have a
+/// `Transform`, and `middle` must be a descendant of `root` and `end` of `middle`.
+#[derive(Clone, Debug)]
+pub struct TwoBoneIk {
+    /// First node of the chain, like the thigh
+    pub root: Entity,
+    /// Node the chain bends at, like the knee
+    pub middle: Entity,
+    /// Node reaching the target, like the foot
+    pub end: Entity,
+    /// Entity the end of the chain reaches for
+    pub target: Entity,
+    /// Direction the middle node bends towards, in the space of the parent of the root node.
+    /// `None` keeps the direction it bends towards in the animation.
+    pub pole: Option<Vector3<f32>>,
+    /// Strength of the correction, from 0.0 for the animated pose to 1.0 to fully reach the
+    /// target, default is 1.0
+    pub weight: f32,
+}
+
+impl TwoBoneIk {
+    /// Create a new chain reaching for the target at full strength
+    pub fn new(root: Entity, middle: Entity, end: Entity, target: Entity) -> Self {
+        TwoBoneIk {
+            root,
+            middle,
+            end,
+            target,
+            pole: None,
+            weight: 1.0,
+        }
+    }
+
+    /// Bend the middle node towards the given direction, in the space of the parent of the root
+    /// node
+    pub fn with_pole(mut self, pole: Vector3<f32>) -> Self {
+        self.pole = Some(pole);
+        self
+    }
+
+    /// Set the strength of the correction
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+}
+
+impl Component for TwoBoneIk {
+    type Storage = DenseVecStorage<Self>;
+}
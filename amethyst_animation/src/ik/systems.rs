@@ -0,0 +1,142 @@
+use amethyst_core::{Parent, Transform};
+use amethyst_core::cgmath::{InnerSpace, Matrix4, One, Quaternion, SquareMatrix, Vector3};
+use specs::{Entity, Join, ReadStorage, System, WriteStorage};
+
+use super::resources::*;
+
+/// Distance under which positions are considered equal
+const EPSILON: f32 = 1e-5;
+
+/// System solving `TwoBoneIk` chains.
+///
+/// Needs to run after the animations were sampled, and before `TransformSystem` so the global
+/// transforms include the corrections.
+#[derive(Default)]
+pub struct TwoBoneIkSystem;
+
+impl TwoBoneIkSystem {
+    pub fn new() -> Self {
+        TwoBoneIkSystem
+    }
+}
+
+impl<'a> System<'a> for TwoBoneIkSystem {
+    type SystemData = (
+        ReadStorage<'a, TwoBoneIk>,
+        ReadStorage<'a, Parent>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(&mut self, (chains, parents, mut locals): Self::SystemData) {
+        for chain in chains.join() {
+            solve(chain, &parents, &mut locals);
+        }
+    }
+}
+
+/// Rotate the root and middle nodes of the chain so that the end node reaches the target.
+fn solve(chain: &TwoBoneIk, parents: &ReadStorage<Parent>, locals: &mut WriteStorage<Transform>) {
+    if chain.weight <= 0. {
+        return;
+    }
+    let a = position(chain.root, parents, locals);
+    let b = position(chain.middle, parents, locals);
+    let c = position(chain.end, parents, locals);
+    let t = position(chain.target, parents, locals);
+    let upper = (b - a).magnitude();
+    let lower = (c - b).magnitude();
+    let reach = t - a;
+    if upper < EPSILON || lower < EPSILON || reach.magnitude() < EPSILON {
+        return;
+    }
+
+    // Targets out of reach are approached as close as possible
+    let distance = reach
+        .magnitude()
+        .max((upper - lower).abs() + EPSILON)
+        .min(upper + lower - EPSILON);
+    let direction = reach.normalize();
+    let pole = match chain.pole {
+        Some(pole) => parent_rotation(chain.root, parents, locals) * pole,
+        None => b - a,
+    };
+    // Bend in the plane of the pole, or of the animated pose if the pole points at the target
+    let bend = [pole, b - a]
+        .iter()
+        .map(|v| *v - direction * v.dot(direction))
+        .find(|v| v.magnitude() > EPSILON);
+    let bend = match bend {
+        Some(bend) => bend.normalize(),
+        None => return,
+    };
+
+    // Law of cosines for the angle between the upper bone and the target
+    let cos = (upper * upper + distance * distance - lower * lower) / (2. * upper * distance);
+    let cos = cos.max(-1.).min(1.);
+    let middle = a + (direction * cos + bend * (1. - cos * cos).sqrt()) * upper;
+    let end = a + direction * distance;
+
+    let rotation = Quaternion::from_arc(b - a, middle - a, None);
+    rotate(chain.root, Quaternion::one().nlerp(rotation, chain.weight), parents, locals);
+
+    let b = position(chain.middle, parents, locals);
+    let c = position(chain.end, parents, locals);
+    let rotation = Quaternion::from_arc(c - b, end - b, None);
+    rotate(chain.middle, Quaternion::one().nlerp(rotation, chain.weight), parents, locals);
+}
+
+/// Global matrix of the entity, computed from the local transforms as the global transforms are
+/// only updated by `TransformSystem` later in the frame.
+fn global_matrix(
+    entity: Entity,
+    parents: &ReadStorage<Parent>,
+    locals: &WriteStorage<Transform>,
+) -> Matrix4<f32> {
+    let mut matrix = Matrix4::identity();
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        if let Some(local) = locals.get(entity) {
+            matrix = local.matrix() * matrix;
+        }
+        current = parents.get(entity).map(|parent| parent.entity);
+    }
+    matrix
+}
+
+fn position(
+    entity: Entity,
+    parents: &ReadStorage<Parent>,
+    locals: &WriteStorage<Transform>,
+) -> Vector3<f32> {
+    global_matrix(entity, parents, locals).w.truncate()
+}
+
+/// Global rotation of the parent of the entity.
+fn parent_rotation(
+    entity: Entity,
+    parents: &ReadStorage<Parent>,
+    locals: &WriteStorage<Transform>,
+) -> Quaternion<f32> {
+    let mut rotation = Quaternion::one();
+    let mut current = parents.get(entity).map(|parent| parent.entity);
+    while let Some(entity) = current {
+        if let Some(local) = locals.get(entity) {
+            rotation = local.rotation * rotation;
+        }
+        current = parents.get(entity).map(|parent| parent.entity);
+    }
+    rotation
+}
+
+/// Apply a rotation in world space to the local rotation of the entity.
+fn rotate(
+    entity: Entity,
+    rotation: Quaternion<f32>,
+    parents: &ReadStorage<Parent>,
+    locals: &mut WriteStorage<Transform>,
+) {
+    let parent = parent_rotation(entity, parents, locals);
+    if let Some(local) = locals.get_mut(entity) {
+        local.rotation = (parent.conjugate() * rotation * parent * local.rotation).normalize();
+    }
+}
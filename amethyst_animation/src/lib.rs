@@ -2,30 +2,59 @@ extern crate amethyst_assets;
 extern crate amethyst_core;
 extern crate amethyst_renderer;
 extern crate fnv;
+extern crate gfx;
 extern crate hibitset;
 extern crate itertools;
 #[macro_use]
 extern crate log;
 extern crate minterpolate;
+extern crate ron;
 #[macro_use]
 extern crate serde;
 extern crate shred;
+extern crate shrev;
 extern crate specs;
 
-pub use self::bundle::{AnimationBundle, SamplingBundle, VertexSkinningBundle};
-pub use self::resources::{Animation, AnimationCommand, AnimationControl, AnimationControlSet, AnimationHierarchy,
-                          AnimationSampling, AnimationSet, BlendMethod, ControlState, EndControl,
-                          Sampler, SamplerControl, SamplerControlSet, StepDirection};
+pub use self::bundle::{AnimationBundle, SamplingBundle, TwoBoneIkBundle, VertexSkinningBundle};
+pub use self::resources::{Animation, AnimationCommand, AnimationControl, AnimationControlSet, AnimationEvent,
+                          AnimationHierarchy, AnimationMarker, AnimationSampling, AnimationSet,
+                          AnimationTimeScale, BlendMethod, ControlState, DeferStartRelation,
+                          EndControl, QuantizeRotation, Sampler, SamplerControl,
+                          SamplerControlSet, StepDirection};
+pub use self::camera::CameraChannel;
+pub use self::debug::SkeletonDebugSystem;
+pub use self::easing::{cubic_bezier, ease_in_cubic, ease_in_out_cubic, ease_in_out_quad,
+                       ease_in_quad, ease_out_cubic, ease_out_quad};
+pub use self::format::{AnimationDefinition, AnimationFormat, SamplerCompression, SamplerFormat};
+pub use self::ik::{TwoBoneIk, TwoBoneIkSystem};
+pub use self::light::LightChannel;
+pub use self::material::MaterialChannel;
+pub use self::morph::MorphChannel;
+pub use self::prefab::{AnimationHierarchyPrefab, AnimationPrefab, AnimationSetPrefab};
 pub use self::skinning::{Joint, Skin, VertexSkinningSystem};
-pub use self::systems::{AnimationControlSystem, AnimationProcessor, SamplerInterpolationSystem,
-                        SamplerProcessor};
+pub use self::state_machine::{AnimationState, AnimationStateMachine, AnimationTransition,
+                              TransitionCondition};
+pub use self::systems::{AnimationControlSystem, AnimationProcessor, AnimationStateMachineSystem,
+                        SamplerInterpolationSystem, SamplerProcessor};
 pub use self::transform::TransformChannel;
+pub use self::uniforms::UniformChannel;
 pub use self::util::{get_animation_set, SamplerPrimitive};
 pub use minterpolate::{InterpolationFunction, InterpolationPrimitive};
 
+mod camera;
+mod debug;
+mod easing;
+mod format;
+mod ik;
+mod light;
+mod material;
+mod morph;
+mod prefab;
 mod skinning;
 mod resources;
+mod state_machine;
 mod systems;
 mod transform;
+mod uniforms;
 mod bundle;
 mod util;
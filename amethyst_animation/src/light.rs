@@ -0,0 +1,92 @@
+use amethyst_renderer::{Light, Rgba};
+
+use resources::{AnimationSampling, BlendMethod};
+use util::SamplerPrimitive;
+
+/// Channels that can be animated on `Light`
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LightChannel {
+    /// Color of the light, sampled as `Vec4`
+    Color,
+    /// Intensity of the light, sampled as `Scalar`, directional lights have none and ignore it
+    Intensity,
+}
+
+impl AnimationSampling for Light {
+    type Channel = LightChannel;
+    type Primitive = SamplerPrimitive<f32>;
+
+    fn apply_sample(&mut self, channel: &Self::Channel, data: &SamplerPrimitive<f32>) {
+        use self::LightChannel::*;
+        use util::SamplerPrimitive::*;
+        match (channel, *data) {
+            (&Color, Vec4(ref d)) => *color_mut(self) = Rgba::from(*d),
+            (&Intensity, Scalar(ref d)) => {
+                if let Some(intensity) = intensity_mut(self) {
+                    *intensity = *d;
+                }
+            }
+            _ => panic!("Attempt to apply invalid sample to Light"),
+        }
+    }
+
+    fn current_sample(&self, channel: &Self::Channel) -> SamplerPrimitive<f32> {
+        use self::LightChannel::*;
+        match channel {
+            &Color => SamplerPrimitive::Vec4(color(self).into()),
+            &Intensity => SamplerPrimitive::Scalar(intensity(self).unwrap_or(0.)),
+        }
+    }
+
+    fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
+        use self::LightChannel::*;
+        match channel {
+            &Color => SamplerPrimitive::Vec4([0.; 4]),
+            &Intensity => SamplerPrimitive::Scalar(0.),
+        }
+    }
+
+    fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
+        Some(BlendMethod::Linear)
+    }
+}
+
+fn color(light: &Light) -> Rgba {
+    match *light {
+        Light::Area(ref light) => light.color,
+        Light::Directional(ref light) => light.color,
+        Light::Point(ref light) => light.color,
+        Light::Spot(ref light) => light.color,
+        Light::Sun(ref light) => light.color,
+    }
+}
+
+fn color_mut(light: &mut Light) -> &mut Rgba {
+    match *light {
+        Light::Area(ref mut light) => &mut light.color,
+        Light::Directional(ref mut light) => &mut light.color,
+        Light::Point(ref mut light) => &mut light.color,
+        Light::Spot(ref mut light) => &mut light.color,
+        Light::Sun(ref mut light) => &mut light.color,
+    }
+}
+
+fn intensity(light: &Light) -> Option<f32> {
+    match *light {
+        Light::Area(ref light) => Some(light.intensity),
+        Light::Directional(_) => None,
+        Light::Point(ref light) => Some(light.intensity),
+        Light::Spot(ref light) => Some(light.intensity),
+        Light::Sun(ref light) => Some(light.intensity),
+    }
+}
+
+fn intensity_mut(light: &mut Light) -> Option<&mut f32> {
+    match *light {
+        Light::Area(ref mut light) => Some(&mut light.intensity),
+        Light::Directional(_) => None,
+        Light::Point(ref mut light) => Some(&mut light.intensity),
+        Light::Spot(ref mut light) => Some(&mut light.intensity),
+        Light::Sun(ref mut light) => Some(&mut light.intensity),
+    }
+}
@@ -0,0 +1,53 @@
+use amethyst_renderer::Material;
+
+use resources::{AnimationSampling, BlendMethod};
+use util::SamplerPrimitive;
+
+/// Channels that can be animated on `Material`, for example to fade an entity out or to make it
+/// glow
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MaterialChannel {
+    /// Color the albedo map is multiplied with, sampled as `Vec4`
+    AlbedoFactor,
+    /// Color the emission map is multiplied with, sampled as `Vec4`
+    EmissionFactor,
+    /// Offset of the texture coordinates, sampled as `Vec2`
+    UvOffset,
+}
+
+impl AnimationSampling for Material {
+    type Channel = MaterialChannel;
+    type Primitive = SamplerPrimitive<f32>;
+
+    fn apply_sample(&mut self, channel: &Self::Channel, data: &SamplerPrimitive<f32>) {
+        use self::MaterialChannel::*;
+        use util::SamplerPrimitive::*;
+        match (channel, *data) {
+            (&AlbedoFactor, Vec4(d)) => self.albedo_factor = d,
+            (&EmissionFactor, Vec4(d)) => self.emission_factor = d,
+            (&UvOffset, Vec2(d)) => self.uv_offset = d,
+            _ => panic!("Attempt to apply invalid sample to Material"),
+        }
+    }
+
+    fn current_sample(&self, channel: &Self::Channel) -> SamplerPrimitive<f32> {
+        use self::MaterialChannel::*;
+        match channel {
+            &AlbedoFactor => SamplerPrimitive::Vec4(self.albedo_factor),
+            &EmissionFactor => SamplerPrimitive::Vec4(self.emission_factor),
+            &UvOffset => SamplerPrimitive::Vec2(self.uv_offset),
+        }
+    }
+
+    fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
+        use self::MaterialChannel::*;
+        match channel {
+            &AlbedoFactor | &EmissionFactor => SamplerPrimitive::Vec4([0.; 4]),
+            &UvOffset => SamplerPrimitive::Vec2([0.; 2]),
+        }
+    }
+
+    fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
+        Some(BlendMethod::Linear)
+    }
+}
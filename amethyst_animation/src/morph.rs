@@ -0,0 +1,67 @@
+use amethyst_renderer::{MorphWeights, MAX_MORPH_TARGETS};
+
+use resources::{AnimationSampling, BlendMethod};
+use util::SamplerPrimitive;
+
+/// Channels that can be animated on `MorphWeights`
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MorphChannel {
+    /// Weight of the morph target with the given index, sampled as `Scalar`
+    Weight(usize),
+    /// Weights of the first four morph targets, sampled as `Vec4`, like the weights of glTF
+    /// animations
+    Weights,
+}
+
+impl AnimationSampling for MorphWeights {
+    type Channel = MorphChannel;
+    type Primitive = SamplerPrimitive<f32>;
+
+    fn apply_sample(&mut self, channel: &Self::Channel, data: &SamplerPrimitive<f32>) {
+        use self::MorphChannel::*;
+        use util::SamplerPrimitive::*;
+        match (channel, *data) {
+            (&Weight(index), Scalar(d)) => {
+                if self.weights.len() <= index {
+                    self.weights.resize(index + 1, 0.);
+                }
+                self.weights[index] = d;
+            }
+            (&Weights, Vec4(d)) => {
+                if self.weights.len() < MAX_MORPH_TARGETS {
+                    self.weights.resize(MAX_MORPH_TARGETS, 0.);
+                }
+                self.weights[..MAX_MORPH_TARGETS].copy_from_slice(&d);
+            }
+            _ => panic!("Attempt to apply invalid sample to MorphWeights"),
+        }
+    }
+
+    fn current_sample(&self, channel: &Self::Channel) -> SamplerPrimitive<f32> {
+        use self::MorphChannel::*;
+        match channel {
+            &Weight(index) => {
+                SamplerPrimitive::Scalar(self.weights.get(index).cloned().unwrap_or(0.))
+            }
+            &Weights => {
+                let mut weights = [0.; MAX_MORPH_TARGETS];
+                for (weight, current) in weights.iter_mut().zip(&self.weights) {
+                    *weight = *current;
+                }
+                SamplerPrimitive::Vec4(weights)
+            }
+        }
+    }
+
+    fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
+        use self::MorphChannel::*;
+        match channel {
+            &Weight(_) => SamplerPrimitive::Scalar(0.),
+            &Weights => SamplerPrimitive::Vec4([0.; MAX_MORPH_TARGETS]),
+        }
+    }
+
+    fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
+        Some(BlendMethod::Linear)
+    }
+}
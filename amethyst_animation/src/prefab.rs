@@ -0,0 +1,169 @@
+//! Prefab data of the animation components.
+
+use std::marker;
+
+use amethyst_assets::{AssetStorage, Handle, Loader, PrefabData, ProgressCounter, Result};
+use fnv::FnvHashMap;
+use serde::de::DeserializeOwned;
+use specs::{Entity, Fetch, WriteStorage};
+
+use format::{AnimationFormat, SamplerFormat};
+use resources::{Animation, AnimationHierarchy, AnimationSampling, AnimationSet, QuantizeRotation,
+                Sampler};
+
+/// An animation of an `AnimationSetPrefab`, loaded from an `AnimationFormat` file.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AnimationPrefab {
+    /// The `AnimationFormat` file, relative to the asset directory
+    pub animation: String,
+    /// The `SamplerFormat` files the nodes of the animation refer to by name, relative to the
+    /// asset directory
+    pub samplers: Vec<String>,
+}
+
+/// Animations of a prefab entity, added as an `AnimationSet`.
+///
+/// The samplers and the animations are loaded when the prefab is processed, so all the instances
+/// of the prefab share them.
+///
+/// ### Type parameters:
+///
+/// - `T`: the component type that the animations should be applied to
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct AnimationSetPrefab<T>
+where
+    T: AnimationSampling,
+{
+    /// The animations of the set.
+    pub animations: Vec<AnimationPrefab>,
+    #[serde(skip)]
+    handles: Option<Vec<Handle<Animation<T>>>>,
+}
+
+impl<T> AnimationSetPrefab<T>
+where
+    T: AnimationSampling,
+{
+    /// Creates a new prefab of an animation set.
+    pub fn new(animations: Vec<AnimationPrefab>) -> Self {
+        AnimationSetPrefab {
+            animations,
+            handles: None,
+        }
+    }
+}
+
+impl<'a, T> PrefabData<'a> for AnimationSetPrefab<T>
+where
+    T: AnimationSampling,
+    T::Channel: DeserializeOwned,
+    T::Primitive: DeserializeOwned + QuantizeRotation,
+{
+    type SystemData = (
+        Fetch<'a, Loader>,
+        Fetch<'a, AssetStorage<Sampler<T::Primitive>>>,
+        Fetch<'a, AssetStorage<Animation<T>>>,
+        WriteStorage<'a, AnimationSet<T>>,
+    );
+
+    fn load_sub_assets(
+        &mut self,
+        progress: &mut ProgressCounter,
+        system_data: &mut Self::SystemData,
+    ) {
+        if self.handles.is_some() {
+            return;
+        }
+        let (ref loader, ref sampler_storage, ref animation_storage, _) = *system_data;
+        let mut handles = Vec::with_capacity(self.animations.len());
+        for animation in &self.animations {
+            let mut samplers = FnvHashMap::default();
+            for name in &animation.samplers {
+                let sampler = loader.load(
+                    name.as_str(),
+                    SamplerFormat,
+                    Default::default(),
+                    &mut *progress,
+                    sampler_storage,
+                );
+                samplers.insert(name.clone(), sampler);
+            }
+            handles.push(loader.load(
+                animation.animation.as_str(),
+                AnimationFormat::new(),
+                samplers,
+                &mut *progress,
+                animation_storage,
+            ));
+        }
+        self.handles = Some(handles);
+    }
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+        _: &[Entity],
+    ) -> Result<()> {
+        match self.handles {
+            Some(ref handles) => {
+                let set = AnimationSet {
+                    animations: handles.clone(),
+                };
+                system_data.3.insert(entity, set);
+                Ok(())
+            }
+            None => Err("The animations of the prefab have not been loaded".into()),
+        }
+    }
+}
+
+/// Nodes of an `AnimationHierarchy` added to a prefab entity, as pairs of a node index and the
+/// index of an entity of the prefab.
+///
+/// ### Type parameters:
+///
+/// - `T`: the component type that the animations should be applied to
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct AnimationHierarchyPrefab<T> {
+    /// The node index and the index of the prefab entity of each node.
+    pub nodes: Vec<(usize, usize)>,
+    #[serde(skip)]
+    m: marker::PhantomData<T>,
+}
+
+impl<T> AnimationHierarchyPrefab<T> {
+    /// Creates a new prefab of an animation hierarchy.
+    pub fn new(nodes: Vec<(usize, usize)>) -> Self {
+        AnimationHierarchyPrefab {
+            nodes,
+            m: marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, T> PrefabData<'a> for AnimationHierarchyPrefab<T>
+where
+    T: AnimationSampling,
+{
+    type SystemData = WriteStorage<'a, AnimationHierarchy<T>>;
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        hierarchies: &mut Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<()> {
+        let nodes = self.nodes
+            .iter()
+            .map(|&(node_index, entity_index)| match entities.get(entity_index) {
+                Some(node) => Ok((node_index, *node)),
+                None => Err(format!("The prefab has no entity {}", entity_index).into()),
+            })
+            .collect::<Result<_>>()?;
+        hierarchies.insert(entity, AnimationHierarchy::new_many(nodes));
+        Ok(())
+    }
+}
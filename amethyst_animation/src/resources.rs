@@ -5,9 +5,10 @@ use std::time::Duration;
 
 use amethyst_assets::{Asset, AssetStorage, Handle, Result};
 use amethyst_core::timing::{duration_to_secs, secs_to_duration};
-use fnv::FnvHashMap;
+use amethyst_core::transform::Parent;
+use fnv::{FnvHashMap, FnvHashSet};
 use minterpolate::{get_input_index, InterpolationFunction, InterpolationPrimitive};
-use specs::{Component, DenseVecStorage, Entity, VecStorage};
+use specs::{Component, DenseVecStorage, Entities, Entity, Join, ReadStorage, VecStorage};
 
 /// Blend method for sampler blending
 #[derive(Clone, Copy, Debug, PartialOrd, PartialEq, Eq, Hash)]
@@ -33,9 +34,35 @@ pub trait AnimationSampling: Send + Sync + 'static {
 
     /// Get blend config
     fn blend_method(&self, channel: &Self::Channel) -> Option<BlendMethod>;
+
+    /// Get the delta of an additive animation between its first sample and the current one,
+    /// component wise by default
+    fn additive_delta(
+        _channel: &Self::Channel,
+        first: &Self::Primitive,
+        current: &Self::Primitive,
+    ) -> Self::Primitive {
+        current.sub(first)
+    }
+
+    /// Apply the delta of an additive animation on top of a sample, scaled by the weight of the
+    /// animation, component wise by default
+    fn add_delta(
+        _channel: &Self::Channel,
+        sample: &Self::Primitive,
+        delta: &Self::Primitive,
+        weight: f32,
+    ) -> Self::Primitive {
+        sample.add(&delta.mul(weight))
+    }
 }
 
 /// Sampler defines a single animation for a single channel on a single component
+///
+/// Spline interpolations use the keyframes as they are imported, without resampling them to dense
+/// linear keyframes: `CubicSpline` takes an in tangent, a value and an out tangent per keyframe,
+/// like glTF defines them, and `CatmullRomSpline` computes the tangents from the neighbouring
+/// keyframes, taking an in tangent before the first value and an out tangent after the last one.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sampler<T>
 where
@@ -43,12 +70,96 @@ where
 {
     /// Time of key frames
     pub input: Vec<f32>,
-    /// Actual output data to interpolate
+    /// Actual output data to interpolate, three per key frame for `CubicSpline`, and two more than
+    /// the key frames for `CatmullRomSpline`
     pub output: Vec<T>,
     /// How should interpolation be done
     pub function: InterpolationFunction<T>,
 }
 
+/// Primitives of samplers that can hold rotations, see `Sampler::quantize_rotations`.
+pub trait QuantizeRotation {
+    /// Rounds a quaternion to the precision of its quantized encoding, other primitives are
+    /// returned as they are.
+    fn quantize_rotation(&self) -> Self;
+}
+
+/// Maximum number of consecutive key frames `Sampler::compress` drops.
+const MAX_COMPRESSED_SPAN: usize = 32;
+
+impl<T> Sampler<T>
+where
+    T: InterpolationPrimitive + Clone,
+{
+    /// Remove the key frames that interpolating between their neighbours reproduces within the
+    /// given tolerance, like the dense key frames of imported or motion captured animations.
+    ///
+    /// Only linear and step interpolations are compressed, splines are left as they are. At most
+    /// 32 key frames are dropped in a row, so long clips are compressed in linear time.
+    pub fn compress(&mut self, tolerance: f32) {
+        match self.function {
+            InterpolationFunction::Linear
+            | InterpolationFunction::SphericalLinear
+            | InterpolationFunction::Step => {}
+            _ => return,
+        }
+        let count = self.input.len();
+        if count <= 2 || self.output.len() != count {
+            return;
+        }
+        let mut keep = vec![0];
+        for i in 1..count - 1 {
+            let last = *keep.last().unwrap();
+            if i - last > MAX_COMPRESSED_SPAN {
+                keep.push(i);
+                continue;
+            }
+            // Key frame i can be dropped if the span from the last kept key frame to the next one
+            // still reproduces all the key frames in between
+            let inputs = [self.input[last], self.input[i + 1]];
+            let outputs = [self.output[last].clone(), self.output[i + 1].clone()];
+            let redundant = (last + 1..i + 1).all(|j| {
+                self.function
+                    .interpolate(self.input[j], &inputs, &outputs, false)
+                    .sub(&self.output[j])
+                    .magnitude2()
+                    .sqrt() <= tolerance
+            });
+            if !redundant {
+                keep.push(i);
+            }
+        }
+        keep.push(count - 1);
+        self.input = keep.iter().map(|&i| self.input[i]).collect();
+        self.output = keep.iter().map(|&i| self.output[i].clone()).collect();
+    }
+}
+
+impl<T> Sampler<T>
+where
+    T: InterpolationPrimitive + QuantizeRotation,
+{
+    /// Round the quaternions of a rotation sampler to what a smallest three encoding with 16
+    /// bits per component holds, dropping the jitter of motion captured rotations so `compress`
+    /// removes more key frames. The outputs are still stored as they were, with the rounded
+    /// values.
+    ///
+    /// Every `Vec4` output is taken for a quaternion, so this is only meant for rotation
+    /// samplers. Only linear and step interpolations are quantized, as spline tangents aren't
+    /// rotations.
+    pub fn quantize_rotations(&mut self) {
+        match self.function {
+            InterpolationFunction::Linear
+            | InterpolationFunction::SphericalLinear
+            | InterpolationFunction::Step => {}
+            _ => return,
+        }
+        for output in &mut self.output {
+            *output = output.quantize_rotation();
+        }
+    }
+}
+
 impl<T> Asset for Sampler<T>
 where
     T: InterpolationPrimitive + Send + Sync + 'static,
@@ -63,6 +174,22 @@ where
     T: InterpolationPrimitive,
 {
     fn into(self) -> Result<Sampler<T>> {
+        if self.input.windows(2).any(|w| w[0] > w[1]) {
+            return Err("Sampler key frame times must be increasing".into());
+        }
+        let expected = match self.function {
+            InterpolationFunction::CubicSpline => self.input.len() * 3,
+            InterpolationFunction::CatmullRomSpline => self.input.len() + 2,
+            _ => self.input.len(),
+        };
+        if self.output.len() != expected {
+            return Err(format!(
+                "Sampler has {} outputs for {} key frames, expected {}",
+                self.output.len(),
+                self.input.len(),
+                expected
+            ).into());
+        }
         Ok(self)
     }
 }
@@ -111,6 +238,51 @@ where
             m: marker::PhantomData,
         }
     }
+
+    /// Create a new hierarchy containing the given root entity and all its descendants, by walking
+    /// the `Parent` links.
+    ///
+    /// The root has index 0 and the other nodes are numbered breadth first, the children of a node
+    /// being ordered by entity id, so the same entity tree always gets the same indices.
+    pub fn from_parents(root: Entity, entities: &Entities, parents: &ReadStorage<Parent>) -> Self {
+        let mut children = FnvHashMap::<Entity, Vec<Entity>>::default();
+        for (entity, parent) in (&**entities, parents).join() {
+            children
+                .entry(parent.entity)
+                .or_insert_with(Vec::default)
+                .push(entity);
+        }
+        let mut order = vec![root];
+        let mut next = 0;
+        while next < order.len() {
+            if let Some(mut nodes) = children.remove(&order[next]) {
+                nodes.sort_by_key(|entity| entity.id());
+                order.extend(nodes);
+            }
+            next += 1;
+        }
+        AnimationHierarchy::new_many(order.into_iter().enumerate().collect())
+    }
+
+    /// Get the indices of the nodes in the subtree of the given entity, including the entity
+    /// itself, for use as the mask of an `AnimationControl`. For example the subtree of the spine
+    /// of a character is its upper body.
+    pub fn subtree(&self, root: Entity, parents: &ReadStorage<Parent>) -> FnvHashSet<usize> {
+        self.nodes
+            .iter()
+            .filter(|&(_, entity)| {
+                let mut current = Some(*entity);
+                while let Some(entity) = current {
+                    if entity == root {
+                        return true;
+                    }
+                    current = parents.get(entity).map(|parent| parent.entity);
+                }
+                false
+            })
+            .map(|(index, _)| *index)
+            .collect()
+    }
 }
 
 impl<T> Component for AnimationHierarchy<T>
@@ -120,6 +292,42 @@ where
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Named event marker at a specific input time of an `Animation`, for example the frame a foot
+/// touches the ground in a walk cycle.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnimationMarker {
+    /// Input time of the marker, in seconds
+    pub time: f32,
+    /// Name of the marker, sent along with the `AnimationEvent`
+    pub name: String,
+}
+
+impl AnimationMarker {
+    /// Create a new marker
+    pub fn new(time: f32, name: &str) -> Self {
+        AnimationMarker {
+            time,
+            name: name.to_string(),
+        }
+    }
+}
+
+/// Event sent through an `EventChannel<AnimationEvent<I>>` by the `AnimationControlSystem` when
+/// the playback of an animation crosses one of its markers.
+///
+/// ### Type parameters:
+///
+/// - `I`: identifier type for running animations
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationEvent<I> {
+    /// The entity the animation is running on
+    pub entity: Entity,
+    /// Id of the animation in the `AnimationControlSet` of the entity
+    pub id: I,
+    /// Name of the crossed marker
+    pub name: String,
+}
+
 /// Defines a single animation.
 /// Defines relationships between the node index in `AnimationHierarchy` and a `Sampler` handle.
 /// If the animation only targets a single node index, `AnimationHierarchy` is not required.
@@ -134,6 +342,8 @@ where
 {
     /// node index -> sampler handle
     pub nodes: Vec<(usize, T::Channel, Handle<Sampler<T::Primitive>>)>,
+    /// Event markers, an `AnimationEvent` is sent each time the playback crosses one of them
+    pub events: Vec<AnimationMarker>,
 }
 
 impl<T> Asset for Animation<T>
@@ -194,6 +404,13 @@ pub enum EndControl {
     Loop(Option<u32>),
     /// When duration of sampler/animation is reached, go back to rest state
     Normal,
+    /// When duration of sampler/animation is reached, hold the pose of the last frame, or of the
+    /// first frame when playing backwards
+    Stay,
+    /// When duration of sampler/animation is reached, hold the pose at the given input time
+    StayAt(f32),
+    /// Play back and forth infinitely, reversing the direction of playback at both ends
+    PingPong,
 }
 
 /// Control a single active sampler
@@ -212,6 +429,9 @@ where
     pub channel: T::Channel,
     /// Blend weight
     pub blend_weight: f32,
+    /// Blend weight of the animation this entry belongs to, multiplied with `blend_weight`,
+    /// default is 1.0
+    pub animation_weight: f32,
     /// Sampler
     pub sampler: Handle<Sampler<T::Primitive>>,
     /// State of sampling
@@ -220,15 +440,35 @@ where
     pub end: EndControl,
     /// What the transform should return to after end
     pub after: T::Primitive,
-    /// Control the rate of animation, default is 1.0
+    /// Control the rate of animation, default is 1.0, negative rates play backwards
     pub rate_multiplier: f32,
+    /// Whether the direction of playback was reversed by `EndControl::PingPong`
+    pub reversed: bool,
+    /// Whether the sampler is additive, see `AnimationControl::additive`
+    pub additive: bool,
+}
+
+/// Scale of the time of all animations, multiplied with the `time_scale` of each
+/// `AnimationControlSet`, default is 1.0. Setting it to 0 pauses all animations, for example while
+/// a pause menu is open, and values below 1 slow them down for bullet time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationTimeScale(pub f32);
+
+impl Default for AnimationTimeScale {
+    fn default() -> Self {
+        AnimationTimeScale(1.0)
+    }
 }
 
 /// Sampler control set, containing a set of sampler controllers for a single component.
 ///
 /// Have support for multiple samplers per channel, will do linear blending between all active
-/// samplers. The target component specifies if it can be blended, if it can't, the last added
-/// sampler wins.
+/// samplers, weighted by the product of their `blend_weight` and `animation_weight`. The weights
+/// are normalized per channel. The target component specifies if it can be blended, if it can't,
+/// the last added sampler wins.
+///
+/// Additive samplers are not part of the normalized blend, their weighted deltas from their first
+/// keyframe are added to the blended value instead.
 ///
 /// ### Type parameters:
 ///
@@ -239,6 +479,8 @@ where
     T: AnimationSampling,
 {
     pub samplers: Vec<SamplerControl<T>>,
+    /// Scale of the time of all samplers in the set, see `AnimationControlSet::time_scale`
+    pub time_scale: f32,
 }
 
 impl<T> Default for SamplerControlSet<T>
@@ -248,6 +490,7 @@ where
     fn default() -> Self {
         SamplerControlSet {
             samplers: Vec::default(),
+            time_scale: 1.0,
         }
     }
 }
@@ -326,6 +569,14 @@ where
             .for_each(|sampler| sampler.rate_multiplier = rate_multiplier);
     }
 
+    /// Update animation blend weight
+    pub fn set_animation_weight(&mut self, control_id: u64, weight: f32) {
+        self.samplers
+            .iter_mut()
+            .filter(|t| t.control_id == control_id)
+            .for_each(|sampler| sampler.animation_weight = weight);
+    }
+
     /// Forcibly set the input value (point of interpolation)
     pub fn set_input(&mut self, control_id: u64, input: f32)
     where
@@ -342,6 +593,21 @@ where
             });
     }
 
+    /// Jump to the given input value, in seconds, keeping the samplers running or paused
+    pub fn seek(&mut self, control_id: u64, input: f32) {
+        let dur = secs_to_duration(input.max(0.));
+        for sampler in self.samplers
+            .iter_mut()
+            .filter(|t| t.control_id == control_id)
+        {
+            sampler.state = match sampler.state {
+                ControlState::Running(_) => ControlState::Running(dur),
+                ControlState::Paused(_) => ControlState::Paused(dur),
+                ref state => state.clone(),
+            }
+        }
+    }
+
     /// Check if a control set can be terminated
     pub fn check_termination(&self, control_id: u64) -> bool {
         self.samplers
@@ -432,8 +698,15 @@ where
     Step(StepDirection),
     /// Forcibly set current interpolation point for the animation, value in seconds
     SetInputValue(f32),
+    /// Jump to the given input time of the animation, in seconds, whether it's running or paused.
+    /// The markers in between don't send events, and a time past the end is handled like the
+    /// animation reaching its end
+    Seek(f32),
     /// Set blend weights
     SetBlendWeights(Vec<(usize, T::Channel, f32)>),
+    /// Set the rate of the animation without restarting it, 0 freezes it, the animation keeps
+    /// running or paused
+    SetRate(f32),
     /// Pause the animation
     Pause,
     /// Abort the animation, will cause the control object to be removed from the world
@@ -463,8 +736,24 @@ where
     pub state: ControlState,
     /// Animation command
     pub command: AnimationCommand<T>,
-    /// Control the rate of animation, default is 1.0
+    /// Control the rate of animation, default is 1.0, negative rates play the animation
+    /// backwards, starting from its end
     pub rate_multiplier: f32,
+    /// Weight of the animation when blended with the other animations running on the same
+    /// channels, default is 1.0
+    pub blend_weight: f32,
+    /// Whether the animation is an additive layer, default is false. An additive animation adds
+    /// its deltas from its first keyframe, scaled by `blend_weight`, on top of the pose of the
+    /// other animations instead of being blended with them. This requires the target component to
+    /// support blending.
+    pub additive: bool,
+    /// Indices of the nodes of the `AnimationHierarchy` the animation is applied to, `None` to
+    /// apply it to all nodes. The other nodes keep playing the other animations, for example the
+    /// legs keep walking while an aiming animation is masked to the upper body. Only taken into
+    /// account when the animation starts.
+    pub mask: Option<FnvHashSet<usize>>,
+    /// Input time of the animation last frame, used to find the crossed markers
+    pub(crate) last_time: Option<f32>,
     m: marker::PhantomData<T>,
 }
 
@@ -486,9 +775,18 @@ where
             state,
             command,
             rate_multiplier,
+            blend_weight: 1.0,
+            additive: false,
+            mask: None,
+            last_time: None,
             m: marker::PhantomData,
         }
     }
+
+    /// Check if the animation is applied to the node with the given index
+    pub fn applies_to(&self, node_index: usize) -> bool {
+        self.mask.as_ref().map_or(true, |mask| mask.contains(&node_index))
+    }
 }
 
 impl<T> Component for AnimationControl<T>
@@ -498,12 +796,50 @@ where
     type Storage = DenseVecStorage<Self>;
 }
 
+/// When an animation added with `AnimationControlSet::add_deferred_animation` starts
+///
+/// ### Type parameters:
+///
+/// - `I`: identifier type for running animations
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeferStartRelation<I> {
+    /// Start after the given delay, in seconds
+    Delay(f32),
+    /// Start when the animation with the given id ended, immediately if it isn't running nor
+    /// deferred
+    End(I),
+}
+
+/// Animation waiting in an `AnimationControlSet` to be started
+#[derive(Clone, Debug)]
+pub(crate) struct DeferredStart<I, T>
+where
+    T: AnimationSampling,
+{
+    pub id: I,
+    pub relation: DeferStartRelation<I>,
+    pub control: AnimationControl<T>,
+}
+
 /// Contains all currently running animations for an entity.
 ///
 /// Have support for running multiple animations, will do linear blending between all active
 /// animations. The target component specifies if it can be blended, if it can't, the last added
 /// animation wins.
 ///
+/// Animations are blended at full strength by default, `set_weight` changes the share of an
+/// animation, for example to mix walk and run cycles by the speed of a character. The weights are
+/// normalized per channel, so only their ratio matters.
+///
+/// Animations added with `add_additive_animation` are layered on top of the others instead, like
+/// breathing or recoil on top of a locomotion cycle, their weight scaling their deltas.
+///
+/// Animations added with `add_deferred_animation` wait in the set until they are started by the
+/// `AnimationControlSystem`, after a delay or when another animation ended.
+///
+/// The `time_scale` of the set slows down or pauses all its animations at once, along with the
+/// global `AnimationTimeScale`, without changing their rates.
+///
 /// ### Type parameters:
 ///
 /// - `I`: identifier type for running animations, only one animation can be run at the same time
@@ -515,6 +851,9 @@ where
     T: AnimationSampling,
 {
     pub animations: Vec<(I, AnimationControl<T>)>,
+    /// Scale of the time of all animations in the set, default is 1.0, 0 pauses them
+    pub time_scale: f32,
+    pub(crate) deferred: Vec<DeferredStart<I, T>>,
 }
 
 impl<I, T> Default for AnimationControlSet<I, T>
@@ -524,6 +863,8 @@ where
     fn default() -> Self {
         AnimationControlSet {
             animations: Vec::default(),
+            time_scale: 1.0,
+            deferred: Vec::default(),
         }
     }
 }
@@ -535,7 +876,7 @@ where
 {
     /// Is the animation set empty?
     pub fn is_empty(&self) -> bool {
-        self.animations.is_empty()
+        self.animations.is_empty() && self.deferred.is_empty()
     }
 
     /// Remove animation from set
@@ -575,13 +916,27 @@ where
         }
     }
 
-    /// Set animation rate
+    /// Set animation rate, applied to the running animation without restarting it, 0 freezes it
     pub fn set_rate(&mut self, id: I, rate_multiplier: f32) {
         if let Some(&mut (_, ref mut control)) = self.animations.iter_mut().find(|a| a.0 == id) {
             control.rate_multiplier = rate_multiplier;
         }
     }
 
+    /// Set animation blend weight, for additive animations the strength of the layer
+    pub fn set_weight(&mut self, id: I, weight: f32) {
+        if let Some(&mut (_, ref mut control)) = self.animations.iter_mut().find(|a| a.0 == id) {
+            control.blend_weight = weight;
+        }
+    }
+
+    /// Restrict the animation to the given nodes of the `AnimationHierarchy`, before it is started
+    pub fn set_mask(&mut self, id: I, mask: FnvHashSet<usize>) {
+        if let Some(&mut (_, ref mut control)) = self.animations.iter_mut().find(|a| a.0 == id) {
+            control.mask = Some(mask);
+        }
+    }
+
     /// Step animation
     pub fn step(&mut self, id: I, direction: StepDirection) {
         self.set_command(id, AnimationCommand::Step(direction));
@@ -592,13 +947,21 @@ where
         self.set_command(id, AnimationCommand::SetInputValue(input));
     }
 
+    /// Jump to the given input time of the animation, in seconds, whether it's running or paused,
+    /// for example to scrub through it or to correct its time from the network. Starts the
+    /// animation if it was only added.
+    pub fn seek(&mut self, id: I, time: f32) {
+        self.set_command(id, AnimationCommand::Seek(time));
+    }
+
     /// Set blend weights
     pub fn set_blend_weight(&mut self, id: I, weights: Vec<(usize, T::Channel, f32)>) {
         self.set_command(id, AnimationCommand::SetBlendWeights(weights));
     }
 
-    /// Abort animation
+    /// Abort animation, also cancelling its deferred start
     pub fn abort(&mut self, id: I) {
+        self.deferred.retain(|d| d.id != id);
         self.set_command(id, AnimationCommand::Abort);
     }
 
@@ -611,19 +974,104 @@ where
         rate_multiplier: f32,
         command: AnimationCommand<T>,
     ) {
-        if let Some(_) = self.animations.iter().find(|a| a.0 == id) {
+        self.insert(id, animation, end, rate_multiplier, command, false);
+    }
+
+    /// Add additive animation with the given id, unless it already exists
+    ///
+    /// The deltas of the animation from its first keyframe are added on top of the other
+    /// animations, scaled by its weight.
+    pub fn add_additive_animation(
+        &mut self,
+        id: I,
+        animation: &Handle<Animation<T>>,
+        end: EndControl,
+        rate_multiplier: f32,
+        command: AnimationCommand<T>,
+    ) {
+        self.insert(id, animation, end, rate_multiplier, command, true);
+    }
+
+    /// Add animation with the given id, started after a delay or when another animation ended,
+    /// unless an animation with the same id is already deferred
+    ///
+    /// The animation waits for any running animation with the same id to end before starting, so
+    /// animations can be queued with the same id.
+    pub fn add_deferred_animation(
+        &mut self,
+        id: I,
+        animation: &Handle<Animation<T>>,
+        end: EndControl,
+        rate_multiplier: f32,
+        relation: DeferStartRelation<I>,
+    ) {
+        if let Some(_) = self.deferred.iter().find(|d| d.id == id) {
             return;
         }
-        self.animations.push((
+        let control = AnimationControl::new(
+            animation.clone(),
+            end,
+            ControlState::Requested,
+            AnimationCommand::Start,
+            rate_multiplier,
+        );
+        self.deferred.push(DeferredStart {
             id,
-            AnimationControl::new(
-                animation.clone(),
-                end,
-                ControlState::Requested,
-                command,
-                rate_multiplier,
-            ),
-        ));
+            relation,
+            control,
+        });
+    }
+
+    /// Count down the delays of the deferred animations, and move the animations that are due to
+    /// the running animations.
+    pub(crate) fn start_deferred(&mut self, delta: f32) {
+        for deferred in &mut self.deferred {
+            if let DeferStartRelation::Delay(ref mut delay) = deferred.relation {
+                *delay -= delta;
+            }
+        }
+        while let Some(index) = (0..self.deferred.len()).find(|&i| self.is_due(i)) {
+            let deferred = self.deferred.remove(index);
+            self.animations.push((deferred.id, deferred.control));
+        }
+    }
+
+    fn is_due(&self, index: usize) -> bool {
+        let deferred = &self.deferred[index];
+        if self.animations.iter().any(|a| a.0 == deferred.id) {
+            return false;
+        }
+        match deferred.relation {
+            DeferStartRelation::Delay(delay) => delay <= 0.,
+            // Animations deferred earlier may be queued before the one waited for
+            DeferStartRelation::End(ref other) => {
+                !self.animations.iter().any(|a| a.0 == *other)
+                    && !self.deferred[..index].iter().any(|d| d.id == *other)
+            }
+        }
+    }
+
+    fn insert(
+        &mut self,
+        id: I,
+        animation: &Handle<Animation<T>>,
+        end: EndControl,
+        rate_multiplier: f32,
+        command: AnimationCommand<T>,
+        additive: bool,
+    ) {
+        if let Some(_) = self.animations.iter().find(|a| a.0 == id) {
+            return;
+        }
+        let mut control = AnimationControl::new(
+            animation.clone(),
+            end,
+            ControlState::Requested,
+            command,
+            rate_multiplier,
+        );
+        control.additive = additive;
+        self.animations.push((id, control));
     }
 
     /// Check if there is an animation with the given id in the set
@@ -663,3 +1111,170 @@ where
 {
     type Storage = DenseVecStorage<Self>;
 }
+
+#[cfg(test)]
+mod tests {
+    use minterpolate::{InterpolationFunction, InterpolationPrimitive};
+
+    use super::Sampler;
+    use util::SamplerPrimitive;
+
+    fn sampler(output: Vec<SamplerPrimitive<f32>>) -> Sampler<SamplerPrimitive<f32>> {
+        Sampler {
+            input: (0..output.len()).map(|i| i as f32).collect(),
+            output,
+            function: InterpolationFunction::Linear,
+        }
+    }
+
+    fn ramp(count: usize) -> Sampler<SamplerPrimitive<f32>> {
+        sampler((0..count).map(|i| [i as f32, 0., 0.].into()).collect())
+    }
+
+    fn max_error(
+        original: &Sampler<SamplerPrimitive<f32>>,
+        compressed: &Sampler<SamplerPrimitive<f32>>,
+    ) -> f32 {
+        original
+            .input
+            .iter()
+            .zip(&original.output)
+            .map(|(&input, output)| {
+                compressed
+                    .function
+                    .interpolate(input, &compressed.input, &compressed.output, false)
+                    .sub(output)
+                    .magnitude2()
+                    .sqrt()
+            })
+            .fold(0., f32::max)
+    }
+
+    #[test]
+    fn compress_keeps_first_and_last() {
+        let original = ramp(10);
+        let mut s = original.clone();
+        s.compress(0.001);
+        assert_eq!(s.input, vec![0., 9.]);
+        assert!(max_error(&original, &s) <= 0.001);
+        match (s.output[0], s.output[1]) {
+            (SamplerPrimitive::Vec3(first), SamplerPrimitive::Vec3(last)) => {
+                assert_eq!(first, [0., 0., 0.]);
+                assert_eq!(last, [9., 0., 0.]);
+            }
+            _ => panic!("Compression changed the primitives"),
+        }
+    }
+
+    #[test]
+    fn compress_within_tolerance() {
+        let original = sampler(
+            (0..20)
+                .map(|i| [i as f32, if i == 10 { 0.5 } else { 0. }, 0.].into())
+                .collect(),
+        );
+
+        let mut strict = original.clone();
+        strict.compress(0.1);
+        assert!(strict.input.contains(&10.));
+        assert!(max_error(&original, &strict) <= 0.1);
+
+        let mut loose = original.clone();
+        loose.compress(1.);
+        assert_eq!(loose.input, vec![0., 19.]);
+        assert!(max_error(&original, &loose) <= 1.);
+    }
+
+    #[test]
+    fn compress_negative_scalar_error() {
+        let original = sampler(
+            [0., -5., 0.]
+                .iter()
+                .map(|&v| SamplerPrimitive::Scalar(v))
+                .collect(),
+        );
+        let mut s = original.clone();
+        s.compress(0.1);
+        assert_eq!(s.input, vec![0., 1., 2.]);
+    }
+
+    #[test]
+    fn compress_limits_dropped_span() {
+        let original = ramp(100);
+        let mut s = original.clone();
+        s.compress(0.001);
+        assert_eq!(s.input.first(), Some(&0.));
+        assert_eq!(s.input.last(), Some(&99.));
+        assert!(
+            s.input
+                .windows(2)
+                .all(|w| (w[1] - w[0]) as usize <= super::MAX_COMPRESSED_SPAN + 1)
+        );
+        assert!(max_error(&original, &s) <= 0.001);
+    }
+
+    #[test]
+    fn compress_leaves_splines() {
+        let mut s = ramp(12);
+        s.function = InterpolationFunction::CatmullRomSpline;
+        s.input.truncate(10);
+        s.compress(1.);
+        assert_eq!(s.input.len(), 10);
+        assert_eq!(s.output.len(), 12);
+    }
+
+    fn rotations() -> Vec<[f32; 4]> {
+        vec![
+            [0., 0., 0., 1.],
+            [0.5, -0.5, 0.5, -0.5],
+            [0.1, 0.2, -0.3, 0.927],
+            [-0.6, 0.1, 0.05, 0.79],
+            [0., 0.70710677, 0., 0.70710677],
+            [0., 0., -0.8, 0.6],
+        ]
+    }
+
+    #[test]
+    fn quantize_rotations_within_precision() {
+        let mut s = sampler(rotations().into_iter().map(Into::into).collect());
+        s.quantize_rotations();
+        for (original, quantized) in rotations().iter().zip(&s.output) {
+            let original = SamplerPrimitive::from(*original).normalize();
+            assert!((quantized.magnitude() - 1.).abs() < 1e-5);
+            // Same sign as the original, not the opposite quaternion
+            assert!(quantized.dot(&original) > 0.);
+            assert!(quantized.sub(&original).magnitude() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn quantize_rotations_on_16_bit_steps() {
+        use std::f32::consts::FRAC_1_SQRT_2;
+
+        let mut s = sampler(vec![[0.1, 0.2, -0.3, 0.927].into()]);
+        s.quantize_rotations();
+        match s.output[0] {
+            SamplerPrimitive::Vec4(q) => for &v in &q[0..3] {
+                let step = (v + FRAC_1_SQRT_2) / (2. * FRAC_1_SQRT_2) * 65535.;
+                assert!((step - step.round()).abs() < 0.01);
+            },
+            _ => panic!("Quantization changed the primitives"),
+        }
+    }
+
+    #[test]
+    fn quantize_rotations_leaves_other_primitives() {
+        let original = ramp(4);
+        let mut s = original.clone();
+        s.quantize_rotations();
+        assert_eq!(max_error(&original, &s), 0.);
+
+        let mut spline = sampler(rotations().into_iter().map(Into::into).collect());
+        spline.function = InterpolationFunction::CubicSpline;
+        spline.quantize_rotations();
+        match spline.output[2] {
+            SamplerPrimitive::Vec4(q) => assert_eq!(q, [0.1, 0.2, -0.3, 0.927]),
+            _ => panic!("Quantization changed the primitives"),
+        }
+    }
+}
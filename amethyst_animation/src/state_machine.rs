@@ -0,0 +1,226 @@
+use amethyst_assets::Handle;
+use fnv::FnvHashMap;
+use specs::{Component, DenseVecStorage};
+
+use resources::{Animation, AnimationSampling, EndControl};
+
+/// A state of an `AnimationStateMachine`, playing a single animation.
+///
+/// ### Type parameters:
+///
+/// - `I`: identifier type for running animations, the animation of the state is run with this id
+/// - `T`: the component type that the animation should be applied to
+#[derive(Clone, Debug)]
+pub struct AnimationState<I, T>
+where
+    T: AnimationSampling,
+{
+    /// Id of the animation in the `AnimationControlSet`
+    pub id: I,
+    /// Animation handle
+    pub animation: Handle<Animation<T>>,
+    /// What to do when the animation ends
+    pub end: EndControl,
+    /// Rate of the animation, default is 1.0
+    pub rate_multiplier: f32,
+    /// Parameter the rate is multiplied with, if any
+    pub rate_parameter: Option<String>,
+}
+
+impl<I, T> AnimationState<I, T>
+where
+    T: AnimationSampling,
+{
+    /// Create a new state, playing the given animation with the given id
+    pub fn new(id: I, animation: Handle<Animation<T>>, end: EndControl) -> Self {
+        AnimationState {
+            id,
+            animation,
+            end,
+            rate_multiplier: 1.0,
+            rate_parameter: None,
+        }
+    }
+
+    /// Set the rate of the animation
+    pub fn with_rate(mut self, rate_multiplier: f32) -> Self {
+        self.rate_multiplier = rate_multiplier;
+        self
+    }
+
+    /// Multiply the rate of the animation with the given parameter, for example to match the
+    /// stride of a walk cycle with the speed of a character
+    pub fn with_rate_parameter(mut self, parameter: &str) -> Self {
+        self.rate_parameter = Some(parameter.to_string());
+        self
+    }
+}
+
+/// Condition of an `AnimationTransition`
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransitionCondition {
+    /// The parameter is greater than the value
+    Greater(String, f32),
+    /// The parameter is less than the value
+    Less(String, f32),
+    /// The animation of the current state is done, it must not loop infinitely
+    Done,
+}
+
+/// Transition between the states of an `AnimationStateMachine`, taken when all its conditions
+/// hold.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationTransition {
+    /// State the transition leaves, `None` to leave any other state
+    pub from: Option<String>,
+    /// State the transition enters
+    pub to: String,
+    /// Conditions that must all hold for the transition to be taken
+    pub conditions: Vec<TransitionCondition>,
+    /// Duration of the cross fade between the animations of both states, in seconds
+    pub duration: f32,
+}
+
+impl AnimationTransition {
+    /// Create a new transition from the given state, without conditions nor cross fade
+    pub fn new(from: &str, to: &str) -> Self {
+        AnimationTransition {
+            from: Some(from.to_string()),
+            to: to.to_string(),
+            conditions: Vec::default(),
+            duration: 0.,
+        }
+    }
+
+    /// Create a new transition from any other state, without conditions nor cross fade
+    pub fn from_any(to: &str) -> Self {
+        AnimationTransition {
+            from: None,
+            ..AnimationTransition::new("", to)
+        }
+    }
+
+    /// Take the transition only when the parameter is greater than the value
+    pub fn when_greater(mut self, parameter: &str, value: f32) -> Self {
+        self.conditions
+            .push(TransitionCondition::Greater(parameter.to_string(), value));
+        self
+    }
+
+    /// Take the transition only when the parameter is less than the value
+    pub fn when_less(mut self, parameter: &str, value: f32) -> Self {
+        self.conditions
+            .push(TransitionCondition::Less(parameter.to_string(), value));
+        self
+    }
+
+    /// Take the transition only when the animation of the current state is done
+    pub fn when_done(mut self) -> Self {
+        self.conditions.push(TransitionCondition::Done);
+        self
+    }
+
+    /// Cross fade the animations of both states over the given duration, in seconds
+    pub fn with_duration(mut self, duration: f32) -> Self {
+        self.duration = duration;
+        self
+    }
+}
+
+/// Animation of a previous state, fading out after a transition
+#[derive(Clone, Debug)]
+pub(crate) struct FadeOut<I> {
+    pub id: I,
+    pub weight: f32,
+    pub speed: f32,
+}
+
+/// Plays animations through named states and parameter-driven transitions between them, on top of
+/// the `AnimationControlSet` of the entity.
+///
+/// Gameplay code only sets parameters, like the speed of a character, and the
+/// `AnimationStateMachineSystem` takes the first transition of the current state whose conditions
+/// hold, cross fading the animations of both states with their blend weights. The animations are
+/// run in the `AnimationControlSet` with the ids of the states, which shouldn't be used by other
+/// animations.
+///
+/// ### Type parameters:
+///
+/// - `I`: identifier type for running animations, only one animation can be run at the same time
+///        with the same id
+/// - `T`: the component type that the animation should be applied to
+#[derive(Clone, Debug)]
+pub struct AnimationStateMachine<I, T>
+where
+    T: AnimationSampling,
+{
+    /// States by name
+    pub states: FnvHashMap<String, AnimationState<I, T>>,
+    /// Transitions, checked in order
+    pub transitions: Vec<AnimationTransition>,
+    pub(crate) parameters: FnvHashMap<String, f32>,
+    pub(crate) current: String,
+    pub(crate) entering: bool,
+    pub(crate) weight: f32,
+    pub(crate) fade_in: f32,
+    pub(crate) fading: Vec<FadeOut<I>>,
+}
+
+impl<I, T> AnimationStateMachine<I, T>
+where
+    T: AnimationSampling,
+{
+    /// Create a new state machine, starting in the given state
+    pub fn new(initial: &str) -> Self {
+        AnimationStateMachine {
+            states: FnvHashMap::default(),
+            transitions: Vec::default(),
+            parameters: FnvHashMap::default(),
+            current: initial.to_string(),
+            entering: true,
+            weight: 1.,
+            fade_in: 0.,
+            fading: Vec::default(),
+        }
+    }
+
+    /// Add a state with the given name
+    pub fn with_state(mut self, name: &str, state: AnimationState<I, T>) -> Self {
+        self.states.insert(name.to_string(), state);
+        self
+    }
+
+    /// Add a transition, checked after the ones already added
+    pub fn with_transition(mut self, transition: AnimationTransition) -> Self {
+        self.transitions.push(transition);
+        self
+    }
+
+    /// Set the value of a parameter
+    pub fn set_parameter(&mut self, name: &str, value: f32) {
+        self.parameters.insert(name.to_string(), value);
+    }
+
+    /// Get the value of a parameter, parameters never set are 0
+    pub fn parameter(&self, name: &str) -> f32 {
+        self.parameters.get(name).cloned().unwrap_or(0.)
+    }
+
+    /// Name of the current state
+    pub fn current_state(&self) -> &str {
+        &self.current
+    }
+
+    /// Check if the machine is cross fading from previous states
+    pub fn in_transition(&self) -> bool {
+        !self.fading.is_empty()
+    }
+}
+
+impl<I, T> Component for AnimationStateMachine<I, T>
+where
+    I: Send + Sync + 'static,
+    T: AnimationSampling,
+{
+    type Storage = DenseVecStorage<Self>;
+}
@@ -2,12 +2,15 @@ use std::marker;
 use std::time::Duration;
 
 use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{duration_to_secs, Time};
 use minterpolate::InterpolationPrimitive;
-use specs::{Component, Entities, Entity, Fetch, Join, ReadStorage, System, WriteStorage};
+use shrev::EventChannel;
+use specs::{Component, Entities, Entity, Fetch, FetchMut, Join, ReadStorage, System,
+            WriteStorage};
 
 use resources::{Animation, AnimationCommand, AnimationControl, AnimationControlSet,
-                AnimationHierarchy, AnimationSampling, ControlState, Sampler, SamplerControl,
-                SamplerControlSet, StepDirection};
+                AnimationEvent, AnimationHierarchy, AnimationSampling, AnimationTimeScale,
+                ControlState, Sampler, SamplerControl, SamplerControlSet, StepDirection};
 
 /// System for setting up animations, should run before `SamplerInterpolationSystem`.
 ///
@@ -15,6 +18,9 @@ use resources::{Animation, AnimationCommand, AnimationControl, AnimationControlS
 /// animations they describe. If an animation only targets a single node/entity, there is no need
 /// for `AnimationHierarchy`.
 ///
+/// Will also send an `AnimationEvent` through the `EventChannel<AnimationEvent<I>>` for each
+/// marker of the animations the playback crossed since the last frame.
+///
 /// ### Type parameters:
 ///
 /// - `I`: identifier type for running animations, only one animation can be run at the same time
@@ -42,29 +48,59 @@ where
 {
     type SystemData = (
         Entities<'a>,
+        Fetch<'a, Time>,
+        Fetch<'a, AnimationTimeScale>,
         Fetch<'a, AssetStorage<Animation<T>>>,
         Fetch<'a, AssetStorage<Sampler<T::Primitive>>>,
         WriteStorage<'a, AnimationControlSet<I, T>>,
         WriteStorage<'a, SamplerControlSet<T>>,
         ReadStorage<'a, AnimationHierarchy<T>>,
         ReadStorage<'a, T>,
+        FetchMut<'a, EventChannel<AnimationEvent<I>>>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
             entities,
+            time,
+            time_scale,
             animation_storage,
             sampler_storage,
             mut controls,
             mut samplers,
             hierarchies,
             transforms,
+            mut events,
         ) = data;
         let mut remove_sets = Vec::default();
         for (entity, control_set) in (&*entities, &mut controls).join() {
+            let delta = time.delta_seconds() * time_scale.0 * control_set.time_scale;
+            control_set.start_deferred(delta);
             let mut remove_ids = Vec::default();
             for &mut (ref id, ref mut control) in control_set.animations.iter_mut() {
                 let mut remove = false;
+                // The rate is applied to the samplers along with the other running updates
+                if let AnimationCommand::SetRate(rate) = control.command {
+                    control.rate_multiplier = rate;
+                    control.command = if control.state.is_paused() {
+                        AnimationCommand::Pause
+                    } else {
+                        AnimationCommand::Start
+                    };
+                }
+                // A seek is only applied to started animations
+                let started = control.state.is_running() || control.state.is_paused();
+                // Read before processing, as finished animations get their samplers removed
+                let time = animation_storage.get(&control.animation).and_then(|animation| {
+                    animation_time(
+                        &entity,
+                        animation,
+                        control.id,
+                        hierarchies.get(entity),
+                        &*sampler_storage,
+                        &samplers,
+                    )
+                });
                 if let Some(state) = animation_storage.get(&control.animation).and_then(
                     |animation| {
                         process_animation_control(
@@ -82,16 +118,75 @@ where
                 ) {
                     control.state = state;
                 }
+                if let (Some((time, backwards)), Some(animation)) =
+                    (time, animation_storage.get(&control.animation))
+                {
+                    for marker in &animation.events {
+                        let crossed = match (control.last_time, backwards) {
+                            (None, false) => marker.time <= time,
+                            (None, true) => marker.time >= time,
+                            (Some(last), false) if last <= time => {
+                                marker.time > last && marker.time <= time
+                            }
+                            (Some(last), true) if last >= time => {
+                                marker.time < last && marker.time >= time
+                            }
+                            // Looped since last frame
+                            (Some(last), false) => marker.time > last || marker.time <= time,
+                            (Some(last), true) => marker.time < last || marker.time >= time,
+                        };
+                        if crossed {
+                            events.single_write(AnimationEvent {
+                                entity,
+                                id: *id,
+                                name: marker.name.clone(),
+                            });
+                        }
+                    }
+                    control.last_time = Some(time);
+                }
                 if let AnimationCommand::Step(_) = control.command {
                     control.command = AnimationCommand::Start;
                 }
                 if let AnimationCommand::SetInputValue(_) = control.command {
                     control.command = AnimationCommand::Start;
                 }
+                // Blend weights are applied once the animation is started, and keep it running
+                // or paused
+                let weights_set = match control.command {
+                    AnimationCommand::SetBlendWeights(_) => started,
+                    _ => false,
+                };
+                if weights_set {
+                    control.command = if control.state.is_paused() {
+                        AnimationCommand::Pause
+                    } else {
+                        AnimationCommand::Start
+                    };
+                }
+                let seek = match control.command {
+                    AnimationCommand::Seek(time) if started => Some(time),
+                    _ => None,
+                };
+                if let Some(time) = seek {
+                    // Markers are crossed from the new time on
+                    control.last_time = Some(time);
+                    control.command = if control.state.is_paused() {
+                        AnimationCommand::Pause
+                    } else {
+                        AnimationCommand::Start
+                    };
+                }
                 if remove {
                     remove_ids.push(*id);
                 }
             }
+            set_time_scale(
+                entity,
+                hierarchies.get(entity),
+                &mut samplers,
+                control_set.time_scale,
+            );
             for id in remove_ids {
                 control_set.remove(id);
                 if control_set.is_empty() {
@@ -106,6 +201,76 @@ where
     }
 }
 
+/// Pass the time scale of an `AnimationControlSet` on to the `SamplerControlSet`s of the nodes it
+/// animates.
+fn set_time_scale<T>(
+    entity: Entity,
+    hierarchy: Option<&AnimationHierarchy<T>>,
+    samplers: &mut WriteStorage<SamplerControlSet<T>>,
+    time_scale: f32,
+) where
+    T: AnimationSampling + Component,
+{
+    let nodes = match hierarchy {
+        Some(hierarchy) => hierarchy.nodes.values().cloned().collect(),
+        None => vec![entity],
+    };
+    for node in nodes {
+        if let Some(sampler_set) = samplers.get_mut(node) {
+            sampler_set.time_scale = time_scale;
+        }
+    }
+}
+
+/// Get the input time of a running animation, from the sampler of its first node, and whether it
+/// is playing backwards.
+///
+/// Done samplers are at the end of their playback, so that the markers of the last frames are still
+/// crossed.
+fn animation_time<T>(
+    entity: &Entity,
+    animation: &Animation<T>,
+    control_id: u64,
+    hierarchy: Option<&AnimationHierarchy<T>>,
+    sampler_storage: &AssetStorage<Sampler<T::Primitive>>,
+    samplers: &WriteStorage<SamplerControlSet<T>>,
+) -> Option<(f32, bool)>
+where
+    T: AnimationSampling,
+{
+    if animation.events.is_empty() {
+        return None;
+    }
+    // The first node may be masked out, use the first node with a sampler of the animation
+    let control = animation
+        .nodes
+        .iter()
+        .filter_map(|&(ref node_index, _, _)| {
+            let node_entity = match hierarchy {
+                Some(h) => *h.nodes.get(node_index)?,
+                None => *entity,
+            };
+            samplers
+                .get(node_entity)?
+                .samplers
+                .iter()
+                .find(|s| s.control_id == control_id)
+        })
+        .next()?;
+    let backwards = (control.rate_multiplier < 0.) != control.reversed;
+    match control.state {
+        ControlState::Running(duration) | ControlState::Paused(duration) => {
+            Some((duration_to_secs(duration), backwards))
+        }
+        ControlState::Done if backwards => Some((0., backwards)),
+        ControlState::Done => sampler_storage
+            .get(&control.sampler)
+            .and_then(|sampler| sampler.input.last().cloned())
+            .map(|time| (time, backwards)),
+        _ => None,
+    }
+}
+
 /// Check if the given animation list is for a single node. If so, we don't need an
 /// `AnimationHierarchy`.
 fn only_one_index<C, P>(nodes: &[(usize, C, Handle<Sampler<P>>)]) -> bool
@@ -183,7 +348,9 @@ where
         // We ignore the command here because we need the animation to be
         // started before we can pause it, and to avoid a lot of checks for
         // abort. The command will be processed next frame.
-        (&ControlState::Requested, &AnimationCommand::Start) => {
+        (&ControlState::Requested, &AnimationCommand::Start)
+        | (&ControlState::Requested, &AnimationCommand::Seek(_))
+        | (&ControlState::Requested, &AnimationCommand::SetBlendWeights(_)) => {
             control.id = *next_id;
             *next_id += 1;
             if start_animation(
@@ -222,7 +389,14 @@ where
             None
         }
 
-        (&ControlState::Running(..), &AnimationCommand::SetBlendWeights(ref weights)) => {
+        (&ControlState::Running(..), &AnimationCommand::Seek(time))
+        | (&ControlState::Paused(..), &AnimationCommand::Seek(time)) => {
+            seek_animation(control.id, hierarchy, samplers, time);
+            None
+        }
+
+        (&ControlState::Running(..), &AnimationCommand::SetBlendWeights(ref weights))
+        | (&ControlState::Paused(..), &AnimationCommand::SetBlendWeights(ref weights)) => {
             set_blend_weights(control.id, hierarchy, samplers, weights);
             None
         }
@@ -245,6 +419,7 @@ where
                 *remove = true;
             } else {
                 update_animation_rate(control.id, hierarchy, samplers, control.rate_multiplier);
+                update_animation_weight(control.id, hierarchy, samplers, control.blend_weight);
             }
             None
         }
@@ -284,6 +459,7 @@ where
     if animation
         .nodes
         .iter()
+        .filter(|&&(node_index, _, _)| control.applies_to(node_index))
         .any(|&(ref node_index, _, ref sampler_handle)| {
             hierarchy.nodes.get(node_index).is_none()
                 || sampler_storage.get(sampler_handle).is_none()
@@ -291,8 +467,11 @@ where
         return false;
     }
 
-    // setup sampler tree
+    // setup sampler tree, skipping the nodes outside of the mask
     for &(ref node_index, ref channel, ref sampler_handle) in &animation.nodes {
+        if !control.applies_to(*node_index) {
+            continue;
+        }
         let node_entity = hierarchy.nodes.get(node_index).unwrap();
         let component = targets.get(*node_entity).unwrap();
         let sampler_control = SamplerControl::<T> {
@@ -304,6 +483,9 @@ where
             after: component.current_sample(channel),
             rate_multiplier: control.rate_multiplier,
             blend_weight: 1.0,
+            animation_weight: control.blend_weight,
+            reversed: false,
+            additive: control.additive,
         };
         let add = if let Some(ref mut set) = samplers.get_mut(*node_entity) {
             set.add_control(sampler_control);
@@ -379,6 +561,21 @@ fn set_animation_input<T>(
     }
 }
 
+fn seek_animation<T>(
+    control_id: u64,
+    hierarchy: &AnimationHierarchy<T>,
+    controls: &mut WriteStorage<SamplerControlSet<T>>,
+    time: f32,
+) where
+    T: AnimationSampling,
+{
+    for (_, node_entity) in &hierarchy.nodes {
+        if let Some(ref mut s) = controls.get_mut(*node_entity) {
+            s.seek(control_id, time);
+        }
+    }
+}
+
 fn set_blend_weights<T>(
     control_id: u64,
     hierarchy: &AnimationHierarchy<T>,
@@ -411,6 +608,21 @@ fn update_animation_rate<T>(
     }
 }
 
+fn update_animation_weight<T>(
+    control_id: u64,
+    hierarchy: &AnimationHierarchy<T>,
+    samplers: &mut WriteStorage<SamplerControlSet<T>>,
+    weight: f32,
+) where
+    T: AnimationSampling,
+{
+    for (_, node_entity) in &hierarchy.nodes {
+        if let Some(ref mut s) = samplers.get_mut(*node_entity) {
+            s.set_animation_weight(control_id, weight);
+        }
+    }
+}
+
 /// Check if all nodes in an `AnimationHierarchy` are ready for termination, if so remove all
 /// `SamplerControlSet`s for the hierarchy, if not request termination on all sampler controls
 fn check_and_terminate_animation<T>(
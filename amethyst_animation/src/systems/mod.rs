@@ -1,5 +1,6 @@
 pub use self::control::AnimationControlSystem;
 pub use self::sampling::SamplerInterpolationSystem;
+pub use self::state_machine::AnimationStateMachineSystem;
 
 use amethyst_assets::Processor;
 
@@ -7,6 +8,7 @@ use resources::{Animation, Sampler};
 
 mod sampling;
 mod control;
+mod state_machine;
 
 /// Asset storage processor for `Sampler`
 pub type SamplerProcessor<S> = Processor<Sampler<S>>;
@@ -7,8 +7,8 @@ use itertools::Itertools;
 use minterpolate::InterpolationPrimitive;
 use specs::{Component, Fetch, Join, System, WriteStorage};
 
-use resources::{AnimationSampling, BlendMethod, ControlState, EndControl, Sampler, SamplerControl,
-                SamplerControlSet};
+use resources::{AnimationSampling, AnimationTimeScale, BlendMethod, ControlState, EndControl,
+                Sampler, SamplerControl, SamplerControlSet};
 
 /// System for interpolating active samplers.
 ///
@@ -18,6 +18,12 @@ use resources::{AnimationSampling, BlendMethod, ControlState, EndControl, Sample
 /// Will process all active `SamplerControlSet`, and update the target component for the entity they
 /// belong to.
 ///
+/// The deltas of additive samplers are added to the blended value of their channel, or to the rest
+/// pose if no other sampler runs on the channel.
+///
+/// The time of the samplers is scaled by the `AnimationTimeScale` resource and the `time_scale` of
+/// their `SamplerControlSet`.
+///
 /// ### Type parameters:
 ///
 /// - `T`: the component type that the animation should be applied to
@@ -28,6 +34,7 @@ where
 {
     m: marker::PhantomData<T>,
     inner: Vec<(f32, T::Channel, T::Primitive)>,
+    additive: Vec<(f32, T::Channel, T::Primitive, T::Primitive)>,
     channels: Vec<T::Channel>,
 }
 
@@ -39,6 +46,7 @@ where
         Self {
             m: marker::PhantomData,
             inner: Vec::default(),
+            additive: Vec::default(),
             channels: Vec::default(),
         }
     }
@@ -50,23 +58,33 @@ where
 {
     type SystemData = (
         Fetch<'a, Time>,
+        Fetch<'a, AnimationTimeScale>,
         Fetch<'a, AssetStorage<Sampler<T::Primitive>>>,
         WriteStorage<'a, SamplerControlSet<T>>,
         WriteStorage<'a, T>,
     );
 
-    fn run(&mut self, (time, samplers, mut control_sets, mut comps): Self::SystemData) {
+    fn run(&mut self, data: Self::SystemData) {
+        let (time, time_scale, samplers, mut control_sets, mut comps) = data;
         for (control_set, comp) in (&mut control_sets, &mut comps).join() {
+            let delta = time.delta_seconds() * time_scale.0 * control_set.time_scale;
             self.inner.clear();
+            self.additive.clear();
             for control in control_set.samplers.iter_mut() {
                 if let Some(ref sampler) = samplers.get(&control.sampler) {
-                    process_sampler(control, sampler, &time, &mut self.inner);
+                    process_sampler(control, sampler, delta, &mut self.inner, &mut self.additive);
                 }
             }
-            if self.inner.len() > 0 {
+            if self.inner.len() > 0 || self.additive.len() > 0 {
                 self.channels.clear();
-                self.channels
-                    .extend(self.inner.iter().map(|o| &o.1).unique().cloned());
+                self.channels.extend(
+                    self.inner
+                        .iter()
+                        .map(|o| &o.1)
+                        .chain(self.additive.iter().map(|o| &o.1))
+                        .unique()
+                        .cloned(),
+                );
                 for channel in &self.channels {
                     match comp.blend_method(channel) {
                         None => {
@@ -81,7 +99,13 @@ where
                         }
 
                         Some(BlendMethod::Linear) => {
-                            if let Some(p) = linear_blend::<T>(channel, &self.inner) {
+                            if let Some(p) = linear_blend::<T>(channel, &self.inner)
+                                .or_else(|| rest_pose::<T>(channel, &self.additive))
+                            {
+                                let p = self.additive
+                                    .iter()
+                                    .filter(|o| o.1 == *channel)
+                                    .fold(p, |p, o| T::add_delta(channel, &p, &o.2, o.0));
                                 comp.apply_sample(channel, &p);
                             }
                         }
@@ -99,29 +123,52 @@ where
 /// - `control`: sampler control object
 /// - `sampler`: the sampler reference from the control object
 /// - `component`: the component to update
-/// - `now`: synchronized `Instant` for the current frame
+/// - `delta`: scaled time elapsed since the last frame, in seconds
+/// - `output`: weighted samples to blend
+/// - `additive`: weighted deltas of additive samplers, along with their rest pose
 fn process_sampler<T>(
     control: &mut SamplerControl<T>,
     sampler: &Sampler<T::Primitive>,
-    time: &Time,
+    delta: f32,
     output: &mut Vec<(f32, T::Channel, T::Primitive)>,
+    additive: &mut Vec<(f32, T::Channel, T::Primitive, T::Primitive)>,
 ) where
     T: AnimationSampling,
 {
     use resources::ControlState::*;
 
-    let (new_state, new_end) = update_duration_and_check(&control, sampler, time);
+    let (new_state, new_end, bounced) = update_duration_and_check(&control, sampler, delta);
 
     // If a new end condition has been computed, update in control state
     if let Some(end) = new_end {
         control.end = end;
     }
+    if bounced {
+        control.reversed = !control.reversed;
+    }
 
     // Do sampling
     match new_state {
+        Running(duration) | Paused(duration) if control.additive => {
+            let interpolate = |input| {
+                sampler.function.interpolate(input, &sampler.input, &sampler.output, false)
+            };
+            let first = sampler.input.first().cloned().unwrap_or(0.);
+            let delta = T::additive_delta(
+                &control.channel,
+                &interpolate(first),
+                &interpolate(duration_to_secs(duration)),
+            );
+            additive.push((
+                control.blend_weight * control.animation_weight,
+                control.channel.clone(),
+                delta,
+                control.after,
+            ));
+        }
         Running(duration) | Paused(duration) => {
             output.push((
-                control.blend_weight,
+                control.blend_weight * control.animation_weight,
                 control.channel.clone(),
                 sampler.function.interpolate(
                     duration_to_secs(duration),
@@ -131,9 +178,34 @@ fn process_sampler<T>(
                 ),
             ));
         }
+        // Additive samplers have no delta once done
+        Done if control.additive => {}
         Done => {
-            if let EndControl::Normal = control.end {
-                output.push((control.blend_weight, control.channel.clone(), control.after));
+            // An aborted animation doesn't jump to the pose it would hold at its end
+            let aborted = control.state == Abort;
+            let interpolate = |input| {
+                sampler.function.interpolate(input, &sampler.input, &sampler.output, false)
+            };
+            let sample = match control.end {
+                EndControl::Normal => Some(control.after),
+                EndControl::Stay if !aborted => {
+                    // Playing backwards ends on the first frame
+                    let end = if (control.rate_multiplier < 0.) != control.reversed {
+                        sampler.input.first()
+                    } else {
+                        sampler.input.last()
+                    };
+                    Some(interpolate(end.cloned().unwrap_or(0.)))
+                }
+                EndControl::StayAt(input) if !aborted => Some(interpolate(input)),
+                _ => None,
+            };
+            if let Some(sample) = sample {
+                output.push((
+                    control.blend_weight * control.animation_weight,
+                    control.channel.clone(),
+                    sample,
+                ));
             }
         }
         _ => {}
@@ -150,72 +222,120 @@ fn process_sampler<T>(
 ///
 /// - `control`: sampler control object
 /// - `sampler`: sampler reference from control
-/// - `now`: synchronized `Instant` for the current frame
+/// - `delta`: scaled time elapsed since the last frame, in seconds
 ///
 /// ## Returns
 ///
-/// Will return the new state of the sampling, and optionally a new end control state (for looping)
+/// Will return the new state of the sampling, optionally a new end control state (for looping),
+/// and whether the direction of playback has to be reversed (for ping pong)
 fn update_duration_and_check<T>(
     control: &SamplerControl<T>,
     sampler: &Sampler<T::Primitive>,
-    time: &Time,
-) -> (ControlState, Option<EndControl>)
+    delta: f32,
+) -> (ControlState, Option<EndControl>, bool)
 where
     T: AnimationSampling,
 {
     use resources::ControlState::*;
+    let zero = Duration::from_secs(0);
+    let last_frame = sampler
+        .input
+        .last()
+        .cloned()
+        .map(secs_to_duration)
+        .unwrap_or(zero.clone());
+    let rate = if control.reversed {
+        -control.rate_multiplier
+    } else {
+        control.rate_multiplier
+    };
     // Update state with new duration
     // Check duration for end of sampling
     match control.state {
-        // requested sampling => start interpolating
-        Requested => (Running(Duration::from_secs(0)), None),
+        // requested sampling => start interpolating, from the end when playing backwards
+        Requested if rate < 0. => (Running(last_frame), None, false),
+        Requested => (Running(zero), None, false),
 
         // abort sampling => end interpolating
-        Abort => (Done, None),
+        Abort => (Done, None, false),
 
-        // sampling is running, update duration and check end condition
-        Running(duration) => {
-            let zero = Duration::from_secs(0);
-            let current_dur =
-                duration + secs_to_duration(time.delta_seconds() * control.rate_multiplier);
-            let last_frame = sampler
-                .input
-                .last()
-                .cloned()
-                .map(secs_to_duration)
-                .unwrap_or(zero.clone());
+        // sampling is running forward, update duration and check end condition
+        Running(duration) if rate >= 0. => {
+            let current_dur = duration + secs_to_duration(delta * rate);
             // duration is past last frame of sampling
             if last_frame != zero && current_dur > last_frame {
                 // Check end conditions
                 match control.end {
                     // Do loop control
-                    EndControl::Loop(Some(i)) if i <= 1 => (Done, Some(EndControl::Normal)),
+                    EndControl::Loop(Some(i)) if i <= 1 => (Done, Some(EndControl::Normal), false),
                     EndControl::Loop(None) => {
-                        (Running(next_duration(last_frame, current_dur).0), None)
+                        (Running(next_duration(last_frame, current_dur).0), None, false)
                     }
                     EndControl::Loop(Some(i)) => {
                         let (next_dur, loops_removed) = next_duration(last_frame, current_dur);
-                        let remaining_loops = i - loops_removed;
-                        if remaining_loops <= 1 {
-                            (Done, Some(EndControl::Normal))
-                        } else {
-                            (
-                                Running(next_dur),
-                                Some(EndControl::Loop(Some(remaining_loops))),
-                            )
-                        }
+                        end_loops(i, loops_removed, next_dur)
+                    }
+                    // Bounce back from the last frame
+                    EndControl::PingPong => {
+                        let past = current_dur - last_frame;
+                        (Running(last_frame - past.min(last_frame)), None, true)
                     }
                     // All other end cases will be handled during sampling
-                    _ => (Done, None),
+                    _ => (Done, None, false),
                 }
             } else {
                 // last frame not reached, keep sampling
-                (Running(current_dur), None)
+                (Running(current_dur), None, false)
+            }
+        }
+
+        // sampling is running backwards, the start is the end of sampling
+        Running(duration) => {
+            let delta = secs_to_duration(delta * -rate);
+            if delta <= duration {
+                (Running(duration - delta), None, false)
+            } else if last_frame == zero {
+                (Running(zero), None, false)
+            } else {
+                let before = delta - duration;
+                match control.end {
+                    EndControl::Loop(Some(i)) if i <= 1 => (Done, Some(EndControl::Normal), false),
+                    EndControl::Loop(None) => {
+                        let next_dur = last_frame - next_duration(last_frame, before).0;
+                        (Running(next_dur), None, false)
+                    }
+                    EndControl::Loop(Some(i)) => {
+                        let (remain, loops) = next_duration(last_frame, before);
+                        end_loops(i, loops + 1, last_frame - remain)
+                    }
+                    // Bounce forward from the first frame
+                    EndControl::PingPong => (Running(before.min(last_frame)), None, true),
+                    _ => (Done, None, false),
+                }
             }
         }
 
         // Done and paused will be handled during sampling
-        ref state => (state.clone(), None),
+        ref state => (state.clone(), None, false),
+    }
+}
+
+/// Count down the remaining loops of `EndControl::Loop`, ending the sampling when the last loop is
+/// done.
+fn end_loops(
+    loops: u32,
+    loops_removed: u32,
+    next_dur: Duration,
+) -> (ControlState, Option<EndControl>, bool) {
+    let remaining_loops = loops.saturating_sub(loops_removed);
+    if remaining_loops <= 1 {
+        (ControlState::Done, Some(EndControl::Normal), false)
+    } else {
+        (
+            ControlState::Running(next_dur),
+            Some(EndControl::Loop(Some(remaining_loops))),
+            false,
+        )
     }
 }
 
@@ -250,6 +370,18 @@ where
     }
 }
 
+/// Rest pose the deltas of additive samplers are added to when no other sampler runs on the
+/// channel, the component itself can't be used as it already contains the previous deltas.
+fn rest_pose<T>(
+    channel: &T::Channel,
+    additive: &Vec<(f32, T::Channel, T::Primitive, T::Primitive)>,
+) -> Option<T::Primitive>
+where
+    T: AnimationSampling,
+{
+    additive.iter().find(|o| o.1 == *channel).map(|o| o.3)
+}
+
 fn single_blend<T>(
     total: f32,
     &(ref weight, _, ref primitive): &(f32, T::Channel, T::Primitive),
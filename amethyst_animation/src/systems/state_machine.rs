@@ -0,0 +1,191 @@
+use std::marker;
+
+use amethyst_core::Time;
+use specs::{Component, Entities, Fetch, Join, System, WriteStorage};
+
+use resources::{AnimationCommand, AnimationControlSet, AnimationSampling, AnimationTimeScale};
+use state_machine::{AnimationStateMachine, FadeOut, TransitionCondition};
+use util::get_animation_set;
+
+/// System driving `AnimationStateMachine`s, should run before `AnimationControlSystem`.
+///
+/// Will start the animation of the current state of each machine, take the transitions whose
+/// conditions hold, and cross fade the animations of the previous and new states by updating their
+/// blend weights in the `AnimationControlSet` of the entity.
+///
+/// ### Type parameters:
+///
+/// - `I`: identifier type for running animations, only one animation can be run at the same time
+///        with the same id
+/// - `T`: the component type that the animation should be applied to
+#[derive(Default)]
+pub struct AnimationStateMachineSystem<I, T> {
+    m: marker::PhantomData<(I, T)>,
+}
+
+impl<I, T> AnimationStateMachineSystem<I, T> {
+    pub fn new() -> Self {
+        Self {
+            m: marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, I, T> System<'a> for AnimationStateMachineSystem<I, T>
+where
+    I: PartialEq + Copy + Send + Sync + 'static,
+    T: AnimationSampling + Component,
+{
+    type SystemData = (
+        Entities<'a>,
+        Fetch<'a, Time>,
+        Fetch<'a, AnimationTimeScale>,
+        WriteStorage<'a, AnimationStateMachine<I, T>>,
+        WriteStorage<'a, AnimationControlSet<I, T>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, time, time_scale, mut machines, mut controls) = data;
+        for (entity, machine) in (&*entities, &mut machines).join() {
+            let control_set = get_animation_set(&mut controls, entity);
+            if let Some((to, duration)) = find_transition(machine, control_set) {
+                begin_transition(machine, control_set, to, duration);
+            }
+            start_current(machine, control_set);
+            let delta = time.delta_seconds() * time_scale.0 * control_set.time_scale;
+            update_weights(machine, control_set, delta);
+        }
+    }
+}
+
+/// Find the first transition leaving the current state whose conditions all hold.
+fn find_transition<I, T>(
+    machine: &AnimationStateMachine<I, T>,
+    control_set: &mut AnimationControlSet<I, T>,
+) -> Option<(String, f32)>
+where
+    I: PartialEq + Copy,
+    T: AnimationSampling,
+{
+    let done = match machine.states.get(&machine.current) {
+        Some(state) => !machine.entering && !control_set.has_animation(state.id),
+        None => false,
+    };
+    machine
+        .transitions
+        .iter()
+        .filter(|t| t.to != machine.current && machine.states.contains_key(&t.to))
+        .filter(|t| t.from.as_ref().map_or(true, |from| *from == machine.current))
+        .find(|t| {
+            t.conditions.iter().all(|condition| match *condition {
+                TransitionCondition::Greater(ref name, value) => machine.parameter(name) > value,
+                TransitionCondition::Less(ref name, value) => machine.parameter(name) < value,
+                TransitionCondition::Done => done,
+            })
+        })
+        .map(|t| (t.to.clone(), t.duration))
+}
+
+/// Leave the current state, fading its animation out over the given duration, and enter the
+/// given state.
+fn begin_transition<I, T>(
+    machine: &mut AnimationStateMachine<I, T>,
+    control_set: &mut AnimationControlSet<I, T>,
+    to: String,
+    duration: f32,
+) where
+    I: PartialEq + Copy,
+    T: AnimationSampling,
+{
+    // Nothing to fade out if the animation of the current state wasn't started yet
+    let previous = machine.states.get(&machine.current).map(|s| s.id);
+    if let (Some(id), false) = (previous, machine.entering) {
+        if duration > 0. && machine.weight > 0. {
+            machine.fading.push(FadeOut {
+                id,
+                weight: machine.weight,
+                speed: 1. / duration,
+            });
+        } else {
+            control_set.abort(id);
+        }
+    }
+
+    machine.current = to;
+    let id = machine.states[&machine.current].id;
+    // A state still fading out fades back in from its current weight
+    let weight = match machine.fading.iter().position(|fade| fade.id == id) {
+        Some(index) => {
+            machine.entering = false;
+            machine.fading.remove(index).weight
+        }
+        None => {
+            machine.entering = true;
+            0.
+        }
+    };
+    if duration > 0. {
+        machine.weight = weight;
+        machine.fade_in = 1. / duration;
+    } else {
+        machine.weight = 1.;
+        machine.fade_in = 0.;
+    }
+}
+
+/// Start the animation of the current state, once the previous animation with the same id was
+/// removed from the control set.
+fn start_current<I, T>(
+    machine: &mut AnimationStateMachine<I, T>,
+    control_set: &mut AnimationControlSet<I, T>,
+) where
+    I: PartialEq + Copy,
+    T: AnimationSampling,
+{
+    if !machine.entering {
+        return;
+    }
+    if let Some(state) = machine.states.get(&machine.current) {
+        if !control_set.has_animation(state.id) {
+            control_set.add_animation(
+                state.id,
+                &state.animation,
+                state.end.clone(),
+                state.rate_multiplier,
+                AnimationCommand::Start,
+            );
+            machine.entering = false;
+        }
+    }
+}
+
+/// Advance the cross fades, abort the animations that faded out, and update the weights and rate
+/// of the animations in the control set.
+fn update_weights<I, T>(
+    machine: &mut AnimationStateMachine<I, T>,
+    control_set: &mut AnimationControlSet<I, T>,
+    delta: f32,
+) where
+    I: PartialEq + Copy,
+    T: AnimationSampling,
+{
+    machine.weight = (machine.weight + machine.fade_in * delta).min(1.);
+    for fade in &mut machine.fading {
+        fade.weight -= fade.speed * delta;
+        if fade.weight <= 0. {
+            control_set.abort(fade.id);
+        } else {
+            control_set.set_weight(fade.id, fade.weight);
+        }
+    }
+    machine.fading.retain(|fade| fade.weight > 0.);
+
+    if let Some(state) = machine.states.get(&machine.current) {
+        let parameter = state
+            .rate_parameter
+            .as_ref()
+            .map_or(1., |name| machine.parameter(name));
+        control_set.set_weight(state.id, machine.weight);
+        control_set.set_rate(state.id, state.rate_multiplier * parameter);
+    }
+}
@@ -1,5 +1,6 @@
 use amethyst_core::Transform;
 use amethyst_core::cgmath::{InnerSpace, Quaternion, Vector3};
+use minterpolate::InterpolationPrimitive;
 
 use resources::{AnimationSampling, BlendMethod};
 use util::SamplerPrimitive;
@@ -47,4 +48,41 @@ impl AnimationSampling for Transform {
     fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
         Some(BlendMethod::Linear)
     }
+
+    fn additive_delta(
+        channel: &Self::Channel,
+        first: &SamplerPrimitive<f32>,
+        current: &SamplerPrimitive<f32>,
+    ) -> SamplerPrimitive<f32> {
+        use util::SamplerPrimitive::*;
+        match (channel, *first, *current) {
+            (&TransformChannel::Rotation, Vec4(first), Vec4(current)) => {
+                let first = Quaternion::from(first);
+                let mut current = Quaternion::from(current);
+                // Both quaternions describe the same rotation, use the shortest delta
+                if first.dot(current) < 0. {
+                    current = -current;
+                }
+                Vec4((first.conjugate() * current).normalize().into())
+            }
+            _ => current.sub(first),
+        }
+    }
+
+    fn add_delta(
+        channel: &Self::Channel,
+        sample: &SamplerPrimitive<f32>,
+        delta: &SamplerPrimitive<f32>,
+        weight: f32,
+    ) -> SamplerPrimitive<f32> {
+        use util::SamplerPrimitive::*;
+        match (channel, *sample, *delta) {
+            (&TransformChannel::Rotation, Vec4(sample), Vec4(delta)) => {
+                let identity = Quaternion::new(1., 0., 0., 0.);
+                let delta = identity.slerp(Quaternion::from(delta), weight);
+                Vec4((Quaternion::from(sample).normalize() * delta).normalize().into())
+            }
+            _ => sample.add(&delta.mul(weight)),
+        }
+    }
 }
@@ -0,0 +1,72 @@
+use amethyst_renderer::CustomUniforms;
+use gfx::shade::core::UniformValue;
+
+use resources::{AnimationSampling, BlendMethod};
+use util::SamplerPrimitive;
+
+/// Channels that can be animated on `CustomUniforms`, for example the tint color of a material
+/// drawn with a custom shader.
+///
+/// Each channel is the name of a uniform along with its type, uniforms not set yet start at zero.
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum UniformChannel {
+    /// `float` uniform, sampled as `Scalar`
+    Scalar(String),
+    /// `vec2` uniform, sampled as `Vec2`
+    Vec2(String),
+    /// `vec3` uniform, sampled as `Vec3`
+    Vec3(String),
+    /// `vec4` uniform, sampled as `Vec4`
+    Vec4(String),
+}
+
+impl UniformChannel {
+    /// Name of the uniform
+    pub fn name(&self) -> &str {
+        use self::UniformChannel::*;
+        match *self {
+            Scalar(ref name) | Vec2(ref name) | Vec3(ref name) | Vec4(ref name) => name,
+        }
+    }
+}
+
+impl AnimationSampling for CustomUniforms {
+    type Channel = UniformChannel;
+    type Primitive = SamplerPrimitive<f32>;
+
+    fn apply_sample(&mut self, channel: &Self::Channel, data: &SamplerPrimitive<f32>) {
+        use self::UniformChannel::*;
+        match (channel, *data) {
+            (&Scalar(ref name), SamplerPrimitive::Scalar(d)) => self.set(name.as_str(), d),
+            (&Vec2(ref name), SamplerPrimitive::Vec2(d)) => self.set(name.as_str(), d),
+            (&Vec3(ref name), SamplerPrimitive::Vec3(d)) => self.set(name.as_str(), d),
+            (&Vec4(ref name), SamplerPrimitive::Vec4(d)) => self.set(name.as_str(), d),
+            _ => panic!("Attempt to apply invalid sample to CustomUniforms"),
+        }
+    }
+
+    fn current_sample(&self, channel: &Self::Channel) -> SamplerPrimitive<f32> {
+        use self::UniformChannel::*;
+        match (channel, self.get(channel.name())) {
+            (&Scalar(_), Some(UniformValue::F32(v))) => SamplerPrimitive::Scalar(v),
+            (&Vec2(_), Some(UniformValue::F32Vector2(v))) => SamplerPrimitive::Vec2(v),
+            (&Vec3(_), Some(UniformValue::F32Vector3(v))) => SamplerPrimitive::Vec3(v),
+            (&Vec4(_), Some(UniformValue::F32Vector4(v))) => SamplerPrimitive::Vec4(v),
+            _ => Self::default_primitive(channel),
+        }
+    }
+
+    fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
+        use self::UniformChannel::*;
+        match channel {
+            &Scalar(_) => SamplerPrimitive::Scalar(0.),
+            &Vec2(_) => SamplerPrimitive::Vec2([0.; 2]),
+            &Vec3(_) => SamplerPrimitive::Vec3([0.; 3]),
+            &Vec4(_) => SamplerPrimitive::Vec4([0.; 4]),
+        }
+    }
+
+    fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
+        Some(BlendMethod::Linear)
+    }
+}
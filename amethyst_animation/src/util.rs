@@ -1,9 +1,11 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
 use amethyst_core::cgmath::BaseNum;
 use amethyst_core::cgmath::num_traits::NumCast;
 use minterpolate::InterpolationPrimitive;
 use specs::{Entity, WriteStorage};
 
-use resources::{AnimationControlSet, AnimationSampling};
+use resources::{AnimationControlSet, AnimationSampling, QuantizeRotation};
 
 /// Get the animation set for an entity. If none exists, one will be added.
 ///
@@ -150,6 +152,66 @@ where
     }
 }
 
+impl<S> QuantizeRotation for SamplerPrimitive<S>
+where
+    S: BaseNum,
+{
+    fn quantize_rotation(&self) -> Self {
+        match *self {
+            SamplerPrimitive::Vec4(ref q) => {
+                let q = [
+                    q[0].to_f32().unwrap(),
+                    q[1].to_f32().unwrap(),
+                    q[2].to_f32().unwrap(),
+                    q[3].to_f32().unwrap(),
+                ];
+                let q = quantize_smallest_three(q);
+                SamplerPrimitive::Vec4([
+                    NumCast::from(q[0]).unwrap(),
+                    NumCast::from(q[1]).unwrap(),
+                    NumCast::from(q[2]).unwrap(),
+                    NumCast::from(q[3]).unwrap(),
+                ])
+            }
+            _ => *self,
+        }
+    }
+}
+
+/// Rounds a quaternion to the values a smallest three encoding with 16 bits per component
+/// decodes to: the largest component is dropped and recomputed from the three others, which
+/// are within plus or minus one over the square root of two.
+///
+/// The result is normalized, with the sign of the input.
+fn quantize_smallest_three(q: [f32; 4]) -> [f32; 4] {
+    const STEPS: f32 = 65535.;
+    let norm = q.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0. || !norm.is_finite() {
+        return q;
+    }
+    let largest = (1..4).fold(0, |largest, i| {
+        if q[i].abs() > q[largest].abs() {
+            i
+        } else {
+            largest
+        }
+    });
+    // The encoding flips the quaternion so the dropped component is positive, the sign is put
+    // back afterwards to keep interpolating between neighbouring key frames the same way.
+    let sign = q[largest].signum() / norm;
+    let mut out = [0.; 4];
+    let mut sum = 0.;
+    for i in (0..4).filter(|&i| i != largest) {
+        let v = (q[i] * sign).max(-FRAC_1_SQRT_2).min(FRAC_1_SQRT_2);
+        let step = ((v + FRAC_1_SQRT_2) / (2. * FRAC_1_SQRT_2) * STEPS).round();
+        out[i] = step / STEPS * 2. * FRAC_1_SQRT_2 - FRAC_1_SQRT_2;
+        sum += out[i] * out[i];
+    }
+    out[largest] = (1. - sum).max(0.).sqrt();
+    let sign = sign.signum();
+    [out[0] * sign, out[1] * sign, out[2] * sign, out[3] * sign]
+}
+
 fn mul_f32<T>(s: T, scalar: f32) -> T
 where
     T: BaseNum,
@@ -76,6 +76,7 @@ fn main() {
         }
 
         // Do per-frame stuff (display loading screen, ..)
+        println!("Loading: {:.0}%", progress.fraction_done() * 100.0);
         sleep(Duration::new(1, 0));
 
         storage.process(
@@ -0,0 +1,73 @@
+//! Assets depending on other assets.
+
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use amethyst_core::Time;
+use rayon::ThreadPool;
+use specs::{Fetch, FetchMut, System, SystemData};
+
+use {Asset, AssetStorage, HotReloadStrategy, ProgressCounter, Result};
+
+/// Asset data referring to other assets, like a material referring to the names of its textures.
+///
+/// A `Format` declares the dependencies of an asset by importing data implementing this trait.
+/// Once the data is imported, the `DependencyProcessor` loads the dependencies and only creates
+/// the asset when all of them are loaded. The asset fails to load if one of them fails.
+pub trait AssetDependencies<'a> {
+    /// The system data needed to load the dependencies, usually the `Loader` and the
+    /// `AssetStorage`s of the dependencies.
+    type SystemData: SystemData<'a>;
+
+    /// Loads the dependencies with the given progress, usually keeping their handles in the data
+    /// so the asset can be created from them.
+    fn load_dependencies(
+        &mut self,
+        progress: &mut ProgressCounter,
+        system_data: &mut Self::SystemData,
+    );
+}
+
+/// An asset processing system like `Processor`, for assets whose data implements
+/// `AssetDependencies`.
+///
+/// The data of the assets is converted with `Into<Result<A>>` once their dependencies are loaded.
+pub struct DependencyProcessor<A> {
+    marker: PhantomData<A>,
+}
+
+impl<A> DependencyProcessor<A> {
+    /// Creates a new asset processor for assets of type `A`.
+    pub fn new() -> Self {
+        DependencyProcessor {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, A> System<'a> for DependencyProcessor<A>
+where
+    A: Asset,
+    A::Data: AssetDependencies<'a> + Into<Result<A>>,
+{
+    type SystemData = (
+        FetchMut<'a, AssetStorage<A>>,
+        Fetch<'a, Arc<ThreadPool>>,
+        Fetch<'a, Time>,
+        Option<Fetch<'a, HotReloadStrategy>>,
+        <A::Data as AssetDependencies<'a>>::SystemData,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut storage, pool, time, strategy, mut dependency_data) = data;
+
+        storage.process_with_dependencies(
+            |data, progress| data.load_dependencies(progress, &mut dependency_data),
+            Into::into,
+            time.frame_number(),
+            &**pool,
+            strategy.as_ref().map(Deref::deref),
+        );
+    }
+}
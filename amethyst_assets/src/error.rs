@@ -19,6 +19,17 @@ error_chain! {
             description("Failed to load bytes from source")
         }
 
+        /// Returned if a source has nothing with the given path.
+        NotFound(path: String) {
+            description("Path not found in source")
+            display("Path {:?} not found in source", path)
+        }
+
+        /// Returned if an asset which an asset depends on failed to load.
+        Dependency {
+            description("Failed to load a dependency of the asset")
+        }
+
         /// Returned if a format failed to load the asset data.
         Format(format: &'static str) {
             description("Format could not load asset")
@@ -0,0 +1,116 @@
+use ron::de::from_bytes;
+use serde::de::DeserializeOwned;
+
+use {Asset, ErrorKind, Result, ResultExt, SimpleFormat, Source};
+
+/// Allows loading the data of any asset whose data can be deserialized, from a RON file.
+/// see: https://github.com/ron-rs/ron
+#[derive(Clone, Debug, Default)]
+pub struct RonFormat;
+
+impl<A> SimpleFormat<A> for RonFormat
+where
+    A: Asset,
+    A::Data: DeserializeOwned,
+{
+    const NAME: &'static str = "RON";
+
+    type Options = ();
+
+    fn import(&self, bytes: Vec<u8>, _: ()) -> Result<A::Data> {
+        from_bytes(&bytes).chain_err(|| "Failed to parse RON")
+    }
+}
+
+/// Options of a format which can be overridden by a metadata file next to the asset, see
+/// `Loader::load_with_metadata`.
+pub trait MetadataOptions {
+    /// The overrides read from the metadata file, usually a struct of optional fields.
+    type Overrides: DeserializeOwned;
+
+    /// Applies the overrides to the options the asset is loaded with.
+    fn merge(self, overrides: Self::Overrides) -> Self;
+}
+
+/// Reads the metadata file of an asset, if any, and merges it into the options.
+///
+/// Only a missing metadata file is ignored, other errors of the source are returned.
+pub(crate) fn merge_metadata<O>(name: &str, source: &Source, options: O) -> Result<O>
+where
+    O: MetadataOptions,
+{
+    let meta = format!("{}.meta.ron", name);
+    match source.load(&meta) {
+        Ok(bytes) => {
+            let overrides = from_bytes(&bytes)
+                .chain_err(|| format!("Failed to parse metadata file {:?}", meta))?;
+
+            Ok(options.merge(overrides))
+        }
+        Err(e) => {
+            if let ErrorKind::NotFound(_) = *e.kind() {
+                trace!("No metadata file {:?}, using the options as is", meta);
+
+                return Ok(options);
+            }
+
+            Err(e)
+                .chain_err(|| format!("Failed to load metadata file {:?}", meta))
+                .chain_err(|| ErrorKind::Source)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_metadata, MetadataOptions};
+    use {ErrorKind, Result, Source};
+
+    struct Scale(f32);
+
+    impl MetadataOptions for Scale {
+        type Overrides = Option<f32>;
+
+        fn merge(self, overrides: Option<f32>) -> Self {
+            Scale(overrides.unwrap_or(self.0))
+        }
+    }
+
+    enum TestSource {
+        Missing,
+        Broken,
+        Metadata(&'static str),
+    }
+
+    impl Source for TestSource {
+        fn modified(&self, _: &str) -> Result<u64> {
+            Ok(0)
+        }
+
+        fn load(&self, path: &str) -> Result<Vec<u8>> {
+            match *self {
+                TestSource::Missing => Err(ErrorKind::NotFound(path.to_string()).into()),
+                TestSource::Broken => Err(ErrorKind::Source.into()),
+                TestSource::Metadata(metadata) => Ok(metadata.as_bytes().to_vec()),
+            }
+        }
+    }
+
+    #[test]
+    fn missing_metadata() {
+        let options = merge_metadata("mesh.obj", &TestSource::Missing, Scale(1.0)).unwrap();
+        assert_eq!(options.0, 1.0);
+    }
+
+    #[test]
+    fn merged_metadata() {
+        let source = TestSource::Metadata("Some(2.0)");
+        let options = merge_metadata("mesh.obj", &source, Scale(1.0)).unwrap();
+        assert_eq!(options.0, 2.0);
+    }
+
+    #[test]
+    fn failing_metadata() {
+        assert!(merge_metadata("mesh.obj", &TestSource::Broken, Scale(1.0)).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+/// A stable identifier of an asset, which stays the same across runs unlike the id of its
+/// `Handle`.
+///
+/// Assets loaded by the `Loader` are identified by their source and name. Other assets, like the
+/// ones loaded from data, can be given a custom identifier with `AssetStorage::set_id`, like a
+/// UUID.
+///
+/// Components with `Handle` fields can be serialized by storing the identifiers of their assets,
+/// given by `AssetStorage::id`, and re-resolved on load with `AssetStorage::handle` or
+/// `Loader::load_id`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub enum AssetId {
+    /// Asset loaded from a source.
+    Path {
+        /// The id of the source, empty for the default directory.
+        source: String,
+        /// The name of the asset in the source.
+        name: String,
+    },
+    /// Custom identifier.
+    Custom(String),
+}
+
+impl AssetId {
+    /// Creates the identifier of an asset loaded from the default directory.
+    pub fn path<N: Into<String>>(name: N) -> Self {
+        AssetId::Path {
+            source: String::new(),
+            name: name.into(),
+        }
+    }
+}
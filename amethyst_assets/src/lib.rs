@@ -19,23 +19,36 @@ extern crate fnv;
 extern crate hibitset;
 #[macro_use]
 extern crate log;
+extern crate notify;
 extern crate parking_lot;
 extern crate rayon;
+extern crate ron;
+#[macro_use]
+extern crate serde;
 extern crate specs;
+extern crate zip;
 
 pub use asset::{Asset, Format, FormatValue, SimpleFormat};
 pub use cache::Cache;
+pub use dependency::{AssetDependencies, DependencyProcessor};
 pub use error::{Error, ErrorKind, Result, ResultExt};
+pub use formats::{MetadataOptions, RonFormat};
+pub use id::AssetId;
 pub use loader::Loader;
+pub use prefab::{Prefab, PrefabData, PrefabEntity, PrefabLoader, PrefabLoaderSystem};
 pub use progress::{Completion, Progress, ProgressCounter, Tracker};
 pub use reload::{HotReloadBundle, HotReloadStrategy, HotReloadSystem, Reload, SingleFile};
-pub use source::{Directory, Source};
+pub use source::{Directory, Source, ZipArchive};
 pub use storage::{AssetStorage, Handle, Processor, WeakHandle};
 
 mod asset;
 mod cache;
+mod dependency;
 mod error;
+mod formats;
+mod id;
 mod loader;
+mod prefab;
 mod progress;
 mod reload;
 mod source;
@@ -1,19 +1,29 @@
 use std::borrow::Borrow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
 use std::hash::Hash;
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use fnv::FnvHashMap;
+use parking_lot::Mutex;
 use rayon::ThreadPool;
 
-use {Asset, Directory, ErrorKind, Format, FormatValue, Progress, ResultExt, Source};
+use {Asset, AssetId, Directory, ErrorKind, Format, FormatValue, MetadataOptions, Progress, Result,
+     ResultExt, Source};
+use formats::merge_metadata;
 use storage::{AssetStorage, Handle, Processed};
 
 /// The asset loader, holding the sources and a reference to the `ThreadPool`.
+///
+/// Assets are imported by at most `num_workers` threads of the pool at the same time, leaving the
+/// other threads to the systems. Loads waiting for a worker are imported in the order of their
+/// priority, see `load_with_priority`.
 pub struct Loader {
     directory: Arc<Directory>,
     hot_reload: bool,
     pool: Arc<ThreadPool>,
+    queue: Arc<Mutex<LoadQueue>>,
     sources: FnvHashMap<String, Arc<Source>>,
 }
 
@@ -24,14 +34,37 @@ impl Loader {
     where
         P: Into<PathBuf>,
     {
+        let queue = LoadQueue {
+            jobs: BinaryHeap::new(),
+            next_order: 0,
+            num_running: 0,
+            num_workers: pool.current_num_threads(),
+        };
+
         Loader {
             directory: Arc::new(Directory::new(directory)),
             hot_reload: true,
             pool,
+            queue: Arc::new(Mutex::new(queue)),
             sources: Default::default(),
         }
     }
 
+    /// Returns the maximum number of threads importing assets at the same time, which is the
+    /// number of threads of the pool by default.
+    pub fn num_workers(&self) -> usize {
+        self.queue.lock().num_workers
+    }
+
+    /// Sets the maximum number of threads importing assets at the same time, at least one.
+    ///
+    /// Using less threads than the pool has keeps some of them free for the systems while
+    /// assets are streamed in the background.
+    pub fn set_num_workers(&mut self, num_workers: usize) {
+        self.queue.lock().num_workers = num_workers.max(1);
+        self.spawn_workers();
+    }
+
     /// Add a source to the `Loader`, given an id and the source.
     pub fn add_source<I, S>(&mut self, id: I, source: S)
     where
@@ -72,6 +105,37 @@ impl Loader {
         self.load_from::<A, F, _, _, _>(name, format, options, "", progress, storage)
     }
 
+    /// Loads an asset like `load`, imported before the waiting loads with a lower priority.
+    ///
+    /// Loads have the priority `0` by default, so critical assets can be given a positive
+    /// priority and assets streamed in the background a negative one. Loads with the same
+    /// priority are imported in the order they were requested.
+    pub fn load_with_priority<A, F, N, P>(
+        &self,
+        name: N,
+        format: F,
+        options: F::Options,
+        priority: i32,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        F: Format<A>,
+        N: Into<String>,
+        P: Progress,
+    {
+        self.load_from_with_priority::<A, F, _, _, _>(
+            name,
+            format,
+            options,
+            "",
+            priority,
+            progress,
+            storage,
+        )
+    }
+
     /// Loads an asset with a given id and format from a custom source.
     /// The actual work is done in a worker thread, thus this method immediately returns a handle.
     ///
@@ -93,12 +157,139 @@ impl Loader {
         format: F,
         options: F::Options,
         source: &S,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        F: Format<A> + 'static,
+        N: Into<String>,
+        P: Progress,
+        S: AsRef<str> + Eq + Hash + ?Sized,
+        String: Borrow<S>,
+    {
+        self.load_from_with_priority::<A, F, _, _, _>(
+            name,
+            format,
+            options,
+            source,
+            0,
+            progress,
+            storage,
+        )
+    }
+
+    /// Loads an asset from a custom source like `load_from`, with a priority like
+    /// `load_with_priority`.
+    pub fn load_from_with_priority<A, F, N, P, S>(
+        &self,
+        name: N,
+        format: F,
+        options: F::Options,
+        source: &S,
+        priority: i32,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        F: Format<A> + 'static,
+        N: Into<String>,
+        P: Progress,
+        S: AsRef<str> + Eq + Hash + ?Sized,
+        String: Borrow<S>,
+    {
+        self.load_inner::<A, F, _, _, _, _>(
+            name,
+            format,
+            move |_: &str, _: &Source| Ok(options),
+            source,
+            priority,
+            progress,
+            storage,
+        )
+    }
+
+    /// Loads an asset like `load`, merging the sidecar metadata file of the asset into the
+    /// options if there is one.
+    ///
+    /// The metadata is read from the RON file next to the asset, with the same name followed by
+    /// `.meta.ron`, like `texture.png.meta.ron`, and contains the overrides of the options, like
+    /// the sRGB flag or the sampler of a texture. Assets without a metadata file are loaded with
+    /// the given options. Note that the metadata file isn't watched for hot reloading.
+    pub fn load_with_metadata<A, F, N, P>(
+        &self,
+        name: N,
+        format: F,
+        options: F::Options,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        F: Format<A>,
+        F::Options: MetadataOptions,
+        N: Into<String>,
+        P: Progress,
+    {
+        self.load_from_with_metadata::<A, F, _, _, _>(
+            name,
+            format,
+            options,
+            "",
+            0,
+            progress,
+            storage,
+        )
+    }
+
+    /// Loads an asset from a custom source with a priority like `load_from_with_priority`, with
+    /// its metadata file like `load_with_metadata`.
+    pub fn load_from_with_metadata<A, F, N, P, S>(
+        &self,
+        name: N,
+        format: F,
+        options: F::Options,
+        source: &S,
+        priority: i32,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        F: Format<A> + 'static,
+        F::Options: MetadataOptions,
+        N: Into<String>,
+        P: Progress,
+        S: AsRef<str> + Eq + Hash + ?Sized,
+        String: Borrow<S>,
+    {
+        self.load_inner::<A, F, _, _, _, _>(
+            name,
+            format,
+            move |name: &str, source: &Source| merge_metadata(name, source, options),
+            source,
+            priority,
+            progress,
+            storage,
+        )
+    }
+
+    /// Loads an asset, the options being created by `options` on the worker importing it.
+    fn load_inner<A, F, O, N, P, S>(
+        &self,
+        name: N,
+        format: F,
+        options: O,
+        source: &S,
+        priority: i32,
         mut progress: P,
         storage: &AssetStorage<A>,
     ) -> Handle<A>
     where
         A: Asset,
         F: Format<A> + 'static,
+        O: FnOnce(&str, &Source) -> Result<F::Options> + Send + 'static,
         N: Into<String>,
         P: Progress,
         S: AsRef<str> + Eq + Hash + ?Sized,
@@ -116,6 +307,13 @@ impl Loader {
         };
 
         let handle = storage.allocate();
+        storage.set_id(
+            &handle,
+            AssetId::Path {
+                source: source.to_string(),
+                name: name.clone(),
+            },
+        );
 
         debug!(
             "{:?}: Loading asset {:?} with format {:?} from source {:?} (handle id: {:?})",
@@ -140,8 +338,9 @@ impl Loader {
         let hot_reload = self.hot_reload;
 
         let cl = move || {
-            let data = format
-                .import(name.clone(), source, options, hot_reload)
+            let options = options(&name, &*source);
+            let data = options
+                .and_then(|options| format.import(name.clone(), source, options, hot_reload))
                 .chain_err(|| ErrorKind::Format(F::NAME));
             let tracker = Box::new(tracker) as Box<Tracker>;
 
@@ -152,11 +351,59 @@ impl Loader {
                 tracker,
             });
         };
-        self.pool.spawn(cl);
+        {
+            let mut queue = self.queue.lock();
+            let order = queue.next_order;
+            queue.next_order += 1;
+            queue.jobs.push(Job {
+                priority,
+                order: Reverse(order),
+                task: Box::new(cl),
+            });
+        }
+        self.spawn_workers();
 
         handle_clone
     }
 
+    /// Returns a handle to the asset with the given stable identifier, loading it with the given
+    /// format if it isn't loaded or loading yet.
+    ///
+    /// Returns `None` for a custom identifier which isn't given to any asset of the storage, as
+    /// the asset can't be loaded from it.
+    pub fn load_id<A, F, P>(
+        &self,
+        id: &AssetId,
+        format: F,
+        options: F::Options,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Option<Handle<A>>
+    where
+        A: Asset,
+        F: Format<A>,
+        P: Progress,
+    {
+        if let Some(handle) = storage.handle(id) {
+            return Some(handle);
+        }
+
+        match *id {
+            AssetId::Path {
+                ref source,
+                ref name,
+            } => Some(self.load_from::<A, F, _, _, _>(
+                name.as_str(),
+                format,
+                options,
+                source.as_str(),
+                progress,
+                storage,
+            )),
+            AssetId::Custom(_) => None,
+        }
+    }
+
     /// Load an asset from data and return a handle.
     pub fn load_from_data<A, P>(
         &self,
@@ -182,6 +429,25 @@ impl Loader {
         handle
     }
 
+    /// Spawns workers on the pool for the waiting loads, as long as there are less than
+    /// `num_workers` of them running.
+    fn spawn_workers(&self) {
+        let mut queue = self.queue.lock();
+        while queue.num_running < queue.num_workers && queue.num_running < queue.jobs.len() {
+            queue.num_running += 1;
+
+            let mut worker = Worker {
+                queue: self.queue.clone(),
+                running: true,
+            };
+            self.pool.spawn(move || {
+                while let Some(job) = worker.next_job() {
+                    job.task.run();
+                }
+            });
+        }
+    }
+
     fn source(&self, source: &str) -> Arc<Source> {
         self.sources
             .get(source)
@@ -189,3 +455,153 @@ impl Loader {
             .clone()
     }
 }
+
+/// The loads waiting for a worker, shared by the `Loader` and its workers.
+struct LoadQueue {
+    jobs: BinaryHeap<Job>,
+    next_order: u64,
+    num_running: usize,
+    num_workers: usize,
+}
+
+/// A worker importing the waiting loads until there are none left.
+///
+/// The worker stops while the queue is locked, so a load requested in the meantime either is
+/// taken by it or spawns another worker. It also stops when dropped, so a panicking load doesn't
+/// use up one of the workers for good.
+struct Worker {
+    queue: Arc<Mutex<LoadQueue>>,
+    running: bool,
+}
+
+impl Worker {
+    fn next_job(&mut self) -> Option<Job> {
+        let mut queue = self.queue.lock();
+        let job = queue.jobs.pop();
+        if job.is_none() {
+            queue.num_running -= 1;
+            self.running = false;
+        }
+
+        job
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        if self.running {
+            self.queue.lock().num_running -= 1;
+        }
+    }
+}
+
+/// A load waiting for a worker, ordered by priority then by the order it was requested in.
+struct Job {
+    priority: i32,
+    order: Reverse<u64>,
+    task: Box<Task>,
+}
+
+impl PartialEq for Job {
+    fn eq(&self, other: &Job) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Job {}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Job) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Job {
+    fn cmp(&self, other: &Job) -> Ordering {
+        (self.priority, self.order).cmp(&(other.priority, other.order))
+    }
+}
+
+/// A boxed closure which can be called once.
+trait Task: Send {
+    fn run(self: Box<Self>);
+}
+
+impl<F> Task for F
+where
+    F: FnOnce() + Send,
+{
+    fn run(self: Box<Self>) {
+        (*self)()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(priority: i32, order: u64) -> Job {
+        Job {
+            priority,
+            order: Reverse(order),
+            task: Box::new(|| {}),
+        }
+    }
+
+    fn pop_all(mut jobs: BinaryHeap<Job>) -> Vec<(i32, u64)> {
+        let mut popped = Vec::new();
+        while let Some(job) = jobs.pop() {
+            popped.push((job.priority, job.order.0));
+        }
+
+        popped
+    }
+
+    #[test]
+    fn job_priority_first() {
+        let mut jobs = BinaryHeap::new();
+        jobs.push(job(0, 0));
+        jobs.push(job(-1, 1));
+        jobs.push(job(5, 2));
+        jobs.push(job(1, 3));
+
+        assert_eq!(pop_all(jobs), vec![(5, 2), (1, 3), (0, 0), (-1, 1)]);
+    }
+
+    #[test]
+    fn job_fifo_within_priority() {
+        let mut jobs = BinaryHeap::new();
+        jobs.push(job(0, 0));
+        jobs.push(job(1, 1));
+        jobs.push(job(0, 2));
+        jobs.push(job(1, 3));
+        jobs.push(job(0, 4));
+
+        assert_eq!(pop_all(jobs), vec![(1, 1), (1, 3), (0, 0), (0, 2), (0, 4)]);
+    }
+
+    #[test]
+    fn dropped_worker_stops_running() {
+        let queue = Arc::new(Mutex::new(LoadQueue {
+            jobs: BinaryHeap::new(),
+            next_order: 0,
+            num_running: 2,
+            num_workers: 2,
+        }));
+
+        // A worker dropped while running a job, like when the job panics
+        drop(Worker {
+            queue: queue.clone(),
+            running: true,
+        });
+        assert_eq!(queue.lock().num_running, 1);
+
+        let mut worker = Worker {
+            queue: queue.clone(),
+            running: true,
+        };
+        assert!(worker.next_job().is_none());
+        drop(worker);
+        assert_eq!(queue.lock().num_running, 0);
+    }
+}
@@ -0,0 +1,367 @@
+//! Prefabs, trees of entities and their components described by assets.
+
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use amethyst_core::{GlobalTransform, Parent, Time, Transform};
+use rayon::ThreadPool;
+use specs::{Entities, Entity, Fetch, FetchMut, Join, System, SystemData, VecStorage,
+            WriteStorage};
+
+use {Asset, AssetStorage, Format, Handle, HotReloadStrategy, Loader, Progress, ProgressCounter,
+     Result};
+
+/// Data of a prefab entity, which adds components to the entity when the prefab is
+/// instantiated.
+///
+/// Implemented for tuples and `Option`s of prefab data, so the data of a prefab can be made of the
+/// data of different crates, like `(Option<Transform>, Option<Light>)`.
+pub trait PrefabData<'a> {
+    /// The system data needed to load the sub assets and to add the components.
+    type SystemData: SystemData<'a>;
+
+    /// Loads the assets the data refers to, like meshes or textures, keeping their handles in the
+    /// data.
+    ///
+    /// This is called once, when the prefab is processed, so that all the instances of the prefab
+    /// share the same assets. The prefab is only available once they are loaded, and fails to
+    /// load if one of them fails. Does nothing by default.
+    fn load_sub_assets(
+        &mut self,
+        _progress: &mut ProgressCounter,
+        _system_data: &mut Self::SystemData,
+    ) {
+    }
+
+    /// Adds the components to the entity.
+    ///
+    /// `entities` are all the entities of the prefab, in the same order as the entities of the
+    /// `Prefab`, for data which refers to other entities of the prefab.
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<()>;
+}
+
+/// An entity of a `Prefab`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PrefabEntity<T> {
+    /// Index of the parent entity in the prefab, which has to come before this entity.
+    #[serde(default)]
+    pub parent: Option<usize>,
+    /// Data adding the components of the entity.
+    #[serde(default)]
+    pub data: Option<T>,
+}
+
+impl<T> PrefabEntity<T> {
+    /// Creates a new prefab entity.
+    pub fn new(parent: Option<usize>, data: Option<T>) -> Self {
+        PrefabEntity { parent, data }
+    }
+}
+
+/// A tree of entities along with their components, as an asset.
+///
+/// The first entity is the root of the tree: when the prefab is instantiated by the
+/// `PrefabLoaderSystem`, its data is added to the entity the `Handle<Prefab<T>>` is attached to,
+/// and the other entities are created as its descendants, linked with `Parent` components.
+///
+/// Prefabs can be loaded from RON files with the `RonFormat`, for example:
+///
+/// ```ron
+/// (
+///     entities: [
+///         (data: Some(...)),
+///         (parent: Some(0), data: Some(...)),
+///     ],
+/// )
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Prefab<T> {
+    /// The entities, starting with the root.
+    pub entities: Vec<PrefabEntity<T>>,
+}
+
+impl<T> Prefab<T> {
+    /// Creates a new prefab containing only the root entity, with the given data.
+    pub fn new(root: Option<T>) -> Self {
+        Prefab {
+            entities: vec![PrefabEntity::new(None, root)],
+        }
+    }
+
+    /// Adds an entity to the prefab and returns its index.
+    pub fn add(&mut self, parent: Option<usize>, data: Option<T>) -> usize {
+        self.entities.push(PrefabEntity::new(parent, data));
+        self.entities.len() - 1
+    }
+}
+
+impl<T> Asset for Prefab<T>
+where
+    T: Send + Sync + 'static,
+{
+    const NAME: &'static str = "PREFAB";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl<T> Into<Result<Prefab<T>>> for Prefab<T> {
+    fn into(self) -> Result<Prefab<T>> {
+        if self.entities.is_empty() {
+            bail!("Prefab has no entities");
+        }
+        for (index, entity) in self.entities.iter().enumerate() {
+            match entity.parent {
+                Some(parent) if parent >= index => bail!(
+                    "Entity {} of the prefab has the parent {}, which doesn't come before it",
+                    index,
+                    parent
+                ),
+                _ => {}
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Loads prefabs, a shortcut for `Loader::load` with the `AssetStorage` of the prefabs.
+pub struct PrefabLoader<'a, T: 'a> {
+    loader: &'a Loader,
+    storage: &'a AssetStorage<Prefab<T>>,
+}
+
+impl<'a, T> PrefabLoader<'a, T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Creates a new prefab loader.
+    pub fn new(loader: &'a Loader, storage: &'a AssetStorage<Prefab<T>>) -> Self {
+        PrefabLoader { loader, storage }
+    }
+
+    /// Loads a prefab from the default source, see `Loader::load`.
+    pub fn load<F, N, P>(
+        &self,
+        name: N,
+        format: F,
+        options: F::Options,
+        progress: P,
+    ) -> Handle<Prefab<T>>
+    where
+        F: Format<Prefab<T>>,
+        N: Into<String>,
+        P: Progress,
+    {
+        self.loader.load(name, format, options, progress, self.storage)
+    }
+
+    /// Loads a prefab built in code.
+    pub fn load_from_data<P>(&self, prefab: Prefab<T>, progress: P) -> Handle<Prefab<T>>
+    where
+        P: Progress,
+    {
+        self.loader.load_from_data(prefab, progress, self.storage)
+    }
+}
+
+/// Instantiates the prefabs attached to entities, and processes the `AssetStorage` of the prefabs.
+///
+/// Processing a prefab loads its sub assets, see `PrefabData::load_sub_assets`. Once the prefab of
+/// a `Handle<Prefab<T>>` is loaded, the data of its root is added to the entity
+/// the handle is attached to, the other entities of the prefab are created, and the handle is
+/// removed from the entity. Attaching the handle to another entity creates another instance of
+/// the prefab.
+///
+/// Note that the `AssetStorage<Prefab<T>>` resource has to be added, and the
+/// `Handle<Prefab<T>>` component registered.
+///
+/// ### Type parameters:
+///
+/// - `T`: the data of the prefab entities
+pub struct PrefabLoaderSystem<T> {
+    entities: Vec<Entity>,
+    m: PhantomData<T>,
+}
+
+impl<T> PrefabLoaderSystem<T> {
+    /// Creates a new prefab loader system.
+    pub fn new() -> Self {
+        PrefabLoaderSystem {
+            entities: Vec::default(),
+            m: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> System<'a> for PrefabLoaderSystem<T>
+where
+    T: PrefabData<'a> + Send + Sync + 'static,
+{
+    type SystemData = (
+        Entities<'a>,
+        FetchMut<'a, AssetStorage<Prefab<T>>>,
+        WriteStorage<'a, Handle<Prefab<T>>>,
+        Fetch<'a, Time>,
+        Fetch<'a, Arc<ThreadPool>>,
+        Option<Fetch<'a, HotReloadStrategy>>,
+        WriteStorage<'a, Parent>,
+        T::SystemData,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut prefab_storage,
+            mut prefab_handles,
+            time,
+            pool,
+            strategy,
+            mut parents,
+            mut prefab_system_data,
+        ) = data;
+
+        let strategy = strategy.as_ref().map(Deref::deref);
+        prefab_storage.process_with_dependencies(
+            |prefab, progress| {
+                for entity in &mut prefab.entities {
+                    if let Some(ref mut data) = entity.data {
+                        data.load_sub_assets(progress, &mut prefab_system_data);
+                    }
+                }
+            },
+            Into::into,
+            time.frame_number(),
+            &**pool,
+            strategy,
+        );
+
+        let mut instantiated = Vec::default();
+        for (root, handle) in (&*entities, &prefab_handles).join() {
+            let prefab = match prefab_storage.get(handle) {
+                Some(prefab) => prefab,
+                None => continue,
+            };
+            instantiated.push(root);
+
+            self.entities.clear();
+            self.entities.push(root);
+            for _ in 1..prefab.entities.len() {
+                self.entities.push(entities.create());
+            }
+            for (index, prefab_entity) in prefab.entities.iter().enumerate() {
+                let entity = self.entities[index];
+                if let Some(parent) = prefab_entity.parent {
+                    parents.insert(
+                        entity,
+                        Parent {
+                            entity: self.entities[parent],
+                        },
+                    );
+                }
+                let result = match prefab_entity.data {
+                    Some(ref data) => {
+                        data.add_to_entity(entity, &mut prefab_system_data, &self.entities)
+                    }
+                    None => Ok(()),
+                };
+                if let Err(e) = result {
+                    error!("Failed to add entity {} of prefab {:?}: {}", index, handle, e);
+                }
+            }
+        }
+
+        for root in instantiated {
+            prefab_handles.remove(root);
+        }
+    }
+}
+
+impl<'a, T> PrefabData<'a> for Option<T>
+where
+    T: PrefabData<'a>,
+{
+    type SystemData = T::SystemData;
+
+    fn load_sub_assets(
+        &mut self,
+        progress: &mut ProgressCounter,
+        system_data: &mut Self::SystemData,
+    ) {
+        if let Some(ref mut data) = *self {
+            data.load_sub_assets(progress, system_data);
+        }
+    }
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<()> {
+        match *self {
+            Some(ref data) => data.add_to_entity(entity, system_data, entities),
+            None => Ok(()),
+        }
+    }
+}
+
+macro_rules! impl_data {
+    ( $($ty:ident : $i:tt),* ) => {
+        impl<'a, $($ty),*> PrefabData<'a> for ( $($ty,)* )
+        where
+            $( $ty: PrefabData<'a> ),*
+        {
+            type SystemData = ( $( <$ty as PrefabData<'a>>::SystemData, )* );
+
+            fn load_sub_assets(
+                &mut self,
+                progress: &mut ProgressCounter,
+                system_data: &mut Self::SystemData,
+            ) {
+                $( self.$i.load_sub_assets(progress, &mut system_data.$i); )*
+            }
+
+            fn add_to_entity(
+                &self,
+                entity: Entity,
+                system_data: &mut Self::SystemData,
+                entities: &[Entity],
+            ) -> Result<()> {
+                $( self.$i.add_to_entity(entity, &mut system_data.$i, entities)?; )*
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_data!(A: 0);
+impl_data!(A: 0, B: 1);
+impl_data!(A: 0, B: 1, C: 2);
+impl_data!(A: 0, B: 1, C: 2, D: 3);
+impl_data!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_data!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_data!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_data!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+
+impl<'a> PrefabData<'a> for Transform {
+    type SystemData = (
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, GlobalTransform>,
+    );
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+        _: &[Entity],
+    ) -> Result<()> {
+        system_data.0.insert(entity, self.clone());
+        system_data.1.insert(entity, GlobalTransform::default());
+        Ok(())
+    }
+}
@@ -80,9 +80,22 @@ impl ProgressCounter {
         self.num_loading.load(Ordering::Relaxed)
     }
 
-    /// Returns the number of assets this struct is tracking.
+    /// Returns the number of assets that have been imported successfully.
     pub fn num_finished(&self) -> usize {
-        self.num_assets - self.num_loading()
+        // Both counters may be updated by the loader in between
+        (self.num_assets - self.num_loading()).saturating_sub(self.num_failed())
+    }
+
+    /// Returns the share of the tracked assets that are done, successfully or not, between `0.0`
+    /// and `1.0`. This is `1.0` if no assets are tracked.
+    ///
+    /// Can be used to display the progress on a loading screen.
+    pub fn fraction_done(&self) -> f32 {
+        if self.num_assets == 0 {
+            return 1.0;
+        }
+
+        (self.num_assets - self.num_loading()) as f32 / self.num_assets as f32
     }
 
     /// Returns `Completion::Complete` if all tracked assets are finished.
@@ -140,6 +153,7 @@ impl Tracker for ProgressCounterTracker {
     fn fail(self: Box<Self>, e: Error) {
         self.errors.lock().push(e);
         self.num_failed.fetch_add(1, Ordering::Relaxed);
+        self.num_loading.fetch_sub(1, Ordering::Relaxed);
     }
 }
 
@@ -161,3 +175,81 @@ impl Tracker for () {
         error!("note: to handle the error, use a `Progress` other than `()`");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(progress: &mut ProgressCounter) -> Box<ProgressCounterTracker> {
+        let mut progress = progress;
+        progress.add_assets(1);
+        Box::new(progress.create_tracker())
+    }
+
+    #[test]
+    fn no_assets() {
+        let progress = ProgressCounter::new();
+        assert_eq!(progress.fraction_done(), 1.0);
+        assert_eq!(progress.num_finished(), 0);
+        assert_eq!(progress.complete(), Completion::Complete);
+    }
+
+    #[test]
+    fn success() {
+        let mut progress = ProgressCounter::new();
+        let a = tracker(&mut progress);
+        let b = tracker(&mut progress);
+        assert_eq!(progress.complete(), Completion::Loading);
+        assert_eq!(progress.fraction_done(), 0.0);
+
+        a.success();
+        assert_eq!(progress.num_loading(), 1);
+        assert_eq!(progress.num_finished(), 1);
+        assert_eq!(progress.fraction_done(), 0.5);
+
+        b.success();
+        assert_eq!(progress.num_finished(), 2);
+        assert_eq!(progress.fraction_done(), 1.0);
+        assert!(progress.is_complete());
+    }
+
+    #[test]
+    fn failure() {
+        let mut progress = ProgressCounter::new();
+        let a = tracker(&mut progress);
+        a.fail("failed".into());
+
+        assert_eq!(progress.num_loading(), 0);
+        assert_eq!(progress.num_failed(), 1);
+        assert_eq!(progress.num_finished(), 0);
+        assert_eq!(progress.fraction_done(), 1.0);
+        assert_eq!(progress.complete(), Completion::Failed);
+        assert_eq!(progress.errors().len(), 1);
+        assert!(progress.errors().is_empty());
+    }
+
+    #[test]
+    fn mixed() {
+        let mut progress = ProgressCounter::new();
+        let a = tracker(&mut progress);
+        let b = tracker(&mut progress);
+        let c = tracker(&mut progress);
+        let d = tracker(&mut progress);
+
+        a.success();
+        b.fail("failed".into());
+        assert_eq!(progress.num_assets(), 4);
+        assert_eq!(progress.num_loading(), 2);
+        assert_eq!(progress.num_failed(), 1);
+        assert_eq!(progress.num_finished(), 1);
+        assert_eq!(progress.fraction_done(), 0.5);
+        assert_eq!(progress.complete(), Completion::Failed);
+
+        c.success();
+        d.success();
+        assert_eq!(progress.num_loading(), 0);
+        assert_eq!(progress.num_finished(), 3);
+        assert_eq!(progress.fraction_done(), 1.0);
+        assert!(!progress.is_complete());
+    }
+}
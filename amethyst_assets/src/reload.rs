@@ -1,13 +1,17 @@
 //! Defines the `Reload` trait.
 
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Instant;
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
 
 use amethyst_core as core;
 use amethyst_core::{ECSBundle, Time};
+use notify::{watcher, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
 use specs::{DispatcherBuilder, Fetch, FetchMut, System, World};
 
-use {Asset, Format, FormatValue, Loader, Result, Source};
+use {Asset, Format, FormatValue, Loader, Result, ResultExt, Source};
 
 /// This bundle activates hot reload for the `Loader`,
 /// adds a `HotReloadStrategy` and the `HotReloadSystem`.
@@ -83,6 +87,35 @@ impl HotReloadStrategy {
         }
     }
 
+    /// Reloads the changed assets as soon as files change in the given directory or its
+    /// subdirectories, usually the directory of the `Loader`.
+    ///
+    /// Instead of checking the modification time of all assets periodically, this watches the
+    /// directory for changes and only checks the assets once a file was written. The changes are
+    /// debounced, so that a file saved in several steps is reloaded once.
+    pub fn when_changed<P>(directory: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        use std::u64::MAX;
+
+        let directory = directory.as_ref();
+        let (sender, events) = channel();
+        let mut watcher = watcher(sender, Duration::from_millis(100))
+            .chain_err(|| "Failed to create file watcher")?;
+        watcher
+            .watch(directory, RecursiveMode::Recursive)
+            .chain_err(|| format!("Failed to watch directory {:?}", directory))?;
+
+        Ok(HotReloadStrategy {
+            inner: HotReloadStrategyInner::Watch {
+                watcher: Mutex::new(watcher),
+                events: Mutex::new(events),
+                frame_number: MAX,
+            },
+        })
+    }
+
     /// Never do any hot-reloading.
     pub fn never() -> Self {
         HotReloadStrategy {
@@ -108,6 +141,7 @@ impl HotReloadStrategy {
         match self.inner {
             HotReloadStrategyInner::Every { frame_number, .. } => frame_number == current_frame,
             HotReloadStrategyInner::Trigger { frame_number, .. } => frame_number == current_frame,
+            HotReloadStrategyInner::Watch { frame_number, .. } => frame_number == current_frame,
             HotReloadStrategyInner::Never => false,
         }
     }
@@ -129,6 +163,13 @@ enum HotReloadStrategyInner {
         triggered: bool,
         frame_number: u64,
     },
+    Watch {
+        /// Only kept alive, the directory is watched until it's dropped.
+        #[allow(dead_code)]
+        watcher: Mutex<RecommendedWatcher>,
+        events: Mutex<Receiver<DebouncedEvent>>,
+        frame_number: u64,
+    },
     Never,
 }
 
@@ -157,6 +198,27 @@ impl<'a> System<'a> for HotReloadSystem {
                 *frame_number = time.frame_number() + 1;
                 *last = Instant::now();
             },
+            HotReloadStrategyInner::Watch {
+                ref events,
+                ref mut frame_number,
+                ..
+            } => {
+                let mut changed = false;
+                for event in events.lock().try_iter() {
+                    match event {
+                        DebouncedEvent::Create(_)
+                        | DebouncedEvent::Write(_)
+                        | DebouncedEvent::Rename(..) => changed = true,
+                        DebouncedEvent::Error(e, path) => {
+                            warn!("Failed to watch assets for changes ({:?}): {}", path, e)
+                        }
+                        _ => {}
+                    }
+                }
+                if changed {
+                    *frame_number = time.frame_number() + 1;
+                }
+            }
             HotReloadStrategyInner::Never => {}
         }
     }
@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use fnv::FnvHashMap;
+use parking_lot::Mutex;
+use zip::ZipArchive as Zip;
+
+use {ErrorKind, Result, ResultExt};
+use source::Source;
+
+/// Zip archive source.
+///
+/// Reads the assets out of a single zip file, so a game can ship one data file
+/// instead of a directory of loose files. The entries of the archive are indexed
+/// by their path when it is opened, and are named like files of a `Directory`,
+/// relative to the root of the archive.
+///
+/// Add it to the `Loader` with `Loader::add_source` and load assets with
+/// `Loader::load_from`. All the entries have the modification time of the archive.
+pub struct ZipArchive {
+    loc: PathBuf,
+    archive: Mutex<Zip<File>>,
+    index: FnvHashMap<String, usize>,
+    modified: u64,
+}
+
+impl ZipArchive {
+    /// Opens the zip archive at the given location and indexes its entries.
+    pub fn open<P>(loc: P) -> Result<Self>
+    where
+        P: Into<PathBuf>,
+    {
+        let loc = loc.into();
+
+        let file = File::open(&loc)
+            .chain_err(|| format!("Failed to open archive {:?}", loc))
+            .chain_err(|| ErrorKind::Source)?;
+        let modified = file.metadata()
+            .chain_err(|| format!("Failed to fetch metadata for {:?}", loc))?
+            .modified()
+            .chain_err(|| "Could not get modification time")?
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut archive = Zip::new(file)
+            .chain_err(|| format!("Failed to read archive {:?}", loc))
+            .chain_err(|| ErrorKind::Source)?;
+
+        let mut index = FnvHashMap::default();
+        for i in 0..archive.len() {
+            let entry = archive
+                .by_index(i)
+                .chain_err(|| format!("Failed to read entry {} of archive {:?}", i, loc))
+                .chain_err(|| ErrorKind::Source)?;
+            // Directories are stored as entries ending with a separator
+            if !entry.name().ends_with('/') {
+                index.insert(entry.name().to_string(), i);
+            }
+        }
+
+        Ok(ZipArchive {
+            loc,
+            archive: Mutex::new(archive),
+            index,
+            modified,
+        })
+    }
+
+    fn entry(&self, path: &str) -> Result<usize> {
+        match self.index.get(path) {
+            Some(&i) => Ok(i),
+            None => Err(ErrorKind::NotFound(path.to_string()).into()),
+        }
+    }
+}
+
+impl Source for ZipArchive {
+    fn modified(&self, path: &str) -> Result<u64> {
+        self.entry(path)?;
+
+        Ok(self.modified)
+    }
+
+    fn load(&self, path: &str) -> Result<Vec<u8>> {
+        let i = self.entry(path)?;
+
+        let mut archive = self.archive.lock();
+        let mut entry = archive
+            .by_index(i)
+            .chain_err(|| format!("Failed to open entry {:?} of archive {:?}", path, self.loc))
+            .chain_err(|| ErrorKind::Source)?;
+        let mut v = Vec::with_capacity(entry.size() as usize);
+        entry
+            .read_to_end(&mut v)
+            .chain_err(|| format!("Failed to read entry {:?} of archive {:?}", path, self.loc))
+            .chain_err(|| ErrorKind::Source)?;
+
+        Ok(v)
+    }
+}
@@ -1,4 +1,5 @@
 use std::fs::File;
+use std::io::ErrorKind as IoErrorKind;
 use std::path::PathBuf;
 use std::time::UNIX_EPOCH;
 
@@ -54,9 +55,17 @@ impl Source for Directory {
         let path = self.path(path);
 
         let mut v = Vec::new();
-        let mut file = File::open(&path)
-            .chain_err(|| format!("Failed to open file {:?}", path))
-            .chain_err(|| ErrorKind::Source)?;
+        let mut file = match File::open(&path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == IoErrorKind::NotFound => {
+                return Err(ErrorKind::NotFound(path.to_string_lossy().into_owned()).into());
+            }
+            Err(e) => {
+                return Err(e)
+                    .chain_err(|| format!("Failed to open file {:?}", path))
+                    .chain_err(|| ErrorKind::Source);
+            }
+        };
         file.read_to_end(&mut v)
             .chain_err(|| format!("Failed to read file {:?}", path))
             .chain_err(|| ErrorKind::Source)?;
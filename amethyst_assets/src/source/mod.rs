@@ -1,7 +1,9 @@
+pub use self::archive::ZipArchive;
 pub use self::dir::Directory;
 
 use Result;
 
+mod archive;
 mod dir;
 
 /// A trait for asset sources, which provides
@@ -14,7 +16,9 @@ pub trait Source: Send + Sync + 'static {
 
     /// Loads the bytes given a path.
     ///
-    /// The id should always use `/` as separator in paths.
+    /// The id should always use `/` as separator in paths. Returns `ErrorKind::NotFound` if
+    /// there is nothing at that path, so optional files like metadata can be told apart from
+    /// files failing to load.
     fn load(&self, path: &str) -> Result<Vec<u8>>;
 
     /// Returns both the result of `load` and `modified` as a tuple.
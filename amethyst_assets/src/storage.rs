@@ -4,13 +4,16 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 
 use amethyst_core::Time;
 use crossbeam::sync::MsQueue;
+use fnv::FnvHashMap;
 use hibitset::BitSet;
+use parking_lot::Mutex;
 use rayon::ThreadPool;
 use specs::{Component, Fetch, FetchMut, System, UnprotectedStorage, VecStorage};
 
 use asset::{Asset, FormatValue};
 use error::{ErrorKind, Result, ResultExt};
-use progress::Tracker;
+use id::AssetId;
+use progress::{Completion, ProgressCounter, Tracker};
 use reload::{HotReloadStrategy, Reload};
 
 /// An `Allocator`, holding a counter for producing unique IDs.
@@ -28,13 +31,19 @@ impl Allocator {
 
 /// An asset storage, storing the actual assets and allocating
 /// handles to them.
+///
+/// Handles are reference counted, and an asset is freed once all
+/// the handles to it were dropped, see `collect_garbage`.
 pub struct AssetStorage<A: Asset> {
     assets: VecStorage<A>,
     bitset: BitSet,
     handles: Vec<Handle<A>>,
     handle_alloc: Allocator,
+    ids: Mutex<AssetIds<A>>,
     pub(crate) processed: Arc<MsQueue<Processed<A>>>,
+    pending: Mutex<Vec<Pending<A>>>,
     reloads: Vec<(WeakHandle<A>, Box<Reload<A>>)>,
+    unloaded: Vec<Handle<A>>,
     unused_handles: MsQueue<Handle<A>>,
 }
 
@@ -61,6 +70,24 @@ impl<A: Asset> AssetStorage<A> {
         handle
     }
 
+    /// Adds an already created asset to the storage, returning a handle to it.
+    ///
+    /// This is useful for assets which aren't loaded from data, e.g. ones
+    /// created by a system at runtime.
+    pub fn insert(&mut self, asset: A) -> Handle<A> {
+        let h = self.allocate();
+
+        let id = h.id();
+        self.bitset.add(id);
+        self.handles.push(h.clone());
+
+        unsafe {
+            self.assets.insert(id, asset);
+        }
+
+        h
+    }
+
     /// When cloning an asset handle, you'll get another handle,
     /// but pointing to the same asset. If you instead want to
     /// indeed create a new asset, you can use this method.
@@ -105,6 +132,26 @@ impl<A: Asset> AssetStorage<A> {
         }
     }
 
+    /// Returns the stable identifier of an asset, if it was loaded from a source or given one
+    /// with `set_id`.
+    pub fn id(&self, handle: &Handle<A>) -> Option<AssetId> {
+        self.ids.lock().ids.get(&handle.id()).cloned()
+    }
+
+    /// Gives an asset a stable identifier, replacing the one it had.
+    pub fn set_id(&self, handle: &Handle<A>, id: AssetId) {
+        let mut ids = self.ids.lock();
+        ids.remove(handle.id());
+        ids.ids.insert(handle.id(), id.clone());
+        ids.handles.insert(id, handle.downgrade());
+    }
+
+    /// Returns a handle to the asset with the given stable identifier, if it is loaded or still
+    /// loading.
+    pub fn handle(&self, id: &AssetId) -> Option<Handle<A>> {
+        self.ids.lock().handles.get(id).and_then(WeakHandle::upgrade)
+    }
+
     /// Process finished asset data and maintain the storage.
     pub fn process<F>(
         &mut self,
@@ -122,34 +169,118 @@ impl<A: Asset> AssetStorage<A> {
     /// This calls the `drop_fn` closure for assets that were removed from the storage.
     pub fn process_custom_drop<F, D>(
         &mut self,
+        f: F,
+        drop_fn: D,
+        frame_number: u64,
+        pool: &ThreadPool,
+        strategy: Option<&HotReloadStrategy>,
+    ) where
+        D: FnMut(A),
+        F: FnMut(A::Data) -> Result<A>,
+    {
+        self.process_all(|_, _| {}, f, drop_fn, frame_number, pool, strategy);
+    }
+
+    /// Process finished asset data which depends on other assets, and maintain the storage.
+    ///
+    /// `load` is called with the data of each imported asset to load its dependencies, tracked
+    /// by the given `ProgressCounter`. The asset is only created by `f` once all of them are
+    /// loaded, and fails to load if one of them fails.
+    pub fn process_with_dependencies<L, F>(
+        &mut self,
+        load: L,
+        f: F,
+        frame_number: u64,
+        pool: &ThreadPool,
+        strategy: Option<&HotReloadStrategy>,
+    ) where
+        L: FnMut(&mut A::Data, &mut ProgressCounter),
+        F: FnMut(A::Data) -> Result<A>,
+    {
+        self.process_all(load, f, |_| {}, frame_number, pool, strategy);
+    }
+
+    fn process_all<L, F, D>(
+        &mut self,
+        mut load: L,
         mut f: F,
-        mut drop_fn: D,
+        drop_fn: D,
         frame_number: u64,
         pool: &ThreadPool,
         strategy: Option<&HotReloadStrategy>,
     ) where
+        L: FnMut(&mut A::Data, &mut ProgressCounter),
         D: FnMut(A),
         F: FnMut(A::Data) -> Result<A>,
     {
         while let Some(processed) = self.processed.try_pop() {
+            let (mut data, handle, name, origin) = match processed {
+                Processed::NewAsset {
+                    data,
+                    handle,
+                    name,
+                    tracker,
+                } => (data, handle, name, Origin::NewAsset(tracker)),
+                Processed::HotReload {
+                    data,
+                    handle,
+                    name,
+                    old_reload,
+                } => (data, handle, name, Origin::HotReload(old_reload)),
+            };
+
+            let mut dependencies = ProgressCounter::new();
+            if let Ok(ref mut value) = data {
+                load(&mut value.data, &mut dependencies);
+            }
+            self.pending.get_mut().push(Pending {
+                data,
+                dependencies,
+                handle,
+                name,
+                origin,
+            });
+        }
+
+        let mut i = 0;
+        while i < self.pending.get_mut().len() {
+            // The asset is only created once its dependencies are done loading.
+            let pending = {
+                let pending = self.pending.get_mut();
+                if pending[i].dependencies.complete() == Completion::Loading {
+                    i += 1;
+                    continue;
+                }
+
+                pending.swap_remove(i)
+            };
+            let Pending {
+                data,
+                dependencies,
+                handle,
+                name,
+                origin,
+            } = pending;
+
             let assets = &mut self.assets;
             let bitset = &mut self.bitset;
             let handles = &mut self.handles;
             let reloads = &mut self.reloads;
+            let ids = self.ids.get_mut();
 
             let f = &mut f;
-            let (reload_obj, handle) = match processed {
-                Processed::NewAsset {
-                    data,
-                    handle,
-                    name,
-                    tracker,
-                } => {
-                    let (asset, reload_obj) = match data.map(|FormatValue { data, reload }| {
-                        (data, reload)
-                    }).and_then(|(d, rel)| f(d).map(|a| (a, rel)))
-                        .chain_err(|| ErrorKind::Asset(name.clone()))
-                    {
+            let result = data.and_then(|value| match dependencies.complete() {
+                Completion::Failed => match dependencies.errors().into_iter().next() {
+                    Some(e) => Err(e).chain_err(|| ErrorKind::Dependency),
+                    None => Err(ErrorKind::Dependency.into()),
+                },
+                _ => Ok(value),
+            }).and_then(|FormatValue { data, reload }| f(data).map(|a| (a, reload)))
+                .chain_err(|| ErrorKind::Asset(name.clone()));
+
+            let (reload_obj, handle) = match origin {
+                Origin::NewAsset(tracker) => {
+                    let (asset, reload_obj) = match result {
                         Ok(x) => {
                             debug!(
                                 "{:?}: Asset {:?} (handle id: {:?}) has been loaded successfully",
@@ -170,6 +301,7 @@ impl<A: Asset> AssetStorage<A> {
                                 e,
                             );
                             tracker.fail(e);
+                            ids.remove(handle.id());
 
                             continue;
                         }
@@ -187,17 +319,8 @@ impl<A: Asset> AssetStorage<A> {
 
                     (reload_obj, handle)
                 }
-                Processed::HotReload {
-                    data,
-                    handle,
-                    name,
-                    old_reload,
-                } => {
-                    let (asset, reload_obj) = match data.map(|FormatValue { data, reload }| {
-                        (data, reload)
-                    }).and_then(|(d, rel)| f(d).map(|a| (a, rel)))
-                        .chain_err(|| ErrorKind::Asset(name.clone()))
-                    {
+                Origin::HotReload(old_reload) => {
+                    let (asset, reload_obj) = match result {
                         Ok(x) => x,
                         Err(e) => {
                             error!(
@@ -216,11 +339,16 @@ impl<A: Asset> AssetStorage<A> {
                     };
 
                     let id = handle.id();
-                    assert!(
-                        bitset.contains(id),
-                        "Expected handle {:?} to be valid, but the asset storage says otherwise",
-                        handle,
-                    );
+                    if !bitset.contains(id) {
+                        debug!(
+                            "{:?}: Asset {:?} (handle id: {:?}) was unloaded while reloading it",
+                            A::NAME,
+                            name,
+                            handle,
+                        );
+
+                        continue;
+                    }
                     unsafe {
                         let old = assets.get_mut(id);
                         *old = asset;
@@ -236,36 +364,93 @@ impl<A: Asset> AssetStorage<A> {
             }
         }
 
+        self.collect_garbage(drop_fn);
+
+        if strategy
+            .map(|s| s.needs_reload(frame_number))
+            .unwrap_or(false)
+        {
+            trace!("Testing for asset reloads..");
+            self.hot_reload(pool);
+        }
+    }
+
+    /// Frees the assets whose handles were all dropped, calling `drop_fn` with each of them, and
+    /// returns how many were freed.
+    ///
+    /// This is done by `process` every frame, but can be called right away to free memory, for
+    /// example after a level was unloaded.
+    pub fn collect_garbage<D>(&mut self, mut drop_fn: D) -> usize
+    where
+        D: FnMut(A),
+    {
         let mut count = 0;
-        let mut skip = 0;
-        while let Some(i) = self.handles.iter().skip(skip).position(Handle::is_unique) {
+        let mut i = 0;
+        while i < self.handles.len() {
+            if !self.handles[i].is_unique() {
+                i += 1;
+                continue;
+            }
+
             count += 1;
-            skip = i;
             let handle = self.handles.swap_remove(i);
             let id = handle.id();
             unsafe {
                 drop_fn(self.assets.remove(id));
             }
             self.bitset.remove(id);
-
-            // Can't reuse old handle here, because otherwise weak handles would still be valid.
-            // TODO: maybe just store u32?
-            self.unused_handles.push(Handle {
-                id: Arc::new(id),
-                marker: PhantomData,
-            });
+            self.ids.get_mut().remove(id);
+            self.recycle(id);
         }
         if count != 0 {
             debug!("{:?}: Freed {} handle ids", A::NAME, count,);
         }
 
-        if strategy
-            .map(|s| s.needs_reload(frame_number))
-            .unwrap_or(false)
-        {
-            trace!("Testing for asset reloads..");
-            self.hot_reload(pool);
+        // The ids of unloaded assets are only reused once all their handles are gone.
+        let mut i = 0;
+        while i < self.unloaded.len() {
+            if self.unloaded[i].is_unique() {
+                let id = self.unloaded.swap_remove(i).id();
+                self.recycle(id);
+            } else {
+                i += 1;
+            }
+        }
+
+        count
+    }
+
+    /// Removes an asset from the storage and returns it, even if handles to it are still alive,
+    /// for example to free the assets of a level when leaving it.
+    ///
+    /// `get` returns `None` for the remaining handles, and the asset isn't hot-reloaded anymore.
+    /// Assets which are still loading aren't affected.
+    pub fn unload(&mut self, handle: &Handle<A>) -> Option<A> {
+        let id = handle.id();
+        if !self.bitset.remove(id) {
+            return None;
         }
+
+        let i = self.handles
+            .iter()
+            .position(|h| h.id() == id)
+            .expect("Every loaded asset has a handle in the storage");
+        self.unloaded.push(self.handles.swap_remove(i));
+        self.ids.get_mut().remove(id);
+        self.reloads.retain(|&(ref weak, _)| {
+            weak.upgrade().map_or(false, |h| h.id() != id)
+        });
+
+        Some(unsafe { self.assets.remove(id) })
+    }
+
+    fn recycle(&mut self, id: u32) {
+        // Can't reuse old handle here, because otherwise weak handles would still be valid.
+        // TODO: maybe just store u32?
+        self.unused_handles.push(Handle {
+            id: Arc::new(id),
+            marker: PhantomData,
+        });
     }
 
     fn hot_reload(&mut self, pool: &ThreadPool) {
@@ -311,8 +496,11 @@ impl<A: Asset> Default for AssetStorage<A> {
             bitset: Default::default(),
             handles: Default::default(),
             handle_alloc: Default::default(),
+            ids: Default::default(),
             processed: Arc::new(MsQueue::new()),
+            pending: Default::default(),
             reloads: Default::default(),
+            unloaded: Default::default(),
             unused_handles: MsQueue::new(),
         }
     }
@@ -424,6 +612,43 @@ pub(crate) enum Processed<A: Asset> {
     },
 }
 
+/// The stable identifiers of the assets of a storage.
+#[derive(Derivative)]
+#[derivative(Default(bound = ""))]
+struct AssetIds<A> {
+    ids: FnvHashMap<u32, AssetId>,
+    handles: FnvHashMap<AssetId, WeakHandle<A>>,
+}
+
+impl<A> AssetIds<A> {
+    fn remove(&mut self, id: u32) {
+        if let Some(asset_id) = self.ids.remove(&id) {
+            // The identifier may have been given to another asset since
+            let other = self.handles
+                .get(&asset_id)
+                .and_then(WeakHandle::upgrade)
+                .map_or(false, |handle| handle.id() != id);
+            if !other {
+                self.handles.remove(&asset_id);
+            }
+        }
+    }
+}
+
+/// Imported asset data, waiting for its dependencies to be loaded.
+struct Pending<A: Asset> {
+    data: Result<FormatValue<A>>,
+    dependencies: ProgressCounter,
+    handle: Handle<A>,
+    name: String,
+    origin: Origin<A>,
+}
+
+enum Origin<A: Asset> {
+    NewAsset(Box<Tracker>),
+    HotReload(Box<Reload<A>>),
+}
+
 /// A weak handle, which is useful if you don't directly need the asset
 /// like in caches. This way, the asset can still get dropped (if you want that).
 #[derive(Derivative)]
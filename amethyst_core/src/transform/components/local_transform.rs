@@ -9,7 +9,10 @@ use specs::{Component, DenseVecStorage, FlaggedStorage};
 /// Local position, rotation, and scale (from parent if it exists).
 ///
 /// Used for rendering position and orientation.
-#[derive(Clone, Debug, PartialEq)]
+///
+/// The fields missing when deserializing are the ones of the default transform.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
 pub struct Transform {
     /// Quaternion [w (scalar), x, y, z]
     pub rotation: Quaternion<f32>,
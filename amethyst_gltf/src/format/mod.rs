@@ -18,8 +18,8 @@ use gltf;
 use gltf::Gltf;
 use gltf_utils::AccessorIter;
 use itertools::Itertools;
-use renderer::{Color, JointIds, JointWeights, JpgFormat, Normal, PngFormat, Position, Separate,
-               Tangent, TexCoord, TextureMetadata};
+use renderer::{Attribute, Color, JointIds, JointWeights, JpgFormat, Normal, PngFormat,
+               PosNormTangTex, Position, Separate, Tangent, TexCoord, TextureMetadata};
 
 use super::*;
 
@@ -159,7 +159,7 @@ fn load_data(
     debug!("Loading animations");
     let animations = if options.load_animations {
         gltf.animations()
-            .map(|ref animation| load_animation(animation, buffers))
+            .map(|ref animation| load_animation(animation, buffers, options))
             .collect::<Result<Vec<GltfAnimation>, GltfError>>()?
     } else {
         Vec::default()
@@ -181,11 +181,24 @@ fn load_data(
 fn load_animation(
     animation: &gltf::Animation,
     buffers: &Buffers,
+    options: &GltfSceneOptions,
 ) -> Result<GltfAnimation, GltfError> {
-    let samplers = animation
+    let mut samplers = animation
         .channels()
         .map(|ref channel| load_channel(channel, buffers))
         .collect::<Result<Vec<_>, GltfError>>()?;
+    if options.quantize_rotations {
+        for &mut (_, ref channel, ref mut sampler) in &mut samplers {
+            if let TransformChannel::Rotation = *channel {
+                sampler.quantize_rotations();
+            }
+        }
+    }
+    if let Some(tolerance) = options.compress_animations {
+        for &mut (_, _, ref mut sampler) in &mut samplers {
+            sampler.compress(tolerance);
+        }
+    }
     Ok(GltfAnimation {
         samplers,
         handle: None,
@@ -292,17 +305,18 @@ fn load_material(
         material
             .pbr_metallic_roughness()
             .metallic_roughness_texture(),
+        // Same layout as the texture, roughness in green and metallic in blue
         [
-            material.pbr_metallic_roughness().metallic_factor(),
-            material.pbr_metallic_roughness().roughness_factor(),
             1.0,
+            material.pbr_metallic_roughness().roughness_factor(),
+            material.pbr_metallic_roughness().metallic_factor(),
             1.0,
         ],
         buffers,
         source.clone(),
         name,
     ).map(|(texture, factors)| {
-        deconstruct_metallic_roughness(texture, factors[0], factors[1])
+        deconstruct_metallic_roughness(texture, factors[2], factors[1])
     })?;
 
     let double_sided = material.double_sided();
@@ -409,6 +423,7 @@ fn deconstruct_image(data: &TextureData, offset: usize, step: usize) -> TextureD
     }
 }
 
+// The factor is applied to the texture, the returned one is left for the engine to apply
 fn load_texture_with_factor(
     texture: Option<gltf::texture::Info>,
     factor: [f32; 4],
@@ -418,13 +433,30 @@ fn load_texture_with_factor(
 ) -> Result<(TextureData, [f32; 4]), GltfError> {
     match texture {
         Some(info) => Ok((
-            load_texture(&info.texture(), buffers, source, name)?,
-            factor,
+            apply_factor(load_texture(&info.texture(), buffers, source, name)?, factor),
+            [1.0, 1.0, 1.0, 1.0],
         )),
         None => Ok((TextureData::color(factor), [1.0, 1.0, 1.0, 1.0])),
     }
 }
 
+// Multiplies the channels of the texels by the factor
+fn apply_factor(data: TextureData, factor: [f32; 4]) -> TextureData {
+    match data {
+        TextureData::Image(mut image_data, metadata) => {
+            if factor != [1.0, 1.0, 1.0, 1.0] {
+                for texel in image_data.raw.buf.chunks_mut(4) {
+                    for (value, factor) in texel.iter_mut().zip(&factor) {
+                        *value = (*value as f32 * factor).round().min(255.0) as u8;
+                    }
+                }
+            }
+            TextureData::Image(image_data, metadata)
+        }
+        data => data,
+    }
+}
+
 fn load_texture(
     texture: &gltf::Texture,
     buffers: &Buffers,
@@ -586,106 +618,66 @@ fn load_mesh(
 
         let positions = primitive
             .positions(buffers)
-            .map(|positions| match faces {
-                Some(ref faces) => {
-                    let vertices = positions.collect::<Vec<_>>();
-                    faces
-                        .iter()
-                        .map(|i| Separate::<Position>::new(vertices[*i]))
-                        .collect::<Vec<_>>()
-                }
-                None => positions
-                    .map(|pos| Separate::<Position>::new(pos))
-                    .collect(),
-            })
+            .map(|positions| unwind(positions.collect(), &faces))
             .ok_or(GltfError::MissingPositions)?;
         let bounds = primitive.position_bounds().unwrap();
 
         let colors = primitive
             .colors_rgba_f32(0, 1., buffers)
-            .map(|colors| match faces {
-                Some(ref faces) => {
-                    let colors = colors.collect::<Vec<_>>();
-                    faces
-                        .iter()
-                        .map(|i| Separate::<Color>::new(colors[*i]))
-                        .collect()
-                }
-                None => colors.map(|color| Separate::<Color>::new(color)).collect(),
-            });
+            .map(|colors| unwind(colors.collect(), &faces));
 
         let tex_coord = match primitive.tex_coords_f32(0, buffers) {
-            Some(tex_coords) => Some(tex_coords.collect::<Vec<[f32; 2]>>()),
+            Some(tex_coords) => Some(unwind(tex_coords.collect(), &faces)),
             None => match options.generate_tex_coords {
-                Some((u, v)) => Some((0..positions.len()).map(|_| [u, v]).collect()),
+                Some((u, v)) => Some(vec![[u, v]; positions.len()]),
                 None => None,
             },
-        }.map(|texs| match faces {
-            Some(ref faces) => faces
-                .iter()
-                .map(|i| Separate::<TexCoord>::new(flip_check(texs[*i], options.flip_v_coord)))
-                .collect(),
-            None => texs.into_iter()
-                .map(|t| Separate::<TexCoord>::new(flip_check(t, options.flip_v_coord)))
-                .collect(),
+        }.map(|texs| {
+            texs.into_iter()
+                .map(|t| flip_check(t, options.flip_v_coord))
+                .collect::<Vec<_>>()
         });
 
-        let normals = primitive.normals(buffers).map(|normals| match faces {
-            Some(ref faces) => {
-                let normals = normals.collect::<Vec<_>>();
-                faces
-                    .iter()
-                    .map(|i| Separate::<Normal>::new(normals[*i]))
-                    .collect()
-            }
-            None => normals.map(|n| Separate::<Normal>::new(n)).collect(),
-        });
+        let normals = primitive
+            .normals(buffers)
+            .map(|normals| unwind(normals.collect(), &faces));
 
-        let tangents = primitive.tangents(buffers).map(|tangents| match faces {
-            Some(ref faces) => {
-                let tangents = tangents.collect::<Vec<_>>();
-                faces
-                    .iter()
-                    .map(|i| {
-                        Separate::<Tangent>::new([
-                            tangents[*i][0],
-                            tangents[*i][1],
-                            tangents[*i][2],
-                        ])
-                    })
-                    .collect()
-            }
-            None => tangents
-                .map(|t| Separate::<Tangent>::new([t[0], t[1], t[2]]))
-                .collect(),
+        let tangents = primitive.tangents(buffers).map(|tangents| {
+            unwind(tangents.collect(), &faces)
+                .into_iter()
+                .map(|t| [t[0], t[1], t[2]])
+                .collect::<Vec<_>>()
         });
 
-        let joint_ids = primitive.joints_u16(0, buffers).map(|joints| match faces {
-            Some(ref faces) => {
-                let joints = joints.collect::<Vec<_>>();
-                faces
-                    .iter()
-                    .map(|i| Separate::<JointIds>::new(joints[*i]))
-                    .collect()
-            }
-            None => joints.map(|j| Separate::<JointIds>::new(j)).collect(),
-        });
+        let joint_ids = primitive
+            .joints_u16(0, buffers)
+            .map(|joints| unwind(joints.collect(), &faces));
         trace!("Joint ids: {:?}", joint_ids);
 
         let joint_weights = primitive
             .weights_f32(0, buffers)
-            .map(|weights| match faces {
-                Some(ref faces) => {
-                    let weights = weights.collect::<Vec<_>>();
-                    faces
-                        .iter()
-                        .map(|i| Separate::<JointWeights>::new(weights[*i]))
-                        .collect()
-                }
-                None => weights.map(|w| Separate::<JointWeights>::new(w)).collect(),
-            });
+            .map(|weights| unwind(weights.collect(), &faces));
         trace!("Joint weights: {:?}", joint_weights);
 
+        let attributes = if options.interleaved {
+            GltfAttributes::Interleaved(interleave(
+                &positions,
+                tex_coord.as_ref(),
+                normals.as_ref(),
+                tangents.as_ref(),
+            ))
+        } else {
+            GltfAttributes::Separate((
+                separate::<Position>(positions),
+                colors.map(separate::<Color>),
+                tex_coord.map(separate::<TexCoord>),
+                normals.map(separate::<Normal>),
+                tangents.map(separate::<Tangent>),
+                joint_ids.map(separate::<JointIds>),
+                joint_weights.map(separate::<JointWeights>),
+            ))
+        };
+
         let material = primitive.material().index();
 
         match map_mode(primitive.mode()) {
@@ -694,15 +686,7 @@ fn load_mesh(
                 primitive,
                 indices: faces,
                 material,
-                attributes: (
-                    positions,
-                    colors,
-                    tex_coord,
-                    normals,
-                    tangents,
-                    joint_ids,
-                    joint_weights,
-                ),
+                attributes,
                 handle: None,
             }),
             Err(err) => return Err(err),
@@ -711,6 +695,38 @@ fn load_mesh(
     Ok(primitives)
 }
 
+/// Lists the values of the vertices of the faces, or returns the values as
+/// they are if the primitive has no indices.
+fn unwind<T: Copy>(values: Vec<T>, faces: &Option<Vec<usize>>) -> Vec<T> {
+    match *faces {
+        Some(ref faces) => faces.iter().map(|i| values[*i]).collect(),
+        None => values,
+    }
+}
+
+fn separate<A: Attribute>(values: Vec<A::Repr>) -> Vec<Separate<A>> {
+    values.into_iter().map(Separate::new).collect()
+}
+
+/// Interleaves the attributes of the vertices, the missing ones are zeroed.
+fn interleave(
+    positions: &[[f32; 3]],
+    tex_coord: Option<&Vec<[f32; 2]>>,
+    normals: Option<&Vec<[f32; 3]>>,
+    tangents: Option<&Vec<[f32; 3]>>,
+) -> Vec<PosNormTangTex> {
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, &position)| PosNormTangTex {
+            position,
+            normal: normals.map_or([0.0; 3], |normals| normals[i]),
+            tangent: tangents.map_or([0.0; 3], |tangents| tangents[i]),
+            tex_coord: tex_coord.map_or([0.0; 2], |tex_coord| tex_coord[i]),
+        })
+        .collect()
+}
+
 fn map_mode(mode: gltf::mesh::Mode) -> Result<Primitive, GltfError> {
     use gltf::mesh::Mode::*;
     match mode {
@@ -23,7 +23,8 @@ use animation::{Animation, Sampler, SamplerPrimitive, TransformChannel};
 use assets::{Asset, Error as AssetError, Handle};
 use core::transform::Transform;
 use gfx::Primitive;
-use renderer::{AnimatedVertexBufferCombination, MeshHandle, TextureData, TextureHandle};
+use renderer::{AnimatedVertexBufferCombination, MeshHandle, PosNormTangTex, TextureData,
+               TextureHandle};
 use specs::VecStorage;
 
 mod format;
@@ -36,10 +37,20 @@ pub struct GltfPrimitive {
     pub primitive: Primitive,
     pub material: Option<usize>,
     pub indices: Option<Vec<usize>>,
-    pub attributes: AnimatedVertexBufferCombination,
+    pub attributes: GltfAttributes,
     pub handle: Option<MeshHandle>,
 }
 
+/// Vertex attributes of a primitive
+#[derive(Debug)]
+pub enum GltfAttributes {
+    /// Each attribute in its own buffer, for the passes drawing `Separate` attributes
+    Separate(AnimatedVertexBufferCombination),
+    /// Position, normal, tangent and texture coordinates in a single buffer, for the passes
+    /// drawing `PosNormTangTex` vertices
+    Interleaved(Vec<PosNormTangTex>),
+}
+
 /// Alpha mode for material
 #[derive(Debug)]
 pub enum AlphaMode {
@@ -115,6 +126,15 @@ pub struct GltfSceneOptions {
     pub load_animations: bool,
     pub flip_v_coord: bool,
     pub move_to_origin: bool,
+    /// Load the meshes with interleaved `PosNormTangTex` vertices instead of separate
+    /// attributes. The vertex colors and joints are dropped, and the missing attributes zeroed.
+    pub interleaved: bool,
+    /// Compress the animation samplers with the given tolerance, removing the key frames
+    /// interpolation reproduces, see `Sampler::compress`.
+    pub compress_animations: Option<f32>,
+    /// Quantize the rotations of the animations before compressing them, see
+    /// `Sampler::quantize_rotations`.
+    pub quantize_rotations: bool,
 }
 
 /// Actual asset produced on finished loading of a GLTF scene file.
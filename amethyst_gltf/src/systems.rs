@@ -9,10 +9,10 @@ use core::transform::*;
 use fnv::FnvHashMap;
 use hibitset::BitSet;
 use renderer::{AnimatedComboMeshCreator, JointTransforms, Material, MaterialDefaults, Mesh,
-               Texture};
+               MeshData, Texture, Transparent};
 use specs::{Entities, Entity, Fetch, FetchMut, Join, System, WriteStorage};
 
-use {GltfMaterial, GltfPrimitive, GltfSceneAsset, GltfSkin};
+use {AlphaMode, GltfAttributes, GltfMaterial, GltfPrimitive, GltfSceneAsset, GltfSkin};
 
 /// A GLTF scene loader, will transform `Handle<GltfSceneAsset>` into full entity hierarchies.
 ///
@@ -55,6 +55,7 @@ impl<'a> System<'a> for GltfSceneLoaderSystem {
         WriteStorage<'a, GlobalTransform>,
         WriteStorage<'a, Parent>,
         WriteStorage<'a, Material>,
+        WriteStorage<'a, Transparent>,
         WriteStorage<'a, AnimationHierarchy<Transform>>,
         WriteStorage<'a, AnimationSet<Transform>>,
         WriteStorage<'a, Joint>,
@@ -83,6 +84,7 @@ impl<'a> System<'a> for GltfSceneLoaderSystem {
             mut transforms,
             mut parents,
             mut materials,
+            mut transparents,
             mut animation_hierarchies,
             mut animation_sets,
             mut joints,
@@ -127,6 +129,7 @@ impl<'a> System<'a> for GltfSceneLoaderSystem {
                         &mut meshes,
                         &mesh_storage,
                         &mut materials,
+                        &mut transparents,
                         &texture_storage,
                         &*material_defaults,
                         &mut mesh_handles,
@@ -153,6 +156,7 @@ impl<'a> System<'a> for GltfSceneLoaderSystem {
                             &mut meshes,
                             &mesh_storage,
                             &mut materials,
+                            &mut transparents,
                             &texture_storage,
                             &*material_defaults,
                             &mut mesh_handles,
@@ -243,7 +247,10 @@ impl<'a> System<'a> for GltfSceneLoaderSystem {
                                 })
                                 .collect::<Vec<_>>();
                             animation.handle = Some(loader.load_from_data(
-                                Animation { nodes },
+                                Animation {
+                                    nodes,
+                                    events: Vec::new(),
+                                },
                                 (),
                                 &*animation_storage,
                             ));
@@ -297,6 +304,7 @@ fn load_node(
     meshes: &mut WriteStorage<Handle<Mesh>>,
     mesh_storage: &AssetStorage<Mesh>,
     materials: &mut WriteStorage<Material>,
+    transparents: &mut WriteStorage<Transparent>,
     texture_storage: &AssetStorage<Texture>,
     material_defaults: &MaterialDefaults,
     mesh_handles: &mut Vec<(usize, usize, Handle<Mesh>)>,
@@ -332,6 +340,7 @@ fn load_node(
             meshes,
             mesh_storage,
             materials,
+            transparents,
             texture_storage,
             material_defaults,
             mesh_handles,
@@ -376,6 +385,7 @@ fn load_node(
                 meshes,
                 mesh_storage,
                 materials,
+                transparents,
                 texture_storage,
                 material_defaults,
                 mesh_handles,
@@ -405,6 +415,7 @@ fn load_node(
                     meshes,
                     mesh_storage,
                     materials,
+                    transparents,
                     texture_storage,
                     material_defaults,
                     mesh_handles,
@@ -480,21 +491,34 @@ fn load_primitive(
     meshes: &mut WriteStorage<Handle<Mesh>>,
     mesh_storage: &AssetStorage<Mesh>,
     materials: &mut WriteStorage<Material>,
+    transparents: &mut WriteStorage<Transparent>,
     texture_storage: &AssetStorage<Texture>,
     material_defaults: &MaterialDefaults,
     mesh_handles: &mut Vec<(usize, usize, Handle<Mesh>)>,
     texture_handles: &mut Vec<(usize, TextureHandleLocation, Handle<Texture>)>,
 ) {
     let mesh = primitive.handle.as_ref().cloned().unwrap_or_else(|| {
-        let mesh_creator = AnimatedComboMeshCreator::new(primitive.attributes.clone());
-        let handle = loader.load_from_data(mesh_creator.into(), (), mesh_storage);
+        let data: MeshData = match primitive.attributes {
+            GltfAttributes::Separate(ref attributes) => {
+                AnimatedComboMeshCreator::new(attributes.clone()).into()
+            }
+            GltfAttributes::Interleaved(ref vertices) => vertices.clone().into(),
+        };
+        let handle = loader.load_from_data(data, (), mesh_storage);
         mesh_handles.push((node_index, primitive_index, handle.clone()));
         handle
     });
 
     // Load material for the primitive
-    let material = primitive.material
-        .and_then(|index| scene_asset.materials.get(index).map(|m| (index, m)))
+    let gltf_material = primitive
+        .material
+        .and_then(|index| scene_asset.materials.get(index).map(|m| (index, m)));
+    if let Some((_, material)) = gltf_material {
+        if let AlphaMode::Blend = material.alpha.0 {
+            transparents.insert(*entity, Transparent);
+        }
+    }
+    let material = gltf_material
         .map(|(index, material)| load_material(
             index,
             material,
@@ -521,11 +545,10 @@ fn load_material(
     texture_handles: &mut Vec<(usize, TextureHandleLocation, Handle<Texture>)>,
 ) -> Material {
     use self::TextureHandleLocation::*;
-    // TODO: base color factor
-    // TODO: metallic/roughness factors
+    // The base color, metallic, roughness and emissive factors are applied to the textures by
+    // the format
     // TODO: normal scale
-    // TODO: emissive factor
-    // TODO: alpha
+    // TODO: alpha mask
     // TODO: double sided
     let albedo = material
         .base_color
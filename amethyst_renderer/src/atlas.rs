@@ -0,0 +1,80 @@
+//! Texture atlases.
+
+use std::collections::HashMap;
+
+use amethyst_assets::{Asset, AssetStorage, Handle, Result};
+use specs::VecStorage;
+
+use tex::{Texture, TextureHandle, TextureRegion};
+
+/// A handle to a `TextureAtlas` asset.
+pub type TextureAtlasHandle = Handle<TextureAtlas>;
+
+/// Named regions of a single texture, each usable as a texture of its own.
+///
+/// Loaded with `TextureAtlasFormat`, the texture handle is passed as the
+/// format options.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureAtlas {
+    /// Texture the regions are cut out of.
+    pub texture: TextureHandle,
+    /// The regions of the atlas, by name.
+    pub regions: HashMap<String, TextureRegion>,
+    handles: HashMap<String, TextureHandle>,
+}
+
+impl TextureAtlas {
+    /// Creates a new `TextureAtlas`.
+    pub fn new(texture: TextureHandle, regions: HashMap<String, TextureRegion>) -> Self {
+        TextureAtlas {
+            texture,
+            regions,
+            handles: HashMap::new(),
+        }
+    }
+
+    /// Returns a handle to the texture of the region with the given name.
+    ///
+    /// The handle can be used anywhere a texture handle is, like in a
+    /// `Material` or a `SpriteRender`. All the handles share the texture of
+    /// the atlas, so they're cheap to draw one after another.
+    ///
+    /// Returns `None` if there's no such region or the texture of the atlas
+    /// isn't loaded yet. Handles are created once and reused by later calls.
+    pub fn sub_texture(
+        &mut self,
+        name: &str,
+        storage: &mut AssetStorage<Texture>,
+    ) -> Option<TextureHandle> {
+        if let Some(handle) = self.handles.get(name) {
+            return Some(handle.clone());
+        }
+        let region = match self.regions.get(name) {
+            Some(region) => *region,
+            None => return None,
+        };
+        let texture = match storage.get(&self.texture) {
+            Some(texture) => texture.sub_texture(region),
+            None => return None,
+        };
+        let handle = storage.insert(texture);
+        self.handles.insert(name.to_owned(), handle.clone());
+        Some(handle)
+    }
+}
+
+impl Asset for TextureAtlas {
+    const NAME: &'static str = "renderer::TextureAtlas";
+    type Data = TextureAtlasData;
+    type HandleStorage = VecStorage<TextureAtlasHandle>;
+}
+
+/// Texture atlas data, as produced by `TextureAtlasFormat`.
+#[derive(Clone, Debug)]
+pub struct TextureAtlasData(pub TextureAtlas);
+
+impl Into<Result<TextureAtlas>> for TextureAtlasData {
+    fn into(self) -> Result<TextureAtlas> {
+        Ok(self.0)
+    }
+}
@@ -0,0 +1,160 @@
+//! Camera projections following the window size.
+
+use amethyst_core::cgmath::{perspective, Rad};
+use shrev::{EventChannel, ReaderId};
+use specs::{Component, DenseVecStorage, Fetch, Join, NullStorage, ReadStorage, System,
+            WriteStorage};
+use winit::{Event, WindowEvent};
+
+use cam::{Camera, Projection};
+use resources::ScreenDimensions;
+
+/// Keeps the aspect ratio of the `Camera` projection equal to the one of the
+/// window, preserving the vertical extent of the view.
+///
+/// Works with both perspective and orthographic projections.
+#[derive(Clone, Debug, Default)]
+pub struct AutoAspect;
+
+impl Component for AutoAspect {
+    type Storage = NullStorage<Self>;
+}
+
+/// Recomputes the perspective projection of the `Camera` when the window is
+/// resized, so that it always covers at least the given fields of view.
+///
+/// The vertical field of view is widened on narrow windows, where the
+/// horizontal one would otherwise fall below `min_fov_x`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AutoFov {
+    /// Vertical field of view in radians, used on wide enough windows.
+    pub fov_y: f32,
+    /// Minimum horizontal field of view in radians.
+    pub min_fov_x: f32,
+    /// Distance of the near clipping plane.
+    pub near: f32,
+    /// Distance of the far clipping plane.
+    pub far: f32,
+}
+
+impl AutoFov {
+    /// Creates a new `AutoFov` with the default clipping planes of
+    /// `Projection::perspective`.
+    pub fn new(fov_y: f32, min_fov_x: f32) -> Self {
+        AutoFov {
+            fov_y,
+            min_fov_x,
+            near: 0.1,
+            far: 2000.0,
+        }
+    }
+
+    /// Returns the vertical field of view for the given aspect ratio.
+    pub fn fov_y_for(&self, aspect: f32) -> f32 {
+        let min_fov_y = 2.0 * ((self.min_fov_x / 2.0).tan() / aspect).atan();
+        self.fov_y.max(min_fov_y)
+    }
+}
+
+impl Component for AutoFov {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Keeps the orthographic projection of the `Camera` in sync with the
+/// `ScreenDimensions`, with one unit per pixel, for pixel perfect 2D games.
+///
+/// The projection is the one of `Camera::screen_2d`, or of
+/// `Camera::centered_2d` when `centered` is set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AutoOrtho {
+    /// Puts the origin at the center of the screen instead of its lower left
+    /// corner.
+    pub centered: bool,
+}
+
+impl AutoOrtho {
+    /// Returns the projection covering a screen of the given size.
+    pub fn projection(&self, width: f32, height: f32) -> Projection {
+        if self.centered {
+            let (x, y) = (width / 2., height / 2.);
+            Projection::orthographic(-x, x, y, -y)
+        } else {
+            Projection::orthographic(0., width, height, 0.)
+        }
+    }
+}
+
+impl Component for AutoOrtho {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Updates the projection of cameras with an `AutoAspect` or `AutoFov`
+/// component whenever the window is resized, and the one of cameras with an
+/// `AutoOrtho` component whenever the `ScreenDimensions` change.
+pub struct AutoFovSystem {
+    event_reader: ReaderId<Event>,
+}
+
+impl AutoFovSystem {
+    /// Creates a new `AutoFovSystem` that listens with the given reader Id.
+    pub fn new(winit_event_reader: ReaderId<Event>) -> Self {
+        AutoFovSystem {
+            event_reader: winit_event_reader,
+        }
+    }
+}
+
+impl<'a> System<'a> for AutoFovSystem {
+    type SystemData = (
+        Fetch<'a, EventChannel<Event>>,
+        Fetch<'a, ScreenDimensions>,
+        ReadStorage<'a, AutoAspect>,
+        ReadStorage<'a, AutoFov>,
+        ReadStorage<'a, AutoOrtho>,
+        WriteStorage<'a, Camera>,
+    );
+
+    fn run(
+        &mut self,
+        (events, dimensions, auto_aspect, auto_fov, auto_ortho, mut camera): Self::SystemData,
+    ) {
+        // Checked every frame rather than on resize events, so that new cameras get their
+        // projection right away.
+        let (width, height) = (dimensions.width(), dimensions.height());
+        if width > 0.0 && height > 0.0 {
+            for (camera, auto_ortho) in (&mut camera, &auto_ortho).join() {
+                camera.proj = auto_ortho.projection(width, height).into();
+            }
+        }
+
+        let mut size = None;
+        for event in events.read(&mut self.event_reader) {
+            if let Event::WindowEvent {
+                event: WindowEvent::Resized(width, height),
+                ..
+            } = *event
+            {
+                size = Some((width, height));
+            }
+        }
+        let aspect = match size {
+            Some((width, height)) if width > 0 && height > 0 => width as f32 / height as f32,
+            _ => return,
+        };
+
+        for (camera, _) in (&mut camera, &auto_aspect).join() {
+            // Both projections scale x by the same factor as y divided by the aspect ratio,
+            // the x offset of off-center projections is scaled along to keep their center.
+            let scale_x = camera.proj.y.y / aspect;
+            if camera.proj.x.x != 0.0 {
+                camera.proj.w.x *= scale_x / camera.proj.x.x;
+            }
+            camera.proj.x.x = scale_x;
+        }
+
+        for (camera, auto_fov) in (&mut camera, &auto_fov).join() {
+            let fov_y = auto_fov.fov_y_for(aspect);
+            camera.proj = perspective(Rad(fov_y), aspect, auto_fov.near, auto_fov.far);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Meshes turned towards the camera.
+
+use amethyst_core::GlobalTransform;
+use amethyst_core::cgmath::{InnerSpace, Vector3};
+use specs::{Component, DenseVecStorage, Fetch, Join, ReadStorage, System, WriteStorage};
+
+use cam::{ActiveCamera, Camera};
+
+/// Keeps the mesh of the entity facing the camera.
+///
+/// The local z axis of the mesh is turned towards the camera, so quads are
+/// expected to lie in the xy plane.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Billboard {
+    /// Faces the camera on all axes, aligned with the screen.
+    Spherical,
+    /// Only rotates around the world y axis, for trees and other upright
+    /// impostors.
+    Cylindrical,
+}
+
+impl Default for Billboard {
+    fn default() -> Self {
+        Billboard::Spherical
+    }
+}
+
+impl Component for Billboard {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Replaces the rotation of the `GlobalTransform` of `Billboard` entities, so
+/// that they face the active camera.
+///
+/// Translation and scale are kept. Note that this should run after `GlobalTransform` has been
+/// updated for the current frame, and before rendering occurs.
+pub struct BillboardSystem;
+
+impl BillboardSystem {
+    /// Create new billboard system
+    pub fn new() -> Self {
+        BillboardSystem
+    }
+}
+
+impl<'a> System<'a> for BillboardSystem {
+    type SystemData = (
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, Billboard>,
+        WriteStorage<'a, GlobalTransform>,
+    );
+
+    fn run(&mut self, (active, camera, billboard, mut global): Self::SystemData) {
+        let camera = active
+            .and_then(|a| global.get(a.entity).cloned())
+            .or_else(|| (&camera, &global).join().map(|cg| *cg.1).next());
+        let camera = match camera {
+            Some(camera) => camera.0,
+            None => return,
+        };
+        let camera_position = camera.w.truncate();
+
+        for (billboard, global) in (&billboard, &mut global).join() {
+            let position = global.0.w.truncate();
+            let (x, y, z) = match *billboard {
+                Billboard::Spherical => (
+                    camera.x.truncate().normalize(),
+                    camera.y.truncate().normalize(),
+                    camera.z.truncate().normalize(),
+                ),
+                Billboard::Cylindrical => {
+                    let mut forward = camera_position - position;
+                    forward.y = 0.0;
+                    if forward.magnitude2() < 1e-10 {
+                        continue;
+                    }
+                    let forward = forward.normalize();
+                    let up = Vector3::unit_y();
+                    (up.cross(forward), up, forward)
+                }
+            };
+            let scale = Vector3::new(
+                global.0.x.truncate().magnitude(),
+                global.0.y.truncate().magnitude(),
+                global.0.z.truncate().magnitude(),
+            );
+            global.0.x = (x * scale.x).extend(0.0);
+            global.0.y = (y * scale.y).extend(0.0);
+            global.0.z = (z * scale.z).extend(0.0);
+        }
+    }
+}
@@ -1,16 +1,28 @@
 //! ECS rendering bundle
 
-use {AmbientColor, Camera, Light, Material, MaterialDefaults, Mesh, Rgba, ScreenDimensions,
-     Texture, WindowMessages};
-use amethyst_assets::{AssetStorage, Handle, Loader};
+use {ActiveCameraEvent, ActiveCameraSystem, AmbientColor, AutoAspect, AutoFov, AutoFovSystem,
+     AutoOrtho, BloomParams, Camera, ChromaticAberrationParams, CustomUniforms, DebugLines, Decal,
+     DofParams, Fog, Hidden, Light, Material, MaterialDefaults, MaterialLibrary, Mesh, MorphWeights,
+     MotionBlurParams, Outline, ParticleEmitter, RenderMask, RenderStats, ResolutionScale, Rgba,
+     ScreenDimensions, ScreenDump, Shader, SpriteRender, SpriteSheet, SsaoParams, TargetTextures,
+     Terrain, TerrainMaterial, Texture, TextureAtlas, TileMap, VignetteParams, Water,
+     WaterReflection, WaterReflectionSystem, WindowMessages, ZOrder};
+use amethyst_assets::{AssetStorage, Handle, Loader, Processor};
 use amethyst_core::bundle::{ECSBundle, Result, ResultExt};
+use amethyst_core::frame_limiter::{FrameLimiter, FrameRateLimitStrategy};
 use amethyst_core::orientation::Orientation;
 use amethyst_core::transform::components::*;
+use billboard::{Billboard, BillboardSystem};
 use config::DisplayConfig;
+use occlusion::{Occluder, OcclusionCullingSystem};
 use pipe::{PipelineBuild, PolyPipeline};
+use shadow::{PointShadowMaps, ShadowCaster, ShadowConfig};
+use shrev::EventChannel;
 use specs::{DispatcherBuilder, World};
 use system::RenderSystem;
 use transparent::{Transparent, TransparentBackToFront, TransparentSortingSystem};
+use visibility::{BoundingSphere, FrustumCullingSystem, Visibility};
+use winit::Event;
 
 /// Rendering bundle
 ///
@@ -18,9 +30,38 @@ use transparent::{Transparent, TransparentBackToFront, TransparentSortingSystem}
 /// Will also register asset contexts with the asset `Loader`, and add systems for merging
 /// `AssetFuture` into its related component.
 ///
-/// Will register `TransparentSortingSystem`, with name `transparent_sorting_system` if sorting is
+/// Will register `TransparentSortingSystem`, with name `transparent_sorting_system`, so that
+/// `Transparent` entities are sorted every frame.
+///
+/// Will register `BillboardSystem`, with name `billboard_system` if billboards are requested.
+///
+/// Will register `WaterReflectionSystem`, with name `water_reflection_system` if water
+/// reflections are requested.
+///
+/// Will register `FrustumCullingSystem`, with name `frustum_culling_system` if culling is
 /// requested.
 ///
+/// Will register `OcclusionCullingSystem`, with name `occlusion_culling_system` if both frustum
+/// and occlusion culling are requested.
+///
+/// Will add the `RenderStats` resource if GPU timing is requested.
+///
+/// Will set the rate of the `FrameLimiter` resource if `max_fps` is configured.
+///
+/// Will register `Processor<SpriteSheet>`, with name `sprite_sheet_processor`.
+///
+/// Will register `Processor<TextureAtlas>`, with name `texture_atlas_processor`.
+///
+/// Will register `Processor<Terrain>`, with name `terrain_processor`.
+///
+/// Will register `Processor<Shader>`, with name `shader_processor`.
+///
+/// Will register `Processor<MaterialLibrary>`, with name `material_library_processor`.
+///
+/// Will register `AutoFovSystem`, with name `auto_fov_system`.
+///
+/// Will register `ActiveCameraSystem`, with name `active_camera_system`.
+///
 pub struct RenderBundle<'a, B, P>
 where
     B: PipelineBuild<Pipeline = P>,
@@ -28,7 +69,12 @@ where
 {
     pipe: B,
     config: Option<DisplayConfig>,
-    transparent_sorting: Option<&'a [&'a str]>,
+    transparent_sorting: &'a [&'a str],
+    billboards: Option<&'a [&'a str]>,
+    water_reflections: Option<&'a [&'a str]>,
+    frustum_culling: Option<&'a [&'a str]>,
+    occlusion_culling: bool,
+    gpu_timing: bool,
 }
 
 impl<'a, B, P> RenderBundle<'a, B, P>
@@ -41,13 +87,51 @@ where
         RenderBundle {
             pipe,
             config,
-            transparent_sorting: None,
+            transparent_sorting: &[],
+            billboards: None,
+            water_reflections: None,
+            frustum_culling: None,
+            occlusion_culling: false,
+            gpu_timing: false,
         }
     }
 
-    /// Enable transparent mesh sorting, with the given dependencies
+    /// Sets the dependencies of the transparent mesh sorting, e.g. the system updating
+    /// `GlobalTransform` such as `"transform_system"`, none by default
     pub fn with_transparent_sorting(mut self, dep: &'a [&'a str]) -> Self {
-        self.transparent_sorting = Some(dep);
+        self.transparent_sorting = dep;
+        self
+    }
+
+    /// Enable turning `Billboard` entities towards the camera, with the given dependencies
+    pub fn with_billboards(mut self, dep: &'a [&'a str]) -> Self {
+        self.billboards = Some(dep);
+        self
+    }
+
+    /// Enable mirroring `WaterReflection` cameras below their water, with the given dependencies
+    pub fn with_water_reflections(mut self, dep: &'a [&'a str]) -> Self {
+        self.water_reflections = Some(dep);
+        self
+    }
+
+    /// Enable frustum culling of meshes, with the given dependencies
+    pub fn with_frustum_culling(mut self, dep: &'a [&'a str]) -> Self {
+        self.frustum_culling = Some(dep);
+        self
+    }
+
+    /// Enable occlusion culling of meshes behind `Occluder`s
+    ///
+    /// Has no effect unless frustum culling is enabled too.
+    pub fn with_occlusion_culling(mut self) -> Self {
+        self.occlusion_culling = true;
+        self
+    }
+
+    /// Enable timing the passes on the GPU, the results are written to the `RenderStats` resource
+    pub fn with_gpu_timing(mut self) -> Self {
+        self.gpu_timing = true;
         self
     }
 }
@@ -64,32 +148,125 @@ impl<'a, 'b, 'c, B: PipelineBuild<Pipeline = P>, P: 'b + PolyPipeline> ECSBundle
         world.res.entry().or_insert_with(|| WindowMessages::new());
         world.add_resource(AssetStorage::<Mesh>::new());
         world.add_resource(AssetStorage::<Texture>::new());
+        world.add_resource(AssetStorage::<SpriteSheet>::new());
+        world.add_resource(AssetStorage::<TextureAtlas>::new());
+        world.add_resource(AssetStorage::<Terrain>::new());
+        world.add_resource(AssetStorage::<Shader>::new());
+        world.add_resource(AssetStorage::<MaterialLibrary>::new());
         world.add_resource(Orientation::default());
         world.add_resource(TransparentBackToFront::default());
+        world.res.entry().or_insert_with(|| ShadowConfig::default());
+        world.add_resource(PointShadowMaps::default());
+        world.res.entry().or_insert_with(|| BloomParams::default());
+        world.res.entry().or_insert_with(|| SsaoParams::default());
+        world.res.entry().or_insert_with(|| DofParams::default());
+        world.res.entry().or_insert_with(|| MotionBlurParams::default());
+        world.res.entry().or_insert_with(|| VignetteParams::default());
+        world.res.entry().or_insert_with(|| ChromaticAberrationParams::default());
+        world.res.entry().or_insert_with(|| Fog::default());
+        world.res.entry().or_insert_with(|| DebugLines::default());
+        world.res.entry().or_insert_with(|| ScreenDump::default());
+        world.res.entry().or_insert_with(|| TargetTextures::default());
+        world.res.entry().or_insert_with(|| ResolutionScale::default());
 
         let mat = create_default_mat(world);
         world.add_resource(MaterialDefaults(mat));
 
         world.register::<GlobalTransform>();
         world.register::<Light>();
+        world.register::<ShadowCaster>();
         world.register::<Material>();
+        world.register::<CustomUniforms>();
         world.register::<Handle<Mesh>>();
         world.register::<Handle<Texture>>();
+        world.register::<Handle<SpriteSheet>>();
+        world.register::<Handle<TextureAtlas>>();
+        world.register::<Handle<Terrain>>();
         world.register::<Camera>();
         world.register::<Transparent>();
+        world.register::<Hidden>();
+        world.register::<RenderMask>();
+        world.register::<BoundingSphere>();
+        world.register::<Billboard>();
+        world.register::<Decal>();
+        world.register::<Occluder>();
+        world.register::<ParticleEmitter>();
+        world.register::<SpriteRender>();
+        world.register::<TileMap>();
+        world.register::<TerrainMaterial>();
+        world.register::<Water>();
+        world.register::<WaterReflection>();
+        world.register::<ZOrder>();
+        world.register::<Outline>();
+        world.register::<MorphWeights>();
+        world.register::<AutoAspect>();
+        world.register::<AutoFov>();
+        world.register::<AutoOrtho>();
+
+        if let Some(fps) = self.config.as_ref().and_then(|config| config.max_fps) {
+            world.res.entry().or_insert_with(|| FrameLimiter::default());
+            world
+                .write_resource::<FrameLimiter>()
+                .set_rate(FrameRateLimitStrategy::Sleep, fps);
+        }
 
         let system = RenderSystem::build(self.pipe, self.config).chain_err(|| "Renderer error!")?;
         let (width, height) = system
             .window_size()
             .expect("Window closed during initialization!");
         world.add_resource(ScreenDimensions::new(width, height));
-        if let Some(dep) = self.transparent_sorting {
+        if self.gpu_timing {
+            world.add_resource(RenderStats::default());
+        }
+        builder = builder.add(
+            TransparentSortingSystem::new(),
+            "transparent_sorting_system",
+            self.transparent_sorting,
+        );
+        if let Some(dep) = self.billboards {
+            builder = builder.add(BillboardSystem::new(), "billboard_system", dep);
+        };
+        if let Some(dep) = self.water_reflections {
             builder = builder.add(
-                TransparentSortingSystem::new(),
-                "transparent_sorting_system",
+                WaterReflectionSystem::new(),
+                "water_reflection_system",
                 dep,
             );
         };
+        if let Some(dep) = self.frustum_culling {
+            // Passes only cull when `Visibility` exists, so it's added along with the system.
+            world.add_resource(Visibility::default());
+            builder = builder.add(FrustumCullingSystem::new(), "frustum_culling_system", dep);
+            if self.occlusion_culling {
+                builder = builder.add(
+                    OcclusionCullingSystem::new(),
+                    "occlusion_culling_system",
+                    &["frustum_culling_system"],
+                );
+            }
+        };
+        builder = builder.add(Processor::<SpriteSheet>::new(), "sprite_sheet_processor", &[]);
+        builder = builder.add(
+            Processor::<TextureAtlas>::new(),
+            "texture_atlas_processor",
+            &[],
+        );
+        builder = builder.add(Processor::<Terrain>::new(), "terrain_processor", &[]);
+        builder = builder.add(Processor::<Shader>::new(), "shader_processor", &[]);
+        builder = builder.add(
+            Processor::<MaterialLibrary>::new(),
+            "material_library_processor",
+            &[],
+        );
+        let reader = world
+            .write_resource::<EventChannel<Event>>()
+            .register_reader();
+        builder = builder.add(AutoFovSystem::new(reader), "auto_fov_system", &[]);
+        world
+            .res
+            .entry()
+            .or_insert_with(|| EventChannel::<ActiveCameraEvent>::new());
+        builder = builder.add(ActiveCameraSystem::new(), "active_camera_system", &[]);
         Ok(builder.add_thread_local(system))
     }
 }
@@ -123,5 +300,8 @@ fn create_default_mat(world: &World) -> Material {
         roughness,
         ambient_occlusion,
         caveat,
+        albedo_factor: [1.0; 4],
+        emission_factor: [1.0; 4],
+        uv_offset: [0.0; 2],
     }
 }
@@ -1,7 +1,11 @@
 //! Camera type with support for perspective and orthographic projections.
 
-use amethyst_core::cgmath::{Deg, Matrix4, Ortho, PerspectiveFov};
-use specs::{Component, Entity, HashMapStorage};
+use amethyst_core::cgmath::{Deg, Matrix4, Ortho, PerspectiveFov, Rad};
+use shrev::EventChannel;
+use specs::{Component, Entities, Entity, Fetch, FetchMut, HashMapStorage, Join, ReadStorage,
+            System, World};
+
+use error::{Error, Result};
 
 /// The projection mode of a `Camera`.
 ///
@@ -76,6 +80,25 @@ impl Camera {
         Self::from(Projection::orthographic(-1., 1., 1., -1.))
     }
 
+    /// Create a pixel perfect camera for 2D, given the screen dimensions.
+    ///
+    /// Will use an orthographic projection with one unit per pixel, lower left corner being
+    /// (0., 0.) and upper right (width, height).
+    /// View transformation will be multiplicative identity.
+    pub fn screen_2d(width: f32, height: f32) -> Self {
+        Self::from(Projection::orthographic(0., width, height, 0.))
+    }
+
+    /// Create a pixel perfect camera for 2D centered on the origin, given the screen dimensions.
+    ///
+    /// Will use an orthographic projection with one unit per pixel, lower left corner being
+    /// (-width / 2., -height / 2.) and upper right (width / 2., height / 2.).
+    /// View transformation will be multiplicative identity.
+    pub fn centered_2d(width: f32, height: f32) -> Self {
+        let (x, y) = (width / 2., height / 2.);
+        Self::from(Projection::orthographic(-x, x, y, -y))
+    }
+
     /// Create a standard camera for 3D.
     ///
     /// Will use a perspective projection with aspect from the given screen dimensions and a field
@@ -85,6 +108,33 @@ impl Camera {
         use amethyst_core::cgmath::Deg;
         Self::from(Projection::perspective(width / height, Deg(60.)))
     }
+
+    /// Returns the projection the matrix of the camera was built from.
+    ///
+    /// The matrix is assumed to be an orthographic or a perspective projection matrix, as built
+    /// from a `Projection`.
+    pub fn projection(&self) -> Projection {
+        let m = &self.proj;
+        if m.w.w == 0. {
+            Projection::Perspective(PerspectiveFov {
+                fovy: Rad(2. * (1. / m.y.y).atan()),
+                aspect: m.y.y / m.x.x,
+                near: m.w.z / (m.z.z - 1.),
+                far: m.w.z / (m.z.z + 1.),
+            })
+        } else {
+            let (width, height, depth) = (2. / m.x.x, 2. / m.y.y, -2. / m.z.z);
+            let (x, y, z) = (-m.w.x * width, -m.w.y * height, -m.w.z * depth);
+            Projection::Orthographic(Ortho {
+                left: (x - width) / 2.,
+                right: (x + width) / 2.,
+                top: (y + height) / 2.,
+                bottom: (y - height) / 2.,
+                near: (z - depth) / 2.,
+                far: (z + depth) / 2.,
+            })
+        }
+    }
 }
 
 impl Component for Camera {
@@ -98,3 +148,101 @@ pub struct ActiveCamera {
     /// Camera entity
     pub entity: Entity,
 }
+
+impl ActiveCamera {
+    /// Makes the given entity the active camera.
+    ///
+    /// Fails with `Error::NoCamera`, leaving the active camera unchanged, if the
+    /// entity is dead or has no `Camera`.
+    pub fn set(world: &mut World, entity: Entity) -> Result<()> {
+        let is_camera =
+            world.is_alive(entity) && world.read::<Camera>().get(entity).is_some();
+        if !is_camera {
+            return Err(Error::NoCamera);
+        }
+        world.add_resource(ActiveCamera { entity });
+        Ok(())
+    }
+
+    /// Makes the camera following the active one the active camera, wrapping
+    /// around to the first camera, and returns it.
+    ///
+    /// Cameras are ordered by entity id. Returns `None` when there's no camera.
+    pub fn cycle(world: &mut World) -> Option<Entity> {
+        let next = {
+            let current = world.res.try_fetch::<ActiveCamera>(0).map(|a| a.entity);
+            let entities = world.entities();
+            let cameras = world.read::<Camera>();
+            let all = (&*entities, &cameras)
+                .join()
+                .map(|(entity, _)| entity)
+                .collect::<Vec<_>>();
+            let position = current.and_then(|current| all.iter().position(|&e| e == current));
+            match position {
+                Some(position) => all.get(position + 1).or(all.first()).cloned(),
+                None => all.first().cloned(),
+            }
+        };
+        if let Some(entity) = next {
+            world.add_resource(ActiveCamera { entity });
+        }
+        next
+    }
+}
+
+/// Event sent through an `EventChannel<ActiveCameraEvent>` by the
+/// `ActiveCameraSystem` when the `ActiveCamera` changes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ActiveCameraEvent {
+    /// The previously active camera, `None` if there was no `ActiveCamera`.
+    pub previous: Option<Entity>,
+    /// The newly active camera, `None` if the `ActiveCamera` was removed.
+    pub current: Option<Entity>,
+}
+
+/// Sends an `ActiveCameraEvent` whenever the `ActiveCamera` changes, whether
+/// through `ActiveCamera::set` or by writing the resource directly.
+///
+/// Also warns once when the active camera entity is dead or has no `Camera`,
+/// the renderer then falling back to the first camera.
+#[derive(Default)]
+pub struct ActiveCameraSystem {
+    previous: Option<Entity>,
+    warned: bool,
+}
+
+impl ActiveCameraSystem {
+    /// Creates a new `ActiveCameraSystem`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> System<'a> for ActiveCameraSystem {
+    type SystemData = (
+        Entities<'a>,
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        FetchMut<'a, EventChannel<ActiveCameraEvent>>,
+    );
+
+    fn run(&mut self, (entities, active, camera, mut events): Self::SystemData) {
+        let current = active.map(|active| active.entity);
+        if current != self.previous {
+            events.single_write(ActiveCameraEvent {
+                previous: self.previous,
+                current,
+            });
+            self.previous = current;
+            self.warned = false;
+        }
+
+        if let Some(entity) = current {
+            let is_camera = entities.is_alive(entity) && camera.get(entity).is_some();
+            if !is_camera && !self.warned {
+                warn!("Active camera {:?} is not a camera anymore", entity);
+                self.warned = true;
+            }
+        }
+    }
+}
@@ -23,12 +23,40 @@ pub struct DisplayConfig {
     /// Enables or disables vertical synchronization.
     #[serde(default = "default_vsync")]
     pub vsync: bool,
-    /// Level of MSAA anti-aliasing.
+    /// Maximum number of frames drawn per second.
+    ///
+    /// `RenderBundle` sets the rate of the application's `FrameLimiter` to it,
+    /// which keeps the frame pacing steady when `vsync` is disabled. Drawing
+    /// itself never blocks. `None` keeps the `FrameLimiter` as it is.
+    #[serde(default)]
+    pub max_fps: Option<u32>,
+    /// Number of MSAA samples of the backbuffer, which should be a power of two.
+    ///
+    /// `0` disables multisampling, `DrawFxaa` can be used instead when it is
+    /// unavailable.
     #[serde(default = "default_multisampling")]
     pub multisampling: u16,
     /// Sets the visibility of the window.
     #[serde(default = "default_visibility")]
     pub visibility: bool,
+    /// Creates the GPU context without a window and draws into an offscreen
+    /// main target, e.g. for image comparison tests on CI or thumbnail
+    /// generation on servers, where there is no display.
+    ///
+    /// The main target has the size given by `dimensions`, 1024 by 768 by
+    /// default, and resizing happens through `ScreenDimensions` only. There
+    /// are no window events. Backends that can't create a context without a
+    /// window fall back to `hidden_window`.
+    #[serde(default)]
+    pub headless: bool,
+    /// Hides the window and draws into an offscreen main target instead.
+    ///
+    /// Unlike `headless`, the GPU context still comes from a window, so a
+    /// display (or a virtual one like Xvfb) is required. The main target has
+    /// the size given by `dimensions`, and resizing happens through
+    /// `ScreenDimensions` only.
+    #[serde(default)]
+    pub hidden_window: bool,
 }
 
 impl Default for DisplayConfig {
@@ -40,8 +68,11 @@ impl Default for DisplayConfig {
             min_dimensions: None,
             max_dimensions: None,
             vsync: default_vsync(),
+            max_fps: None,
             multisampling: default_multisampling(),
             visibility: default_visibility(),
+            headless: false,
+            hidden_window: false,
         }
     }
 }
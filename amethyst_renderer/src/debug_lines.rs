@@ -0,0 +1,104 @@
+//! Immediate mode debug lines.
+
+use std::f32::consts::PI;
+
+use amethyst_core::cgmath::{EuclideanSpace, Point3, Transform, Vector3};
+use amethyst_core::transform::GlobalTransform;
+
+use color::Rgba;
+use vertex::PosColor;
+
+const SPHERE_SEGMENTS: usize = 32;
+
+/// Lines drawn by the `DrawDebugLines` pass.
+///
+/// The lines are in world space and are cleared after every frame, so they
+/// need to be added again each frame they should stay visible.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DebugLines {
+    vertices: Vec<PosColor>,
+}
+
+impl DebugLines {
+    /// Creates an empty set of lines.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a line from `start` to `end`.
+    pub fn add_line<C: Into<Rgba>>(&mut self, start: [f32; 3], end: [f32; 3], color: C) {
+        let color = color.into().into();
+        self.vertices.push(PosColor {
+            position: start,
+            color,
+        });
+        self.vertices.push(PosColor {
+            position: end,
+            color,
+        });
+    }
+
+    /// Adds the edges of the axis aligned box spanning from `min` to `max`.
+    pub fn add_box<C: Into<Rgba>>(&mut self, min: [f32; 3], max: [f32; 3], color: C) {
+        let color = color.into();
+        let corner = |i: usize| {
+            [
+                if i & 1 == 0 { min[0] } else { max[0] },
+                if i & 2 == 0 { min[1] } else { max[1] },
+                if i & 4 == 0 { min[2] } else { max[2] },
+            ]
+        };
+        for i in 0..8 {
+            for &axis in &[1, 2, 4] {
+                if i & axis == 0 {
+                    self.add_line(corner(i), corner(i | axis), color);
+                }
+            }
+        }
+    }
+
+    /// Adds a wire sphere, drawn as one circle around each axis.
+    pub fn add_sphere<C: Into<Rgba>>(&mut self, center: [f32; 3], radius: f32, color: C) {
+        let color = color.into();
+        let center = Vector3::from(center);
+        let point = |axis: usize, angle: f32| -> [f32; 3] {
+            let (sin, cos) = angle.sin_cos();
+            let mut offset = Vector3::new(0.0, 0.0, 0.0);
+            offset[(axis + 1) % 3] = cos * radius;
+            offset[(axis + 2) % 3] = sin * radius;
+            (center + offset).into()
+        };
+        for axis in 0..3 {
+            for i in 0..SPHERE_SEGMENTS {
+                let start = 2.0 * PI * i as f32 / SPHERE_SEGMENTS as f32;
+                let end = 2.0 * PI * (i + 1) as f32 / SPHERE_SEGMENTS as f32;
+                self.add_line(point(axis, start), point(axis, end), color);
+            }
+        }
+    }
+
+    /// Adds the axes of the given transform, with the x axis in red, the y
+    /// axis in green and the z axis in blue.
+    pub fn add_axes(&mut self, transform: &GlobalTransform, length: f32) {
+        let origin = transform.0.transform_point(Point3::new(0.0, 0.0, 0.0));
+        let axes = [
+            (Vector3::unit_x(), Rgba::red()),
+            (Vector3::unit_y(), Rgba::green()),
+            (Vector3::unit_z(), Rgba::blue()),
+        ];
+        for &(axis, color) in &axes {
+            let end = transform.0.transform_point(Point3::from_vec(axis * length));
+            self.add_line(origin.into(), end.into(), color);
+        }
+    }
+
+    /// Removes all lines.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    /// Returns the vertices of the lines, two per line.
+    pub fn vertices(&self) -> &[PosColor] {
+        &self.vertices
+    }
+}
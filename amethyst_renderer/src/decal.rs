@@ -0,0 +1,31 @@
+//! Decal component.
+
+use specs::{Component, DenseVecStorage};
+
+/// Projects the `Material` of its entity onto the geometry around it, drawn
+/// by the `DrawDecal` pass.
+///
+/// The decal covers the unit cube centered on the entity, scaled and rotated
+/// by its `GlobalTransform`. The albedo map is projected along the local z
+/// axis, with the local x and y axes mapping to the texture coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decal {
+    /// Multiplies the alpha of the projected albedo.
+    pub opacity: f32,
+    /// Fraction of the depth of the box over which the decal fades out
+    /// towards its front and back faces, between `0.0` and `1.0`.
+    pub fade: f32,
+}
+
+impl Default for Decal {
+    fn default() -> Self {
+        Decal {
+            opacity: 1.0,
+            fade: 0.0,
+        }
+    }
+}
+
+impl Component for Decal {
+    type Storage = DenseVecStorage<Self>;
+}
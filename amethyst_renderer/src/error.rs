@@ -14,8 +14,18 @@ pub type Result<T> = StdResult<T, Error>;
 /// Common renderer error type.
 #[derive(Debug)]
 pub enum Error {
+    /// The graphics backend does not support the operation.
+    Backend(String),
     /// Failed to create a buffer.
     BufferCreation(gfx::buffer::CreationError),
+    /// Failed to read back the backbuffer.
+    Capture(String),
+    /// Failed to update the vertices of a mesh.
+    MeshUpdate(String),
+    /// The entity is dead or has no `Camera`.
+    NoCamera,
+    /// The stage of a pass reading the stage input has no input target.
+    NoStageInput,
     /// A render target with the given name does not exist.
     NoSuchTarget(String),
     /// Failed to initialize a render pass.
@@ -32,6 +42,8 @@ pub enum Error {
     TargetCreation(gfx::CombinedError),
     /// Failed to create a texture resource.
     TextureCreation(gfx::texture::CreationError),
+    /// Failed to update the pixels of a texture.
+    TextureUpdate(String),
     /// The window handle associated with the renderer has been destroyed.
     WindowDestroyed,
 }
@@ -39,7 +51,12 @@ pub enum Error {
 impl StdError for Error {
     fn description(&self) -> &str {
         match *self {
+            Error::Backend(_) => "Operation not supported by the backend!",
             Error::BufferCreation(_) => "Failed to create buffer!",
+            Error::Capture(_) => "Failed to capture the backbuffer!",
+            Error::MeshUpdate(_) => "Failed to update mesh!",
+            Error::NoCamera => "Entity is not a camera!",
+            Error::NoStageInput => "Stage has no input target!",
             Error::NoSuchTarget(_) => "Target with this name does not exist!",
             Error::PassInit(_) => "Failed to initialize render pass!",
             Error::PipelineCreation(_) => "Failed to create PSO!",
@@ -48,6 +65,7 @@ impl StdError for Error {
             Error::ResViewCreation(_) => "Failed to create resource view!",
             Error::TargetCreation(_) => "Failed to create render target!",
             Error::TextureCreation(_) => "Failed to create texture!",
+            Error::TextureUpdate(_) => "Failed to update texture!",
             Error::WindowDestroyed => "Window has been destroyed!",
         }
     }
@@ -69,7 +87,12 @@ impl StdError for Error {
 impl Display for Error {
     fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
         match *self {
+            Error::Backend(ref e) => write!(fmt, "Backend operation failed: {}", e),
             Error::BufferCreation(ref e) => write!(fmt, "Buffer creation failed: {}", e),
+            Error::Capture(ref e) => write!(fmt, "Backbuffer capture failed: {}", e),
+            Error::MeshUpdate(ref e) => write!(fmt, "Mesh update failed: {}", e),
+            Error::NoCamera => write!(fmt, "Entity is not a camera"),
+            Error::NoStageInput => write!(fmt, "Stage has no input target"),
             Error::NoSuchTarget(ref e) => write!(fmt, "Nonexistent target: {}", e),
             Error::PassInit(ref e) => write!(fmt, "Pass initialization failed: {}", e),
             Error::PipelineCreation(ref e) => write!(fmt, "PSO creation failed: {}", e),
@@ -78,6 +101,7 @@ impl Display for Error {
             Error::ResViewCreation(ref e) => write!(fmt, "Resource view creation failed: {}", e),
             Error::TargetCreation(ref e) => write!(fmt, "Target creation failed: {}", e),
             Error::TextureCreation(ref e) => write!(fmt, "Texture creation failed: {}", e),
+            Error::TextureUpdate(ref e) => write!(fmt, "Texture update failed: {}", e),
             Error::WindowDestroyed => write!(fmt, "Window has been destroyed"),
         }
     }
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::str::from_utf8;
+
+use amethyst_assets::{Result, ResultExt, SimpleFormat};
+use ron::de::from_str;
+
+use atlas::{TextureAtlas, TextureAtlasData};
+use tex::{TextureHandle, TextureRegion};
+
+/// Contents of a `TextureAtlasFormat` file.
+///
+/// Regions are in pixels, with the origin at the top left corner of the
+/// texture.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TextureAtlasDefinition {
+    /// The regions of the atlas, by name.
+    pub regions: HashMap<String, TextureRegion>,
+}
+
+impl TextureAtlasDefinition {
+    /// Creates a texture atlas using the given texture.
+    pub fn build(&self, texture: TextureHandle) -> TextureAtlas {
+        TextureAtlas::new(texture, self.regions.clone())
+    }
+}
+
+/// Allows loading texture atlases from RON files containing a
+/// `TextureAtlasDefinition`.
+///
+/// The options are the handle of the texture the regions are cut out of.
+#[derive(Clone)]
+pub struct TextureAtlasFormat;
+
+impl SimpleFormat<TextureAtlas> for TextureAtlasFormat {
+    const NAME: &'static str = "TEXTURE_ATLAS";
+
+    type Options = TextureHandle;
+
+    fn import(&self, bytes: Vec<u8>, texture: TextureHandle) -> Result<TextureAtlasData> {
+        let definition: TextureAtlasDefinition =
+            from_str(from_utf8(&bytes)?).chain_err(|| "Failed to parse texture atlas")?;
+        for (name, region) in &definition.regions {
+            if region.width == 0 || region.height == 0 {
+                bail!("Texture atlas region {:?} is empty", name);
+            }
+        }
+        Ok(TextureAtlasData(definition.build(texture)))
+    }
+}
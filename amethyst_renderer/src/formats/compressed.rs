@@ -0,0 +1,449 @@
+//! DDS and KTX texture containers.
+
+use amethyst_assets::{Result, SimpleFormat};
+use gfx::format::{ChannelType, SurfaceType};
+
+use super::{TextureData, TextureMetadata};
+use tex::{mip_level_size, Texture};
+
+/// Texture data read from a container, with all of its mip levels.
+///
+/// The data may be block compressed, it's uploaded as is.
+#[derive(Clone, Debug)]
+pub struct CompressedData {
+    /// Surface type of the data.
+    pub format: SurfaceType,
+    /// Channel type, `Srgb` for color data stored in sRGB.
+    pub channel: ChannelType,
+    /// Width of the first level in pixels.
+    pub width: u16,
+    /// Height of the first level in pixels.
+    pub height: u16,
+    /// Number of mip levels.
+    pub levels: u8,
+    /// Whether the data holds the six faces of a cubemap.
+    pub cube: bool,
+    /// Every mip level of each face one after another, starting with the
+    /// largest level of the first face.
+    pub data: Vec<u8>,
+}
+
+impl CompressedData {
+    fn check(self) -> Result<Self> {
+        let faces = if self.cube { 6 } else { 1 };
+        let expected = faces * (0..self.levels)
+            .map(|level| mip_level_size(self.format, self.width, self.height, level))
+            .sum::<usize>();
+        if self.data.len() < expected {
+            bail!(
+                "Texture data is truncated (expected: {} bytes, got: {})",
+                expected,
+                self.data.len()
+            );
+        }
+        Ok(self)
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize, big_endian: bool) -> Result<u32> {
+    let b = match offset.checked_add(4) {
+        Some(end) if end <= bytes.len() => &bytes[offset..end],
+        _ => bail!("Unexpected end of texture header"),
+    };
+    let (b0, b1, b2, b3) = (b[0] as u32, b[1] as u32, b[2] as u32, b[3] as u32);
+    Ok(if big_endian {
+        b0 << 24 | b1 << 16 | b2 << 8 | b3
+    } else {
+        b3 << 24 | b2 << 16 | b1 << 8 | b0
+    })
+}
+
+fn check_size(width: u32, height: u32) -> Result<(u16, u16)> {
+    if width == 0 || height == 0 || width > u16::max_value() as u32
+        || height > u16::max_value() as u32
+    {
+        bail!("Unsupported texture size ({}, {})", width, height);
+    }
+    Ok((width as u16, height as u16))
+}
+
+/// Checks the mip level count against the size of the first level, so every
+/// level is at least 1 by 1 pixel.
+fn check_levels(levels: u32, width: u16, height: u16, container: &str) -> Result<u8> {
+    let max_levels = 32 - (width.max(height) as u32).leading_zeros();
+    if levels > max_levels {
+        bail!("Too many mip levels in {} file: {}", container, levels);
+    }
+    Ok(levels as u8)
+}
+
+fn unsupported_block_format(name: &str) -> Result<(SurfaceType, ChannelType)> {
+    bail!(
+        "{} compressed textures are not supported by the graphics backend, \
+         only BC1 (DXT1) and BC3 (DXT5) are",
+        name
+    )
+}
+
+/// Allows loading of DirectDraw Surface files.
+///
+/// Supports BC1 (DXT1) and BC3 (DXT5) compressed data and uncompressed
+/// 32 bit RGBA or BGRA data, with their mip levels and cubemap faces.
+#[derive(Clone)]
+pub struct DdsFormat;
+
+impl DdsFormat {
+    /// Load DDS from memory buffer
+    pub fn from_data(&self, data: Vec<u8>, options: TextureMetadata) -> Result<TextureData> {
+        const HEADER_END: usize = 128;
+        const DDPF_FOURCC: u32 = 0x4;
+        const DDPF_RGB: u32 = 0x40;
+        const DDSCAPS2_CUBEMAP_ALL_FACES: u32 = 0xFE00;
+
+        if data.len() < HEADER_END || &data[0..4] != b"DDS " {
+            bail!("Not a DDS file");
+        }
+        let height = read_u32(&data, 12, false)?;
+        let width = read_u32(&data, 16, false)?;
+        let levels = read_u32(&data, 28, false)?.max(1);
+        let pf_flags = read_u32(&data, 80, false)?;
+        let four_cc = &data[84..88];
+        let caps2 = read_u32(&data, 112, false)?;
+
+        let mut start = HEADER_END;
+        let (format, channel) = if pf_flags & DDPF_FOURCC != 0 {
+            match four_cc {
+                b"DXT1" => (SurfaceType::BC1_R8_G8_B8, ChannelType::Unorm),
+                b"DXT5" => (SurfaceType::BC3_R8_G8_B8_A8, ChannelType::Unorm),
+                b"DXT2" | b"DXT3" => unsupported_block_format("BC2")?,
+                b"DXT4" => unsupported_block_format("BC3 premultiplied")?,
+                b"ATI1" | b"BC4U" | b"BC4S" => unsupported_block_format("BC4")?,
+                b"ATI2" | b"BC5U" | b"BC5S" => unsupported_block_format("BC5")?,
+                b"DX10" => {
+                    start += 20;
+                    match read_u32(&data, HEADER_END, false)? {
+                        71 => (SurfaceType::BC1_R8_G8_B8, ChannelType::Unorm),
+                        72 => (SurfaceType::BC1_R8_G8_B8, ChannelType::Srgb),
+                        77 => (SurfaceType::BC3_R8_G8_B8_A8, ChannelType::Unorm),
+                        78 => (SurfaceType::BC3_R8_G8_B8_A8, ChannelType::Srgb),
+                        28 => (SurfaceType::R8_G8_B8_A8, ChannelType::Unorm),
+                        29 => (SurfaceType::R8_G8_B8_A8, ChannelType::Srgb),
+                        87 => (SurfaceType::B8_G8_R8_A8, ChannelType::Unorm),
+                        91 => (SurfaceType::B8_G8_R8_A8, ChannelType::Srgb),
+                        74...76 => unsupported_block_format("BC2")?,
+                        79...84 => unsupported_block_format("BC4 and BC5")?,
+                        94...96 => unsupported_block_format("BC6H")?,
+                        97...99 => unsupported_block_format("BC7")?,
+                        other => bail!("Unsupported DXGI format {} in DDS file", other),
+                    }
+                }
+                _ => bail!(
+                    "Unsupported DDS format {:?}",
+                    String::from_utf8_lossy(four_cc)
+                ),
+            }
+        } else if pf_flags & DDPF_RGB != 0 && read_u32(&data, 88, false)? == 32 {
+            match read_u32(&data, 92, false)? {
+                0x0000_00FF => (SurfaceType::R8_G8_B8_A8, ChannelType::Unorm),
+                0x00FF_0000 => (SurfaceType::B8_G8_R8_A8, ChannelType::Unorm),
+                mask => bail!("Unsupported DDS red channel mask {:#x}", mask),
+            }
+        } else {
+            bail!("Unsupported DDS pixel format");
+        };
+
+        let cube = caps2 & DDSCAPS2_CUBEMAP_ALL_FACES == DDSCAPS2_CUBEMAP_ALL_FACES;
+        let (width, height) = check_size(width, height)?;
+        let levels = check_levels(levels, width, height, "DDS")?;
+        if data.len() < start {
+            bail!("Unexpected end of texture header");
+        }
+
+        let compressed = CompressedData {
+            format,
+            channel,
+            width,
+            height,
+            levels,
+            cube,
+            data: data[start..].to_vec(),
+        }.check()?;
+        Ok(TextureData::Compressed(compressed, options))
+    }
+}
+
+impl SimpleFormat<Texture> for DdsFormat {
+    const NAME: &'static str = "DDS";
+
+    type Options = TextureMetadata;
+
+    fn import(&self, bytes: Vec<u8>, options: TextureMetadata) -> Result<TextureData> {
+        self.from_data(bytes, options)
+    }
+}
+
+/// Allows loading of Khronos KTX (version 1) files.
+///
+/// Supports BC1 (DXT1) and BC3 (DXT5) compressed data and uncompressed RGBA8
+/// data, with their mip levels and cubemap faces.
+#[derive(Clone)]
+pub struct KtxFormat;
+
+impl KtxFormat {
+    /// Load KTX from memory buffer
+    pub fn from_data(&self, data: Vec<u8>, options: TextureMetadata) -> Result<TextureData> {
+        const IDENTIFIER: &[u8] = b"\xABKTX 11\xBB\r\n\x1A\n";
+        const HEADER_END: usize = 64;
+
+        if data.len() < HEADER_END || &data[0..12] != IDENTIFIER {
+            bail!("Not a KTX file");
+        }
+        let big_endian = match read_u32(&data, 12, false)? {
+            0x0403_0201 => false,
+            0x0102_0304 => true,
+            _ => bail!("Invalid KTX endianness"),
+        };
+        let field = |i: usize| read_u32(&data, 16 + i * 4, big_endian);
+        let internal_format = field(3)?;
+        let width = field(5)?;
+        let height = field(6)?.max(1);
+        let depth = field(7)?;
+        let array_elements = field(8)?;
+        let faces = field(9)?;
+        let levels = field(10)?.max(1);
+        let key_value_bytes = field(11)? as usize;
+
+        let (format, channel) = match internal_format {
+            0x83F0 | 0x83F1 => (SurfaceType::BC1_R8_G8_B8, ChannelType::Unorm),
+            0x8C4C | 0x8C4D => (SurfaceType::BC1_R8_G8_B8, ChannelType::Srgb),
+            0x83F3 => (SurfaceType::BC3_R8_G8_B8_A8, ChannelType::Unorm),
+            0x8C4F => (SurfaceType::BC3_R8_G8_B8_A8, ChannelType::Srgb),
+            0x8058 => (SurfaceType::R8_G8_B8_A8, ChannelType::Unorm),
+            0x8C43 => (SurfaceType::R8_G8_B8_A8, ChannelType::Srgb),
+            0x83F2 | 0x8C4E => unsupported_block_format("BC2")?,
+            0x8DBB...0x8DBE => unsupported_block_format("BC4 and BC5")?,
+            0x8E8E | 0x8E8F => unsupported_block_format("BC6H")?,
+            0x8E8C | 0x8E8D => unsupported_block_format("BC7")?,
+            other => bail!("Unsupported KTX internal format {:#x}", other),
+        };
+        if depth > 1 || array_elements > 0 {
+            bail!("3D and array textures are not supported");
+        }
+        let cube = match faces {
+            1 => false,
+            6 => true,
+            _ => bail!("Invalid KTX face count {}", faces),
+        };
+        let (width, height) = check_size(width, height)?;
+        let levels = check_levels(levels, width, height, "KTX")?;
+
+        // KTX stores the faces of each level together, they are reordered to
+        // have the levels of each face together.
+        let faces = faces as usize;
+        let mut per_face = vec![Vec::new(); faces];
+        let mut offset = match HEADER_END.checked_add(key_value_bytes) {
+            Some(offset) => offset,
+            None => bail!("Invalid KTX key/value data size {}", key_value_bytes),
+        };
+        for level in 0..levels {
+            let image_size = read_u32(&data, offset, big_endian)? as usize;
+            offset += 4;
+            let face_size = mip_level_size(format, width, height, level);
+            if !cube && image_size < face_size {
+                bail!("KTX image size doesn't match its format");
+            }
+            // Cubemap faces are padded to 4 bytes, other textures have their
+            // whole image padded.
+            let stride = if cube { face_size } else { image_size };
+            for face in per_face.iter_mut() {
+                let end = match offset.checked_add(face_size) {
+                    Some(end) if end <= data.len() => end,
+                    _ => bail!("KTX texture data is truncated"),
+                };
+                face.extend_from_slice(&data[offset..end]);
+                offset = match stride
+                    .checked_add(3)
+                    .and_then(|stride| offset.checked_add(stride & !3))
+                {
+                    Some(offset) => offset,
+                    None => bail!("KTX texture data is truncated"),
+                };
+            }
+        }
+
+        let compressed = CompressedData {
+            format,
+            channel,
+            width,
+            height,
+            levels,
+            cube,
+            data: per_face.into_iter().flat_map(|face| face).collect(),
+        }.check()?;
+        Ok(TextureData::Compressed(compressed, options))
+    }
+}
+
+impl SimpleFormat<Texture> for KtxFormat {
+    const NAME: &'static str = "KTX";
+
+    type Options = TextureMetadata;
+
+    fn import(&self, bytes: Vec<u8>, options: TextureMetadata) -> Result<TextureData> {
+        self.from_data(bytes, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompressedData, DdsFormat, KtxFormat};
+    use formats::{TextureData, TextureMetadata};
+    use gfx::format::{ChannelType, SurfaceType};
+
+    fn put_u32(bytes: &mut [u8], offset: usize, value: u32) {
+        for i in 0..4 {
+            bytes[offset + i] = (value >> (i * 8)) as u8;
+        }
+    }
+
+    fn dds(width: u32, height: u32, levels: u32, four_cc: Option<&[u8]>, data: usize) -> Vec<u8> {
+        let mut bytes = vec![0; 128 + data];
+        bytes[0..4].copy_from_slice(b"DDS ");
+        put_u32(&mut bytes, 4, 124);
+        put_u32(&mut bytes, 12, height);
+        put_u32(&mut bytes, 16, width);
+        put_u32(&mut bytes, 28, levels);
+        match four_cc {
+            Some(four_cc) => {
+                put_u32(&mut bytes, 80, 0x4);
+                bytes[84..88].copy_from_slice(four_cc);
+            }
+            None => {
+                put_u32(&mut bytes, 80, 0x40);
+                put_u32(&mut bytes, 88, 32);
+                put_u32(&mut bytes, 92, 0xFF);
+            }
+        }
+        bytes
+    }
+
+    fn ktx(width: u32, height: u32, internal_format: u32, images: &[usize]) -> Vec<u8> {
+        let mut bytes = vec![0; 64];
+        bytes[0..12].copy_from_slice(b"\xABKTX 11\xBB\r\n\x1A\n");
+        put_u32(&mut bytes, 12, 0x0403_0201);
+        put_u32(&mut bytes, 28, internal_format);
+        put_u32(&mut bytes, 36, width);
+        put_u32(&mut bytes, 40, height);
+        put_u32(&mut bytes, 52, 1);
+        put_u32(&mut bytes, 56, images.len() as u32);
+        for &size in images {
+            let start = bytes.len();
+            bytes.resize(start + 4 + size, 0);
+            put_u32(&mut bytes, start, size as u32);
+        }
+        bytes
+    }
+
+    fn load_dds(bytes: Vec<u8>) -> Option<CompressedData> {
+        match DdsFormat.from_data(bytes, TextureMetadata::default()) {
+            Ok(TextureData::Compressed(data, _)) => Some(data),
+            Ok(_) => panic!("DDS data should be loaded as compressed data"),
+            Err(_) => None,
+        }
+    }
+
+    fn load_ktx(bytes: Vec<u8>) -> Option<CompressedData> {
+        match KtxFormat.from_data(bytes, TextureMetadata::default()) {
+            Ok(TextureData::Compressed(data, _)) => Some(data),
+            Ok(_) => panic!("KTX data should be loaded as compressed data"),
+            Err(_) => None,
+        }
+    }
+
+    #[test]
+    fn dds_bc1() {
+        let data = load_dds(dds(8, 4, 2, Some(b"DXT1"), 16 + 8)).unwrap();
+        assert_eq!(data.format, SurfaceType::BC1_R8_G8_B8);
+        assert_eq!(data.channel, ChannelType::Unorm);
+        assert_eq!((data.width, data.height, data.levels), (8, 4, 2));
+        assert!(!data.cube);
+        assert_eq!(data.data.len(), 24);
+    }
+
+    #[test]
+    fn dds_rgba8() {
+        let data = load_dds(dds(4, 4, 3, None, 64 + 16 + 4)).unwrap();
+        assert_eq!(data.format, SurfaceType::R8_G8_B8_A8);
+        assert_eq!((data.width, data.height, data.levels), (4, 4, 3));
+        assert_eq!(data.data.len(), 84);
+    }
+
+    #[test]
+    fn dds_wrong_magic() {
+        let mut bytes = dds(4, 4, 1, Some(b"DXT1"), 8);
+        bytes[0..4].copy_from_slice(b"KTX ");
+        assert!(load_dds(bytes).is_none());
+    }
+
+    #[test]
+    fn dds_truncated() {
+        let mut bytes = dds(4, 4, 1, Some(b"DXT1"), 8);
+        bytes.truncate(100);
+        assert!(load_dds(bytes).is_none());
+        assert!(load_dds(dds(8, 8, 2, Some(b"DXT1"), 32)).is_none());
+        assert!(load_dds(dds(4, 4, 1, Some(b"DX10"), 0)).is_none());
+    }
+
+    #[test]
+    fn dds_too_many_levels() {
+        assert!(load_dds(dds(4, 4, 4, None, 64 + 16 + 4 + 4)).is_none());
+        assert!(load_dds(dds(4, 4, 200, None, 1024)).is_none());
+    }
+
+    #[test]
+    fn ktx_bc1() {
+        let data = load_ktx(ktx(8, 4, 0x83F1, &[16, 8])).unwrap();
+        assert_eq!(data.format, SurfaceType::BC1_R8_G8_B8);
+        assert_eq!((data.width, data.height, data.levels), (8, 4, 2));
+        assert_eq!(data.data.len(), 24);
+    }
+
+    #[test]
+    fn ktx_rgba8() {
+        let mut bytes = ktx(2, 2, 0x8C43, &[16, 4]);
+        bytes[68] = 1;
+        let data = load_ktx(bytes).unwrap();
+        assert_eq!(data.format, SurfaceType::R8_G8_B8_A8);
+        assert_eq!(data.channel, ChannelType::Srgb);
+        assert_eq!((data.width, data.height, data.levels), (2, 2, 2));
+        assert_eq!(data.data.len(), 20);
+        assert_eq!(data.data[0], 1);
+    }
+
+    #[test]
+    fn ktx_wrong_magic() {
+        let mut bytes = ktx(4, 4, 0x83F1, &[8]);
+        bytes[1] = b'D';
+        assert!(load_ktx(bytes).is_none());
+    }
+
+    #[test]
+    fn ktx_truncated() {
+        let mut bytes = ktx(4, 4, 0x83F1, &[8]);
+        bytes.truncate(70);
+        assert!(load_ktx(bytes).is_none());
+        let mut bytes = ktx(4, 4, 0x83F1, &[8]);
+        bytes.truncate(50);
+        assert!(load_ktx(bytes).is_none());
+    }
+
+    #[test]
+    fn ktx_oversized_fields() {
+        let mut image_size = ktx(4, 4, 0x83F1, &[8, 8]);
+        put_u32(&mut image_size, 64, u32::max_value());
+        assert!(load_ktx(image_size).is_none());
+        let mut key_values = ktx(4, 4, 0x83F1, &[8]);
+        put_u32(&mut key_values, 60, u32::max_value());
+        assert!(load_ktx(key_values).is_none());
+    }
+}
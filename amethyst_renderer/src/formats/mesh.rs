@@ -1,7 +1,7 @@
 use std::fmt::Debug;
 
-use amethyst_assets::{Asset, Error, Result, ResultExt, SimpleFormat};
-use amethyst_core::cgmath::{InnerSpace, Vector3};
+use amethyst_assets::{Asset, Error, MetadataOptions, Result, ResultExt, SimpleFormat};
+use amethyst_core::cgmath::{ElementWise, InnerSpace, Vector3};
 use specs::VecStorage;
 use wavefront_obj::obj::{parse, Normal, NormalIndex, ObjSet, Object, Primitive, TVertex,
                          TextureIndex, Vertex, VertexIndex};
@@ -68,28 +68,120 @@ impl Asset for Mesh {
     type HandleStorage = VecStorage<MeshHandle>;
 }
 
+/// Mesh metadata, used while loading
+#[derive(Clone, Debug, Default)]
+pub struct MeshMetadata {
+    /// Scale of the mesh along each axis, `None` to keep it as it is in the file
+    pub scale: Option<[f32; 3]>,
+}
+
+impl MeshMetadata {
+    /// Scales the mesh along each axis
+    pub fn with_scale(mut self, scale: [f32; 3]) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+}
+
+/// Overrides of the `MeshMetadata` of a mesh, read from its metadata file like
+/// `mesh.obj.meta.ron` by `Loader::load_with_metadata`.
+///
+/// ```ron
+/// (
+///     scale: Some((0.01, 0.01, 0.01)),
+/// )
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MeshMetadataOverrides {
+    /// Scale of the mesh along each axis
+    pub scale: Option<[f32; 3]>,
+}
+
+impl MetadataOptions for MeshMetadata {
+    type Overrides = MeshMetadataOverrides;
+
+    fn merge(mut self, overrides: MeshMetadataOverrides) -> Self {
+        if let Some(scale) = overrides.scale {
+            self.scale = Some(scale);
+        }
+        self
+    }
+}
+
 /// Allows loading from Wavefront files
 /// see: https://en.wikipedia.org/wiki/Wavefront_.obj_file
+///
+/// All the objects of the file are merged into a single mesh, their
+/// materials can be loaded with `MtlFormat`.
 #[derive(Clone)]
 pub struct ObjFormat;
 
 impl SimpleFormat<Mesh> for ObjFormat {
     const NAME: &'static str = "WAVEFRONT_OBJ";
 
-    type Options = ();
+    type Options = MeshMetadata;
 
-    fn import(&self, bytes: Vec<u8>, _: ()) -> Result<MeshData> {
-        String::from_utf8(bytes)
-            .map_err(Into::into)
-            .and_then(|string| {
-                parse(string)
-                    .map_err(|e| Error::from(format!("In line {}: {:?}", e.line_number, e.message)))
-                    .chain_err(|| "Failed to parse OBJ")
-            })
-            .map(|set| from_data(set).into())
+    fn import(&self, bytes: Vec<u8>, metadata: MeshMetadata) -> Result<MeshData> {
+        parse_obj(bytes).map(|set| scale(from_data(set, None), metadata.scale).into())
+    }
+}
+
+/// Allows loading the faces of Wavefront files drawn with a single material,
+/// selected by the `usemtl` statements, so each material of a model can be
+/// drawn with its own `Material`.
+///
+/// The options are the name of the material, as found in the MTL file.
+#[derive(Clone)]
+pub struct ObjMaterialFormat;
+
+impl SimpleFormat<Mesh> for ObjMaterialFormat {
+    const NAME: &'static str = "WAVEFRONT_OBJ_MATERIAL";
+
+    type Options = String;
+
+    fn import(&self, bytes: Vec<u8>, material: String) -> Result<MeshData> {
+        parse_obj(bytes).map(|set| from_data(set, Some(&material)).into())
     }
 }
 
+/// Scales the vertices, the normals following the scaled surface.
+///
+/// The missing normals, which are zero, stay zero.
+fn scale(vertices: Vec<PosNormTex>, scale: Option<[f32; 3]>) -> Vec<PosNormTex> {
+    let scale = match scale {
+        Some(scale) => Vector3::from(scale),
+        None => return vertices,
+    };
+    vertices
+        .into_iter()
+        .map(|vertex| {
+            let normal = Vector3::from(vertex.normal).div_element_wise(scale);
+            PosNormTex {
+                position: Vector3::from(vertex.position)
+                    .mul_element_wise(scale)
+                    .into(),
+                normal: if normal.magnitude2() > 0.0 {
+                    normal.normalize().into()
+                } else {
+                    vertex.normal
+                },
+                tex_coord: vertex.tex_coord,
+            }
+        })
+        .collect()
+}
+
+fn parse_obj(bytes: Vec<u8>) -> Result<ObjSet> {
+    String::from_utf8(bytes)
+        .map_err(Into::into)
+        .and_then(|string| {
+            parse(string)
+                .map_err(|e| Error::from(format!("In line {}: {:?}", e.line_number, e.message)))
+                .chain_err(|| "Failed to parse OBJ")
+        })
+}
+
 fn convert(
     object: &Object,
     vi: VertexIndex,
@@ -125,19 +217,27 @@ fn convert_primitive(object: &Object, prim: &Primitive) -> Option<[PosNormTex; 3
     }
 }
 
-fn from_data(obj_set: ObjSet) -> Vec<PosNormTex> {
+fn from_data(obj_set: ObjSet, material: Option<&str>) -> Vec<PosNormTex> {
     // Takes a list of objects that contain geometries that contain shapes that contain
     // vertex/texture/normal indices into the main list of vertices, and converts to a
     // flat vec of `PosNormTex` objects.
     // TODO: Doesn't differentiate between objects in a `*.obj` file, treats
     // them all as a single mesh.
     let vertices = obj_set.objects.iter().flat_map(|object| {
-        object.geometry.iter().flat_map(move |geometry| {
-            geometry
-                .shapes
-                .iter()
-                .filter_map(move |s| convert_primitive(object, &s.primitive))
-        })
+        object
+            .geometry
+            .iter()
+            .filter(move |geometry| {
+                material.map_or(true, |material| {
+                    geometry.material_name.as_ref().map(|name| &name[..]) == Some(material)
+                })
+            })
+            .flat_map(move |geometry| {
+                geometry
+                    .shapes
+                    .iter()
+                    .filter_map(move |s| convert_primitive(object, &s.primitive))
+            })
     });
 
     let mut result = Vec::new();
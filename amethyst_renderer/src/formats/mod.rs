@@ -1,8 +1,20 @@
 //! Provides texture formats
 //!
 
+pub use self::atlas::*;
+pub use self::compressed::*;
 pub use self::mesh::*;
+pub use self::mtl::*;
+pub use self::shader::*;
+pub use self::sprite::*;
+pub use self::terrain::*;
 pub use self::texture::*;
 
+mod atlas;
+mod compressed;
 mod mesh;
+mod mtl;
+mod shader;
+mod sprite;
+mod terrain;
 mod texture;
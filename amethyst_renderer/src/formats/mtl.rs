@@ -0,0 +1,203 @@
+use std::path::Path;
+use std::str::from_utf8;
+use std::sync::Arc;
+
+use amethyst_assets::{ErrorKind, Format, FormatValue, Reload, Result, ResultExt, SingleFile,
+                      Source};
+
+use mtl::{LibraryMaterial, MaterialLibrary, MaterialLibraryData};
+
+use super::{ImageData, TextureData, TextureMetadata};
+
+/// Allows loading the materials of Wavefront MTL files, usually referenced by
+/// the `mtllib` statement of an OBJ file.
+/// see: https://en.wikipedia.org/wiki/Wavefront_.obj_file#Material_template_library
+///
+/// The diffuse (`Kd`, `d` and `map_Kd`), emissive (`Ke` and `map_Ke`) and
+/// normal (`norm`, `bump` and `map_Bump`) statements are imported, the
+/// others are ignored. The maps are PNG, JPEG or BMP images loaded from the
+/// same source, relative to the MTL file.
+#[derive(Clone)]
+pub struct MtlFormat;
+
+impl Format<MaterialLibrary> for MtlFormat {
+    const NAME: &'static str = "WAVEFRONT_MTL";
+
+    type Options = ();
+
+    fn import(
+        &self,
+        name: String,
+        source: Arc<Source>,
+        _: (),
+        create_reload: bool,
+    ) -> Result<FormatValue<MaterialLibrary>> {
+        let (bytes, modified) = source
+            .load_with_metadata(&name)
+            .chain_err(|| ErrorKind::Source)?;
+        let library =
+            parse(from_utf8(&bytes)?, &name, &*source).chain_err(|| "Failed to parse MTL")?;
+        let data = MaterialLibraryData(library);
+        if create_reload {
+            let reload = SingleFile::new(self.clone(), modified, (), name, source);
+            Ok(FormatValue {
+                data,
+                reload: Some(Box::new(reload) as Box<Reload<MaterialLibrary>>),
+            })
+        } else {
+            Ok(FormatValue::data(data))
+        }
+    }
+}
+
+/// A material while it's being parsed.
+struct Definition {
+    name: String,
+    diffuse: [f32; 3],
+    alpha: f32,
+    diffuse_map: Option<String>,
+    emissive: Option<[f32; 3]>,
+    emissive_map: Option<String>,
+    normal_map: Option<String>,
+}
+
+impl Definition {
+    fn new(name: String) -> Self {
+        Definition {
+            name,
+            diffuse: [1.0; 3],
+            alpha: 1.0,
+            diffuse_map: None,
+            emissive: None,
+            emissive_map: None,
+            normal_map: None,
+        }
+    }
+
+    fn build(self, name: &str, source: &Source) -> Result<LibraryMaterial> {
+        let albedo = match self.diffuse_map {
+            Some(ref map) => load_map(map, name, source, TextureMetadata::srgb())?,
+            None => {
+                let diffuse = self.diffuse;
+                TextureData::color([diffuse[0], diffuse[1], diffuse[2], self.alpha])
+            }
+        };
+        let emission = match (self.emissive_map, self.emissive) {
+            (Some(ref map), _) => Some(load_map(map, name, source, TextureMetadata::srgb())?),
+            (None, Some(color)) => Some(TextureData::color([color[0], color[1], color[2], 1.0])),
+            (None, None) => None,
+        };
+        let normal = match self.normal_map {
+            Some(ref map) => Some(load_map(map, name, source, TextureMetadata::default())?),
+            None => None,
+        };
+        Ok(LibraryMaterial {
+            name: self.name,
+            albedo,
+            emission,
+            normal,
+        })
+    }
+}
+
+fn parse(text: &str, name: &str, source: &Source) -> Result<MaterialLibrary> {
+    let mut definitions = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let arguments = words.collect::<Vec<_>>();
+        let line_error = || format!("In line {}: invalid `{}` statement", number + 1, keyword);
+        if keyword == "newmtl" {
+            if arguments.is_empty() {
+                bail!(line_error());
+            }
+            definitions.push(Definition::new(arguments.join(" ")));
+            continue;
+        }
+        let definition = match definitions.last_mut() {
+            Some(definition) => definition,
+            None => bail!("In line {}: statement before the first `newmtl`", number + 1),
+        };
+        let arguments = &arguments;
+        match keyword {
+            "Kd" => definition.diffuse = parse_color(arguments).chain_err(&line_error)?,
+            "Ke" => definition.emissive = Some(parse_color(arguments).chain_err(&line_error)?),
+            "d" => definition.alpha = parse_float(arguments).chain_err(&line_error)?,
+            "Tr" => definition.alpha = 1.0 - parse_float(arguments).chain_err(&line_error)?,
+            "map_Kd" => {
+                definition.diffuse_map = Some(parse_map(arguments).chain_err(&line_error)?)
+            }
+            "map_Ke" => {
+                definition.emissive_map = Some(parse_map(arguments).chain_err(&line_error)?)
+            }
+            "norm" | "bump" | "map_Bump" | "map_bump" => {
+                definition.normal_map = Some(parse_map(arguments).chain_err(&line_error)?)
+            }
+            _ => {}
+        }
+    }
+    let materials = definitions
+        .into_iter()
+        .map(|definition| definition.build(name, source))
+        .collect::<Result<_>>()?;
+    Ok(MaterialLibrary { materials })
+}
+
+/// Parses a number, skipping the options before it.
+fn parse_float(arguments: &[&str]) -> Result<f32> {
+    match arguments.last() {
+        Some(argument) => parse_number(argument),
+        None => bail!("Missing value"),
+    }
+}
+
+fn parse_number(argument: &str) -> Result<f32> {
+    argument
+        .parse()
+        .chain_err(|| format!("Invalid number {:?}", argument))
+}
+
+/// Parses an RGB color, a single value being used for all the channels.
+fn parse_color(arguments: &[&str]) -> Result<[f32; 3]> {
+    let values = arguments
+        .iter()
+        .map(|argument| parse_number(argument))
+        .collect::<Result<Vec<_>>>()?;
+    match values.len() {
+        1 => Ok([values[0]; 3]),
+        3 => Ok([values[0], values[1], values[2]]),
+        _ => bail!("Expected 1 or 3 values, found {}", values.len()),
+    }
+}
+
+/// Returns the file of a map statement, skipping its options.
+fn parse_map(arguments: &[&str]) -> Result<String> {
+    match arguments.last() {
+        Some(file) => Ok(file.replace('\\', "/")),
+        None => bail!("Missing file"),
+    }
+}
+
+/// Loads a map, relative to the MTL file.
+fn load_map(
+    map: &str,
+    name: &str,
+    source: &Source,
+    options: TextureMetadata,
+) -> Result<TextureData> {
+    let path = match Path::new(name).parent() {
+        Some(parent) => parent.join(map),
+        None => Path::new(map).to_owned(),
+    };
+    let path = path.to_string_lossy();
+    let bytes = source
+        .load(&path)
+        .chain_err(|| format!("Failed to load map {:?}", path))?;
+    let image =
+        ImageData::from_data(bytes).chain_err(|| format!("Failed to decode map {:?}", path))?;
+    Ok(TextureData::Image(image, options))
+}
@@ -0,0 +1,17 @@
+use amethyst_assets::{Result, SimpleFormat};
+
+use shader::{Shader, ShaderData};
+
+/// Format for loading GLSL shader sources.
+#[derive(Clone)]
+pub struct GlslFormat;
+
+impl SimpleFormat<Shader> for GlslFormat {
+    const NAME: &'static str = "GLSL";
+
+    type Options = ();
+
+    fn import(&self, bytes: Vec<u8>, _: ()) -> Result<ShaderData> {
+        Ok(ShaderData(bytes))
+    }
+}
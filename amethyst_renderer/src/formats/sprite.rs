@@ -0,0 +1,169 @@
+use std::str::from_utf8;
+
+use amethyst_assets::{Result, ResultExt, SimpleFormat};
+use ron::de::from_str;
+use serde_json;
+
+use sprite::{Sprite, SpriteSheet, SpriteSheetData, TextureCoordinates};
+use tex::TextureHandle;
+
+/// Definition of a single sprite in a `SpriteSheetFormat` file.
+///
+/// All values are in pixels, with the origin at the top left corner of the
+/// texture.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SpriteDefinition {
+    /// Left edge of the sprite.
+    pub x: f32,
+    /// Top edge of the sprite.
+    pub y: f32,
+    /// Width of the sprite.
+    pub width: f32,
+    /// Height of the sprite.
+    pub height: f32,
+    /// Pivot of the sprite, relative to its bottom left corner.
+    /// Takes precedence over `pivot`.
+    #[serde(default)]
+    pub offsets: Option<[f32; 2]>,
+    /// Pivot of the sprite, normalized to the size of the sprite with
+    /// `[0.0, 0.0]` being the top left corner. Defaults to the center.
+    #[serde(default)]
+    pub pivot: Option<[f32; 2]>,
+}
+
+/// Contents of a `SpriteSheetFormat` file.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SpriteSheetDefinition {
+    /// Width of the texture in pixels.
+    pub texture_width: f32,
+    /// Height of the texture in pixels.
+    pub texture_height: f32,
+    /// The sprites of the sheet.
+    pub sprites: Vec<SpriteDefinition>,
+}
+
+impl SpriteSheetDefinition {
+    /// Creates a sprite sheet using the given texture.
+    ///
+    /// Sprites are sized in pixels, so one world unit maps to one texel.
+    pub fn build(&self, texture: TextureHandle) -> SpriteSheet {
+        let sprites = self.sprites
+            .iter()
+            .map(|def| {
+                let offsets = def.offsets.unwrap_or_else(|| {
+                    let pivot = def.pivot.unwrap_or([0.5, 0.5]);
+                    [pivot[0] * def.width, (1.0 - pivot[1]) * def.height]
+                });
+                Sprite {
+                    width: def.width,
+                    height: def.height,
+                    offsets,
+                    tex_coords: TextureCoordinates {
+                        left: def.x / self.texture_width,
+                        right: (def.x + def.width) / self.texture_width,
+                        bottom: 1.0 - (def.y + def.height) / self.texture_height,
+                        top: 1.0 - def.y / self.texture_height,
+                    },
+                }
+            })
+            .collect();
+        SpriteSheet { texture, sprites }
+    }
+}
+
+/// Allows loading sprite sheets from RON files containing a
+/// `SpriteSheetDefinition`.
+///
+/// The options are the handle of the texture the sprites are cut out of.
+#[derive(Clone)]
+pub struct SpriteSheetFormat;
+
+impl SimpleFormat<SpriteSheet> for SpriteSheetFormat {
+    const NAME: &'static str = "SPRITE_SHEET";
+
+    type Options = TextureHandle;
+
+    fn import(&self, bytes: Vec<u8>, texture: TextureHandle) -> Result<SpriteSheetData> {
+        let definition: SpriteSheetDefinition =
+            from_str(from_utf8(&bytes)?).chain_err(|| "Failed to parse sprite sheet")?;
+        Ok(SpriteSheetData(definition.build(texture)))
+    }
+}
+
+#[derive(Deserialize)]
+struct PackerRect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+#[derive(Deserialize)]
+struct PackerPoint {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Deserialize)]
+struct PackerSize {
+    w: f32,
+    h: f32,
+}
+
+#[derive(Deserialize)]
+struct PackerFrame {
+    frame: PackerRect,
+    #[serde(default)]
+    rotated: bool,
+    pivot: Option<PackerPoint>,
+}
+
+#[derive(Deserialize)]
+struct PackerMeta {
+    size: PackerSize,
+}
+
+#[derive(Deserialize)]
+struct PackerSheet {
+    frames: Vec<PackerFrame>,
+    meta: PackerMeta,
+}
+
+/// Allows loading sprite sheets exported by TexturePacker with the
+/// "JSON (Array)" data format.
+///
+/// The options are the handle of the texture the sprites are cut out of.
+/// Rotated frames are not supported.
+#[derive(Clone)]
+pub struct TexturePackerFormat;
+
+impl SimpleFormat<SpriteSheet> for TexturePackerFormat {
+    const NAME: &'static str = "TEXTURE_PACKER_JSON";
+
+    type Options = TextureHandle;
+
+    fn import(&self, bytes: Vec<u8>, texture: TextureHandle) -> Result<SpriteSheetData> {
+        let sheet: PackerSheet =
+            serde_json::from_slice(&bytes).chain_err(|| "Failed to parse TexturePacker file")?;
+        if sheet.frames.iter().any(|frame| frame.rotated) {
+            bail!("Rotated TexturePacker frames are not supported");
+        }
+        let definition = SpriteSheetDefinition {
+            texture_width: sheet.meta.size.w,
+            texture_height: sheet.meta.size.h,
+            sprites: sheet
+                .frames
+                .into_iter()
+                .map(|frame| SpriteDefinition {
+                    x: frame.frame.x,
+                    y: frame.frame.y,
+                    width: frame.frame.w,
+                    height: frame.frame.h,
+                    offsets: None,
+                    pivot: frame.pivot.map(|pivot| [pivot.x, pivot.y]),
+                })
+                .collect(),
+        };
+        Ok(SpriteSheetData(definition.build(texture)))
+    }
+}
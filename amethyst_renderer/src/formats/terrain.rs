@@ -0,0 +1,106 @@
+use amethyst_assets::{MetadataOptions, Result, SimpleFormat};
+
+use super::ImageData;
+use terrain::{Terrain, TerrainData};
+
+/// Terrain metadata, used while loading
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TerrainMetadata {
+    /// Distance between two neighbouring samples
+    pub spacing: f32,
+    /// Height of the white samples, black samples are at zero
+    pub height_scale: f32,
+    /// Number of grid cells on each side of a chunk, a power of two
+    pub chunk_size: u16,
+}
+
+impl Default for TerrainMetadata {
+    fn default() -> Self {
+        TerrainMetadata {
+            spacing: 1.0,
+            height_scale: 32.0,
+            chunk_size: 32,
+        }
+    }
+}
+
+/// Overrides of the `TerrainMetadata` of a heightmap, read from its metadata file like
+/// `heightmap.png.meta.ron` by `Loader::load_with_metadata`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct TerrainMetadataOverrides {
+    /// Distance between two neighbouring samples
+    pub spacing: Option<f32>,
+    /// Height of the white samples
+    pub height_scale: Option<f32>,
+    /// Number of grid cells on each side of a chunk
+    pub chunk_size: Option<u16>,
+}
+
+impl MetadataOptions for TerrainMetadata {
+    type Overrides = TerrainMetadataOverrides;
+
+    fn merge(self, overrides: TerrainMetadataOverrides) -> Self {
+        TerrainMetadata {
+            spacing: overrides.spacing.unwrap_or(self.spacing),
+            height_scale: overrides.height_scale.unwrap_or(self.height_scale),
+            chunk_size: overrides.chunk_size.unwrap_or(self.chunk_size),
+        }
+    }
+}
+
+impl TerrainMetadata {
+    /// Distance between two neighbouring samples
+    pub fn with_spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Height of the white samples
+    pub fn with_height_scale(mut self, height_scale: f32) -> Self {
+        self.height_scale = height_scale;
+        self
+    }
+
+    /// Number of grid cells on each side of a chunk
+    pub fn with_chunk_size(mut self, chunk_size: u16) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+}
+
+/// Allows loading terrains from PNG, JPEG or BMP heightmaps.
+///
+/// The height of each sample is read from the red channel of a pixel.
+#[derive(Clone)]
+pub struct TerrainFormat;
+
+impl TerrainFormat {
+    /// Load heightmap from memory buffer
+    pub fn from_data(&self, data: Vec<u8>, options: TerrainMetadata) -> Result<TerrainData> {
+        let image = ImageData::from_data(data)?.raw;
+        let heights = image
+            .buf
+            .chunks(4)
+            .map(|texel| texel[0] as f32 / 255.0 * options.height_scale)
+            .collect();
+        let terrain = Terrain::new(
+            image.w,
+            image.h,
+            heights,
+            options.spacing,
+            options.chunk_size as usize,
+        )?;
+        Ok(TerrainData(terrain))
+    }
+}
+
+impl SimpleFormat<Terrain> for TerrainFormat {
+    const NAME: &'static str = "TERRAIN";
+
+    type Options = TerrainMetadata;
+
+    fn import(&self, bytes: Vec<u8>, options: TerrainMetadata) -> Result<TerrainData> {
+        self.from_data(bytes, options)
+    }
+}
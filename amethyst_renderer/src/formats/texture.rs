@@ -3,14 +3,17 @@ pub use imagefmt::Error as ImageError;
 use std::io::Cursor;
 
 use Renderer;
-use amethyst_assets::{Result, ResultExt, SimpleFormat};
+use amethyst_assets::{MetadataOptions, Result, ResultExt, SimpleFormat};
 use gfx::format::{ChannelType, SurfaceType};
-use gfx::texture::SamplerInfo;
+use gfx::texture::{FilterMethod, SamplerInfo, WrapMode};
 use gfx::traits::Pod;
 use imagefmt;
 use imagefmt::{ColFmt, Image};
+use stream::StreamingTexture;
 use tex::{Texture, TextureBuilder};
 
+use super::CompressedData;
+
 /// Texture metadata, used while loading
 #[derive(Debug, Clone)]
 pub struct TextureMetadata {
@@ -18,6 +21,8 @@ pub struct TextureMetadata {
     pub sampler: Option<SamplerInfo>,
     /// Mipmapping
     pub mip_levels: Option<u8>,
+    /// Generate the mip levels from the image
+    pub generate_mipmaps: bool,
     /// Texture size
     pub size: Option<(u16, u16)>,
     /// Dynamic texture
@@ -33,6 +38,7 @@ impl Default for TextureMetadata {
         Self {
             sampler: None,
             mip_levels: None,
+            generate_mipmaps: false,
             size: None,
             dynamic: false,
             format: None,
@@ -42,18 +48,58 @@ impl Default for TextureMetadata {
 }
 
 impl TextureMetadata {
+    /// Metadata for color textures stored in sRGB, like most albedo and
+    /// emission maps.
+    ///
+    /// They are converted to linear values when sampled, which keeps the
+    /// lighting correct with the `srgb` feature. Normal, metallic and
+    /// roughness maps hold linear data and should be loaded with the default
+    /// metadata instead.
+    pub fn srgb() -> Self {
+        Self::default().with_channel(ChannelType::Srgb)
+    }
+
     /// Sampler info
     pub fn with_sampler(mut self, info: SamplerInfo) -> Self {
         self.sampler = Some(info);
         self
     }
 
+    /// Sampler filtering, nearest neighbor by default
+    pub fn with_filter(mut self, filter: FilterMethod) -> Self {
+        self.sampler_mut().filter = filter;
+        self
+    }
+
+    /// Anisotropic sampler filtering, with a maximum between 1 and 16
+    pub fn with_anisotropy(self, max: u8) -> Self {
+        self.with_filter(FilterMethod::Anisotropic(max.max(1).min(16)))
+    }
+
+    /// Sampler wrap mode on all axes, clamped by default
+    pub fn with_wrap_mode(mut self, wrap: WrapMode) -> Self {
+        self.sampler_mut().wrap_mode = (wrap, wrap, wrap);
+        self
+    }
+
+    fn sampler_mut(&mut self) -> &mut SamplerInfo {
+        self.sampler
+            .get_or_insert(SamplerInfo::new(FilterMethod::Scale, WrapMode::Clamp))
+    }
+
     /// Mipmapping
     pub fn with_mip_levels(mut self, mip_levels: u8) -> Self {
         self.mip_levels = Some(mip_levels);
         self
     }
 
+    /// Generate the full chain of mip levels from the image, see
+    /// `TextureBuilder::with_generated_mipmaps`
+    pub fn with_generated_mipmaps(mut self) -> Self {
+        self.generate_mipmaps = true;
+        self
+    }
+
     /// Texture size
     pub fn with_size(mut self, width: u16, height: u16) -> Self {
         self.size = Some((width, height));
@@ -79,6 +125,56 @@ impl TextureMetadata {
     }
 }
 
+/// Overrides of the `TextureMetadata` of a texture, read from its metadata file like
+/// `texture.png.meta.ron` by `Loader::load_with_metadata`.
+///
+/// ```ron
+/// (
+///     srgb: Some(true),
+///     filter: Some(Anisotropic(8)),
+///     wrap_mode: Some(Tile),
+/// )
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TextureMetadataOverrides {
+    /// Whether the image holds sRGB colors, like most albedo and emission maps
+    pub srgb: Option<bool>,
+    /// Sampler filtering
+    pub filter: Option<FilterMethod>,
+    /// Sampler wrap mode on all axes
+    pub wrap_mode: Option<WrapMode>,
+    /// Mipmapping
+    pub mip_levels: Option<u8>,
+    /// Generate the mip levels from the image
+    pub generate_mipmaps: Option<bool>,
+}
+
+impl MetadataOptions for TextureMetadata {
+    type Overrides = TextureMetadataOverrides;
+
+    fn merge(mut self, overrides: TextureMetadataOverrides) -> Self {
+        match overrides.srgb {
+            Some(true) => self.channel = Some(ChannelType::Srgb),
+            Some(false) if self.channel == Some(ChannelType::Srgb) => self.channel = None,
+            _ => {}
+        }
+        if let Some(filter) = overrides.filter {
+            self = self.with_filter(filter);
+        }
+        if let Some(wrap_mode) = overrides.wrap_mode {
+            self = self.with_wrap_mode(wrap_mode);
+        }
+        if let Some(mip_levels) = overrides.mip_levels {
+            self.mip_levels = Some(mip_levels);
+        }
+        if let Some(generate_mipmaps) = overrides.generate_mipmaps {
+            self.generate_mipmaps = generate_mipmaps;
+        }
+        self
+    }
+}
+
 /// Texture data for loading
 #[derive(Debug, Clone)]
 pub enum TextureData {
@@ -105,6 +201,24 @@ pub enum TextureData {
 
     /// Byte data
     U64(Vec<u64>, TextureMetadata),
+
+    /// Cubemap from six square images, in the order +X, -X, +Y, -Y, +Z, -Z
+    Cubemap(Vec<ImageData>, TextureMetadata),
+
+    /// Cubemap projected from an equirectangular image.
+    ///
+    /// The data holds four floats per pixel, starting with the top row.
+    /// The image size has to be given through `TextureMetadata::with_size`.
+    Equirectangular(Vec<f32>, TextureMetadata),
+
+    /// Data with a provided mip chain, possibly block compressed, read from
+    /// a DDS or KTX file
+    Compressed(CompressedData, TextureMetadata),
+
+    /// Texture whose pixels are streamed from the CPU, see `StreamingTexture`.
+    ///
+    /// Only the sampler and channel type of the metadata are used.
+    Stream(StreamingTexture, TextureMetadata),
 }
 
 impl From<[f32; 4]> for TextureData {
@@ -126,6 +240,26 @@ pub struct ImageData {
     /// The raw image data.
     pub raw: Image<u8>,
 }
+
+impl ImageData {
+    /// Decodes a PNG, JPEG or BMP image from memory, detecting the format
+    /// from its header.
+    ///
+    /// Useful to build a `TextureData::Cubemap` from six separate images.
+    pub fn from_data(data: Vec<u8>) -> Result<ImageData> {
+        let raw = if data.starts_with(b"\x89PNG") {
+            imagefmt::png::read(&mut Cursor::new(data), ColFmt::RGBA)
+        } else if data.starts_with(b"\xFF\xD8") {
+            imagefmt::jpeg::read(&mut Cursor::new(data), ColFmt::RGBA)
+        } else if data.starts_with(b"BM") {
+            imagefmt::bmp::read(&mut Cursor::new(data), ColFmt::RGBA)
+        } else {
+            bail!("Unknown image format, expected PNG, JPEG or BMP");
+        };
+        raw.map(|raw| ImageData { raw })
+            .chain_err(|| "Image decoding failed")
+    }
+}
 /// Allows loading of jpg or jpeg files.
 #[derive(Clone)]
 pub struct JpgFormat;
@@ -190,6 +324,153 @@ impl SimpleFormat<Texture> for BmpFormat {
     }
 }
 
+/// Arrangement of the six faces of a cubemap in a single image.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum CubemapLayout {
+    /// Faces unfolded in a cross, either horizontal with four faces on the
+    /// middle row (-X, +Z, +X, -Z) or vertical with three (-X, +Z, +X) and -Z
+    /// upside down at the bottom. +Y is above +Z and -Y below it.
+    Cross,
+    /// Square faces next to each other in a row or a column, in the order
+    /// +X, -X, +Y, -Y, +Z, -Z.
+    Strip,
+    /// Panorama with longitude along the width and latitude along the height,
+    /// twice as wide as it is high. Faces are half as wide as the image.
+    Equirectangular,
+}
+
+/// Grid cell of each face of a horizontal cross, and whether it's upside down.
+const HORIZONTAL_CROSS: [(usize, usize, bool); 6] = [
+    (2, 1, false),
+    (0, 1, false),
+    (1, 0, false),
+    (1, 2, false),
+    (1, 1, false),
+    (3, 1, false),
+];
+
+/// Grid cell of each face of a vertical cross, and whether it's upside down.
+const VERTICAL_CROSS: [(usize, usize, bool); 6] = [
+    (2, 1, false),
+    (0, 1, false),
+    (1, 0, false),
+    (1, 2, false),
+    (1, 1, false),
+    (1, 3, true),
+];
+
+/// Allows loading cubemaps from a single PNG, JPEG or BMP image, see
+/// `CubemapLayout`.
+///
+/// For cubemaps made of six separate images, decode them with
+/// `ImageData::from_data` and create a `TextureData::Cubemap`.
+#[derive(Clone, Debug)]
+pub struct CubemapFormat {
+    /// How the faces are laid out in the image.
+    pub layout: CubemapLayout,
+}
+
+impl CubemapFormat {
+    /// Creates a `CubemapFormat` reading images with the given layout.
+    pub fn new(layout: CubemapLayout) -> Self {
+        CubemapFormat { layout }
+    }
+
+    /// Load cubemap from memory buffer
+    pub fn from_data(&self, data: Vec<u8>, options: TextureMetadata) -> Result<TextureData> {
+        let image = ImageData::from_data(data)?.raw;
+        let (w, h) = (image.w, image.h);
+        let faces = match self.layout {
+            CubemapLayout::Cross => {
+                let (size, cells) = if w * 3 == h * 4 {
+                    (w / 4, &HORIZONTAL_CROSS)
+                } else if w * 4 == h * 3 {
+                    (w / 3, &VERTICAL_CROSS)
+                } else {
+                    bail!("Cubemap cross must be 4:3 or 3:4, got {}x{}", w, h);
+                };
+                cells
+                    .iter()
+                    .map(|&(x, y, flip)| copy_face(&image.buf, w, x * size, y * size, size, flip))
+                    .collect()
+            }
+            CubemapLayout::Strip => {
+                let (size, horizontal) = if w == h * 6 {
+                    (h, true)
+                } else if h == w * 6 {
+                    (w, false)
+                } else {
+                    bail!("Cubemap strip must be 6:1 or 1:6, got {}x{}", w, h);
+                };
+                (0..6)
+                    .map(|i| {
+                        let (x, y) = if horizontal {
+                            (i * size, 0)
+                        } else {
+                            (0, i * size)
+                        };
+                        copy_face(&image.buf, w, x, y, size, false)
+                    })
+                    .collect()
+            }
+            CubemapLayout::Equirectangular => {
+                if w == 0 || h == 0 {
+                    bail!("Equirectangular texture can't be empty");
+                }
+                let size = (h / 2).max(1);
+                let data = equirectangular_to_cube(&image.buf, w, h, size);
+                data.chunks(size * size * 4)
+                    .map(|face| ImageData {
+                        raw: Image {
+                            w: size,
+                            h: size,
+                            fmt: ColFmt::RGBA,
+                            buf: face.to_vec(),
+                        },
+                    })
+                    .collect()
+            }
+        };
+        Ok(TextureData::Cubemap(faces, options))
+    }
+}
+
+impl SimpleFormat<Texture> for CubemapFormat {
+    const NAME: &'static str = "CUBEMAP";
+
+    type Options = TextureMetadata;
+
+    fn import(&self, bytes: Vec<u8>, options: TextureMetadata) -> Result<TextureData> {
+        self.from_data(bytes, options)
+    }
+}
+
+/// Copies a square RGBA face out of an image, rotating it by half a turn if
+/// `flip` is set.
+fn copy_face(data: &[u8], w: usize, x: usize, y: usize, size: usize, flip: bool) -> ImageData {
+    let mut buf = Vec::with_capacity(size * size * 4);
+    for row in 0..size {
+        let src_row = if flip { size - 1 - row } else { row };
+        let start = ((y + src_row) * w + x) * 4;
+        let line = &data[start..start + size * 4];
+        if flip {
+            for texel in line.chunks(4).rev() {
+                buf.extend_from_slice(texel);
+            }
+        } else {
+            buf.extend_from_slice(line);
+        }
+    }
+    ImageData {
+        raw: Image {
+            w: size,
+            h: size,
+            fmt: ColFmt::RGBA,
+            buf,
+        },
+    }
+}
+
 /// Create a texture asset.
 pub fn create_texture_asset(data: TextureData, renderer: &mut Renderer) -> Result<Texture> {
     use self::TextureData::*;
@@ -246,6 +527,62 @@ pub fn create_texture_asset(data: TextureData, renderer: &mut Renderer) -> Resul
                 .create_texture(tb)
                 .chain_err(|| "Failed to build texture")
         }
+
+        Cubemap(faces, options) => create_cubemap_asset(faces, options, renderer),
+
+        Compressed(data, options) => {
+            let mut tb = apply_options(
+                TextureBuilder::new(data.data)
+                    .with_format(data.format)
+                    .with_channel_type(data.channel),
+                options,
+            ).with_size(data.width, data.height)
+                .mip_levels(data.levels)
+                .with_mip_data();
+            if data.cube {
+                tb = tb.with_cube_size(data.width);
+            }
+            renderer
+                .create_texture(tb)
+                .chain_err(|| "Failed to build compressed texture")
+        }
+
+        Equirectangular(data, options) => {
+            let (w, h) = options
+                .size
+                .chain_err(|| "Equirectangular texture needs a size")?;
+            if w == 0 || h == 0 {
+                bail!("Equirectangular texture can't be empty");
+            }
+            if data.len() != w as usize * h as usize * 4 {
+                bail!(
+                    "Equirectangular texture data doesn't match its size (expected: {}, got: {})",
+                    w as usize * h as usize * 4,
+                    data.len()
+                );
+            }
+            let size = (h / 2).max(1);
+            let faces = equirectangular_to_cube(&data, w as usize, h as usize, size as usize);
+            let tb = apply_options(
+                TextureBuilder::new(faces)
+                    .with_format(SurfaceType::R32_G32_B32_A32)
+                    .with_channel_type(ChannelType::Float),
+                options,
+            ).with_cube_size(size);
+            renderer
+                .create_texture(tb)
+                .chain_err(|| "Failed to build cubemap texture")
+        }
+
+        Stream(stream, options) => {
+            let sampler = options
+                .sampler
+                .unwrap_or(SamplerInfo::new(FilterMethod::Scale, WrapMode::Clamp));
+            let channel = options.channel.unwrap_or(ChannelType::Unorm);
+            renderer
+                .create_streaming_texture(&stream, sampler, channel)
+                .chain_err(|| "Failed to build streaming texture")
+        }
     }
 }
 
@@ -265,6 +602,9 @@ where
         Some(mip) => tb = tb.mip_levels(mip),
         _ => (),
     }
+    if metadata.generate_mipmaps {
+        tb = tb.with_generated_mipmaps();
+    }
     match metadata.size {
         Some((w, h)) => tb = tb.with_size(w, h),
         _ => (),
@@ -284,20 +624,20 @@ where
     tb
 }
 
+fn convert_color_format(fmt: ColFmt) -> Option<SurfaceType> {
+    match fmt {
+        ColFmt::Auto => unreachable!(),
+        ColFmt::RGBA => Some(SurfaceType::R8_G8_B8_A8),
+        ColFmt::BGRA => Some(SurfaceType::B8_G8_R8_A8),
+        _ => None,
+    }
+}
+
 fn create_texture_asset_from_image(
     image: ImageData,
     options: TextureMetadata,
     renderer: &mut Renderer,
 ) -> Result<Texture> {
-    fn convert_color_format(fmt: ColFmt) -> Option<SurfaceType> {
-        match fmt {
-            ColFmt::Auto => unreachable!(),
-            ColFmt::RGBA => Some(SurfaceType::R8_G8_B8_A8),
-            ColFmt::BGRA => Some(SurfaceType::B8_G8_R8_A8),
-            _ => None,
-        }
-    }
-
     let image = image.raw;
     let fmt = convert_color_format(image.fmt)
         .chain_err(|| format!("Unsupported color format {:?}", image.fmt))?;
@@ -323,3 +663,72 @@ fn create_texture_asset_from_image(
         .create_texture(tb)
         .chain_err(|| "Failed to create texture from texture data")
 }
+
+fn create_cubemap_asset(
+    faces: Vec<ImageData>,
+    options: TextureMetadata,
+    renderer: &mut Renderer,
+) -> Result<Texture> {
+    if faces.len() != 6 {
+        bail!("A cubemap needs 6 faces, got {}", faces.len());
+    }
+
+    let size = faces[0].raw.w;
+    let fmt = faces[0].raw.fmt;
+    for face in &faces {
+        if face.raw.w != size || face.raw.h != size || face.raw.fmt != fmt {
+            bail!("Cubemap faces must be square and share the same size and color format");
+        }
+    }
+    if size > u16::max_value() as usize {
+        bail!(
+            "Unsupported cubemap size (expected: {}, got: {})",
+            u16::max_value(),
+            size
+        );
+    }
+    let surface = convert_color_format(fmt)
+        .chain_err(|| format!("Unsupported color format {:?}", fmt))?;
+
+    let data = faces
+        .into_iter()
+        .flat_map(|face| face.raw.buf)
+        .collect::<Vec<_>>();
+    let tb = apply_options(TextureBuilder::new(data).with_format(surface), options)
+        .with_cube_size(size as u16);
+
+    renderer
+        .create_texture(tb)
+        .chain_err(|| "Failed to build cubemap texture")
+}
+
+/// Samples the faces of a cubemap from an equirectangular RGBA image.
+fn equirectangular_to_cube<T: Copy>(data: &[T], w: usize, h: usize, size: usize) -> Vec<T> {
+    use std::f32::consts::PI;
+
+    let mut faces = Vec::with_capacity(size * size * 6 * 4);
+    for face in 0..6 {
+        for y in 0..size {
+            for x in 0..size {
+                let s = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+                let t = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+                let (dx, dy, dz) = match face {
+                    0 => (1.0, -t, -s),
+                    1 => (-1.0, -t, s),
+                    2 => (s, 1.0, t),
+                    3 => (s, -1.0, -t),
+                    4 => (s, -t, 1.0),
+                    _ => (-s, -t, -1.0),
+                };
+                let len = (dx * dx + dy * dy + dz * dz).sqrt();
+                let u = 0.5 + dx.atan2(-dz) / (2.0 * PI);
+                let v = 0.5 - (dy / len).asin() / PI;
+                let px = ((u * w as f32) as usize).min(w - 1);
+                let py = ((v * h as f32) as usize).min(h - 1);
+                let i = (py * w + px) * 4;
+                faces.extend_from_slice(&data[i..i + 4]);
+            }
+        }
+    }
+    faces
+}
@@ -0,0 +1,14 @@
+//! Hiding entities from the render passes.
+
+use specs::{Component, NullStorage};
+
+/// Hides an entity from all the render passes.
+///
+/// Hidden entities keep their `MeshHandle`, `Material` and other rendering
+/// components, so they can be shown again by removing this component.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Hidden;
+
+impl Component for Hidden {
+    type Storage = NullStorage<Self>;
+}
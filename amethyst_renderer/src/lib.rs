@@ -21,13 +21,16 @@ extern crate gfx_core;
 #[macro_use]
 extern crate gfx_macros;
 extern crate hetseq;
+extern crate hibitset;
 extern crate imagefmt;
 #[macro_use]
 extern crate log;
 extern crate rayon;
+extern crate ron;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+extern crate serde_json;
 extern crate shred;
 extern crate shrev;
 extern crate smallvec;
@@ -48,6 +51,8 @@ extern crate gfx_window_metal;
 #[cfg(feature = "opengl")]
 extern crate gfx_device_gl;
 #[cfg(feature = "opengl")]
+extern crate gfx_gl;
+#[cfg(feature = "opengl")]
 extern crate gfx_window_glutin;
 #[cfg(feature = "opengl")]
 extern crate glutin;
@@ -57,34 +62,75 @@ extern crate gfx_device_vulkan;
 #[cfg(feature = "vulkan")]
 extern crate gfx_window_vulkan;
 
+pub use atlas::{TextureAtlas, TextureAtlasData, TextureAtlasHandle};
+pub use auto_fov::{AutoAspect, AutoFov, AutoFovSystem, AutoOrtho};
+pub use billboard::{Billboard, BillboardSystem};
 pub use bundle::RenderBundle;
-pub use cam::{ActiveCamera, Camera, Projection};
+pub use cam::{ActiveCamera, ActiveCameraEvent, ActiveCameraSystem, Camera, Projection};
 pub use color::Rgba;
 pub use config::DisplayConfig;
+pub use debug_lines::DebugLines;
+pub use decal::Decal;
 pub use formats::{build_mesh_with_combo, create_mesh_asset, create_texture_asset, BmpFormat,
-                  ComboMeshCreator, ImageData, ImageError, JpgFormat, MeshCreator, MeshData,
-                  ObjFormat, PngFormat, TextureData, TextureMetadata};
+                  ComboMeshCreator, CompressedData, CubemapFormat, CubemapLayout, DdsFormat,
+                  GlslFormat, ImageData, ImageError, JpgFormat, KtxFormat, MeshCreator, MeshData,
+                  MeshMetadata, MeshMetadataOverrides, MtlFormat, ObjFormat, ObjMaterialFormat,
+                  PngFormat, SpriteDefinition, SpriteSheetDefinition, SpriteSheetFormat,
+                  TerrainFormat, TerrainMetadata, TerrainMetadataOverrides,
+                  TextureAtlasDefinition, TextureAtlasFormat, TextureData, TextureMetadata,
+                  TextureMetadataOverrides, TexturePackerFormat};
+pub use hidden::Hidden;
 pub use input::{ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
-pub use light::{DirectionalLight, Light, PointLight, SpotLight, SunLight};
+pub use light::{AreaLight, AreaShape, Attenuation, DirectionalLight, Light, PointLight, SpotLight,
+                SunLight};
 pub use mesh::{vertex_data, Mesh, MeshBuilder, MeshHandle, VertexBuffer};
-pub use mtl::{Material, MaterialDefaults};
-pub use pass::{DrawFlat, DrawFlatSeparate, DrawPbm, DrawPbmSeparate, DrawShaded,
-               DrawShadedSeparate};
-pub use pipe::{ColorBuffer, Data, DepthBuffer, DepthMode, Effect, EffectBuilder, Init, Meta,
-               NewEffect, Pipeline, PipelineBuild, PipelineBuilder, PipelineData, PolyPipeline,
-               PolyStage, PolyStages, Stage, StageBuilder, Target, TargetBuilder, Targets};
+pub use morph::{MorphNormal0, MorphNormal1, MorphNormal2, MorphNormal3, MorphPosition0,
+                MorphPosition1, MorphPosition2, MorphPosition3, MorphWeights, MAX_MORPH_TARGETS};
+pub use mtl::{CustomUniforms, LibraryMaterial, Material, MaterialDefaults, MaterialLibrary,
+              MaterialLibraryData, MaterialLibraryHandle};
+pub use occlusion::{Occluder, OcclusionCullingSystem};
+pub use outline::Outline;
+pub use particle::ParticleEmitter;
+pub use pass::{DrawBloom, DrawChromaticAberration, DrawColorGrade, DrawDebugLines, DrawDecal,
+               DrawDepth, DrawDof, DrawFlat, DrawFlatSeparate, DrawFxaa, DrawMotionBlur,
+               DrawOutline, DrawParticles, DrawPbm, DrawPbmSeparate, DrawPointShadow,
+               DrawPostEffect, DrawShaded, DrawShadedSeparate, DrawShadow, DrawSkybox, DrawSprite,
+               DrawSsao, DrawTerrain, DrawTileMap, DrawTonemap, DrawVignette, DrawWater,
+               DrawWireframe, Tonemapping};
+pub use pipe::{ColorBuffer, Comparison, Data, DepthBuffer, DepthMode, Effect, EffectBuilder, Init,
+               Meta, NewEffect, Pipeline, PipelineBuild, PipelineBuilder, PipelineData,
+               PolyPipeline, PolyStage, PolyStages, PostProcessing, Stage, StageBuilder, Stencil,
+               StencilOp, Target, TargetBuilder, TargetInput, Targets, Viewport};
+pub use prefab::{MaterialPrefab, MeshPrefab, TexturePrefab};
+pub use render_mask::RenderMask;
 pub use renderer::Renderer;
-pub use resources::{AmbientColor, ScreenDimensions, WindowMessages};
+pub use resources::{AmbientColor, BloomParams, ChromaticAberrationParams, DofParams, EnvironmentMap,
+                    Fog, FogMode, MotionBlurParams, ResolutionScale, ScreenDimensions, ScreenDump,
+                    Skybox, SsaoParams, TargetTextures, VignetteParams, WindowMessages};
+pub use shader::{Shader, ShaderData, ShaderHandle};
+pub use shadow::{PointShadowMaps, ShadowCaster, ShadowConfig, MAX_POINT_SHADOWS,
+                 MAX_SHADOW_CASCADES};
+pub use shape::{Shape, ShapeVertex};
+pub use stats::{GpuTimer, PassStats, RenderStats};
+pub use stream::StreamingTexture;
+pub use sprite::{Sprite, SpriteRender, SpriteSheet, SpriteSheetData, SpriteSheetHandle,
+                 TextureCoordinates};
 pub use skinning::{AnimatedComboMeshCreator, AnimatedVertexBufferCombination, JointIds,
                    JointTransforms, JointWeights};
 pub use system::RenderSystem;
-pub use tex::{Texture, TextureBuilder, TextureHandle};
+pub use terrain::{Terrain, TerrainData, TerrainHandle, TerrainMaterial};
+pub use tex::{FilterMethod, Texture, TextureBuilder, TextureHandle, TextureRegion, WrapMode};
+pub use tilemap::{TileMap, CHUNK_SIZE};
 pub use transparent::{Blend, BlendChannel, ColorMask, Equation, Factor, Transparent,
                       TransparentBackToFront, TransparentSortingSystem};
-pub use types::{Encoder, Factory, PipelineState, Resources};
-pub use vertex::{Attribute, AttributeFormat, Attributes, Color, Normal, PosColor, PosNormTangTex,
-                 PosNormTex, PosTex, Position, Query, Separate, Tangent, TexCoord,
-                 VertexBufferCombination, VertexFormat, With};
+pub use types::{Backend, BackendContext, DefaultBackend, Encoder, Factory, PipelineState,
+                Resources};
+pub use vertex::{Attribute, AttributeFormat, Attributes, Color, InstanceTransform, Normal,
+                 PosColor, PosColorTex, PosNormTangTex, PosNormTex, PosTex, Position, Query,
+                 Separate, Tangent, TexCoord, VertexBufferCombination, VertexFormat, With};
+pub use visibility::{BoundingSphere, FrustumCullingSystem, Visibility};
+pub use water::{Water, WaterReflection, WaterReflectionSystem};
+pub use z_order::ZOrder;
 
 pub mod error;
 pub mod pipe;
@@ -93,21 +139,44 @@ pub mod mouse;
 #[macro_use]
 mod macros;
 
+mod atlas;
+mod auto_fov;
+mod billboard;
 mod bundle;
 mod cam;
 mod color;
 mod config;
+mod debug_lines;
+mod decal;
 mod formats;
+mod hidden;
 mod input;
 mod light;
 mod mesh;
+mod morph;
 mod mtl;
+mod occlusion;
+mod outline;
+mod particle;
 mod pass;
+mod prefab;
+mod render_mask;
 mod renderer;
 mod resources;
+mod shader;
+mod shadow;
+mod shape;
 mod transparent;
 mod skinning;
+mod sprite;
+mod stats;
+mod stream;
 mod system;
+mod terrain;
 mod tex;
+mod tilemap;
 mod types;
 mod vertex;
+mod visibility;
+mod water;
+mod z_order;
@@ -11,8 +11,7 @@ use color::Rgba;
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum Light {
     /// An area light.
-    /// FIXME: Missing implementation!
-    Area,
+    Area(AreaLight),
     /// A directional light.
     Directional(DirectionalLight),
     /// A point light.
@@ -23,6 +22,80 @@ pub enum Light {
     Sun(SunLight),
 }
 
+/// A light source with a sphere, tube or rectangle shape, for large soft
+/// lights.
+///
+/// Only the `DrawPbm` passes light with it, approximating its specular
+/// reflection by the point of the shape closest to the reflected view ray.
+/// Its intensity fades out like a `PointLight` with the distance to the
+/// shape.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AreaLight {
+    /// Location of the center of the shape in three dimensional space.
+    pub center: [f32; 3], //TODO: Replace with a cgmath type when gfx version > 0.16
+    /// Color of the light in RGBA8 format.
+    pub color: Rgba,
+    /// Brightness of the light source, in lumens.
+    pub intensity: f32,
+    /// Maximum radius of the light's affected area around its shape.
+    pub radius: f32,
+    /// Shape of the light source.
+    pub shape: AreaShape,
+    /// Smoothness of the light-to-dark transition from the shape to the
+    /// radius.
+    pub smoothness: f32,
+}
+
+impl Default for AreaLight {
+    fn default() -> Self {
+        AreaLight {
+            center: [0.0, 0.0, 0.0],
+            color: Rgba::default(),
+            intensity: 10.0,
+            radius: 10.0,
+            shape: AreaShape::Sphere { radius: 0.5 },
+            smoothness: 4.0,
+        }
+    }
+}
+
+impl From<AreaLight> for Light {
+    fn from(area: AreaLight) -> Self {
+        Light::Area(area)
+    }
+}
+
+/// Shape of an `AreaLight`, centered on its `center`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum AreaShape {
+    /// A sphere.
+    Sphere {
+        /// Radius of the sphere.
+        radius: f32,
+    },
+    /// A capsule, like a fluorescent tube.
+    Tube {
+        /// Direction of the axis of the tube.
+        axis: [f32; 3],
+        /// Length of the tube along its axis, without the rounded ends.
+        length: f32,
+        /// Radius of the tube.
+        radius: f32,
+    },
+    /// A rectangle only emitting light on the side of its normal, `right`
+    /// cross `up`.
+    Rect {
+        /// Direction of the width of the rectangle.
+        right: [f32; 3],
+        /// Direction of the height of the rectangle.
+        up: [f32; 3],
+        /// Width of the rectangle.
+        width: f32,
+        /// Height of the rectangle.
+        height: f32,
+    },
+}
+
 /// A directional light source.
 #[repr(C)]
 #[derive(Clone, ConstantBuffer, Debug, Deserialize, PartialEq, Serialize)]
@@ -67,10 +140,15 @@ impl From<DirectionalLight> for Light {
 /// * *lightRadius* = `radius`
 /// * *n* = `smoothness`
 ///
+/// This is the default `Attenuation::InverseSquare`, other falloffs can be
+/// chosen through `attenuation`.
+///
 /// [fb]: http://www.frostbite.com/wp-content/uploads/2014/11/course_notes_moving_frostbite_to_pbr.pdf
-#[repr(C)]
-#[derive(Clone, ConstantBuffer, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct PointLight {
+    /// How the intensity falls off with the distance to the light.
+    #[serde(default)]
+    pub attenuation: Attenuation,
     /// Location of the light source in three dimensional space.
     pub center: [f32; 3], //TODO: Replace with a cgmath type when gfx version > 0.16
     /// Color of the light in RGBA8 format.
@@ -87,6 +165,7 @@ pub struct PointLight {
 impl Default for PointLight {
     fn default() -> Self {
         PointLight {
+            attenuation: Attenuation::default(),
             center: [0.0, 0.0, 0.0],
             color: Rgba::default(),
             intensity: 10.0,
@@ -102,21 +181,56 @@ impl From<PointLight> for Light {
     }
 }
 
+/// Falloff of the intensity of a `PointLight` with the distance *d* to the
+/// light. They all reach zero at the `radius` of the light.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Attenuation {
+    /// Physically based inverse-square falloff, smoothly cut off at the
+    /// radius as set by the `smoothness` of the light.
+    InverseSquare,
+    /// Linear falloff, from the full intensity at the light to zero at its
+    /// radius.
+    Linear,
+    /// Classic falloff dividing the intensity by
+    /// `constant + linear * d + quadratic * d * d`, smoothly cut off at the
+    /// radius like `InverseSquare`.
+    Constants {
+        /// Constant term of the divisor.
+        constant: f32,
+        /// Term of the divisor proportional to the distance.
+        linear: f32,
+        /// Term of the divisor proportional to the squared distance.
+        quadratic: f32,
+    },
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Attenuation::InverseSquare
+    }
+}
+
 /// A spot light source.
+///
+/// It lights like a `PointLight` inside a cone: fully within `inner_angle`,
+/// fading out towards `outer_angle`.
 #[repr(C)]
 #[derive(Clone, ConstantBuffer, Debug, Deserialize, PartialEq, Serialize)]
 pub struct SpotLight {
-    /// Opening angle of the light cone in degrees.
-    pub angle: f32, //TODO: Replace with a cgmath type when gfx version > 0.16
     /// Location of the light source in three dimensional space.
     pub center: [f32; 3], //TODO: Replace with a cgmath type when gfx version > 0.16
     /// Color of the light in RGBA8 format.
     pub color: Rgba,
     /// Direction that the light is pointing.
     pub direction: [f32; 3], //TODO: Replace with a cgmath type when gfx version > 0.16
+    /// Opening angle of the fully lit inner cone, in degrees.
+    pub inner_angle: f32,
     /// Brightness of the light source, in lumens.
     pub intensity: f32,
-    /// Maximum radius of the point light's affected area.
+    /// Opening angle of the light cone, in degrees. The light fades out
+    /// between the inner cone and this one.
+    pub outer_angle: f32,
+    /// Maximum radius of the spot light's affected area.
     pub radius: f32,
     /// Smoothness of the light-to-dark transition from the center to the
     /// radius.
@@ -126,11 +240,12 @@ pub struct SpotLight {
 impl Default for SpotLight {
     fn default() -> Self {
         SpotLight {
-            angle: 60.0,
             center: [0.0, 1.0, 0.0],
             color: Rgba::default(),
             direction: [0.0, -1.0, 0.0],
+            inner_angle: 45.0,
             intensity: 10.0,
+            outer_angle: 60.0,
             radius: 10.0,
             smoothness: 4.0,
         }
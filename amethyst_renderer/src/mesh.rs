@@ -2,15 +2,18 @@
 
 use std::iter::{once, Chain, Once};
 use std::marker::PhantomData;
+use std::mem;
+use std::sync::{Arc, Mutex};
 
 use amethyst_assets::Handle;
 
 use amethyst_core::cgmath::{Deg, Matrix4, Point3, Transform, Vector3};
 use gfx::Primitive;
 
-use error::Result;
-use types::{Factory, RawBuffer, Slice};
-use vertex::{Attributes, VertexFormat};
+use error::{Error, Result};
+use types::{Encoder, Factory, RawBuffer, Slice};
+use vertex::{Attribute, Attributes, Position, VertexFormat};
+use visibility::BoundingSphere;
 
 /// Raw buffer with its attributes
 #[derive(Clone, Debug)]
@@ -27,8 +30,11 @@ pub trait VertexData {
     /// Get vertex count in buffer
     fn len(&self) -> usize;
 
-    /// Build `VertexBuffer`
-    fn build(&self, factory: &mut Factory) -> Result<VertexBuffer>;
+    /// Build `VertexBuffer`, a dynamic one whose vertices are queued to the uploads if any
+    fn build(&self, factory: &mut Factory, uploads: Option<&MeshUploads>) -> Result<VertexBuffer>;
+
+    /// Compute the bounds of the vertex positions, if there are any
+    fn bounds(&self) -> Option<BoundingSphere>;
 }
 
 /// Construct new vertex data from raw data and vertex format
@@ -51,10 +57,10 @@ where
         self.0.as_ref().len()
     }
 
-    fn build(&self, factory: &mut Factory) -> Result<VertexBuffer> {
+    fn build(&self, factory: &mut Factory, uploads: Option<&MeshUploads>) -> Result<VertexBuffer> {
         use gfx::Factory;
-        use gfx::buffer::Role;
-        use gfx::memory::{cast_slice, Bind};
+        use gfx::buffer::{Info, Role};
+        use gfx::memory::{cast_slice, Bind, Usage};
 
         let verts = self.0.as_ref();
         let slice = cast_slice(verts);
@@ -62,12 +68,46 @@ where
         let role = Role::Vertex;
         let bind = Bind::empty();
 
-        let vbuf = factory.create_buffer_immutable_raw(slice, stride, role, bind)?;
+        let vbuf = match uploads {
+            Some(uploads) => {
+                let info = Info {
+                    role,
+                    usage: Usage::Dynamic,
+                    bind,
+                    size: slice.len(),
+                    stride,
+                };
+                let vbuf = factory.create_buffer_raw(info)?;
+                uploads.push(vbuf.clone(), slice.to_vec());
+                vbuf
+            }
+            None => factory.create_buffer_immutable_raw(slice, stride, role, bind)?,
+        };
         Ok(VertexBuffer {
             attrs: V::ATTRIBUTES,
             raw: vbuf,
         })
     }
+
+    fn bounds(&self) -> Option<BoundingSphere> {
+        use gfx::memory::cast_slice;
+        use std::ptr;
+
+        let offset = match V::ATTRIBUTES.iter().find(|&&(name, _)| name == Position::NAME) {
+            Some(&(_, element)) if element.format == Position::FORMAT => element.offset as usize,
+            _ => return None,
+        };
+        let verts = self.0.as_ref();
+        let bytes: &[u8] = cast_slice(verts);
+        let stride = V::size();
+        BoundingSphere::from_points((0..verts.len()).map(|i| {
+            let start = i * stride + offset;
+            let position = &bytes[start..start + Position::SIZE as usize];
+            // Vertex formats are `Pod`, the read can't produce an invalid value.
+            let position = unsafe { ptr::read_unaligned(position.as_ptr() as *const [f32; 3]) };
+            Point3::from(position)
+        }))
+    }
 }
 
 /// Set of vertex data
@@ -79,8 +119,15 @@ pub trait VertexDataSet {
     /// Get smalles vertex count across buffers
     fn len(&self) -> usize;
 
-    /// Build `VertexBuffer`s
-    fn build(&self, factory: &mut Factory) -> Result<Self::VertexBufferIter>;
+    /// Build `VertexBuffer`s, dynamic ones if uploads are given
+    fn build(
+        &self,
+        factory: &mut Factory,
+        uploads: Option<&MeshUploads>,
+    ) -> Result<Self::VertexBufferIter>;
+
+    /// Compute the bounds of the first buffer with vertex positions
+    fn bounds(&self) -> Option<BoundingSphere>;
 }
 
 impl<H> VertexDataSet for (H, ())
@@ -93,9 +140,17 @@ where
         self.0.len()
     }
 
-    fn build(&self, factory: &mut Factory) -> Result<Self::VertexBufferIter> {
+    fn build(
+        &self,
+        factory: &mut Factory,
+        uploads: Option<&MeshUploads>,
+    ) -> Result<Self::VertexBufferIter> {
         let (ref head, _) = *self;
-        Ok(once(head.build(factory)?))
+        Ok(once(head.build(factory, uploads)?))
+    }
+
+    fn bounds(&self) -> Option<BoundingSphere> {
+        self.0.bounds()
     }
 }
 
@@ -111,9 +166,39 @@ where
         min(self.0.len(), self.1.len())
     }
 
-    fn build(&self, factory: &mut Factory) -> Result<Self::VertexBufferIter> {
+    fn build(
+        &self,
+        factory: &mut Factory,
+        uploads: Option<&MeshUploads>,
+    ) -> Result<Self::VertexBufferIter> {
         let (ref head, ref tail) = *self;
-        Ok(once(head.build(factory)?).chain(tail.build(factory)?))
+        Ok(once(head.build(factory, uploads)?).chain(tail.build(factory, uploads)?))
+    }
+
+    fn bounds(&self) -> Option<BoundingSphere> {
+        self.0.bounds().or_else(|| self.1.bounds())
+    }
+}
+
+/// Vertices waiting to be uploaded to the buffers of dynamic meshes, shared by the meshes
+/// built by a `Renderer`.
+#[doc(hidden)]
+#[derive(Clone, Debug, Default)]
+pub struct MeshUploads(Arc<Mutex<Vec<(RawBuffer, Vec<u8>)>>>);
+
+impl MeshUploads {
+    fn push(&self, buffer: RawBuffer, data: Vec<u8>) {
+        self.0.lock().unwrap().push((buffer, data));
+    }
+
+    /// Uploads the pending vertices, in the order they were queued.
+    pub(crate) fn upload(&self, encoder: &mut Encoder) {
+        let uploads = mem::replace(&mut *self.0.lock().unwrap(), Vec::new());
+        for (buffer, data) in uploads {
+            if let Err(err) = encoder.update_buffer_raw(&buffer, &data, 0) {
+                eprintln!("WARNING: Failed to update mesh vertices: {:?}", err);
+            }
+        }
     }
 }
 
@@ -123,9 +208,12 @@ pub type MeshHandle = Handle<Mesh>;
 /// Represents a polygonal mesh.
 #[derive(Clone, Debug)]
 pub struct Mesh {
+    prim: Primitive,
     slice: Slice,
     transform: Matrix4<f32>,
     vbufs: Vec<VertexBuffer>,
+    bounds: Option<BoundingSphere>,
+    uploads: Option<MeshUploads>,
 }
 
 impl Mesh {
@@ -179,6 +267,11 @@ impl Mesh {
         &self.slice
     }
 
+    /// Returns the primitive type of the mesh.
+    pub fn primitive(&self) -> Primitive {
+        self.prim
+    }
+
     /// Returns the transformation matrix of the mesh.
     ///
     /// This four-by-four matrix applies translation, rotation, and scaling to
@@ -187,6 +280,48 @@ impl Mesh {
     pub fn transform(&self) -> Matrix4<f32> {
         self.transform
     }
+
+    /// Returns the sphere enclosing the vertex positions of the mesh, if it
+    /// has any.
+    pub fn bounds(&self) -> Option<&BoundingSphere> {
+        self.bounds.as_ref()
+    }
+
+    /// Replaces the contents of the vertex buffer of the given vertex format,
+    /// for procedural geometry like trails, ropes or cloth.
+    ///
+    /// The mesh has to be built with `MeshBuilder::dynamic` by the `Renderer`,
+    /// for example by a `MeshCreator` loaded as `MeshData`. It can't hold more
+    /// vertices than it was built with, and draws as many vertices as the last
+    /// update. The vertices are uploaded before the next frame is drawn.
+    pub fn update_vertices<V>(&mut self, vertices: &[V]) -> Result<()>
+    where
+        V: VertexFormat,
+    {
+        use gfx::memory::cast_slice;
+
+        let uploads = match self.uploads {
+            Some(ref uploads) => uploads,
+            None => return Err(Error::MeshUpdate("The mesh is not dynamic".into())),
+        };
+        let vbuf = match self.vbufs.iter().find(|vbuf| vbuf.attrs == V::ATTRIBUTES) {
+            Some(vbuf) => vbuf,
+            None => return Err(Error::MeshUpdate("No buffer with this vertex format".into())),
+        };
+        let data: &[u8] = cast_slice(vertices);
+        if data.len() > vbuf.raw.get_info().size {
+            return Err(Error::MeshUpdate(format!(
+                "{} vertices exceed the size of the buffer",
+                vertices.len()
+            )));
+        }
+        uploads.push(vbuf.raw.clone(), data.to_vec());
+        self.slice.end = vertices.len() as u32;
+        if let Some(bounds) = vertex_data(vertices).bounds() {
+            self.bounds = Some(bounds);
+        }
+        Ok(())
+    }
 }
 
 /// Builds new meshes.
@@ -195,6 +330,8 @@ pub struct MeshBuilder<T> {
     prim: Primitive,
     transform: Matrix4<f32>,
     vertices: T,
+    #[serde(default)]
+    dynamic: bool,
 }
 
 impl<D, V> MeshBuilder<((D, PhantomData<V>), ())>
@@ -210,6 +347,7 @@ where
             prim: Primitive::TriangleList,
             transform: Matrix4::identity(),
             vertices: (vertex_data(verts), ()),
+            dynamic: false,
         }
     }
 }
@@ -229,17 +367,28 @@ where
             prim: self.prim,
             transform: self.transform,
             vertices: (vertex_data(verts), self.vertices),
+            dynamic: self.dynamic,
         }
     }
 
     /// Sets the primitive type of the mesh.
     ///
-    /// By default, meshes are constructed as triangle lists.
+    /// By default, meshes are constructed as triangle lists. Meshes of other
+    /// types, like lines or points, are only drawn by the passes set to draw
+    /// the same type, see `DrawFlat::with_primitive`.
     pub fn with_prim_type(mut self, prim: Primitive) -> Self {
         self.prim = prim;
         self
     }
 
+    /// Sets whether the vertices can be updated with `Mesh::update_vertices`.
+    ///
+    /// Dynamic meshes have to be built by the `Renderer`.
+    pub fn dynamic(mut self, mutable: bool) -> Self {
+        self.dynamic = mutable;
+        self
+    }
+
     /// Sets the position of the mesh in 3D space.
     pub fn with_position<P: Into<Point3<f32>>>(mut self, pos: P) -> Self {
         use amethyst_core::cgmath::EuclideanSpace;
@@ -279,6 +428,29 @@ where
 
     /// Builds and returns the new mesh.
     pub fn build(self, fac: &mut Factory) -> Result<Mesh> {
+        if self.dynamic {
+            return Err(Error::MeshUpdate(
+                "Dynamic meshes have to be built by the `Renderer`".into(),
+            ));
+        }
+        self.build_mesh(fac, None)
+    }
+
+    /// Builds the new mesh, queuing the vertices of dynamic meshes to the uploads.
+    pub(crate) fn build_with_uploads(
+        self,
+        fac: &mut Factory,
+        uploads: &MeshUploads,
+    ) -> Result<Mesh> {
+        let uploads = if self.dynamic {
+            Some(uploads.clone())
+        } else {
+            None
+        };
+        self.build_mesh(fac, uploads)
+    }
+
+    fn build_mesh(self, fac: &mut Factory, uploads: Option<MeshUploads>) -> Result<Mesh> {
         use gfx::IndexBuffer;
         let count = self.vertices.len();
 
@@ -291,9 +463,12 @@ where
         };
 
         Ok(Mesh {
+            prim: self.prim,
             slice: slice,
             transform: self.transform,
-            vbufs: self.vertices.build(fac)?.collect(),
+            vbufs: self.vertices.build(fac, uploads.as_ref())?.collect(),
+            bounds: self.vertices.bounds(),
+            uploads,
         })
     }
 }
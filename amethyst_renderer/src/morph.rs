@@ -0,0 +1,61 @@
+//! Morph target (blend shape) support.
+
+use gfx::format::{ChannelType, Format, SurfaceType};
+use specs::{Component, DenseVecStorage};
+
+use vertex::Attribute;
+
+/// Maximum number of morph targets a mesh can be drawn with.
+pub const MAX_MORPH_TARGETS: usize = 4;
+
+/// Weights of the morph targets of a mesh.
+///
+/// Each morph target is stored in the mesh as a pair of vertex buffers holding
+/// position and normal offsets, `Separate<MorphPosition0>` and
+/// `Separate<MorphNormal0>` for the first target and so on. The offsets are
+/// scaled by the weight of their target and added to the vertices before
+/// skinning. Only the first `MAX_MORPH_TARGETS` weights are used.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MorphWeights {
+    /// Weight of each morph target.
+    pub weights: Vec<f32>,
+}
+
+impl MorphWeights {
+    /// Returns the weights of the targets supported by the renderer, with the
+    /// missing weights set to zero.
+    pub fn padded(&self) -> [f32; MAX_MORPH_TARGETS] {
+        let mut weights = [0.0; MAX_MORPH_TARGETS];
+        for (weight, &value) in weights.iter_mut().zip(self.weights.iter()) {
+            *weight = value;
+        }
+        weights
+    }
+}
+
+impl Component for MorphWeights {
+    type Storage = DenseVecStorage<Self>;
+}
+
+macro_rules! morph_attribute {
+    ($name:ident, $attr:expr, $doc:expr) => {
+        #[doc = $doc]
+        #[derive(Clone, Debug)]
+        pub enum $name {}
+        impl Attribute for $name {
+            const NAME: &'static str = $attr;
+            const FORMAT: Format = Format(SurfaceType::R32_G32_B32, ChannelType::Float);
+            const SIZE: u32 = 12;
+            type Repr = [f32; 3];
+        }
+    };
+}
+
+morph_attribute!(MorphPosition0, "morph_position_0", "Position offset of the first morph target");
+morph_attribute!(MorphPosition1, "morph_position_1", "Position offset of the second morph target");
+morph_attribute!(MorphPosition2, "morph_position_2", "Position offset of the third morph target");
+morph_attribute!(MorphPosition3, "morph_position_3", "Position offset of the fourth morph target");
+morph_attribute!(MorphNormal0, "morph_normal_0", "Normal offset of the first morph target");
+morph_attribute!(MorphNormal1, "morph_normal_1", "Normal offset of the second morph target");
+morph_attribute!(MorphNormal2, "morph_normal_2", "Normal offset of the third morph target");
+morph_attribute!(MorphNormal3, "morph_normal_3", "Normal offset of the fourth morph target");
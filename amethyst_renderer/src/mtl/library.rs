@@ -0,0 +1,86 @@
+//! Material libraries.
+
+use amethyst_assets::{Asset, AssetStorage, Handle, Loader, Result};
+use specs::VecStorage;
+
+use formats::TextureData;
+use mtl::{Material, MaterialDefaults};
+use tex::Texture;
+
+/// A handle to a `MaterialLibrary` asset.
+pub type MaterialLibraryHandle = Handle<MaterialLibrary>;
+
+/// The materials of a Wavefront MTL file, loaded with `MtlFormat`.
+///
+/// The textures are kept as data until a `Material` is created from them,
+/// see `LibraryMaterial::load`.
+#[derive(Clone, Debug, Default)]
+pub struct MaterialLibrary {
+    /// The materials, in the order of the file.
+    pub materials: Vec<LibraryMaterial>,
+}
+
+impl MaterialLibrary {
+    /// Returns the material with the given name.
+    pub fn get(&self, name: &str) -> Option<&LibraryMaterial> {
+        self.materials.iter().find(|material| material.name == name)
+    }
+}
+
+impl Asset for MaterialLibrary {
+    const NAME: &'static str = "renderer::MaterialLibrary";
+    type Data = MaterialLibraryData;
+    type HandleStorage = VecStorage<MaterialLibraryHandle>;
+}
+
+/// Material library data, as produced by `MtlFormat`.
+#[derive(Clone, Debug)]
+pub struct MaterialLibraryData(pub MaterialLibrary);
+
+impl Into<Result<MaterialLibrary>> for MaterialLibraryData {
+    fn into(self) -> Result<MaterialLibrary> {
+        Ok(self.0)
+    }
+}
+
+/// A single material of a `MaterialLibrary`.
+#[derive(Clone, Debug)]
+pub struct LibraryMaterial {
+    /// Name of the material, as used by the `usemtl` statements of the
+    /// OBJ files.
+    pub name: String,
+    /// Diffuse map, or diffuse color and opacity.
+    pub albedo: TextureData,
+    /// Emission map or color, if any.
+    pub emission: Option<TextureData>,
+    /// Normal map, if any.
+    pub normal: Option<TextureData>,
+}
+
+impl LibraryMaterial {
+    /// Creates a `Material` out of this one, the maps it doesn't have are
+    /// taken from the defaults.
+    ///
+    /// The textures are uploaded again on each call, so the material should
+    /// be created once and cloned to the entities sharing it.
+    pub fn load(
+        &self,
+        loader: &Loader,
+        storage: &AssetStorage<Texture>,
+        defaults: &MaterialDefaults,
+    ) -> Material {
+        let load = |data: &TextureData| loader.load_from_data(data.clone(), (), storage);
+        Material {
+            albedo: load(&self.albedo),
+            emission: self.emission
+                .as_ref()
+                .map(&load)
+                .unwrap_or_else(|| defaults.0.emission.clone()),
+            normal: self.normal
+                .as_ref()
+                .map(&load)
+                .unwrap_or_else(|| defaults.0.normal.clone()),
+            ..defaults.0.clone()
+        }
+    }
+}
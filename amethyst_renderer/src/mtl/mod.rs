@@ -1,11 +1,26 @@
 //! Physically-based material.
 
+pub use self::library::{LibraryMaterial, MaterialLibrary, MaterialLibraryData,
+                        MaterialLibraryHandle};
+
+mod library;
+
+use std::hash::{Hash, Hasher};
+
+use gfx::shade::ToUniform;
+use gfx::shade::core::UniformValue;
 use specs::{Component, DenseVecStorage};
 
 use tex::TextureHandle;
 
 /// Material struct.
-#[derive(Clone, Eq, Hash, PartialEq)]
+///
+/// If the albedo map is a sub texture of an atlas, its region applies to the
+/// texture coordinates of all maps.
+///
+/// Materials are compared and hashed by the bits of their factors, so that
+/// entities sharing a material can be drawn together.
+#[derive(Clone)]
 pub struct Material {
     /// Diffuse map.
     pub albedo: TextureHandle,
@@ -21,12 +36,111 @@ pub struct Material {
     pub ambient_occlusion: TextureHandle,
     /// Caveat map.
     pub caveat: TextureHandle,
+    /// Color the albedo map is multiplied with, white by default.
+    pub albedo_factor: [f32; 4],
+    /// Color the emission map is multiplied with, white by default.
+    pub emission_factor: [f32; 4],
+    /// Offset of the texture coordinates, in the unit of the albedo map, to
+    /// scroll the textures. Zero by default.
+    ///
+    /// The offset isn't wrapped around the region of a sub texture of an
+    /// atlas.
+    pub uv_offset: [f32; 2],
+}
+
+impl Material {
+    fn factor_bits(&self) -> [u32; 10] {
+        let mut bits = [0; 10];
+        let factors = self.albedo_factor
+            .iter()
+            .chain(&self.emission_factor)
+            .chain(&self.uv_offset);
+        for (bits, factor) in bits.iter_mut().zip(factors) {
+            *bits = factor.to_bits();
+        }
+        bits
+    }
+}
+
+impl PartialEq for Material {
+    fn eq(&self, other: &Material) -> bool {
+        self.albedo == other.albedo && self.emission == other.emission
+            && self.normal == other.normal && self.metallic == other.metallic
+            && self.roughness == other.roughness
+            && self.ambient_occlusion == other.ambient_occlusion
+            && self.caveat == other.caveat && self.factor_bits() == other.factor_bits()
+    }
+}
+
+impl Eq for Material {}
+
+impl Hash for Material {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.albedo.hash(state);
+        self.emission.hash(state);
+        self.normal.hash(state);
+        self.metallic.hash(state);
+        self.roughness.hash(state);
+        self.ambient_occlusion.hash(state);
+        self.caveat.hash(state);
+        self.factor_bits().hash(state);
+    }
 }
 
 impl Component for Material {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Additional uniform values of a mesh, for passes drawing with a custom
+/// shader.
+///
+/// Each value is applied to the global uniform of the same name when the
+/// entity is drawn, uniforms the pass doesn't know about are ignored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CustomUniforms {
+    uniforms: Vec<(String, UniformValue)>,
+}
+
+impl CustomUniforms {
+    /// Creates an empty `CustomUniforms`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the uniform with the given name, builder style.
+    pub fn with<N: Into<String>, T: ToUniform>(mut self, name: N, value: T) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// Sets the uniform with the given name.
+    pub fn set<N: Into<String>, T: ToUniform>(&mut self, name: N, value: T) {
+        let name = name.into();
+        let value = value.convert();
+        match self.uniforms.iter_mut().find(|&&mut (ref n, _)| *n == name) {
+            Some(uniform) => uniform.1 = value,
+            None => self.uniforms.push((name, value)),
+        }
+    }
+
+    /// Returns the value of the uniform with the given name.
+    pub fn get(&self, name: &str) -> Option<UniformValue> {
+        self.uniforms
+            .iter()
+            .find(|&&(ref n, _)| n == name)
+            .map(|&(_, value)| value)
+    }
+
+    /// Returns an iterator over the names and values of the uniforms.
+    pub fn iter(&self) -> ::std::slice::Iter<(String, UniformValue)> {
+        self.uniforms.iter()
+    }
+}
+
+impl Component for CustomUniforms {
+    type Storage = DenseVecStorage<Self>;
+}
+
 /// A resource providing default textures for `Material`.
 /// These will be be used by the renderer in case a texture
 /// handle points to a texture which is not loaded already.
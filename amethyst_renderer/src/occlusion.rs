@@ -0,0 +1,299 @@
+//! Occlusion culling of meshes with a software depth pyramid.
+
+use amethyst_core::GlobalTransform;
+use amethyst_core::cgmath::{Matrix4, Point3, SquareMatrix, Vector4};
+use specs::{Component, DenseVecStorage, Entities, Fetch, FetchMut, Join, ReadStorage, System};
+
+use cam::{ActiveCamera, Camera};
+use pass::util::get_camera;
+use visibility::{BoundingSphere, Visibility};
+
+const DEFAULT_WIDTH: usize = 256;
+const DEFAULT_HEIGHT: usize = 128;
+
+/// Box hiding what's behind it, in the local space of the entity.
+///
+/// Occluders should be simple and lie inside the visible geometry, like the
+/// inner volume of walls and large props.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Occluder {
+    /// Minimum corner of the box.
+    pub min: Point3<f32>,
+    /// Maximum corner of the box.
+    pub max: Point3<f32>,
+}
+
+impl Occluder {
+    /// Creates a new `Occluder` from two opposite corners.
+    pub fn new<P: Into<Point3<f32>>>(min: P, max: P) -> Self {
+        Occluder {
+            min: min.into(),
+            max: max.into(),
+        }
+    }
+
+    fn corners(&self) -> [Point3<f32>; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Point3::new(a.x, a.y, a.z),
+            Point3::new(b.x, a.y, a.z),
+            Point3::new(a.x, b.y, a.z),
+            Point3::new(b.x, b.y, a.z),
+            Point3::new(a.x, a.y, b.z),
+            Point3::new(b.x, a.y, b.z),
+            Point3::new(a.x, b.y, b.z),
+            Point3::new(b.x, b.y, b.z),
+        ]
+    }
+}
+
+impl Component for Occluder {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Triangles of the box faces, indexing `Occluder::corners`.
+const BOX_TRIANGLES: [[usize; 3]; 12] = [
+    [0, 1, 3],
+    [0, 3, 2],
+    [4, 6, 7],
+    [4, 7, 5],
+    [0, 4, 5],
+    [0, 5, 1],
+    [2, 3, 7],
+    [2, 7, 6],
+    [0, 2, 6],
+    [0, 6, 4],
+    [1, 5, 7],
+    [1, 7, 3],
+];
+
+/// Removes the meshes hidden behind `Occluder`s from `Visibility`.
+///
+/// The occluders are rasterized into a small depth buffer, from which a
+/// pyramid of the farthest depths is built. Bounding spheres entirely behind
+/// the depths they cover are culled.
+///
+/// Must run after `FrustumCullingSystem`, it only removes entities from the
+/// visible set. Uses the same camera.
+pub struct OcclusionCullingSystem {
+    width: usize,
+    height: usize,
+    pyramid: Vec<Vec<f32>>,
+}
+
+impl OcclusionCullingSystem {
+    /// Create new culling system
+    pub fn new() -> Self {
+        Self::with_resolution(DEFAULT_WIDTH, DEFAULT_HEIGHT)
+    }
+
+    /// Create new culling system rasterizing occluders at the given resolution
+    pub fn with_resolution(width: usize, height: usize) -> Self {
+        OcclusionCullingSystem {
+            width: width.max(1),
+            height: height.max(1),
+            pyramid: Vec::new(),
+        }
+    }
+
+    fn level_size(&self, level: usize) -> (usize, usize) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+
+    fn rasterize(&mut self, clip: &[Vector4<f32>; 3]) {
+        // Triangles crossing the near plane are skipped, which only makes culling less effective.
+        if clip.iter().any(|v| v.w <= 1e-5 || v.z < -v.w) {
+            return;
+        }
+        let (width, height) = (self.width as f32, self.height as f32);
+        let mut screen = [(0.0, 0.0, 0.0); 3];
+        for (s, v) in screen.iter_mut().zip(clip.iter()) {
+            *s = (
+                (v.x / v.w * 0.5 + 0.5) * width,
+                (v.y / v.w * 0.5 + 0.5) * height,
+                v.z / v.w,
+            );
+        }
+        let (a, b, c) = (screen[0], screen[1], screen[2]);
+        let area = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+        if area.abs() < 1e-8 {
+            return;
+        }
+
+        let min_x = a.0.min(b.0).min(c.0).max(0.0) as usize;
+        let min_y = a.1.min(b.1).min(c.1).max(0.0) as usize;
+        let max_x = (a.0.max(b.0).max(c.0).ceil() as usize).min(self.width);
+        let max_y = (a.1.max(b.1).max(c.1).ceil() as usize).min(self.height);
+        let depth = &mut self.pyramid[0];
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let w0 = ((b.0 - px) * (c.1 - py) - (b.1 - py) * (c.0 - px)) / area;
+                let w1 = ((c.0 - px) * (a.1 - py) - (c.1 - py) * (a.0 - px)) / area;
+                let w2 = 1.0 - w0 - w1;
+                if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                    continue;
+                }
+                // Normalized device depth is affine in screen space.
+                let z = w0 * a.2 + w1 * b.2 + w2 * c.2;
+                let texel = &mut depth[y * self.width + x];
+                *texel = texel.min(z);
+            }
+        }
+    }
+
+    fn build_pyramid(&mut self) {
+        let mut level = 1;
+        while self.level_size(level - 1) != (1, 1) {
+            let (src_w, src_h) = self.level_size(level - 1);
+            let (dst_w, dst_h) = self.level_size(level);
+            let mut dst = vec![1.0; dst_w * dst_h];
+            {
+                let src = &self.pyramid[level - 1];
+                // The last texels also cover the odd row and column of the source.
+                let end = |i: usize, dst: usize, src: usize| if i == dst - 1 {
+                    src
+                } else {
+                    i * 2 + 2
+                };
+                for y in 0..dst_h {
+                    for x in 0..dst_w {
+                        let mut farthest: f32 = -1.0;
+                        for sy in (y * 2)..end(y, dst_h, src_h) {
+                            for sx in (x * 2)..end(x, dst_w, src_w) {
+                                farthest = farthest.max(src[sy * src_w + sx]);
+                            }
+                        }
+                        dst[y * dst_w + x] = farthest;
+                    }
+                }
+            }
+            if self.pyramid.len() > level {
+                self.pyramid[level] = dst;
+            } else {
+                self.pyramid.push(dst);
+            }
+            level += 1;
+        }
+        self.pyramid.truncate(level);
+    }
+
+    /// Returns `true` if the sphere, in view space, is entirely behind the occluders.
+    fn is_occluded(&self, proj: &Matrix4<f32>, center: Point3<f32>, radius: f32) -> bool {
+        // The camera looks towards negative z in view space.
+        let nearest = *proj * Vector4::new(center.x, center.y, center.z + radius, 1.0);
+        if nearest.w <= 1e-5 {
+            return false;
+        }
+        let nearest_z = nearest.z / nearest.w;
+
+        let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+        let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for &dx in &[-radius, radius] {
+            for &dy in &[-radius, radius] {
+                for &dz in &[-radius, radius] {
+                    let corner = Vector4::new(center.x + dx, center.y + dy, center.z + dz, 1.0);
+                    let clip = *proj * corner;
+                    if clip.w <= 1e-5 {
+                        return false;
+                    }
+                    min_x = min_x.min(clip.x / clip.w);
+                    min_y = min_y.min(clip.y / clip.w);
+                    max_x = max_x.max(clip.x / clip.w);
+                    max_y = max_y.max(clip.y / clip.w);
+                }
+            }
+        }
+        let to_texel = |ndc: f32, size: usize| {
+            ((ndc.max(-1.0).min(1.0) * 0.5 + 0.5) * size as f32) as usize
+        };
+        let x0 = to_texel(min_x, self.width);
+        let y0 = to_texel(min_y, self.height);
+        let x1 = to_texel(max_x, self.width).min(self.width - 1);
+        let y1 = to_texel(max_y, self.height).min(self.height - 1);
+        if x0 > x1 || y0 > y1 {
+            return false;
+        }
+
+        // Pick the level where the rectangle covers at most two texels on each side.
+        let mut level = 0;
+        while level + 1 < self.pyramid.len()
+            && ((x1 >> level) - (x0 >> level) > 1 || (y1 >> level) - (y0 >> level) > 1)
+        {
+            level += 1;
+        }
+        let (level_w, level_h) = self.level_size(level);
+        let depth = &self.pyramid[level];
+        for y in (y0 >> level)..((y1 >> level) + 1).min(level_h) {
+            for x in (x0 >> level)..((x1 >> level) + 1).min(level_w) {
+                if depth[y * level_w + x] >= nearest_z {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+impl<'a> System<'a> for OcclusionCullingSystem {
+    type SystemData = (
+        Entities<'a>,
+        FetchMut<'a, Visibility>,
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, BoundingSphere>,
+        ReadStorage<'a, Occluder>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut visibility, active, camera, global, bounds, occluder): Self::SystemData,
+    ) {
+        let (proj, view) = match get_camera(None, active, &camera, &global) {
+            Some((camera, global)) => match global.0.invert() {
+                Some(view) => (camera.proj, view),
+                None => return,
+            },
+            None => return,
+        };
+        let view_proj = proj * view;
+
+        let size = self.width * self.height;
+        if self.pyramid.is_empty() {
+            self.pyramid.push(vec![1.0; size]);
+        } else {
+            for texel in self.pyramid[0].iter_mut() {
+                *texel = 1.0;
+            }
+        }
+
+        for (occluder, global) in (&occluder, &global).join() {
+            let model_view_proj = view_proj * global.0;
+            let corners = occluder.corners();
+            for triangle in BOX_TRIANGLES.iter() {
+                let mut clip = [Vector4::new(0.0, 0.0, 0.0, 0.0); 3];
+                for (v, &i) in clip.iter_mut().zip(triangle.iter()) {
+                    let corner = corners[i];
+                    *v = model_view_proj * Vector4::new(corner.x, corner.y, corner.z, 1.0);
+                }
+                self.rasterize(&clip);
+            }
+        }
+        self.build_pyramid();
+
+        let mut occluded = Vec::new();
+        for (entity, sphere, global) in (&*entities, &bounds, &global).join() {
+            if !visibility.is_visible(entity) {
+                continue;
+            }
+            let (center, radius) = sphere.transformed(&(view * global.0));
+            if self.is_occluded(&proj, center, radius) {
+                occluded.push(entity);
+            }
+        }
+        for entity in occluded {
+            visibility.visible.remove(entity.id());
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Selection outlines.
+
+use specs::{Component, HashMapStorage};
+
+use color::Rgba;
+
+/// Highlights the mesh of an entity with an outline, drawn by the
+/// `DrawOutline` pass.
+///
+/// The outline is the mesh scaled up around the origin of the entity, drawn in
+/// a flat color where the mesh itself isn't, so it fits convex meshes
+/// centered on their origin best.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outline {
+    /// Color of the outline.
+    pub color: Rgba,
+    /// Scale added to the mesh for the outline, as a fraction of its size.
+    pub thickness: f32,
+}
+
+impl Outline {
+    /// Creates an outline of the given color, 5% larger than the mesh.
+    pub fn new<C: Into<Rgba>>(color: C) -> Self {
+        Outline {
+            color: color.into(),
+            thickness: 0.05,
+        }
+    }
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Outline::new(Rgba(1.0, 0.6, 0.0, 1.0))
+    }
+}
+
+impl Component for Outline {
+    type Storage = HashMapStorage<Self>;
+}
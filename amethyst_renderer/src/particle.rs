@@ -0,0 +1,93 @@
+//! Particle emitter component.
+
+use specs::{Component, DenseVecStorage};
+
+use color::Rgba;
+use tex::TextureHandle;
+
+/// Emits billboarded particles from the position of its entity.
+///
+/// The particles are simulated and drawn by the `DrawParticles` pass.
+/// Curves are lists of keys spread evenly over the lifetime of a particle,
+/// values between keys are linearly interpolated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParticleEmitter {
+    /// Number of particles spawned per second.
+    pub spawn_rate: f32,
+    /// Maximum number of particles alive at the same time.
+    pub max_particles: usize,
+    /// Lifetime of a particle in seconds.
+    pub lifetime: f32,
+    /// Initial velocity of a particle in world space.
+    pub velocity: [f32; 3],
+    /// Maximum random deviation added to each component of the initial velocity.
+    pub spread: f32,
+    /// Acceleration applied to the particles, for example gravity.
+    pub acceleration: [f32; 3],
+    /// Multiplier of the particle velocity over its lifetime.
+    /// An empty curve keeps the velocity unchanged.
+    pub velocity_over_life: Vec<f32>,
+    /// Side length of a particle in world units.
+    pub size: f32,
+    /// Color of a particle over its lifetime, multiplied with the texture.
+    /// An empty curve is white.
+    pub color_over_life: Vec<Rgba>,
+    /// Texture of the particles, or plain squares if `None`.
+    pub texture: Option<TextureHandle>,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        ParticleEmitter {
+            spawn_rate: 10.0,
+            max_particles: 100,
+            lifetime: 1.0,
+            velocity: [0.0, 1.0, 0.0],
+            spread: 0.0,
+            acceleration: [0.0; 3],
+            velocity_over_life: Vec::new(),
+            size: 0.1,
+            color_over_life: Vec::new(),
+            texture: None,
+        }
+    }
+}
+
+impl ParticleEmitter {
+    /// Returns the velocity multiplier at `t`, a fraction of the lifetime.
+    pub fn velocity_at(&self, t: f32) -> f32 {
+        sample(&self.velocity_over_life, t, 1.0, |a, b, f| a + (b - a) * f)
+    }
+
+    /// Returns the color at `t`, a fraction of the lifetime.
+    pub fn color_at(&self, t: f32) -> Rgba {
+        sample(&self.color_over_life, t, Rgba::white(), |a, b, f| {
+            Rgba(
+                a.0 + (b.0 - a.0) * f,
+                a.1 + (b.1 - a.1) * f,
+                a.2 + (b.2 - a.2) * f,
+                a.3 + (b.3 - a.3) * f,
+            )
+        })
+    }
+}
+
+impl Component for ParticleEmitter {
+    type Storage = DenseVecStorage<Self>;
+}
+
+fn sample<T, F>(keys: &[T], t: f32, default: T, lerp: F) -> T
+where
+    T: Copy,
+    F: Fn(T, T, f32) -> T,
+{
+    match keys.len() {
+        0 => default,
+        1 => keys[0],
+        n => {
+            let pos = t.max(0.0).min(1.0) * (n - 1) as f32;
+            let i = (pos as usize).min(n - 2);
+            lerp(keys[i], keys[i + 1], pos - i as f32)
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Bloom post-processing pass.
+
+use gfx::memory::Typed;
+use gfx::texture::{FilterMethod, SamplerInfo, WrapMode};
+use specs::Fetch;
+
+use error::Result;
+use pass::post::{create_fullscreen_effect, draw_fullscreen};
+use pipe::{Effect, NewEffect, Target, TargetBuilder};
+use pipe::pass::{Pass, PassData};
+use resources::BloomParams;
+use types::{Encoder, Factory, RawShaderResourceView, RenderTargetView, Sampler};
+
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/bloom.glsl");
+
+/// Makes bright areas of the stage input glow.
+///
+/// Must be used in a `PostProcessing` stage. The bright parts of the input are
+/// extracted into a half resolution buffer, blurred with a separable gaussian
+/// filter and added back on top of the input. Configured by `BloomParams`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawBloom {
+    sampler: Option<Sampler>,
+    buffers: Option<(Target, Target)>,
+}
+
+impl DrawBloom {
+    /// Create instance of `DrawBloom` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> PassData<'a> for DrawBloom {
+    type Data = Fetch<'a, BloomParams>;
+}
+
+impl Pass for DrawBloom {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        use gfx::Factory;
+
+        self.sampler = Some(effect.factory.create_sampler(SamplerInfo::new(
+            FilterMethod::Bilinear,
+            WrapMode::Clamp,
+        )));
+        let mut builder = create_fullscreen_effect(effect, FRAG_SRC);
+        builder
+            .with_stage_input("source")?
+            .with_texture("bloom")
+            .with_raw_global("mode")
+            .with_raw_global("threshold")
+            .with_raw_global("intensity")
+            .with_output("color", None);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        params: <Self as PassData<'a>>::Data,
+    ) {
+        let out = match effect.data.out_colors.first() {
+            Some(out) => out.clone(),
+            None => return,
+        };
+        let (w, h, _, _) = out.get_dimensions();
+        let size = ((w as u32 / 2).max(1), (h as u32 / 2).max(1));
+        if self.buffers.as_ref().map(|&(ref ping, _)| ping.size()) != Some(size) {
+            let ping = TargetBuilder::new("bloom_ping").build(&mut factory, size);
+            let pong = TargetBuilder::new("bloom_pong").build(&mut factory, size);
+            match (ping, pong) {
+                (Ok((_, ping)), Ok((_, pong))) => self.buffers = Some((ping, pong)),
+                _ => {
+                    eprintln!("WARNING: Failed to create bloom buffers!");
+                    return;
+                }
+            }
+        }
+
+        let sampler = self.sampler.as_ref().unwrap();
+        let (ref ping, ref pong) = *self.buffers.as_ref().unwrap();
+        let source = effect.data.textures[0].clone();
+
+        effect.update_global("threshold", params.threshold);
+        effect.update_global("intensity", params.intensity);
+
+        draw_step(effect, encoder, sampler, 0, &source, output(ping));
+        for _ in 0..params.iterations {
+            draw_step(effect, encoder, sampler, 1, input(ping), output(pong));
+            draw_step(effect, encoder, sampler, 2, input(pong), output(ping));
+        }
+        draw_step(effect, encoder, sampler, 3, input(ping), &out);
+    }
+}
+
+fn input(target: &Target) -> &RawShaderResourceView {
+    target.color_bufs()[0].as_input.as_ref().unwrap().raw()
+}
+
+fn output(target: &Target) -> &RenderTargetView {
+    &target.color_bufs()[0].as_output
+}
+
+fn draw_step(
+    effect: &mut Effect,
+    encoder: &mut Encoder,
+    sampler: &Sampler,
+    mode: i32,
+    bloom: &RawShaderResourceView,
+    out: &RenderTargetView,
+) {
+    effect.update_global("mode", mode);
+    effect.data.out_colors[0] = out.clone();
+    effect.data.textures.push(bloom.clone());
+    effect.data.samplers.push(sampler.clone());
+    draw_fullscreen(effect, encoder);
+    effect.clear();
+}
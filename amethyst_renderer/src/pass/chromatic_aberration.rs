@@ -0,0 +1,53 @@
+//! Chromatic aberration post-processing pass.
+
+use specs::Fetch;
+
+use error::Result;
+use pass::post::{create_fullscreen_effect, draw_fullscreen};
+use pipe::{Effect, NewEffect};
+use pipe::pass::{Pass, PassData};
+use resources::ChromaticAberrationParams;
+use types::{Encoder, Factory};
+
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/chromatic_aberration.glsl");
+
+/// Offsets the red and blue channels of the stage input away from each other
+/// towards the edges of the screen, like a cheap lens would.
+///
+/// Must be used in a `PostProcessing` stage. Configured by
+/// `ChromaticAberrationParams`, which can be changed every frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawChromaticAberration;
+
+impl DrawChromaticAberration {
+    /// Create instance of `DrawChromaticAberration` pass
+    pub fn new() -> Self {
+        DrawChromaticAberration
+    }
+}
+
+impl<'a> PassData<'a> for DrawChromaticAberration {
+    type Data = Fetch<'a, ChromaticAberrationParams>;
+}
+
+impl Pass for DrawChromaticAberration {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = create_fullscreen_effect(effect, FRAG_SRC);
+        builder
+            .with_stage_input("source")?
+            .with_raw_global("intensity")
+            .with_output("color", None);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        params: <Self as PassData<'a>>::Data,
+    ) {
+        effect.update_global("intensity", params.intensity);
+        draw_fullscreen(effect, encoder);
+    }
+}
@@ -0,0 +1,250 @@
+//! Clustered light culling of the forward passes.
+
+use amethyst_core::GlobalTransform;
+use amethyst_core::cgmath::{Matrix4, SquareMatrix, Vector3, Vector4};
+use gfx::format::{ChannelType, Rgba32F, SurfaceType, R32_G32_B32_A32};
+use gfx::handle;
+use gfx::memory::{cast_slice, Typed};
+use gfx::texture::NewImageInfo;
+
+use cam::Camera;
+use error::Result;
+use pipe::{Effect, EffectBuilder};
+use tex::{Texture, TextureBuilder};
+use types::{Encoder, Factory, Resources};
+
+/// Number of clusters across the width, height and depth of the view, has
+/// to match the shaders.
+const CLUSTERS: [usize; 3] = [16, 9, 24];
+
+/// Maximum number of point lights, the others are ignored.
+pub(crate) const MAX_POINT_LIGHTS: usize = 1024;
+
+/// Texels holding each point light.
+pub(crate) const POINT_LIGHT_TEXELS: usize = 4;
+
+/// Width of the light index texture, each texel holding 4 indices. Has to
+/// match the shaders.
+const INDEX_WIDTH: usize = 1024;
+
+/// Maximum number of rows of the light index texture.
+const INDEX_ROWS: usize = 16;
+
+/// Closest depth of the clusters, the depth slices grow exponentially from
+/// there.
+const MIN_NEAR: f32 = 0.05;
+
+/// The point lights of a forward pass, along with the lights reaching each
+/// cluster of the view of its camera.
+///
+/// The view is split into a grid of clusters, with depth slices growing
+/// exponentially. Each frame, the bounding sphere of the point lights is
+/// tested against the clusters on the CPU. The shaders then only loop over
+/// the lights of the cluster of the fragment, or all the lights for the
+/// fragments outside of the clusters.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct LightClusters {
+    lights: Texture,
+    grid: Texture,
+    indices: Texture,
+    cluster_lights: Vec<Vec<usize>>,
+}
+
+impl LightClusters {
+    /// Creates the textures holding the lights and clusters.
+    pub fn new(factory: &mut Factory) -> Result<Self> {
+        let clusters = CLUSTERS[0] * CLUSTERS[1] * CLUSTERS[2];
+        Ok(LightClusters {
+            lights: data_texture(factory, POINT_LIGHT_TEXELS, MAX_POINT_LIGHTS)?,
+            grid: data_texture(factory, CLUSTERS[0] * CLUSTERS[1], CLUSTERS[2])?,
+            indices: data_texture(factory, INDEX_WIDTH, INDEX_ROWS)?,
+            cluster_lights: vec![Vec::new(); clusters],
+        })
+    }
+
+    /// Uploads the point lights, given as `POINT_LIGHT_TEXELS` texels each
+    /// along with their bounding sphere, and their clusters for the view of
+    /// the camera.
+    pub fn update(
+        &mut self,
+        effect: &mut Effect,
+        encoder: &mut Encoder,
+        texels: &[[f32; 4]],
+        spheres: &[(Vector3<f32>, f32)],
+        camera: Option<(&Camera, &GlobalTransform)>,
+    ) {
+        upload(encoder, &self.lights, POINT_LIGHT_TEXELS, texels);
+
+        let view = camera.and_then(|(camera, transform)| {
+            let view = transform.0.invert()?;
+            let (near, far) = depth_range(camera.proj)?;
+            Some((camera.proj, view, near, far))
+        });
+        let (proj, view, near, far) = match view {
+            Some(view) => view,
+            None => {
+                effect.update_global("clustered", 0);
+                return;
+            }
+        };
+
+        for lights in &mut self.cluster_lights {
+            lights.clear();
+        }
+        for (i, &(center, radius)) in spheres.iter().enumerate() {
+            let center = view * center.extend(1.0);
+            let (min, max) = match cluster_bounds(proj, center.truncate(), radius, near, far) {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+            for z in min[2]..max[2] + 1 {
+                for y in min[1]..max[1] + 1 {
+                    for x in min[0]..max[0] + 1 {
+                        let cluster = x + (y + z * CLUSTERS[1]) * CLUSTERS[0];
+                        self.cluster_lights[cluster].push(i);
+                    }
+                }
+            }
+        }
+
+        // Lights past the capacity of the index texture are dropped from
+        // their clusters.
+        let capacity = INDEX_WIDTH * INDEX_ROWS * 4;
+        let mut grid = Vec::with_capacity(self.cluster_lights.len());
+        let mut indices = Vec::new();
+        for lights in &self.cluster_lights {
+            let count = lights.len().min(capacity - indices.len());
+            grid.push([indices.len() as f32, count as f32, 0.0, 0.0]);
+            indices.extend(lights[..count].iter().map(|&i| i as f32));
+        }
+        let rows = (indices.len() + INDEX_WIDTH * 4 - 1) / (INDEX_WIDTH * 4);
+        indices.resize(rows * INDEX_WIDTH * 4, 0.0);
+        let indices = indices
+            .chunks(4)
+            .map(|chunk| [chunk[0], chunk[1], chunk[2], chunk[3]])
+            .collect::<Vec<_>>();
+
+        upload(encoder, &self.grid, CLUSTERS[0] * CLUSTERS[1], &grid);
+        upload(encoder, &self.indices, INDEX_WIDTH, &indices);
+        effect.update_global("clustered", 1);
+        effect.update_global("cluster_view", Into::<[[f32; 4]; 4]>::into(view));
+        effect.update_global("cluster_proj", Into::<[[f32; 4]; 4]>::into(proj));
+        effect.update_global("cluster_depth", [near, far]);
+    }
+}
+
+pub(crate) fn setup_light_clusters(builder: &mut EffectBuilder, clusters: &LightClusters) {
+    builder
+        .with_persistent_texture("point_lights", &clusters.lights)
+        .with_persistent_texture("light_grid", &clusters.grid)
+        .with_persistent_texture("light_indices", &clusters.indices)
+        .with_raw_global("clustered")
+        .with_raw_global("cluster_view")
+        .with_raw_global("cluster_proj")
+        .with_raw_global("cluster_depth");
+}
+
+fn data_texture(factory: &mut Factory, width: usize, height: usize) -> Result<Texture> {
+    TextureBuilder::new(vec![[0.0f32; 4]; width * height])
+        .with_size(width as u16, height as u16)
+        .with_format(SurfaceType::R32_G32_B32_A32)
+        .with_channel_type(ChannelType::Float)
+        .build(factory)
+}
+
+/// Writes whole rows of texels at the top of a texture.
+fn upload(encoder: &mut Encoder, texture: &Texture, width: usize, texels: &[[f32; 4]]) {
+    let (_, height, _, _) = texture.raw().get_info().kind.get_dimensions();
+    let rows = (texels.len() / width).min(height as usize);
+    if rows == 0 {
+        return;
+    }
+    let typed = handle::Texture::<Resources, R32_G32_B32_A32>::new(texture.raw().clone());
+    let info = NewImageInfo {
+        xoffset: 0,
+        yoffset: 0,
+        zoffset: 0,
+        width: width as u16,
+        height: rows as u16,
+        depth: 0,
+        format: (),
+        mipmap: 0,
+    };
+    let data = cast_slice(&texels[..rows * width]);
+    if let Err(err) = encoder.update_texture::<R32_G32_B32_A32, Rgba32F>(&typed, None, info, data) {
+        eprintln!("WARNING: Failed to upload the light clusters: {:?}", err);
+    }
+}
+
+/// Returns the distances to the near and far planes of a projection.
+fn depth_range(proj: Matrix4<f32>) -> Option<(f32, f32)> {
+    let inverse = proj.invert()?;
+    let depth = |z: f32| {
+        let point = inverse * Vector4::new(0.0, 0.0, z, 1.0);
+        -point.z / point.w
+    };
+    let near = depth(-1.0).max(MIN_NEAR);
+    let far = depth(1.0);
+    if far > near {
+        Some((near, far))
+    } else {
+        None
+    }
+}
+
+/// Returns the first and last clusters, along each axis, touched by a
+/// sphere in view space.
+fn cluster_bounds(
+    proj: Matrix4<f32>,
+    center: Vector3<f32>,
+    radius: f32,
+    near: f32,
+    far: f32,
+) -> Option<([usize; 3], [usize; 3])> {
+    let (closest, farthest) = (-center.z - radius, -center.z + radius);
+    if farthest < near || closest > far {
+        return None;
+    }
+    let slice = |depth: f32| {
+        let slice = (depth / near).ln() / (far / near).ln() * CLUSTERS[2] as f32;
+        (slice.max(0.0) as usize).min(CLUSTERS[2] - 1)
+    };
+
+    // Projects the corners of the box around the sphere, the whole view is
+    // covered when one of them is behind the camera.
+    let mut min = [1.0f32; 2];
+    let mut max = [-1.0f32; 2];
+    for &(x, y, z) in &[
+        (-1.0, -1.0, -1.0),
+        (1.0, -1.0, -1.0),
+        (-1.0, 1.0, -1.0),
+        (1.0, 1.0, -1.0),
+        (-1.0, -1.0, 1.0),
+        (1.0, -1.0, 1.0),
+        (-1.0, 1.0, 1.0),
+        (1.0, 1.0, 1.0),
+    ] {
+        let corner = center + Vector3::new(x, y, z) * radius;
+        let clip = proj * corner.extend(1.0);
+        if clip.w <= 0.0 {
+            min = [-1.0; 2];
+            max = [1.0; 2];
+            break;
+        }
+        for axis in 0..2 {
+            min[axis] = min[axis].min(clip[axis] / clip.w);
+            max[axis] = max[axis].max(clip[axis] / clip.w);
+        }
+    }
+    if min[0] > 1.0 || min[1] > 1.0 || max[0] < -1.0 || max[1] < -1.0 {
+        return None;
+    }
+    let cluster = |ndc: f32, axis: usize| {
+        let cluster = (ndc * 0.5 + 0.5) * CLUSTERS[axis] as f32;
+        (cluster.max(0.0) as usize).min(CLUSTERS[axis] - 1)
+    };
+    Some((
+        [cluster(min[0], 0), cluster(min[1], 1), slice(closest.max(near))],
+        [cluster(max[0], 0), cluster(max[1], 1), slice(farthest.min(far))],
+    ))
+}
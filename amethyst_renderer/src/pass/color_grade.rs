@@ -0,0 +1,115 @@
+//! Color grading pass.
+
+use amethyst_assets::AssetStorage;
+use gfx::texture::{FilterMethod, SamplerInfo, WrapMode};
+use specs::Fetch;
+
+use error::Result;
+use pass::post::{create_fullscreen_effect, draw_fullscreen};
+use pipe::{Effect, NewEffect};
+use pipe::pass::{Pass, PassData};
+use tex::{Texture, TextureBuilder, TextureHandle};
+use types::{Encoder, Factory};
+
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/color_grade.glsl");
+
+/// Number of entries along each axis of the identity lookup table.
+const IDENTITY_SIZE: usize = 16;
+
+/// Grades the colors of the stage input through a 3D lookup table.
+///
+/// Must be used in a `PostProcessing` stage, usually the last one and after
+/// the tonemapping of HDR pipelines. The table is a texture unwrapped into a
+/// horizontal strip of `size` square slices of `size` by `size` texels, one
+/// slice per blue value, with red growing to the right and green downwards
+/// within each slice, as exported by most grading tools. It should be loaded
+/// with a linear channel type and a bilinear filter.
+///
+/// Until the table is loaded, or without one, an identity table leaves the
+/// colors unchanged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawColorGrade {
+    lut: Option<TextureHandle>,
+    intensity: f32,
+    identity: Option<Texture>,
+}
+
+impl DrawColorGrade {
+    /// Create instance of `DrawColorGrade` pass
+    pub fn new() -> Self {
+        DrawColorGrade {
+            lut: None,
+            intensity: 1.0,
+            identity: None,
+        }
+    }
+
+    /// Grade the colors through the given lookup table
+    pub fn with_lut(mut self, lut: TextureHandle) -> Self {
+        self.lut = Some(lut);
+        self
+    }
+
+    /// Blends between the input colors, at 0.0, and the graded ones, at 1.0.
+    /// Defaults to 1.0.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+}
+
+impl<'a> PassData<'a> for DrawColorGrade {
+    type Data = Fetch<'a, AssetStorage<Texture>>;
+}
+
+impl Pass for DrawColorGrade {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let identity = identity_lut(effect.factory)?;
+        let mut builder = create_fullscreen_effect(effect, FRAG_SRC);
+        builder
+            .with_stage_input("source")?
+            .with_persistent_texture("lut", &identity)
+            .with_raw_global("intensity")
+            .with_raw_global("srgb")
+            .with_output("color", None);
+        self.identity = Some(identity);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        tex_storage: <Self as PassData<'a>>::Data,
+    ) {
+        let lut = self.lut
+            .as_ref()
+            .and_then(|lut| tex_storage.get(lut))
+            .or(self.identity.as_ref());
+        if let Some(lut) = lut {
+            effect.update_texture("lut", lut);
+        }
+        effect.update_global("intensity", self.intensity);
+        effect.update_global("srgb", cfg!(feature = "srgb") as i32);
+        draw_fullscreen(effect, encoder);
+    }
+}
+
+/// Creates the lookup table mapping each color to itself.
+fn identity_lut(factory: &mut Factory) -> Result<Texture> {
+    let size = IDENTITY_SIZE;
+    let value = |i: usize| (i * 255 / (size - 1)) as u8;
+    let mut data = Vec::with_capacity(size * size * size);
+    for green in 0..size {
+        for blue in 0..size {
+            for red in 0..size {
+                data.push([value(red), value(green), value(blue), 255]);
+            }
+        }
+    }
+    TextureBuilder::new(data)
+        .with_size((size * size) as u16, size as u16)
+        .with_sampler(SamplerInfo::new(FilterMethod::Bilinear, WrapMode::Clamp))
+        .build(factory)
+}
@@ -0,0 +1,87 @@
+//! Debug lines drawing pass.
+
+use amethyst_core::transform::GlobalTransform;
+use gfx::{IndexBuffer, Primitive};
+use gfx::handle::Buffer;
+use gfx::pso::buffer::ElemStride;
+use specs::{Fetch, FetchMut, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use debug_lines::DebugLines;
+use error::Result;
+use pass::util::{get_camera, set_vertex_args, setup_vertex_args, update_dynamic_buffer};
+use pipe::{DepthMode, Effect, NewEffect};
+use pipe::pass::{Pass, PassData};
+use types::{Encoder, Factory, Resources, Slice};
+use vertex::{PosColor, VertexFormat};
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/debug_lines.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/debug_lines.glsl");
+
+/// Draws the lines of the `DebugLines` resource, and clears it afterwards.
+///
+/// The lines are depth tested against the scene but don't write depth.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawDebugLines {
+    buffer: Option<Buffer<Resources, PosColor>>,
+}
+
+impl DrawDebugLines {
+    /// Create instance of `DrawDebugLines` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> PassData<'a> for DrawDebugLines {
+    type Data = (
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        FetchMut<'a, DebugLines>,
+    );
+}
+
+impl Pass for DrawDebugLines {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder
+            .with_primitive_type(Primitive::LineList)
+            .with_raw_vertex_buffer(PosColor::ATTRIBUTES, PosColor::size() as ElemStride, 0);
+        setup_vertex_args(&mut builder);
+        builder.with_output("color", Some(DepthMode::LessEqualTest));
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (active, camera, global, mut lines): <Self as PassData<'a>>::Data,
+    ) {
+        use gfx::memory::Typed;
+
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+        let count = lines.vertices().len();
+        if count > 0
+            && update_dynamic_buffer(&mut factory, encoder, &mut self.buffer, lines.vertices())
+        {
+            effect
+                .data
+                .vertex_bufs
+                .push(self.buffer.as_ref().unwrap().raw().clone());
+            set_vertex_args(effect, encoder, camera, &GlobalTransform::default());
+            let slice = Slice {
+                start: 0,
+                end: count as u32,
+                base_vertex: 0,
+                instances: None,
+                buffer: IndexBuffer::Auto,
+            };
+            effect.draw(&slice, encoder);
+            effect.clear();
+        }
+        lines.clear();
+    }
+}
@@ -0,0 +1,191 @@
+//! Decal projection pass.
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::cgmath::SquareMatrix;
+use amethyst_core::transform::GlobalTransform;
+use gfx::IndexBuffer;
+use gfx::preset::blend;
+use gfx::state::{Blend, ColorMask, CullFace};
+use specs::{Entities, Fetch, Join, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use decal::Decal;
+use error::Result;
+use hidden::Hidden;
+use mtl::{Material, MaterialDefaults};
+use pass::util::{add_textures, albedo_uv_transform, get_camera, get_camera_mask,
+                 set_textured_vertex_args, setup_textures, setup_vertex_args, TextureType};
+use pipe::{DepthMode, Effect, NewEffect, TargetInput};
+use pipe::pass::{Pass, PassData};
+use render_mask::{in_render_mask, RenderMask};
+use tex::Texture;
+use types::{Encoder, Factory, Slice};
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/decal.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/decal.glsl");
+
+static TEXTURES: [TextureType; 1] = [TextureType::Albedo];
+
+/// Draws the `Decal`s onto the geometry already in the stage target.
+///
+/// The surfaces are reconstructed from the depth buffer of another target,
+/// filled by a depth pre-pass like `DrawDepth`, so the decals follow them
+/// without any geometry of their own. The stage viewport has to cover its
+/// whole target, with the same size as the depth target.
+///
+/// By default the back faces of the decal boxes are drawn without depth test,
+/// which keeps working with the camera inside a box. `with_depth_offset`
+/// draws the front faces tested against the depth buffer of the stage target
+/// instead, which shades fewer pixels for small decals.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawDecal {
+    depth: String,
+    blend: (ColorMask, Blend),
+    depth_offset: Option<(i32, i32)>,
+}
+
+impl DrawDecal {
+    /// Create instance of `DrawDecal` pass reading the depth buffer of the
+    /// given target, decals are alpha blended.
+    pub fn new<N: Into<String>>(depth: N) -> Self {
+        DrawDecal {
+            depth: depth.into(),
+            blend: (ColorMask::all(), blend::ALPHA),
+            depth_offset: None,
+        }
+    }
+
+    /// Blend the decals with the given settings
+    pub fn with_blend(mut self, mask: ColorMask, blend: Blend) -> Self {
+        self.blend = (mask, blend);
+        self
+    }
+
+    /// Test the front faces of the decal boxes against the depth buffer of
+    /// the stage target, offset by the given polygon offset to avoid
+    /// z-fighting with the surfaces they lie on.
+    pub fn with_depth_offset(mut self, slope: i32, units: i32) -> Self {
+        self.depth_offset = Some((slope, units));
+        self
+    }
+}
+
+impl<'a> PassData<'a> for DrawDecal {
+    type Data = (
+        Entities<'a>,
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        Fetch<'a, AssetStorage<Texture>>,
+        Fetch<'a, MaterialDefaults>,
+        ReadStorage<'a, Decal>,
+        ReadStorage<'a, Material>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
+    );
+}
+
+impl Pass for DrawDecal {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        setup_vertex_args(&mut builder);
+        builder
+            .with_input("depth", TargetInput::Depth(self.depth.clone()))
+            .with_raw_global("inv_view_proj")
+            .with_raw_global("inv_model")
+            .with_raw_global("opacity")
+            .with_raw_global("fade");
+        setup_textures(&mut builder, &TEXTURES);
+        let (mask, blend) = self.blend;
+        match self.depth_offset {
+            Some((slope, units)) => {
+                builder.with_depth_offset(slope, units).with_blended_output(
+                    "color",
+                    mask,
+                    blend,
+                    Some(DepthMode::LessEqualTest),
+                );
+            }
+            None => {
+                builder
+                    .with_cull_face(CullFace::Front)
+                    .with_blended_output("color", mask, blend, None);
+            }
+        }
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (
+            entities,
+            active,
+            camera,
+            tex_storage,
+            material_defaults,
+            decal,
+            material,
+            global,
+            hidden,
+            render_mask,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+        let inv_view_proj = match camera {
+            Some((camera, transform)) => transform
+                .0
+                .invert()
+                .and_then(|view| (camera.proj * view).invert()),
+            None => None,
+        };
+        let inv_view_proj = match inv_view_proj {
+            Some(inv_view_proj) => inv_view_proj,
+            None => return,
+        };
+
+        let slice = Slice {
+            start: 0,
+            end: 36,
+            base_vertex: 0,
+            instances: None,
+            buffer: IndexBuffer::Auto,
+        };
+        for (entity, decal, material, global, _) in
+            (&*entities, &decal, &material, &global, !&hidden).join()
+        {
+            if !in_render_mask(mask, &render_mask, entity) {
+                continue;
+            }
+            let inv_model = match global.0.invert() {
+                Some(inv_model) => inv_model,
+                None => continue,
+            };
+            let uv_transform = albedo_uv_transform(&tex_storage, material, &material_defaults);
+            set_textured_vertex_args(effect, encoder, camera, global, uv_transform);
+            effect.update_global("inv_view_proj", Into::<[[f32; 4]; 4]>::into(inv_view_proj));
+            effect.update_global("inv_model", Into::<[[f32; 4]; 4]>::into(inv_model));
+            effect.update_global("opacity", decal.opacity);
+            effect.update_global("fade", decal.fade.max(0.0).min(1.0));
+            add_textures(
+                effect,
+                &tex_storage,
+                material,
+                &material_defaults.0,
+                &TEXTURES,
+            );
+            effect.draw(&slice, encoder);
+            effect.clear();
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Depth of field post-processing pass.
+
+use amethyst_core::cgmath::{Matrix4, SquareMatrix};
+use amethyst_core::transform::GlobalTransform;
+use specs::{Fetch, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use error::{Error, Result};
+use pass::post::{create_fullscreen_effect, draw_fullscreen};
+use pass::util::get_camera;
+use pipe::{Effect, NewEffect, TargetInput};
+use pipe::pass::{Pass, PassData};
+use resources::DofParams;
+use types::{Encoder, Factory};
+
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/dof.glsl");
+
+/// Blurs the stage input away from the focus distance of the camera.
+///
+/// Must be used in a `PostProcessing` stage whose input has a depth buffer.
+/// Each pixel gathers a disc of samples whose size follows the distance of
+/// their surface to the focus, which gives round bokeh shapes to bright
+/// spots. Configured by `DofParams`, which can focus on the surface at the
+/// center of the screen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawDof;
+
+impl DrawDof {
+    /// Create instance of `DrawDof` pass
+    pub fn new() -> Self {
+        DrawDof
+    }
+}
+
+impl<'a> PassData<'a> for DrawDof {
+    type Data = (
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        Fetch<'a, DofParams>,
+    );
+}
+
+impl Pass for DrawDof {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let input = effect.input().ok_or(Error::NoStageInput)?;
+        let mut builder = create_fullscreen_effect(effect, FRAG_SRC);
+        builder
+            .with_stage_input("source")?
+            .with_input("depth", TargetInput::Depth(input.to_string()))
+            .with_raw_global("inv_proj")
+            .with_raw_global("focus_distance")
+            .with_raw_global("aperture")
+            .with_raw_global("max_blur")
+            .with_raw_global("auto_focus")
+            .with_raw_global("samples")
+            .with_output("color", None);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (active, camera, global, params): <Self as PassData<'a>>::Data,
+    ) {
+        let inv_proj = get_camera(effect.camera(), active, &camera, &global)
+            .and_then(|(camera, _)| camera.proj.invert())
+            .unwrap_or_else(Matrix4::identity);
+
+        effect.update_global("inv_proj", Into::<[[f32; 4]; 4]>::into(inv_proj));
+        effect.update_global("focus_distance", params.focus_distance);
+        effect.update_global("aperture", params.aperture);
+        effect.update_global("max_blur", params.max_blur);
+        effect.update_global("auto_focus", params.auto_focus as i32);
+        effect.update_global("samples", params.samples as i32);
+        draw_fullscreen(effect, encoder);
+    }
+}
@@ -0,0 +1,56 @@
+//! Image based lighting utilities for the physically based passes.
+
+use amethyst_assets::AssetStorage;
+
+use error::Result;
+use pipe::{Effect, EffectBuilder};
+use resources::EnvironmentMap;
+use tex::{Texture, TextureBuilder};
+use types::Factory;
+
+/// Creates the black cubemap and lookup table which are bound while there
+/// is no `EnvironmentMap`.
+pub(crate) fn default_environment(factory: &mut Factory) -> Result<(Texture, Texture)> {
+    let cube = TextureBuilder::new([0u8; 4 * 6])
+        .with_cube_size(1)
+        .build(factory)?;
+    let lut = TextureBuilder::new([0u8; 4]).build(factory)?;
+    Ok((cube, lut))
+}
+
+pub(crate) fn setup_environment_map(builder: &mut EffectBuilder, defaults: &(Texture, Texture)) {
+    builder
+        .with_persistent_texture("irradiance_map", &defaults.0)
+        .with_persistent_texture("specular_map", &defaults.0)
+        .with_persistent_texture("brdf_lut", &defaults.1)
+        .with_raw_global("specular_levels")
+        .with_raw_global("ibl_enabled");
+}
+
+pub(crate) fn set_environment_args(
+    effect: &mut Effect,
+    storage: &AssetStorage<Texture>,
+    environment: Option<&EnvironmentMap>,
+) {
+    let maps = environment.map(|env| {
+        (
+            storage.get(&env.irradiance),
+            storage.get(&env.specular),
+            storage.get(&env.brdf_lut),
+            env.specular_levels,
+        )
+    });
+    match maps {
+        Some((Some(irradiance), Some(specular), Some(brdf_lut), levels)) => {
+            effect.update_texture("irradiance_map", irradiance);
+            effect.update_texture("specular_map", specular);
+            effect.update_texture("brdf_lut", brdf_lut);
+            effect.update_global("specular_levels", levels.max(1) as f32);
+            effect.update_global("ibl_enabled", 1);
+        }
+        _ => {
+            effect.update_global("specular_levels", 1.0);
+            effect.update_global("ibl_enabled", 0);
+        }
+    }
+}
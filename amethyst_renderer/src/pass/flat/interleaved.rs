@@ -4,22 +4,30 @@ use std::marker::PhantomData;
 
 use amethyst_assets::AssetStorage;
 use amethyst_core::transform::GlobalTransform;
+use fnv::FnvHashMap as HashMap;
+use gfx::Primitive;
+use gfx::handle::Buffer;
 use gfx::pso::buffer::ElemStride;
 use gfx_core::state::{Blend, ColorMask};
-use specs::{Fetch, Join, ReadStorage};
+use specs::{Entities, Fetch, Join, ReadStorage};
 
 use super::*;
 use cam::{ActiveCamera, Camera};
 use error::Result;
+use hidden::Hidden;
 use mesh::{Mesh, MeshHandle};
-use mtl::{Material, MaterialDefaults};
-use pass::util::{draw_mesh, get_camera, VertexArgs};
+use mtl::{CustomUniforms, Material, MaterialDefaults};
+use pass::util::{draw_mesh, draw_mesh_instanced, get_camera, get_camera_mask, is_visible,
+                 setup_textures, VertexArgs};
 use pipe::{DepthMode, Effect, NewEffect};
 use pipe::pass::{Pass, PassData};
+use render_mask::{in_render_mask, RenderMask};
 use tex::Texture;
 use transparent::{Transparent, TransparentBackToFront};
-use types::{Encoder, Factory};
-use vertex::{Position, Query, TexCoord};
+use types::{Encoder, Factory, Resources};
+use vertex::{InstanceTransform, Position, Query, TexCoord, VertexFormat};
+use visibility::Visibility;
+use z_order::{sort_by_z_order, ZOrder};
 
 /// Draw mesh without lighting
 /// `V` is `VertexFormat`
@@ -28,6 +36,11 @@ use vertex::{Position, Query, TexCoord};
 pub struct DrawFlat<V> {
     _pd: PhantomData<V>,
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    instancing: bool,
+    instances: Option<Buffer<Resources, InstanceTransform>>,
+    custom_shader: Option<(&'static [u8], CustomUniforms)>,
+    #[derivative(Default(value = "Primitive::TriangleList"))]
+    prim: Primitive,
 }
 
 impl<V> DrawFlat<V>
@@ -50,6 +63,40 @@ where
         self.transparency = Some((mask, blend, depth));
         self
     }
+
+    /// Enable instancing
+    ///
+    /// Opaque entities sharing the same mesh and material are drawn with a
+    /// single instanced draw call.
+    pub fn with_instancing(mut self) -> Self {
+        self.instancing = true;
+        self
+    }
+
+    /// Draw the meshes of the given primitive type, like lines or points,
+    /// instead of triangle lists
+    ///
+    /// The meshes of the other types are ignored, so drawing all of them
+    /// takes a pass per type.
+    pub fn with_primitive(mut self, prim: Primitive) -> Self {
+        self.prim = prim;
+        self
+    }
+
+    /// Draw with a custom GLSL fragment shader, declaring the given uniforms
+    ///
+    /// The values of `uniforms` are the defaults, overridden per entity by
+    /// its `CustomUniforms` component.
+    pub fn with_custom_shader(mut self, frag: &'static [u8], uniforms: CustomUniforms) -> Self {
+        self.custom_shader = Some((frag, uniforms));
+        self
+    }
+
+    fn reset_uniforms(&self, effect: &mut Effect) {
+        if let Some((_, ref defaults)) = self.custom_shader {
+            effect.update_custom_uniforms(defaults);
+        }
+    }
 }
 
 impl<'a, V> PassData<'a> for DrawFlat<V>
@@ -57,7 +104,9 @@ where
     V: Query<(Position, TexCoord)>,
 {
     type Data = (
+        Entities<'a>,
         Option<Fetch<'a, ActiveCamera>>,
+        Option<Fetch<'a, Visibility>>,
         ReadStorage<'a, Camera>,
         Fetch<'a, AssetStorage<Mesh>>,
         Fetch<'a, AssetStorage<Texture>>,
@@ -67,6 +116,10 @@ where
         ReadStorage<'a, Material>,
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, Transparent>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
+        ReadStorage<'a, CustomUniforms>,
+        ReadStorage<'a, ZOrder>,
     );
 }
 
@@ -76,11 +129,33 @@ where
 {
     fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
         use std::mem;
-        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        let vert = if self.instancing {
+            INSTANCED_VERT_SRC
+        } else {
+            VERT_SRC
+        };
+        let frag = match self.custom_shader {
+            Some((frag, _)) => frag,
+            None => FRAG_SRC,
+        };
+        let mut builder = effect.simple(vert, frag);
         builder
+            .with_primitive_type(self.prim)
             .with_raw_constant_buffer("VertexArgs", mem::size_of::<VertexArgs>(), 1)
-            .with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0)
-            .with_texture("albedo");
+            .with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0);
+        if self.instancing {
+            builder.with_raw_vertex_buffer(
+                InstanceTransform::ATTRIBUTES,
+                InstanceTransform::size() as ElemStride,
+                1,
+            );
+        }
+        setup_textures(&mut builder, &TEXTURES);
+        if let Some((_, ref uniforms)) = self.custom_shader {
+            for &(ref name, _) in uniforms.iter() {
+                builder.with_raw_global(name);
+            }
+        }
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
             None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
@@ -92,9 +167,11 @@ where
         &'a mut self,
         encoder: &mut Encoder,
         effect: &mut Effect,
-        _factory: Factory,
+        mut factory: Factory,
         (
+            entities,
             active,
+            visibility,
             camera,
             mesh_storage,
             tex_storage,
@@ -104,11 +181,151 @@ where
             material,
             global,
             transparent,
+            hidden,
+            render_mask,
+            custom,
+            z_order,
         ): <Self as PassData<'a>>::Data,
     ) {
-        let camera = get_camera(active, &camera, &global);
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+
+        if self.instancing {
+            let mut groups = HashMap::default();
+            let opaque = (
+                &*entities,
+                &mesh,
+                &material,
+                &global,
+                !&custom,
+                !&transparent,
+                !&hidden,
+            );
+            for (entity, mesh, material, global, _, _, _) in opaque.join() {
+                if !is_visible(effect, &visibility, entity)
+                    || !in_render_mask(mask, &render_mask, entity)
+                {
+                    continue;
+                }
+                let order = z_order.get(entity).cloned().unwrap_or_default();
+                groups
+                    .entry((order, mesh, material))
+                    .or_insert_with(Vec::new)
+                    .push(InstanceTransform {
+                        model: *global.as_ref(),
+                    });
+            }
+            let mut groups = groups.into_iter().collect::<Vec<_>>();
+            groups.sort_by_key(|&((order, _, _), _)| order);
+            for ((_, mesh, material), instances) in groups {
+                self.reset_uniforms(effect);
+                draw_mesh_instanced(
+                    encoder,
+                    effect,
+                    &mut factory,
+                    &mut self.instances,
+                    mesh_storage.get(mesh),
+                    &instances,
+                    &*tex_storage,
+                    material,
+                    &*material_defaults,
+                    None,
+                    camera,
+                    &[V::QUERIED_ATTRIBUTES],
+                    &TEXTURES,
+                );
+            }
+
+            // Entities with their own uniforms can't share a draw call.
+            let mut opaque = (
+                &*entities,
+                &mesh,
+                &material,
+                &global,
+                &custom,
+                !&transparent,
+                !&hidden,
+            ).join()
+                .collect::<Vec<_>>();
+            sort_by_z_order(&mut opaque, &z_order, |item| item.0);
+            for (entity, mesh, material, global, uniforms, _, _) in opaque {
+                if !is_visible(effect, &visibility, entity)
+                    || !in_render_mask(mask, &render_mask, entity)
+                {
+                    continue;
+                }
+                self.reset_uniforms(effect);
+                draw_mesh_instanced(
+                    encoder,
+                    effect,
+                    &mut factory,
+                    &mut self.instances,
+                    mesh_storage.get(mesh),
+                    &[InstanceTransform {
+                        model: *global.as_ref(),
+                    }],
+                    &*tex_storage,
+                    material,
+                    &*material_defaults,
+                    Some(uniforms),
+                    camera,
+                    &[V::QUERIED_ATTRIBUTES],
+                    &TEXTURES,
+                );
+            }
+
+            let mut back_to_front = back_to_front.entities.clone();
+            sort_by_z_order(&mut back_to_front, &z_order, |entity| *entity);
+            for entity in &back_to_front {
+                if !is_visible(effect, &visibility, *entity)
+                    || !in_render_mask(mask, &render_mask, *entity)
+                {
+                    continue;
+                }
+                let mesh = mesh.get(*entity).and_then(|mesh| mesh_storage.get(mesh));
+                let material = material.get(*entity);
+                if let (Some(material), Some(global)) = (material, global.get(*entity)) {
+                    self.reset_uniforms(effect);
+                    draw_mesh_instanced(
+                        encoder,
+                        effect,
+                        &mut factory,
+                        &mut self.instances,
+                        mesh,
+                        &[InstanceTransform {
+                            model: *global.as_ref(),
+                        }],
+                        &*tex_storage,
+                        material,
+                        &*material_defaults,
+                        custom.get(*entity),
+                        camera,
+                        &[V::QUERIED_ATTRIBUTES],
+                        &TEXTURES,
+                    );
+                }
+            }
+            return;
+        }
 
-        for (mesh, material, global, _) in (&mesh, &material, &global, !&transparent).join() {
+        let mut opaque = (&*entities, &mesh, &material, &global, !&transparent, !&hidden)
+            .join()
+            .collect::<Vec<_>>();
+        sort_by_z_order(&mut opaque, &z_order, |item| item.0);
+        for (entity, mesh, material, global, _, _) in opaque {
+            if !is_visible(effect, &visibility, entity)
+                || !in_render_mask(mask, &render_mask, entity)
+            {
+                continue;
+            }
+            self.reset_uniforms(effect);
             draw_mesh(
                 encoder,
                 effect,
@@ -118,6 +335,7 @@ where
                 &*tex_storage,
                 Some(material),
                 &*material_defaults,
+                custom.get(entity),
                 camera,
                 Some(global),
                 &[V::QUERIED_ATTRIBUTES],
@@ -125,8 +343,16 @@ where
             );
         }
 
-        for entity in &back_to_front.entities {
+        let mut back_to_front = back_to_front.entities.clone();
+        sort_by_z_order(&mut back_to_front, &z_order, |entity| *entity);
+        for entity in &back_to_front {
+            if !is_visible(effect, &visibility, *entity)
+                || !in_render_mask(mask, &render_mask, *entity)
+            {
+                continue;
+            }
             if let Some(mesh) = mesh.get(*entity) {
+                self.reset_uniforms(effect);
                 draw_mesh(
                     encoder,
                     effect,
@@ -136,6 +362,7 @@ where
                     &*tex_storage,
                     material.get(*entity),
                     &*material_defaults,
+                    custom.get(*entity),
                     camera,
                     global.get(*entity),
                     &[V::QUERIED_ATTRIBUTES],
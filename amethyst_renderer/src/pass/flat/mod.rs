@@ -6,7 +6,18 @@ mod separate;
 
 use pass::util::TextureType;
 
+#[cfg(not(feature = "gles"))]
 static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/basic.glsl");
+#[cfg(not(feature = "gles"))]
+static INSTANCED_VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/instanced.glsl");
+#[cfg(not(feature = "gles"))]
 static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/flat.glsl");
 
+#[cfg(feature = "gles")]
+static VERT_SRC: &[u8] = include_bytes!("../shaders/gles/vertex/basic.glsl");
+#[cfg(feature = "gles")]
+static INSTANCED_VERT_SRC: &[u8] = include_bytes!("../shaders/gles/vertex/instanced.glsl");
+#[cfg(feature = "gles")]
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/gles/fragment/flat.glsl");
+
 static TEXTURES: [TextureType; 1] = [TextureType::Albedo];
@@ -2,6 +2,7 @@
 
 use amethyst_assets::AssetStorage;
 use amethyst_core::transform::GlobalTransform;
+use gfx::Primitive;
 use gfx::pso::buffer::ElemStride;
 use gfx_core::state::{Blend, ColorMask};
 use specs::{Entities, Fetch, Join, ReadStorage};
@@ -9,17 +10,21 @@ use specs::{Entities, Fetch, Join, ReadStorage};
 use super::*;
 use cam::{ActiveCamera, Camera};
 use error::Result;
+use hidden::Hidden;
 use mesh::{Mesh, MeshHandle};
-use mtl::{Material, MaterialDefaults};
+use mtl::{CustomUniforms, Material, MaterialDefaults};
 use pass::skinning::{create_skinning_effect, setup_skinning_buffers};
-use pass::util::{draw_mesh, get_camera, VertexArgs};
+use pass::util::{draw_mesh, get_camera, get_camera_mask, is_visible, setup_textures, VertexArgs};
 use pipe::{DepthMode, Effect, NewEffect};
 use pipe::pass::{Pass, PassData};
+use render_mask::{in_render_mask, RenderMask};
 use skinning::JointTransforms;
 use tex::Texture;
 use transparent::{Transparent, TransparentBackToFront};
 use types::{Encoder, Factory};
 use vertex::{Attributes, Position, Separate, TexCoord, VertexFormat};
+use visibility::Visibility;
+use z_order::{sort_by_z_order, ZOrder};
 
 static ATTRIBUTES: [Attributes<'static>; 2] = [
     Separate::<Position>::ATTRIBUTES,
@@ -32,6 +37,9 @@ static ATTRIBUTES: [Attributes<'static>; 2] = [
 pub struct DrawFlatSeparate {
     skinning: bool,
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    custom_shader: Option<(&'static [u8], CustomUniforms)>,
+    #[derivative(Default(value = "Primitive::TriangleList"))]
+    prim: Primitive,
 }
 
 impl DrawFlatSeparate
@@ -59,12 +67,38 @@ where
         self.transparency = Some((mask, blend, depth));
         self
     }
+
+    /// Draw the meshes of the given primitive type, like lines or points,
+    /// instead of triangle lists
+    ///
+    /// The meshes of the other types are ignored, so drawing all of them
+    /// takes a pass per type.
+    pub fn with_primitive(mut self, prim: Primitive) -> Self {
+        self.prim = prim;
+        self
+    }
+
+    /// Draw with a custom GLSL fragment shader, declaring the given uniforms
+    ///
+    /// The values of `uniforms` are the defaults, overridden per entity by
+    /// its `CustomUniforms` component.
+    pub fn with_custom_shader(mut self, frag: &'static [u8], uniforms: CustomUniforms) -> Self {
+        self.custom_shader = Some((frag, uniforms));
+        self
+    }
+
+    fn reset_uniforms(&self, effect: &mut Effect) {
+        if let Some((_, ref defaults)) = self.custom_shader {
+            effect.update_custom_uniforms(defaults);
+        }
+    }
 }
 
 impl<'a> PassData<'a> for DrawFlatSeparate {
     type Data = (
         Entities<'a>,
         Option<Fetch<'a, ActiveCamera>>,
+        Option<Fetch<'a, Visibility>>,
         ReadStorage<'a, Camera>,
         Fetch<'a, AssetStorage<Mesh>>,
         Fetch<'a, AssetStorage<Texture>>,
@@ -75,18 +109,27 @@ impl<'a> PassData<'a> for DrawFlatSeparate {
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, JointTransforms>,
         ReadStorage<'a, Transparent>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
+        ReadStorage<'a, CustomUniforms>,
+        ReadStorage<'a, ZOrder>,
     );
 }
 
 impl Pass for DrawFlatSeparate {
     fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
         use std::mem;
+        let frag = match self.custom_shader {
+            Some((frag, _)) => frag,
+            None => FRAG_SRC,
+        };
         let mut builder = if self.skinning {
-            create_skinning_effect(effect, FRAG_SRC)
+            create_skinning_effect(effect, frag)
         } else {
-            effect.simple(VERT_SRC, FRAG_SRC)
+            effect.simple(VERT_SRC, frag)
         };
         builder
+            .with_primitive_type(self.prim)
             .with_raw_vertex_buffer(
                 Separate::<Position>::ATTRIBUTES,
                 Separate::<Position>::size() as ElemStride,
@@ -100,9 +143,13 @@ impl Pass for DrawFlatSeparate {
         if self.skinning {
             setup_skinning_buffers(&mut builder);
         }
-        builder
-            .with_raw_constant_buffer("VertexArgs", mem::size_of::<VertexArgs>(), 1)
-            .with_texture("albedo");
+        builder.with_raw_constant_buffer("VertexArgs", mem::size_of::<VertexArgs>(), 1);
+        setup_textures(&mut builder, &TEXTURES);
+        if let Some((_, ref uniforms)) = self.custom_shader {
+            for &(ref name, _) in uniforms.iter() {
+                builder.with_raw_global(name);
+            }
+        }
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
             None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
@@ -118,6 +165,7 @@ impl Pass for DrawFlatSeparate {
         (
             entities,
             active,
+            visibility,
             camera,
             mesh_storage,
             tex_storage,
@@ -128,13 +176,33 @@ impl Pass for DrawFlatSeparate {
             global,
             joints,
             transparent,
+            hidden,
+            render_mask,
+            custom,
+            z_order,
         ): <Self as PassData<'a>>::Data,
     ) {
-        let camera = get_camera(active, &camera, &global);
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
 
-        for (entity, mesh, material, global, _) in
-            (&*entities, &mesh, &material, &global, !&transparent).join()
-        {
+        let mut opaque = (&*entities, &mesh, &material, &global, !&transparent, !&hidden)
+            .join()
+            .collect::<Vec<_>>();
+        sort_by_z_order(&mut opaque, &z_order, |item| item.0);
+        for (entity, mesh, material, global, _, _) in opaque {
+            if !is_visible(effect, &visibility, entity)
+                || !in_render_mask(mask, &render_mask, entity)
+            {
+                continue;
+            }
+            self.reset_uniforms(effect);
             draw_mesh(
                 encoder,
                 effect,
@@ -144,6 +212,7 @@ impl Pass for DrawFlatSeparate {
                 &*tex_storage,
                 Some(material),
                 &*material_defaults,
+                custom.get(entity),
                 camera,
                 Some(global),
                 &ATTRIBUTES,
@@ -151,8 +220,16 @@ impl Pass for DrawFlatSeparate {
             );
         }
 
-        for entity in &back_to_front.entities {
+        let mut back_to_front = back_to_front.entities.clone();
+        sort_by_z_order(&mut back_to_front, &z_order, |entity| *entity);
+        for entity in &back_to_front {
+            if !is_visible(effect, &visibility, *entity)
+                || !in_render_mask(mask, &render_mask, *entity)
+            {
+                continue;
+            }
             if let Some(mesh) = mesh.get(*entity) {
+                self.reset_uniforms(effect);
                 draw_mesh(
                     encoder,
                     effect,
@@ -162,6 +239,7 @@ impl Pass for DrawFlatSeparate {
                     &*tex_storage,
                     material.get(*entity),
                     &*material_defaults,
+                    custom.get(*entity),
                     camera,
                     global.get(*entity),
                     &ATTRIBUTES,
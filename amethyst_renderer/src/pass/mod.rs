@@ -1,13 +1,58 @@
 //! Different kinds of render passes.
 //
+pub use self::bloom::DrawBloom;
+pub use self::chromatic_aberration::DrawChromaticAberration;
+pub use self::color_grade::DrawColorGrade;
+pub use self::debug_lines::DrawDebugLines;
+pub use self::decal::DrawDecal;
+pub use self::depth::DrawDepth;
+pub use self::dof::DrawDof;
 pub use self::flat::*;
+pub use self::motion_blur::DrawMotionBlur;
+pub use self::outline::DrawOutline;
+pub use self::particles::DrawParticles;
 pub use self::pbm::*;
+pub use self::post::{DrawFxaa, DrawPostEffect};
 pub use self::shaded::*;
+pub use self::shadow::{DrawPointShadow, DrawShadow};
+pub use self::skybox::DrawSkybox;
+pub use self::sprite::DrawSprite;
+pub use self::ssao::DrawSsao;
+pub use self::terrain::DrawTerrain;
+pub use self::tilemap::DrawTileMap;
+pub use self::tonemap::{DrawTonemap, Tonemapping};
+pub use self::vignette::DrawVignette;
+pub use self::water::DrawWater;
+pub use self::wireframe::DrawWireframe;
 pub use self::skinning::set_skinning_buffers;
 
+mod bloom;
+mod chromatic_aberration;
+mod clusters;
+mod color_grade;
+mod debug_lines;
+mod decal;
+mod depth;
+mod dof;
+mod environment;
 mod flat;
+mod morph;
+mod motion_blur;
+mod outline;
+mod particles;
 mod pbm;
+mod post;
 mod shaded;
+mod shadow;
+mod skybox;
+mod sprite;
+mod ssao;
+mod terrain;
+mod tilemap;
 mod skinning;
+mod tonemap;
 mod util;
+mod vignette;
+mod water;
+mod wireframe;
 mod shaded_util;
@@ -0,0 +1,61 @@
+//! Utilities for morph targets
+
+use std::mem;
+
+use gfx::pso::buffer::ElemStride;
+
+use morph::{MorphNormal0, MorphNormal1, MorphNormal2, MorphNormal3, MorphPosition0,
+            MorphPosition1, MorphPosition2, MorphPosition3, MorphWeights, MAX_MORPH_TARGETS};
+use pipe::{Effect, EffectBuilder, NewEffect};
+use types::Encoder;
+use vertex::{Attributes, Separate, VertexFormat};
+
+static VERT_MORPH_SRC: &[u8] = include_bytes!("shaders/vertex/morph.glsl");
+static VERT_SKIN_MORPH_SRC: &[u8] = include_bytes!("shaders/vertex/skinned_morph.glsl");
+static VERT_SKIN_DQ_MORPH_SRC: &[u8] = include_bytes!("shaders/vertex/skinned_dq_morph.glsl");
+
+pub(crate) static MORPH_ATTRIBUTES: [Attributes<'static>; 8] = [
+    Separate::<MorphPosition0>::ATTRIBUTES,
+    Separate::<MorphPosition1>::ATTRIBUTES,
+    Separate::<MorphPosition2>::ATTRIBUTES,
+    Separate::<MorphPosition3>::ATTRIBUTES,
+    Separate::<MorphNormal0>::ATTRIBUTES,
+    Separate::<MorphNormal1>::ATTRIBUTES,
+    Separate::<MorphNormal2>::ATTRIBUTES,
+    Separate::<MorphNormal3>::ATTRIBUTES,
+];
+
+pub(crate) fn create_morph_effect<'a>(
+    effect: NewEffect<'a>,
+    frag: &'a [u8],
+    skinning: bool,
+    dual_quaternion: bool,
+) -> EffectBuilder<'a> {
+    let vert = match (skinning, dual_quaternion) {
+        (true, true) => VERT_SKIN_DQ_MORPH_SRC,
+        (true, false) => VERT_SKIN_MORPH_SRC,
+        (false, _) => VERT_MORPH_SRC,
+    };
+    effect.simple(vert, frag)
+}
+
+/// Adds the morph target vertex buffers, must be set up right after the
+/// vertex buffers of the mesh so that `MORPH_ATTRIBUTES` can be appended to
+/// the mesh attributes.
+pub(crate) fn setup_morph_buffers(builder: &mut EffectBuilder) {
+    for attributes in MORPH_ATTRIBUTES.iter() {
+        builder.with_raw_vertex_buffer(attributes, mem::size_of::<[f32; 3]>() as ElemStride, 0);
+    }
+    builder.with_raw_constant_buffer("MorphWeights", mem::size_of::<[f32; MAX_MORPH_TARGETS]>(), 1);
+}
+
+pub(crate) fn set_morph_weights(
+    effect: &mut Effect,
+    encoder: &mut Encoder,
+    weights: Option<&MorphWeights>,
+) {
+    let weights = weights
+        .map(MorphWeights::padded)
+        .unwrap_or([0.0; MAX_MORPH_TARGETS]);
+    effect.update_constant_buffer("MorphWeights", &weights, encoder);
+}
@@ -0,0 +1,85 @@
+//! Camera motion blur post-processing pass.
+
+use amethyst_core::cgmath::{Matrix4, SquareMatrix};
+use amethyst_core::transform::GlobalTransform;
+use specs::{Fetch, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use error::{Error, Result};
+use pass::post::{create_fullscreen_effect, draw_fullscreen};
+use pass::util::get_camera;
+use pipe::{Effect, NewEffect, TargetInput};
+use pipe::pass::{Pass, PassData};
+use resources::MotionBlurParams;
+use types::{Encoder, Factory};
+
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/motion_blur.glsl");
+
+/// Blurs the stage input along the movement of the camera since the previous
+/// frame.
+///
+/// Must be used in a `PostProcessing` stage whose input has a depth buffer.
+/// The position of each pixel is reconstructed from depth and projected with
+/// the view and projection of the camera in the previous frame, which gives
+/// its velocity on the screen. Only the movement of the camera is taken into
+/// account, not the one of the entities. Configured by `MotionBlurParams`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawMotionBlur {
+    previous: Option<Matrix4<f32>>,
+}
+
+impl DrawMotionBlur {
+    /// Create instance of `DrawMotionBlur` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> PassData<'a> for DrawMotionBlur {
+    type Data = (
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        Fetch<'a, MotionBlurParams>,
+    );
+}
+
+impl Pass for DrawMotionBlur {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let input = effect.input().ok_or(Error::NoStageInput)?;
+        let mut builder = create_fullscreen_effect(effect, FRAG_SRC);
+        builder
+            .with_stage_input("source")?
+            .with_input("depth", TargetInput::Depth(input.to_string()))
+            .with_raw_global("inv_view_proj")
+            .with_raw_global("prev_view_proj")
+            .with_raw_global("intensity")
+            .with_raw_global("max_length")
+            .with_raw_global("samples")
+            .with_output("color", None);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (active, camera, global, params): <Self as PassData<'a>>::Data,
+    ) {
+        let view_proj = get_camera(effect.camera(), active, &camera, &global)
+            .and_then(|(camera, transform)| Some(camera.proj * transform.0.invert()?))
+            .unwrap_or_else(Matrix4::identity);
+        let inv_view_proj = view_proj.invert().unwrap_or_else(Matrix4::identity);
+        // Without a previous frame, nothing moved.
+        let previous = self.previous.unwrap_or(view_proj);
+        self.previous = Some(view_proj);
+
+        effect.update_global("inv_view_proj", Into::<[[f32; 4]; 4]>::into(inv_view_proj));
+        effect.update_global("prev_view_proj", Into::<[[f32; 4]; 4]>::into(previous));
+        effect.update_global("intensity", params.intensity);
+        effect.update_global("max_length", params.max_length);
+        effect.update_global("samples", params.samples as i32);
+        draw_fullscreen(effect, encoder);
+    }
+}
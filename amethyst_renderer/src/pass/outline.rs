@@ -0,0 +1,198 @@
+//! Selection outline pass.
+
+use std::marker::PhantomData;
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::cgmath::Matrix4;
+use amethyst_core::transform::GlobalTransform;
+use gfx::preset::blend;
+use gfx::pso::buffer::ElemStride;
+use gfx::state::ColorMask;
+use specs::{Entities, Fetch, Join, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use error::Result;
+use hidden::Hidden;
+use mesh::{Mesh, MeshHandle};
+use outline::Outline;
+use pass::util::{get_camera, get_camera_mask, set_attribute_buffers, set_vertex_args,
+                 setup_vertex_args};
+use pipe::{Comparison, DepthMode, Effect, NewEffect, Stencil, StencilOp};
+use pipe::pass::{Pass, PassData};
+use render_mask::{in_render_mask, RenderMask};
+use types::{Encoder, Factory};
+use vertex::{Position, Query};
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/basic.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/outline.glsl");
+
+/// Stencil bit marking the outlined meshes. The highest one, as the stages
+/// clear the stencil buffer to the integer part of their depth clear value.
+const MASK: u8 = 0x80;
+
+/// Draws the `Outline` of the meshes, for example to highlight the selected
+/// entities of an editor.
+///
+/// The meshes are first drawn into the stencil buffer only, then drawn again
+/// scaled up, with the color of their outline, where the stencil buffer wasn't
+/// marked. It needs a stage target with a depth buffer, which gets a stencil
+/// buffer along with it except with the Metal backend, and should come after
+/// the passes drawing the meshes themselves.
+///
+/// The outlines are drawn over the rest of the scene by default.
+/// `V` is `VertexFormat`
+#[derive(Derivative, Clone, Debug, PartialEq)]
+#[derivative(Default(bound = "V: Query<(Position,)>"))]
+pub struct DrawOutline<V> {
+    _pd: PhantomData<V>,
+    depth_test: bool,
+    mask: Option<Effect>,
+}
+
+impl<V> DrawOutline<V>
+where
+    V: Query<(Position,)>,
+{
+    /// Create instance of `DrawOutline` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Hide the outlines behind the rest of the scene
+    pub fn with_depth_test(mut self) -> Self {
+        self.depth_test = true;
+        self
+    }
+}
+
+impl<'a, V> PassData<'a> for DrawOutline<V>
+where
+    V: Query<(Position,)>,
+{
+    type Data = (
+        Entities<'a>,
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        Fetch<'a, AssetStorage<Mesh>>,
+        ReadStorage<'a, MeshHandle>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Outline>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
+    );
+}
+
+impl<V> Pass for DrawOutline<V>
+where
+    V: Query<(Position,)>,
+{
+    fn compile(&mut self, mut effect: NewEffect) -> Result<Effect> {
+        let depth = if self.depth_test {
+            Some(DepthMode::LessEqualTest)
+        } else {
+            None
+        };
+
+        let mut mask = {
+            let mut builder = effect.reborrow().simple(VERT_SRC, FRAG_SRC);
+            builder
+                .with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0)
+                .with_raw_global("outline_color");
+            setup_vertex_args(&mut builder);
+            builder
+                .with_blended_output("color", ColorMask::empty(), blend::REPLACE, depth)
+                .with_stencil(Stencil::new(
+                    Comparison::Always,
+                    MASK,
+                    (StencilOp::Keep, StencilOp::Keep, StencilOp::Replace),
+                ));
+            builder.build()?
+        };
+        mask.update_stencil_reference(MASK);
+        self.mask = Some(mask);
+
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder
+            .with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0)
+            .with_raw_global("outline_color");
+        setup_vertex_args(&mut builder);
+        builder.with_output("color", depth).with_stencil(Stencil::new(
+            Comparison::NotEqual,
+            MASK,
+            (StencilOp::Keep, StencilOp::Keep, StencilOp::Keep),
+        ));
+        let mut outline = builder.build()?;
+        outline.update_stencil_reference(MASK);
+        Ok(outline)
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (
+            entities,
+            active,
+            camera,
+            mesh_storage,
+            mesh,
+            global,
+            outline,
+            hidden,
+            render_mask,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let mask = match self.mask {
+            Some(ref mut mask) => mask,
+            None => return,
+        };
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+
+        // Only the outline effect follows the resizing of the target.
+        mask.data.out_colors.clone_from(&effect.data.out_colors);
+        mask.data.out_blends.clone_from(&effect.data.out_blends);
+        mask.data.out_depth.clone_from(&effect.data.out_depth);
+        mask.data.scissor = effect.data.scissor;
+
+        let outlined = (&*entities, &mesh, &global, &outline, !&hidden)
+            .join()
+            .filter(|&(entity, _, _, _, _)| in_render_mask(mask, &render_mask, entity))
+            .filter_map(|(_, mesh, global, outline, _)| {
+                mesh_storage
+                    .get(mesh)
+                    .map(|mesh| (mesh, global, outline))
+            })
+            .collect::<Vec<_>>();
+
+        for &(mesh, global, _) in &outlined {
+            if !set_attribute_buffers(mask, mesh, &[V::QUERIED_ATTRIBUTES]) {
+                mask.clear();
+                continue;
+            }
+            set_vertex_args(mask, encoder, camera, global);
+            mask.draw(mesh.slice(), encoder);
+            mask.clear();
+        }
+
+        for &(mesh, global, outline) in &outlined {
+            if !set_attribute_buffers(effect, mesh, &[V::QUERIED_ATTRIBUTES]) {
+                effect.clear();
+                continue;
+            }
+            let scaled = GlobalTransform(global.0 * Matrix4::from_scale(1.0 + outline.thickness));
+            set_vertex_args(effect, encoder, camera, &scaled);
+            effect.update_global("outline_color", Into::<[f32; 4]>::into(outline.color));
+            effect.draw(mesh.slice(), encoder);
+            effect.clear();
+        }
+    }
+}
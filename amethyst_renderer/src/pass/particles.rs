@@ -0,0 +1,258 @@
+//! Particle simulation and drawing pass.
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::cgmath::{InnerSpace, Vector3};
+use amethyst_core::timing::Time;
+use amethyst_core::transform::GlobalTransform;
+use fnv::FnvHashMap as HashMap;
+use gfx::IndexBuffer;
+use gfx::handle::Buffer;
+use gfx::preset::blend;
+use gfx::pso::buffer::ElemStride;
+use gfx::state::ColorMask;
+use specs::{Entities, Entity, Fetch, Join, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use error::Result;
+use hidden::Hidden;
+use particle::ParticleEmitter;
+use pass::util::{add_texture, get_camera, get_camera_mask, set_vertex_args, setup_vertex_args,
+                 update_dynamic_buffer};
+use pipe::{DepthMode, Effect, NewEffect};
+use pipe::pass::{Pass, PassData};
+use render_mask::{in_render_mask, RenderMask};
+use tex::{Texture, TextureBuilder};
+use types::{Encoder, Factory, Resources, Slice};
+use vertex::{PosColorTex, VertexFormat};
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/particle.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/particle.glsl");
+
+const CORNERS: [([f32; 2], [f32; 2]); 6] = [
+    ([-0.5, -0.5], [0.0, 0.0]),
+    ([0.5, -0.5], [1.0, 0.0]),
+    ([0.5, 0.5], [1.0, 1.0]),
+    ([-0.5, -0.5], [0.0, 0.0]),
+    ([0.5, 0.5], [1.0, 1.0]),
+    ([-0.5, 0.5], [0.0, 1.0]),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+struct Particle {
+    position: Vector3<f32>,
+    velocity: Vector3<f32>,
+    age: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Particles {
+    particles: Vec<Particle>,
+    to_spawn: f32,
+}
+
+impl Particles {
+    fn update(&mut self, emitter: &ParticleEmitter, origin: Vector3<f32>, dt: f32, seed: &mut u32) {
+        let lifetime = emitter.lifetime;
+        self.particles.retain(|p| p.age + dt < lifetime);
+
+        let acceleration = Vector3::from(emitter.acceleration);
+        for particle in &mut self.particles {
+            particle.age += dt;
+            particle.velocity += acceleration * dt;
+            let speed = emitter.velocity_at(particle.age / lifetime);
+            particle.position += particle.velocity * speed * dt;
+        }
+
+        self.to_spawn += emitter.spawn_rate * dt;
+        while self.to_spawn >= 1.0 {
+            self.to_spawn -= 1.0;
+            if self.particles.len() >= emitter.max_particles {
+                continue;
+            }
+            let spread = Vector3::new(random(seed), random(seed), random(seed)) * emitter.spread;
+            self.particles.push(Particle {
+                position: origin,
+                velocity: Vector3::from(emitter.velocity) + spread,
+                age: 0.0,
+            });
+        }
+    }
+}
+
+/// Simulates and draws the particles of all `ParticleEmitter`s.
+///
+/// The particles always face the camera and are alpha blended without
+/// writing depth, so this pass should come after the opaque passes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawParticles {
+    systems: HashMap<Entity, Particles>,
+    vertices: Vec<PosColorTex>,
+    buffer: Option<Buffer<Resources, PosColorTex>>,
+    white: Option<Texture>,
+    seed: u32,
+}
+
+impl DrawParticles {
+    /// Create instance of `DrawParticles` pass
+    pub fn new() -> Self {
+        DrawParticles {
+            systems: HashMap::default(),
+            vertices: Vec::new(),
+            buffer: None,
+            white: None,
+            seed: 0x9E37_79B9,
+        }
+    }
+}
+
+impl<'a> PassData<'a> for DrawParticles {
+    type Data = (
+        Entities<'a>,
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        Fetch<'a, Time>,
+        Fetch<'a, AssetStorage<Texture>>,
+        ReadStorage<'a, ParticleEmitter>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
+    );
+}
+
+impl Pass for DrawParticles {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        self.white = Some(TextureBuilder::from_color_val([1.0; 4]).build(effect.factory)?);
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder.with_raw_vertex_buffer(
+            PosColorTex::ATTRIBUTES,
+            PosColorTex::size() as ElemStride,
+            0,
+        );
+        setup_vertex_args(&mut builder);
+        builder
+            .with_texture("albedo")
+            .with_blended_output(
+                "color",
+                ColorMask::all(),
+                blend::ALPHA,
+                Some(DepthMode::LessEqualTest),
+            );
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            entities,
+            active,
+            camera,
+            time,
+            tex_storage,
+            emitter,
+            global,
+            hidden,
+            render_mask,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        use gfx::memory::Typed;
+
+        let dt = time.delta_seconds();
+        self.systems
+            .retain(|entity, _| emitter.get(*entity).is_some());
+        for (entity, emitter, global) in (&*entities, &emitter, &global).join() {
+            let origin = global.0.w.truncate();
+            let seed = &mut self.seed;
+            self.systems
+                .entry(entity)
+                .or_insert_with(Particles::default)
+                .update(emitter, origin, dt, seed);
+        }
+
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+        let (right, up) = match camera {
+            Some((_, transform)) => (
+                transform.0.x.truncate().normalize(),
+                transform.0.y.truncate().normalize(),
+            ),
+            None => return,
+        };
+
+        self.vertices.clear();
+        let mut ranges = Vec::with_capacity(self.systems.len());
+        // Hidden emitters keep simulating their particles, only their drawing
+        // is skipped.
+        for (entity, emitter, _) in (&*entities, &emitter, !&hidden).join() {
+            if !in_render_mask(mask, &render_mask, entity) {
+                continue;
+            }
+            let system = match self.systems.get(&entity) {
+                Some(system) => system,
+                None => continue,
+            };
+            let start = self.vertices.len();
+            for particle in &system.particles {
+                let color = emitter.color_at(particle.age / emitter.lifetime).into();
+                for &(corner, tex_coord) in &CORNERS {
+                    let offset = (right * corner[0] + up * corner[1]) * emitter.size;
+                    self.vertices.push(PosColorTex {
+                        position: (particle.position + offset).into(),
+                        color,
+                        tex_coord,
+                    });
+                }
+            }
+            ranges.push((emitter, start, self.vertices.len()));
+        }
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        if !update_dynamic_buffer(&mut factory, encoder, &mut self.buffer, &self.vertices) {
+            return;
+        }
+        let buffer = self.buffer.as_ref().unwrap();
+
+        set_vertex_args(effect, encoder, camera, &GlobalTransform::default());
+        let white = self.white.as_ref().unwrap();
+        for (emitter, start, end) in ranges {
+            if start == end {
+                continue;
+            }
+            let texture = emitter
+                .texture
+                .as_ref()
+                .and_then(|texture| tex_storage.get(texture))
+                .unwrap_or(white);
+            effect.data.vertex_bufs.push(buffer.raw().clone());
+            add_texture(effect, texture);
+            let slice = Slice {
+                start: start as u32,
+                end: end as u32,
+                base_vertex: 0,
+                instances: None,
+                buffer: IndexBuffer::Auto,
+            };
+            effect.draw(&slice, encoder);
+            effect.clear();
+        }
+    }
+}
+
+/// Returns a pseudo random number between -1 and 1.
+fn random(seed: &mut u32) -> f32 {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 17;
+    *seed ^= *seed << 5;
+    (*seed as f32 / u32::max_value() as f32) * 2.0 - 1.0
+}
@@ -6,23 +6,33 @@ use amethyst_assets::AssetStorage;
 use amethyst_core::transform::GlobalTransform;
 use gfx::pso::buffer::ElemStride;
 use gfx_core::state::{Blend, ColorMask};
-use specs::{Fetch, Join, ReadStorage};
+use specs::{Entities, Fetch, Join, ReadStorage};
 
 use super::*;
 use cam::{ActiveCamera, Camera};
 use error::Result;
+use hidden::Hidden;
 use light::Light;
 use mesh::{Mesh, MeshHandle};
 use mtl::{Material, MaterialDefaults};
+use pass::environment::{default_environment, set_environment_args, setup_environment_map};
+use pass::clusters::LightClusters;
 use pass::shaded_util::{set_light_args, setup_light_buffers};
-use pass::util::{draw_mesh, get_camera, setup_textures, setup_vertex_args};
+use pass::shadow::{default_point_shadow_map, set_point_shadow_args, set_shadow_args,
+                   setup_point_shadow_maps, setup_shadow_map};
+use pass::ssao::{set_ssao_args, setup_ssao};
+use pass::util::{draw_mesh, get_camera, get_camera_mask, is_visible, setup_textures,
+                 setup_vertex_args};
 use pipe::{DepthMode, Effect, NewEffect};
 use pipe::pass::{Pass, PassData};
-use resources::AmbientColor;
+use render_mask::{in_render_mask, RenderMask};
+use resources::{AmbientColor, EnvironmentMap, Fog};
+use shadow::{PointShadowMaps, ShadowCaster, ShadowConfig};
 use tex::Texture;
 use transparent::{Transparent, TransparentBackToFront};
 use types::{Encoder, Factory};
 use vertex::{Normal, Position, Query, Tangent, TexCoord};
+use visibility::Visibility;
 
 /// Draw mesh with physically based lighting
 /// `V` is `VertexFormat`
@@ -31,6 +41,9 @@ use vertex::{Normal, Position, Query, Tangent, TexCoord};
 pub struct DrawPbm<V> {
     _pd: PhantomData<V>,
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    shadow_map: Option<String>,
+    ssao: Option<String>,
+    clusters: Option<LightClusters>,
 }
 
 impl<V> DrawPbm<V>
@@ -52,6 +65,18 @@ where
         self.transparency = Some((mask, blend, depth));
         self
     }
+
+    /// Enable shadows, using the depth buffer of the given target as shadow map
+    pub fn with_shadow_map<N: Into<String>>(mut self, target: N) -> Self {
+        self.shadow_map = Some(target.into());
+        self
+    }
+
+    /// Enable screen space ambient occlusion, read from the given target
+    pub fn with_ssao<N: Into<String>>(mut self, target: N) -> Self {
+        self.ssao = Some(target.into());
+        self
+    }
 }
 
 impl<'a, V> PassData<'a> for DrawPbm<V>
@@ -59,9 +84,15 @@ where
     V: Query<(Position, Normal, Tangent, TexCoord)>,
 {
     type Data = (
+        Entities<'a>,
         Option<Fetch<'a, ActiveCamera>>,
+        Option<Fetch<'a, Visibility>>,
         ReadStorage<'a, Camera>,
         Fetch<'a, AmbientColor>,
+        Fetch<'a, Fog>,
+        Fetch<'a, ShadowConfig>,
+        Fetch<'a, PointShadowMaps>,
+        Option<Fetch<'a, EnvironmentMap>>,
         Fetch<'a, AssetStorage<Mesh>>,
         Fetch<'a, AssetStorage<Texture>>,
         Fetch<'a, MaterialDefaults>,
@@ -70,7 +101,10 @@ where
         ReadStorage<'a, Material>,
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, Light>,
+        ReadStorage<'a, ShadowCaster>,
         ReadStorage<'a, Transparent>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
     );
 }
 
@@ -79,15 +113,27 @@ where
     V: Query<(Position, Normal, Tangent, TexCoord)>,
 {
     fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let point_shadow_map = default_point_shadow_map(effect.factory)?;
+        let clusters = LightClusters::new(effect.factory)?;
+        let environment = default_environment(effect.factory)?;
         let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
         builder.with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0);
         setup_vertex_args(&mut builder);
-        setup_light_buffers(&mut builder);
+        setup_light_buffers(&mut builder, &clusters);
+        if let Some(ref target) = self.shadow_map {
+            setup_shadow_map(&mut builder, target);
+        }
+        setup_point_shadow_maps(&mut builder, &point_shadow_map);
+        if let Some(ref target) = self.ssao {
+            setup_ssao(&mut builder, target);
+        }
+        setup_environment_map(&mut builder, &environment);
         setup_textures(&mut builder, &TEXTURES);
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
             None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
         };
+        self.clusters = Some(clusters);
         builder.build()
     }
 
@@ -97,9 +143,15 @@ where
         effect: &mut Effect,
         _factory: Factory,
         (
+            entities,
             active,
+            visibility,
             camera,
             ambient,
+            fog,
+            shadow,
+            point_shadows,
+            environment,
             mesh_storage,
             tex_storage,
             material_defaults,
@@ -108,14 +160,49 @@ where
             material,
             global,
             light,
+            shadow_caster,
             transparent,
+            hidden,
+            render_mask,
         ): <Self as PassData<'a>>::Data,
     ) {
-        let camera = get_camera(active, &camera, &global);
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
 
-        set_light_args(effect, encoder, &light, &ambient, camera);
+        if let Some(ref mut clusters) = self.clusters {
+            set_light_args(effect, encoder, clusters, &light, &ambient, &fog, camera);
+        }
+        if self.shadow_map.is_some() {
+            set_shadow_args(effect, &light, &shadow, camera);
+        }
+        set_point_shadow_args(
+            effect,
+            &entities,
+            &light,
+            &shadow_caster,
+            &point_shadows,
+            &shadow,
+        );
+        if self.ssao.is_some() {
+            set_ssao_args(effect);
+        }
+        set_environment_args(effect, &tex_storage, environment.as_ref().map(|env| &**env));
 
-        for (mesh, material, global, _) in (&mesh, &material, &global, !&transparent).join() {
+        for (entity, mesh, material, global, _, _) in
+            (&*entities, &mesh, &material, &global, !&transparent, !&hidden).join()
+        {
+            if !is_visible(effect, &visibility, entity)
+                || !in_render_mask(mask, &render_mask, entity)
+            {
+                continue;
+            }
             draw_mesh(
                 encoder,
                 effect,
@@ -125,6 +212,7 @@ where
                 &*tex_storage,
                 Some(material),
                 &*material_defaults,
+                None,
                 camera,
                 Some(global),
                 &[V::QUERIED_ATTRIBUTES],
@@ -133,6 +221,11 @@ where
         }
 
         for entity in &back_to_front.entities {
+            if !is_visible(effect, &visibility, *entity)
+                || !in_render_mask(mask, &render_mask, *entity)
+            {
+                continue;
+            }
             if let Some(mesh) = mesh.get(*entity) {
                 draw_mesh(
                     encoder,
@@ -143,6 +236,7 @@ where
                     &*tex_storage,
                     material.get(*entity),
                     &*material_defaults,
+                    None,
                     camera,
                     global.get(*entity),
                     &[V::QUERIED_ATTRIBUTES],
@@ -9,20 +9,34 @@ use specs::{Entities, Fetch, Join, ReadStorage};
 use super::*;
 use cam::{ActiveCamera, Camera};
 use error::Result;
+use hidden::Hidden;
 use light::Light;
 use mesh::{Mesh, MeshHandle};
+use morph::{MorphNormal0, MorphNormal1, MorphNormal2, MorphNormal3, MorphPosition0,
+            MorphPosition1, MorphPosition2, MorphPosition3, MorphWeights};
 use mtl::{Material, MaterialDefaults};
+use pass::morph::{create_morph_effect, set_morph_weights, setup_morph_buffers};
+use pass::environment::{default_environment, set_environment_args, setup_environment_map};
+use pass::clusters::LightClusters;
 use pass::shaded_util::{set_light_args, setup_light_buffers};
-use pass::skinning::{create_skinning_effect, setup_skinning_buffers};
-use pass::util::{draw_mesh, get_camera, setup_textures, setup_vertex_args};
+use pass::shadow::{default_point_shadow_map, set_point_shadow_args, set_shadow_args,
+                   setup_point_shadow_maps, setup_shadow_map};
+use pass::ssao::{set_ssao_args, setup_ssao};
+use pass::skinning::{create_dual_quaternion_skinning_effect, create_skinning_effect,
+                     setup_skinning_buffers};
+use pass::util::{draw_mesh, get_camera, get_camera_mask, is_visible, setup_textures,
+                 setup_vertex_args};
 use pipe::{DepthMode, Effect, NewEffect};
 use pipe::pass::{Pass, PassData};
-use resources::AmbientColor;
+use render_mask::{in_render_mask, RenderMask};
+use resources::{AmbientColor, EnvironmentMap, Fog};
+use shadow::{PointShadowMaps, ShadowCaster, ShadowConfig};
 use skinning::JointTransforms;
 use tex::Texture;
 use transparent::{Transparent, TransparentBackToFront};
 use types::{Encoder, Factory};
 use vertex::{Attributes, Normal, Position, Separate, Tangent, TexCoord, VertexFormat};
+use visibility::Visibility;
 
 static ATTRIBUTES: [Attributes<'static>; 4] = [
     Separate::<Position>::ATTRIBUTES,
@@ -31,11 +45,31 @@ static ATTRIBUTES: [Attributes<'static>; 4] = [
     Separate::<TexCoord>::ATTRIBUTES,
 ];
 
+static MORPH_ATTRIBUTES: [Attributes<'static>; 12] = [
+    Separate::<Position>::ATTRIBUTES,
+    Separate::<Normal>::ATTRIBUTES,
+    Separate::<Tangent>::ATTRIBUTES,
+    Separate::<TexCoord>::ATTRIBUTES,
+    Separate::<MorphPosition0>::ATTRIBUTES,
+    Separate::<MorphPosition1>::ATTRIBUTES,
+    Separate::<MorphPosition2>::ATTRIBUTES,
+    Separate::<MorphPosition3>::ATTRIBUTES,
+    Separate::<MorphNormal0>::ATTRIBUTES,
+    Separate::<MorphNormal1>::ATTRIBUTES,
+    Separate::<MorphNormal2>::ATTRIBUTES,
+    Separate::<MorphNormal3>::ATTRIBUTES,
+];
+
 /// Draw mesh with physically based lighting
 #[derive(Default, Clone, Debug, PartialEq)]
 pub struct DrawPbmSeparate {
     skinning: bool,
+    dual_quaternion: bool,
+    morph_targets: bool,
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    shadow_map: Option<String>,
+    ssao: Option<String>,
+    clusters: Option<LightClusters>,
 }
 
 impl DrawPbmSeparate {
@@ -50,6 +84,25 @@ impl DrawPbmSeparate {
         self
     }
 
+    /// Enable vertex skinning, blending the joints as dual quaternions
+    ///
+    /// Avoids the loss of volume of linear blend skinning around twisting
+    /// joints, but ignores any scale in the joint transforms.
+    pub fn with_dual_quaternion_skinning(mut self) -> Self {
+        self.skinning = true;
+        self.dual_quaternion = true;
+        self
+    }
+
+    /// Enable morph targets
+    ///
+    /// Only meshes which have the vertex buffers of all the morph targets are
+    /// drawn, see `MorphWeights`.
+    pub fn with_morph_targets(mut self) -> Self {
+        self.morph_targets = true;
+        self
+    }
+
     /// Enable transparency
     pub fn with_transparency(
         mut self,
@@ -60,14 +113,31 @@ impl DrawPbmSeparate {
         self.transparency = Some((mask, blend, depth));
         self
     }
+
+    /// Enable shadows, using the depth buffer of the given target as shadow map
+    pub fn with_shadow_map<N: Into<String>>(mut self, target: N) -> Self {
+        self.shadow_map = Some(target.into());
+        self
+    }
+
+    /// Enable screen space ambient occlusion, read from the given target
+    pub fn with_ssao<N: Into<String>>(mut self, target: N) -> Self {
+        self.ssao = Some(target.into());
+        self
+    }
 }
 
 impl<'a> PassData<'a> for DrawPbmSeparate {
     type Data = (
         Entities<'a>,
         Option<Fetch<'a, ActiveCamera>>,
+        Option<Fetch<'a, Visibility>>,
         ReadStorage<'a, Camera>,
         Fetch<'a, AmbientColor>,
+        Fetch<'a, Fog>,
+        Fetch<'a, ShadowConfig>,
+        Fetch<'a, PointShadowMaps>,
+        Option<Fetch<'a, EnvironmentMap>>,
         Fetch<'a, AssetStorage<Mesh>>,
         Fetch<'a, AssetStorage<Texture>>,
         Fetch<'a, MaterialDefaults>,
@@ -76,14 +146,25 @@ impl<'a> PassData<'a> for DrawPbmSeparate {
         ReadStorage<'a, Material>,
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, Light>,
+        ReadStorage<'a, ShadowCaster>,
         ReadStorage<'a, JointTransforms>,
+        ReadStorage<'a, MorphWeights>,
         ReadStorage<'a, Transparent>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
     );
 }
 
 impl Pass for DrawPbmSeparate {
     fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
-        let mut builder = if self.skinning {
+        let point_shadow_map = default_point_shadow_map(effect.factory)?;
+        let clusters = LightClusters::new(effect.factory)?;
+        let environment = default_environment(effect.factory)?;
+        let mut builder = if self.morph_targets {
+            create_morph_effect(effect, FRAG_SRC, self.skinning, self.dual_quaternion)
+        } else if self.dual_quaternion {
+            create_dual_quaternion_skinning_effect(effect, FRAG_SRC)
+        } else if self.skinning {
             create_skinning_effect(effect, FRAG_SRC)
         } else {
             effect.simple(VERT_SRC, FRAG_SRC)
@@ -109,16 +190,28 @@ impl Pass for DrawPbmSeparate {
                 Separate::<TexCoord>::size() as ElemStride,
                 0,
             );
+        if self.morph_targets {
+            setup_morph_buffers(&mut builder);
+        }
         if self.skinning {
             setup_skinning_buffers(&mut builder);
         }
         setup_vertex_args(&mut builder);
-        setup_light_buffers(&mut builder);
+        setup_light_buffers(&mut builder, &clusters);
+        if let Some(ref target) = self.shadow_map {
+            setup_shadow_map(&mut builder, target);
+        }
+        setup_point_shadow_maps(&mut builder, &point_shadow_map);
+        if let Some(ref target) = self.ssao {
+            setup_ssao(&mut builder, target);
+        }
+        setup_environment_map(&mut builder, &environment);
         setup_textures(&mut builder, &TEXTURES);
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
             None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
         };
+        self.clusters = Some(clusters);
         builder.build()
     }
 
@@ -130,8 +223,13 @@ impl Pass for DrawPbmSeparate {
         (
             entities,
             active,
+            visibility,
             camera,
             ambient,
+            fog,
+            shadow,
+            point_shadows,
+            environment,
             mesh_storage,
             tex_storage,
             material_defaults,
@@ -140,17 +238,59 @@ impl Pass for DrawPbmSeparate {
             material,
             global,
             light,
+            shadow_caster,
             joints,
+            morph_weights,
             transparent,
+            hidden,
+            render_mask,
         ): <Self as PassData<'a>>::Data,
     ) {
-        let camera = get_camera(active, &camera, &global);
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
 
-        set_light_args(effect, encoder, &light, &ambient, camera);
+        if let Some(ref mut clusters) = self.clusters {
+            set_light_args(effect, encoder, clusters, &light, &ambient, &fog, camera);
+        }
+        if self.shadow_map.is_some() {
+            set_shadow_args(effect, &light, &shadow, camera);
+        }
+        set_point_shadow_args(
+            effect,
+            &entities,
+            &light,
+            &shadow_caster,
+            &point_shadows,
+            &shadow,
+        );
+        if self.ssao.is_some() {
+            set_ssao_args(effect);
+        }
+        set_environment_args(effect, &tex_storage, environment.as_ref().map(|env| &**env));
+        let attributes: &[Attributes<'static>] = if self.morph_targets {
+            &MORPH_ATTRIBUTES
+        } else {
+            &ATTRIBUTES
+        };
 
-        for (entity, mesh, material, global, _) in
-            (&*entities, &mesh, &material, &global, !&transparent).join()
+        for (entity, mesh, material, global, _, _) in
+            (&*entities, &mesh, &material, &global, !&transparent, !&hidden).join()
         {
+            if !is_visible(effect, &visibility, entity)
+                || !in_render_mask(mask, &render_mask, entity)
+            {
+                continue;
+            }
+            if self.morph_targets {
+                set_morph_weights(effect, encoder, morph_weights.get(entity));
+            }
             draw_mesh(
                 encoder,
                 effect,
@@ -160,15 +300,24 @@ impl Pass for DrawPbmSeparate {
                 &*tex_storage,
                 Some(material),
                 &*material_defaults,
+                None,
                 camera,
                 Some(global),
-                &ATTRIBUTES,
+                attributes,
                 &TEXTURES,
             );
         }
 
         for entity in &back_to_front.entities {
+            if !is_visible(effect, &visibility, *entity)
+                || !in_render_mask(mask, &render_mask, *entity)
+            {
+                continue;
+            }
             if let Some(mesh) = mesh.get(*entity) {
+                if self.morph_targets {
+                    set_morph_weights(effect, encoder, morph_weights.get(*entity));
+                }
                 draw_mesh(
                     encoder,
                     effect,
@@ -178,9 +327,10 @@ impl Pass for DrawPbmSeparate {
                     &*tex_storage,
                     material.get(*entity),
                     &*material_defaults,
+                    None,
                     camera,
                     global.get(*entity),
-                    &ATTRIBUTES,
+                    attributes,
                     &TEXTURES,
                 );
             }
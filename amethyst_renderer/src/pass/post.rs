@@ -0,0 +1,146 @@
+//! Fullscreen post-processing passes.
+
+use amethyst_assets::AssetStorage;
+use gfx::IndexBuffer;
+use specs::Fetch;
+
+use error::Result;
+use pipe::{Effect, EffectBuilder, NewEffect};
+use pipe::pass::{Pass, PassData};
+use shader::{Shader, ShaderHandle};
+use types::{Encoder, Factory, Slice};
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/fullscreen.glsl");
+static FXAA_SRC: &[u8] = include_bytes!("shaders/fragment/fxaa.glsl");
+
+pub(crate) fn create_fullscreen_effect<'a>(
+    effect: NewEffect<'a>,
+    frag: &'a [u8],
+) -> EffectBuilder<'a> {
+    effect.simple(VERT_SRC, frag)
+}
+
+pub(crate) fn draw_fullscreen(effect: &mut Effect, encoder: &mut Encoder) {
+    let slice = Slice {
+        start: 0,
+        end: 3,
+        base_vertex: 0,
+        instances: None,
+        buffer: IndexBuffer::Auto,
+    };
+    effect.draw(&slice, encoder);
+}
+
+/// Runs a custom fragment shader over the whole stage target.
+///
+/// Must be used in a `PostProcessing` stage. The shader reads the stage input
+/// through `uniform sampler2D source`, gets its texture coordinates from
+/// `in VertexData { vec2 tex_coord; } vertex` and writes `out vec4 out_color`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawPostEffect {
+    frag: &'static [u8],
+    shader: Option<ShaderHandle>,
+    loaded: Option<Shader>,
+}
+
+impl DrawPostEffect {
+    /// Create instance of `DrawPostEffect` pass with the given GLSL fragment shader
+    pub fn new(frag: &'static [u8]) -> Self {
+        DrawPostEffect {
+            frag,
+            shader: None,
+            loaded: None,
+        }
+    }
+
+    /// Use the fragment shader from the given `Shader` asset once it is
+    /// loaded, the pass is compiled again whenever the asset is reloaded.
+    ///
+    /// The shader given to `new` is used until then.
+    pub fn with_shader(mut self, shader: ShaderHandle) -> Self {
+        self.shader = Some(shader);
+        self
+    }
+}
+
+impl<'a> PassData<'a> for DrawPostEffect {
+    type Data = Option<Fetch<'a, AssetStorage<Shader>>>;
+}
+
+impl Pass for DrawPostEffect {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let frag = self.loaded
+            .as_ref()
+            .map(Shader::source)
+            .unwrap_or(self.frag);
+        let mut builder = create_fullscreen_effect(effect, frag);
+        builder.with_stage_input("source")?;
+        builder.with_output("color", None);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        _data: <Self as PassData<'a>>::Data,
+    ) {
+        draw_fullscreen(effect, encoder);
+    }
+
+    fn needs_recompile<'a>(&mut self, storage: &<Self as PassData<'a>>::Data) -> bool {
+        let shader = match (self.shader.as_ref(), storage.as_ref()) {
+            (Some(handle), Some(storage)) => match storage.get(handle) {
+                Some(shader) => shader,
+                None => return false,
+            },
+            _ => return false,
+        };
+        if let Some(ref loaded) = self.loaded {
+            if loaded.same_source(shader) {
+                return false;
+            }
+        }
+        self.loaded = Some(shader.clone());
+        true
+    }
+}
+
+/// Smooths jagged edges of the stage input with fast approximate anti-aliasing.
+///
+/// Must be used in a `PostProcessing` stage. Cheaper than MSAA and works
+/// where multisampled backbuffers are unavailable, at the cost of some blur.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawFxaa {
+    inner: DrawPostEffect,
+}
+
+impl DrawFxaa {
+    /// Create instance of `DrawFxaa` pass
+    pub fn new() -> Self {
+        DrawFxaa {
+            inner: DrawPostEffect::new(FXAA_SRC),
+        }
+    }
+}
+
+impl<'a> PassData<'a> for DrawFxaa {
+    type Data = ();
+}
+
+impl Pass for DrawFxaa {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        self.inner.compile(effect)
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        factory: Factory,
+        _data: <Self as PassData<'a>>::Data,
+    ) {
+        self.inner.apply(encoder, effect, factory, None)
+    }
+}
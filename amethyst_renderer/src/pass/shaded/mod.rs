@@ -6,7 +6,18 @@ mod separate;
 
 use pass::util::TextureType;
 
+#[cfg(not(feature = "gles"))]
 static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/basic.glsl");
+#[cfg(not(feature = "gles"))]
 static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/shaded.glsl");
 
-static TEXTURES: [TextureType; 2] = [TextureType::Albedo, TextureType::Emission];
+#[cfg(feature = "gles")]
+static VERT_SRC: &[u8] = include_bytes!("../shaders/gles/vertex/basic.glsl");
+#[cfg(feature = "gles")]
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/gles/fragment/shaded.glsl");
+
+static TEXTURES: [TextureType; 3] = [
+    TextureType::Albedo,
+    TextureType::Emission,
+    TextureType::Normal,
+];
@@ -9,20 +9,29 @@ use specs::{Entities, Fetch, Join, ReadStorage};
 use super::*;
 use cam::{ActiveCamera, Camera};
 use error::Result;
+use hidden::Hidden;
 use light::Light;
 use mesh::{Mesh, MeshHandle};
 use mtl::{Material, MaterialDefaults};
+use pass::clusters::LightClusters;
 use pass::shaded_util::{set_light_args, setup_light_buffers};
+use pass::shadow::{default_point_shadow_map, set_point_shadow_args, set_shadow_args,
+                   setup_point_shadow_maps, setup_shadow_map};
+use pass::ssao::{set_ssao_args, setup_ssao};
 use pass::skinning::{create_skinning_effect, setup_skinning_buffers};
-use pass::util::{draw_mesh, get_camera, setup_textures, setup_vertex_args};
+use pass::util::{draw_mesh, get_camera, get_camera_mask, is_visible, setup_textures,
+                 setup_vertex_args};
 use pipe::{DepthMode, Effect, NewEffect};
 use pipe::pass::{Pass, PassData};
-use resources::AmbientColor;
+use render_mask::{in_render_mask, RenderMask};
+use resources::{AmbientColor, Fog};
+use shadow::{PointShadowMaps, ShadowCaster, ShadowConfig};
 use skinning::JointTransforms;
 use tex::Texture;
 use transparent::{Transparent, TransparentBackToFront};
 use types::{Encoder, Factory};
-use vertex::{Attributes, Normal, Position, Separate, TexCoord, VertexFormat};
+use vertex::{Attributes, Normal, Position, Separate, Tangent, TexCoord, VertexFormat};
+use visibility::Visibility;
 
 static ATTRIBUTES: [Attributes<'static>; 3] = [
     Separate::<Position>::ATTRIBUTES,
@@ -30,11 +39,22 @@ static ATTRIBUTES: [Attributes<'static>; 3] = [
     Separate::<TexCoord>::ATTRIBUTES,
 ];
 
+static NORMAL_MAPPING_ATTRIBUTES: [Attributes<'static>; 4] = [
+    Separate::<Position>::ATTRIBUTES,
+    Separate::<Normal>::ATTRIBUTES,
+    Separate::<Tangent>::ATTRIBUTES,
+    Separate::<TexCoord>::ATTRIBUTES,
+];
+
 /// Draw mesh with simple lighting technique
 #[derive(Default, Clone, Debug, PartialEq)]
 pub struct DrawShadedSeparate {
     skinning: bool,
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    shadow_map: Option<String>,
+    ssao: Option<String>,
+    clusters: Option<LightClusters>,
+    normal_mapping: bool,
 }
 
 impl DrawShadedSeparate {
@@ -59,14 +79,46 @@ impl DrawShadedSeparate {
         self.transparency = Some((mask, blend, depth));
         self
     }
+
+    /// Enable shadows, using the depth buffer of the given target as shadow map
+    pub fn with_shadow_map<N: Into<String>>(mut self, target: N) -> Self {
+        self.shadow_map = Some(target.into());
+        self
+    }
+
+    /// Enable screen space ambient occlusion, read from the given target
+    pub fn with_ssao<N: Into<String>>(mut self, target: N) -> Self {
+        self.ssao = Some(target.into());
+        self
+    }
+
+    /// Enable tangent space normal mapping, using the normal texture of the material
+    ///
+    /// Meshes without a separate tangent buffer are skipped.
+    pub fn with_normal_mapping(mut self) -> Self {
+        self.normal_mapping = true;
+        self
+    }
+
+    fn attributes(&self) -> &'static [Attributes<'static>] {
+        if self.normal_mapping {
+            &NORMAL_MAPPING_ATTRIBUTES
+        } else {
+            &ATTRIBUTES
+        }
+    }
 }
 
 impl<'a> PassData<'a> for DrawShadedSeparate {
     type Data = (
         Entities<'a>,
         Option<Fetch<'a, ActiveCamera>>,
+        Option<Fetch<'a, Visibility>>,
         ReadStorage<'a, Camera>,
         Fetch<'a, AmbientColor>,
+        Fetch<'a, Fog>,
+        Fetch<'a, ShadowConfig>,
+        Fetch<'a, PointShadowMaps>,
         Fetch<'a, AssetStorage<Mesh>>,
         Fetch<'a, AssetStorage<Texture>>,
         Fetch<'a, MaterialDefaults>,
@@ -75,13 +127,18 @@ impl<'a> PassData<'a> for DrawShadedSeparate {
         ReadStorage<'a, Material>,
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, Light>,
+        ReadStorage<'a, ShadowCaster>,
         ReadStorage<'a, JointTransforms>,
         ReadStorage<'a, Transparent>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
     );
 }
 
 impl Pass for DrawShadedSeparate {
     fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let point_shadow_map = default_point_shadow_map(effect.factory)?;
+        let clusters = LightClusters::new(effect.factory)?;
         debug!("Building shaded pass");
         let mut builder = if self.skinning {
             create_skinning_effect(effect, FRAG_SRC)
@@ -99,22 +156,39 @@ impl Pass for DrawShadedSeparate {
                 Separate::<Normal>::ATTRIBUTES,
                 Separate::<Normal>::size() as ElemStride,
                 0,
-            )
+            );
+        if self.normal_mapping {
+            builder.with_raw_vertex_buffer(
+                Separate::<Tangent>::ATTRIBUTES,
+                Separate::<Tangent>::size() as ElemStride,
+                0,
+            );
+        }
+        builder
             .with_raw_vertex_buffer(
                 Separate::<TexCoord>::ATTRIBUTES,
                 Separate::<TexCoord>::size() as ElemStride,
                 0,
-            );
+            )
+            .with_raw_global("normal_mapping");
         if self.skinning {
             setup_skinning_buffers(&mut builder);
         }
         setup_vertex_args(&mut builder);
-        setup_light_buffers(&mut builder);
+        setup_light_buffers(&mut builder, &clusters);
+        if let Some(ref target) = self.shadow_map {
+            setup_shadow_map(&mut builder, target);
+        }
+        setup_point_shadow_maps(&mut builder, &point_shadow_map);
+        if let Some(ref target) = self.ssao {
+            setup_ssao(&mut builder, target);
+        }
         setup_textures(&mut builder, &TEXTURES);
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
             None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
         };
+        self.clusters = Some(clusters);
         builder.build()
     }
 
@@ -126,8 +200,12 @@ impl Pass for DrawShadedSeparate {
         (
             entities,
             active,
+            visibility,
             camera,
             ambient,
+            fog,
+            shadow,
+            point_shadows,
             mesh_storage,
             tex_storage,
             material_defaults,
@@ -136,18 +214,52 @@ impl Pass for DrawShadedSeparate {
             material,
             global,
             light,
+            shadow_caster,
             joints,
             transparent,
+            hidden,
+            render_mask,
         ): <Self as PassData<'a>>::Data,
     ) {
         trace!("Drawing shaded pass");
-        let camera = get_camera(active, &camera, &global);
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+        let attributes = self.attributes();
 
-        set_light_args(effect, encoder, &light, &ambient, camera);
+        effect.update_global("normal_mapping", self.normal_mapping as i32);
+        if let Some(ref mut clusters) = self.clusters {
+            set_light_args(effect, encoder, clusters, &light, &ambient, &fog, camera);
+        }
+        if self.shadow_map.is_some() {
+            set_shadow_args(effect, &light, &shadow, camera);
+        }
+        set_point_shadow_args(
+            effect,
+            &entities,
+            &light,
+            &shadow_caster,
+            &point_shadows,
+            &shadow,
+        );
+        if self.ssao.is_some() {
+            set_ssao_args(effect);
+        }
 
-        for (entity, mesh, material, global, _) in
-            (&*entities, &mesh, &material, &global, !&transparent).join()
+        for (entity, mesh, material, global, _, _) in
+            (&*entities, &mesh, &material, &global, !&transparent, !&hidden).join()
         {
+            if !is_visible(effect, &visibility, entity)
+                || !in_render_mask(mask, &render_mask, entity)
+            {
+                continue;
+            }
             draw_mesh(
                 encoder,
                 effect,
@@ -157,14 +269,20 @@ impl Pass for DrawShadedSeparate {
                 &*tex_storage,
                 Some(material),
                 &*material_defaults,
+                None,
                 camera,
                 Some(global),
-                &ATTRIBUTES,
+                attributes,
                 &TEXTURES,
             );
         }
 
         for entity in &back_to_front.entities {
+            if !is_visible(effect, &visibility, *entity)
+                || !in_render_mask(mask, &render_mask, *entity)
+            {
+                continue;
+            }
             if let Some(mesh) = mesh.get(*entity) {
                 draw_mesh(
                     encoder,
@@ -175,9 +293,10 @@ impl Pass for DrawShadedSeparate {
                     &*tex_storage,
                     material.get(*entity),
                     &*material_defaults,
+                    None,
                     camera,
                     global.get(*entity),
-                    &ATTRIBUTES,
+                    attributes,
                     &TEXTURES,
                 );
             }
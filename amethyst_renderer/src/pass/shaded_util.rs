@@ -1,37 +1,80 @@
 use std::mem;
 
 use amethyst_core::GlobalTransform;
+use amethyst_core::cgmath::{InnerSpace, Vector3};
+use gfx::memory::cast_slice;
 use gfx::traits::Pod;
 use specs::{Join, ReadStorage};
 
 use cam::Camera;
-use light::{DirectionalLight, Light, PointLight};
+use light::{AreaLight, AreaShape, Attenuation, DirectionalLight, Light, PointLight, SpotLight};
+use pass::clusters::{setup_light_clusters, LightClusters, MAX_POINT_LIGHTS};
 use pipe::{Effect, EffectBuilder};
-use resources::AmbientColor;
+use resources::{AmbientColor, Fog, FogMode};
 use types::Encoder;
 
 fn pad(x: [f32; 3]) -> [f32; 4] {
     [x[0], x[1], x[2], 1.0]
 }
 
+/// Normalizes a direction, or returns `default` for a null vector.
+fn direction(x: [f32; 3], default: Vector3<f32>) -> Vector3<f32> {
+    let x = Vector3::from(x);
+    if x.magnitude2() > 0.0 {
+        x.normalize()
+    } else {
+        default
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct FragmentArgs {
     point_light_count: i32,
     directional_light_count: i32,
+    spot_light_count: i32,
+    area_light_count: i32,
 }
 
+/// The texels of a point light in the `LightClusters`.
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct PointLightPod {
     position: [f32; 4],
     color: [f32; 4],
+    // Constant, linear and quadratic terms of `Attenuation::Constants`.
+    falloff: [f32; 4],
     intensity: f32,
-    _pad: [f32; 3],
+    radius: f32,
+    smoothness: f32,
+    attenuation: f32,
 }
 
 unsafe impl Pod for PointLightPod {}
 
+impl PointLightPod {
+    fn new(light: &PointLight) -> Self {
+        let (attenuation, falloff) = match light.attenuation {
+            Attenuation::InverseSquare => (0.0, [0.0; 4]),
+            Attenuation::Linear => (1.0, [0.0; 4]),
+            Attenuation::Constants {
+                constant,
+                linear,
+                quadratic,
+            } => (2.0, [constant, linear, quadratic, 0.0]),
+        };
+        PointLightPod {
+            position: pad(light.center.into()),
+            color: pad(light.color.into()),
+            falloff,
+            intensity: light.intensity,
+            radius: light.radius.max(0.001),
+            smoothness: light.smoothness,
+            attenuation,
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct DirectionalLightPod {
@@ -41,28 +84,137 @@ pub(crate) struct DirectionalLightPod {
 
 unsafe impl Pod for DirectionalLightPod {}
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SpotLightPod {
+    position: [f32; 4],
+    color: [f32; 4],
+    direction: [f32; 4],
+    intensity: f32,
+    radius: f32,
+    smoothness: f32,
+    angle_scale: f32,
+    angle_offset: f32,
+    _pad: [f32; 3],
+}
+
+unsafe impl Pod for SpotLightPod {}
+
+impl SpotLightPod {
+    fn new(light: &SpotLight) -> Self {
+        let direction = direction(light.direction, -Vector3::unit_y());
+        // The cone falloff is `saturate(cos(angle) * scale + offset)`, going
+        // from 1 at the inner cone to 0 at the outer one.
+        let cos_outer = (light.outer_angle / 2.0).to_radians().cos();
+        let cos_inner = (light.inner_angle.min(light.outer_angle) / 2.0)
+            .to_radians()
+            .cos();
+        let angle_scale = 1.0 / (cos_inner - cos_outer).max(0.001);
+        SpotLightPod {
+            position: pad(light.center.into()),
+            color: pad(light.color.into()),
+            direction: pad(direction.into()),
+            intensity: light.intensity,
+            radius: light.radius.max(0.001),
+            smoothness: light.smoothness,
+            angle_scale,
+            angle_offset: -cos_outer * angle_scale,
+            _pad: [0.0; 3],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct AreaLightPod {
+    position: [f32; 4],
+    color: [f32; 4],
+    // Axes of the shape, along with its half extents in `w`.
+    axis_x: [f32; 4],
+    axis_y: [f32; 4],
+    intensity: f32,
+    radius: f32,
+    smoothness: f32,
+    source_radius: f32,
+    shape: i32,
+    _pad: [f32; 3],
+}
+
+unsafe impl Pod for AreaLightPod {}
+
+impl AreaLightPod {
+    fn new(light: &AreaLight) -> Self {
+        let (shape, axis_x, axis_y, source_radius) = match light.shape {
+            AreaShape::Sphere { radius } => (
+                0,
+                Vector3::unit_x().extend(0.0),
+                Vector3::unit_y().extend(0.0),
+                radius,
+            ),
+            AreaShape::Tube {
+                axis,
+                length,
+                radius,
+            } => {
+                let axis = direction(axis, Vector3::unit_y());
+                (
+                    1,
+                    axis.extend(length / 2.0),
+                    Vector3::unit_y().extend(0.0),
+                    radius,
+                )
+            }
+            AreaShape::Rect {
+                right,
+                up,
+                width,
+                height,
+            } => {
+                let right = direction(right, Vector3::unit_x());
+                let up = direction(up, Vector3::unit_y());
+                let up = direction(
+                    (up - right * up.dot(right)).into(),
+                    right.cross(Vector3::unit_z()),
+                );
+                (2, right.extend(width / 2.0), up.extend(height / 2.0), 0.0)
+            }
+        };
+        AreaLightPod {
+            position: pad(light.center.into()),
+            color: pad(light.color.into()),
+            axis_x: axis_x.into(),
+            axis_y: axis_y.into(),
+            intensity: light.intensity,
+            radius: light.radius.max(0.001),
+            smoothness: light.smoothness,
+            source_radius: source_radius.max(0.0),
+            shape,
+            _pad: [0.0; 3],
+        }
+    }
+}
+
 pub(crate) fn set_light_args(
     effect: &mut Effect,
     encoder: &mut Encoder,
+    clusters: &mut LightClusters,
     light: &ReadStorage<Light>,
     ambient: &AmbientColor,
+    fog: &Fog,
     camera: Option<(&Camera, &GlobalTransform)>,
 ) {
-    let point_lights: Vec<PointLightPod> = light
+    let (point_lights, spheres): (Vec<PointLightPod>, Vec<_>) = light
         .join()
         .filter_map(|light| {
             if let Light::Point(ref light) = *light {
-                Some(PointLightPod {
-                    position: pad(light.center.into()),
-                    color: pad(light.color.into()),
-                    intensity: light.intensity,
-                    _pad: [0.0; 3],
-                })
+                let sphere = (Vector3::from(light.center), light.radius.max(0.001));
+                Some((PointLightPod::new(light), sphere))
             } else {
                 None
             }
         })
-        .collect();
+        .take(MAX_POINT_LIGHTS)
+        .unzip();
 
     let directional_lights: Vec<DirectionalLightPod> = light
         .join()
@@ -78,14 +230,40 @@ pub(crate) fn set_light_args(
         })
         .collect();
 
+    let spot_lights: Vec<SpotLightPod> = light
+        .join()
+        .filter_map(|light| {
+            if let Light::Spot(ref light) = *light {
+                Some(SpotLightPod::new(light))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let area_lights: Vec<AreaLightPod> = light
+        .join()
+        .filter_map(|light| {
+            if let Light::Area(ref light) = *light {
+                Some(AreaLightPod::new(light))
+            } else {
+                None
+            }
+        })
+        .collect();
+
     let fragment_args = FragmentArgs {
         point_light_count: point_lights.len() as i32,
         directional_light_count: directional_lights.len() as i32,
+        spot_light_count: spot_lights.len() as i32,
+        area_light_count: area_lights.len() as i32,
     };
 
     effect.update_constant_buffer("FragmentArgs", &fragment_args, encoder);
-    effect.update_buffer("PointLights", &point_lights[..], encoder);
     effect.update_buffer("DirectionalLights", &directional_lights[..], encoder);
+    effect.update_buffer("SpotLights", &spot_lights[..], encoder);
+    effect.update_buffer("AreaLights", &area_lights[..], encoder);
+    clusters.update(effect, encoder, cast_slice(&point_lights), &spheres, camera);
 
     effect.update_global("ambient_color", Into::<[f32; 3]>::into(*ambient.as_ref()));
 
@@ -96,13 +274,34 @@ pub(crate) fn set_light_args(
             .map(|&(_, ref trans)| [trans.0[3][0], trans.0[3][1], trans.0[3][2]])
             .unwrap_or([0.0; 3]),
     );
+
+    let (mode, range) = match fog.mode {
+        FogMode::Disabled => (0, [0.0; 2]),
+        FogMode::Linear { start, end } => (1, [start, end]),
+        FogMode::Exponential => (2, [0.0; 2]),
+        FogMode::ExponentialSquared => (3, [0.0; 2]),
+    };
+    effect.update_global("fog_mode", mode);
+    effect.update_global("fog_color", Into::<[f32; 3]>::into(fog.color));
+    effect.update_global("fog_density", fog.density);
+    effect.update_global("fog_range", range);
+    effect.update_global("fog_height", fog.height);
+    effect.update_global("fog_height_falloff", fog.height_falloff);
 }
 
-pub(crate) fn setup_light_buffers(builder: &mut EffectBuilder) {
+pub(crate) fn setup_light_buffers(builder: &mut EffectBuilder, clusters: &LightClusters) {
+    setup_light_clusters(builder, clusters);
     builder
         .with_raw_constant_buffer("FragmentArgs", mem::size_of::<FragmentArgs>(), 1)
-        .with_raw_constant_buffer("PointLights", mem::size_of::<PointLight>(), 128)
         .with_raw_constant_buffer("DirectionalLights", mem::size_of::<DirectionalLight>(), 16)
+        .with_raw_constant_buffer("SpotLights", mem::size_of::<SpotLightPod>(), 128)
+        .with_raw_constant_buffer("AreaLights", mem::size_of::<AreaLightPod>(), 32)
         .with_raw_global("ambient_color")
-        .with_raw_global("camera_position");
+        .with_raw_global("camera_position")
+        .with_raw_global("fog_mode")
+        .with_raw_global("fog_color")
+        .with_raw_global("fog_density")
+        .with_raw_global("fog_range")
+        .with_raw_global("fog_height")
+        .with_raw_global("fog_height_falloff");
 }
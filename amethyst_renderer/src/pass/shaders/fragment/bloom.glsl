@@ -0,0 +1,45 @@
+// Bloom: bright-pass extraction, separable gaussian blur and composition.
+
+#version 150 core
+
+uniform sampler2D source;
+uniform sampler2D bloom;
+
+// 0: extract, 1: horizontal blur, 2: vertical blur, 3: composite.
+uniform int mode;
+uniform float threshold;
+uniform float intensity;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+const float WEIGHTS[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+vec4 blur(vec2 direction) {
+    vec2 texel = direction / vec2(textureSize(bloom, 0));
+    vec4 result = texture(bloom, vertex.tex_coord) * WEIGHTS[0];
+    for (int i = 1; i < 5; i++) {
+        result += texture(bloom, vertex.tex_coord + texel * i) * WEIGHTS[i];
+        result += texture(bloom, vertex.tex_coord - texel * i) * WEIGHTS[i];
+    }
+    return result;
+}
+
+void main() {
+    if (mode == 0) {
+        vec4 color = texture(source, vertex.tex_coord);
+        float luminance = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+        out_color = luminance > threshold ? vec4(color.rgb, 1.0) : vec4(0.0, 0.0, 0.0, 1.0);
+    } else if (mode == 1) {
+        out_color = blur(vec2(1.0, 0.0));
+    } else if (mode == 2) {
+        out_color = blur(vec2(0.0, 1.0));
+    } else {
+        vec4 color = texture(source, vertex.tex_coord);
+        vec3 glow = texture(bloom, vertex.tex_coord).rgb * intensity;
+        out_color = vec4(color.rgb + glow, color.a);
+    }
+}
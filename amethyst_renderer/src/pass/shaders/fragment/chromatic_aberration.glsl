@@ -0,0 +1,22 @@
+// Splits the color channels apart towards the edges of the screen.
+
+#version 150 core
+
+uniform sampler2D source;
+uniform float intensity;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+void main() {
+    vec2 uv = vertex.tex_coord;
+    // Grows from none at the center to `intensity` at the corners.
+    vec2 offset = (uv - 0.5) * intensity * 2.0 / sqrt(2.0);
+    vec4 color = texture(source, uv);
+    float red = texture(source, uv + offset).r;
+    float blue = texture(source, uv - offset).b;
+    out_color = vec4(red, color.g, blue, color.a);
+}
@@ -0,0 +1,48 @@
+// Color grading through a lookup table unwrapped into a horizontal strip.
+
+#version 150 core
+
+uniform sampler2D source;
+uniform sampler2D lut;
+uniform float intensity;
+uniform int srgb;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+vec3 to_srgb(vec3 color) {
+    vec3 low = color * 12.92;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(low, high, step(vec3(0.0031308), color));
+}
+
+vec3 to_linear(vec3 color) {
+    vec3 low = color / 12.92;
+    vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(low, high, step(vec3(0.04045), color));
+}
+
+// The strip holds one square slice per blue value, red growing to the right
+// and green downwards within each slice. Neighbouring slices are blended.
+vec3 lookup(vec3 color) {
+    float size = float(textureSize(lut, 0).y);
+    vec3 scaled = clamp(color, 0.0, 1.0) * (size - 1.0);
+    float slice = floor(scaled.b);
+    float next = min(slice + 1.0, size - 1.0);
+    vec2 texel = (scaled.rg + 0.5) / vec2(size * size, size);
+    vec3 low = texture(lut, texel + vec2(slice / size, 0.0)).rgb;
+    vec3 high = texture(lut, texel + vec2(next / size, 0.0)).rgb;
+    return mix(low, high, scaled.b - slice);
+}
+
+void main() {
+    vec4 color = texture(source, vertex.tex_coord);
+    // The table maps display colors, the targets hold linear ones with sRGB.
+    vec3 display = srgb == 1 ? to_srgb(color.rgb) : color.rgb;
+    vec3 graded = lookup(display);
+    graded = srgb == 1 ? to_linear(graded) : graded;
+    out_color = vec4(mix(color.rgb, graded, intensity), color.a);
+}
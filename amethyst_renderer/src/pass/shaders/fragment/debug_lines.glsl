@@ -0,0 +1,13 @@
+// Colored lines in world space.
+
+#version 150 core
+
+in VertexData {
+    vec4 color;
+} vertex;
+
+out vec4 out_color;
+
+void main() {
+    out_color = vertex.color;
+}
@@ -0,0 +1,35 @@
+// Projects the albedo of a decal onto the surfaces reconstructed from depth.
+
+#version 150 core
+
+uniform sampler2D depth;
+uniform sampler2D albedo;
+uniform vec4 albedo_factor;
+
+uniform mat4 inv_view_proj;
+uniform mat4 inv_model;
+uniform float opacity;
+uniform float fade;
+
+in VertexData {
+    vec4 uv_transform;
+} vertex;
+
+out vec4 out_color;
+
+void main() {
+    vec2 screen = gl_FragCoord.xy / vec2(textureSize(depth, 0));
+    float d = texture(depth, screen).r * 2.0 - 1.0;
+    vec4 world = inv_view_proj * vec4(screen * 2.0 - 1.0, d, 1.0);
+    vec3 local = (inv_model * vec4(world.xyz / world.w, 1.0)).xyz;
+    if (any(greaterThan(abs(local), vec3(0.5)))) {
+        discard;
+    }
+
+    vec2 tex_coord = local.xy + 0.5;
+    vec4 color = texture(albedo, tex_coord * vertex.uv_transform.zw + vertex.uv_transform.xy)
+        * albedo_factor;
+    float edge = 1.0 - abs(local.z) * 2.0;
+    float alpha = fade > 0.0 ? clamp(edge / fade, 0.0, 1.0) : 1.0;
+    out_color = vec4(color.rgb, color.a * opacity * alpha);
+}
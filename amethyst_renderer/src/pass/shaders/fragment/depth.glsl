@@ -0,0 +1,6 @@
+// Depth only, the depth buffer of the target is read by later passes.
+
+#version 150 core
+
+void main() {
+}
@@ -0,0 +1,65 @@
+// Depth of field, gathering a bokeh disc around each pixel.
+
+#version 150 core
+
+const int MAX_SAMPLES = 64;
+const float GOLDEN_ANGLE = 2.39996323;
+// Width of the edge of the blur discs, as a fraction of the screen height.
+const float EDGE = 0.002;
+
+uniform sampler2D source;
+uniform sampler2D depth;
+uniform mat4 inv_proj;
+uniform float focus_distance;
+uniform float aperture;
+uniform float max_blur;
+uniform int auto_focus;
+uniform int samples;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+float view_depth(vec2 uv) {
+    float d = texture(depth, uv).r * 2.0 - 1.0;
+    vec4 view = inv_proj * vec4(uv * 2.0 - 1.0, d, 1.0);
+    return -view.z / view.w;
+}
+
+// Radius of the circle of confusion, as a fraction of the screen height.
+float blur_radius(float distance, float focus) {
+    return clamp(aperture * abs(distance - focus) / max(distance, 0.0001), 0.0, max_blur);
+}
+
+void main() {
+    vec2 uv = vertex.tex_coord;
+    vec2 size = vec2(textureSize(source, 0));
+    vec2 aspect = vec2(size.y / size.x, 1.0);
+
+    float focus = auto_focus == 1 ? view_depth(vec2(0.5)) : focus_distance;
+    float center_depth = view_depth(uv);
+    float center_radius = blur_radius(center_depth, focus);
+
+    vec4 color = texture(source, uv);
+    float total = 1.0;
+    int count = clamp(samples, 1, MAX_SAMPLES);
+    for (int i = 0; i < count; i++) {
+        // Spiral covering the largest disc evenly.
+        float radius = sqrt((float(i) + 0.5) / float(count)) * max_blur;
+        float angle = float(i) * GOLDEN_ANGLE;
+        vec2 sample_uv = uv + vec2(cos(angle), sin(angle)) * radius * aspect;
+        float sample_depth = view_depth(sample_uv);
+        float sample_radius = blur_radius(sample_depth, focus);
+        // Sharp surfaces in front don't bleed over the blurred background
+        // behind them, while blurred ones in front spread over everything.
+        if (sample_depth > center_depth) {
+            sample_radius = min(sample_radius, center_radius);
+        }
+        float weight = smoothstep(radius - EDGE, radius + EDGE, sample_radius);
+        color += texture(source, sample_uv) * weight;
+        total += weight;
+    }
+    out_color = color / total;
+}
@@ -3,6 +3,7 @@
 #version 150 core
 
 uniform sampler2D albedo;
+uniform vec4 albedo_factor;
 
 in VertexData {
     vec4 position;
@@ -14,5 +15,5 @@ in VertexData {
 out vec4 color;
 
 void main() {
-    color = texture(albedo, vertex.tex_coord);
+    color = texture(albedo, vertex.tex_coord) * albedo_factor;
 }
@@ -0,0 +1,51 @@
+// Fast approximate anti-aliasing.
+
+#version 150 core
+
+const float SPAN_MAX = 8.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float REDUCE_MIN = 1.0 / 128.0;
+const vec3 LUMA = vec3(0.299, 0.587, 0.114);
+
+uniform sampler2D source;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+void main() {
+    vec2 uv = vertex.tex_coord;
+    vec2 texel = 1.0 / vec2(textureSize(source, 0));
+
+    vec4 color = texture(source, uv);
+    float luma_nw = dot(texture(source, uv + vec2(-1.0, -1.0) * texel).rgb, LUMA);
+    float luma_ne = dot(texture(source, uv + vec2(1.0, -1.0) * texel).rgb, LUMA);
+    float luma_sw = dot(texture(source, uv + vec2(-1.0, 1.0) * texel).rgb, LUMA);
+    float luma_se = dot(texture(source, uv + vec2(1.0, 1.0) * texel).rgb, LUMA);
+    float luma_m = dot(color.rgb, LUMA);
+
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    vec2 dir = vec2(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * texel;
+
+    vec3 rgb_a = 0.5 * (
+        texture(source, uv + dir * (1.0 / 3.0 - 0.5)).rgb +
+        texture(source, uv + dir * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 rgb_b = rgb_a * 0.5 + 0.25 * (
+        texture(source, uv - dir * 0.5).rgb +
+        texture(source, uv + dir * 0.5).rgb
+    );
+    float luma_b = dot(rgb_b, LUMA);
+
+    out_color = vec4(luma_b < luma_min || luma_b > luma_max ? rgb_a : rgb_b, color.a);
+}
@@ -0,0 +1,46 @@
+// Blurs the scene along the movement of the camera, reconstructed from depth.
+
+#version 150 core
+
+const int MAX_SAMPLES = 32;
+
+uniform sampler2D source;
+uniform sampler2D depth;
+uniform mat4 inv_view_proj;
+uniform mat4 prev_view_proj;
+uniform float intensity;
+uniform float max_length;
+uniform int samples;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+void main() {
+    vec2 uv = vertex.tex_coord;
+    float d = texture(depth, uv).r * 2.0 - 1.0;
+    vec4 ndc = vec4(uv * 2.0 - 1.0, d, 1.0);
+    vec4 world = inv_view_proj * ndc;
+    vec4 previous = prev_view_proj * (world / world.w);
+    if (previous.w <= 0.0) {
+        out_color = texture(source, uv);
+        return;
+    }
+
+    // Velocity in texture coordinates, from the previous frame to this one.
+    vec2 velocity = (ndc.xy - previous.xy / previous.w) * 0.5 * intensity;
+    float speed = length(velocity);
+    if (speed > max_length) {
+        velocity *= max_length / speed;
+    }
+
+    int count = clamp(samples, 1, MAX_SAMPLES);
+    vec4 color = vec4(0.0);
+    for (int i = 0; i < count; i++) {
+        float t = count > 1 ? float(i) / float(count - 1) - 0.5 : 0.0;
+        color += texture(source, uv - velocity * t);
+    }
+    out_color = color / float(count);
+}
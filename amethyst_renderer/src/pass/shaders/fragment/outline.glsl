@@ -0,0 +1,11 @@
+// Single colored outline.
+
+#version 150 core
+
+uniform vec4 outline_color;
+
+out vec4 out_color;
+
+void main() {
+    out_color = outline_color;
+}
@@ -0,0 +1,16 @@
+// Textured and tinted particles.
+
+#version 150 core
+
+uniform sampler2D albedo;
+
+in VertexData {
+    vec4 color;
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+void main() {
+    out_color = texture(albedo, vertex.tex_coord) * vertex.color;
+}
@@ -5,20 +5,33 @@
 layout (std140) uniform FragmentArgs {
     int point_light_count;
     int directional_light_count;
+    int spot_light_count;
+    int area_light_count;
 };
 
 struct PointLight {
     vec4 position;
     vec4 color;
+    // Constant, linear and quadratic terms of the custom falloff.
+    vec4 falloff;
     float intensity;
     float radius;
     float smoothness;
-    float _pad;
+    // 0: inverse square, 1: linear, 2: custom constants.
+    int attenuation;
 };
 
-layout (std140) uniform PointLights {
-    PointLight plight[128];
-};
+// Point lights, 4 texels per light.
+uniform sampler2D point_lights;
+// Offset and count of the light indices of each cluster.
+uniform sampler2D light_grid;
+// Indices of the point lights of the clusters, 4 per texel.
+uniform sampler2D light_indices;
+uniform int clustered;
+uniform mat4 cluster_view;
+uniform mat4 cluster_proj;
+// Near and far depth of the clusters.
+uniform vec2 cluster_depth;
 
 struct DirectionalLight {
     vec4 color;
@@ -29,17 +42,89 @@ layout (std140) uniform DirectionalLights {
     DirectionalLight dlight[16];
 };
 
+struct SpotLight {
+    vec4 position;
+    vec4 color;
+    vec4 direction;
+    float intensity;
+    float radius;
+    float smoothness;
+    // Map the cosine of the angle to the axis of the cone to its falloff.
+    float angle_scale;
+    float angle_offset;
+};
+
+layout (std140) uniform SpotLights {
+    SpotLight slight[128];
+};
+
+struct AreaLight {
+    vec4 position;
+    vec4 color;
+    // Axes of the shape, along with its half extents in `w`.
+    vec4 axis_x;
+    vec4 axis_y;
+    float intensity;
+    float radius;
+    float smoothness;
+    float source_radius;
+    // 0: sphere, 1: tube, 2: rectangle.
+    int shape;
+};
+
+layout (std140) uniform AreaLights {
+    AreaLight alight[32];
+};
+
 uniform vec3 ambient_color;
 uniform vec3 camera_position;
 
 uniform sampler2D albedo;
 uniform sampler2D emission;
+uniform vec4 albedo_factor;
+uniform vec4 emission_factor;
 uniform sampler2D normal;
 uniform sampler2D metallic;
 uniform sampler2D roughness;
 uniform sampler2D ambient_occlusion;
 uniform sampler2D caveat;
 
+uniform sampler2D shadow_map;
+// Light space of each cascade, mapped to its part of the shadow map.
+uniform mat4 light_space0;
+uniform mat4 light_space1;
+uniform mat4 light_space2;
+uniform mat4 light_space3;
+uniform int cascade_count;
+// 0: no shadows, 1: first directional light, 2: first point light.
+uniform int shadow_light;
+uniform float shadow_bias;
+uniform int pcf_radius;
+uniform samplerCube point_shadow_map0;
+uniform samplerCube point_shadow_map1;
+uniform samplerCube point_shadow_map2;
+uniform samplerCube point_shadow_map3;
+// Index of the point light casting shadows through each cubemap, -1 if unused.
+uniform ivec4 point_shadow_lights;
+uniform vec4 point_shadow_far;
+uniform float point_shadow_bias;
+uniform sampler2D ssao;
+uniform int ssao_enabled;
+
+// 0: no fog, 1: linear, 2: exponential, 3: exponential squared.
+uniform int fog_mode;
+uniform vec3 fog_color;
+uniform float fog_density;
+uniform vec2 fog_range;
+uniform float fog_height;
+uniform float fog_height_falloff;
+
+uniform samplerCube irradiance_map;
+uniform samplerCube specular_map;
+uniform sampler2D brdf_lut;
+uniform float specular_levels;
+uniform int ibl_enabled;
+
 in VertexData {
     vec4 position;
     vec3 normal;
@@ -76,9 +161,218 @@ vec3 fresnel(float HdotV, vec3 fresnel_base) {
     return fresnel_base + (1.0 - fresnel_base) * pow(1.0 - HdotV, 5.0);
 }
 
+vec3 fresnel_roughness(float NdotV, vec3 fresnel_base, float roughness) {
+    return fresnel_base + (max(vec3(1.0 - roughness), fresnel_base) - fresnel_base) * pow(1.0 - NdotV, 5.0);
+}
+
+const ivec3 CLUSTERS = ivec3(16, 9, 24);
+const int INDEX_WIDTH = 1024;
+
+PointLight point_light(int i) {
+    vec4 params = texelFetch(point_lights, ivec2(3, i), 0);
+    PointLight light;
+    light.position = texelFetch(point_lights, ivec2(0, i), 0);
+    light.color = texelFetch(point_lights, ivec2(1, i), 0);
+    light.falloff = texelFetch(point_lights, ivec2(2, i), 0);
+    light.intensity = params.x;
+    light.radius = params.y;
+    light.smoothness = params.z;
+    light.attenuation = int(params.w);
+    return light;
+}
+
+// Offset and count of the point light indices of the cluster of the
+// position, the third component is 0 when it's outside of the clusters and
+// all the lights are used.
+ivec3 light_cluster(vec3 position) {
+    if (clustered == 0) {
+        return ivec3(0, point_light_count, 0);
+    }
+    vec4 view_position = cluster_view * vec4(position, 1.0);
+    vec4 clip = cluster_proj * view_position;
+    float depth = -view_position.z;
+    vec2 ndc = clip.xy / clip.w;
+    if (clip.w <= 0.0 || depth > cluster_depth.y || any(greaterThan(abs(ndc), vec2(1.0)))) {
+        return ivec3(0, point_light_count, 0);
+    }
+    float slice = log(max(depth, cluster_depth.x) / cluster_depth.x)
+        / log(cluster_depth.y / cluster_depth.x) * float(CLUSTERS.z);
+    int z = clamp(int(slice), 0, CLUSTERS.z - 1);
+    ivec2 xy = clamp(ivec2((ndc * 0.5 + 0.5) * vec2(CLUSTERS.xy)), ivec2(0), CLUSTERS.xy - 1);
+    vec4 cluster = texelFetch(light_grid, ivec2(xy.x + xy.y * CLUSTERS.x, z), 0);
+    return ivec3(int(cluster.x), int(cluster.y), 1);
+}
+
+// Index of the n-th point light of a cluster.
+int cluster_light(ivec3 cluster, int n) {
+    if (cluster.z == 0) {
+        return n;
+    }
+    int index = cluster.x + n;
+    int texel = index / 4;
+    vec4 indices = texelFetch(light_indices, ivec2(texel % INDEX_WIDTH, texel / INDEX_WIDTH), 0);
+    return int(indices[index % 4]);
+}
+
+float point_shadow_depth(int map, vec3 direction) {
+    if (map == 0) {
+        return texture(point_shadow_map0, direction).r;
+    } else if (map == 1) {
+        return texture(point_shadow_map1, direction).r;
+    } else if (map == 2) {
+        return texture(point_shadow_map2, direction).r;
+    }
+    return texture(point_shadow_map3, direction).r;
+}
+
+float point_shadow_factor(int light, vec3 position) {
+    for (int map = 0; map < 4; map++) {
+        if (point_shadow_lights[map] == light) {
+            vec3 direction = position - point_light(light).position.xyz;
+            float depth = length(direction) / point_shadow_far[map];
+            return depth - point_shadow_bias > point_shadow_depth(map, direction) ? 0.0 : 1.0;
+        }
+    }
+    return 1.0;
+}
+
+// Light reaching the position from a point light, fading out towards its
+// radius.
+float point_attenuation(PointLight light, vec3 position) {
+    float dist = distance(light.position.xyz, position);
+    float ratio = dist / light.radius;
+    if (light.attenuation == 1) {
+        return light.intensity * clamp(1.0 - ratio, 0.0, 1.0);
+    }
+    float window = clamp(1.0 - pow(ratio, light.smoothness), 0.0, 1.0);
+    float divisor = light.attenuation == 2
+        ? dot(light.falloff.xyz, vec3(1.0, dist, dist * dist))
+        : dist * dist;
+    return light.intensity / max(divisor, 0.0001) * window * window;
+}
+
+// Light reaching the position from a spot light, fading out towards its
+// radius and the edge of its cone.
+float spot_attenuation(int i, vec3 position) {
+    vec3 to_light = slight[i].position.xyz - position;
+    float dist2 = max(dot(to_light, to_light), 0.0001);
+    float window = clamp(1.0 - pow(sqrt(dist2) / slight[i].radius, slight[i].smoothness), 0.0, 1.0);
+    float cos_angle = dot(-normalize(to_light), slight[i].direction.xyz);
+    float cone = clamp(cos_angle * slight[i].angle_scale + slight[i].angle_offset, 0.0, 1.0);
+    return slight[i].intensity / dist2 * window * window * cone * cone;
+}
+
+// Representative point of an area light: the point of its shape closest to a
+// ray from the position, relative to the position.
+vec3 area_light_point(int i, vec3 position, vec3 ray) {
+    vec3 center = alight[i].position.xyz - position;
+    vec3 axis_x = alight[i].axis_x.xyz;
+    vec3 axis_y = alight[i].axis_y.xyz;
+    if (alight[i].shape == 1) {
+        vec3 start = center - axis_x * alight[i].axis_x.w;
+        vec3 segment = axis_x * (2.0 * alight[i].axis_x.w);
+        float ray_segment = dot(ray, segment);
+        float t = (dot(ray, start) * ray_segment - dot(start, segment))
+            / max(dot(segment, segment) - ray_segment * ray_segment, 0.0001);
+        center = start + clamp(t, 0.0, 1.0) * segment;
+    } else if (alight[i].shape == 2) {
+        vec3 normal = cross(axis_x, axis_y);
+        float facing = dot(ray, normal);
+        // Where the ray crosses the plane of the rectangle, or the closest
+        // point of the plane when the ray goes away from it.
+        vec3 hit = facing < -0.0001
+            ? ray * (dot(center, normal) / facing)
+            : normal * dot(center, normal);
+        vec3 offset = hit - center;
+        return center
+            + axis_x * clamp(dot(offset, axis_x), -alight[i].axis_x.w, alight[i].axis_x.w)
+            + axis_y * clamp(dot(offset, axis_y), -alight[i].axis_y.w, alight[i].axis_y.w);
+    }
+    vec3 to_ray = dot(center, ray) * ray - center;
+    return center + to_ray * clamp(alight[i].source_radius / max(length(to_ray), 0.0001), 0.0, 1.0);
+}
+
+// Light reaching the position from an area light, fading out towards its
+// radius.
+float area_light_attenuation(int i, vec3 position, vec3 closest) {
+    if (alight[i].shape == 2) {
+        vec3 normal = cross(alight[i].axis_x.xyz, alight[i].axis_y.xyz);
+        if (dot(position - alight[i].position.xyz, normal) <= 0.0) {
+            return 0.0;
+        }
+    }
+    float dist2 = dot(closest, closest);
+    float window = clamp(1.0 - pow(sqrt(dist2) / alight[i].radius, alight[i].smoothness), 0.0, 1.0);
+    float source2 = alight[i].source_radius * alight[i].source_radius;
+    return alight[i].intensity / max(dist2 + source2, 0.0001) * window * window;
+}
+
+// Half size of an area light, widening the specular highlight it makes.
+float area_light_size(int i) {
+    if (alight[i].shape == 1) {
+        return alight[i].source_radius + alight[i].axis_x.w;
+    } else if (alight[i].shape == 2) {
+        return max(alight[i].axis_x.w, alight[i].axis_y.w);
+    }
+    return alight[i].source_radius;
+}
+
+mat4 cascade_light_space(int cascade) {
+    if (cascade == 0) {
+        return light_space0;
+    } else if (cascade == 1) {
+        return light_space1;
+    } else if (cascade == 2) {
+        return light_space2;
+    }
+    return light_space3;
+}
+
+float shadow_factor(vec4 position) {
+    vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0));
+    // The filtered texels have to stay in the part of the shadow map of the cascade.
+    vec2 margin = texel * float(pcf_radius) * vec2(float(cascade_count), 1.0);
+    for (int cascade = 0; cascade < cascade_count; cascade++) {
+        vec4 light_position = cascade_light_space(cascade) * position;
+        vec3 coords = light_position.xyz / light_position.w * 0.5 + 0.5;
+        vec2 tile = vec2(coords.x * float(cascade_count) - float(cascade), coords.y);
+        if (coords.z > 1.0 || any(lessThan(tile, margin)) || any(greaterThan(tile, 1.0 - margin))) {
+            continue;
+        }
+        float lit = 0.0;
+        for (int x = -pcf_radius; x <= pcf_radius; x++) {
+            for (int y = -pcf_radius; y <= pcf_radius; y++) {
+                float depth = texture(shadow_map, coords.xy + vec2(x, y) * texel).r;
+                lit += coords.z - shadow_bias > depth ? 0.0 : 1.0;
+            }
+        }
+        float size = float(2 * pcf_radius + 1);
+        return lit / (size * size);
+    }
+    return 1.0;
+}
+
+vec3 apply_fog(vec3 color, vec3 position) {
+    if (fog_mode == 0) {
+        return color;
+    }
+    float dist = distance(camera_position, position);
+    float fog;
+    if (fog_mode == 1) {
+        fog = clamp((dist - fog_range.x) / max(fog_range.y - fog_range.x, 0.0001), 0.0, 1.0);
+    } else if (fog_mode == 2) {
+        fog = 1.0 - exp(-fog_density * dist);
+    } else {
+        float d = fog_density * dist;
+        fog = 1.0 - exp(-d * d);
+    }
+    fog *= exp(-fog_height_falloff * max(position.y - fog_height, 0.0));
+    return mix(color, fog_color, fog);
+}
+
 void main() {
-    vec3 albedo             = texture(albedo, vertex.tex_coord).rgb;
-    vec3 emission           = texture(emission, vertex.tex_coord).rgb;
+    vec3 albedo             = texture(albedo, vertex.tex_coord).rgb * albedo_factor.rgb;
+    vec3 emission           = texture(emission, vertex.tex_coord).rgb * emission_factor.rgb;
     vec3 normal             = texture(normal, vertex.tex_coord).rgb;
     float metallic          = texture(metallic, vertex.tex_coord).r;
     float roughness         = texture(roughness, vertex.tex_coord).r;
@@ -98,11 +392,20 @@ void main() {
     normal = normalize(vertex_basis * normal);
 
 
+    float shadow = shadow_light != 0 ? shadow_factor(vertex.position) : 1.0;
+
     vec3 lighted = vec3(0.0);
-    for (int i = 0; i < point_light_count; i++) {
+    ivec3 cluster = light_cluster(vertex.position.xyz);
+    for (int n = 0; n < cluster.y; n++) {
+        int i = cluster_light(cluster, n);
+        PointLight light = point_light(i);
         vec3 view_direction = normalize(camera_position - vertex.position.xyz);
-        vec3 light_direction = normalize(plight[i].position.xyz - vertex.position.xyz);
-        float intensity = plight[i].intensity / dot(light_direction, light_direction);
+        vec3 light_direction = normalize(light.position.xyz - vertex.position.xyz);
+        float intensity = point_attenuation(light, vertex.position.xyz);
+        if (i == 0 && shadow_light == 2) {
+            intensity *= shadow;
+        }
+        intensity *= point_shadow_factor(i, vertex.position.xyz);
 
         vec3 halfway = normalize(view_direction + light_direction);
         float normal_distribution = normal_distribution(normal, halfway, roughness2);
@@ -120,11 +423,113 @@ void main() {
         float denominator = 4 * NdotV * NdotL + 0.0001;
         vec3 specular = nominator / denominator;
 
-        lighted += (diffuse * albedo / PI + specular) * plight[i].color.rgb * intensity * NdotL;
+        lighted += (diffuse * albedo / PI + specular) * light.color.rgb * intensity * NdotL;
     }
 
-    vec3 ambient = ambient_color * albedo * ambient_occlusion;
-    vec3 color = ambient + lighted + emission;
+    for (int i = 0; i < directional_light_count; i++) {
+        vec3 view_direction = normalize(camera_position - vertex.position.xyz);
+        vec3 light_direction = -normalize(dlight[i].direction.xyz);
+        float intensity = i == 0 && shadow_light == 1 ? shadow : 1.0;
+
+        vec3 halfway = normalize(view_direction + light_direction);
+        float normal_distribution = normal_distribution(normal, halfway, roughness2);
+
+        float NdotV = max(dot(normal, view_direction), 0.0);
+        float NdotL = max(dot(normal, light_direction), 0.0);
+        float HdotV = max(dot(halfway, view_direction), 0.0);
+        float geometry = geometry(NdotV, NdotL, roughness2);
+
+        vec3 fresnel = fresnel_base + (1.0 - fresnel_base) * pow(1.0 - HdotV, 5.0);
+        vec3 diffuse = vec3(1.0) - fresnel;
+        diffuse *= 1.0 - metallic;
+
+        vec3 nominator = normal_distribution * geometry * fresnel;
+        float denominator = 4 * NdotV * NdotL + 0.0001;
+        vec3 specular = nominator / denominator;
+
+        lighted += (diffuse * albedo / PI + specular) * dlight[i].color.rgb * intensity * NdotL;
+    }
+
+    for (int i = 0; i < spot_light_count; i++) {
+        vec3 view_direction = normalize(camera_position - vertex.position.xyz);
+        vec3 light_direction = normalize(slight[i].position.xyz - vertex.position.xyz);
+        float intensity = spot_attenuation(i, vertex.position.xyz);
+
+        vec3 halfway = normalize(view_direction + light_direction);
+        float normal_distribution = normal_distribution(normal, halfway, roughness2);
+
+        float NdotV = max(dot(normal, view_direction), 0.0);
+        float NdotL = max(dot(normal, light_direction), 0.0);
+        float HdotV = max(dot(halfway, view_direction), 0.0);
+        float geometry = geometry(NdotV, NdotL, roughness2);
+
+        vec3 fresnel = fresnel_base + (1.0 - fresnel_base) * pow(1.0 - HdotV, 5.0);
+        vec3 diffuse = vec3(1.0) - fresnel;
+        diffuse *= 1.0 - metallic;
+
+        vec3 nominator = normal_distribution * geometry * fresnel;
+        float denominator = 4 * NdotV * NdotL + 0.0001;
+        vec3 specular = nominator / denominator;
+
+        lighted += (diffuse * albedo / PI + specular) * slight[i].color.rgb * intensity * NdotL;
+    }
+
+    for (int i = 0; i < area_light_count; i++) {
+        vec3 view_direction = normalize(camera_position - vertex.position.xyz);
+        vec3 reflected = reflect(-view_direction, normal);
+        vec3 diffuse_point = area_light_point(i, vertex.position.xyz, normal);
+        vec3 specular_point = area_light_point(i, vertex.position.xyz, reflected);
+        vec3 diffuse_direction = normalize(diffuse_point);
+        vec3 light_direction = normalize(specular_point);
+        float intensity = area_light_attenuation(i, vertex.position.xyz, diffuse_point);
+
+        // The highlight spreads over the shape of the light, widening the
+        // distribution keeps its energy.
+        float widened = clamp(roughness2 + area_light_size(i) / (2.0 * length(specular_point) + 0.0001), 0.0, 1.0);
+        float energy = roughness2 / max(widened, 0.0001);
+        energy *= energy;
+
+        vec3 halfway = normalize(view_direction + light_direction);
+        float normal_distribution = normal_distribution(normal, halfway, roughness2);
+
+        float NdotV = max(dot(normal, view_direction), 0.0);
+        float NdotL = max(dot(normal, light_direction), 0.0);
+        float HdotV = max(dot(halfway, view_direction), 0.0);
+        float geometry = geometry(NdotV, NdotL, roughness2);
+
+        vec3 fresnel = fresnel_base + (1.0 - fresnel_base) * pow(1.0 - HdotV, 5.0);
+        vec3 diffuse = vec3(1.0) - fresnel;
+        diffuse *= 1.0 - metallic;
+        diffuse *= max(dot(normal, diffuse_direction), 0.0);
+
+        vec3 nominator = normal_distribution * geometry * fresnel;
+        float denominator = 4 * NdotV * NdotL + 0.0001;
+        vec3 specular = nominator / denominator * energy * NdotL;
+
+        lighted += (diffuse * albedo / PI + specular) * alight[i].color.rgb * intensity;
+    }
+
+    float screen_occlusion = ssao_enabled != 0
+        ? texture(ssao, gl_FragCoord.xy / vec2(textureSize(ssao, 0))).r
+        : 1.0;
+    vec3 ambient;
+    if (ibl_enabled != 0) {
+        vec3 view_direction = normalize(camera_position - vertex.position.xyz);
+        float NdotV = max(dot(normal, view_direction), 0.0);
+        vec3 fresnel = fresnel_roughness(NdotV, fresnel_base, roughness);
+        vec3 diffuse = (vec3(1.0) - fresnel) * (1.0 - metallic);
+        diffuse *= texture(irradiance_map, normal).rgb * albedo;
+
+        vec3 reflected = reflect(-view_direction, normal);
+        vec3 prefiltered = textureLod(specular_map, reflected, roughness * (specular_levels - 1.0)).rgb;
+        vec2 brdf = texture(brdf_lut, vec2(NdotV, roughness)).rg;
+        vec3 specular = prefiltered * (fresnel * brdf.x + brdf.y);
+
+        ambient = (diffuse + specular) * ambient_occlusion * screen_occlusion;
+    } else {
+        ambient = ambient_color * albedo * ambient_occlusion * screen_occlusion;
+    }
+    vec3 color = apply_fog(ambient + lighted + emission, vertex.position.xyz);
    
     out_color = vec4(color, 1.0);
 }
@@ -0,0 +1,17 @@
+// Distance to the point light, relative to the range of its shadow cubemap.
+
+#version 150 core
+
+uniform vec3 light_position;
+uniform float light_far;
+
+in VertexData {
+    vec4 position;
+    vec3 normal;
+    vec3 tangent;
+    vec2 tex_coord;
+} vertex;
+
+void main() {
+    gl_FragDepth = distance(vertex.position.xyz, light_position) / light_far;
+}
@@ -0,0 +1,15 @@
+// Samples the sky cubemap in the view direction.
+
+#version 150 core
+
+uniform samplerCube skybox;
+
+in VertexData {
+    vec3 direction;
+} vertex;
+
+out vec4 out_color;
+
+void main() {
+    out_color = texture(skybox, normalize(vertex.direction));
+}
@@ -0,0 +1,19 @@
+// Textured sprites, fully transparent texels are discarded.
+
+#version 150 core
+
+uniform sampler2D albedo;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+void main() {
+    vec4 color = texture(albedo, vertex.tex_coord);
+    if (color.a == 0.0) {
+        discard;
+    }
+    out_color = color;
+}
@@ -0,0 +1,69 @@
+// Screen space ambient occlusion, view space normals are reconstructed from depth.
+
+#version 150 core
+
+const int MAX_SAMPLES = 64;
+
+uniform sampler2D depth;
+
+uniform mat4 proj;
+uniform mat4 inv_proj;
+uniform float radius;
+uniform float bias;
+uniform int samples;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+float random(vec2 seed) {
+    return fract(sin(dot(seed, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+vec3 view_position(vec2 tex_coord) {
+    float d = texture(depth, tex_coord).r * 2.0 - 1.0;
+    vec4 position = inv_proj * vec4(tex_coord * 2.0 - 1.0, d, 1.0);
+    return position.xyz / position.w;
+}
+
+void main() {
+    vec2 uv = vertex.tex_coord;
+    if (texture(depth, uv).r >= 1.0) {
+        out_color = vec4(1.0);
+        return;
+    }
+
+    vec3 position = view_position(uv);
+    vec3 normal = normalize(cross(dFdx(position), dFdy(position)));
+
+    vec3 random_vec = normalize(vec3(random(uv) * 2.0 - 1.0, random(uv.yx) * 2.0 - 1.0, 0.0));
+    vec3 tangent = normalize(random_vec - normal * dot(random_vec, normal));
+    vec3 bitangent = cross(normal, tangent);
+    mat3 basis = mat3(tangent, bitangent, normal);
+
+    int count = clamp(samples, 1, MAX_SAMPLES);
+    float occlusion = 0.0;
+    for (int i = 0; i < count; i++) {
+        float fi = float(i);
+        vec3 offset = vec3(
+            random(uv + fi) * 2.0 - 1.0,
+            random(uv * 1.7 + fi) * 2.0 - 1.0,
+            random(uv * 2.3 + fi)
+        );
+        float scale = fi / float(count);
+        offset = normalize(offset) * random(uv * 3.1 + fi) * mix(0.1, 1.0, scale * scale);
+
+        vec3 sample_position = position + basis * offset * radius;
+        vec4 projected = proj * vec4(sample_position, 1.0);
+        vec2 sample_uv = projected.xy / projected.w * 0.5 + 0.5;
+
+        float sample_depth = view_position(sample_uv).z;
+        float range = smoothstep(0.0, 1.0, radius / abs(position.z - sample_depth));
+        occlusion += (sample_depth >= sample_position.z + bias ? 1.0 : 0.0) * range;
+    }
+
+    float ambient_occlusion = 1.0 - occlusion / float(count);
+    out_color = vec4(vec3(ambient_occlusion), 1.0);
+}
@@ -0,0 +1,213 @@
+// Terrain layers blended by a splat map, lit like the shaded pass.
+
+#version 150 core
+
+layout (std140) uniform FragmentArgs {
+    int point_light_count;
+    int directional_light_count;
+    int spot_light_count;
+    int area_light_count;
+};
+
+struct PointLight {
+    vec4 position;
+    vec4 color;
+    // Constant, linear and quadratic terms of the custom falloff.
+    vec4 falloff;
+    float intensity;
+    float radius;
+    float smoothness;
+    // 0: inverse square, 1: linear, 2: custom constants.
+    int attenuation;
+};
+
+// Point lights, 4 texels per light.
+uniform sampler2D point_lights;
+// Offset and count of the light indices of each cluster.
+uniform sampler2D light_grid;
+// Indices of the point lights of the clusters, 4 per texel.
+uniform sampler2D light_indices;
+uniform int clustered;
+uniform mat4 cluster_view;
+uniform mat4 cluster_proj;
+// Near and far depth of the clusters.
+uniform vec2 cluster_depth;
+
+struct DirectionalLight {
+    vec4 color;
+    vec4 direction;
+};
+
+layout (std140) uniform DirectionalLights {
+    DirectionalLight dlight[16];
+};
+
+struct SpotLight {
+    vec4 position;
+    vec4 color;
+    vec4 direction;
+    float intensity;
+    float radius;
+    float smoothness;
+    // Map the cosine of the angle to the axis of the cone to its falloff.
+    float angle_scale;
+    float angle_offset;
+};
+
+layout (std140) uniform SpotLights {
+    SpotLight slight[128];
+};
+
+uniform vec3 ambient_color;
+uniform vec3 camera_position;
+
+uniform sampler2D splat_map;
+uniform sampler2D layer0;
+uniform sampler2D layer1;
+uniform sampler2D layer2;
+uniform sampler2D layer3;
+uniform int layer_count;
+uniform float tiling;
+
+// 0: no fog, 1: linear, 2: exponential, 3: exponential squared.
+uniform int fog_mode;
+uniform vec3 fog_color;
+uniform float fog_density;
+uniform vec2 fog_range;
+uniform float fog_height;
+uniform float fog_height_falloff;
+
+in VertexData {
+    vec4 position;
+    vec3 normal;
+    vec3 tangent;
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+vec3 apply_fog(vec3 color, vec3 position) {
+    if (fog_mode == 0) {
+        return color;
+    }
+    float dist = distance(camera_position, position);
+    float fog;
+    if (fog_mode == 1) {
+        fog = clamp((dist - fog_range.x) / max(fog_range.y - fog_range.x, 0.0001), 0.0, 1.0);
+    } else if (fog_mode == 2) {
+        fog = 1.0 - exp(-fog_density * dist);
+    } else {
+        float d = fog_density * dist;
+        fog = 1.0 - exp(-d * d);
+    }
+    fog *= exp(-fog_height_falloff * max(position.y - fog_height, 0.0));
+    return mix(color, fog_color, fog);
+}
+
+const ivec3 CLUSTERS = ivec3(16, 9, 24);
+const int INDEX_WIDTH = 1024;
+
+PointLight point_light(int i) {
+    vec4 params = texelFetch(point_lights, ivec2(3, i), 0);
+    PointLight light;
+    light.position = texelFetch(point_lights, ivec2(0, i), 0);
+    light.color = texelFetch(point_lights, ivec2(1, i), 0);
+    light.falloff = texelFetch(point_lights, ivec2(2, i), 0);
+    light.intensity = params.x;
+    light.radius = params.y;
+    light.smoothness = params.z;
+    light.attenuation = int(params.w);
+    return light;
+}
+
+// Offset and count of the point light indices of the cluster of the
+// position, the third component is 0 when it's outside of the clusters and
+// all the lights are used.
+ivec3 light_cluster(vec3 position) {
+    if (clustered == 0) {
+        return ivec3(0, point_light_count, 0);
+    }
+    vec4 view_position = cluster_view * vec4(position, 1.0);
+    vec4 clip = cluster_proj * view_position;
+    float depth = -view_position.z;
+    vec2 ndc = clip.xy / clip.w;
+    if (clip.w <= 0.0 || depth > cluster_depth.y || any(greaterThan(abs(ndc), vec2(1.0)))) {
+        return ivec3(0, point_light_count, 0);
+    }
+    float slice = log(max(depth, cluster_depth.x) / cluster_depth.x)
+        / log(cluster_depth.y / cluster_depth.x) * float(CLUSTERS.z);
+    int z = clamp(int(slice), 0, CLUSTERS.z - 1);
+    ivec2 xy = clamp(ivec2((ndc * 0.5 + 0.5) * vec2(CLUSTERS.xy)), ivec2(0), CLUSTERS.xy - 1);
+    vec4 cluster = texelFetch(light_grid, ivec2(xy.x + xy.y * CLUSTERS.x, z), 0);
+    return ivec3(int(cluster.x), int(cluster.y), 1);
+}
+
+// Index of the n-th point light of a cluster.
+int cluster_light(ivec3 cluster, int n) {
+    if (cluster.z == 0) {
+        return n;
+    }
+    int index = cluster.x + n;
+    int texel = index / 4;
+    vec4 indices = texelFetch(light_indices, ivec2(texel % INDEX_WIDTH, texel / INDEX_WIDTH), 0);
+    return int(indices[index % 4]);
+}
+
+// Light reaching the position from a point light, fading out towards its
+// radius.
+float point_attenuation(PointLight light, vec3 position) {
+    float dist = distance(light.position.xyz, position);
+    float ratio = dist / light.radius;
+    if (light.attenuation == 1) {
+        return light.intensity * clamp(1.0 - ratio, 0.0, 1.0);
+    }
+    float window = clamp(1.0 - pow(ratio, light.smoothness), 0.0, 1.0);
+    float divisor = light.attenuation == 2
+        ? dot(light.falloff.xyz, vec3(1.0, dist, dist * dist))
+        : dist * dist;
+    return light.intensity / max(divisor, 0.0001) * window * window;
+}
+
+// Light reaching the position from a spot light, fading out towards its
+// radius and the edge of its cone.
+float spot_attenuation(int i, vec3 position) {
+    vec3 to_light = slight[i].position.xyz - position;
+    float dist2 = max(dot(to_light, to_light), 0.0001);
+    float window = clamp(1.0 - pow(sqrt(dist2) / slight[i].radius, slight[i].smoothness), 0.0, 1.0);
+    float cos_angle = dot(-normalize(to_light), slight[i].direction.xyz);
+    float cone = clamp(cos_angle * slight[i].angle_scale + slight[i].angle_offset, 0.0, 1.0);
+    return slight[i].intensity / dist2 * window * window * cone * cone;
+}
+
+void main() {
+    vec4 weights = texture(splat_map, vertex.tex_coord);
+    weights *= vec4(greaterThan(vec4(layer_count), vec4(0.0, 1.0, 2.0, 3.0)));
+    float total = max(dot(weights, vec4(1.0)), 0.0001);
+    vec2 uv = vertex.tex_coord * tiling;
+    vec4 color = (texture(layer0, uv) * weights.r
+        + texture(layer1, uv) * weights.g
+        + texture(layer2, uv) * weights.b
+        + texture(layer3, uv) * weights.a) / total;
+
+    vec4 lighting = vec4(0.0);
+    vec4 normal = vec4(normalize(vertex.normal), 0.0);
+    ivec3 cluster = light_cluster(vertex.position.xyz);
+    for (int n = 0; n < cluster.y; n++) {
+        PointLight light = point_light(cluster_light(cluster, n));
+        vec4 light_dir = normalize(light.position - vertex.position);
+        float diff = max(dot(light_dir, normal), 0.0);
+        lighting += diff * light.color * point_attenuation(light, vertex.position.xyz);
+    }
+    for (int i = 0; i < directional_light_count; i++) {
+        float diff = max(dot(-dlight[i].direction, normal), 0.0);
+        lighting += diff * dlight[i].color;
+    }
+    for (int i = 0; i < spot_light_count; i++) {
+        vec4 light_dir = normalize(slight[i].position - vertex.position);
+        float diff = max(dot(light_dir, normal), 0.0);
+        lighting += diff * slight[i].color * spot_attenuation(i, vertex.position.xyz);
+    }
+    lighting += vec4(ambient_color, 0.0);
+    vec4 lit = lighting * color;
+    out_color = vec4(apply_fog(lit.rgb, vertex.position.xyz), 1.0);
+}
@@ -0,0 +1,34 @@
+// Maps high dynamic range colors into the displayable range.
+
+#version 150 core
+
+uniform sampler2D source;
+uniform int operator;
+uniform float exposure;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+vec3 reinhard(vec3 color) {
+    return color / (color + vec3(1.0));
+}
+
+// Narkowicz's fit of the ACES filmic curve.
+vec3 aces(vec3 color) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), 0.0, 1.0);
+}
+
+void main() {
+    vec4 color = texture(source, vertex.tex_coord);
+    vec3 hdr = color.rgb * exposure;
+    vec3 ldr = operator == 1 ? aces(hdr) : reinhard(hdr);
+    out_color = vec4(ldr, color.a);
+}
@@ -0,0 +1,22 @@
+// Darkens the corners of the screen.
+
+#version 150 core
+
+uniform sampler2D source;
+uniform float intensity;
+uniform float radius;
+uniform float smoothness;
+uniform vec3 vignette_color;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+void main() {
+    vec4 color = texture(source, vertex.tex_coord);
+    float distance = length(vertex.tex_coord * 2.0 - 1.0);
+    float amount = smoothstep(radius, radius + smoothness, distance) * intensity;
+    out_color = vec4(mix(color.rgb, vignette_color, clamp(amount, 0.0, 1.0)), color.a);
+}
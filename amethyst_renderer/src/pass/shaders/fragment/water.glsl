@@ -0,0 +1,59 @@
+// Water surface blending its reflection and refraction with a fresnel term.
+
+#version 150 core
+
+uniform sampler2D normal_map;
+uniform sampler2D reflection;
+uniform sampler2D refraction;
+uniform int has_reflection;
+uniform int has_refraction;
+
+uniform vec3 camera_position;
+uniform vec4 water_color;
+uniform float tiling;
+uniform vec2 wave_offset;
+uniform float distortion;
+uniform float fresnel_power;
+
+in VertexData {
+    vec4 position;
+    vec3 normal;
+    vec3 tangent;
+    vec3 bitangent;
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+void main() {
+    // Two layers of waves scrolling in different directions.
+    vec2 uv = vertex.tex_coord * tiling;
+    vec3 waves = texture(normal_map, uv + wave_offset).rgb
+        + texture(normal_map, uv * 0.8 - wave_offset.yx).rgb
+        - 1.0;
+    vec3 normal = normalize(
+        vertex.tangent * waves.x
+        + vertex.bitangent * waves.y
+        + vertex.normal * max(waves.z, 0.001)
+    );
+    vec2 offset = waves.xy * distortion;
+
+    vec3 refracted = water_color.rgb;
+    float alpha = water_color.a;
+    if (has_refraction != 0) {
+        vec2 screen = gl_FragCoord.xy / vec2(textureSize(refraction, 0));
+        refracted = mix(texture(refraction, screen + offset).rgb, water_color.rgb, water_color.a);
+        alpha = 1.0;
+    }
+
+    vec3 reflected = water_color.rgb;
+    if (has_reflection != 0) {
+        // The reflection camera draws a horizontally flipped image.
+        vec2 screen = gl_FragCoord.xy / vec2(textureSize(reflection, 0));
+        reflected = texture(reflection, vec2(1.0 - screen.x, screen.y) + offset).rgb;
+    }
+
+    vec3 view_dir = normalize(camera_position - vertex.position.xyz);
+    float fresnel = pow(1.0 - max(dot(view_dir, normal), 0.0), fresnel_power);
+    out_color = vec4(mix(refracted, reflected, fresnel), mix(alpha, 1.0, fresnel));
+}
@@ -0,0 +1,11 @@
+// Single colored wireframe.
+
+#version 150 core
+
+uniform vec4 wireframe_color;
+
+out vec4 out_color;
+
+void main() {
+    out_color = wireframe_color;
+}
@@ -0,0 +1,19 @@
+// GLSL ES variant of fragment/flat.glsl, without the interface block.
+
+#version 300 es
+
+precision highp float;
+
+uniform sampler2D albedo;
+uniform vec4 albedo_factor;
+
+in vec4 vertex_position;
+in vec3 vertex_normal;
+in vec3 vertex_tangent;
+in vec2 vertex_tex_coord;
+
+out vec4 color;
+
+void main() {
+    color = texture(albedo, vertex_tex_coord) * albedo_factor;
+}
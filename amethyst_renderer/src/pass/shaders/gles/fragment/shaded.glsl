@@ -0,0 +1,314 @@
+// GLSL ES variant of fragment/shaded.glsl, without the interface block.
+
+#version 300 es
+
+precision highp float;
+precision highp int;
+// Samplers default to a low precision in fragment shaders, which would round
+// the lights read from textures.
+precision highp sampler2D;
+precision highp samplerCube;
+
+layout (std140) uniform FragmentArgs {
+    int point_light_count;
+    int directional_light_count;
+    int spot_light_count;
+    int area_light_count;
+};
+
+struct PointLight {
+    vec4 position;
+    vec4 color;
+    // Constant, linear and quadratic terms of the custom falloff.
+    vec4 falloff;
+    float intensity;
+    float radius;
+    float smoothness;
+    // 0: inverse square, 1: linear, 2: custom constants.
+    int attenuation;
+};
+
+// Point lights, 4 texels per light.
+uniform sampler2D point_lights;
+// Offset and count of the light indices of each cluster.
+uniform sampler2D light_grid;
+// Indices of the point lights of the clusters, 4 per texel.
+uniform sampler2D light_indices;
+uniform int clustered;
+uniform mat4 cluster_view;
+uniform mat4 cluster_proj;
+// Near and far depth of the clusters.
+uniform vec2 cluster_depth;
+
+struct DirectionalLight {
+    vec4 color;
+    vec4 direction;
+};
+
+layout (std140) uniform DirectionalLights {
+    DirectionalLight dlight[16];
+};
+
+struct SpotLight {
+    vec4 position;
+    vec4 color;
+    vec4 direction;
+    float intensity;
+    float radius;
+    float smoothness;
+    // Map the cosine of the angle to the axis of the cone to its falloff.
+    float angle_scale;
+    float angle_offset;
+};
+
+layout (std140) uniform SpotLights {
+    SpotLight slight[128];
+};
+
+uniform vec3 ambient_color;
+uniform vec3 camera_position;
+
+uniform sampler2D albedo;
+uniform sampler2D emission;
+uniform vec4 albedo_factor;
+uniform vec4 emission_factor;
+uniform sampler2D normal;
+uniform int normal_mapping;
+
+uniform sampler2D shadow_map;
+// Light space of each cascade, mapped to its part of the shadow map.
+uniform mat4 light_space0;
+uniform mat4 light_space1;
+uniform mat4 light_space2;
+uniform mat4 light_space3;
+uniform int cascade_count;
+// 0: no shadows, 1: first directional light, 2: first point light.
+uniform int shadow_light;
+uniform float shadow_bias;
+uniform int pcf_radius;
+uniform samplerCube point_shadow_map0;
+uniform samplerCube point_shadow_map1;
+uniform samplerCube point_shadow_map2;
+uniform samplerCube point_shadow_map3;
+// Index of the point light casting shadows through each cubemap, -1 if unused.
+uniform ivec4 point_shadow_lights;
+uniform vec4 point_shadow_far;
+uniform float point_shadow_bias;
+uniform sampler2D ssao;
+uniform int ssao_enabled;
+
+// 0: no fog, 1: linear, 2: exponential, 3: exponential squared.
+uniform int fog_mode;
+uniform vec3 fog_color;
+uniform float fog_density;
+uniform vec2 fog_range;
+uniform float fog_height;
+uniform float fog_height_falloff;
+
+in vec4 vertex_position;
+in vec3 vertex_normal;
+in vec3 vertex_tangent;
+in vec2 vertex_tex_coord;
+
+out vec4 out_color;
+
+vec3 apply_fog(vec3 color, vec3 position) {
+    if (fog_mode == 0) {
+        return color;
+    }
+    float dist = distance(camera_position, position);
+    float fog;
+    if (fog_mode == 1) {
+        fog = clamp((dist - fog_range.x) / max(fog_range.y - fog_range.x, 0.0001), 0.0, 1.0);
+    } else if (fog_mode == 2) {
+        fog = 1.0 - exp(-fog_density * dist);
+    } else {
+        float d = fog_density * dist;
+        fog = 1.0 - exp(-d * d);
+    }
+    fog *= exp(-fog_height_falloff * max(position.y - fog_height, 0.0));
+    return mix(color, fog_color, fog);
+}
+
+const ivec3 CLUSTERS = ivec3(16, 9, 24);
+const int INDEX_WIDTH = 1024;
+
+PointLight point_light(int i) {
+    vec4 params = texelFetch(point_lights, ivec2(3, i), 0);
+    PointLight light;
+    light.position = texelFetch(point_lights, ivec2(0, i), 0);
+    light.color = texelFetch(point_lights, ivec2(1, i), 0);
+    light.falloff = texelFetch(point_lights, ivec2(2, i), 0);
+    light.intensity = params.x;
+    light.radius = params.y;
+    light.smoothness = params.z;
+    light.attenuation = int(params.w);
+    return light;
+}
+
+// Offset and count of the point light indices of the cluster of the
+// position, the third component is 0 when it's outside of the clusters and
+// all the lights are used.
+ivec3 light_cluster(vec3 position) {
+    if (clustered == 0) {
+        return ivec3(0, point_light_count, 0);
+    }
+    vec4 view_position = cluster_view * vec4(position, 1.0);
+    vec4 clip = cluster_proj * view_position;
+    float depth = -view_position.z;
+    vec2 ndc = clip.xy / clip.w;
+    if (clip.w <= 0.0 || depth > cluster_depth.y || any(greaterThan(abs(ndc), vec2(1.0)))) {
+        return ivec3(0, point_light_count, 0);
+    }
+    float slice = log(max(depth, cluster_depth.x) / cluster_depth.x)
+        / log(cluster_depth.y / cluster_depth.x) * float(CLUSTERS.z);
+    int z = clamp(int(slice), 0, CLUSTERS.z - 1);
+    ivec2 xy = clamp(ivec2((ndc * 0.5 + 0.5) * vec2(CLUSTERS.xy)), ivec2(0), CLUSTERS.xy - 1);
+    vec4 cluster = texelFetch(light_grid, ivec2(xy.x + xy.y * CLUSTERS.x, z), 0);
+    return ivec3(int(cluster.x), int(cluster.y), 1);
+}
+
+// Index of the n-th point light of a cluster.
+int cluster_light(ivec3 cluster, int n) {
+    if (cluster.z == 0) {
+        return n;
+    }
+    int index = cluster.x + n;
+    int texel = index / 4;
+    vec4 indices = texelFetch(light_indices, ivec2(texel % INDEX_WIDTH, texel / INDEX_WIDTH), 0);
+    return int(indices[index % 4]);
+}
+
+float point_shadow_depth(int map, vec3 direction) {
+    if (map == 0) {
+        return texture(point_shadow_map0, direction).r;
+    } else if (map == 1) {
+        return texture(point_shadow_map1, direction).r;
+    } else if (map == 2) {
+        return texture(point_shadow_map2, direction).r;
+    }
+    return texture(point_shadow_map3, direction).r;
+}
+
+float point_shadow_factor(int light, vec3 position) {
+    for (int map = 0; map < 4; map++) {
+        if (point_shadow_lights[map] == light) {
+            vec3 direction = position - point_light(light).position.xyz;
+            float depth = length(direction) / point_shadow_far[map];
+            return depth - point_shadow_bias > point_shadow_depth(map, direction) ? 0.0 : 1.0;
+        }
+    }
+    return 1.0;
+}
+
+// Light reaching the position from a point light, fading out towards its
+// radius.
+float point_attenuation(PointLight light, vec3 position) {
+    float dist = distance(light.position.xyz, position);
+    float ratio = dist / light.radius;
+    if (light.attenuation == 1) {
+        return light.intensity * clamp(1.0 - ratio, 0.0, 1.0);
+    }
+    float window = clamp(1.0 - pow(ratio, light.smoothness), 0.0, 1.0);
+    float divisor = light.attenuation == 2
+        ? dot(light.falloff.xyz, vec3(1.0, dist, dist * dist))
+        : dist * dist;
+    return light.intensity / max(divisor, 0.0001) * window * window;
+}
+
+// Light reaching the position from a spot light, fading out towards its
+// radius and the edge of its cone.
+float spot_attenuation(int i, vec3 position) {
+    vec3 to_light = slight[i].position.xyz - position;
+    float dist2 = max(dot(to_light, to_light), 0.0001);
+    float window = clamp(1.0 - pow(sqrt(dist2) / slight[i].radius, slight[i].smoothness), 0.0, 1.0);
+    float cos_angle = dot(-normalize(to_light), slight[i].direction.xyz);
+    float cone = clamp(cos_angle * slight[i].angle_scale + slight[i].angle_offset, 0.0, 1.0);
+    return slight[i].intensity / dist2 * window * window * cone * cone;
+}
+
+mat4 cascade_light_space(int cascade) {
+    if (cascade == 0) {
+        return light_space0;
+    } else if (cascade == 1) {
+        return light_space1;
+    } else if (cascade == 2) {
+        return light_space2;
+    }
+    return light_space3;
+}
+
+float shadow_factor(vec4 position) {
+    vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0));
+    // The filtered texels have to stay in the part of the shadow map of the cascade.
+    vec2 margin = texel * float(pcf_radius) * vec2(float(cascade_count), 1.0);
+    for (int cascade = 0; cascade < cascade_count; cascade++) {
+        vec4 light_position = cascade_light_space(cascade) * position;
+        vec3 coords = light_position.xyz / light_position.w * 0.5 + 0.5;
+        vec2 tile = vec2(coords.x * float(cascade_count) - float(cascade), coords.y);
+        if (coords.z > 1.0 || any(lessThan(tile, margin)) || any(greaterThan(tile, 1.0 - margin))) {
+            continue;
+        }
+        float lit = 0.0;
+        for (int x = -pcf_radius; x <= pcf_radius; x++) {
+            for (int y = -pcf_radius; y <= pcf_radius; y++) {
+                float depth = texture(shadow_map, coords.xy + vec2(x, y) * texel).r;
+                lit += coords.z - shadow_bias > depth ? 0.0 : 1.0;
+            }
+        }
+        float size = float(2 * pcf_radius + 1);
+        return lit / (size * size);
+    }
+    return 1.0;
+}
+
+void main() {
+    vec4 color = texture(albedo, vertex_tex_coord) * albedo_factor;
+    vec4 ecolor = texture(emission, vertex_tex_coord) * emission_factor;
+    vec4 lighting = vec4(0.0);
+    vec3 surface_normal = normalize(vertex_normal);
+    if (normal_mapping != 0) {
+        vec3 surface_tangent = normalize(vertex_tangent - surface_normal * dot(surface_normal, vertex_tangent));
+        vec3 surface_bitangent = normalize(cross(surface_normal, surface_tangent));
+        mat3 surface_basis = mat3(surface_tangent, surface_bitangent, surface_normal);
+        surface_normal = normalize(surface_basis * (texture(normal, vertex_tex_coord).rgb * 2.0 - 1.0));
+    }
+    vec4 normal = vec4(surface_normal, 0.0);
+    float shadow = shadow_light != 0 ? shadow_factor(vertex_position) : 1.0;
+    ivec3 cluster = light_cluster(vertex_position.xyz);
+    for (int n = 0; n < cluster.y; n++) {
+        int i = cluster_light(cluster, n);
+        PointLight light = point_light(i);
+        // Calculate diffuse light
+        vec4 light_dir = normalize(light.position - vertex_position);
+        float diff = max(dot(light_dir, normal), 0.0);
+        vec4 diffuse = diff * light.color;
+        // Calculate attenuation
+        float attenuation = point_attenuation(light, vertex_position.xyz);
+        if (i == 0 && shadow_light == 2) {
+            attenuation *= shadow;
+        }
+        attenuation *= point_shadow_factor(i, vertex_position.xyz);
+        lighting += diffuse * attenuation;
+    }
+    for (int i = 0; i < directional_light_count; i++) {
+        vec4 dir = dlight[i].direction;
+        float diff = max(dot(-dir, normal), 0.0);
+        vec4 diffuse = diff * dlight[i].color;
+        if (i == 0 && shadow_light == 1) {
+            diffuse *= shadow;
+        }
+        lighting += diffuse;
+    }
+    for (int i = 0; i < spot_light_count; i++) {
+        vec4 light_dir = normalize(slight[i].position - vertex_position);
+        float diff = max(dot(light_dir, normal), 0.0);
+        lighting += diff * slight[i].color * spot_attenuation(i, vertex_position.xyz);
+    }
+    float screen_occlusion = ssao_enabled != 0
+        ? texture(ssao, gl_FragCoord.xy / vec2(textureSize(ssao, 0))).r
+        : 1.0;
+    lighting += vec4(ambient_color * screen_occlusion, 0.0);
+    vec4 lit = lighting * color + ecolor;
+    out_color = vec4(apply_fog(lit.rgb, vertex_position.xyz), lit.a);
+}
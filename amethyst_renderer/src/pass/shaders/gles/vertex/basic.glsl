@@ -0,0 +1,30 @@
+// GLSL ES variant of vertex/basic.glsl, without the interface block.
+
+#version 300 es
+
+precision highp float;
+
+layout (std140) uniform VertexArgs {
+    mat4 proj;
+    mat4 view;
+    mat4 model;
+    vec4 uv_transform;
+};
+
+in vec3 position;
+in vec3 normal;
+in vec3 tangent;
+in vec2 tex_coord;
+
+out vec4 vertex_position;
+out vec3 vertex_normal;
+out vec3 vertex_tangent;
+out vec2 vertex_tex_coord;
+
+void main() {
+    vertex_position = model * vec4(position, 1.0);
+    vertex_normal = mat3(model) * normal;
+    vertex_tangent = mat3(model) * tangent;
+    vertex_tex_coord = tex_coord * uv_transform.zw + uv_transform.xy;
+    gl_Position = proj * view * vertex_position;
+}
@@ -0,0 +1,35 @@
+// GLSL ES variant of vertex/instanced.glsl, without the interface block.
+
+#version 300 es
+
+precision highp float;
+
+layout (std140) uniform VertexArgs {
+    mat4 proj;
+    mat4 view;
+    mat4 model;
+    vec4 uv_transform;
+};
+
+in vec3 position;
+in vec3 normal;
+in vec3 tangent;
+in vec2 tex_coord;
+in vec4 model_0;
+in vec4 model_1;
+in vec4 model_2;
+in vec4 model_3;
+
+out vec4 vertex_position;
+out vec3 vertex_normal;
+out vec3 vertex_tangent;
+out vec2 vertex_tex_coord;
+
+void main() {
+    mat4 instance_model = mat4(model_0, model_1, model_2, model_3);
+    vertex_position = instance_model * vec4(position, 1.0);
+    vertex_normal = mat3(instance_model) * normal;
+    vertex_tangent = mat3(instance_model) * tangent;
+    vertex_tex_coord = tex_coord * uv_transform.zw + uv_transform.xy;
+    gl_Position = proj * view * vertex_position;
+}
@@ -6,6 +6,7 @@ layout (std140) uniform VertexArgs {
     uniform mat4 proj;
     uniform mat4 view;
     uniform mat4 model;
+    uniform vec4 uv_transform;
 };
 
 in vec3 position;
@@ -24,6 +25,6 @@ void main() {
     vertex.position = model * vec4(position, 1.0);
     vertex.normal = mat3(model) * normal;
     vertex.tangent = mat3(model) * tangent;
-    vertex.tex_coord = tex_coord;
+    vertex.tex_coord = tex_coord * uv_transform.zw + uv_transform.xy;
     gl_Position = proj * view * vertex.position;
 }
@@ -0,0 +1,21 @@
+// Colored lines in world space.
+
+#version 150 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+};
+
+in vec3 position;
+in vec4 color;
+
+out VertexData {
+    vec4 color;
+} vertex;
+
+void main() {
+    vertex.color = color;
+    gl_Position = proj * view * vec4(position, 1.0);
+}
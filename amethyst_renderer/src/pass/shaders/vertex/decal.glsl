@@ -0,0 +1,40 @@
+// Unit cube of a decal, generated from the vertex index.
+
+#version 150 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 uv_transform;
+};
+
+const vec3 CORNERS[8] = vec3[8](
+    vec3(-0.5, -0.5, -0.5),
+    vec3(0.5, -0.5, -0.5),
+    vec3(-0.5, 0.5, -0.5),
+    vec3(0.5, 0.5, -0.5),
+    vec3(-0.5, -0.5, 0.5),
+    vec3(0.5, -0.5, 0.5),
+    vec3(-0.5, 0.5, 0.5),
+    vec3(0.5, 0.5, 0.5)
+);
+
+// Counter clockwise triangles seen from outside the cube.
+const int INDICES[36] = int[36](
+    0, 2, 3, 0, 3, 1,
+    4, 5, 7, 4, 7, 6,
+    0, 1, 5, 0, 5, 4,
+    2, 6, 7, 2, 7, 3,
+    0, 4, 6, 0, 6, 2,
+    1, 3, 7, 1, 7, 5
+);
+
+out VertexData {
+    vec4 uv_transform;
+} vertex;
+
+void main() {
+    vertex.uv_transform = uv_transform;
+    gl_Position = proj * view * model * vec4(CORNERS[INDICES[gl_VertexID]], 1.0);
+}
@@ -0,0 +1,12 @@
+// Fullscreen triangle, drawn without vertex buffers.
+
+#version 150 core
+
+out VertexData {
+    vec2 tex_coord;
+} vertex;
+
+void main() {
+    vertex.tex_coord = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    gl_Position = vec4(vertex.tex_coord * 2.0 - 1.0, 0.0, 1.0);
+}
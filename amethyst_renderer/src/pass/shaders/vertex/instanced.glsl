@@ -0,0 +1,35 @@
+// Same as basic.glsl, with the model matrix read from per instance attributes.
+
+#version 150 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 uv_transform;
+};
+
+in vec3 position;
+in vec3 normal;
+in vec3 tangent;
+in vec2 tex_coord;
+in vec4 model_0;
+in vec4 model_1;
+in vec4 model_2;
+in vec4 model_3;
+
+out VertexData {
+    vec4 position;
+    vec3 normal;
+    vec3 tangent;
+    vec2 tex_coord;
+} vertex;
+
+void main() {
+    mat4 instance_model = mat4(model_0, model_1, model_2, model_3);
+    vertex.position = instance_model * vec4(position, 1.0);
+    vertex.normal = mat3(instance_model) * normal;
+    vertex.tangent = mat3(instance_model) * tangent;
+    vertex.tex_coord = tex_coord * uv_transform.zw + uv_transform.xy;
+    gl_Position = proj * view * vertex.position;
+}
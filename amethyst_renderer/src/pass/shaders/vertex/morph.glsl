@@ -0,0 +1,53 @@
+// Same as basic.glsl, with the morph target offsets added to the vertices.
+
+#version 150 core
+
+layout (std140) uniform MorphWeights {
+    vec4 morph_weights;
+};
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 uv_transform;
+};
+
+in vec3 position;
+in vec3 normal;
+in vec3 tangent;
+in vec2 tex_coord;
+in vec3 morph_position_0;
+in vec3 morph_position_1;
+in vec3 morph_position_2;
+in vec3 morph_position_3;
+in vec3 morph_normal_0;
+in vec3 morph_normal_1;
+in vec3 morph_normal_2;
+in vec3 morph_normal_3;
+
+out VertexData {
+    vec4 position;
+    vec3 normal;
+    vec3 tangent;
+    vec2 tex_coord;
+} vertex;
+
+void main() {
+    vec3 morphed_position = position +
+        morph_weights.x * morph_position_0 +
+        morph_weights.y * morph_position_1 +
+        morph_weights.z * morph_position_2 +
+        morph_weights.w * morph_position_3;
+    vec3 morphed_normal = normal +
+        morph_weights.x * morph_normal_0 +
+        morph_weights.y * morph_normal_1 +
+        morph_weights.z * morph_normal_2 +
+        morph_weights.w * morph_normal_3;
+
+    vertex.position = model * vec4(morphed_position, 1.0);
+    vertex.normal = mat3(model) * morphed_normal;
+    vertex.tangent = mat3(model) * tangent;
+    vertex.tex_coord = tex_coord * uv_transform.zw + uv_transform.xy;
+    gl_Position = proj * view * vertex.position;
+}
@@ -0,0 +1,24 @@
+// Billboarded particles, expanded on the CPU.
+
+#version 150 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+};
+
+in vec3 position;
+in vec4 color;
+in vec2 tex_coord;
+
+out VertexData {
+    vec4 color;
+    vec2 tex_coord;
+} vertex;
+
+void main() {
+    vertex.color = color;
+    vertex.tex_coord = tex_coord;
+    gl_Position = proj * view * vec4(position, 1.0);
+}
@@ -10,6 +10,7 @@ layout (std140) uniform VertexArgs {
     uniform mat4 proj;
     uniform mat4 view;
     uniform mat4 model;
+    uniform vec4 uv_transform;
 };
 
 in vec3 position;
@@ -38,6 +39,6 @@ void main() {
     mat3 mat3_transform = mat3(model) * mat3(joint_transform);
     vertex.normal = mat3_transform * normal;
     vertex.tangent = mat3_transform * tangent;
-    vertex.tex_coord = tex_coord;
+    vertex.tex_coord = tex_coord * uv_transform.zw + uv_transform.xy;
     gl_Position = proj * view * vertex.position;
 }
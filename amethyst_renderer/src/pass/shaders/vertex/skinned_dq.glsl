@@ -0,0 +1,87 @@
+// Dual quaternion variant of skinned.glsl.
+//
+// The joint matrices are converted to dual quaternions which are blended
+// instead of the matrices, this preserves volume around twisting joints.
+// Scale in the joint matrices is ignored.
+
+#version 150 core
+
+layout (std140) uniform JointTransforms {
+    mat4 joints[100];
+};
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 uv_transform;
+};
+
+in vec3 position;
+in vec3 normal;
+in vec3 tangent;
+in vec2 tex_coord;
+in uvec4 joint_ids;
+in vec4 joint_weights;
+
+out VertexData {
+    vec4 position;
+    vec3 normal;
+    vec3 tangent;
+    vec2 tex_coord;
+} vertex;
+
+vec4 rotation(mat4 joint) {
+    mat3 m = mat3(normalize(joint[0].xyz), normalize(joint[1].xyz), normalize(joint[2].xyz));
+    float trace = m[0][0] + m[1][1] + m[2][2];
+    if (trace > 0.0) {
+        float s = 0.5 / sqrt(trace + 1.0);
+        return vec4((m[1][2] - m[2][1]) * s, (m[2][0] - m[0][2]) * s, (m[0][1] - m[1][0]) * s, 0.25 / s);
+    } else if (m[0][0] > m[1][1] && m[0][0] > m[2][2]) {
+        float s = 2.0 * sqrt(1.0 + m[0][0] - m[1][1] - m[2][2]);
+        return vec4(0.25 * s, (m[1][0] + m[0][1]) / s, (m[2][0] + m[0][2]) / s, (m[1][2] - m[2][1]) / s);
+    } else if (m[1][1] > m[2][2]) {
+        float s = 2.0 * sqrt(1.0 + m[1][1] - m[0][0] - m[2][2]);
+        return vec4((m[1][0] + m[0][1]) / s, 0.25 * s, (m[2][1] + m[1][2]) / s, (m[2][0] - m[0][2]) / s);
+    } else {
+        float s = 2.0 * sqrt(1.0 + m[2][2] - m[0][0] - m[1][1]);
+        return vec4((m[2][0] + m[0][2]) / s, (m[2][1] + m[1][2]) / s, 0.25 * s, (m[0][1] - m[1][0]) / s);
+    }
+}
+
+// Converts the joint matrix into a dual quaternion, stored as (real, dual).
+mat2x4 dual_quaternion(mat4 joint) {
+    vec4 real = rotation(joint);
+    vec3 t = joint[3].xyz;
+    vec4 dual = 0.5 * vec4(real.w * t + cross(t, real.xyz), -dot(t, real.xyz));
+    return mat2x4(real, dual);
+}
+
+vec3 rotate(vec4 real, vec3 v) {
+    return v + 2.0 * cross(real.xyz, cross(real.xyz, v) + real.w * v);
+}
+
+void main() {
+    mat2x4 dq0 = dual_quaternion(joints[int(joint_ids.x)]);
+    mat2x4 dq1 = dual_quaternion(joints[int(joint_ids.y)]);
+    mat2x4 dq2 = dual_quaternion(joints[int(joint_ids.z)]);
+    mat2x4 dq3 = dual_quaternion(joints[int(joint_ids.w)]);
+
+    // Blend along the shortest path.
+    float w1 = dot(dq0[0], dq1[0]) < 0.0 ? -joint_weights.y : joint_weights.y;
+    float w2 = dot(dq0[0], dq2[0]) < 0.0 ? -joint_weights.z : joint_weights.z;
+    float w3 = dot(dq0[0], dq3[0]) < 0.0 ? -joint_weights.w : joint_weights.w;
+    mat2x4 blended = joint_weights.x * dq0 + w1 * dq1 + w2 * dq2 + w3 * dq3;
+
+    float len = length(blended[0]);
+    vec4 real = blended[0] / len;
+    vec4 dual = blended[1] / len;
+    vec3 translation = 2.0 * (real.w * dual.xyz - dual.w * real.xyz + cross(real.xyz, dual.xyz));
+
+    vec3 skinned = rotate(real, position) + translation;
+    vertex.position = model * vec4(skinned, 1.0);
+    vertex.normal = mat3(model) * rotate(real, normal);
+    vertex.tangent = mat3(model) * rotate(real, tangent);
+    vertex.tex_coord = tex_coord * uv_transform.zw + uv_transform.xy;
+    gl_Position = proj * view * vertex.position;
+}
@@ -0,0 +1,66 @@
+// Same as skinned.glsl, with the morph target offsets added to the vertices
+// before skinning.
+
+#version 150 core
+
+layout (std140) uniform JointTransforms {
+    mat4 joints[100];
+};
+
+layout (std140) uniform MorphWeights {
+    vec4 morph_weights;
+};
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 uv_transform;
+};
+
+in vec3 position;
+in vec3 normal;
+in vec3 tangent;
+in vec2 tex_coord;
+in vec3 morph_position_0;
+in vec3 morph_position_1;
+in vec3 morph_position_2;
+in vec3 morph_position_3;
+in vec3 morph_normal_0;
+in vec3 morph_normal_1;
+in vec3 morph_normal_2;
+in vec3 morph_normal_3;
+in uvec4 joint_ids;
+in vec4 joint_weights;
+
+out VertexData {
+    vec4 position;
+    vec3 normal;
+    vec3 tangent;
+    vec2 tex_coord;
+} vertex;
+
+void main() {
+    vec3 morphed_position = position +
+        morph_weights.x * morph_position_0 +
+        morph_weights.y * morph_position_1 +
+        morph_weights.z * morph_position_2 +
+        morph_weights.w * morph_position_3;
+    vec3 morphed_normal = normal +
+        morph_weights.x * morph_normal_0 +
+        morph_weights.y * morph_normal_1 +
+        morph_weights.z * morph_normal_2 +
+        morph_weights.w * morph_normal_3;
+
+    mat4 joint_transform = joint_weights.x * joints[int(joint_ids.x)] +
+        joint_weights.y * joints[int(joint_ids.y)] +
+        joint_weights.z * joints[int(joint_ids.z)] +
+        joint_weights.w * joints[int(joint_ids.w)];
+
+    vertex.position = model * joint_transform * vec4(morphed_position, 1.0);
+    mat3 mat3_transform = mat3(model) * mat3(joint_transform);
+    vertex.normal = mat3_transform * morphed_normal;
+    vertex.tangent = mat3_transform * tangent;
+    vertex.tex_coord = tex_coord * uv_transform.zw + uv_transform.xy;
+    gl_Position = proj * view * vertex.position;
+}
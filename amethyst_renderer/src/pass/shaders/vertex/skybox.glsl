@@ -0,0 +1,17 @@
+// Fullscreen triangle on the far plane, drawn without vertex buffers.
+
+#version 150 core
+
+uniform mat4 inv_view_proj;
+
+out VertexData {
+    vec3 direction;
+} vertex;
+
+void main() {
+    vec2 tex_coord = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    vec4 position = vec4(tex_coord * 2.0 - 1.0, 1.0, 1.0);
+    vec4 world = inv_view_proj * position;
+    vertex.direction = world.xyz / world.w;
+    gl_Position = position;
+}
@@ -0,0 +1,38 @@
+// Unit square of a water surface, generated from the vertex index.
+
+#version 150 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 uv_transform;
+};
+
+// Counter clockwise triangles seen from above the surface.
+const vec2 CORNERS[6] = vec2[6](
+    vec2(-0.5, -0.5),
+    vec2(-0.5, 0.5),
+    vec2(0.5, 0.5),
+    vec2(-0.5, -0.5),
+    vec2(0.5, 0.5),
+    vec2(0.5, -0.5)
+);
+
+out VertexData {
+    vec4 position;
+    vec3 normal;
+    vec3 tangent;
+    vec3 bitangent;
+    vec2 tex_coord;
+} vertex;
+
+void main() {
+    vec2 corner = CORNERS[gl_VertexID];
+    vertex.position = model * vec4(corner.x, 0.0, corner.y, 1.0);
+    vertex.normal = normalize(mat3(model) * vec3(0.0, 1.0, 0.0));
+    vertex.tangent = normalize(mat3(model) * vec3(1.0, 0.0, 0.0));
+    vertex.bitangent = normalize(mat3(model) * vec3(0.0, 0.0, -1.0));
+    vertex.tex_coord = vec2(corner.x + 0.5, 0.5 - corner.y);
+    gl_Position = proj * view * vertex.position;
+}
@@ -0,0 +1,377 @@
+//! Shadow map depth pass and utilities for shadow receiving passes.
+
+use std::marker::PhantomData;
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::cgmath::{Matrix4, One};
+use amethyst_core::transform::GlobalTransform;
+use gfx::Rect;
+use gfx::pso::buffer::ElemStride;
+use specs::{Entities, Fetch, Join, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use error::Result;
+use hidden::Hidden;
+use light::Light;
+use mesh::{Mesh, MeshHandle};
+use pass::util::{get_camera, set_attribute_buffers, setup_vertex_args, VertexArgs,
+                 IDENTITY_UV_TRANSFORM};
+use pipe::{DepthMode, Effect, EffectBuilder, NewEffect, TargetInput};
+use pipe::pass::{Pass, PassData};
+use shadow::{point_light_faces, point_shadow_casters, point_shadow_far, PointShadowMaps,
+             ShadowCaster, ShadowConfig, MAX_POINT_SHADOWS, MAX_SHADOW_CASCADES};
+use tex::{Texture, TextureBuilder};
+use transparent::Transparent;
+use types::{Encoder, Factory};
+use vertex::{Position, Query};
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/basic.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/depth.glsl");
+static POINT_FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/point_shadow.glsl");
+
+static LIGHT_SPACES: [&str; MAX_SHADOW_CASCADES] =
+    ["light_space0", "light_space1", "light_space2", "light_space3"];
+
+static POINT_SHADOW_MAPS: [&str; MAX_POINT_SHADOWS] = [
+    "point_shadow_map0",
+    "point_shadow_map1",
+    "point_shadow_map2",
+    "point_shadow_map3",
+];
+
+/// Draws the depth of all opaque meshes, as seen from the shadow casting light.
+///
+/// Should be put in a stage which outputs to a target with a depth buffer,
+/// the lighting passes then read that buffer as shadow map. The cascades of
+/// a directional light are drawn side by side, as configured by the
+/// `ShadowConfig`.
+/// `V` is `VertexFormat`
+#[derive(Derivative, Clone, Debug, PartialEq)]
+#[derivative(Default(bound = "V: Query<(Position,)>"))]
+pub struct DrawShadow<V> {
+    _pd: PhantomData<V>,
+}
+
+impl<V> DrawShadow<V>
+where
+    V: Query<(Position,)>,
+{
+    /// Create instance of `DrawShadow` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a, V> PassData<'a> for DrawShadow<V>
+where
+    V: Query<(Position,)>,
+{
+    type Data = (
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        Fetch<'a, ShadowConfig>,
+        Fetch<'a, AssetStorage<Mesh>>,
+        ReadStorage<'a, MeshHandle>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Light>,
+        ReadStorage<'a, Transparent>,
+        ReadStorage<'a, Hidden>,
+    );
+}
+
+impl<V> Pass for DrawShadow<V>
+where
+    V: Query<(Position,)>,
+{
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder.with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0);
+        setup_vertex_args(&mut builder);
+        builder
+            .with_scissor()
+            .with_output("depth", Some(DepthMode::LessEqualWrite));
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (
+            active,
+            camera,
+            config,
+            mesh_storage,
+            mesh,
+            global,
+            light,
+            transparent,
+            hidden,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+        let cascades = match shadow_caster(&light, &config, camera) {
+            Some((_, cascades)) => cascades,
+            None => return,
+        };
+        let (width, height) = match effect.data.out_depth {
+            Some((ref depth, _)) => {
+                let (width, height, _, _) = depth.get_dimensions();
+                (width as usize, height)
+            }
+            None => return,
+        };
+
+        // Each cascade only draws into its part of the shadow map.
+        let scissor = effect.data.scissor;
+        for (i, light_space) in cascades.iter().enumerate() {
+            effect.data.scissor = Some(Rect {
+                x: (width * i / cascades.len()) as u16,
+                y: 0,
+                w: (width / cascades.len()) as u16,
+                h: height,
+            });
+            for (mesh, global, _, _) in (&mesh, &global, !&transparent, !&hidden).join() {
+                let mesh = match mesh_storage.get(mesh) {
+                    Some(mesh) => mesh,
+                    None => continue,
+                };
+                if !set_attribute_buffers(effect, mesh, &[V::QUERIED_ATTRIBUTES]) {
+                    effect.clear();
+                    continue;
+                }
+                let vertex_args = VertexArgs {
+                    proj: (*light_space).into(),
+                    view: Matrix4::one().into(),
+                    model: *global.as_ref(),
+                    uv_transform: IDENTITY_UV_TRANSFORM,
+                };
+                effect.update_constant_buffer("VertexArgs", &vertex_args, encoder);
+                effect.draw(mesh.slice(), encoder);
+                effect.clear();
+            }
+        }
+        effect.data.scissor = scissor;
+    }
+}
+
+/// Draws the depth cubemaps of the point lights of `ShadowCaster` entities,
+/// from all opaque meshes.
+///
+/// It draws into the `PointShadowMaps` only, so it can be put in any stage
+/// before the lighting passes, which then read the cubemaps. The stage target
+/// is left untouched.
+/// `V` is `VertexFormat`
+#[derive(Derivative, Clone, Debug, PartialEq)]
+#[derivative(Default(bound = "V: Query<(Position,)>"))]
+pub struct DrawPointShadow<V> {
+    _pd: PhantomData<V>,
+}
+
+impl<V> DrawPointShadow<V>
+where
+    V: Query<(Position,)>,
+{
+    /// Create instance of `DrawPointShadow` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a, V> PassData<'a> for DrawPointShadow<V>
+where
+    V: Query<(Position,)>,
+{
+    type Data = (
+        Entities<'a>,
+        Fetch<'a, PointShadowMaps>,
+        Fetch<'a, AssetStorage<Mesh>>,
+        ReadStorage<'a, MeshHandle>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Light>,
+        ReadStorage<'a, ShadowCaster>,
+        ReadStorage<'a, Transparent>,
+        ReadStorage<'a, Hidden>,
+    );
+}
+
+impl<V> Pass for DrawPointShadow<V>
+where
+    V: Query<(Position,)>,
+{
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = effect.simple(VERT_SRC, POINT_FRAG_SRC);
+        builder.with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0);
+        setup_vertex_args(&mut builder);
+        builder
+            .with_raw_global("light_position")
+            .with_raw_global("light_far")
+            .with_output("depth", Some(DepthMode::LessEqualWrite));
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (
+            entities,
+            maps,
+            mesh_storage,
+            mesh,
+            global,
+            light,
+            caster,
+            transparent,
+            hidden,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        // The faces of the cubemaps replace the stage target while drawing.
+        let out_depth = effect.data.out_depth.take();
+        let scissor = effect.data.scissor.take();
+
+        let casters = point_shadow_casters(&entities, &light, &caster);
+        for (&(_, light), map) in casters.iter().zip(&maps.maps) {
+            effect.update_global("light_position", light.center);
+            effect.update_global("light_far", point_shadow_far(light));
+            for (face, light_space) in map.faces.iter().zip(&point_light_faces(light)) {
+                encoder.clear_depth(face, 1.0);
+                effect.data.out_depth = Some((face.clone(), (0, 0)));
+                for (mesh, global, _, _) in (&mesh, &global, !&transparent, !&hidden).join() {
+                    let mesh = match mesh_storage.get(mesh) {
+                        Some(mesh) => mesh,
+                        None => continue,
+                    };
+                    if !set_attribute_buffers(effect, mesh, &[V::QUERIED_ATTRIBUTES]) {
+                        effect.clear();
+                        continue;
+                    }
+                    let vertex_args = VertexArgs {
+                        proj: (*light_space).into(),
+                        view: Matrix4::one().into(),
+                        model: *global.as_ref(),
+                        uv_transform: IDENTITY_UV_TRANSFORM,
+                    };
+                    effect.update_constant_buffer("VertexArgs", &vertex_args, encoder);
+                    effect.draw(mesh.slice(), encoder);
+                    effect.clear();
+                }
+            }
+        }
+
+        effect.data.out_depth = out_depth;
+        effect.data.scissor = scissor;
+    }
+}
+
+/// Finds the shadow casting light.
+///
+/// Returns `1` for the first directional light, `2` for the first point light,
+/// along with the light space matrices of its cascades.
+pub(crate) fn shadow_caster(
+    light: &ReadStorage<Light>,
+    config: &ShadowConfig,
+    camera: Option<(&Camera, &GlobalTransform)>,
+) -> Option<(i32, Vec<Matrix4<f32>>)> {
+    let cascades = |light: &Light| {
+        let cascades = config.cascades(light, camera);
+        if cascades.is_empty() {
+            None
+        } else {
+            Some(cascades)
+        }
+    };
+    light
+        .join()
+        .filter(|light| if let Light::Directional(_) = **light {
+            true
+        } else {
+            false
+        })
+        .next()
+        .and_then(&cascades)
+        .map(|cascades| (1, cascades))
+        .or_else(|| {
+            light
+                .join()
+                .filter(|light| if let Light::Point(_) = **light {
+                    true
+                } else {
+                    false
+                })
+                .next()
+                .and_then(&cascades)
+                .map(|cascades| (2, cascades))
+        })
+}
+
+pub(crate) fn setup_shadow_map(builder: &mut EffectBuilder, target: &str) {
+    builder
+        .with_input("shadow_map", TargetInput::Depth(target.to_string()));
+    for name in LIGHT_SPACES.iter() {
+        builder.with_raw_global(name);
+    }
+    builder
+        .with_raw_global("cascade_count")
+        .with_raw_global("shadow_light")
+        .with_raw_global("shadow_bias")
+        .with_raw_global("pcf_radius");
+}
+
+pub(crate) fn set_shadow_args(
+    effect: &mut Effect,
+    light: &ReadStorage<Light>,
+    config: &ShadowConfig,
+    camera: Option<(&Camera, &GlobalTransform)>,
+) {
+    let (kind, cascades) = shadow_caster(light, config, camera).unwrap_or((0, Vec::new()));
+    for (i, name) in LIGHT_SPACES.iter().enumerate() {
+        let light_space = cascades.get(i).cloned().unwrap_or_else(Matrix4::one);
+        effect.update_global(name, Into::<[[f32; 4]; 4]>::into(light_space));
+    }
+    effect.update_global("cascade_count", cascades.len() as i32);
+    effect.update_global("shadow_light", kind);
+    effect.update_global("shadow_bias", config.bias);
+    effect.update_global("pcf_radius", config.pcf_radius as i32);
+}
+
+/// Creates the cubemap bound in place of the missing point shadow maps.
+pub(crate) fn default_point_shadow_map(factory: &mut Factory) -> Result<Texture> {
+    TextureBuilder::new([255u8; 4 * 6])
+        .with_cube_size(1)
+        .build(factory)
+}
+
+pub(crate) fn setup_point_shadow_maps(builder: &mut EffectBuilder, default: &Texture) {
+    for name in POINT_SHADOW_MAPS.iter() {
+        builder.with_persistent_texture(name, default);
+    }
+    builder
+        .with_raw_global("point_shadow_lights")
+        .with_raw_global("point_shadow_far")
+        .with_raw_global("point_shadow_bias");
+}
+
+pub(crate) fn set_point_shadow_args(
+    effect: &mut Effect,
+    entities: &Entities,
+    light: &ReadStorage<Light>,
+    caster: &ReadStorage<ShadowCaster>,
+    maps: &PointShadowMaps,
+    config: &ShadowConfig,
+) {
+    // Index of the point light using each cubemap, -1 for the unused ones.
+    let mut lights = [-1i32; MAX_POINT_SHADOWS];
+    let mut far = [1.0f32; MAX_POINT_SHADOWS];
+    let casters = point_shadow_casters(entities, light, caster);
+    for (i, (&(index, light), map)) in casters.iter().zip(&maps.maps).enumerate() {
+        lights[i] = index as i32;
+        far[i] = point_shadow_far(light);
+        effect.update_texture(POINT_SHADOW_MAPS[i], &map.texture);
+    }
+    effect.update_global("point_shadow_lights", lights);
+    effect.update_global("point_shadow_far", far);
+    effect.update_global("point_shadow_bias", config.bias);
+}
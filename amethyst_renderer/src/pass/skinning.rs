@@ -11,6 +11,7 @@ use skinning::{JointIds, JointWeights};
 use vertex::{Attributes, Separate, VertexFormat};
 
 static VERT_SKIN_SRC: &[u8] = include_bytes!("shaders/vertex/skinned.glsl");
+static VERT_SKIN_DQ_SRC: &[u8] = include_bytes!("shaders/vertex/skinned_dq.glsl");
 static ATTRIBUTES: [Attributes<'static>; 2] = [
     Separate::<JointIds>::ATTRIBUTES,
     Separate::<JointWeights>::ATTRIBUTES,
@@ -23,6 +24,14 @@ pub(crate) fn create_skinning_effect<'a>(
     effect.simple(VERT_SKIN_SRC, frag)
 }
 
+/// Same as `create_skinning_effect`, but blends the joints as dual quaternions.
+pub(crate) fn create_dual_quaternion_skinning_effect<'a>(
+    effect: NewEffect<'a>,
+    frag: &'a [u8],
+) -> EffectBuilder<'a> {
+    effect.simple(VERT_SKIN_DQ_SRC, frag)
+}
+
 pub(crate) fn setup_skinning_buffers<'a>(builder: &mut EffectBuilder<'a>) {
     builder
         .with_raw_vertex_buffer(
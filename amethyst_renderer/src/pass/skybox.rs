@@ -0,0 +1,84 @@
+//! Skybox pass.
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::cgmath::{Matrix4, SquareMatrix, Vector4};
+use amethyst_core::transform::GlobalTransform;
+use specs::{Fetch, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use error::Result;
+use pass::post::draw_fullscreen;
+use pass::util::{add_texture, get_camera};
+use pipe::{DepthMode, Effect, NewEffect};
+use pipe::pass::{Pass, PassData};
+use resources::Skybox;
+use tex::Texture;
+use types::{Encoder, Factory};
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/skybox.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/skybox.glsl");
+
+/// Draws the cubemap of the `Skybox` resource behind everything else.
+///
+/// Should be put after the opaque passes of a stage which outputs to a target
+/// with a depth buffer, the sky is only drawn where nothing was drawn before.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawSkybox;
+
+impl DrawSkybox {
+    /// Create instance of `DrawSkybox` pass
+    pub fn new() -> Self {
+        DrawSkybox
+    }
+}
+
+impl<'a> PassData<'a> for DrawSkybox {
+    type Data = (
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        Fetch<'a, AssetStorage<Texture>>,
+        Option<Fetch<'a, Skybox>>,
+    );
+}
+
+impl Pass for DrawSkybox {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder
+            .with_raw_global("inv_view_proj")
+            .with_texture("skybox")
+            .with_output("color", Some(DepthMode::LessEqualTest));
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (active, camera, global, tex_storage, skybox): <Self as PassData<'a>>::Data,
+    ) {
+        let texture = match skybox.as_ref().and_then(|sky| tex_storage.get(&sky.0)) {
+            // Sampling another kind of texture as a cubemap isn't valid.
+            Some(texture) if texture.is_cube() => texture,
+            _ => return,
+        };
+        let (camera, transform) = match get_camera(effect.camera(), active, &camera, &global) {
+            Some(camera) => camera,
+            None => return,
+        };
+
+        // Only the rotation of the camera matters for the sky.
+        let mut view = transform.0.invert().unwrap_or_else(Matrix4::identity);
+        view.w = Vector4::new(0.0, 0.0, 0.0, 1.0);
+        let inv_view_proj = (effect.viewport_transform() * camera.proj * view)
+            .invert()
+            .unwrap_or_else(Matrix4::identity);
+
+        effect.update_global("inv_view_proj", Into::<[[f32; 4]; 4]>::into(inv_view_proj));
+        add_texture(effect, texture);
+        draw_fullscreen(effect, encoder);
+        effect.clear();
+    }
+}
@@ -0,0 +1,243 @@
+//! 2D sprite drawing pass.
+
+use std::cmp::Ordering;
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::cgmath::{Point3, Transform};
+use amethyst_core::transform::GlobalTransform;
+use gfx::IndexBuffer;
+use gfx::handle::Buffer;
+use gfx::pso::buffer::ElemStride;
+use gfx_core::state::{Blend, ColorMask};
+use specs::{Entities, Fetch, Join, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use error::Result;
+use hidden::Hidden;
+use pass::util::{add_texture, get_camera, get_camera_mask, set_vertex_args, setup_vertex_args,
+                 update_dynamic_buffer};
+use pipe::{DepthMode, Effect, NewEffect};
+use pipe::pass::{Pass, PassData};
+use render_mask::{in_render_mask, RenderMask};
+use sprite::SpriteRender;
+use tex::Texture;
+use types::{Encoder, Factory, Resources, Slice};
+use vertex::{PosTex, VertexFormat};
+use z_order::ZOrder;
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/sprite.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/sprite.glsl");
+
+/// Draws the `SpriteRender` components as textured quads.
+///
+/// All quads are built into one dynamic vertex buffer each frame, so no
+/// `Mesh` is needed per sprite. Sprites sharing a texture, including the sub
+/// textures of a `TextureAtlas`, are batched into a single draw call. With
+/// transparency enabled the sprites are drawn back to front instead, and only
+/// consecutive sprites with the same texture are batched. In both cases, the
+/// sprites are drawn in the order of their `ZOrder` first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawSprite {
+    transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    vertices: Vec<PosTex>,
+    buffer: Option<Buffer<Resources, PosTex>>,
+}
+
+impl DrawSprite {
+    /// Create instance of `DrawSprite` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Enable transparency
+    pub fn with_transparency(
+        mut self,
+        mask: ColorMask,
+        blend: Blend,
+        depth: Option<DepthMode>,
+    ) -> Self {
+        self.transparency = Some((mask, blend, depth));
+        self
+    }
+}
+
+impl<'a> PassData<'a> for DrawSprite {
+    type Data = (
+        Entities<'a>,
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        Fetch<'a, AssetStorage<Texture>>,
+        ReadStorage<'a, SpriteRender>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, ZOrder>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
+    );
+}
+
+impl Pass for DrawSprite {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder.with_raw_vertex_buffer(PosTex::ATTRIBUTES, PosTex::size() as ElemStride, 0);
+        setup_vertex_args(&mut builder);
+        builder.with_texture("albedo");
+        match self.transparency {
+            Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
+            None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
+        };
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            entities,
+            active,
+            camera,
+            tex_storage,
+            sprite_render,
+            global,
+            z_order,
+            hidden,
+            render_mask,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        use gfx::memory::Typed;
+
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+
+        let mut sprites = (&*entities, &sprite_render, &global, !&hidden)
+            .join()
+            .filter(|&(entity, _, _, _)| in_render_mask(mask, &render_mask, entity))
+            .filter_map(|(entity, sprite_render, global, _)| {
+                let order = z_order.get(entity).cloned().unwrap_or_default();
+                tex_storage
+                    .get(&sprite_render.texture)
+                    .map(|texture| (order, sprite_render, global, texture))
+            })
+            .collect::<Vec<_>>();
+        if self.transparency.is_some() {
+            sprites.sort_by(|&(a_order, _, a, _), &(b_order, _, b, _)| {
+                a_order
+                    .cmp(&b_order)
+                    .then_with(|| a.0.w.z.partial_cmp(&b.0.w.z).unwrap_or(Ordering::Equal))
+            });
+        } else {
+            // Sub textures of an atlas have their own handles, so sprites are
+            // grouped by the GPU texture they sample.
+            let mut views = Vec::new();
+            let mut keyed = sprites
+                .into_iter()
+                .map(|sprite| {
+                    let texture: &Texture = sprite.3;
+                    let view = texture.view();
+                    let key = match views.iter().position(|v| *v == view) {
+                        Some(key) => key,
+                        None => {
+                            views.push(view);
+                            views.len() - 1
+                        }
+                    };
+                    ((sprite.0, key), sprite)
+                })
+                .collect::<Vec<_>>();
+            keyed.sort_by_key(|&(key, _)| key);
+            sprites = keyed.into_iter().map(|(_, sprite)| sprite).collect();
+        }
+
+        self.vertices.clear();
+        let mut batches: Vec<(&Texture, usize, usize)> = Vec::new();
+        for (_, sprite_render, global, texture) in sprites {
+            push_sprite(&mut self.vertices, sprite_render, global, texture);
+            let end = self.vertices.len();
+            if let Some(batch) = batches.last_mut() {
+                if batch.0.view() == texture.view() {
+                    batch.2 = end;
+                    continue;
+                }
+            }
+            batches.push((texture, end - 6, end));
+        }
+        if self.vertices.is_empty() {
+            return;
+        }
+        if !update_dynamic_buffer(&mut factory, encoder, &mut self.buffer, &self.vertices) {
+            return;
+        }
+        let buffer = self.buffer.as_ref().unwrap();
+
+        set_vertex_args(effect, encoder, camera, &GlobalTransform::default());
+        for (texture, start, end) in batches {
+            effect.data.vertex_bufs.push(buffer.raw().clone());
+            add_texture(effect, texture);
+            let slice = Slice {
+                start: start as u32,
+                end: end as u32,
+                base_vertex: 0,
+                instances: None,
+                buffer: IndexBuffer::Auto,
+            };
+            effect.draw(&slice, encoder);
+            effect.clear();
+        }
+    }
+}
+
+/// Appends the two triangles of a sprite quad, in world space.
+///
+/// The texture coordinates are mapped to the region of the texture.
+fn push_sprite(
+    vertices: &mut Vec<PosTex>,
+    sprite_render: &SpriteRender,
+    global: &GlobalTransform,
+    texture: &Texture,
+) {
+    let sprite = &sprite_render.sprite;
+    let coords = &sprite.tex_coords;
+    let (left, right) = if sprite_render.flip_horizontal {
+        (coords.right, coords.left)
+    } else {
+        (coords.left, coords.right)
+    };
+    let (bottom, top) = if sprite_render.flip_vertical {
+        (coords.top, coords.bottom)
+    } else {
+        (coords.bottom, coords.top)
+    };
+    let uv = texture.uv_transform();
+    let (left, right) = (uv[0] + left * uv[2], uv[0] + right * uv[2]);
+    let (bottom, top) = (uv[1] + bottom * uv[3], uv[1] + top * uv[3]);
+
+    let x0 = -sprite.offsets[0];
+    let y0 = -sprite.offsets[1];
+    let x1 = x0 + sprite.width;
+    let y1 = y0 + sprite.height;
+    let corners = [
+        ([x0, y0], [left, bottom]),
+        ([x1, y0], [right, bottom]),
+        ([x1, y1], [right, top]),
+        ([x0, y0], [left, bottom]),
+        ([x1, y1], [right, top]),
+        ([x0, y1], [left, top]),
+    ];
+    for &(position, tex_coord) in &corners {
+        let position = global
+            .0
+            .transform_point(Point3::new(position[0], position[1], 0.0));
+        vertices.push(PosTex {
+            position: position.into(),
+            tex_coord,
+        });
+    }
+}
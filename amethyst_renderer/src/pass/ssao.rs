@@ -0,0 +1,87 @@
+//! Screen space ambient occlusion pass.
+
+use amethyst_core::cgmath::{Matrix4, SquareMatrix};
+use amethyst_core::transform::GlobalTransform;
+use specs::{Fetch, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use error::{Error, Result};
+use pass::post::{create_fullscreen_effect, draw_fullscreen};
+use pass::util::get_camera;
+use pipe::{Effect, EffectBuilder, NewEffect, TargetInput};
+use pipe::pass::{Pass, PassData};
+use resources::SsaoParams;
+use types::{Encoder, Factory};
+
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/ssao.glsl");
+
+/// Computes ambient occlusion from the depth buffer of the stage input.
+///
+/// Must be used in a `PostProcessing` stage whose input was filled by a
+/// depth pre-pass like `DrawDepth`. The occlusion is written to the stage
+/// target, which the lighting passes read through `with_ssao`.
+/// Configured by `SsaoParams`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawSsao;
+
+impl DrawSsao {
+    /// Create instance of `DrawSsao` pass
+    pub fn new() -> Self {
+        DrawSsao
+    }
+}
+
+impl<'a> PassData<'a> for DrawSsao {
+    type Data = (
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        Fetch<'a, SsaoParams>,
+    );
+}
+
+impl Pass for DrawSsao {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let input = effect.input().ok_or(Error::NoStageInput)?;
+        let mut builder = create_fullscreen_effect(effect, FRAG_SRC);
+        builder
+            .with_input("depth", TargetInput::Depth(input.to_string()))
+            .with_raw_global("proj")
+            .with_raw_global("inv_proj")
+            .with_raw_global("radius")
+            .with_raw_global("bias")
+            .with_raw_global("samples")
+            .with_output("color", None);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (active, camera, global, params): <Self as PassData<'a>>::Data,
+    ) {
+        let proj = get_camera(effect.camera(), active, &camera, &global)
+            .map(|(camera, _)| camera.proj)
+            .unwrap_or_else(Matrix4::identity);
+        let inv_proj = proj.invert().unwrap_or_else(Matrix4::identity);
+
+        effect.update_global("proj", Into::<[[f32; 4]; 4]>::into(proj));
+        effect.update_global("inv_proj", Into::<[[f32; 4]; 4]>::into(inv_proj));
+        effect.update_global("radius", params.radius);
+        effect.update_global("bias", params.bias);
+        effect.update_global("samples", params.samples as i32);
+        draw_fullscreen(effect, encoder);
+    }
+}
+
+pub(crate) fn setup_ssao(builder: &mut EffectBuilder, target: &str) {
+    builder
+        .with_input("ssao", TargetInput::Color(target.to_string(), 0))
+        .with_raw_global("ssao_enabled");
+}
+
+pub(crate) fn set_ssao_args(effect: &mut Effect) {
+    effect.update_global("ssao_enabled", 1);
+}
@@ -0,0 +1,220 @@
+//! Terrain drawing pass.
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::cgmath::{EuclideanSpace, InnerSpace, Transform};
+use amethyst_core::transform::GlobalTransform;
+use fnv::FnvHashMap as HashMap;
+use gfx::pso::buffer::ElemStride;
+use specs::{Entities, Fetch, Join, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use error::Result;
+use hidden::Hidden;
+use light::Light;
+use mesh::Mesh;
+use mtl::MaterialDefaults;
+use pass::clusters::LightClusters;
+use pass::shaded_util::{set_light_args, setup_light_buffers};
+use pass::util::{add_texture, get_camera, get_camera_mask, set_attribute_buffers,
+                 set_vertex_args, setup_vertex_args};
+use pipe::{DepthMode, Effect, NewEffect};
+use pipe::pass::{Pass, PassData};
+use render_mask::{in_render_mask, RenderMask};
+use resources::{AmbientColor, Fog};
+use terrain::{Terrain, TerrainHandle, TerrainMaterial};
+use tex::Texture;
+use types::{Encoder, Factory};
+use vertex::{PosNormTangTex, VertexFormat};
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/basic.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/terrain.glsl");
+
+const LAYERS: usize = 4;
+
+/// Draws the `Terrain`s with a `TerrainMaterial`, blending its layers.
+///
+/// Each chunk of a terrain is drawn at a level of detail depending on its
+/// distance to the camera: the full resolution up to `lod_distance`, then
+/// half of it up to twice that distance, and so on. The meshes of the chunks
+/// are built when first needed and kept for the lifetime of the pass.
+#[derive(Clone, Debug)]
+pub struct DrawTerrain {
+    lod_distance: f32,
+    chunks: HashMap<(u32, usize, u8), Mesh>,
+    clusters: Option<LightClusters>,
+}
+
+impl DrawTerrain {
+    /// Create instance of `DrawTerrain` pass
+    pub fn new() -> Self {
+        DrawTerrain {
+            lod_distance: 64.0,
+            chunks: HashMap::default(),
+            clusters: None,
+        }
+    }
+
+    /// Distance up to which the chunks are drawn at full resolution, in
+    /// world units
+    pub fn with_lod_distance(mut self, distance: f32) -> Self {
+        self.lod_distance = distance.max(0.001);
+        self
+    }
+
+    fn lod(&self, terrain: &Terrain, distance: f32) -> u8 {
+        let ratio = distance / self.lod_distance;
+        let lod = if ratio < 1.0 {
+            0
+        } else {
+            ratio.log2() as u8 + 1
+        };
+        lod.min(terrain.lod_count() - 1)
+    }
+}
+
+impl<'a> PassData<'a> for DrawTerrain {
+    type Data = (
+        Entities<'a>,
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        Fetch<'a, AmbientColor>,
+        Fetch<'a, Fog>,
+        Fetch<'a, AssetStorage<Terrain>>,
+        Fetch<'a, AssetStorage<Texture>>,
+        Fetch<'a, MaterialDefaults>,
+        ReadStorage<'a, TerrainHandle>,
+        ReadStorage<'a, TerrainMaterial>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Light>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
+    );
+}
+
+impl Pass for DrawTerrain {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let clusters = LightClusters::new(effect.factory)?;
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder.with_raw_vertex_buffer(
+            PosNormTangTex::ATTRIBUTES,
+            PosNormTangTex::size() as ElemStride,
+            0,
+        );
+        setup_vertex_args(&mut builder);
+        setup_light_buffers(&mut builder, &clusters);
+        builder
+            .with_raw_global("layer_count")
+            .with_raw_global("tiling")
+            .with_texture("splat_map")
+            .with_texture("layer0")
+            .with_texture("layer1")
+            .with_texture("layer2")
+            .with_texture("layer3")
+            .with_output("color", Some(DepthMode::LessEqualWrite));
+        self.clusters = Some(clusters);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            entities,
+            active,
+            camera,
+            ambient,
+            fog,
+            terrain_storage,
+            tex_storage,
+            material_defaults,
+            terrain,
+            material,
+            global,
+            light,
+            hidden,
+            render_mask,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+        let camera_position = camera.map(|(_, transform)| transform.0.w.truncate());
+        let default = match tex_storage.get(&material_defaults.0.albedo) {
+            Some(texture) => texture,
+            None => return,
+        };
+
+        if let Some(ref mut clusters) = self.clusters {
+            set_light_args(effect, encoder, clusters, &light, &ambient, &fog, camera);
+        }
+
+        for (entity, handle, material, global, _) in
+            (&*entities, &terrain, &material, &global, !&hidden).join()
+        {
+            if !in_render_mask(mask, &render_mask, entity) {
+                continue;
+            }
+            let terrain = match terrain_storage.get(handle) {
+                Some(terrain) => terrain,
+                None => continue,
+            };
+            let (chunks_x, chunks_z) = terrain.chunk_count();
+            for chunk_z in 0..chunks_z {
+                for chunk_x in 0..chunks_x {
+                    let center = global
+                        .0
+                        .transform_point(terrain.chunk_center(chunk_x, chunk_z))
+                        .to_vec();
+                    let distance = camera_position
+                        .map(|position| (center - position).magnitude())
+                        .unwrap_or(0.0);
+                    let lod = self.lod(terrain, distance);
+                    let key = (handle.id(), chunk_z * chunks_x + chunk_x, lod);
+                    if !self.chunks.contains_key(&key) {
+                        let vertices = terrain.chunk_vertices(chunk_x, chunk_z, lod);
+                        match Mesh::build(vertices).build(&mut factory) {
+                            Ok(mesh) => {
+                                self.chunks.insert(key, mesh);
+                            }
+                            Err(err) => {
+                                eprintln!("WARNING: Failed to build terrain chunk: {}", err);
+                                continue;
+                            }
+                        }
+                    }
+                    let mesh = &self.chunks[&key];
+
+                    if !set_attribute_buffers(effect, mesh, &[PosNormTangTex::ATTRIBUTES]) {
+                        effect.clear();
+                        continue;
+                    }
+                    set_vertex_args(effect, encoder, camera, global);
+                    effect.update_global("layer_count", material.layers.len() as i32);
+                    effect.update_global("tiling", material.tiling);
+                    add_texture(
+                        effect,
+                        tex_storage.get(&material.splat_map).unwrap_or(default),
+                    );
+                    for i in 0..LAYERS {
+                        let layer = material
+                            .layers
+                            .get(i)
+                            .and_then(|layer| tex_storage.get(layer))
+                            .unwrap_or(default);
+                        add_texture(effect, layer);
+                    }
+                    effect.draw(mesh.slice(), encoder);
+                    effect.clear();
+                }
+            }
+        }
+    }
+}
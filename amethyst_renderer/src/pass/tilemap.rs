@@ -0,0 +1,223 @@
+//! Chunked tile map drawing pass.
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::transform::GlobalTransform;
+use fnv::FnvHashMap as HashMap;
+use gfx::pso::buffer::ElemStride;
+use gfx_core::state::{Blend, ColorMask};
+use specs::{Entities, Entity, Fetch, Join, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use error::Result;
+use hidden::Hidden;
+use mesh::Mesh;
+use pass::util::{add_texture, get_camera, get_camera_mask, set_attribute_buffers,
+                 set_vertex_args, setup_vertex_args};
+use pipe::{DepthMode, Effect, NewEffect};
+use pipe::pass::{Pass, PassData};
+use render_mask::{in_render_mask, RenderMask};
+use sprite::{SpriteSheet, SpriteSheetHandle};
+use tex::Texture;
+use tilemap::{TileMap, CHUNK_SIZE};
+use types::{Encoder, Factory};
+use vertex::{PosTex, VertexFormat};
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/sprite.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/sprite.glsl");
+
+#[derive(Clone, Debug)]
+struct Chunk {
+    generation: u64,
+    mesh: Option<Mesh>,
+}
+
+#[derive(Clone, Debug)]
+struct Chunks {
+    sprite_sheet: SpriteSheetHandle,
+    chunks: Vec<Option<Chunk>>,
+}
+
+/// Draws `TileMap`s with one draw call per chunk of tiles.
+///
+/// The chunk meshes are kept between frames and only rebuilt when one of
+/// their tiles changed.
+#[derive(Clone, Debug, Default)]
+pub struct DrawTileMap {
+    transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    maps: HashMap<Entity, Chunks>,
+}
+
+impl DrawTileMap {
+    /// Create instance of `DrawTileMap` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Enable transparency
+    pub fn with_transparency(
+        mut self,
+        mask: ColorMask,
+        blend: Blend,
+        depth: Option<DepthMode>,
+    ) -> Self {
+        self.transparency = Some((mask, blend, depth));
+        self
+    }
+}
+
+impl<'a> PassData<'a> for DrawTileMap {
+    type Data = (
+        Entities<'a>,
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        Fetch<'a, AssetStorage<SpriteSheet>>,
+        Fetch<'a, AssetStorage<Texture>>,
+        ReadStorage<'a, TileMap>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
+    );
+}
+
+impl Pass for DrawTileMap {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder.with_raw_vertex_buffer(PosTex::ATTRIBUTES, PosTex::size() as ElemStride, 0);
+        setup_vertex_args(&mut builder);
+        builder.with_texture("albedo");
+        match self.transparency {
+            Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
+            None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
+        };
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            entities,
+            active,
+            camera,
+            sheets,
+            tex_storage,
+            tile_map,
+            global,
+            hidden,
+            render_mask,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+
+        self.maps.retain(|entity, _| tile_map.get(*entity).is_some());
+        for (entity, tile_map, global, _) in (&*entities, &tile_map, &global, !&hidden).join() {
+            if !in_render_mask(mask, &render_mask, entity) {
+                continue;
+            }
+            let sheet = match sheets.get(tile_map.sprite_sheet()) {
+                Some(sheet) => sheet,
+                None => continue,
+            };
+            let texture = match tex_storage.get(&sheet.texture) {
+                Some(texture) => texture,
+                None => continue,
+            };
+
+            let (columns, rows) = tile_map.chunks();
+            let chunks = self.maps.entry(entity).or_insert_with(|| Chunks {
+                sprite_sheet: tile_map.sprite_sheet().clone(),
+                chunks: Vec::new(),
+            });
+            if chunks.sprite_sheet != *tile_map.sprite_sheet()
+                || chunks.chunks.len() != (columns * rows) as usize
+            {
+                chunks.sprite_sheet = tile_map.sprite_sheet().clone();
+                chunks.chunks = vec![None; (columns * rows) as usize];
+            }
+
+            set_vertex_args(effect, encoder, camera, global);
+            for chunk_y in 0..rows {
+                for chunk_x in 0..columns {
+                    let generation = tile_map.generation(chunk_x, chunk_y);
+                    let chunk = &mut chunks.chunks[(chunk_y * columns + chunk_x) as usize];
+                    if chunk.as_ref().map(|c| c.generation) != Some(generation) {
+                        *chunk = Some(Chunk {
+                            generation,
+                            mesh: build_chunk(&mut factory, tile_map, sheet, chunk_x, chunk_y),
+                        });
+                    }
+                    let mesh = match chunk.as_ref().and_then(|c| c.mesh.as_ref()) {
+                        Some(mesh) => mesh,
+                        None => continue,
+                    };
+                    if !set_attribute_buffers(effect, mesh, &[PosTex::ATTRIBUTES]) {
+                        effect.clear();
+                        continue;
+                    }
+                    add_texture(effect, texture);
+                    effect.draw(mesh.slice(), encoder);
+                    effect.clear();
+                }
+            }
+        }
+    }
+}
+
+/// Builds the mesh of a chunk, or `None` if the chunk has no tiles.
+fn build_chunk(
+    factory: &mut Factory,
+    tile_map: &TileMap,
+    sheet: &SpriteSheet,
+    chunk_x: u32,
+    chunk_y: u32,
+) -> Option<Mesh> {
+    let size = tile_map.tile_size();
+    let mut vertices = Vec::new();
+    for y in chunk_y * CHUNK_SIZE..((chunk_y + 1) * CHUNK_SIZE).min(tile_map.height()) {
+        for x in chunk_x * CHUNK_SIZE..((chunk_x + 1) * CHUNK_SIZE).min(tile_map.width()) {
+            let sprite = match tile_map.get(x, y).and_then(|tile| sheet.sprites.get(tile)) {
+                Some(sprite) => sprite,
+                None => continue,
+            };
+            let coords = &sprite.tex_coords;
+            let x0 = x as f32 * size[0];
+            let y0 = y as f32 * size[1];
+            let x1 = x0 + size[0];
+            let y1 = y0 + size[1];
+            let corners = [
+                ([x0, y0], [coords.left, coords.bottom]),
+                ([x1, y0], [coords.right, coords.bottom]),
+                ([x1, y1], [coords.right, coords.top]),
+                ([x0, y0], [coords.left, coords.bottom]),
+                ([x1, y1], [coords.right, coords.top]),
+                ([x0, y1], [coords.left, coords.top]),
+            ];
+            for &(position, tex_coord) in &corners {
+                vertices.push(PosTex {
+                    position: [position[0], position[1], 0.0],
+                    tex_coord,
+                });
+            }
+        }
+    }
+    if vertices.is_empty() {
+        return None;
+    }
+    match Mesh::build(vertices).build(factory) {
+        Ok(mesh) => Some(mesh),
+        Err(err) => {
+            eprintln!("WARNING: Failed to build tile map chunk: {:?}", err);
+            None
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Tonemapping pass.
+
+use error::Result;
+use pass::post::{create_fullscreen_effect, draw_fullscreen};
+use pipe::{Effect, NewEffect};
+use pipe::pass::{Pass, PassData};
+use types::{Encoder, Factory};
+
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/tonemap.glsl");
+
+/// Operator used to map high dynamic range colors into the displayable range.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Tonemapping {
+    /// Simple Reinhard operator, `color / (color + 1)`.
+    Reinhard,
+    /// Filmic curve approximating the ACES reference rendering transform.
+    Aces,
+}
+
+impl Default for Tonemapping {
+    fn default() -> Self {
+        Tonemapping::Reinhard
+    }
+}
+
+/// Maps the high dynamic range stage input into the displayable range.
+///
+/// Must be used in a `PostProcessing` stage whose input is an HDR target.
+/// `PipelineBuilder::with_hdr` adds this pass automatically before presenting.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawTonemap {
+    operator: Tonemapping,
+    exposure: f32,
+}
+
+impl DrawTonemap {
+    /// Create instance of `DrawTonemap` pass
+    pub fn new(operator: Tonemapping) -> Self {
+        DrawTonemap {
+            operator,
+            exposure: 1.0,
+        }
+    }
+
+    /// Scales the input colors before they are mapped. Defaults to 1.0.
+    pub fn with_exposure(mut self, exposure: f32) -> Self {
+        self.exposure = exposure;
+        self
+    }
+}
+
+impl<'a> PassData<'a> for DrawTonemap {
+    type Data = ();
+}
+
+impl Pass for DrawTonemap {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = create_fullscreen_effect(effect, FRAG_SRC);
+        builder
+            .with_stage_input("source")?
+            .with_raw_global("operator")
+            .with_raw_global("exposure")
+            .with_output("color", None);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        _data: <Self as PassData<'a>>::Data,
+    ) {
+        let operator = match self.operator {
+            Tonemapping::Reinhard => 0,
+            Tonemapping::Aces => 1,
+        };
+        effect.update_global("operator", operator);
+        effect.update_global("exposure", self.exposure);
+        draw_fullscreen(effect, encoder);
+    }
+}
@@ -3,17 +3,21 @@ use std::mem;
 use amethyst_assets::AssetStorage;
 use amethyst_core::GlobalTransform;
 use amethyst_core::cgmath::{Matrix4, One, SquareMatrix};
-use specs::{Fetch, Join, ReadStorage};
+use gfx::handle::Buffer;
+use gfx::traits::Pod;
+use specs::{Entities, Entity, Fetch, Join, ReadStorage};
 
 use cam::{ActiveCamera, Camera};
 use mesh::Mesh;
-use mtl::{Material, MaterialDefaults};
+use mtl::{CustomUniforms, Material, MaterialDefaults};
 use pass::set_skinning_buffers;
 use pipe::{Effect, EffectBuilder};
+use render_mask::RenderMask;
 use skinning::JointTransforms;
 use tex::Texture;
-use types::Encoder;
-use vertex::Attributes;
+use types::{Encoder, Factory, Resources};
+use vertex::{Attributes, InstanceTransform};
+use visibility::Visibility;
 
 pub(crate) enum TextureType {
     Albedo,
@@ -28,11 +32,16 @@ pub(crate) enum TextureType {
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct VertexArgs {
-    proj: [[f32; 4]; 4],
-    view: [[f32; 4]; 4],
-    model: [[f32; 4]; 4],
+    pub(crate) proj: [[f32; 4]; 4],
+    pub(crate) view: [[f32; 4]; 4],
+    pub(crate) model: [[f32; 4]; 4],
+    /// Offset and scale of the texture coordinates, see `Texture::uv_transform`.
+    pub(crate) uv_transform: [f32; 4],
 }
 
+/// Texture coordinates transform of textures covering their whole GPU texture.
+pub(crate) const IDENTITY_UV_TRANSFORM: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
 pub(crate) fn set_attribute_buffers(
     effect: &mut Effect,
     mesh: &Mesh,
@@ -47,6 +56,37 @@ pub(crate) fn set_attribute_buffers(
     true
 }
 
+/// Uploads `data` into a dynamic vertex buffer, which is (re)created when it
+/// is too small. Returns `false` if the upload failed.
+pub(crate) fn update_dynamic_buffer<V: Pod>(
+    factory: &mut Factory,
+    encoder: &mut Encoder,
+    buffer: &mut Option<Buffer<Resources, V>>,
+    data: &[V],
+) -> bool {
+    use gfx::Factory;
+    use gfx::buffer::Role;
+    use gfx::memory::{Bind, Usage};
+
+    if buffer.as_ref().map_or(true, |buf| buf.len() < data.len()) {
+        let len = data.len().next_power_of_two();
+        match factory.create_buffer(len, Role::Vertex, Usage::Dynamic, Bind::empty()) {
+            Ok(buf) => *buffer = Some(buf),
+            Err(err) => {
+                eprintln!("WARNING: Failed to create vertex buffer: {:?}", err);
+                return false;
+            }
+        }
+    }
+    match encoder.update_buffer(buffer.as_ref().unwrap(), data, 0) {
+        Ok(()) => true,
+        Err(err) => {
+            eprintln!("WARNING: Failed to update vertex buffer: {:?}", err);
+            false
+        }
+    }
+}
+
 pub(crate) fn add_texture(effect: &mut Effect, texture: &Texture) {
     effect.data.textures.push(texture.view().clone());
     effect.data.samplers.push(texture.sampler().clone());
@@ -56,8 +96,12 @@ pub(crate) fn setup_textures(builder: &mut EffectBuilder, types: &[TextureType])
     use self::TextureType::*;
     for ty in types {
         match *ty {
-            Albedo => builder.with_texture("albedo"),
-            Emission => builder.with_texture("emission"),
+            Albedo => builder
+                .with_texture("albedo")
+                .with_raw_global("albedo_factor"),
+            Emission => builder
+                .with_texture("emission")
+                .with_raw_global("emission_factor"),
             Normal => builder.with_texture("normal"),
             Metallic => builder.with_texture("metallic"),
             Roughness => builder.with_texture("roughness"),
@@ -67,6 +111,30 @@ pub(crate) fn setup_textures(builder: &mut EffectBuilder, types: &[TextureType])
     }
 }
 
+/// Returns the texture coordinates transform of the albedo texture of the
+/// material, offset by the UV offset of the material, which is applied to all
+/// of its textures.
+pub(crate) fn albedo_uv_transform(
+    storage: &AssetStorage<Texture>,
+    material: &Material,
+    defaults: &MaterialDefaults,
+) -> [f32; 4] {
+    let transform = storage
+        .get(&material.albedo)
+        .or_else(|| storage.get(&defaults.0.albedo))
+        .map(Texture::uv_transform)
+        .unwrap_or(IDENTITY_UV_TRANSFORM);
+    let offset = material.uv_offset;
+    [
+        transform[0] + offset[0] * transform[2],
+        transform[1] + offset[1] * transform[3],
+        transform[2],
+        transform[3],
+    ]
+}
+
+/// Adds the textures of the material, along with the factors scaling the
+/// albedo and emission maps.
 pub(crate) fn add_textures(
     effect: &mut Effect,
     storage: &AssetStorage<Texture>,
@@ -77,12 +145,18 @@ pub(crate) fn add_textures(
     use self::TextureType::*;
     for ty in types {
         let texture = match *ty {
-            Albedo => storage
-                .get(&material.albedo)
-                .or_else(|| storage.get(&default.albedo)),
-            Emission => storage
-                .get(&material.emission)
-                .or_else(|| storage.get(&default.emission)),
+            Albedo => {
+                effect.update_global("albedo_factor", material.albedo_factor);
+                storage
+                    .get(&material.albedo)
+                    .or_else(|| storage.get(&default.albedo))
+            }
+            Emission => {
+                effect.update_global("emission_factor", material.emission_factor);
+                storage
+                    .get(&material.emission)
+                    .or_else(|| storage.get(&default.emission))
+            }
             Normal => storage
                 .get(&material.normal)
                 .or_else(|| storage.get(&default.normal)),
@@ -113,17 +187,31 @@ pub(crate) fn set_vertex_args(
     camera: Option<(&Camera, &GlobalTransform)>,
     global: &GlobalTransform,
 ) {
+    set_textured_vertex_args(effect, encoder, camera, global, IDENTITY_UV_TRANSFORM);
+}
+
+/// Sets the vertex arguments, mapping texture coordinates with `uv_transform`.
+pub(crate) fn set_textured_vertex_args(
+    effect: &mut Effect,
+    encoder: &mut Encoder,
+    camera: Option<(&Camera, &GlobalTransform)>,
+    global: &GlobalTransform,
+    uv_transform: [f32; 4],
+) {
+    let viewport = effect.viewport_transform();
     let vertex_args = camera
         .as_ref()
         .map(|&(ref cam, ref transform)| VertexArgs {
-            proj: cam.proj.into(),
+            proj: (viewport * cam.proj).into(),
             view: transform.0.invert().unwrap().into(),
             model: *global.as_ref(),
+            uv_transform,
         })
         .unwrap_or_else(|| VertexArgs {
-            proj: Matrix4::one().into(),
+            proj: viewport.into(),
             view: Matrix4::one().into(),
             model: *global.as_ref(),
+            uv_transform,
         });
     effect.update_constant_buffer("VertexArgs", &vertex_args, encoder);
 }
@@ -137,6 +225,7 @@ pub(crate) fn draw_mesh(
     tex_storage: &AssetStorage<Texture>,
     material: Option<&Material>,
     material_defaults: &MaterialDefaults,
+    uniforms: Option<&CustomUniforms>,
     camera: Option<(&Camera, &GlobalTransform)>,
     global: Option<&GlobalTransform>,
     attributes: &[Attributes<'static>],
@@ -146,7 +235,7 @@ pub(crate) fn draw_mesh(
         Some(mesh) => mesh,
         None => return,
     };
-    if material.is_none() || global.is_none() {
+    if material.is_none() || global.is_none() || mesh.primitive() != effect.primitive() {
         return;
     }
 
@@ -157,7 +246,8 @@ pub(crate) fn draw_mesh(
         return;
     }
 
-    set_vertex_args(effect, encoder, camera, global.unwrap());
+    let uv_transform = albedo_uv_transform(tex_storage, material.unwrap(), material_defaults);
+    set_textured_vertex_args(effect, encoder, camera, global.unwrap(), uv_transform);
 
     if skinning {
         if let Some(joint) = joint {
@@ -172,21 +262,136 @@ pub(crate) fn draw_mesh(
         &material_defaults.0,
         textures,
     );
+    if let Some(uniforms) = uniforms {
+        effect.update_custom_uniforms(uniforms);
+    }
 
     effect.draw(mesh.slice(), encoder);
     effect.clear();
 }
 
+/// Draws `instances.len()` copies of the mesh with a single draw call.
+///
+/// The instance transforms are uploaded to `buffer`, which must be bound after
+/// the vertex attribute buffers in the effect.
+pub(crate) fn draw_mesh_instanced(
+    encoder: &mut Encoder,
+    effect: &mut Effect,
+    factory: &mut Factory,
+    buffer: &mut Option<Buffer<Resources, InstanceTransform>>,
+    mesh: Option<&Mesh>,
+    instances: &[InstanceTransform],
+    tex_storage: &AssetStorage<Texture>,
+    material: &Material,
+    material_defaults: &MaterialDefaults,
+    uniforms: Option<&CustomUniforms>,
+    camera: Option<(&Camera, &GlobalTransform)>,
+    attributes: &[Attributes<'static>],
+    textures: &[TextureType],
+) {
+    use gfx::memory::Typed;
+
+    let mesh = match mesh {
+        Some(mesh) => mesh,
+        None => return,
+    };
+    if instances.is_empty() || mesh.primitive() != effect.primitive() {
+        return;
+    }
+
+    if !set_attribute_buffers(effect, mesh, attributes)
+        || !update_dynamic_buffer(factory, encoder, buffer, instances)
+    {
+        effect.clear();
+        return;
+    }
+    effect
+        .data
+        .vertex_bufs
+        .push(buffer.as_ref().unwrap().raw().clone());
+
+    let uv_transform = albedo_uv_transform(tex_storage, material, material_defaults);
+    set_textured_vertex_args(
+        effect,
+        encoder,
+        camera,
+        &GlobalTransform::default(),
+        uv_transform,
+    );
+    add_textures(
+        effect,
+        tex_storage,
+        material,
+        &material_defaults.0,
+        textures,
+    );
+    if let Some(uniforms) = uniforms {
+        effect.update_custom_uniforms(uniforms);
+    }
+
+    let mut slice = mesh.slice().clone();
+    slice.instances = Some((instances.len() as u32, 0));
+    effect.draw(&slice, encoder);
+    effect.clear();
+}
+
+/// Finds the camera to draw with, in order the one of the stage, the active
+/// camera and the first camera.
 pub(crate) fn get_camera<'a>(
+    stage: Option<Entity>,
     active: Option<Fetch<'a, ActiveCamera>>,
     camera: &'a ReadStorage<Camera>,
     global: &'a ReadStorage<GlobalTransform>,
 ) -> Option<(&'a Camera, &'a GlobalTransform)> {
-    active
-        .and_then(|a| {
-            let cam = camera.get(a.entity);
-            let transform = global.get(a.entity);
-            cam.into_iter().zip(transform.into_iter()).next()
-        })
+    let get = |entity: Entity| {
+        let cam = camera.get(entity);
+        let transform = global.get(entity);
+        cam.into_iter().zip(transform.into_iter()).next()
+    };
+    stage
+        .and_then(&get)
+        .or_else(|| active.and_then(|a| get(a.entity)))
         .or_else(|| (camera, global).join().next())
 }
+
+/// Returns the `RenderMask` of the camera `get_camera` draws with, or all the
+/// layers when it has none.
+pub(crate) fn get_camera_mask(
+    stage: Option<Entity>,
+    active: &Option<Fetch<ActiveCamera>>,
+    entities: &Entities,
+    camera: &ReadStorage<Camera>,
+    global: &ReadStorage<GlobalTransform>,
+    render_mask: &ReadStorage<RenderMask>,
+) -> RenderMask {
+    let get = |entity: Entity| {
+        camera
+            .get(entity)
+            .and(global.get(entity))
+            .map(|_| entity)
+    };
+    stage
+        .and_then(&get)
+        .or_else(|| active.as_ref().and_then(|a| get(a.entity)))
+        .or_else(|| (&**entities, camera, global).join().next().map(|(e, _, _)| e))
+        .and_then(|entity| render_mask.get(entity).cloned())
+        .unwrap_or(RenderMask::ALL)
+}
+
+/// Returns `false` for entities culled by the `FrustumCullingSystem`.
+///
+/// Culling is skipped in stages with their own camera, as `Visibility` is
+/// computed for the active camera.
+pub(crate) fn is_visible(
+    effect: &Effect,
+    visibility: &Option<Fetch<Visibility>>,
+    entity: Entity,
+) -> bool {
+    if effect.camera().is_some() {
+        return true;
+    }
+    visibility
+        .as_ref()
+        .map(|visibility| visibility.is_visible(entity))
+        .unwrap_or(true)
+}
@@ -0,0 +1,59 @@
+//! Vignette post-processing pass.
+
+use specs::Fetch;
+
+use error::Result;
+use pass::post::{create_fullscreen_effect, draw_fullscreen};
+use pipe::{Effect, NewEffect};
+use pipe::pass::{Pass, PassData};
+use resources::VignetteParams;
+use types::{Encoder, Factory};
+
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/vignette.glsl");
+
+/// Darkens the corners of the stage input, or tints them with a color, for
+/// example to show damage.
+///
+/// Must be used in a `PostProcessing` stage. Configured by `VignetteParams`,
+/// which can be changed every frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawVignette;
+
+impl DrawVignette {
+    /// Create instance of `DrawVignette` pass
+    pub fn new() -> Self {
+        DrawVignette
+    }
+}
+
+impl<'a> PassData<'a> for DrawVignette {
+    type Data = Fetch<'a, VignetteParams>;
+}
+
+impl Pass for DrawVignette {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = create_fullscreen_effect(effect, FRAG_SRC);
+        builder
+            .with_stage_input("source")?
+            .with_raw_global("intensity")
+            .with_raw_global("radius")
+            .with_raw_global("smoothness")
+            .with_raw_global("vignette_color")
+            .with_output("color", None);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        params: <Self as PassData<'a>>::Data,
+    ) {
+        effect.update_global("intensity", params.intensity);
+        effect.update_global("radius", params.radius);
+        effect.update_global("smoothness", params.smoothness.max(0.001));
+        effect.update_global("vignette_color", Into::<[f32; 3]>::into(params.color));
+        draw_fullscreen(effect, encoder);
+    }
+}
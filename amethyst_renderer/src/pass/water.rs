@@ -0,0 +1,176 @@
+//! Water surface pass.
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::timing::Time;
+use amethyst_core::transform::GlobalTransform;
+use gfx::IndexBuffer;
+use gfx::preset::blend;
+use gfx::state::ColorMask;
+use specs::{Entities, Fetch, Join, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use error::Result;
+use hidden::Hidden;
+use mtl::MaterialDefaults;
+use pass::util::{add_texture, get_camera, get_camera_mask, set_vertex_args, setup_vertex_args};
+use pipe::{DepthMode, Effect, NewEffect, TargetInput};
+use pipe::pass::{Pass, PassData};
+use render_mask::{in_render_mask, RenderMask};
+use tex::Texture;
+use types::{Encoder, Factory, Slice};
+use water::Water;
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/water.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/water.glsl");
+
+/// Draws the `Water` surfaces, blending a reflection and a refraction of the
+/// scene depending on the viewing angle.
+///
+/// The reflection is read from a target filled by a stage drawing the scene
+/// with a `WaterReflection` camera, the refraction from a target filled by a
+/// stage drawing the scene without the water. Both are looked up in screen
+/// space, offset by the waves, so the stage viewport has to cover its whole
+/// target, with the same size as these targets. Without them, the color of
+/// the water is used instead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawWater {
+    reflection: Option<String>,
+    refraction: Option<String>,
+}
+
+impl DrawWater {
+    /// Create instance of `DrawWater` pass
+    pub fn new() -> Self {
+        DrawWater {
+            reflection: None,
+            refraction: None,
+        }
+    }
+
+    /// Reflect the scene drawn into the given target
+    pub fn with_reflection<N: Into<String>>(mut self, target: N) -> Self {
+        self.reflection = Some(target.into());
+        self
+    }
+
+    /// Show the scene drawn into the given target through the water
+    pub fn with_refraction<N: Into<String>>(mut self, target: N) -> Self {
+        self.refraction = Some(target.into());
+        self
+    }
+}
+
+impl<'a> PassData<'a> for DrawWater {
+    type Data = (
+        Entities<'a>,
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        Fetch<'a, Time>,
+        Fetch<'a, AssetStorage<Texture>>,
+        Fetch<'a, MaterialDefaults>,
+        ReadStorage<'a, Water>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
+    );
+}
+
+impl Pass for DrawWater {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        setup_vertex_args(&mut builder);
+        if let Some(ref target) = self.reflection {
+            builder.with_input("reflection", TargetInput::Color(target.clone(), 0));
+        }
+        if let Some(ref target) = self.refraction {
+            builder.with_input("refraction", TargetInput::Color(target.clone(), 0));
+        }
+        builder
+            .with_raw_global("has_reflection")
+            .with_raw_global("has_refraction")
+            .with_raw_global("camera_position")
+            .with_raw_global("water_color")
+            .with_raw_global("tiling")
+            .with_raw_global("wave_offset")
+            .with_raw_global("distortion")
+            .with_raw_global("fresnel_power")
+            .with_texture("normal_map")
+            .with_blended_output(
+                "color",
+                ColorMask::all(),
+                blend::ALPHA,
+                Some(DepthMode::LessEqualWrite),
+            );
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (
+            entities,
+            active,
+            camera,
+            time,
+            tex_storage,
+            material_defaults,
+            water,
+            global,
+            hidden,
+            render_mask,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+        let camera_position = camera
+            .map(|(_, transform)| transform.0.w.truncate().into())
+            .unwrap_or([0.0; 3]);
+        let default = match tex_storage.get(&material_defaults.0.normal) {
+            Some(texture) => texture,
+            None => return,
+        };
+        let seconds = time.absolute_time_seconds();
+
+        let slice = Slice {
+            start: 0,
+            end: 6,
+            base_vertex: 0,
+            instances: None,
+            buffer: IndexBuffer::Auto,
+        };
+        for (entity, water, global, _) in (&*entities, &water, &global, !&hidden).join() {
+            if !in_render_mask(mask, &render_mask, entity) {
+                continue;
+            }
+            // Wrapped to keep the precision of the offset over long sessions.
+            let wave_offset = [
+                (water.wave_velocity[0] as f64 * seconds).fract() as f32,
+                (water.wave_velocity[1] as f64 * seconds).fract() as f32,
+            ];
+            set_vertex_args(effect, encoder, camera, global);
+            effect.update_global("has_reflection", self.reflection.is_some() as i32);
+            effect.update_global("has_refraction", self.refraction.is_some() as i32);
+            effect.update_global("camera_position", camera_position);
+            effect.update_global("water_color", water.color);
+            effect.update_global("tiling", water.tiling);
+            effect.update_global("wave_offset", wave_offset);
+            effect.update_global("distortion", water.distortion);
+            effect.update_global("fresnel_power", water.fresnel_power);
+            add_texture(
+                effect,
+                tex_storage.get(&water.normal_map).unwrap_or(default),
+            );
+            effect.draw(&slice, encoder);
+            effect.clear();
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Wireframe debug pass.
+
+use std::marker::PhantomData;
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::transform::GlobalTransform;
+use gfx::pso::buffer::ElemStride;
+use specs::{Entities, Fetch, Join, ReadStorage};
+
+use cam::{ActiveCamera, Camera};
+use color::Rgba;
+use error::Result;
+use hidden::Hidden;
+use mesh::{Mesh, MeshHandle};
+use pass::util::{get_camera, get_camera_mask, set_attribute_buffers, set_vertex_args,
+                 setup_vertex_args};
+use pipe::{DepthMode, Effect, NewEffect};
+use pipe::pass::{Pass, PassData};
+use render_mask::{in_render_mask, RenderMask};
+use types::{Encoder, Factory};
+use vertex::{Position, Query};
+
+static VERT_SRC: &[u8] = include_bytes!("shaders/vertex/basic.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("shaders/fragment/wireframe.glsl");
+
+/// Draws the edges of all meshes in a single color.
+///
+/// Can be used alone, or after the other passes of a stage to overlay the
+/// wireframe on the shaded scene.
+/// `V` is `VertexFormat`
+#[derive(Derivative, Clone, Debug, PartialEq)]
+#[derivative(Default(bound = "V: Query<(Position,)>"))]
+pub struct DrawWireframe<V> {
+    _pd: PhantomData<V>,
+    #[derivative(Default(value = "Rgba::white()"))]
+    color: Rgba,
+}
+
+impl<V> DrawWireframe<V>
+where
+    V: Query<(Position,)>,
+{
+    /// Create instance of `DrawWireframe` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the color of the lines, white by default
+    pub fn with_color<C: Into<Rgba>>(mut self, color: C) -> Self {
+        self.color = color.into();
+        self
+    }
+}
+
+impl<'a, V> PassData<'a> for DrawWireframe<V>
+where
+    V: Query<(Position,)>,
+{
+    type Data = (
+        Entities<'a>,
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Camera>,
+        Fetch<'a, AssetStorage<Mesh>>,
+        ReadStorage<'a, MeshHandle>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, RenderMask>,
+    );
+}
+
+impl<V> Pass for DrawWireframe<V>
+where
+    V: Query<(Position,)>,
+{
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder
+            .with_wireframe()
+            .with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0)
+            .with_raw_global("wireframe_color");
+        setup_vertex_args(&mut builder);
+        builder.with_output("color", Some(DepthMode::LessEqualTest));
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (
+            entities,
+            active,
+            camera,
+            mesh_storage,
+            mesh,
+            global,
+            hidden,
+            render_mask,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let mask = get_camera_mask(
+            effect.camera(),
+            &active,
+            &entities,
+            &camera,
+            &global,
+            &render_mask,
+        );
+        let camera = get_camera(effect.camera(), active, &camera, &global);
+
+        effect.update_global("wireframe_color", Into::<[f32; 4]>::into(self.color));
+        for (entity, mesh, global, _) in (&*entities, &mesh, &global, !&hidden).join() {
+            if !in_render_mask(mask, &render_mask, entity) {
+                continue;
+            }
+            let mesh = match mesh_storage.get(mesh) {
+                Some(mesh) => mesh,
+                None => continue,
+            };
+            if !set_attribute_buffers(effect, mesh, &[V::QUERIED_ATTRIBUTES]) {
+                effect.clear();
+                continue;
+            }
+            set_vertex_args(effect, encoder, camera, global);
+            effect.draw(mesh.slice(), encoder);
+            effect.clear();
+        }
+    }
+}
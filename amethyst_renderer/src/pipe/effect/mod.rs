@@ -4,22 +4,28 @@
 
 use std::mem;
 
+use amethyst_core::cgmath::{Matrix4, SquareMatrix};
 use fnv::FnvHashMap as HashMap;
 use gfx::{Primitive, ShaderSet};
 use gfx::buffer::{Info as BufferInfo, Role as BufferRole};
-use gfx::memory::{Bind, Usage};
+use gfx::memory::{Bind, Typed, Usage};
 use gfx::preset::depth::{LESS_EQUAL_TEST, LESS_EQUAL_WRITE};
 use gfx::pso::buffer::{ElemStride, InstanceRate};
 use gfx::shade::{ProgramError, ToUniform};
 use gfx::shade::core::UniformValue;
-use gfx::state::{Blend, ColorMask, Comparison, Depth, MultiSample, Rasterizer, Stencil};
+use gfx::state::{Blend, ColorMask, Comparison, CullFace, Depth, MultiSample, Offset,
+                 RasterMethod, Rasterizer, Stencil};
+use gfx::texture::{FilterMethod, SamplerInfo, WrapMode};
 use gfx::traits::Pod;
+use specs::Entity;
 
 pub use self::pso::{Data, Init, Meta};
 
 use error::{Error, Result};
-use pipe::Target;
-use types::{Encoder, Factory, PipelineState, Resources, Slice};
+use mtl::CustomUniforms;
+use pipe::{Target, Targets, Viewport};
+use tex::Texture;
+use types::{Encoder, Factory, PipelineState, RawShaderResourceView, Resources, Sampler, Slice};
 use vertex::Attributes;
 
 mod pso;
@@ -30,6 +36,38 @@ pub enum DepthMode {
     LessEqualWrite,
 }
 
+/// A buffer of a named render target which an `Effect` reads as a texture.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum TargetInput {
+    /// The color buffer with the given index.
+    Color(String, usize),
+    /// The depth buffer.
+    Depth(String),
+}
+
+impl TargetInput {
+    fn view(&self, targets: &Targets) -> Option<RawShaderResourceView> {
+        match *self {
+            TargetInput::Color(ref name, i) => targets
+                .get(name)
+                .and_then(|t| t.color_buf(i))
+                .and_then(|cb| cb.as_input.as_ref())
+                .map(|view| view.raw().clone()),
+            TargetInput::Depth(ref name) => targets
+                .get(name)
+                .and_then(|t| t.depth_buf())
+                .and_then(|db| db.as_input.as_ref())
+                .map(|view| view.raw().clone()),
+        }
+    }
+
+    fn target_name(&self) -> &str {
+        match *self {
+            TargetInput::Color(ref name, _) | TargetInput::Depth(ref name) => name,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub(crate) enum ProgramSource<'a> {
     Simple(&'a [u8], &'a [u8]),
@@ -69,9 +107,32 @@ pub struct Effect {
     pub data: Data,
     const_bufs: HashMap<String, usize>,
     globals: HashMap<String, usize>,
+    inputs: Vec<TargetInput>,
+    textures: HashMap<String, usize>,
+    prim: Primitive,
+    pub(crate) camera: Option<Entity>,
+    pub(crate) viewport: Option<Viewport>,
 }
 
 impl Effect {
+    /// Returns the primitive type the `Effect` draws.
+    pub(crate) fn primitive(&self) -> Primitive {
+        self.prim
+    }
+
+    /// Returns the camera entity set on the stage of this `Effect`.
+    pub(crate) fn camera(&self) -> Option<Entity> {
+        self.camera
+    }
+
+    /// Returns the matrix fitting projected coordinates into the viewport of
+    /// the stage, the identity if the stage covers its whole target.
+    pub(crate) fn viewport_transform(&self) -> Matrix4<f32> {
+        self.viewport
+            .map(|viewport| viewport.transform())
+            .unwrap_or_else(Matrix4::identity)
+    }
+
     pub fn update_global<N: AsRef<str>, T: ToUniform>(&mut self, name: N, data: T) {
         match self.globals.get(name.as_ref()) {
             Some(i) => self.data.globals[*i] = data.convert(),
@@ -84,6 +145,32 @@ impl Effect {
         }
     }
 
+    /// Updates the globals named in `uniforms`, skipping the ones this
+    /// `Effect` doesn't have.
+    pub fn update_custom_uniforms(&mut self, uniforms: &CustomUniforms) {
+        for &(ref name, value) in uniforms.iter() {
+            if let Some(i) = self.globals.get(name) {
+                self.data.globals[*i] = value;
+            }
+        }
+    }
+
+    /// Replaces a texture added with `EffectBuilder::with_persistent_texture`.
+    pub fn update_texture<N: AsRef<str>>(&mut self, name: N, texture: &Texture) {
+        match self.textures.get(name.as_ref()) {
+            Some(i) => {
+                self.data.textures[*i] = texture.view().clone();
+                self.data.samplers[*i] = texture.sampler().clone();
+            }
+            None => {
+                eprintln!(
+                    "WARNING: Texture update for effect failed! Texture not found: {:?}",
+                    name.as_ref()
+                );
+            }
+        }
+    }
+
     /// FIXME: Update raw buffer without transmute, use `Result` somehow.
     pub fn update_buffer<N, T>(&mut self, name: N, data: &[T], enc: &mut Encoder)
     where
@@ -125,12 +212,38 @@ impl Effect {
         }
     }
 
+    /// Clears the per-draw textures and vertex buffers.
+    ///
+    /// Render target inputs and persistent textures stay bound, they always
+    /// come first.
     pub fn clear(&mut self) {
-        self.data.textures.clear();
-        self.data.samplers.clear();
+        let kept = self.inputs.len() + self.textures.len();
+        self.data.textures.truncate(kept);
+        self.data.samplers.truncate(kept);
         self.data.vertex_bufs.clear();
     }
 
+    /// Rebinds the render target inputs after the targets were recreated.
+    pub(crate) fn new_inputs(&mut self, targets: &Targets) {
+        for (i, input) in self.inputs.iter().enumerate() {
+            match input.view(targets) {
+                Some(view) => self.data.textures[i] = view,
+                None => eprintln!(
+                    "WARNING: Input target {:?} of effect not found!",
+                    input.target_name()
+                ),
+            }
+        }
+    }
+
+    /// Sets the reference value of the stencil test, for the front and back
+    /// faces.
+    pub fn update_stencil_reference(&mut self, value: u8) {
+        if let Some(ref mut depth) = self.data.out_depth {
+            depth.1 = (value, value);
+        }
+    }
+
     pub fn draw(&mut self, slice: &Slice, enc: &mut Encoder) {
         enc.draw(&slice, &self.pso, &self.data);
     }
@@ -139,63 +252,106 @@ impl Effect {
 pub struct NewEffect<'f> {
     pub factory: &'f mut Factory,
     out: &'f Target,
+    targets: &'f Targets,
+    input: Option<&'f str>,
+    viewport: Option<Viewport>,
     multisampling: u16,
 }
 
 impl<'f> NewEffect<'f> {
-    pub(crate) fn new(fac: &'f mut Factory, out: &'f Target, multisampling: u16) -> Self {
+    pub(crate) fn new(
+        fac: &'f mut Factory,
+        out: &'f Target,
+        targets: &'f Targets,
+        input: Option<&'f str>,
+        viewport: Option<Viewport>,
+        multisampling: u16,
+    ) -> Self {
         NewEffect {
             factory: fac,
             out,
+            targets,
+            input,
+            viewport,
             multisampling,
         }
     }
 
+    /// Returns a `NewEffect` for another `Effect` drawing into the same
+    /// stage, for passes drawing with several pipeline states.
+    ///
+    /// Only the `Effect` returned by `Pass::compile` is updated when the
+    /// targets are resized, the pass has to copy its outputs to the others.
+    pub fn reborrow(&mut self) -> NewEffect {
+        NewEffect {
+            factory: &mut *self.factory,
+            out: self.out,
+            targets: self.targets,
+            input: self.input,
+            viewport: self.viewport,
+            multisampling: self.multisampling,
+        }
+    }
+
+    /// Returns the name of the input target of the stage, if it has one.
+    pub fn input(&self) -> Option<&'f str> {
+        self.input
+    }
+
     pub fn simple<S: Into<&'f [u8]>>(self, vs: S, ps: S) -> EffectBuilder<'f> {
         let src = ProgramSource::Simple(vs.into(), ps.into());
-        EffectBuilder::new(self.factory, self.out, self.multisampling, src)
+        EffectBuilder::new(self, src)
     }
 
     pub fn geom<S: Into<&'f [u8]>>(self, vs: S, gs: S, ps: S) -> EffectBuilder<'f> {
         let src = ProgramSource::Geometry(vs.into(), gs.into(), ps.into());
-        EffectBuilder::new(self.factory, self.out, self.multisampling, src)
+        EffectBuilder::new(self, src)
     }
 
     pub fn tess<S: Into<&'f [u8]>>(self, vs: S, hs: S, ds: S, ps: S) -> EffectBuilder<'f> {
         let src = ProgramSource::Tessellated(vs.into(), hs.into(), ds.into(), ps.into());
-        EffectBuilder::new(self.factory, self.out, self.multisampling, src)
+        EffectBuilder::new(self, src)
     }
 }
 
 pub struct EffectBuilder<'a> {
     factory: &'a mut Factory,
     out: &'a Target,
+    targets: &'a Targets,
+    input: Option<&'a str>,
+    viewport: Option<Viewport>,
     init: Init<'a>,
     prim: Primitive,
     prog: ProgramSource<'a>,
     rast: Rasterizer,
+    stencil: Option<Stencil>,
+    scissor: bool,
     const_bufs: Vec<BufferInfo>,
+    inputs: Vec<(&'a str, TargetInput)>,
+    textures: Vec<(&'a str, RawShaderResourceView, Sampler)>,
 }
 
 impl<'a> EffectBuilder<'a> {
-    pub(crate) fn new(
-        fac: &'a mut Factory,
-        out: &'a Target,
-        multisampling: u16,
-        src: ProgramSource<'a>,
-    ) -> Self {
+    pub(crate) fn new(effect: NewEffect<'a>, src: ProgramSource<'a>) -> Self {
         let mut rast = Rasterizer::new_fill().with_cull_back();
-        if multisampling > 0 {
+        if effect.multisampling > 0 {
             rast.samples = Some(MultiSample);
         }
         EffectBuilder {
-            factory: fac,
-            out: out,
+            factory: effect.factory,
+            out: effect.out,
+            targets: effect.targets,
+            input: effect.input,
+            viewport: effect.viewport,
             init: Init::default(),
             prim: Primitive::TriangleList,
             rast,
+            stencil: None,
+            scissor: false,
             prog: src,
             const_bufs: Vec::new(),
+            inputs: Vec::new(),
+            textures: Vec::new(),
         }
     }
 
@@ -231,6 +387,46 @@ impl<'a> EffectBuilder<'a> {
         self
     }
 
+    /// Rasterize the edges of the primitives only, without face culling.
+    pub fn with_wireframe(&mut self) -> &mut Self {
+        self.rast.method = RasterMethod::Line(1);
+        self.rast.cull_face = CullFace::Nothing;
+        self
+    }
+
+    /// Sets which faces of the primitives are culled, back faces by default.
+    pub fn with_cull_face(&mut self, cull: CullFace) -> &mut Self {
+        self.rast.cull_face = cull;
+        self
+    }
+
+    /// Offsets the depth of the primitives before the depth test, like
+    /// `glPolygonOffset`. Negative values bring them closer to the camera.
+    pub fn with_depth_offset(&mut self, slope: i32, units: i32) -> &mut Self {
+        self.rast.offset = Some(Offset(slope, units));
+        self
+    }
+
+    /// Sets the stencil test and operations of the PSO, which needs a target
+    /// with a depth-stencil buffer.
+    ///
+    /// Unless a depth mode is given with the output, the depth buffer is
+    /// neither tested nor written.
+    pub fn with_stencil(&mut self, stencil: Stencil) -> &mut Self {
+        self.stencil = Some(stencil);
+        self
+    }
+
+    /// Enables the scissor test of the PSO, limiting the drawing to the
+    /// rectangle set in `Data::scissor`.
+    ///
+    /// It is always enabled in stages with a viewport, which set the
+    /// rectangle to the viewport.
+    pub fn with_scissor(&mut self) -> &mut Self {
+        self.scissor = true;
+        self
+    }
+
     /// Sets the output target of the PSO.
     ///
     /// If the target contains a depth buffer, its mode will be set by `depth`.
@@ -298,6 +494,34 @@ impl<'a> EffectBuilder<'a> {
         self
     }
 
+    /// Adds a texture sampler to this `Effect` which stays bound across draw
+    /// calls, starting out with the given texture.
+    ///
+    /// Use `Effect::update_texture` to bind another texture.
+    pub fn with_persistent_texture(&mut self, name: &'a str, texture: &Texture) -> &mut Self {
+        self.textures
+            .push((name, texture.view().clone(), texture.sampler().clone()));
+        self
+    }
+
+    /// Adds a buffer of another render target as a texture input to this `Effect`.
+    ///
+    /// Unlike regular textures, inputs stay bound across draw calls and are
+    /// updated automatically when the targets are resized.
+    pub fn with_input(&mut self, name: &'a str, input: TargetInput) -> &mut Self {
+        self.inputs.push((name, input));
+        self
+    }
+
+    /// Adds the first color buffer of the stage's input target as a texture
+    /// input to this `Effect`.
+    ///
+    /// Building the `Effect` fails if the stage has no input.
+    pub fn with_stage_input(&mut self, name: &'a str) -> Result<&mut Self> {
+        let input = self.input.ok_or(Error::NoStageInput)?;
+        Ok(self.with_input(name, TargetInput::Color(input.to_string(), 0)))
+    }
+
     /// Adds a vertex buffer to this `Effect`.
     pub fn with_raw_vertex_buffer(
         &mut self,
@@ -309,7 +533,6 @@ impl<'a> EffectBuilder<'a> {
         self
     }
 
-    /// TODO: Support render targets as inputs.
     pub fn build(&mut self) -> Result<Effect> {
         use gfx::Factory;
         use gfx::traits::FactoryExt;
@@ -319,10 +542,57 @@ impl<'a> EffectBuilder<'a> {
         let ref mut fac = self.factory;
         let prog = self.prog.compile(fac)?;
         debug!("Creating pipeline state");
-        let pso = fac.create_pipeline_state(&prog, self.prim, self.rast, self.init.clone())?;
+        // Target inputs and persistent textures are bound first so that
+        // `Effect::clear` can keep them.
+        let mut init = self.init.clone();
+        let names = self.inputs
+            .iter()
+            .map(|&(name, _)| name)
+            .chain(self.textures.iter().map(|&(name, _, _)| name));
+        for (i, name) in names.enumerate() {
+            init.samplers.insert(i, name);
+            init.textures.insert(i, name);
+        }
+        init.scissor = self.scissor || self.viewport.is_some();
+        if let Some(stencil) = self.stencil {
+            let depth = init.out_depth.map(|(depth, _)| depth).unwrap_or(Depth {
+                fun: Comparison::Always,
+                write: false,
+            });
+            init.out_depth = Some((depth, stencil));
+        }
+        let pso = fac.create_pipeline_state(&prog, self.prim, self.rast, init)?;
 
         let mut data = Data::default();
 
+        debug!("Binding target inputs");
+        let targets = self.targets;
+        let inputs = self.inputs
+            .drain(..)
+            .map(|(_, input)| {
+                let view = input
+                    .view(targets)
+                    .ok_or_else(|| Error::NoSuchTarget(input.target_name().to_string()))?;
+                data.textures.push(view);
+                data.samplers.push(fac.create_sampler(SamplerInfo::new(
+                    FilterMethod::Bilinear,
+                    WrapMode::Clamp,
+                )));
+                Ok(input)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        debug!("Binding persistent textures");
+        let textures = self.textures
+            .drain(..)
+            .enumerate()
+            .map(|(i, (name, view, sampler))| {
+                data.textures.push(view);
+                data.samplers.push(sampler);
+                (name.to_string(), inputs.len() + i)
+            })
+            .collect::<HashMap<_, _>>();
+
         debug!("Creating raw constant buffers");
         let const_bufs = self.init
             .const_bufs
@@ -366,6 +636,8 @@ impl<'a> EffectBuilder<'a> {
         data.out_depth = self.out
             .depth_buf()
             .map(|db| (db.as_output.clone(), (0, 0)));
+        data.scissor = self.viewport
+            .map(|viewport| viewport.rect(self.out.size()));
 
         debug!("Finished building effect");
         Ok(Effect {
@@ -373,6 +645,11 @@ impl<'a> EffectBuilder<'a> {
             data,
             const_bufs,
             globals,
+            inputs,
+            textures,
+            prim: self.prim,
+            camera: None,
+            viewport: self.viewport,
         })
     }
 }
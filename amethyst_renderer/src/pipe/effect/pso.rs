@@ -1,4 +1,4 @@
-use gfx::{handle, pso};
+use gfx::{handle, pso, Rect};
 use gfx::pso::{DataBind, DataLink, Descriptor, InitError, PipelineData, PipelineInit};
 use gfx::pso::buffer::{RawConstantBuffer, RawGlobal, RawVertexBuffer};
 use gfx::pso::resource::{RawShaderResource, Sampler};
@@ -12,6 +12,7 @@ type DepthStencilTarget = target::DepthStencilTarget<DepthFormat>;
 type Manager = handle::Manager<Resources>;
 type RenderTarget = target::RenderTarget<ColorFormat>;
 type BlendTarget = target::BlendTarget<ColorFormat>;
+type Scissor = target::Scissor;
 type RawDataSet = pso::RawDataSet<Resources>;
 type InitResult<'r, M> = Result<M, InitError<&'r str>>;
 
@@ -23,6 +24,7 @@ pub struct Meta {
     out_blends: Vec<BlendTarget>,
     out_depth: Option<DepthStencilTarget>,
     samplers: Vec<Sampler>,
+    scissor: Option<Scissor>,
     textures: Vec<RawShaderResource>,
     vertex_bufs: Vec<RawVertexBuffer>,
 }
@@ -36,6 +38,7 @@ pub struct Init<'d> {
     pub out_blends: Vec<<BlendTarget as DataLink<'d>>::Init>,
     pub out_depth: Option<<DepthStencilTarget as DataLink<'d>>::Init>,
     pub samplers: Vec<<Sampler as DataLink<'d>>::Init>,
+    pub scissor: bool,
     pub textures: Vec<<RawShaderResource as DataLink<'d>>::Init>,
     pub vertex_bufs: Vec<<RawVertexBuffer as DataLink<'d>>::Init>,
 }
@@ -132,6 +135,12 @@ impl<'d> PipelineInit for Init<'d> {
             meta.out_depth = Some(meta_depth);
         }
 
+        if self.scissor {
+            let mut meta_scissor = <Scissor as DataLink<'d>>::new();
+            desc.scissor = meta_scissor.link_scissor();
+            meta.scissor = Some(meta_scissor);
+        }
+
         for smp in &self.samplers {
             let mut meta_smp = <Sampler as DataLink<'d>>::new();
             for info in &info.samplers {
@@ -182,6 +191,7 @@ pub struct Data {
     pub out_blends: Vec<<BlendTarget as DataBind<Resources>>::Data>,
     pub out_depth: Option<<DepthStencilTarget as DataBind<Resources>>::Data>,
     pub samplers: Vec<<Sampler as DataBind<Resources>>::Data>,
+    pub scissor: Option<Rect>,
     pub textures: Vec<<RawShaderResource as DataBind<Resources>>::Data>,
     pub vertex_bufs: Vec<<RawVertexBuffer as DataBind<Resources>>::Data>,
 }
@@ -220,6 +230,11 @@ impl PipelineData<Resources> for Data {
             meta_samp.bind_to(out, &samp, mgr, acc);
         }
 
+        let scissor = (meta.scissor.as_ref(), self.scissor.as_ref());
+        if let (Some(meta_scissor), Some(scissor)) = scissor {
+            meta_scissor.bind_to(out, scissor, mgr, acc);
+        }
+
         let textures = meta.textures.iter().zip(&self.textures);
         for (meta_tex, tex) in textures {
             meta_tex.bind_to(out, &tex, mgr, acc);
@@ -16,12 +16,17 @@
 //!     .expect("Could not build pipeline");
 //! ```
 
-pub use self::effect::{Data, DepthMode, Effect, EffectBuilder, Init, Meta, NewEffect};
+pub use self::effect::{Data, DepthMode, Effect, EffectBuilder, Init, Meta, NewEffect,
+                       TargetInput};
 pub use self::pipe::{Pipeline, PipelineBuild, PipelineBuilder, PipelineData, PolyPipeline,
                      PolyStages};
-pub use self::stage::{PolyStage, Stage, StageBuilder};
+pub use self::stage::{PolyStage, PostProcessing, Stage, StageBuilder, Viewport};
 pub use self::target::{ColorBuffer, DepthBuffer, Target, TargetBuilder, Targets};
 
+pub use gfx::state::{Comparison, Stencil, StencilOp};
+
+pub(crate) use self::pipe::HDR_TARGET;
+
 pub mod pass;
 
 mod effect;
@@ -1,9 +1,10 @@
 //! Types for constructing render passes.
 
-use specs::SystemData;
+use specs::{Entity, SystemData};
 
 use error::Result;
-use pipe::{Effect, NewEffect, Target};
+use pipe::{Effect, NewEffect, Target, Targets, Viewport};
+use stats::GpuTimer;
 use types::{Encoder, Factory};
 
 /// Used to fetch data from the game world for rendering in the pass.
@@ -26,6 +27,15 @@ pub trait Pass: for<'a> PassData<'a> {
         factory: Factory,
         data: <Self as PassData<'b>>::Data,
     );
+
+    /// Called before `apply`, returning `true` compiles the pass again.
+    ///
+    /// Passes using shader assets check here whether their shaders were
+    /// reloaded and keep the new sources for `compile`. If compiling fails,
+    /// the previous `Effect` is kept.
+    fn needs_recompile<'a>(&mut self, _data: &<Self as PassData<'a>>::Data) -> bool {
+        false
+    }
 }
 
 /// A compiled pass.  These are created and managed by the `Renderer`.  This should not be
@@ -34,6 +44,11 @@ pub trait Pass: for<'a> PassData<'a> {
 pub struct CompiledPass<P> {
     effect: Effect,
     inner: P,
+    out: Target,
+    targets: Targets,
+    input: Option<String>,
+    viewport: Option<Viewport>,
+    multisampling: u16,
 }
 
 impl<P> CompiledPass<P>
@@ -44,14 +59,44 @@ where
         mut pass: P,
         fac: &mut Factory,
         out: &Target,
+        targets: &Targets,
+        input: Option<&str>,
+        camera: Option<Entity>,
+        viewport: Option<Viewport>,
         multisampling: u16,
     ) -> Result<Self> {
-        let effect = pass.compile(NewEffect::new(fac, out, multisampling))?;
+        let new_effect = NewEffect::new(fac, out, targets, input, viewport, multisampling);
+        let mut effect = pass.compile(new_effect)?;
+        effect.camera = camera;
         Ok(CompiledPass {
             effect,
             inner: pass,
+            out: out.clone(),
+            targets: targets.clone(),
+            input: input.map(str::to_string),
+            viewport,
+            multisampling,
         })
     }
+
+    /// Compiles the inner pass again, keeping the current effect on failure.
+    fn recompile(&mut self, mut factory: Factory) {
+        let new_effect = NewEffect::new(
+            &mut factory,
+            &self.out,
+            &self.targets,
+            self.input.as_ref().map(String::as_str),
+            self.viewport,
+            self.multisampling,
+        );
+        match self.inner.compile(new_effect) {
+            Ok(mut effect) => {
+                effect.camera = self.effect.camera;
+                self.effect = effect;
+            }
+            Err(err) => error!("Failed recompiling pass: {}", err),
+        }
+    }
 }
 
 impl<P> CompiledPass<P> {
@@ -60,15 +105,24 @@ impl<P> CompiledPass<P> {
         &'a mut self,
         encoder: &mut Encoder,
         factory: Factory,
+        timer: &mut GpuTimer,
         data: <P as PassData<'b>>::Data,
     ) where
         P: Pass,
     {
-        self.inner.apply(encoder, &mut self.effect, factory, data)
+        if self.inner.needs_recompile(&data) {
+            self.recompile(factory.clone());
+        }
+        timer.begin(encoder);
+        self.inner.apply(encoder, &mut self.effect, factory, data);
+        timer.end(encoder);
     }
 
     /// Distributes new target data to the pass.
-    pub fn new_target(&mut self, target: &Target) {
+    pub fn new_target(&mut self, target: &Target, targets: &Targets) {
+        self.out = target.clone();
+        self.targets = targets.clone();
+
         // Distribute new targets that don't blend.
         self.effect.data.out_colors.clear();
         self.effect
@@ -84,6 +138,23 @@ impl<P> CompiledPass<P> {
             .extend(target.color_bufs().iter().map(|cb| &cb.as_output).cloned());
 
         // Distribute new depth buffer
-        self.effect.data.out_depth = target.depth_buf().map(|db| (db.as_output.clone(), (0, 0)));
+        // The stencil reference is kept.
+        let stencil = self.effect
+            .data
+            .out_depth
+            .as_ref()
+            .map(|depth| depth.1)
+            .unwrap_or((0, 0));
+        self.effect.data.out_depth = target
+            .depth_buf()
+            .map(|db| (db.as_output.clone(), stencil));
+
+        // Distribute new target inputs
+        self.effect.new_inputs(targets);
+
+        // Fit the drawn area to the new size
+        if let Some(viewport) = self.effect.viewport {
+            self.effect.data.scissor = Some(viewport.rect(target.size()));
+        }
     }
 }
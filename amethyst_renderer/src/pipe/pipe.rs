@@ -1,19 +1,26 @@
 use hetseq::*;
 use specs::SystemData;
 
+use super::pass::CompiledPass;
 use super::stage::*;
 use super::target::*;
 
 // use color::Rgba;
 use error::{Error, Result};
 use fnv::FnvHashMap as HashMap;
+use pass::{DrawTonemap, Tonemapping};
+use stats::GpuTimer;
 use types::{Encoder, Factory};
 
+/// Name of the intermediate target backbuffer stages render into when HDR is enabled.
+pub(crate) const HDR_TARGET: &str = "hdr";
+
 /// Defines how the rendering pipeline should be configured.
 #[derive(Clone, Debug)]
 pub struct Pipeline<L> {
     stages: L,
     targets: HashMap<String, Target>,
+    tonemap: Option<CompiledPass<DrawTonemap>>,
 }
 
 impl Pipeline<List<()>> {
@@ -43,6 +50,7 @@ pub trait PolyStages: for<'a> StagesData<'a> {
         &'a mut self,
         encoders: &mut Encoder,
         factory: Factory,
+        timer: &mut GpuTimer,
         data: <Self as StagesData<'b>>::Data,
     );
 
@@ -65,10 +73,11 @@ where
         &'a mut self,
         encoders: &mut Encoder,
         factory: Factory,
+        timer: &mut GpuTimer,
         hd: <HS as StageData<'b>>::Data,
     ) {
         let List((ref mut hs, _)) = *self;
-        hs.apply(encoders, factory, hd);
+        hs.apply(encoders, factory, timer, hd);
     }
 
     fn new_targets(&mut self, new_targets: &HashMap<String, Target>) {
@@ -94,11 +103,12 @@ where
         &'a mut self,
         encoders: &mut Encoder,
         factory: Factory,
+        timer: &mut GpuTimer,
         (hd, td): <Self as StagesData<'b>>::Data,
     ) {
         let List((ref mut hs, ref mut ts)) = *self;
-        hs.apply(encoders, factory.clone(), hd);
-        ts.apply(encoders, factory, td);
+        hs.apply(encoders, factory.clone(), timer, hd);
+        ts.apply(encoders, factory, timer, td);
     }
 
     fn new_targets(&mut self, new_targets: &HashMap<String, Target>) {
@@ -121,6 +131,7 @@ pub trait PolyPipeline: for<'a> PipelineData<'a> {
         &'a mut self,
         encoder: &mut Encoder,
         factory: Factory,
+        timer: &mut GpuTimer,
         data: <Self as PipelineData<'b>>::Data,
     );
 
@@ -146,13 +157,24 @@ where
         &'a mut self,
         encoders: &mut Encoder,
         factory: Factory,
+        timer: &mut GpuTimer,
         data: <L as StagesData<'b>>::Data,
     ) {
-        self.stages.apply(encoders, factory, data);
+        self.stages.apply(encoders, factory.clone(), timer, data);
+        if let Some(ref mut tonemap) = self.tonemap {
+            timer.start_stage("");
+            tonemap.apply(encoders, factory, timer, ());
+        }
     }
 
     fn new_targets(&mut self, new_targets: HashMap<String, Target>) {
-        self.stages.new_targets(&new_targets);
+        match self.tonemap {
+            Some(ref mut tonemap) => {
+                tonemap.new_target(&new_targets[""], &new_targets);
+                self.stages.new_targets(&redirect_backbuffer(&new_targets));
+            }
+            None => self.stages.new_targets(&new_targets),
+        }
         self.targets = new_targets;
     }
 
@@ -167,6 +189,7 @@ where
 pub struct PipelineBuilder<Q> {
     stages: Q,
     targets: Vec<TargetBuilder>,
+    hdr: Option<Tonemapping>,
 }
 
 impl PipelineBuilder<Queue<()>> {
@@ -181,6 +204,7 @@ impl Default for PipelineBuilder<Queue<()>> {
         PipelineBuilder {
             stages: Queue::new(),
             targets: Vec::new(),
+            hdr: None,
         }
     }
 }
@@ -194,6 +218,7 @@ impl<Q> PipelineBuilder<Queue<Q>> {
         PipelineBuilder {
             stages: self.stages.push(sb),
             targets: self.targets,
+            hdr: self.hdr,
         }
     }
 }
@@ -204,6 +229,16 @@ impl<Q> PipelineBuilder<Q> {
         self.targets.push(tb);
         self
     }
+
+    /// Renders the backbuffer stages into a high dynamic range target,
+    /// which is mapped into the displayable range with the given operator
+    /// before presenting.
+    ///
+    /// The intermediate target is named `"hdr"` and has a depth buffer.
+    pub fn with_hdr(mut self, tonemapping: Tonemapping) -> Self {
+        self.hdr = Some(tonemapping);
+        self
+    }
 }
 
 ///
@@ -231,13 +266,52 @@ where
 
         targets.insert("".into(), out.clone());
 
-        let stages = self.stages
-            .into_list()
-            .fmap(BuildStage::new(fac, &targets, multisampling))
-            .try()?;
+        let tonemap = match self.hdr {
+            Some(tonemapping) => {
+                let (name, hdr) = TargetBuilder::new(HDR_TARGET)
+                    .with_depth_buf(true)
+                    .with_hdr(true)
+                    .build(fac, out.size())?;
+                targets.insert(name, hdr);
+                let tonemap = CompiledPass::compile(
+                    DrawTonemap::new(tonemapping),
+                    fac,
+                    out,
+                    &targets,
+                    Some(HDR_TARGET),
+                    multisampling,
+                )?;
+                Some(tonemap)
+            }
+            None => None,
+        };
+
+        let stages = {
+            let stage_targets = match tonemap {
+                Some(_) => redirect_backbuffer(&targets),
+                None => targets.clone(),
+            };
+            self.stages
+                .into_list()
+                .fmap(BuildStage::new(fac, &stage_targets, multisampling))
+                .try()?
+        };
+
+        Ok(Pipeline {
+            stages,
+            targets,
+            tonemap,
+        })
+    }
+}
 
-        Ok(Pipeline { stages, targets })
+/// Makes the HDR target stand in for the backbuffer.
+fn redirect_backbuffer(targets: &Targets) -> Targets {
+    let mut targets = targets.clone();
+    if let Some(hdr) = targets.get(HDR_TARGET).cloned() {
+        targets.insert("".into(), hdr);
     }
+    targets
 }
 
 pub struct BuildStage<'a> {
@@ -1,15 +1,70 @@
 //! A stage in the rendering pipeline.
 
+use amethyst_core::cgmath::{Matrix4, Vector3};
+use gfx::Rect;
 use hetseq::*;
 
 use error::{Error, Result};
 use fnv::FnvHashMap as HashMap;
 use pipe::{Target, Targets};
 use pipe::pass::{CompiledPass, Pass, PassData};
-use specs::SystemData;
+use specs::{Entity, SystemData};
 
+use stats::GpuTimer;
 use types::{Encoder, Factory};
 
+/// Area of the target a stage draws into, relative to the target size.
+///
+/// The origin is the top left corner of the target, so `Viewport::new(0.0,
+/// 0.0, 0.5, 1.0)` covers the left half of it.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Viewport {
+    /// Left edge, from `0.0` to `1.0`.
+    pub x: f32,
+    /// Top edge, from `0.0` to `1.0`.
+    pub y: f32,
+    /// Width, from `0.0` to `1.0`.
+    pub width: f32,
+    /// Height, from `0.0` to `1.0`.
+    pub height: f32,
+}
+
+impl Viewport {
+    /// Creates a new `Viewport` from its top left corner and size.
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Viewport {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the area in pixels covered in a target of the given size,
+    /// with the origin in the bottom left corner.
+    pub(crate) fn rect(&self, (w, h): (u32, u32)) -> Rect {
+        let (w, h) = (w as f32, h as f32);
+        Rect {
+            x: (self.x * w).round() as u16,
+            y: ((1.0 - self.y - self.height) * h).round() as u16,
+            w: (self.width * w).round() as u16,
+            h: (self.height * h).round() as u16,
+        }
+    }
+
+    /// Returns the matrix mapping the normalized device coordinates of the
+    /// whole target into the viewport, to be applied after the projection.
+    pub(crate) fn transform(&self) -> Matrix4<f32> {
+        let offset = Vector3::new(
+            2.0 * self.x + self.width - 1.0,
+            1.0 - 2.0 * self.y - self.height,
+            0.0,
+        );
+        Matrix4::from_translation(offset)
+            * Matrix4::from_nonuniform_scale(self.width, self.height, 1.0)
+    }
+}
+
 /// A stage in the rendering pipeline.
 #[derive(Clone, Debug)]
 pub struct Stage<L> {
@@ -28,11 +83,52 @@ impl Stage<List<()>> {
     }
 
     /// Builds a new `PolyStage` which outputs straight into the backbuffer.
+    ///
+    /// If the pipeline is built with `PipelineBuilder::with_hdr`, the stage
+    /// outputs into the intermediate HDR target instead.
     pub fn with_backbuffer() -> StageBuilder<Queue<()>> {
         StageBuilder::new("")
     }
 }
 
+/// Builds post-processing stages.
+///
+/// A post-processing stage reads the first color buffer of a previous
+/// stage's target, which its passes bind with `EffectBuilder::with_stage_input`.
+/// Each pass usually draws a fullscreen quad.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let pipe = Pipeline::build()
+///     .with_target(Target::named("scene").with_depth_buf(true))
+///     .with_stage(Stage::with_target("scene")
+///         .clear_target([0.0; 4], 1.0)
+///         .with_pass(DrawShaded::<PosNormTex>::new()))
+///     .with_stage(PostProcessing::with_backbuffer("scene")
+///         .with_pass(DrawPostEffect::new(GRAYSCALE_SRC)));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct PostProcessing;
+
+impl PostProcessing {
+    /// Builds a new post-processing `PolyStage` which reads the target `input`
+    /// and outputs to the `Target` with the given name.
+    pub fn with_target<I, N>(input: I, target_name: N) -> StageBuilder<Queue<()>>
+    where
+        I: Into<String>,
+        N: Into<String>,
+    {
+        StageBuilder::new(target_name).with_input(input)
+    }
+
+    /// Builds a new post-processing `PolyStage` which reads the target `input`
+    /// and outputs straight into the backbuffer.
+    pub fn with_backbuffer<I: Into<String>>(input: I) -> StageBuilder<Queue<()>> {
+        StageBuilder::new("").with_input(input)
+    }
+}
+
 impl<L> Stage<L> {
     /// Enables the `PolyStage` so it will execute on every frame.
     pub fn enable(&mut self) {
@@ -59,11 +155,12 @@ pub trait Passes: for<'a> PassesData<'a> {
         &'a mut self,
         encoder: &mut Encoder,
         factory: Factory,
+        timer: &mut GpuTimer,
         data: <Self as PassesData<'b>>::Data,
     );
 
     /// Distributes new targets
-    fn new_target(&mut self, new_target: &Target);
+    fn new_target(&mut self, new_target: &Target, new_targets: &Targets);
 }
 
 impl<'a, HP> PassesData<'a> for List<(CompiledPass<HP>, List<()>)>
@@ -81,15 +178,16 @@ where
         &'a mut self,
         encoder: &mut Encoder,
         factory: Factory,
+        timer: &mut GpuTimer,
         hd: <HP as PassData<'b>>::Data,
     ) {
         let List((ref mut hp, _)) = *self;
-        hp.apply(encoder, factory, hd);
+        hp.apply(encoder, factory, timer, hd);
     }
 
-    fn new_target(&mut self, new_target: &Target) {
+    fn new_target(&mut self, new_target: &Target, new_targets: &Targets) {
         let List((ref mut hp, _)) = *self;
-        hp.new_target(new_target);
+        hp.new_target(new_target, new_targets);
     }
 }
 
@@ -110,17 +208,18 @@ where
         &'a mut self,
         encoder: &mut Encoder,
         factory: Factory,
+        timer: &mut GpuTimer,
         (hd, td): (<HP as PassData<'b>>::Data, <TP as PassesData<'b>>::Data),
     ) {
         let List((ref mut hp, ref mut tp)) = *self;
-        hp.apply(encoder, factory.clone(), hd);
-        tp.apply(encoder, factory, td);
+        hp.apply(encoder, factory.clone(), timer, hd);
+        tp.apply(encoder, factory, timer, td);
     }
 
-    fn new_target(&mut self, new_target: &Target) {
+    fn new_target(&mut self, new_target: &Target, new_targets: &Targets) {
         let List((ref mut hp, ref mut tp)) = *self;
-        hp.new_target(new_target);
-        tp.new_target(new_target);
+        hp.new_target(new_target, new_targets);
+        tp.new_target(new_target, new_targets);
     }
 }
 
@@ -136,6 +235,7 @@ pub trait PolyStage: for<'a> StageData<'a> {
         &'a mut self,
         encoder: &mut Encoder,
         factory: Factory,
+        timer: &mut GpuTimer,
         data: <Self as StageData<'b>>::Data,
     );
 
@@ -158,21 +258,23 @@ where
         &'a mut self,
         encoder: &mut Encoder,
         factory: Factory,
+        timer: &mut GpuTimer,
         data: <L as PassesData<'b>>::Data,
     ) {
+        timer.start_stage(&self.target_name);
         self.clear_color
             .map(|c| self.target.clear_color(encoder, c));
         self.clear_depth
             .map(|d| self.target.clear_depth_stencil(encoder, d));
 
-        self.passes.apply(encoder, factory, data);
+        self.passes.apply(encoder, factory, timer, data);
     }
 
     fn new_targets(&mut self, new_targets: &HashMap<String, Target>) {
         match new_targets.get(&self.target_name) {
             Some(target) => {
                 self.target = target.clone();
-                self.passes.new_target(target);
+                self.passes.new_target(target, new_targets);
             }
             None => {
                 eprintln!("Target name {:?} not found!", self.target_name);
@@ -185,22 +287,28 @@ where
 #[derive(Derivative)]
 #[derivative(Clone, Debug)]
 pub struct StageBuilder<Q> {
+    camera: Option<Entity>,
     clear_color: Option<[f32; 4]>,
     clear_depth: Option<f32>,
     enabled: bool,
+    input: Option<String>,
     passes: Q,
     target_name: String,
+    viewport: Option<Viewport>,
 }
 
 impl StageBuilder<Queue<()>> {
     /// Creates a new `StageBuilder` using the given target.
     pub fn new<T: Into<String>>(target_name: T) -> Self {
         StageBuilder {
+            camera: None,
             clear_color: None,
             clear_depth: None,
             enabled: true,
+            input: None,
             passes: Queue::new(),
             target_name: target_name.into(),
+            viewport: None,
         }
     }
 }
@@ -224,6 +332,30 @@ impl<Q> StageBuilder<Q> {
         self
     }
 
+    /// Sets the target which the passes of this stage can read as input.
+    pub fn with_input<N: Into<String>>(mut self, target_name: N) -> Self {
+        self.input = Some(target_name.into());
+        self
+    }
+
+    /// Sets the camera entity the passes of this stage draw with, instead of
+    /// the `ActiveCamera`.
+    pub fn with_camera(mut self, camera: Entity) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    /// Restricts the drawing of the passes to an area of the target.
+    ///
+    /// Combined with `with_camera`, several stages outputting to the same
+    /// target can show different cameras side by side, e.g. for split screen.
+    /// Clearing always affects the whole target, so only the first of these
+    /// stages should clear it.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = Some(viewport);
+        self
+    }
+
     pub(crate) fn build<'a, L, Z, R>(
         self,
         fac: &'a mut Factory,
@@ -241,9 +373,23 @@ impl<Q> StageBuilder<Q> {
             .cloned()
             .ok_or(Error::NoSuchTarget(self.target_name.clone()))?;
 
+        if let Some(ref input) = self.input {
+            if !targets.contains_key(input) {
+                return Err(Error::NoSuchTarget(input.clone()));
+            }
+        }
+
         let passes = self.passes
             .into_list()
-            .fmap(CompilePass::new(fac, &out, multisampling))
+            .fmap(CompilePass::new(
+                fac,
+                &out,
+                targets,
+                self.input.as_ref().map(String::as_str),
+                self.camera,
+                self.viewport,
+                multisampling,
+            ))
             .try()?;
 
         Ok(Stage {
@@ -261,11 +407,14 @@ impl<Q> StageBuilder<Queue<Q>> {
     /// Appends another `Pass` to the stage.
     pub fn with_pass<P: Pass>(self, pass: P) -> StageBuilder<Queue<(Queue<Q>, P)>> {
         StageBuilder {
+            camera: self.camera,
             clear_color: self.clear_color,
             clear_depth: self.clear_depth,
             enabled: self.enabled,
+            input: self.input,
             passes: self.passes.push(pass),
             target_name: self.target_name,
+            viewport: self.viewport,
         }
     }
 }
@@ -273,14 +422,30 @@ impl<Q> StageBuilder<Queue<Q>> {
 pub struct CompilePass<'a> {
     factory: &'a mut Factory,
     target: &'a Target,
+    targets: &'a Targets,
+    input: Option<&'a str>,
+    camera: Option<Entity>,
+    viewport: Option<Viewport>,
     multisampling: u16,
 }
 
 impl<'a> CompilePass<'a> {
-    fn new(factory: &'a mut Factory, target: &'a Target, multisampling: u16) -> Self {
+    fn new(
+        factory: &'a mut Factory,
+        target: &'a Target,
+        targets: &'a Targets,
+        input: Option<&'a str>,
+        camera: Option<Entity>,
+        viewport: Option<Viewport>,
+        multisampling: u16,
+    ) -> Self {
         CompilePass {
             factory,
             target,
+            targets,
+            input,
+            camera,
+            viewport,
             multisampling,
         }
     }
@@ -292,7 +457,16 @@ where
 {
     type Output = Result<CompiledPass<P>>;
     fn call_once(self, (pass,): (P,)) -> Result<CompiledPass<P>> {
-        CompiledPass::compile(pass, self.factory, self.target, self.multisampling)
+        CompiledPass::compile(
+            pass,
+            self.factory,
+            self.target,
+            self.targets,
+            self.input,
+            self.camera,
+            self.viewport,
+            self.multisampling,
+        )
     }
 }
 impl<'a, P> HetFnMut<(P,)> for CompilePass<'a>
@@ -300,6 +474,15 @@ where
     P: Pass,
 {
     fn call_mut(&mut self, (pass,): (P,)) -> Result<CompiledPass<P>> {
-        CompiledPass::compile(pass, self.factory, self.target, self.multisampling)
+        CompiledPass::compile(
+            pass,
+            self.factory,
+            self.target,
+            self.targets,
+            self.input,
+            self.camera,
+            self.viewport,
+            self.multisampling,
+        )
     }
 }
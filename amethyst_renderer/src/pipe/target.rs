@@ -3,7 +3,8 @@
 use fnv::FnvHashMap as HashMap;
 
 use error::Result;
-use types::{DepthStencilView, Encoder, Factory, RenderTargetView, ShaderResourceView, Window};
+use types::{Backend, DefaultBackend, DepthStencilView, Encoder, Factory, HdrFormat,
+            RenderTargetView, ShaderResourceView, Window};
 
 /// Target color buffer.
 #[derive(Clone, Debug, PartialEq)]
@@ -35,6 +36,9 @@ pub struct Target {
     color_bufs: Vec<ColorBuffer>,
     depth_buf: Option<DepthBuffer>,
     size: (u32, u32),
+    fixed_size: bool,
+    scaled: bool,
+    hdr: bool,
 }
 
 impl Target {
@@ -44,6 +48,9 @@ impl Target {
             color_bufs: vec![cb],
             depth_buf: Some(db),
             size: size,
+            fixed_size: false,
+            scaled: false,
+            hdr: false,
         }
     }
 
@@ -74,6 +81,24 @@ impl Target {
         self.size
     }
 
+    /// Returns whether the render target was given a custom size, which is
+    /// kept when the window is resized.
+    pub fn is_fixed_size(&self) -> bool {
+        self.fixed_size
+    }
+
+    /// Returns whether the render target follows the resolution scale of
+    /// the `Renderer`.
+    pub fn is_scaled(&self) -> bool {
+        self.scaled
+    }
+
+    /// Returns whether the color buffers of the render target store high
+    /// dynamic range values.
+    pub fn is_hdr(&self) -> bool {
+        self.hdr
+    }
+
     /// Returns the color buffer with index `i`.
     pub fn color_buf(&self, i: usize) -> Option<&ColorBuffer> {
         self.color_bufs.get(i)
@@ -89,26 +114,18 @@ impl Target {
         self.depth_buf.as_ref()
     }
 
-    /// Creates the Direct3D 11 backend.
-    #[cfg(all(feature = "d3d11", target_os = "windows"))]
-    pub fn resize_main_target(window: &Window) -> Result<(Device, Factory, Target)> {
-        unimplemented!()
-    }
-
-    #[cfg(all(feature = "metal", target_os = "macos"))]
-    pub fn resize_main_target(window: &Window) -> Result<(Device, Factory, Target)> {
-        unimplemented!()
-    }
-
-    /// Creates the OpenGL backend.
-    #[cfg(feature = "opengl")]
-    pub fn resize_main_target(&mut self, window: &Window) {
+    /// Updates the views of the backbuffer after the window got resized.
+    pub fn resize_main_target(&mut self, window: &Window) -> Result<()> {
         if let Some(depth_buf) = self.depth_buf.as_mut() {
             for ref mut color_buf in &mut self.color_bufs {
-                use gfx_window_glutin as win;
-                win::update_views(window, &mut color_buf.as_output, &mut depth_buf.as_output);
+                DefaultBackend::update_views(
+                    window,
+                    &mut color_buf.as_output,
+                    &mut depth_buf.as_output,
+                )?;
             }
         }
+        Ok(())
     }
 }
 
@@ -122,6 +139,8 @@ pub struct TargetBuilder {
     name: String,
     has_depth_buf: bool,
     num_color_bufs: usize,
+    scaled: bool,
+    hdr: bool,
 }
 
 impl TargetBuilder {
@@ -132,6 +151,8 @@ impl TargetBuilder {
             name: name.into(),
             has_depth_buf: false,
             num_color_bufs: 1,
+            scaled: false,
+            hdr: false,
         }
     }
 
@@ -152,6 +173,31 @@ impl TargetBuilder {
         self
     }
 
+    /// Sets whether the color buffers store high dynamic range values.
+    ///
+    /// HDR color buffers use a 16 bit floating point format, so values above
+    /// 1.0 are kept instead of being clamped. They are still exposed through
+    /// the regular `RenderTargetView`, only their storage format differs.
+    ///
+    /// By default, render targets are not HDR.
+    pub fn with_hdr(mut self, hdr: bool) -> Self {
+        self.hdr = hdr;
+        self
+    }
+
+    /// Sets whether the render target follows the resolution scale of the
+    /// `Renderer`, see `Renderer::set_resolution_scale`.
+    ///
+    /// Stages drawing the scene into a scaled target are cheaper at lower
+    /// scales, a `PostProcessing` stage then reads the target to upsample it
+    /// into the backbuffer. It has no effect on targets with a custom size.
+    ///
+    /// By default, render targets are not scaled.
+    pub fn with_scaled_resolution(mut self, scaled: bool) -> Self {
+        self.scaled = scaled;
+        self
+    }
+
     /// Specifies a custom target size.
     pub fn with_size(mut self, size: (u32, u32)) -> Self {
         self.custom_size = Some(size);
@@ -161,14 +207,22 @@ impl TargetBuilder {
     /// Builds and returns the new render target.
     pub(crate) fn build(self, fac: &mut Factory, size: (u32, u32)) -> Result<(String, Target)> {
         use gfx::Factory;
+        use gfx::memory::Typed;
 
+        let fixed_size = self.custom_size.is_some();
         let size = self.custom_size.unwrap_or(size);
 
         let color_bufs = (0..self.num_color_bufs)
             .into_iter()
             .map(|_| {
                 let (w, h) = (size.0 as u16, size.1 as u16);
-                let (_, res, rt) = fac.create_render_target(w, h)?;
+                let (res, rt) = if self.hdr {
+                    let (_, res, rt) = fac.create_render_target::<HdrFormat>(w, h)?;
+                    (res, RenderTargetView::new(rt.raw().clone()))
+                } else {
+                    let (_, res, rt) = fac.create_render_target(w, h)?;
+                    (res, rt)
+                };
                 Ok(ColorBuffer {
                     as_input: Some(res),
                     as_output: rt,
@@ -192,6 +246,9 @@ impl TargetBuilder {
             color_bufs: color_bufs,
             depth_buf: depth_buf,
             size: size,
+            fixed_size,
+            scaled: self.scaled,
+            hdr: self.hdr,
         };
 
         Ok((self.name, target))
@@ -0,0 +1,198 @@
+//! Prefab data of the renderer components.
+
+use amethyst_assets::{AssetStorage, Loader, PrefabData, ProgressCounter, Result};
+use specs::{Entity, Fetch, WriteStorage};
+
+use formats::{BmpFormat, JpgFormat, ObjFormat, PngFormat, TextureData, TextureMetadata};
+use light::Light;
+use mesh::{Mesh, MeshHandle};
+use mtl::{Material, MaterialDefaults};
+use shape::Shape;
+use tex::{Texture, TextureHandle};
+use vertex::PosNormTex;
+
+impl<'a> PrefabData<'a> for Light {
+    type SystemData = WriteStorage<'a, Light>;
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        lights: &mut Self::SystemData,
+        _: &[Entity],
+    ) -> Result<()> {
+        lights.insert(entity, self.clone());
+        Ok(())
+    }
+}
+
+/// Mesh of a prefab entity, loaded when the prefab is processed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum MeshPrefab {
+    /// Wavefront OBJ file, relative to the asset directory
+    Obj(String),
+    /// Generated shape, with an optional scale
+    Shape(Shape, Option<[f32; 3]>),
+    /// The loaded mesh, which the other variants are replaced with when the prefab is processed
+    #[serde(skip)]
+    Handle(MeshHandle),
+}
+
+impl<'a> PrefabData<'a> for MeshPrefab {
+    type SystemData = (
+        Fetch<'a, Loader>,
+        Fetch<'a, AssetStorage<Mesh>>,
+        WriteStorage<'a, MeshHandle>,
+    );
+
+    fn load_sub_assets(
+        &mut self,
+        progress: &mut ProgressCounter,
+        system_data: &mut Self::SystemData,
+    ) {
+        let (ref loader, ref storage, _) = *system_data;
+        let handle = match *self {
+            MeshPrefab::Obj(ref name) => {
+                loader.load(name.as_str(), ObjFormat, Default::default(), progress, storage)
+            }
+            MeshPrefab::Shape(ref shape, scale) => {
+                loader.load_from_data(shape.generate::<PosNormTex>(scale), progress, storage)
+            }
+            MeshPrefab::Handle(_) => return,
+        };
+        *self = MeshPrefab::Handle(handle);
+    }
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+        _: &[Entity],
+    ) -> Result<()> {
+        match *self {
+            MeshPrefab::Handle(ref handle) => {
+                system_data.2.insert(entity, handle.clone());
+                Ok(())
+            }
+            _ => bail!("The mesh of the prefab has not been loaded"),
+        }
+    }
+}
+
+/// Texture of a `MaterialPrefab`, loaded when the prefab is processed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum TexturePrefab {
+    /// Single color texture, in linear RGBA
+    Color([f32; 4]),
+    /// PNG image, relative to the asset directory
+    Png(String),
+    /// JPEG image, relative to the asset directory
+    Jpg(String),
+    /// BMP image, relative to the asset directory
+    Bmp(String),
+    /// The loaded texture, which the other variants are replaced with when the prefab is
+    /// processed
+    #[serde(skip)]
+    Handle(TextureHandle),
+}
+
+impl TexturePrefab {
+    fn load(
+        &mut self,
+        metadata: TextureMetadata,
+        progress: &mut ProgressCounter,
+        loader: &Loader,
+        storage: &AssetStorage<Texture>,
+    ) {
+        let handle = match *self {
+            TexturePrefab::Color(color) => {
+                loader.load_from_data(TextureData::color(color), progress, storage)
+            }
+            TexturePrefab::Png(ref name) => {
+                loader.load(name.as_str(), PngFormat, metadata, progress, storage)
+            }
+            TexturePrefab::Jpg(ref name) => {
+                loader.load(name.as_str(), JpgFormat, metadata, progress, storage)
+            }
+            TexturePrefab::Bmp(ref name) => {
+                loader.load(name.as_str(), BmpFormat, metadata, progress, storage)
+            }
+            TexturePrefab::Handle(_) => return,
+        };
+        *self = TexturePrefab::Handle(handle);
+    }
+}
+
+/// Material of a prefab entity, whose textures are loaded when the prefab is processed.
+///
+/// The maps that are not given are the ones of the `MaterialDefaults`. The albedo and emission
+/// images are loaded as sRGB, the other ones as linear data.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MaterialPrefab {
+    /// Diffuse map.
+    pub albedo: Option<TexturePrefab>,
+    /// Emission map.
+    pub emission: Option<TexturePrefab>,
+    /// Normal map.
+    pub normal: Option<TexturePrefab>,
+    /// Metallic map.
+    pub metallic: Option<TexturePrefab>,
+    /// Roughness map.
+    pub roughness: Option<TexturePrefab>,
+    /// Ambient occlusion map.
+    pub ambient_occlusion: Option<TexturePrefab>,
+}
+
+impl<'a> PrefabData<'a> for MaterialPrefab {
+    type SystemData = (
+        Fetch<'a, Loader>,
+        Fetch<'a, AssetStorage<Texture>>,
+        Fetch<'a, MaterialDefaults>,
+        WriteStorage<'a, Material>,
+    );
+
+    fn load_sub_assets(
+        &mut self,
+        progress: &mut ProgressCounter,
+        system_data: &mut Self::SystemData,
+    ) {
+        let (ref loader, ref storage, _, _) = *system_data;
+        let mut load = |texture: &mut Option<TexturePrefab>, metadata| {
+            if let Some(ref mut texture) = *texture {
+                texture.load(metadata, progress, loader, storage);
+            }
+        };
+        load(&mut self.albedo, TextureMetadata::srgb());
+        load(&mut self.emission, TextureMetadata::srgb());
+        load(&mut self.normal, TextureMetadata::default());
+        load(&mut self.metallic, TextureMetadata::default());
+        load(&mut self.roughness, TextureMetadata::default());
+        load(&mut self.ambient_occlusion, TextureMetadata::default());
+    }
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+        _: &[Entity],
+    ) -> Result<()> {
+        let (_, _, ref defaults, ref mut materials) = *system_data;
+        let handle = |texture: &Option<TexturePrefab>, default: &TextureHandle| match *texture {
+            Some(TexturePrefab::Handle(ref handle)) => Ok(handle.clone()),
+            Some(_) => Err("A texture of the prefab has not been loaded"),
+            None => Ok(default.clone()),
+        };
+        let defaults = &defaults.0;
+        let material = Material {
+            albedo: handle(&self.albedo, &defaults.albedo)?,
+            emission: handle(&self.emission, &defaults.emission)?,
+            normal: handle(&self.normal, &defaults.normal)?,
+            metallic: handle(&self.metallic, &defaults.metallic)?,
+            roughness: handle(&self.roughness, &defaults.roughness)?,
+            ambient_occlusion: handle(&self.ambient_occlusion, &defaults.ambient_occlusion)?,
+            ..defaults.clone()
+        };
+        materials.insert(entity, material);
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+//! Render layers of the cameras and entities.
+
+use specs::{Component, DenseVecStorage, Entity, ReadStorage};
+
+/// Bitmask of the render layers of an entity, or of the layers a camera
+/// draws.
+///
+/// A camera only draws the entities sharing at least one layer with it.
+/// Entities without a mask are on the first layer only, cameras without a
+/// mask draw all the layers. For example, a first-person weapon can be put on
+/// its own layer left out by the mask of a minimap camera, or a portrait
+/// camera can draw a single layer holding one character.
+///
+/// Shadows are drawn from the lights and ignore the masks.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct RenderMask(pub u32);
+
+impl RenderMask {
+    /// Mask of the entities without a `RenderMask`.
+    pub const DEFAULT: RenderMask = RenderMask(1);
+
+    /// Mask of all the layers, the one of the cameras without a `RenderMask`.
+    pub const ALL: RenderMask = RenderMask(!0);
+
+    /// Creates a mask of the single given layer, from 0 to 31.
+    pub fn layer(layer: u32) -> Self {
+        RenderMask(1 << layer)
+    }
+
+    /// Adds the given layer to the mask.
+    pub fn with_layer(self, layer: u32) -> Self {
+        RenderMask(self.0 | 1 << layer)
+    }
+
+    /// Removes the given layer from the mask.
+    pub fn without_layer(self, layer: u32) -> Self {
+        RenderMask(self.0 & !(1 << layer))
+    }
+
+    /// Returns `true` when both masks share at least one layer.
+    pub fn intersects(&self, other: RenderMask) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl Default for RenderMask {
+    fn default() -> Self {
+        RenderMask::DEFAULT
+    }
+}
+
+impl Component for RenderMask {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Returns `true` if a camera with the given mask draws the entity.
+pub(crate) fn in_render_mask(
+    camera: RenderMask,
+    render_mask: &ReadStorage<RenderMask>,
+    entity: Entity,
+) -> bool {
+    render_mask
+        .get(entity)
+        .cloned()
+        .unwrap_or_default()
+        .intersects(camera)
+}
@@ -1,14 +1,45 @@
 use config::DisplayConfig;
 use error::{Error, Result};
 use fnv::FnvHashMap as HashMap;
+use formats::ImageData;
+use gfx::format::ChannelType;
 use gfx::memory::Pod;
-use mesh::{Mesh, MeshBuilder, VertexDataSet};
+use gfx::texture::SamplerInfo;
+use mesh::{Mesh, MeshBuilder, MeshUploads, VertexDataSet};
 use pipe::{ColorBuffer, DepthBuffer, PipelineBuild, PipelineData, PolyPipeline, Target,
            TargetBuilder};
+use stats::{GpuTimer, RenderStats, TimerQueries};
+use stream::{StreamingTexture, TextureStreams};
 use tex::{Texture, TextureBuilder};
-use types::{ColorFormat, DepthFormat, Device, Encoder, Factory, Window};
+use types::{Backend, BackendContext, ColorFormat, DefaultBackend, Device, Encoder, Factory,
+            HeadlessBackendContext, HeadlessContext, Window};
 use winit::{EventsLoop, Window as WinitWindow, WindowBuilder};
 
+/// Lowest scale of the resolution of the scaled targets.
+const MIN_RESOLUTION_SCALE: f32 = 0.1;
+
+/// Size of the main target of a headless renderer without configured
+/// dimensions.
+const DEFAULT_OFFSCREEN_SIZE: (u32, u32) = (1024, 768);
+
+/// What the device of a renderer was created with.
+enum Surface {
+    /// A window, hidden when drawing offscreen.
+    Window(Window),
+    /// A context without a window, always drawing offscreen.
+    Headless(HeadlessContext),
+}
+
+/// The device of a new renderer, along with its main target.
+struct RendererContext {
+    surface: Surface,
+    device: Device,
+    factory: Factory,
+    main_target: Target,
+    size: (u32, u32),
+    offscreen: bool,
+}
+
 /// Generic renderer.
 pub struct Renderer {
     /// The gfx factory used for creation of buffers.
@@ -17,10 +48,18 @@ pub struct Renderer {
     device: Device,
     encoder: Encoder,
     main_target: Target,
-    window: Window,
-    events: EventsLoop,
+    surface: Surface,
+    events: Option<EventsLoop>,
     multisampling: u16,
     cached_size: (u32, u32),
+    resolution_scale: f32,
+    rescaled: bool,
+    capture: bool,
+    captured: Option<Result<ImageData>>,
+    offscreen: Option<(u32, u32)>,
+    timer: Option<TimerQueries>,
+    mesh_uploads: MeshUploads,
+    texture_streams: TextureStreams,
 }
 
 impl Renderer {
@@ -34,9 +73,10 @@ impl Renderer {
         RendererBuilder::new(el)
     }
 
-    /// Creates a new `RendererBuilder`, equivalent to `RendererBuilder::new()`.
+    /// Creates a new `RendererBuilder`, without an events loop until the
+    /// window is created, so a headless renderer doesn't need a display.
     pub fn build() -> RendererBuilder {
-        Self::build_with_loop(EventsLoop::new())
+        RendererBuilder::from_events(None)
     }
 
     /// Builds a new mesh from the given vertices.
@@ -44,7 +84,7 @@ impl Renderer {
     where
         T: VertexDataSet,
     {
-        mb.build(&mut self.factory)
+        mb.build_with_uploads(&mut self.factory, &self.mesh_uploads)
     }
 
     /// Builds a new texture resource.
@@ -56,6 +96,16 @@ impl Renderer {
         tb.build(&mut self.factory)
     }
 
+    /// Builds the texture the frames of a `StreamingTexture` are uploaded to.
+    pub fn create_streaming_texture(
+        &mut self,
+        stream: &StreamingTexture,
+        sampler: SamplerInfo,
+        channel: ChannelType,
+    ) -> Result<Texture> {
+        self.texture_streams.create(&mut self.factory, stream, sampler, channel)
+    }
+
     /// Builds a new renderer pipeline.
     pub fn create_pipe<B, P>(&mut self, pb: B) -> Result<P>
     where
@@ -71,70 +121,227 @@ impl Renderer {
         P: PolyPipeline,
     {
         use gfx::Device;
-        #[cfg(feature = "opengl")]
-        use glutin::GlContext;
 
-        if let Some(size) = self.window().get_inner_size() {
-            if size != self.cached_size {
+        if let Some(size) = self.size() {
+            if size != self.cached_size || self.rescaled {
                 self.cached_size = size;
+                self.rescaled = false;
                 self.resize(pipe, size);
             }
         }
 
-        pipe.apply(&mut self.encoder, self.factory.clone(), data);
+        if let Some(ref mut queries) = self.timer {
+            queries.start_frame(&mut self.device);
+        }
+        self.mesh_uploads.upload(&mut self.encoder);
+        self.texture_streams.upload(&mut self.factory, &mut self.encoder);
+        {
+            let mut timer = GpuTimer::new(&mut self.device, self.timer.as_mut());
+            pipe.apply(&mut self.encoder, self.factory.clone(), &mut timer, data);
+        }
+        if self.capture {
+            self.capture = false;
+            self.captured = Some(self.read_main_target());
+        }
         self.encoder.flush(&mut self.device);
         self.device.cleanup();
 
-        #[cfg(feature = "opengl")]
-        self.window
-            .swap_buffers()
-            .expect("OpenGL context has been lost");
+        if self.offscreen.is_none() {
+            if let Surface::Window(ref window) = self.surface {
+                DefaultBackend::present(window);
+            }
+        }
+    }
+
+    /// Enables or disables timing the passes on the GPU.
+    pub fn set_gpu_timing(&mut self, enabled: bool) {
+        match (enabled, self.timer.take()) {
+            (true, timer) => self.timer = Some(timer.unwrap_or_default()),
+            (false, Some(mut timer)) => timer.delete(&mut self.device),
+            (false, None) => {}
+        }
+    }
+
+    /// Returns the time spent by the GPU on each pass, `None` unless GPU
+    /// timing is enabled.
+    pub fn render_stats(&self) -> Option<&RenderStats> {
+        self.timer.as_ref().map(TimerQueries::stats)
+    }
+
+    /// Requests a capture of the next frame drawn with `draw`.
+    pub fn request_capture(&mut self) {
+        self.capture = true;
+    }
+
+    /// Takes the result of the last capture requested with `request_capture`,
+    /// `None` if no frame was drawn since the request.
+    pub fn take_capture(&mut self) -> Option<Result<ImageData>> {
+        self.captured.take()
+    }
+
+    /// Reads back the color buffer of the main target, flipping it so that
+    /// the top row comes first.
+    fn read_main_target(&mut self) -> Result<ImageData> {
+        use gfx::Factory;
+        use gfx::format::Formatted;
+        use gfx::traits::FactoryExt;
+        use gfx::memory::Typed;
+        use gfx::texture::ImageInfoCommon;
+        use imagefmt::{ColFmt, Image};
+
+        let view = self.main_target
+            .color_buf(0)
+            .ok_or_else(|| Error::Capture("Main target has no color buffer".to_string()))?
+            .as_output
+            .clone();
+        let (width, height, _, _) = view.get_dimensions();
+        let buffer = self.factory
+            .create_download_buffer::<[u8; 4]>(width as usize * height as usize)?;
+        let info = ImageInfoCommon {
+            xoffset: 0,
+            yoffset: 0,
+            zoffset: 0,
+            width,
+            height,
+            depth: 0,
+            format: ColorFormat::get_format(),
+            mipmap: 0,
+        };
+        self.encoder
+            .copy_texture_to_buffer_raw(view.raw().get_texture(), None, info, buffer.raw(), 0)
+            .map_err(|err| Error::Capture(format!("{:?}", err)))?;
+        self.encoder.flush(&mut self.device);
+
+        let reader = self.factory
+            .read_mapping(&buffer)
+            .map_err(|err| Error::Capture(format!("{:?}", err)))?;
+        let mut buf = Vec::with_capacity(reader.len() * 4);
+        for row in reader.chunks(width as usize).rev() {
+            for pixel in row {
+                buf.extend_from_slice(pixel);
+            }
+        }
+        Ok(ImageData {
+            raw: Image {
+                w: width as usize,
+                h: height as usize,
+                fmt: ColFmt::RGBA,
+                buf,
+            },
+        })
+    }
+
+    /// Returns whether the renderer draws into an offscreen main target,
+    /// either without a window or instead of its hidden window.
+    pub fn is_offscreen(&self) -> bool {
+        self.offscreen.is_some()
+    }
+
+    /// Returns the size in pixels of the main target, `None` if the window
+    /// went away.
+    pub fn size(&self) -> Option<(u32, u32)> {
+        match self.offscreen {
+            Some(size) => Some(size),
+            None => self.window().and_then(|window| window.get_inner_size()),
+        }
+    }
+
+    /// Resizes the main target, along with the window unless drawing offscreen.
+    ///
+    /// The targets of the pipeline follow during the next `draw`. An offscreen
+    /// main target keeps at least one pixel.
+    pub fn set_size(&mut self, width: u32, height: u32) {
+        if let Some(ref mut size) = self.offscreen {
+            *size = (width.max(1), height.max(1));
+            return;
+        }
+        if let Some(window) = self.window() {
+            window.set_inner_size(width, height);
+        }
+    }
+
+    /// Returns the scale of the resolution of the scaled targets, relative to
+    /// the size of the main target.
+    pub fn resolution_scale(&self) -> f32 {
+        self.resolution_scale
+    }
+
+    /// Scales the resolution of the targets built with
+    /// `TargetBuilder::with_scaled_resolution`, relative to the size of the
+    /// main target. Defaults to 1.0.
+    ///
+    /// Lowering the scale when the frames take too long, and raising it back
+    /// when they are fast enough, holds a target frame rate at the cost of a
+    /// blurrier image. The targets are recreated during the next `draw`, so
+    /// the scale should change in steps rather than every frame.
+    pub fn set_resolution_scale(&mut self, scale: f32) {
+        let scale = scale.max(MIN_RESOLUTION_SCALE);
+        if scale != self.resolution_scale {
+            self.resolution_scale = scale;
+            self.rescaled = true;
+        }
     }
 
-    /// Retrieve a mutable borrow of the events loop
-    pub fn events_mut(&mut self) -> &mut EventsLoop {
-        &mut self.events
+    /// Retrieve a mutable borrow of the events loop, `None` if the renderer
+    /// is headless.
+    pub fn events_mut(&mut self) -> Option<&mut EventsLoop> {
+        self.events.as_mut()
     }
 
     /// Resize the targets associated with this renderer and pipeline.
     pub fn resize<P: PolyPipeline>(&mut self, pipe: &mut P, new_size: (u32, u32)) {
-        self.main_target.resize_main_target(&self.window);
+        if self.offscreen.is_some() {
+            match offscreen_target(&mut self.factory, new_size) {
+                Ok((_, target)) => self.main_target = target,
+                Err(e) => {
+                    // The pipeline keeps its targets, matching the old main target.
+                    error!("Failed to resize the offscreen main target: {}", e);
+                    return;
+                }
+            }
+        } else if let Surface::Window(ref window) = self.surface {
+            if let Err(e) = self.main_target.resize_main_target(window) {
+                error!("Failed to resize the backbuffer: {}", e);
+            }
+        }
         let mut targets = HashMap::default();
         targets.insert("".to_string(), self.main_target.clone());
         for (key, value) in pipe.targets().iter().filter(|&(k, _)| !k.is_empty()) {
-            let (key, target) = TargetBuilder::new(key.clone())
+            let mut builder = TargetBuilder::new(key.clone())
                 .with_num_color_bufs(value.color_bufs().len())
                 .with_depth_buf(value.depth_buf().is_some())
-                .build(&mut self.factory, new_size)
-                .unwrap();
+                .with_scaled_resolution(value.is_scaled())
+                .with_hdr(value.is_hdr());
+            if value.is_fixed_size() {
+                builder = builder.with_size(value.size());
+            }
+            let size = if value.is_scaled() {
+                scale_size(new_size, self.resolution_scale)
+            } else {
+                new_size
+            };
+            let (key, target) = builder.build(&mut self.factory, size).unwrap();
             targets.insert(key, target);
         }
         pipe.new_targets(targets);
     }
 
-    /// Retrieves an immutable borrow of the window.
+    /// Retrieves an immutable borrow of the window, `None` if the renderer
+    /// is headless.
     ///
     /// No operations require a mutable borrow as of 2017-10-02
-    #[cfg(feature = "opengl")]
-    pub fn window(&self) -> &WinitWindow {
-        self.window.window()
-    }
-
-    #[cfg(feature = "metal")]
-    #[cfg(feature = "vulkan")]
-    pub fn window(&self) -> &WinitWindow {
-        &self.window.0
-    }
-
-    #[cfg(feature = "d3d11")]
-    pub fn window(&self) -> &WinitWindow {
-        &*self.window.0
+    pub fn window(&self) -> Option<&WinitWindow> {
+        match self.surface {
+            Surface::Window(ref window) => Some(DefaultBackend::winit_window(window)),
+            Surface::Headless(_) => None,
+        }
     }
 }
 
 impl Drop for Renderer {
     fn drop(&mut self) {
         use gfx::Device;
+        self.set_gpu_timing(false);
         self.device.cleanup();
     }
 }
@@ -142,29 +349,39 @@ impl Drop for Renderer {
 /// Constructs a new `Renderer`.
 pub struct RendererBuilder {
     config: DisplayConfig,
-    events: EventsLoop,
+    events: Option<EventsLoop>,
     winit_builder: WindowBuilder,
 }
 
 impl RendererBuilder {
     /// Creates a new `RendererBuilder`.
     pub fn new(el: EventsLoop) -> Self {
+        Self::from_events(Some(el))
+    }
+
+    fn from_events(events: Option<EventsLoop>) -> Self {
         RendererBuilder {
             config: DisplayConfig::default(),
-            events: el,
+            events,
             winit_builder: WindowBuilder::new().with_title("Amethyst"),
         }
     }
 
+    /// Returns the events loop, creating it the first time it's needed.
+    fn events_loop(&mut self) -> &mut EventsLoop {
+        self.events.get_or_insert_with(EventsLoop::new)
+    }
+
     /// Applies configuration from `Config`
     pub fn with_config(&mut self, config: DisplayConfig) -> &mut Self {
         self.config = config;
+        self.config.multisampling = valid_samples(self.config.multisampling);
         let mut wb = self.winit_builder.clone();
         wb = wb.with_title(self.config.title.clone())
             .with_visibility(self.config.visibility);
 
-        if self.config.fullscreen {
-            wb = wb.with_fullscreen(Some(self.events.get_primary_monitor()));
+        if self.config.fullscreen && !self.config.headless {
+            wb = wb.with_fullscreen(Some(self.events_loop().get_primary_monitor()));
         }
         match self.config.dimensions {
             Some((width, height)) => {
@@ -195,107 +412,165 @@ impl RendererBuilder {
     }
 
     /// Consumes the builder and creates the new `Renderer`.
-    pub fn build(self) -> Result<Renderer> {
-        let Backend(device, mut factory, main_target, window) =
-            init_backend(self.winit_builder.clone(), &self.events, &self.config)?;
-
-        let cached_size = window
-            .get_inner_size()
-            .expect("Unable to fetch window size, as the window went away!");
+    ///
+    /// With `DisplayConfig::headless`, the device is created without a
+    /// window, falling back to a hidden window if the backend can't.
+    pub fn build(mut self) -> Result<Renderer> {
+        let headless = if self.config.headless {
+            match DefaultBackend::init_headless(&self.config) {
+                Ok(context) => Some(context),
+                Err(e) => {
+                    warn!(
+                        "Failed to create a headless renderer, using a hidden window: {}",
+                        e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let RendererContext {
+            surface,
+            device,
+            mut factory,
+            main_target,
+            size,
+            offscreen,
+        } = match headless {
+            Some(context) => self.init_headless(context)?,
+            None => self.init_window()?,
+        };
         let encoder = factory.create_command_buffer().into();
         Ok(Renderer {
             device,
             encoder,
             factory,
             main_target,
-            window,
+            surface,
             events: self.events,
-            multisampling: self.config.multisampling,
-            cached_size,
+            // The offscreen main target isn't multisampled.
+            multisampling: if offscreen {
+                0
+            } else {
+                self.config.multisampling
+            },
+            cached_size: size,
+            resolution_scale: 1.0,
+            rescaled: false,
+            capture: false,
+            captured: None,
+            offscreen: if offscreen { Some(size) } else { None },
+            timer: None,
+            mesh_uploads: MeshUploads::default(),
+            texture_streams: TextureStreams::default(),
+        })
+    }
+
+    /// Creates the offscreen main target of a device without a window.
+    fn init_headless(
+        &self,
+        context: HeadlessBackendContext<DefaultBackend>,
+    ) -> Result<RendererContext> {
+        let HeadlessBackendContext {
+            context,
+            device,
+            mut factory,
+        } = context;
+        let size = self.config.dimensions.unwrap_or(DEFAULT_OFFSCREEN_SIZE);
+        let size = (size.0.max(1), size.1.max(1));
+        let main_target = offscreen_target(&mut factory, size)?.1;
+        Ok(RendererContext {
+            surface: Surface::Headless(context),
+            device,
+            factory,
+            main_target,
+            size,
+            offscreen: true,
+        })
+    }
+
+    /// Creates the window along with the device, and the offscreen main
+    /// target if the window is hidden.
+    fn init_window(&mut self) -> Result<RendererContext> {
+        let hidden = self.config.hidden_window || self.config.headless;
+        let mut wb = self.winit_builder.clone();
+        if hidden {
+            wb = wb.with_visibility(false);
+        }
+        let BackendContext {
+            window,
+            device,
+            mut factory,
+            color,
+            depth,
+        } = {
+            let events = self.events.get_or_insert_with(EventsLoop::new);
+            DefaultBackend::init(wb, events, &self.config)?
+        };
+
+        let window_size = DefaultBackend::winit_window(&window)
+            .get_inner_size()
+            .expect("Unable to fetch window size, as the window went away!");
+        if hidden {
+            let size = self.config.dimensions.unwrap_or(window_size);
+            let size = (size.0.max(1), size.1.max(1));
+            let main_target = offscreen_target(&mut factory, size)?.1;
+            return Ok(RendererContext {
+                surface: Surface::Window(window),
+                device,
+                factory,
+                main_target,
+                size,
+                offscreen: true,
+            });
+        }
+        let main_target = Target::new(
+            ColorBuffer {
+                as_input: None,
+                as_output: color,
+            },
+            DepthBuffer {
+                as_input: None,
+                as_output: depth,
+            },
+            window_size,
+        );
+        Ok(RendererContext {
+            surface: Surface::Window(window),
+            device,
+            factory,
+            main_target,
+            size: window_size,
+            offscreen: false,
         })
     }
 }
 
-/// Represents a graphics backend for the renderer.
-struct Backend(pub Device, pub Factory, pub Target, pub Window);
-
-/// Creates the Direct3D 11 backend.
-#[cfg(all(feature = "d3d11", target_os = "windows"))]
-fn init_backend(wb: WindowBuilder, el: &EventsLoop, config: &DisplayConfig) -> Result<Backend> {
-    use gfx_window_dxgi as win;
-
-    // FIXME: vsync + multisampling from config
-    let (win, dev, mut fac, color) = win::init::<ColorFormat>(wb, el).unwrap();
-    let dev = gfx_device_dx11::Deferred::from(dev);
-
-    let size = win.get_inner_size_points().ok_or(Error::WindowDestroyed)?;
-    let (w, h) = (size.0 as u16, size.1 as u16);
-    let depth = fac.create_depth_stencil_view_only::<DepthFormat>(w, h)?;
-    let main_target = Target::new(
-        ColorBuffer {
-            as_input: None,
-            as_output: color,
-        },
-        DepthBuffer {
-            as_input: None,
-            as_output: depth,
-        },
-        size,
-    );
-
-    Ok(Backend(dev, fac, main_target, win))
+/// Scales the size of a target, keeping at least one pixel.
+fn scale_size(size: (u32, u32), scale: f32) -> (u32, u32) {
+    let scale = |length: u32| ((length as f32 * scale).round() as u32).max(1);
+    (scale(size.0), scale(size.1))
 }
 
-#[cfg(all(feature = "metal", target_os = "macos"))]
-fn init_backend(wb: WindowBuilder, el: &EventsLoop, config: &DisplayConfig) -> Result<Backend> {
-    use gfx_window_metal as win;
-
-    // FIXME: vsync + multisampling from config
-    let (win, dev, mut fac, color) = win::init::<ColorFormat>(wb, el).unwrap();
-
-    let size = win.get_inner_size_points().ok_or(Error::WindowDestroyed)?;
-    let (w, h) = (size.0 as u16, size.1 as u16);
-    let depth = fac.create_depth_stencil_view_only::<DepthFormat>(w, h)?;
-    let main_target = Target::new(
-        ColorBuffer {
-            as_input: None,
-            as_output: color,
-        },
-        DepthBuffer {
-            as_input: None,
-            as_output: depth,
-        },
-        size,
-    );
-
-    Ok(Backend(dev, fac, main_target, win))
+/// Creates the offscreen main target of a headless renderer or of one with a
+/// hidden window.
+fn offscreen_target(factory: &mut Factory, size: (u32, u32)) -> Result<(String, Target)> {
+    TargetBuilder::new("")
+        .with_depth_buf(true)
+        .build(factory, size)
 }
 
-/// Creates the OpenGL backend.
-#[cfg(feature = "opengl")]
-fn init_backend(wb: WindowBuilder, el: &EventsLoop, config: &DisplayConfig) -> Result<Backend> {
-    use gfx_window_glutin as win;
-    use glutin::{self, GlProfile, GlRequest};
-
-    let ctx = glutin::ContextBuilder::new()
-        .with_multisampling(config.multisampling)
-        .with_vsync(config.vsync)
-        .with_gl_profile(GlProfile::Core)
-        .with_gl(GlRequest::Latest);
-
-    let (win, dev, fac, color, depth) = win::init::<ColorFormat, DepthFormat>(wb, ctx, el);
-    let size = win.get_inner_size().ok_or(Error::WindowDestroyed)?;
-    let main_target = Target::new(
-        ColorBuffer {
-            as_input: None,
-            as_output: color,
-        },
-        DepthBuffer {
-            as_input: None,
-            as_output: depth,
-        },
-        size,
-    );
-
-    Ok(Backend(dev, fac, main_target, win))
+/// Rounds the MSAA sample count down to a power of two, as required by the backends.
+fn valid_samples(samples: u16) -> u16 {
+    if samples == 0 || samples.is_power_of_two() {
+        samples
+    } else {
+        let valid = samples.next_power_of_two() / 2;
+        warn!(
+            "Multisampling level {} is not a power of two, using {} instead",
+            samples, valid
+        );
+        valid
+    }
 }
@@ -1,9 +1,14 @@
 //! `amethyst` rendering ecs resources
 
+use std::path::PathBuf;
+
+use fnv::FnvHashMap as HashMap;
 use smallvec::SmallVec;
 use winit::Window;
 
 use color::Rgba;
+use formats::ImageData;
+use tex::TextureHandle;
 
 /// The ambient color of a scene
 #[derive(Clone, Debug, Default)]
@@ -15,6 +20,210 @@ impl AsRef<Rgba> for AmbientColor {
     }
 }
 
+/// The sky of a scene, drawn by the `DrawSkybox` pass.
+///
+/// The texture has to be a cubemap, see `CubemapFormat`, `TextureData::Cubemap`
+/// and `TextureData::Equirectangular`. Other textures aren't drawn.
+#[derive(Clone, Debug)]
+pub struct Skybox(pub TextureHandle);
+
+/// Maps used for image based lighting by the physically based passes.
+///
+/// All maps are usually precomputed from the same environment, for example
+/// the sky cubemap.
+#[derive(Clone, Debug)]
+pub struct EnvironmentMap {
+    /// Cubemap of the diffuse light coming from each direction.
+    pub irradiance: TextureHandle,
+    /// Cubemap of the specular reflections, pre-filtered for increasing
+    /// roughness in each mip level.
+    pub specular: TextureHandle,
+    /// Lookup table of the split sum BRDF, indexed by the cosine of the view
+    /// angle and the roughness.
+    pub brdf_lut: TextureHandle,
+    /// Number of mip levels of the specular map.
+    pub specular_levels: u8,
+}
+
+/// Parameters of the `DrawBloom` post-processing pass.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BloomParams {
+    /// Minimum luminance of a pixel for it to glow.
+    pub threshold: f32,
+    /// Strength of the glow added back to the scene.
+    pub intensity: f32,
+    /// Number of blur iterations, more iterations make the glow wider.
+    pub iterations: u32,
+}
+
+impl Default for BloomParams {
+    fn default() -> Self {
+        BloomParams {
+            threshold: 0.8,
+            intensity: 1.0,
+            iterations: 2,
+        }
+    }
+}
+
+/// Parameters of the `DrawDof` depth of field pass.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct DofParams {
+    /// Distance from the camera that is in focus.
+    pub focus_distance: f32,
+    /// Size of the blur, as a fraction of the screen height, of the surfaces
+    /// infinitely far from the focus. Larger apertures give a shallower depth
+    /// of field.
+    pub aperture: f32,
+    /// Maximum size of the blur, as a fraction of the screen height.
+    pub max_blur: f32,
+    /// Focuses on the surface at the center of the screen, instead of
+    /// `focus_distance`.
+    pub auto_focus: bool,
+    /// Number of samples per pixel, at most 64.
+    pub samples: u32,
+}
+
+impl Default for DofParams {
+    fn default() -> Self {
+        DofParams {
+            focus_distance: 10.0,
+            aperture: 0.01,
+            max_blur: 0.02,
+            auto_focus: false,
+            samples: 32,
+        }
+    }
+}
+
+/// Parameters of the `DrawMotionBlur` post-processing pass.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MotionBlurParams {
+    /// Fraction of the movement of the camera since the previous frame the
+    /// pixels are blurred along.
+    pub intensity: f32,
+    /// Maximum length of the blur, as a fraction of the screen.
+    pub max_length: f32,
+    /// Number of samples per pixel along the blur, at most 32.
+    pub samples: u32,
+}
+
+impl Default for MotionBlurParams {
+    fn default() -> Self {
+        MotionBlurParams {
+            intensity: 1.0,
+            max_length: 0.05,
+            samples: 8,
+        }
+    }
+}
+
+/// Parameters of the `DrawVignette` post-processing pass.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct VignetteParams {
+    /// Strength of the darkening at the corners of the screen, from 0.0 for
+    /// none to 1.0 for the full `color`.
+    pub intensity: f32,
+    /// Distance from the center where the darkening starts, 1.0 being the
+    /// middle of the sides of the screen.
+    pub radius: f32,
+    /// Width of the transition from the scene to the vignette.
+    pub smoothness: f32,
+    /// Color blended over the corners, black by default. Its alpha is ignored.
+    pub color: Rgba,
+}
+
+impl Default for VignetteParams {
+    fn default() -> Self {
+        VignetteParams {
+            intensity: 0.5,
+            radius: 0.75,
+            smoothness: 0.45,
+            color: Rgba::black(),
+        }
+    }
+}
+
+/// Parameters of the `DrawChromaticAberration` post-processing pass.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ChromaticAberrationParams {
+    /// Offset between the red and blue channels at the corners of the screen,
+    /// as a fraction of its size. The offset shrinks towards the center.
+    pub intensity: f32,
+}
+
+impl Default for ChromaticAberrationParams {
+    fn default() -> Self {
+        ChromaticAberrationParams { intensity: 0.005 }
+    }
+}
+
+/// How the density of `Fog` grows with the distance to the camera.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum FogMode {
+    /// No fog.
+    Disabled,
+    /// Fog from none at `start` to full at `end`.
+    Linear {
+        /// Distance where the fog starts.
+        start: f32,
+        /// Distance where the fog hides everything.
+        end: f32,
+    },
+    /// Fog growing exponentially with the distance, scaled by the density.
+    Exponential,
+    /// Like `Exponential`, with a longer clear area around the camera.
+    ExponentialSquared,
+}
+
+/// Fog applied by the `DrawShaded` and `DrawPbm` passes.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Fog {
+    /// Color of the fog.
+    pub color: Rgba,
+    /// How the fog grows with the distance.
+    pub mode: FogMode,
+    /// Density of the exponential modes.
+    pub density: f32,
+    /// Height below which the fog has its full density.
+    pub height: f32,
+    /// How quickly the fog thins out above `height`, `0.0` for a uniform fog.
+    pub height_falloff: f32,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Fog {
+            color: Rgba(0.5, 0.6, 0.7, 1.0),
+            mode: FogMode::Disabled,
+            density: 0.02,
+            height: 0.0,
+            height_falloff: 0.0,
+        }
+    }
+}
+
+/// Parameters of the `DrawSsao` ambient occlusion pass.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SsaoParams {
+    /// Radius of the sampled hemisphere, in world units.
+    pub radius: f32,
+    /// Depth bias which prevents flat surfaces from occluding themselves.
+    pub bias: f32,
+    /// Number of samples per pixel, at most 64.
+    pub samples: u32,
+}
+
+impl Default for SsaoParams {
+    fn default() -> Self {
+        SsaoParams {
+            radius: 0.5,
+            bias: 0.025,
+            samples: 16,
+        }
+    }
+}
+
 /// This specs resource with id 0 permits sending commands to the
 /// renderer internal window.
 #[derive(Default)]
@@ -42,6 +251,113 @@ impl WindowMessages {
     {
         self.queue.push(Box::new(command));
     }
+
+    /// Changes the title of the window next frame.
+    pub fn set_title<S: Into<String>>(&mut self, title: S) {
+        let title = title.into();
+        self.send_command(move |window| window.set_title(&title));
+    }
+
+    /// Switches the window to fullscreen on its current monitor, or back to
+    /// windowed mode, next frame.
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.send_command(move |window| {
+            let monitor = if fullscreen {
+                Some(window.get_current_monitor())
+            } else {
+                None
+            };
+            window.set_fullscreen(monitor);
+        });
+    }
+
+    /// Changes the size of the window next frame, measured in pixels (px).
+    ///
+    /// `ScreenDimensions` is updated once the window was resized.
+    pub fn set_dimensions(&mut self, width: u32, height: u32) {
+        self.send_command(move |window| window.set_inner_size(width, height));
+    }
+}
+
+/// Requests captures of the backbuffer, handled by the `RenderSystem`.
+///
+/// The frame rendered after the request is read back into an image, which
+/// can be retrieved with `take_image` on the following frames.
+#[derive(Debug, Default)]
+pub struct ScreenDump {
+    pub(crate) requested: bool,
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) image: Option<ImageData>,
+}
+
+impl ScreenDump {
+    /// Create a new `ScreenDump`
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Capture the next rendered frame.
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    /// Capture the next rendered frame and write it to the given file.
+    ///
+    /// The image format is chosen from the file extension, `png`, `tga` and
+    /// `bmp` are supported.
+    pub fn request_file<P: Into<PathBuf>>(&mut self, path: P) {
+        self.requested = true;
+        self.path = Some(path.into());
+    }
+
+    /// Returns `true` if a capture was requested but not done yet.
+    pub fn is_pending(&self) -> bool {
+        self.requested
+    }
+
+    /// Takes the last captured image, with the top row first.
+    pub fn take_image(&mut self) -> Option<ImageData> {
+        self.image.take()
+    }
+}
+
+/// Textures sampling the first color buffer of the named pipeline targets.
+///
+/// The handles are kept up to date by the `RenderSystem` when targets are
+/// resized, so they can be used in a `Material` like any other texture, e.g.
+/// to show the output of a security camera on a screen. The stage drawing into
+/// a target has to come before the stages sampling it, and a target must not
+/// be sampled by a stage drawing into it.
+#[derive(Clone, Debug, Default)]
+pub struct TargetTextures {
+    pub(crate) textures: HashMap<String, TextureHandle>,
+}
+
+impl TargetTextures {
+    /// Returns the texture of the target with the given name, if the
+    /// pipeline has such a target.
+    pub fn get(&self, name: &str) -> Option<&TextureHandle> {
+        self.textures.get(name)
+    }
+}
+
+/// Scale of the resolution of the pipeline targets built with
+/// `TargetBuilder::with_scaled_resolution`, relative to the size of the
+/// window.
+///
+/// Rendering the 3D stages into a scaled target, then upsampling it into the
+/// backbuffer in a `PostProcessing` stage, trades sharpness for speed. The
+/// scaled targets are recreated whenever the scale changes, so a game holding
+/// a target frame rate should change it in steps, e.g. by 0.1 once the frame
+/// time stayed off for a while. Defaults to 1.0, the scale never goes below
+/// 0.1.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ResolutionScale(pub f32);
+
+impl Default for ResolutionScale {
+    fn default() -> Self {
+        ResolutionScale(1.0)
+    }
 }
 
 /// World resource that stores screen dimensions.
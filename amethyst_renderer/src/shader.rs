@@ -0,0 +1,54 @@
+//! Shader asset.
+
+use std::sync::Arc;
+
+use amethyst_assets::{Asset, Handle, Result};
+use specs::VecStorage;
+
+/// A handle to a `Shader` asset.
+pub type ShaderHandle = Handle<Shader>;
+
+/// GLSL source code of a single shader stage.
+///
+/// Passes supporting shader assets compile their `Effect` again whenever
+/// the asset is reloaded, see `DrawPostEffect::with_shader`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shader {
+    source: Arc<Vec<u8>>,
+}
+
+impl Shader {
+    /// Creates a new `Shader` from its GLSL source code.
+    pub fn new(source: Vec<u8>) -> Self {
+        Shader {
+            source: Arc::new(source),
+        }
+    }
+
+    /// Returns the GLSL source code.
+    pub fn source(&self) -> &[u8] {
+        &self.source
+    }
+
+    /// Returns whether both shaders share the same source, which is not the
+    /// case anymore once one of them was reloaded.
+    pub(crate) fn same_source(&self, other: &Shader) -> bool {
+        Arc::ptr_eq(&self.source, &other.source)
+    }
+}
+
+impl Asset for Shader {
+    const NAME: &'static str = "renderer::Shader";
+    type Data = ShaderData;
+    type HandleStorage = VecStorage<ShaderHandle>;
+}
+
+/// Shader data, as produced by `GlslFormat`.
+#[derive(Clone, Debug)]
+pub struct ShaderData(pub Vec<u8>);
+
+impl Into<Result<Shader>> for ShaderData {
+    fn into(self) -> Result<Shader> {
+        Ok(Shader::new(self.0))
+    }
+}
@@ -0,0 +1,406 @@
+//! Shadow mapping configuration.
+
+use amethyst_core::GlobalTransform;
+use amethyst_core::cgmath::{ortho, perspective, Deg, EuclideanSpace, InnerSpace, Matrix4, Point3,
+                            Rad, SquareMatrix, Transform, Vector3, Vector4};
+use gfx::texture::{FilterMethod, WrapMode};
+use specs::{Component, Entities, Join, NullStorage, ReadStorage};
+
+use cam::Camera;
+use error::Result;
+use light::{DirectionalLight, Light, PointLight};
+use tex::Texture;
+use types::{DepthStencilView, Factory};
+
+/// Maximum number of cascades of the directional light shadow map.
+pub const MAX_SHADOW_CASCADES: usize = 4;
+
+/// Maximum number of point lights casting shadows through a depth cubemap.
+pub const MAX_POINT_SHADOWS: usize = 4;
+
+/// Distance to the point lights of the near plane of their shadow cubemaps.
+const POINT_SHADOW_NEAR: f32 = 0.05;
+
+/// Configures the region of the scene which is covered by the shadow map.
+///
+/// The first directional light casts shadows, or the first point light if
+/// there is no directional light in the scene.
+///
+/// The shadow map of the directional light can be split into cascades, each
+/// covering a slice of the view of the camera instead of a fixed region: the
+/// closest slices get the most detailed shadows. The cascades are laid out
+/// side by side in the shadow map target, which should then be `cascades`
+/// times as wide as it is high.
+///
+/// The point lights of `ShadowCaster` entities also cast shadows through
+/// their own depth cubemap, drawn by the `DrawPointShadow` pass.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ShadowConfig {
+    /// Center of the shadowed area in world space.
+    pub center: [f32; 3],
+    /// Half of the side length of the shadowed area.
+    pub extent: f32,
+    /// Depth bias used when comparing against the shadow map, it prevents
+    /// surfaces from shadowing themselves.
+    pub bias: f32,
+    /// Number of texels around each lookup averaged by the percentage closer
+    /// filtering of the shadow map, 0 disables the filtering.
+    #[serde(default = "default_pcf_radius")]
+    pub pcf_radius: u32,
+    /// Number of cascades of the directional light shadow map, from 1 to
+    /// `MAX_SHADOW_CASCADES`. With a single one, the shadow map covers the
+    /// region given by `center` and `extent`.
+    #[serde(default = "default_cascades")]
+    pub cascades: usize,
+    /// Distance from the camera up to which the cascades cover its view.
+    #[serde(default = "default_cascade_distance")]
+    pub cascade_distance: f32,
+    /// Distribution of the cascades along the view, from evenly spaced at 0
+    /// to growing exponentially at 1.
+    #[serde(default = "default_cascade_split")]
+    pub cascade_split: f32,
+    /// Side length of each cascade in the shadow map target, in pixels. The
+    /// cascades only move by whole texels, which keeps the edges of the
+    /// shadows from flickering when the camera moves.
+    #[serde(default = "default_cascade_resolution")]
+    pub cascade_resolution: u32,
+    /// Side length of the faces of the point light shadow cubemaps, in
+    /// pixels.
+    #[serde(default = "default_point_shadow_size")]
+    pub point_shadow_size: u32,
+}
+
+fn default_pcf_radius() -> u32 {
+    1
+}
+
+fn default_cascades() -> usize {
+    1
+}
+
+fn default_cascade_distance() -> f32 {
+    100.0
+}
+
+fn default_cascade_split() -> f32 {
+    0.75
+}
+
+fn default_cascade_resolution() -> u32 {
+    1024
+}
+
+fn default_point_shadow_size() -> u32 {
+    512
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        ShadowConfig {
+            center: [0.0, 0.0, 0.0],
+            extent: 20.0,
+            bias: 0.005,
+            pcf_radius: default_pcf_radius(),
+            cascades: default_cascades(),
+            cascade_distance: default_cascade_distance(),
+            cascade_split: default_cascade_split(),
+            cascade_resolution: default_cascade_resolution(),
+            point_shadow_size: default_point_shadow_size(),
+        }
+    }
+}
+
+impl ShadowConfig {
+    /// Returns the matrix transforming world space into the clip space of
+    /// the given light, or `None` if the light can't cast shadows.
+    pub fn light_space(&self, light: &Light) -> Option<Matrix4<f32>> {
+        let center = Point3::from(self.center);
+        let extent = self.extent;
+        match *light {
+            Light::Directional(ref light) => {
+                let direction = Vector3::from(light.direction);
+                if direction.magnitude2() == 0.0 {
+                    return None;
+                }
+                let direction = direction.normalize();
+                let eye = center - direction * extent * 2.0;
+                let view = Matrix4::look_at(eye, center, up_for(direction));
+                let proj = ortho(-extent, extent, -extent, extent, 0.0, extent * 4.0);
+                Some(proj * view)
+            }
+            Light::Point(ref light) => {
+                let eye = Point3::from(light.center);
+                let offset = center - eye;
+                let (direction, distance) = if offset.magnitude2() == 0.0 {
+                    (-Vector3::unit_y(), extent)
+                } else {
+                    (offset.normalize(), offset.magnitude())
+                };
+                let fov = 2.0 * (extent / distance).atan();
+                let view = Matrix4::look_at(eye, eye + direction, up_for(direction));
+                let proj = perspective(Rad(fov.min(3.0)), 1.0, 0.1, distance + extent * 2.0);
+                Some(proj * view)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the matrices transforming world space into the clip space of
+    /// each cascade of the given light, mapped to its part of the shadow map,
+    /// or an empty `Vec` if the light can't cast shadows.
+    ///
+    /// Only directional lights seen from a camera are split into cascades,
+    /// the other lights use the single matrix of `light_space`.
+    pub fn cascades(
+        &self,
+        light: &Light,
+        camera: Option<(&Camera, &GlobalTransform)>,
+    ) -> Vec<Matrix4<f32>> {
+        let count = self.cascades.max(1).min(MAX_SHADOW_CASCADES);
+        let cascades = match (light, camera) {
+            (&Light::Directional(ref light), Some(camera)) if count > 1 => {
+                self.fit_cascades(light, camera, count)
+            }
+            _ => None,
+        };
+        cascades
+            .or_else(|| self.light_space(light).map(|light_space| vec![light_space]))
+            .unwrap_or_default()
+    }
+
+    fn fit_cascades(
+        &self,
+        light: &DirectionalLight,
+        (camera, transform): (&Camera, &GlobalTransform),
+        count: usize,
+    ) -> Option<Vec<Matrix4<f32>>> {
+        let direction = Vector3::from(light.direction);
+        if direction.magnitude2() == 0.0 {
+            return None;
+        }
+        let direction = direction.normalize();
+        let inverse_proj = camera.proj.invert()?;
+
+        // Rays through the corners of the view, from the near to the far plane
+        let unproject = |x: f32, y: f32, z: f32| {
+            let point = inverse_proj * Vector4::new(x, y, z, 1.0);
+            point.truncate() / point.w
+        };
+        let rays = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+            .iter()
+            .map(|&(x, y)| (unproject(x, y, -1.0), unproject(x, y, 1.0)))
+            .collect::<Vec<_>>();
+        let near = -rays[0].0.z;
+        let far = -rays[0].1.z;
+        if far <= near {
+            return None;
+        }
+        let distance = self.cascade_distance.min(far).max(near);
+
+        // Distances of the splits along the view, blending a uniform and a
+        // logarithmic distribution
+        let split = |i: usize| {
+            let ratio = i as f32 / count as f32;
+            let uniform = near + (distance - near) * ratio;
+            let log = near.max(0.001) * (distance / near.max(0.001)).powf(ratio);
+            uniform + (log - uniform) * self.cascade_split
+        };
+
+        // The view of the light is kept at the world origin, so its texels
+        // stay at the same place while the camera moves.
+        let view = Matrix4::look_at(
+            Point3::origin(),
+            Point3::from_vec(direction),
+            up_for(direction),
+        );
+        let resolution = self.cascade_resolution.max(1) as f32;
+        let cascades = (0..count)
+            .map(|i| {
+                let (start, end) = (split(i), split(i + 1));
+                let corners = rays
+                    .iter()
+                    .flat_map(|&(near_point, far_point)| {
+                        let at = |depth: f32| {
+                            let t = (depth - near) / (far - near);
+                            let point = near_point + (far_point - near_point) * t;
+                            transform.0.transform_point(Point3::from_vec(point))
+                        };
+                        vec![at(start), at(end)]
+                    })
+                    .collect::<Vec<_>>();
+
+                // Fitting a sphere keeps the size of the cascade constant
+                // while the camera rotates.
+                let center = corners
+                    .iter()
+                    .fold(Vector3::new(0.0, 0.0, 0.0), |sum, corner| {
+                        sum + corner.to_vec()
+                    }) / corners.len() as f32;
+                let center = Point3::from_vec(center);
+                let radius = corners
+                    .iter()
+                    .map(|corner| (*corner - center).magnitude())
+                    .fold(0.0f32, f32::max);
+                let radius = (radius * 16.0).ceil() / 16.0;
+
+                let texel = radius * 2.0 / resolution;
+                let center = view.transform_point(center);
+                let x = (center.x / texel).floor() * texel;
+                let y = (center.y / texel).floor() * texel;
+                // Casters up to the cascade distance towards the light are kept
+                let proj = ortho(
+                    x - radius,
+                    x + radius,
+                    y - radius,
+                    y + radius,
+                    -center.z - radius - distance,
+                    -center.z + radius,
+                );
+
+                let tile = Matrix4::from_translation(Vector3::new(
+                    (2 * i + 1) as f32 / count as f32 - 1.0,
+                    0.0,
+                    0.0,
+                )) * Matrix4::from_nonuniform_scale(1.0 / count as f32, 1.0, 1.0);
+                tile * proj * view
+            })
+            .collect();
+        Some(cascades)
+    }
+}
+
+/// Makes the `PointLight` of an entity cast shadows through a depth cubemap.
+///
+/// Each of them redraws the scene six times, so only the first
+/// `MAX_POINT_SHADOWS` point lights with it cast shadows.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ShadowCaster;
+
+impl Component for ShadowCaster {
+    type Storage = NullStorage<Self>;
+}
+
+/// Depth cubemaps of the shadow casting point lights, managed by the
+/// `RenderSystem`.
+///
+/// The cubemaps are created when the lights are first seen and kept
+/// afterwards, the first shadow caster uses the first cubemap and so on.
+#[derive(Clone, Debug, Default)]
+pub struct PointShadowMaps {
+    pub(crate) maps: Vec<PointShadowMap>,
+    pub(crate) size: u32,
+}
+
+impl PointShadowMaps {
+    /// Returns the number of depth cubemaps.
+    pub fn len(&self) -> usize {
+        self.maps.len()
+    }
+
+    /// Returns whether there is no depth cubemap yet.
+    pub fn is_empty(&self) -> bool {
+        self.maps.is_empty()
+    }
+}
+
+/// A depth cubemap, drawn face by face.
+#[derive(Clone, Debug)]
+pub(crate) struct PointShadowMap {
+    pub faces: Vec<DepthStencilView>,
+    pub texture: Texture,
+}
+
+impl PointShadowMap {
+    pub fn new(factory: &mut Factory, size: u32) -> Result<Self> {
+        use gfx::{CombinedError, Factory};
+        use gfx::format::{ChannelTyped, Formatted, Swizzle};
+        use gfx::memory::{Bind, Typed, Usage};
+        use gfx::texture::{DepthStencilFlags, Kind, SamplerInfo};
+        use types::DepthFormat;
+
+        type Surface = <DepthFormat as Formatted>::Surface;
+        type Channel = <DepthFormat as Formatted>::Channel;
+
+        let texture = factory.create_texture::<Surface>(
+            Kind::Cube(size as u16),
+            1,
+            Bind::SHADER_RESOURCE | Bind::DEPTH_STENCIL,
+            Usage::Data,
+            Some(Channel::get_channel_type()),
+        )?;
+        let view = factory.view_texture_as_shader_resource::<DepthFormat>(
+            &texture,
+            (0, 0),
+            Swizzle::new(),
+        )?;
+        let faces = (0..6)
+            .map(|face| {
+                factory
+                    .view_texture_as_depth_stencil::<DepthFormat>(
+                        &texture,
+                        0,
+                        Some(face),
+                        DepthStencilFlags::empty(),
+                    )
+                    .map_err(|err| CombinedError::from(err).into())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let sampler =
+            factory.create_sampler(SamplerInfo::new(FilterMethod::Bilinear, WrapMode::Clamp));
+        Ok(PointShadowMap {
+            faces,
+            texture: Texture::from_raw(texture.raw().clone(), view.raw().clone(), sampler),
+        })
+    }
+}
+
+/// Returns the point lights casting shadows through a cubemap, along with
+/// their index among all the point lights.
+pub(crate) fn point_shadow_casters<'a>(
+    entities: &Entities,
+    light: &'a ReadStorage<Light>,
+    caster: &ReadStorage<ShadowCaster>,
+) -> Vec<(usize, &'a PointLight)> {
+    (&**entities, light)
+        .join()
+        .filter_map(|(entity, light)| match *light {
+            Light::Point(ref light) => Some((entity, light)),
+            _ => None,
+        })
+        .enumerate()
+        .filter(|&(_, (entity, _))| caster.get(entity).is_some())
+        .map(|(index, (_, light))| (index, light))
+        .take(MAX_POINT_SHADOWS)
+        .collect()
+}
+
+/// Returns the matrices transforming world space into the clip space of the
+/// faces of a point light cubemap, in the order +X, -X, +Y, -Y, +Z, -Z.
+pub(crate) fn point_light_faces(light: &PointLight) -> [Matrix4<f32>; 6] {
+    let eye = Point3::from(light.center);
+    let proj = perspective(Deg(90.0), 1.0, POINT_SHADOW_NEAR, point_shadow_far(light));
+    let face = |direction: Vector3<f32>, up: Vector3<f32>| {
+        proj * Matrix4::look_at(eye, eye + direction, up)
+    };
+    [
+        face(Vector3::unit_x(), -Vector3::unit_y()),
+        face(-Vector3::unit_x(), -Vector3::unit_y()),
+        face(Vector3::unit_y(), Vector3::unit_z()),
+        face(-Vector3::unit_y(), -Vector3::unit_z()),
+        face(Vector3::unit_z(), -Vector3::unit_y()),
+        face(-Vector3::unit_z(), -Vector3::unit_y()),
+    ]
+}
+
+/// Returns the distance covered by the shadow cubemap of a point light.
+pub(crate) fn point_shadow_far(light: &PointLight) -> f32 {
+    light.radius.max(POINT_SHADOW_NEAR * 2.0)
+}
+
+fn up_for(direction: Vector3<f32>) -> Vector3<f32> {
+    if direction.x.abs() < 0.001 && direction.z.abs() < 0.001 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_y()
+    }
+}
@@ -0,0 +1,523 @@
+//! Generators of simple meshes.
+
+use std::f32::consts::PI;
+
+use amethyst_core::cgmath::{ElementWise, InnerSpace, Vector3};
+
+use formats::{ComboMeshCreator, MeshData};
+use vertex::{Normal, PosColor, PosColorTex, PosNormTangTex, PosNormTex, PosTex, Position,
+             Separate, Tangent, TexCoord};
+
+/// Normal and tangent of each face of a cube.
+const CUBE_FACES: [([f32; 3], [f32; 3]); 6] = [
+    ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+    ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0]),
+    ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0]),
+    ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0]),
+    ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0]),
+];
+
+/// Simple shapes to generate meshes from, for prototypes and examples.
+///
+/// The shapes are centered on the origin and span from -1 to 1 along each
+/// axis, before scaling, with the y axis up. They are generated as lists of
+/// triangles facing outwards, with smooth normals on their curved surfaces.
+/// The texture coordinates go from 0 to 1, with the top of the texture
+/// towards positive y on the sides of the shapes.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Shape {
+    /// Sphere of radius 1.
+    Sphere {
+        /// Number of segments around the y axis, at least 3.
+        segments: usize,
+        /// Number of rings from pole to pole, at least 2.
+        rings: usize,
+    },
+    /// Cube with flat faces, each textured with the whole texture.
+    Cube,
+    /// Square on the xz plane facing up, split into a grid of cells.
+    Plane {
+        /// Number of cells along the x axis, at least 1.
+        columns: usize,
+        /// Number of cells along the z axis, at least 1.
+        rows: usize,
+    },
+    /// Cylinder of radius 1 along the y axis, closed by flat caps.
+    Cylinder {
+        /// Number of segments around the y axis, at least 3.
+        segments: usize,
+    },
+    /// Cylinder of radius 1 along the y axis, closed by half spheres, the
+    /// whole capsule spanning from -1 - `half_length` to 1 + `half_length`.
+    Capsule {
+        /// Number of segments around the y axis, at least 3.
+        segments: usize,
+        /// Number of rings of each half sphere, at least 1.
+        rings: usize,
+        /// Half of the length of the cylinder between the half spheres.
+        half_length: f32,
+    },
+    /// Cone with its base of radius 1 at -1 along the y axis and its apex at
+    /// 1, closed by a flat base.
+    Cone {
+        /// Number of segments around the y axis, at least 3.
+        segments: usize,
+    },
+}
+
+/// A vertex of a generated `Shape`, converted into the vertex format of the
+/// mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShapeVertex {
+    /// Position of the vertex in 3D space.
+    pub position: [f32; 3],
+    /// Normal vector of the vertex.
+    pub normal: [f32; 3],
+    /// Tangent vector of the vertex, along the u texture coordinate.
+    pub tangent: [f32; 3],
+    /// UV texture coordinates used by the vertex.
+    pub tex_coord: [f32; 2],
+}
+
+impl Shape {
+    /// Generates the triangles of the shape, scaled along each axis.
+    pub fn generate_vertices<V>(&self, scale: Option<[f32; 3]>) -> Vec<V>
+    where
+        V: From<ShapeVertex>,
+    {
+        let vertices = self.vertices();
+        match scale {
+            Some(scale) => vertices
+                .into_iter()
+                .map(|vertex| scale_vertex(vertex, scale).into())
+                .collect(),
+            None => vertices.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Generates the data of a mesh of the shape, in the vertex format `V`,
+    /// to be loaded with `Loader::load_from_data`.
+    pub fn generate<V>(&self, scale: Option<[f32; 3]>) -> MeshData
+    where
+        V: From<ShapeVertex>,
+        Vec<V>: Into<MeshData>,
+    {
+        self.generate_vertices::<V>(scale).into()
+    }
+
+    /// Generates the data of a mesh of the shape, with separate position,
+    /// texture coordinate, normal and tangent buffers.
+    pub fn generate_separate(&self, scale: Option<[f32; 3]>) -> MeshData {
+        let vertices = self.generate_vertices::<ShapeVertex>(scale);
+        let positions = vertices
+            .iter()
+            .map(|v| Separate::<Position>::new(v.position))
+            .collect();
+        let tex_coords = vertices
+            .iter()
+            .map(|v| Separate::<TexCoord>::new(v.tex_coord))
+            .collect();
+        let normals = vertices
+            .iter()
+            .map(|v| Separate::<Normal>::new(v.normal))
+            .collect();
+        let tangents = vertices
+            .iter()
+            .map(|v| Separate::<Tangent>::new(v.tangent))
+            .collect();
+        ComboMeshCreator::new((
+            positions,
+            None,
+            Some(tex_coords),
+            Some(normals),
+            Some(tangents),
+        )).into()
+    }
+
+    /// Returns the shape with its segment, ring and cell counts raised to
+    /// their documented minimum.
+    fn clamped(&self) -> Shape {
+        match *self {
+            Shape::Sphere { segments, rings } => Shape::Sphere {
+                segments: segments.max(3),
+                rings: rings.max(2),
+            },
+            Shape::Cube => Shape::Cube,
+            Shape::Plane { columns, rows } => Shape::Plane {
+                columns: columns.max(1),
+                rows: rows.max(1),
+            },
+            Shape::Cylinder { segments } => Shape::Cylinder {
+                segments: segments.max(3),
+            },
+            Shape::Capsule {
+                segments,
+                rings,
+                half_length,
+            } => Shape::Capsule {
+                segments: segments.max(3),
+                rings: rings.max(1),
+                half_length: half_length.max(0.0),
+            },
+            Shape::Cone { segments } => Shape::Cone {
+                segments: segments.max(3),
+            },
+        }
+    }
+
+    fn vertices(&self) -> Vec<ShapeVertex> {
+        let mut vertices = Vec::new();
+        match self.clamped() {
+            Shape::Sphere { segments, rings } => {
+                sphere_band(&mut vertices, segments, rings, -1.0, 1.0, 0.0, (0.0, 1.0));
+            }
+            Shape::Cube => for &(normal, tangent) in &CUBE_FACES {
+                face(&mut vertices, 1, 1, normal, tangent);
+            },
+            Shape::Plane { columns, rows } => {
+                face(&mut vertices, columns, rows, [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]);
+                for vertex in &mut vertices {
+                    vertex.position[1] = 0.0;
+                }
+            }
+            Shape::Cylinder { segments } => {
+                grid(&mut vertices, segments, 1, |u, v| {
+                    let (sin, cos) = (u * 2.0 * PI).sin_cos();
+                    ShapeVertex {
+                        position: [sin, v * 2.0 - 1.0, cos],
+                        normal: [sin, 0.0, cos],
+                        tangent: [cos, 0.0, -sin],
+                        tex_coord: [u, v],
+                    }
+                });
+                disc(&mut vertices, segments, 1.0);
+                disc(&mut vertices, segments, -1.0);
+            }
+            Shape::Capsule {
+                segments,
+                rings,
+                half_length,
+            } => {
+                // The texture is stretched along the whole length of the
+                // capsule.
+                let cap = PI / 2.0 / (PI + 2.0 * half_length);
+                let bottom = (0.0, cap);
+                let top = (1.0 - cap, 1.0);
+                sphere_band(&mut vertices, segments, rings, -1.0, 0.0, -half_length, bottom);
+                grid(&mut vertices, segments, 1, |u, v| {
+                    let (sin, cos) = (u * 2.0 * PI).sin_cos();
+                    ShapeVertex {
+                        position: [sin, (v * 2.0 - 1.0) * half_length, cos],
+                        normal: [sin, 0.0, cos],
+                        tangent: [cos, 0.0, -sin],
+                        tex_coord: [u, cap + v * (1.0 - 2.0 * cap)],
+                    }
+                });
+                sphere_band(&mut vertices, segments, rings, 0.0, 1.0, half_length, top);
+            }
+            Shape::Cone { segments } => {
+                grid(&mut vertices, segments, 1, |u, v| {
+                    let (sin, cos) = (u * 2.0 * PI).sin_cos();
+                    let normal = Vector3::new(sin * 2.0, 1.0, cos * 2.0).normalize();
+                    ShapeVertex {
+                        position: [sin * (1.0 - v), v * 2.0 - 1.0, cos * (1.0 - v)],
+                        normal: normal.into(),
+                        tangent: [cos, 0.0, -sin],
+                        tex_coord: [u, v],
+                    }
+                });
+                disc(&mut vertices, segments, -1.0);
+            }
+        }
+        vertices
+    }
+}
+
+/// Appends the triangles of a grid of `columns` by `rows` cells, with the
+/// vertex at each corner given for u and v from 0 to 1. The triangles face
+/// the direction of the cross product of the u and v directions.
+fn grid<F>(vertices: &mut Vec<ShapeVertex>, columns: usize, rows: usize, vertex: F)
+where
+    F: Fn(f32, f32) -> ShapeVertex,
+{
+    assert!(columns > 0 && rows > 0, "A grid needs at least one cell");
+    let corner = |column: usize, row: usize| {
+        vertex(column as f32 / columns as f32, row as f32 / rows as f32)
+    };
+    for row in 0..rows {
+        for column in 0..columns {
+            let a = corner(column, row);
+            let b = corner(column + 1, row);
+            let c = corner(column + 1, row + 1);
+            let d = corner(column, row + 1);
+            vertices.extend_from_slice(&[a, b, c, a, c, d]);
+        }
+    }
+}
+
+/// Appends a face of the cube from -1 to 1, subdivided into a grid.
+fn face(
+    vertices: &mut Vec<ShapeVertex>,
+    columns: usize,
+    rows: usize,
+    normal: [f32; 3],
+    tangent: [f32; 3],
+) {
+    let normal = Vector3::from(normal);
+    let tangent = Vector3::from(tangent);
+    let bitangent = normal.cross(tangent);
+    grid(vertices, columns, rows, |u, v| {
+        let position = normal + tangent * (u * 2.0 - 1.0) + bitangent * (v * 2.0 - 1.0);
+        ShapeVertex {
+            position: position.into(),
+            normal: normal.into(),
+            tangent: tangent.into(),
+            tex_coord: [u, v],
+        }
+    });
+}
+
+/// Appends the part of a sphere of radius 1 between the heights `from` and
+/// `to`, before moving it by `offset` along the y axis, with its v texture
+/// coordinate from the first to the second value of `tex_v`.
+fn sphere_band(
+    vertices: &mut Vec<ShapeVertex>,
+    segments: usize,
+    rings: usize,
+    from: f32,
+    to: f32,
+    offset: f32,
+    tex_v: (f32, f32),
+) {
+    let (from, to) = (from.asin(), to.asin());
+    grid(vertices, segments, rings, |u, v| {
+        let (sin, cos) = (u * 2.0 * PI).sin_cos();
+        let (height, radius) = (from + (to - from) * v).sin_cos();
+        let normal = [sin * radius, height, cos * radius];
+        ShapeVertex {
+            position: [normal[0], normal[1] + offset, normal[2]],
+            normal,
+            tangent: [cos, 0.0, -sin],
+            tex_coord: [u, tex_v.0 + (tex_v.1 - tex_v.0) * v],
+        }
+    });
+}
+
+/// Appends a disc of radius 1 at the given height, facing up at 1 and down
+/// at -1.
+fn disc(vertices: &mut Vec<ShapeVertex>, segments: usize, height: f32) {
+    let vertex = |x: f32, z: f32| ShapeVertex {
+        position: [x, height, z],
+        normal: [0.0, height, 0.0],
+        tangent: [1.0, 0.0, 0.0],
+        tex_coord: [(x + 1.0) / 2.0, (1.0 - z * height) / 2.0],
+    };
+    let edge = |segment: usize| {
+        let (sin, cos) = (segment as f32 / segments as f32 * 2.0 * PI).sin_cos();
+        vertex(sin, cos)
+    };
+    let center = vertex(0.0, 0.0);
+    for segment in 0..segments {
+        let (a, b) = (edge(segment), edge(segment + 1));
+        if height > 0.0 {
+            vertices.extend_from_slice(&[center, a, b]);
+        } else {
+            vertices.extend_from_slice(&[center, b, a]);
+        }
+    }
+}
+
+/// Scales a vertex, the normal and tangent following the scaled surface.
+fn scale_vertex(vertex: ShapeVertex, scale: [f32; 3]) -> ShapeVertex {
+    let scale = Vector3::from(scale);
+    let normal = Vector3::from(vertex.normal).div_element_wise(scale);
+    let tangent = Vector3::from(vertex.tangent).mul_element_wise(scale);
+    ShapeVertex {
+        position: Vector3::from(vertex.position)
+            .mul_element_wise(scale)
+            .into(),
+        normal: normalize_or(normal, vertex.normal),
+        tangent: normalize_or(tangent, vertex.tangent),
+        tex_coord: vertex.tex_coord,
+    }
+}
+
+/// Normalizes a vector, keeping the fallback when it has no direction.
+fn normalize_or(vector: Vector3<f32>, fallback: [f32; 3]) -> [f32; 3] {
+    if vector.magnitude2() > 0.0 && vector.magnitude2().is_finite() {
+        vector.normalize().into()
+    } else {
+        fallback
+    }
+}
+
+impl From<ShapeVertex> for PosColor {
+    fn from(vertex: ShapeVertex) -> Self {
+        PosColor {
+            position: vertex.position,
+            color: [1.0; 4],
+        }
+    }
+}
+
+impl From<ShapeVertex> for PosTex {
+    fn from(vertex: ShapeVertex) -> Self {
+        PosTex {
+            position: vertex.position,
+            tex_coord: vertex.tex_coord,
+        }
+    }
+}
+
+impl From<ShapeVertex> for PosColorTex {
+    fn from(vertex: ShapeVertex) -> Self {
+        PosColorTex {
+            position: vertex.position,
+            color: [1.0; 4],
+            tex_coord: vertex.tex_coord,
+        }
+    }
+}
+
+impl From<ShapeVertex> for PosNormTex {
+    fn from(vertex: ShapeVertex) -> Self {
+        PosNormTex {
+            position: vertex.position,
+            normal: vertex.normal,
+            tex_coord: vertex.tex_coord,
+        }
+    }
+}
+
+impl From<ShapeVertex> for PosNormTangTex {
+    fn from(vertex: ShapeVertex) -> Self {
+        PosNormTangTex {
+            position: vertex.position,
+            normal: vertex.normal,
+            tangent: vertex.tangent,
+            tex_coord: vertex.tex_coord,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Shape, ShapeVertex};
+    use amethyst_core::cgmath::{InnerSpace, Vector3};
+
+    const SHAPES: [Shape; 6] = [
+        Shape::Sphere {
+            segments: 8,
+            rings: 4,
+        },
+        Shape::Cube,
+        Shape::Plane {
+            columns: 3,
+            rows: 2,
+        },
+        Shape::Cylinder { segments: 8 },
+        Shape::Capsule {
+            segments: 8,
+            rings: 3,
+            half_length: 0.5,
+        },
+        Shape::Cone { segments: 8 },
+    ];
+
+    fn vertex_count(shape: Shape) -> usize {
+        shape.generate_vertices::<ShapeVertex>(None).len()
+    }
+
+    #[test]
+    fn vertex_counts() {
+        let counts: Vec<_> = SHAPES.iter().map(|&shape| vertex_count(shape)).collect();
+        assert_eq!(
+            counts,
+            vec![
+                8 * 4 * 6,
+                6 * 6,
+                3 * 2 * 6,
+                8 * 6 + 2 * 8 * 3,
+                2 * 8 * 3 * 6 + 8 * 6,
+                8 * 6 + 8 * 3,
+            ]
+        );
+    }
+
+    #[test]
+    fn minimum_counts() {
+        let sphere = Shape::Sphere {
+            segments: 0,
+            rings: 0,
+        };
+        assert_eq!(vertex_count(sphere), 3 * 2 * 6);
+        let plane = Shape::Plane {
+            columns: 0,
+            rows: 0,
+        };
+        assert_eq!(vertex_count(plane), 6);
+        assert_eq!(vertex_count(Shape::Cylinder { segments: 1 }), 3 * 12);
+        let capsule = Shape::Capsule {
+            segments: 0,
+            rings: 0,
+            half_length: -1.0,
+        };
+        assert_eq!(vertex_count(capsule), 3 * 6 * 3);
+        assert_eq!(vertex_count(Shape::Cone { segments: 2 }), 3 * 9);
+    }
+
+    fn assert_outward(shape: Shape, scale: Option<[f32; 3]>) {
+        let vertices = shape.generate_vertices::<ShapeVertex>(scale);
+        for vertex in &vertices {
+            let normal = Vector3::from(vertex.normal);
+            assert!(
+                (normal.magnitude() - 1.0).abs() < 1e-5,
+                "{:?}: normal {:?} is not unit length",
+                shape,
+                vertex.normal
+            );
+            // The shapes are convex around the origin, except for the plane
+            // which goes through it.
+            if let Shape::Plane { .. } = shape {
+                assert_eq!(vertex.normal, [0.0, 1.0, 0.0]);
+            } else {
+                assert!(
+                    normal.dot(Vector3::from(vertex.position)) > 0.0,
+                    "{:?}: normal {:?} points inwards at {:?}",
+                    shape,
+                    vertex.normal,
+                    vertex.position
+                );
+            }
+        }
+        for triangle in vertices.chunks(3) {
+            let a = Vector3::from(triangle[0].position);
+            let b = Vector3::from(triangle[1].position);
+            let c = Vector3::from(triangle[2].position);
+            let face = (b - a).cross(c - a);
+            for vertex in triangle {
+                assert!(
+                    face.dot(Vector3::from(vertex.normal)) >= -1e-6,
+                    "{:?}: triangle {:?} is wound inwards",
+                    shape,
+                    triangle
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn outward_unit_normals() {
+        for &shape in &SHAPES {
+            assert_outward(shape, None);
+        }
+    }
+
+    #[test]
+    fn scaled_outward_unit_normals() {
+        for &shape in &SHAPES {
+            assert_outward(shape, Some([2.0, 0.5, 3.0]));
+        }
+    }
+}
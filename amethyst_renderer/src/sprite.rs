@@ -0,0 +1,114 @@
+//! 2D sprite components.
+
+use amethyst_assets::{Asset, Handle, Result};
+use specs::{Component, DenseVecStorage, VecStorage};
+
+use tex::TextureHandle;
+
+/// A handle to a `SpriteSheet` asset.
+pub type SpriteSheetHandle = Handle<SpriteSheet>;
+
+/// Region of a texture, in normalized texture coordinates.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TextureCoordinates {
+    /// Left edge of the region.
+    pub left: f32,
+    /// Right edge of the region.
+    pub right: f32,
+    /// Bottom edge of the region.
+    pub bottom: f32,
+    /// Top edge of the region.
+    pub top: f32,
+}
+
+impl Default for TextureCoordinates {
+    fn default() -> Self {
+        TextureCoordinates {
+            left: 0.0,
+            right: 1.0,
+            bottom: 0.0,
+            top: 1.0,
+        }
+    }
+}
+
+/// A textured quad cut out of a larger texture.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Sprite {
+    /// Width of the quad in world units.
+    pub width: f32,
+    /// Height of the quad in world units.
+    pub height: f32,
+    /// Position of the pivot, relative to the bottom left corner of the quad
+    /// in world units. The pivot is placed at the origin of the entity.
+    pub offsets: [f32; 2],
+    /// Region of the texture drawn on the quad.
+    pub tex_coords: TextureCoordinates,
+}
+
+/// Draws a `Sprite` at the position of its entity, with the `DrawSprite` pass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteRender {
+    /// Texture the sprite is cut out of.
+    pub texture: TextureHandle,
+    /// The sprite to draw.
+    pub sprite: Sprite,
+    /// Mirrors the sprite along its vertical axis.
+    pub flip_horizontal: bool,
+    /// Mirrors the sprite along its horizontal axis.
+    pub flip_vertical: bool,
+}
+
+impl SpriteRender {
+    /// Creates a new `SpriteRender` which isn't flipped.
+    pub fn new(texture: TextureHandle, sprite: Sprite) -> Self {
+        SpriteRender {
+            texture,
+            sprite,
+            flip_horizontal: false,
+            flip_vertical: false,
+        }
+    }
+}
+
+impl Component for SpriteRender {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A set of sprites cut out of a single texture.
+///
+/// Loaded with `SpriteSheetFormat` or `TexturePackerFormat`, the texture
+/// handle is passed as the format options.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteSheet {
+    /// Texture the sprites are cut out of.
+    pub texture: TextureHandle,
+    /// The sprites of the sheet, in the order they are defined in the file.
+    pub sprites: Vec<Sprite>,
+}
+
+impl SpriteSheet {
+    /// Creates a `SpriteRender` drawing the sprite with the given index,
+    /// or `None` if the sheet has no such sprite.
+    pub fn sprite_render(&self, index: usize) -> Option<SpriteRender> {
+        self.sprites
+            .get(index)
+            .map(|sprite| SpriteRender::new(self.texture.clone(), sprite.clone()))
+    }
+}
+
+impl Asset for SpriteSheet {
+    const NAME: &'static str = "renderer::SpriteSheet";
+    type Data = SpriteSheetData;
+    type HandleStorage = VecStorage<SpriteSheetHandle>;
+}
+
+/// Sprite sheet data, as produced by the sprite sheet formats.
+#[derive(Clone, Debug)]
+pub struct SpriteSheetData(pub SpriteSheet);
+
+impl Into<Result<SpriteSheet>> for SpriteSheetData {
+    fn into(self) -> Result<SpriteSheet> {
+        Ok(self.0)
+    }
+}
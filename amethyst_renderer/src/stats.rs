@@ -0,0 +1,231 @@
+//! GPU timing of the render passes.
+
+use std::time::Duration;
+
+use types::{Device, Encoder};
+
+/// Time spent by the GPU on each pass of the pipeline.
+///
+/// Only updated while GPU timing is enabled, with
+/// `RenderBundle::with_gpu_timing` or `Renderer::set_gpu_timing`. The GPU
+/// runs behind the CPU, so the timings are those of the latest frame whose
+/// results came back, usually one or two frames ago.
+///
+/// Timing a pass requires submitting the commands recorded so far before and
+/// after it, which makes the frames themselves somewhat slower.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    /// Timings of the passes, in the order they were applied.
+    pub passes: Vec<PassStats>,
+}
+
+impl RenderStats {
+    /// Returns the time spent by the GPU on all the passes.
+    pub fn total(&self) -> Duration {
+        self.passes
+            .iter()
+            .fold(Duration::new(0, 0), |total, pass| total + pass.gpu_time)
+    }
+}
+
+/// Time spent by the GPU on a single pass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassStats {
+    /// Index of the stage in the pipeline. The tonemapping pass of HDR
+    /// pipelines comes after the last stage.
+    pub stage: usize,
+    /// Index of the pass in its stage.
+    pub pass: usize,
+    /// Name of the target the stage draws into, empty for the backbuffer.
+    pub target: String,
+    /// Time spent by the GPU drawing the pass.
+    pub gpu_time: Duration,
+}
+
+/// Times the passes while a frame is drawn, handed down the pipeline by the
+/// `Renderer`.
+pub struct GpuTimer<'a> {
+    device: &'a mut Device,
+    queries: Option<&'a mut TimerQueries>,
+}
+
+impl<'a> GpuTimer<'a> {
+    pub(crate) fn new(device: &'a mut Device, queries: Option<&'a mut TimerQueries>) -> Self {
+        GpuTimer { device, queries }
+    }
+
+    /// Starts numbering the passes of a new stage.
+    pub(crate) fn start_stage(&mut self, target: &str) {
+        if let Some(ref mut queries) = self.queries {
+            queries.stage = queries.stage.map(|stage| stage + 1).or(Some(0));
+            queries.pass = 0;
+            queries.target.clear();
+            queries.target.push_str(target);
+        }
+    }
+
+    /// Starts timing a pass, after submitting the commands of the previous
+    /// ones.
+    pub(crate) fn begin(&mut self, encoder: &mut Encoder) {
+        let queries = match self.queries {
+            Some(ref mut queries) => queries,
+            None => return,
+        };
+        encoder.flush(&mut *self.device);
+        let query = queries.next_query(self.device);
+        begin_query(self.device, query);
+    }
+
+    /// Stops timing the current pass, after submitting its commands.
+    pub(crate) fn end(&mut self, encoder: &mut Encoder) {
+        let queries = match self.queries {
+            Some(ref mut queries) => queries,
+            None => return,
+        };
+        encoder.flush(&mut *self.device);
+        end_query(self.device);
+        queries.pass += 1;
+    }
+}
+
+/// Timer queries of the passes, reused from frame to frame.
+#[derive(Debug, Default)]
+pub(crate) struct TimerQueries {
+    queries: Vec<(u32, PassStats)>,
+    used: usize,
+    stage: Option<usize>,
+    pass: usize,
+    target: String,
+    stats: RenderStats,
+}
+
+impl TimerQueries {
+    /// Returns the timings of the latest frame whose results came back.
+    pub fn stats(&self) -> &RenderStats {
+        &self.stats
+    }
+
+    /// Reads the results of the previous frame if they are all available,
+    /// and gets ready to time a new one.
+    pub fn start_frame(&mut self, device: &mut Device) {
+        let used = &self.queries[..self.used];
+        if !used.is_empty() && used.iter().all(|&(query, _)| is_available(device, query)) {
+            self.stats.passes = used
+                .iter()
+                .map(|&(query, ref label)| PassStats {
+                    gpu_time: elapsed(device, query),
+                    ..label.clone()
+                })
+                .collect();
+        }
+        self.used = 0;
+        self.stage = None;
+        self.pass = 0;
+    }
+
+    /// Releases the queries.
+    pub fn delete(&mut self, device: &mut Device) {
+        let ids = self.queries.iter().map(|&(query, _)| query).collect::<Vec<_>>();
+        delete_queries(device, &ids);
+        self.queries.clear();
+        self.used = 0;
+    }
+
+    fn next_query(&mut self, device: &mut Device) -> u32 {
+        let label = PassStats {
+            stage: self.stage.unwrap_or(0),
+            pass: self.pass,
+            target: self.target.clone(),
+            gpu_time: Duration::new(0, 0),
+        };
+        if self.used == self.queries.len() {
+            self.queries.push((create_query(device), label));
+        } else {
+            self.queries[self.used].1 = label;
+        }
+        self.used += 1;
+        self.queries[self.used - 1].0
+    }
+}
+
+#[cfg(all(feature = "opengl", not(feature = "gles")))]
+fn create_query(device: &mut Device) -> u32 {
+    let mut query = 0;
+    unsafe {
+        device.with_gl(|gl| gl.GenQueries(1, &mut query));
+    }
+    query
+}
+
+#[cfg(all(feature = "opengl", not(feature = "gles")))]
+fn delete_queries(device: &mut Device, queries: &[u32]) {
+    if queries.is_empty() {
+        return;
+    }
+    unsafe {
+        device.with_gl(|gl| gl.DeleteQueries(queries.len() as i32, queries.as_ptr()));
+    }
+}
+
+#[cfg(all(feature = "opengl", not(feature = "gles")))]
+fn begin_query(device: &mut Device, query: u32) {
+    use gfx_gl::TIME_ELAPSED;
+    unsafe {
+        device.with_gl(|gl| gl.BeginQuery(TIME_ELAPSED, query));
+    }
+}
+
+#[cfg(all(feature = "opengl", not(feature = "gles")))]
+fn end_query(device: &mut Device) {
+    use gfx_gl::TIME_ELAPSED;
+    unsafe {
+        device.with_gl(|gl| gl.EndQuery(TIME_ELAPSED));
+    }
+}
+
+#[cfg(all(feature = "opengl", not(feature = "gles")))]
+fn is_available(device: &mut Device, query: u32) -> bool {
+    use gfx_gl::QUERY_RESULT_AVAILABLE;
+    let mut available = 0;
+    unsafe {
+        device.with_gl(|gl| gl.GetQueryObjectiv(query, QUERY_RESULT_AVAILABLE, &mut available));
+    }
+    available != 0
+}
+
+#[cfg(all(feature = "opengl", not(feature = "gles")))]
+fn elapsed(device: &mut Device, query: u32) -> Duration {
+    use gfx_gl::QUERY_RESULT;
+    let mut nanos = 0;
+    unsafe {
+        device.with_gl(|gl| gl.GetQueryObjectui64v(query, QUERY_RESULT, &mut nanos));
+    }
+    Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+}
+
+// Timer queries are only implemented for desktop OpenGL, the other backends and
+// OpenGL ES report no timings.
+
+#[cfg(any(not(feature = "opengl"), feature = "gles"))]
+fn create_query(_: &mut Device) -> u32 {
+    0
+}
+
+#[cfg(any(not(feature = "opengl"), feature = "gles"))]
+fn delete_queries(_: &mut Device, _: &[u32]) {}
+
+#[cfg(any(not(feature = "opengl"), feature = "gles"))]
+fn begin_query(_: &mut Device, _: u32) {}
+
+#[cfg(any(not(feature = "opengl"), feature = "gles"))]
+fn end_query(_: &mut Device) {}
+
+#[cfg(any(not(feature = "opengl"), feature = "gles"))]
+fn is_available(_: &mut Device, _: u32) -> bool {
+    false
+}
+
+#[cfg(any(not(feature = "opengl"), feature = "gles"))]
+fn elapsed(_: &mut Device, _: u32) -> Duration {
+    Duration::new(0, 0)
+}
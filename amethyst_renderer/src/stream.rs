@@ -0,0 +1,218 @@
+//! Textures streamed from the CPU.
+
+use std::fmt::{Debug, Formatter};
+use std::fmt::Result as FmtResult;
+use std::sync::{Arc, Mutex, Weak};
+
+use gfx::format::{ChannelType, SurfaceType};
+use gfx::handle::Buffer;
+use gfx::texture::SamplerInfo;
+
+use error::{Error, Result};
+use tex::Texture;
+use types::{Encoder, Factory, RawTexture, Resources};
+
+/// Number of staging buffers of each stream, so that a frame can be written
+/// while the GPU still copies the previous ones.
+const STAGING_BUFFERS: usize = 3;
+
+/// Pixels streamed from the CPU into a texture, for example the frames of a
+/// video, a webcam feed or an image generated every frame.
+///
+/// The texture is an RGBA texture with 8 bits per channel, built by loading
+/// `TextureData::Stream` or by `Renderer::create_streaming_texture`. Its
+/// contents are undefined until the first frame was written. Clones share the
+/// same stream, so one clone can be kept to write the frames while another is
+/// loaded as a texture. The renderer stops streaming once all the clones are
+/// dropped, the texture keeping its last frame.
+#[derive(Clone)]
+pub struct StreamingTexture(Arc<Mutex<StreamState>>);
+
+struct StreamState {
+    width: u16,
+    height: u16,
+    /// Pixels of the latest frame, in the order they are uploaded.
+    pixels: Vec<u8>,
+    /// Whether the frame wasn't uploaded yet.
+    pending: bool,
+}
+
+impl StreamingTexture {
+    /// Creates a new stream of frames of the given size in pixels.
+    pub fn new(width: u16, height: u16) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+        StreamingTexture(Arc::new(Mutex::new(StreamState {
+            width,
+            height,
+            pixels: Vec::with_capacity(width as usize * height as usize * 4),
+            pending: false,
+        })))
+    }
+
+    /// Returns the width and height of the frames in pixels.
+    pub fn dimensions(&self) -> (u16, u16) {
+        let state = self.0.lock().unwrap();
+        (state.width, state.height)
+    }
+
+    /// Writes the next frame, as RGBA pixels of 4 bytes starting with the top
+    /// row, uploaded before the next frame is drawn.
+    ///
+    /// A frame written before the previous one was uploaded replaces it, so
+    /// frames coming faster than the renderer draws are dropped instead of
+    /// piling up.
+    pub fn write(&self, pixels: &[u8]) -> Result<()> {
+        let mut state = self.0.lock().unwrap();
+        let row = state.width as usize * 4;
+        let size = row * state.height as usize;
+        if pixels.len() != size {
+            return Err(Error::TextureUpdate(format!(
+                "Expected {} bytes for a {}x{} frame, got {}",
+                size,
+                state.width,
+                state.height,
+                pixels.len()
+            )));
+        }
+        state.pixels.clear();
+        // OpenGL expects the bottom row first, like the data of `TextureBuilder`.
+        if cfg!(feature = "opengl") {
+            for row in pixels.chunks(row).rev() {
+                state.pixels.extend_from_slice(row);
+            }
+        } else {
+            state.pixels.extend_from_slice(pixels);
+        }
+        state.pending = true;
+        Ok(())
+    }
+}
+
+impl Debug for StreamingTexture {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        let (width, height) = self.dimensions();
+        fmt.debug_struct("StreamingTexture")
+            .field("width", &width)
+            .field("height", &height)
+            .finish()
+    }
+}
+
+/// The streams of the textures built by a `Renderer`.
+#[derive(Default)]
+pub(crate) struct TextureStreams {
+    streams: Vec<Stream>,
+}
+
+/// A stream along with its texture and the ring of staging buffers its frames
+/// go through.
+struct Stream {
+    state: Weak<Mutex<StreamState>>,
+    texture: RawTexture,
+    channel: ChannelType,
+    staging: Vec<Buffer<Resources, u8>>,
+    next: usize,
+}
+
+impl TextureStreams {
+    /// Builds the texture the frames of the stream are uploaded to.
+    pub(crate) fn create(
+        &mut self,
+        factory: &mut Factory,
+        stream: &StreamingTexture,
+        sampler: SamplerInfo,
+        channel: ChannelType,
+    ) -> Result<Texture> {
+        use gfx::Factory;
+        use gfx::format::Swizzle;
+        use gfx::memory::{Bind, Usage};
+        use gfx::texture::{AaMode, Info, Kind, ResourceDesc};
+
+        let (width, height) = stream.dimensions();
+        let info = Info {
+            kind: Kind::D2(width, height, AaMode::Single),
+            levels: 1,
+            format: SurfaceType::R8_G8_B8_A8,
+            bind: Bind::SHADER_RESOURCE | Bind::TRANSFER_DST,
+            usage: Usage::Data,
+        };
+        let texture = factory.create_texture_raw(info, Some(channel), None)?;
+        let desc = ResourceDesc {
+            channel,
+            layer: None,
+            min: 1,
+            max: info.levels,
+            swizzle: Swizzle::new(),
+        };
+        let view = factory.view_texture_as_shader_resource_raw(&texture, desc)?;
+        let sampler = factory.create_sampler(sampler);
+
+        let size = width as usize * height as usize * 4;
+        let mut staging = Vec::with_capacity(STAGING_BUFFERS);
+        for _ in 0..STAGING_BUFFERS {
+            staging.push(factory.create_upload_buffer(size)?);
+        }
+        // A frame written before the texture was built stays pending and is uploaded along with
+        // the next ones.
+        self.streams.push(Stream {
+            state: Arc::downgrade(&stream.0),
+            texture: texture.clone(),
+            channel,
+            staging,
+            next: 0,
+        });
+        Ok(Texture::from_raw(texture, view, sampler))
+    }
+
+    /// Uploads the pending frames, dropping the streams no longer written to.
+    pub(crate) fn upload(&mut self, factory: &mut Factory, encoder: &mut Encoder) {
+        self.streams.retain(|stream| stream.state.upgrade().is_some());
+        for stream in &mut self.streams {
+            if let Err(err) = stream.upload(factory, encoder) {
+                eprintln!("WARNING: Failed to stream texture: {}", err);
+            }
+        }
+    }
+}
+
+impl Stream {
+    fn upload(&mut self, factory: &mut Factory, encoder: &mut Encoder) -> Result<()> {
+        use gfx::Factory;
+        use gfx::format::Format;
+        use gfx::memory::Typed;
+        use gfx::texture::ImageInfoCommon;
+
+        let state = match self.state.upgrade() {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+        let mut state = state.lock().unwrap();
+        if !state.pending {
+            return Ok(());
+        }
+        state.pending = false;
+
+        // The buffers are used in turn, the GPU being done with the oldest one by now.
+        let buffer = &self.staging[self.next];
+        self.next = (self.next + 1) % self.staging.len();
+        {
+            let mut writer = factory
+                .write_mapping(buffer)
+                .map_err(|err| Error::TextureUpdate(format!("{:?}", err)))?;
+            writer.copy_from_slice(&state.pixels);
+        }
+        let info = ImageInfoCommon {
+            xoffset: 0,
+            yoffset: 0,
+            zoffset: 0,
+            width: state.width,
+            height: state.height,
+            depth: 0,
+            format: Format(SurfaceType::R8_G8_B8_A8, self.channel),
+            mipmap: 0,
+        };
+        encoder
+            .copy_buffer_to_texture_raw(buffer.raw(), 0, &self.texture, None, info)
+            .map_err(|err| Error::TextureUpdate(format!("{:?}", err)))
+    }
+}
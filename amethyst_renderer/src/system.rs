@@ -9,17 +9,21 @@ use amethyst_core::Time;
 use rayon::ThreadPool;
 use shred::Resources;
 use shrev::EventChannel;
-use specs::{Fetch, FetchMut, RunNow, SystemData};
+use specs::{Entities, Fetch, FetchMut, ReadStorage, RunNow, SystemData};
 use winit::{DeviceEvent, Event, WindowEvent};
 
 use config::DisplayConfig;
 use error::Result;
 use formats::{create_mesh_asset, create_texture_asset};
+use light::Light;
 use mesh::Mesh;
-use pipe::{PipelineBuild, PipelineData, PolyPipeline};
+use pipe::{PipelineBuild, PipelineData, PolyPipeline, HDR_TARGET};
 use renderer::Renderer;
-use resources::{ScreenDimensions, WindowMessages};
+use resources::{ResolutionScale, ScreenDimensions, ScreenDump, TargetTextures, WindowMessages};
+use shadow::{point_shadow_casters, PointShadowMap, PointShadowMaps, ShadowCaster, ShadowConfig};
+use stats::RenderStats;
 use tex::Texture;
+use types::Sampler;
 
 /// Rendering system.
 #[derive(Derivative)]
@@ -29,6 +33,7 @@ pub struct RenderSystem<P> {
     #[derivative(Debug = "ignore")]
     renderer: Renderer,
     cached_size: (u32, u32),
+    target_sampler: Option<Sampler>,
 }
 
 impl<P> RenderSystem<P>
@@ -62,17 +67,19 @@ where
 
     /// Create a new render system
     pub fn new(pipe: P, renderer: Renderer) -> Self {
-        let cached_size = renderer.window().get_inner_size().unwrap();
+        let cached_size = renderer.size().unwrap();
         Self {
             pipe,
             renderer,
             cached_size,
+            target_sampler: None,
         }
     }
 
-    /// Returns the size in pixels of the window.
+    /// Returns the size in pixels of the window, or of the main target when
+    /// the renderer draws offscreen.
     pub fn window_size(&self) -> Option<(u32, u32)> {
-        self.renderer.window().get_inner_size()
+        self.renderer.size()
     }
 
     fn asset_loading(
@@ -98,22 +105,84 @@ where
         );
     }
 
+    fn target_textures(&mut self, (mut texture_storage, mut target_textures): TargetTextureData) {
+        use gfx::Factory;
+        use gfx::texture::{FilterMethod, SamplerInfo, WrapMode};
+
+        let factory = &mut self.renderer.factory;
+        let sampler = self.target_sampler
+            .get_or_insert_with(|| {
+                factory.create_sampler(SamplerInfo::new(FilterMethod::Bilinear, WrapMode::Clamp))
+            })
+            .clone();
+
+        for (name, target) in self.pipe.targets() {
+            if name.is_empty() || name == HDR_TARGET {
+                continue;
+            }
+            let texture = match target
+                .color_buf(0)
+                .and_then(|buf| Texture::from_color_buffer(buf, sampler.clone()))
+            {
+                Some(texture) => texture,
+                None => continue,
+            };
+
+            // Targets are recreated when the window is resized
+            let handle = target_textures.textures.get(name).cloned();
+            match handle {
+                Some(handle) => if let Some(current) = texture_storage.get_mut(&handle) {
+                    *current = texture;
+                },
+                None => {
+                    let handle = texture_storage.insert(texture);
+                    target_textures.textures.insert(name.clone(), handle);
+                }
+            }
+        }
+    }
+
+    fn point_shadow_maps(&mut self, (entities, config, light, caster, mut maps): PointShadowData) {
+        let size = config.point_shadow_size.max(1);
+        if maps.size != size {
+            maps.maps.clear();
+            maps.size = size;
+        }
+
+        let count = point_shadow_casters(&entities, &light, &caster).len();
+        while maps.maps.len() < count {
+            match PointShadowMap::new(&mut self.renderer.factory, size) {
+                Ok(map) => maps.maps.push(map),
+                Err(err) => {
+                    eprintln!("WARNING: Failed to create point shadow map: {}", err);
+                    break;
+                }
+            }
+        }
+    }
+
     fn window_management(&mut self, (mut window_messages, mut screen_dimensions): WindowData) {
-        // Process window commands
-        for mut command in window_messages.queue.drain() {
-            command(self.renderer.window());
+        // Process window commands, a headless renderer has no window to apply
+        // them to
+        {
+            let window = self.renderer.window();
+            for mut command in window_messages.queue.drain() {
+                if let Some(window) = window {
+                    command(window);
+                }
+            }
         }
 
         // Send resource size changes to the window
         if screen_dimensions.dirty {
-            self.renderer.window().set_inner_size(
+            self.renderer.set_size(
                 screen_dimensions.width() as u32,
                 screen_dimensions.height() as u32,
             );
             screen_dimensions.dirty = false;
         }
 
-        if let Some(size) = self.renderer.window().get_inner_size() {
+        if let Some(size) = self.renderer.size() {
             // Send window size changes to the resource
             if size
                 != (
@@ -129,13 +198,50 @@ where
         }
     }
 
-    fn render(&mut self, (mut event_handler, data): RenderData<P>) {
+    fn render(
+        &mut self,
+        (mut event_handler, mut screen_dump, scale, stats, data): RenderData<P>,
+    ) {
+        self.renderer.set_gpu_timing(stats.is_some());
+        self.renderer.set_resolution_scale(scale.0);
+        if screen_dump.requested {
+            screen_dump.requested = false;
+            self.renderer.request_capture();
+        }
+
         self.renderer.draw(&mut self.pipe, data);
 
+        if let Some(mut stats) = stats {
+            if let Some(latest) = self.renderer.render_stats() {
+                *stats = latest.clone();
+            }
+        }
+
+        match self.renderer.take_capture() {
+            Some(Ok(image)) => {
+                if let Some(path) = screen_dump.path.take() {
+                    use imagefmt::{write, ColType};
+
+                    let raw = &image.raw;
+                    if let Err(err) = write(&path, raw.w, raw.h, raw.fmt, &raw.buf, ColType::Auto) {
+                        error!("Failed writing screen dump to {:?}: {}", path, err);
+                    }
+                }
+                screen_dump.image = Some(image);
+            }
+            Some(Err(err)) => {
+                screen_dump.path = None;
+                error!("Failed capturing the screen: {}", err);
+            }
+            None => {}
+        }
+
         let mut events: Vec<Event> = Vec::new();
-        self.renderer.events_mut().poll_events(|new_event| {
-            compress_events(&mut events, new_event);
-        });
+        if let Some(events_loop) = self.renderer.events_mut() {
+            events_loop.poll_events(|new_event| {
+                compress_events(&mut events, new_event);
+            });
+        }
 
         event_handler.iter_write(events);
     }
@@ -149,10 +255,23 @@ type AssetLoadingData<'a> = (
     FetchMut<'a, AssetStorage<Texture>>,
 );
 
+type TargetTextureData<'a> = (FetchMut<'a, AssetStorage<Texture>>, FetchMut<'a, TargetTextures>);
+
+type PointShadowData<'a> = (
+    Entities<'a>,
+    Fetch<'a, ShadowConfig>,
+    ReadStorage<'a, Light>,
+    ReadStorage<'a, ShadowCaster>,
+    FetchMut<'a, PointShadowMaps>,
+);
+
 type WindowData<'a> = (FetchMut<'a, WindowMessages>, FetchMut<'a, ScreenDimensions>);
 
 type RenderData<'a, P> = (
     FetchMut<'a, EventChannel<Event>>,
+    FetchMut<'a, ScreenDump>,
+    Fetch<'a, ResolutionScale>,
+    Option<FetchMut<'a, RenderStats>>,
     <P as PipelineData<'a>>::Data,
 );
 
@@ -164,6 +283,8 @@ where
         #[cfg(feature = "profiler")]
         profile_scope!("render_system");
         self.asset_loading(AssetLoadingData::fetch(res, 0));
+        self.target_textures(TargetTextureData::fetch(res, 0));
+        self.point_shadow_maps(PointShadowData::fetch(res, 0));
         self.window_management(WindowData::fetch(res, 0));
         self.render(RenderData::<P>::fetch(res, 0));
     }
@@ -0,0 +1,264 @@
+//! Heightmap terrain.
+
+use amethyst_assets::{Asset, Handle, Result};
+use amethyst_core::cgmath::{InnerSpace, Point3, Vector3};
+use specs::{Component, DenseVecStorage, VecStorage};
+
+use tex::TextureHandle;
+use vertex::PosNormTangTex;
+
+/// A handle to a `Terrain` asset.
+///
+/// Drawn by the `DrawTerrain` pass at the position of its entity, along with
+/// a `TerrainMaterial`.
+pub type TerrainHandle = Handle<Terrain>;
+
+/// Grid of heights, split into square chunks drawn with their own level of
+/// detail.
+///
+/// In the local space of the terrain, the samples lie on the xz plane
+/// starting at the origin, with the first row of the heightmap along the x
+/// axis and the rows following each other towards positive z.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Terrain {
+    width: usize,
+    depth: usize,
+    heights: Vec<f32>,
+    spacing: f32,
+    chunk_size: usize,
+    max_step: f32,
+}
+
+impl Terrain {
+    /// Creates a terrain of `width` by `depth` samples from their heights,
+    /// given row by row.
+    ///
+    /// `spacing` is the distance between two neighbouring samples and
+    /// `chunk_size` the number of grid cells on each side of a chunk, which
+    /// has to be a power of two.
+    pub fn new(
+        width: usize,
+        depth: usize,
+        heights: Vec<f32>,
+        spacing: f32,
+        chunk_size: usize,
+    ) -> Result<Terrain> {
+        if width < 2 || depth < 2 {
+            bail!("A terrain needs at least 2x2 samples, got {}x{}", width, depth);
+        }
+        if heights.len() != width * depth {
+            bail!(
+                "Terrain heights don't match its size (expected: {}, got: {})",
+                width * depth,
+                heights.len()
+            );
+        }
+        if !chunk_size.is_power_of_two() {
+            bail!("Terrain chunk size must be a power of two, got {}", chunk_size);
+        }
+        if spacing <= 0.0 {
+            bail!("Terrain spacing must be positive, got {}", spacing);
+        }
+
+        let mut max_step: f32 = 0.0;
+        for z in 0..depth {
+            for x in 0..width {
+                let h = heights[z * width + x];
+                if x + 1 < width {
+                    max_step = max_step.max((heights[z * width + x + 1] - h).abs());
+                }
+                if z + 1 < depth {
+                    max_step = max_step.max((heights[(z + 1) * width + x] - h).abs());
+                }
+            }
+        }
+
+        Ok(Terrain {
+            width,
+            depth,
+            heights,
+            spacing,
+            chunk_size,
+            max_step,
+        })
+    }
+
+    /// Returns the number of samples along the x and z axes.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.depth)
+    }
+
+    /// Returns the distance between two neighbouring samples.
+    pub fn spacing(&self) -> f32 {
+        self.spacing
+    }
+
+    /// Returns the height of the sample at the given grid position.
+    ///
+    /// Positions outside of the grid are clamped to its edges.
+    pub fn height(&self, x: usize, z: usize) -> f32 {
+        let x = x.min(self.width - 1);
+        let z = z.min(self.depth - 1);
+        self.heights[z * self.width + x]
+    }
+
+    /// Returns the interpolated height of the terrain at the given local
+    /// position, or `None` outside of it.
+    pub fn height_at(&self, x: f32, z: f32) -> Option<f32> {
+        let (gx, gz) = (x / self.spacing, z / self.spacing);
+        if gx < 0.0 || gz < 0.0 || gx > (self.width - 1) as f32 || gz > (self.depth - 1) as f32 {
+            return None;
+        }
+        let (x0, z0) = (gx.floor() as usize, gz.floor() as usize);
+        let (fx, fz) = (gx - x0 as f32, gz - z0 as f32);
+        let top = self.height(x0, z0) * (1.0 - fx) + self.height(x0 + 1, z0) * fx;
+        let bottom = self.height(x0, z0 + 1) * (1.0 - fx) + self.height(x0 + 1, z0 + 1) * fx;
+        Some(top * (1.0 - fz) + bottom * fz)
+    }
+
+    /// Returns the number of chunks along the x and z axes.
+    pub fn chunk_count(&self) -> (usize, usize) {
+        let count = |samples: usize| (samples - 1 + self.chunk_size - 1) / self.chunk_size;
+        (count(self.width), count(self.depth))
+    }
+
+    /// Returns the number of levels of detail of the chunks.
+    ///
+    /// Each level skips every other sample of the previous one, the last
+    /// level draws a chunk with two triangles.
+    pub fn lod_count(&self) -> u8 {
+        self.chunk_size.trailing_zeros() as u8 + 1
+    }
+
+    /// Returns the center of a chunk in the local space of the terrain.
+    pub fn chunk_center(&self, chunk_x: usize, chunk_z: usize) -> Point3<f32> {
+        let ((x0, x1), (z0, z1)) = self.chunk_bounds(chunk_x, chunk_z);
+        let mut height = 0.0;
+        for &(x, z) in &[(x0, z0), (x1, z0), (x0, z1), (x1, z1)] {
+            height += self.height(x, z) / 4.0;
+        }
+        Point3::new(
+            (x0 + x1) as f32 * 0.5 * self.spacing,
+            height,
+            (z0 + z1) as f32 * 0.5 * self.spacing,
+        )
+    }
+
+    /// Builds the triangles of a chunk at the given level of detail.
+    ///
+    /// Texture coordinates span the whole terrain, with the first row of the
+    /// heightmap at the top of the texture. Skirts hanging below the edges
+    /// hide the cracks between chunks drawn at different levels.
+    pub fn chunk_vertices(&self, chunk_x: usize, chunk_z: usize, lod: u8) -> Vec<PosNormTangTex> {
+        let ((x0, x1), (z0, z1)) = self.chunk_bounds(chunk_x, chunk_z);
+        let step = 1 << lod.min(self.lod_count() - 1);
+        let xs = grid_lines(x0, x1, step);
+        let zs = grid_lines(z0, z1, step);
+
+        let mut vertices = Vec::with_capacity(xs.len() * zs.len() * 6);
+        for zw in zs.windows(2) {
+            for xw in xs.windows(2) {
+                let a = self.vertex(xw[0], zw[0]);
+                let b = self.vertex(xw[1], zw[0]);
+                let c = self.vertex(xw[0], zw[1]);
+                let d = self.vertex(xw[1], zw[1]);
+                vertices.extend_from_slice(&[a, c, b, b, c, d]);
+            }
+        }
+
+        let skirt = self.max_step * step as f32;
+        let edges = [
+            xs.iter().map(|&x| (x, z0)).collect::<Vec<_>>(),
+            xs.iter().map(|&x| (x, z1)).collect::<Vec<_>>(),
+            zs.iter().map(|&z| (x0, z)).collect::<Vec<_>>(),
+            zs.iter().map(|&z| (x1, z)).collect::<Vec<_>>(),
+        ];
+        for edge in edges.iter() {
+            for pair in edge.windows(2) {
+                let top0 = self.vertex(pair[0].0, pair[0].1);
+                let top1 = self.vertex(pair[1].0, pair[1].1);
+                let mut bottom0 = top0;
+                let mut bottom1 = top1;
+                bottom0.position[1] -= skirt;
+                bottom1.position[1] -= skirt;
+                // Both sides are drawn, the skirt can be seen from either.
+                vertices.extend_from_slice(&[
+                    top0, bottom0, top1, top1, bottom0, bottom1, top0, top1, bottom0, top1,
+                    bottom1, bottom0,
+                ]);
+            }
+        }
+        vertices
+    }
+
+    fn chunk_bounds(&self, chunk_x: usize, chunk_z: usize) -> ((usize, usize), (usize, usize)) {
+        let bounds = |chunk: usize, samples: usize| {
+            let start = (chunk * self.chunk_size).min(samples - 1);
+            (start, (start + self.chunk_size).min(samples - 1))
+        };
+        (bounds(chunk_x, self.width), bounds(chunk_z, self.depth))
+    }
+
+    fn vertex(&self, x: usize, z: usize) -> PosNormTangTex {
+        let h = self.height(x, z);
+        let left = self.height(x.saturating_sub(1), z);
+        let right = self.height(x + 1, z);
+        let back = self.height(x, z.saturating_sub(1));
+        let front = self.height(x, z + 1);
+        let normal = Vector3::new(left - right, 2.0 * self.spacing, back - front).normalize();
+        let tangent = Vector3::new(2.0 * self.spacing, right - left, 0.0).normalize();
+        PosNormTangTex {
+            position: [x as f32 * self.spacing, h, z as f32 * self.spacing],
+            normal: normal.into(),
+            tangent: tangent.into(),
+            tex_coord: [
+                x as f32 / (self.width - 1) as f32,
+                1.0 - z as f32 / (self.depth - 1) as f32,
+            ],
+        }
+    }
+}
+
+/// Returns the sample positions from `start` to `end` every `step` samples,
+/// always including `end`.
+fn grid_lines(start: usize, end: usize, step: usize) -> Vec<usize> {
+    let mut lines = (start..end).filter(|i| (i - start) % step == 0).collect::<Vec<_>>();
+    lines.push(end);
+    lines
+}
+
+impl Asset for Terrain {
+    const NAME: &'static str = "renderer::Terrain";
+    type Data = TerrainData;
+    type HandleStorage = VecStorage<TerrainHandle>;
+}
+
+/// Terrain data, as produced by `TerrainFormat`.
+#[derive(Clone, Debug)]
+pub struct TerrainData(pub Terrain);
+
+impl Into<Result<Terrain>> for TerrainData {
+    fn into(self) -> Result<Terrain> {
+        Ok(self.0)
+    }
+}
+
+/// Textures blended over a terrain by the `DrawTerrain` pass.
+///
+/// The red, green, blue and alpha channels of the splat map weight the
+/// first, second, third and fourth layers. The splat map covers the whole
+/// terrain while the layers are repeated `tiling` times over it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TerrainMaterial {
+    /// Weights of the layers over the terrain.
+    pub splat_map: TextureHandle,
+    /// Up to four textures blended together, the channels of the splat map
+    /// without a layer are ignored.
+    pub layers: Vec<TextureHandle>,
+    /// Number of times the layers are repeated along each side of the terrain.
+    pub tiling: f32,
+}
+
+impl Component for TerrainMaterial {
+    type Storage = DenseVecStorage<Self>;
+}
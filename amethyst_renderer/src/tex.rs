@@ -13,17 +13,48 @@ use gfx::texture::{Info, Mipmap, SamplerInfo};
 use gfx::traits::Pod;
 
 use formats::TextureData;
-use types::{ChannelFormat, Factory, RawShaderResourceView, RawTexture, Sampler, SurfaceFormat};
+use pipe::ColorBuffer;
+use types::{Factory, RawShaderResourceView, RawTexture, Sampler, SurfaceFormat};
 
 /// A handle to a `Texture` asset.
 pub type TextureHandle = Handle<Texture>;
 
+/// Rectangle of a texture in pixels, with the origin at the top left corner
+/// of the image.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct TextureRegion {
+    /// Left edge of the region.
+    pub x: u32,
+    /// Top edge of the region.
+    pub y: u32,
+    /// Width of the region.
+    pub width: u32,
+    /// Height of the region.
+    pub height: u32,
+}
+
+impl TextureRegion {
+    /// Creates a new `TextureRegion`.
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        TextureRegion {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
 /// Handle to a GPU texture resource.
+///
+/// A texture may only cover a region of its GPU texture, see
+/// `Texture::sub_texture`.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Texture {
     sampler: Sampler,
     texture: RawTexture,
     view: RawShaderResourceView,
+    region: Option<TextureRegion>,
 }
 
 impl Texture {
@@ -37,6 +68,85 @@ impl Texture {
         TextureBuilder::from_color_val(rgba)
     }
 
+    /// Creates a texture sampling the given render target color buffer,
+    /// returns `None` if the buffer can't be used as shader input.
+    pub(crate) fn from_color_buffer(buf: &ColorBuffer, sampler: Sampler) -> Option<Texture> {
+        use gfx::memory::Typed;
+
+        buf.as_input.as_ref().map(|input| Texture {
+            sampler,
+            texture: buf.as_output.raw().get_texture().clone(),
+            view: input.raw().clone(),
+            region: None,
+        })
+    }
+
+    /// Wraps a texture created by the renderer, along with its shader view.
+    pub(crate) fn from_raw(
+        texture: RawTexture,
+        view: RawShaderResourceView,
+        sampler: Sampler,
+    ) -> Texture {
+        Texture {
+            sampler,
+            texture,
+            view,
+            region: None,
+        }
+    }
+
+    /// Creates a texture covering a region of this one, sharing the same GPU
+    /// texture and sampler.
+    ///
+    /// The region is relative to the region of this texture, if any. Meshes
+    /// and sprites drawn with the sub texture have their texture coordinates
+    /// mapped to the region, so several sub textures can be drawn without
+    /// binding another texture.
+    pub fn sub_texture(&self, region: TextureRegion) -> Texture {
+        let region = match self.region {
+            Some(parent) => TextureRegion {
+                x: parent.x + region.x,
+                y: parent.y + region.y,
+                ..region
+            },
+            None => region,
+        };
+        Texture {
+            region: Some(region),
+            ..self.clone()
+        }
+    }
+
+    /// Returns `true` if this is a cubemap, as needed by skyboxes and
+    /// environment maps.
+    pub fn is_cube(&self) -> bool {
+        self.texture.get_info().kind.is_cube()
+    }
+
+    /// Returns the region of the GPU texture covered by this texture, `None`
+    /// if it covers all of it.
+    pub fn region(&self) -> Option<TextureRegion> {
+        self.region
+    }
+
+    /// Returns the offset and scale mapping texture coordinates to the region
+    /// of the texture, as `[offset_u, offset_v, scale_u, scale_v]`.
+    pub fn uv_transform(&self) -> [f32; 4] {
+        let region = match self.region {
+            Some(region) => region,
+            None => return [0.0, 0.0, 1.0, 1.0],
+        };
+        let (w, h, _, _) = self.texture.get_info().kind.get_dimensions();
+        let (w, h) = (w.max(1) as f32, h.max(1) as f32);
+        // Images are flipped when uploaded, so v starts at the bottom.
+        [
+            region.x as f32 / w,
+            1.0 - (region.y + region.height) as f32 / h,
+            region.width as f32 / w,
+            region.height as f32 / h,
+        ]
+    }
+
     /// Returns the sampler for the texture.
     pub fn sampler(&self) -> &Sampler {
         &self.sampler
@@ -46,6 +156,11 @@ impl Texture {
     pub fn view(&self) -> &RawShaderResourceView {
         &self.view
     }
+
+    /// Returns the raw GPU texture.
+    pub(crate) fn raw(&self) -> &RawTexture {
+        &self.texture
+    }
 }
 
 impl Asset for Texture {
@@ -61,6 +176,8 @@ pub struct TextureBuilder<D, T> {
     info: Info,
     channel_type: ChannelType,
     sampler: SamplerInfo,
+    mip_data: bool,
+    generate_mipmaps: bool,
     pd: PhantomData<T>,
 }
 
@@ -86,7 +203,7 @@ where
 {
     /// Creates a new `TextureBuilder` with the given raw texture data.
     pub fn new(data: D) -> Self {
-        use gfx::format::{ChannelTyped, SurfaceTyped};
+        use gfx::format::SurfaceTyped;
         use gfx::memory::Bind;
         use gfx::memory::Usage;
         use gfx::texture::{AaMode, Kind};
@@ -100,8 +217,10 @@ where
                 bind: Bind::SHADER_RESOURCE,
                 usage: Usage::Dynamic,
             },
-            channel_type: ChannelFormat::get_channel_type(),
+            channel_type: ChannelType::Unorm,
             sampler: SamplerInfo::new(FilterMethod::Scale, WrapMode::Clamp),
+            mip_data: false,
+            generate_mipmaps: false,
             pd: PhantomData,
         }
     }
@@ -120,6 +239,26 @@ where
         self
     }
 
+    /// Marks the data as holding every mip level, from the largest to the
+    /// smallest, instead of only the first one.
+    ///
+    /// Cubemap data holds all levels of a face before the next face. Required
+    /// for block compressed formats.
+    pub fn with_mip_data(mut self) -> Self {
+        self.mip_data = true;
+        self
+    }
+
+    /// Generates the full chain of mip levels from the data when building.
+    ///
+    /// Supported for 2D textures with 8 bits per channel RGBA or BGRA data.
+    /// The levels are box filtered, in linear space for sRGB data. Samplers
+    /// without mipmapping are upgraded to the matching mipmapped filter.
+    pub fn with_generated_mipmaps(mut self) -> Self {
+        self.generate_mipmaps = true;
+        self
+    }
+
     /// Sets the texture width and height in pixels.
     pub fn with_size(mut self, w: u16, h: u16) -> Self {
         use gfx::texture::{AaMode, Kind};
@@ -127,6 +266,16 @@ where
         self
     }
 
+    /// Makes the texture a cubemap with square faces of the given size.
+    ///
+    /// The data has to contain the six faces one after another, in the order
+    /// +X, -X, +Y, -Y, +Z, -Z.
+    pub fn with_cube_size(mut self, size: u16) -> Self {
+        use gfx::texture::Kind;
+        self.info.kind = Kind::Cube(size);
+        self
+    }
+
     /// Sets whether the texture is mutable or not.
     pub fn dynamic(mut self, mutable: bool) -> Self {
         use gfx::memory::Usage;
@@ -154,12 +303,43 @@ where
         use gfx::texture::ResourceDesc;
         use std::mem::size_of;
 
+        if self.mip_data {
+            return self.build_mip_data(fac);
+        }
+        if self.generate_mipmaps && !self.info.kind.is_cube() {
+            let (w, h, _, _) = self.info.kind.get_dimensions();
+            let srgb = self.channel_type == ChannelType::Srgb;
+            let bytes: &[u8] = cast_slice(self.data.as_ref());
+            if let Some((data, levels)) = generate_mipmaps(bytes, self.info.format, w, h, srgb) {
+                let mut sampler = self.sampler;
+                sampler.filter = match sampler.filter {
+                    FilterMethod::Scale => FilterMethod::Mipmap,
+                    FilterMethod::Bilinear => FilterMethod::Trilinear,
+                    filter => filter,
+                };
+                return TextureBuilder {
+                    data,
+                    info: Info { levels, ..self.info },
+                    channel_type: self.channel_type,
+                    sampler,
+                    mip_data: true,
+                    generate_mipmaps: false,
+                    pd: PhantomData,
+                }.build_mip_data(fac);
+            }
+            warn!(
+                "Mipmaps can't be generated for textures of format {:?}",
+                self.info.format
+            );
+        }
+
         // This variable has to live here to make sure the flipped
         // buffer lives long enough. (If one exists)
         let mut v_flip_buffer;
         let mut data = self.data.as_ref();
 
-        if cfg!(feature = "opengl") {
+        // Cubemap faces are already stored top to bottom.
+        if cfg!(feature = "opengl") && !self.info.kind.is_cube() {
             let pixel_width = (self.info.format.get_total_bits() / 8) as usize / size_of::<T>();
             v_flip_buffer = Vec::with_capacity(data.len());
             let (w, h, _, _) = self.info.kind.get_dimensions();
@@ -175,10 +355,17 @@ where
             data = &v_flip_buffer;
         }
 
+        let data = cast_slice(data);
+        let slices = if self.info.kind.is_cube() {
+            data.chunks((data.len() / 6).max(1)).collect::<Vec<_>>()
+        } else {
+            vec![data]
+        };
+
         let tex = fac.create_texture_raw(
             self.info,
             Some(self.channel_type),
-            Some((&[cast_slice(data)], Mipmap::Provided)),
+            Some((&slices, Mipmap::Provided)),
         )?;
 
         let desc = ResourceDesc {
@@ -196,6 +383,194 @@ where
             sampler: sampler,
             texture: tex,
             view: view,
+            region: None,
+        })
+    }
+
+    fn build_mip_data(self, fac: &mut Factory) -> Result<Texture> {
+        use gfx::Factory;
+        use gfx::format::Swizzle;
+        use gfx::memory::cast_slice;
+        use gfx::texture::{CreationError, ResourceDesc};
+
+        let data: &[u8] = cast_slice(self.data.as_ref());
+        let (w, h, _, _) = self.info.kind.get_dimensions();
+        let cube = self.info.kind.is_cube();
+        let faces = if cube { 6 } else { 1 };
+
+        let mut levels = Vec::with_capacity(faces * self.info.levels as usize);
+        let mut offset = 0;
+        for _ in 0..faces {
+            for level in 0..self.info.levels {
+                let size = mip_level_size(self.info.format, w, h, level);
+                if data.len() < offset + size {
+                    return Err(CreationError::Data(data.len()).into());
+                }
+                let level_data = &data[offset..offset + size];
+                offset += size;
+                // Cubemap faces are already stored top to bottom.
+                if cfg!(feature = "opengl") && !cube {
+                    let (lw, lh) = ((w >> level).max(1), (h >> level).max(1));
+                    levels.push(flip_level(level_data, self.info.format, lw, lh));
+                } else {
+                    levels.push(level_data.to_vec());
+                }
+            }
+        }
+        let slices = levels.iter().map(|level| &level[..]).collect::<Vec<_>>();
+
+        let tex = fac.create_texture_raw(
+            self.info,
+            Some(self.channel_type),
+            Some((&slices, Mipmap::Provided)),
+        )?;
+
+        let desc = ResourceDesc {
+            channel: self.channel_type,
+            layer: None,
+            min: 1,
+            max: self.info.levels,
+            swizzle: Swizzle::new(),
+        };
+
+        let view = fac.view_texture_as_shader_resource_raw(&tex, desc)?;
+        let sampler = fac.create_sampler(self.sampler);
+
+        Ok(Texture {
+            sampler,
+            texture: tex,
+            view,
+            region: None,
         })
     }
 }
+
+/// Returns the size in bytes of a mip level of a single texture layer.
+pub(crate) fn mip_level_size(format: SurfaceType, width: u16, height: u16, level: u8) -> usize {
+    let w = (width as usize >> level).max(1);
+    let h = (height as usize >> level).max(1);
+    match block_size(format) {
+        Some(block) => ((w + 3) / 4) * ((h + 3) / 4) * block,
+        None => w * h * format.get_total_bits() as usize / 8,
+    }
+}
+
+/// Box filters the mip levels of 8 bits per channel RGBA or BGRA data,
+/// returns the data of all levels and their count.
+fn generate_mipmaps(
+    data: &[u8],
+    format: SurfaceType,
+    width: u16,
+    height: u16,
+    srgb: bool,
+) -> Option<(Vec<u8>, u8)> {
+    match format {
+        SurfaceType::R8_G8_B8_A8 | SurfaceType::B8_G8_R8_A8 => {}
+        _ => return None,
+    }
+    let (mut w, mut h) = (width as usize, height as usize);
+    if data.len() < w * h * 4 {
+        return None;
+    }
+    let to_linear = |v: u8, channel: usize| {
+        let v = v as f32 / 255.0;
+        if srgb && channel < 3 {
+            v.powf(2.2)
+        } else {
+            v
+        }
+    };
+    let from_linear = |v: f32, channel: usize| {
+        let v = if srgb && channel < 3 { v.powf(1.0 / 2.2) } else { v };
+        (v * 255.0 + 0.5).min(255.0) as u8
+    };
+
+    let mut levels = 1;
+    let mut mips = data[..w * h * 4].to_vec();
+    let mut start = 0;
+    while w > 1 || h > 1 {
+        let (nw, nh) = ((w / 2).max(1), (h / 2).max(1));
+        let mut level = Vec::with_capacity(nw * nh * 4);
+        {
+            let src = &mips[start..];
+            for y in 0..nh {
+                for x in 0..nw {
+                    for channel in 0..4 {
+                        let (x0, y0) = ((x * 2).min(w - 1), (y * 2).min(h - 1));
+                        let (x1, y1) = ((x * 2 + 1).min(w - 1), (y * 2 + 1).min(h - 1));
+                        let sum = [(x0, y0), (x1, y0), (x0, y1), (x1, y1)]
+                            .iter()
+                            .map(|&(sx, sy)| to_linear(src[(sy * w + sx) * 4 + channel], channel))
+                            .sum::<f32>();
+                        level.push(from_linear(sum / 4.0, channel));
+                    }
+                }
+            }
+        }
+        start = mips.len();
+        mips.extend(level);
+        w = nw;
+        h = nh;
+        levels += 1;
+    }
+    Some((mips, levels))
+}
+
+/// Returns the size in bytes of a 4x4 block of block compressed formats.
+fn block_size(format: SurfaceType) -> Option<usize> {
+    match format {
+        SurfaceType::BC1_R8_G8_B8 => Some(8),
+        SurfaceType::BC3_R8_G8_B8_A8 => Some(16),
+        _ => None,
+    }
+}
+
+/// Flips a mip level upside down, as OpenGL expects the bottom row first.
+fn flip_level(data: &[u8], format: SurfaceType, width: u16, height: u16) -> Vec<u8> {
+    let (w, h) = (width as usize, height as usize);
+    let block = match block_size(format) {
+        Some(block) => block,
+        None => {
+            let row = w * format.get_total_bits() as usize / 8;
+            return data.chunks(row).rev().flat_map(|row| row).cloned().collect();
+        }
+    };
+    // Partial blocks can't be flipped in place, the level is kept as is.
+    if h > 4 && h % 4 != 0 {
+        return data.to_vec();
+    }
+    let rows = h.min(4);
+    let row = ((w + 3) / 4) * block;
+    let mut flipped = Vec::with_capacity(data.len());
+    for blocks in data.chunks(row).rev() {
+        for block in blocks.chunks(block) {
+            let mut block = block.to_vec();
+            flip_block(&mut block, format, rows);
+            flipped.extend_from_slice(&block);
+        }
+    }
+    flipped
+}
+
+/// Flips the first `rows` pixel rows of a compressed block.
+fn flip_block(block: &mut [u8], format: SurfaceType, rows: usize) {
+    // The color indices are the last 4 bytes of the block, one byte per row.
+    let colors = block.len() - 4;
+    block[colors..colors + rows].reverse();
+    if format == SurfaceType::BC3_R8_G8_B8_A8 {
+        // The alpha indices follow the two reference alphas, 12 bits per row.
+        let mut bits = 0u64;
+        for (i, byte) in block[2..8].iter().enumerate() {
+            bits |= (*byte as u64) << (i * 8);
+        }
+        let mut flipped = bits;
+        for row in 0..rows {
+            let from = (bits >> (row * 12)) & 0xFFF;
+            let to = (rows - 1 - row) * 12;
+            flipped = (flipped & !(0xFFF << to)) | (from << to);
+        }
+        for (i, byte) in block[2..8].iter_mut().enumerate() {
+            *byte = (flipped >> (i * 8)) as u8;
+        }
+    }
+}
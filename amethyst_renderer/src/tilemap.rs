@@ -0,0 +1,116 @@
+//! Tile map component.
+
+use specs::{Component, DenseVecStorage};
+
+use sprite::SpriteSheetHandle;
+
+/// Width and height of the square chunks a `TileMap` is split into.
+///
+/// The `DrawTileMap` pass builds one mesh per chunk, and only rebuilds the
+/// chunks which changed.
+pub const CHUNK_SIZE: u32 = 32;
+
+/// A grid of tiles, drawn with the `DrawTileMap` pass.
+///
+/// Each tile is the index of a sprite in the sprite sheet, or `None` for an
+/// empty cell. The grid lies in the XY plane of its entity, with tile `(0, 0)`
+/// in the bottom left corner at the origin.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileMap {
+    sprite_sheet: SpriteSheetHandle,
+    width: u32,
+    height: u32,
+    tile_size: [f32; 2],
+    tiles: Vec<Option<usize>>,
+    generations: Vec<u64>,
+}
+
+impl TileMap {
+    /// Creates an empty tile map of `width` by `height` tiles.
+    pub fn new(
+        sprite_sheet: SpriteSheetHandle,
+        width: u32,
+        height: u32,
+        tile_size: [f32; 2],
+    ) -> Self {
+        let chunks = chunk_count(width) * chunk_count(height);
+        TileMap {
+            sprite_sheet,
+            width,
+            height,
+            tile_size,
+            tiles: vec![None; (width * height) as usize],
+            generations: vec![0; chunks as usize],
+        }
+    }
+
+    /// Returns the sprite sheet the tiles are taken from.
+    pub fn sprite_sheet(&self) -> &SpriteSheetHandle {
+        &self.sprite_sheet
+    }
+
+    /// Returns the number of columns.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the number of rows.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the size of a tile in world units.
+    pub fn tile_size(&self) -> [f32; 2] {
+        self.tile_size
+    }
+
+    /// Returns the tile at the given position, `None` if the cell is empty
+    /// or outside of the map.
+    pub fn get(&self, x: u32, y: u32) -> Option<usize> {
+        if x < self.width && y < self.height {
+            self.tiles[(y * self.width + x) as usize]
+        } else {
+            None
+        }
+    }
+
+    /// Sets the tile at the given position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is outside of the map.
+    pub fn set(&mut self, x: u32, y: u32, tile: Option<usize>) {
+        assert!(
+            x < self.width && y < self.height,
+            "Tile ({}, {}) is outside of the {}x{} tile map",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        let index = (y * self.width + x) as usize;
+        if self.tiles[index] != tile {
+            self.tiles[index] = tile;
+            let chunk = (y / CHUNK_SIZE) * chunk_count(self.width) + x / CHUNK_SIZE;
+            self.generations[chunk as usize] += 1;
+        }
+    }
+
+    /// Returns the number of chunks along the x and y axis.
+    pub fn chunks(&self) -> (u32, u32) {
+        (chunk_count(self.width), chunk_count(self.height))
+    }
+
+    /// Returns a counter which changes every time a tile of the chunk is modified.
+    pub(crate) fn generation(&self, chunk_x: u32, chunk_y: u32) -> u64 {
+        self.generations[(chunk_y * chunk_count(self.width) + chunk_x) as usize]
+    }
+}
+
+impl Component for TileMap {
+    type Storage = DenseVecStorage<Self>;
+}
+
+fn chunk_count(tiles: u32) -> u32 {
+    (tiles + CHUNK_SIZE - 1) / CHUNK_SIZE
+}
@@ -7,6 +7,7 @@ use amethyst_core::cgmath::{EuclideanSpace, InnerSpace, MetricSpace, Point3, Tra
 use specs::{Component, Entities, Entity, Fetch, FetchMut, Join, NullStorage, ReadStorage, System};
 
 use cam::{ActiveCamera, Camera};
+use hidden::Hidden;
 
 /// Transparent mesh component
 #[derive(Clone, Debug, Default)]
@@ -17,6 +18,8 @@ impl Component for Transparent {
 }
 
 /// Transparent mesh entities sorted back to front
+///
+/// Filled every frame by the `TransparentSortingSystem`, which the `RenderBundle` adds.
 #[derive(Clone, Debug, Default)]
 pub struct TransparentBackToFront {
     /// Entities
@@ -25,6 +28,9 @@ pub struct TransparentBackToFront {
 
 /// Sort transparent entities back to front using the active camera.
 ///
+/// Entities behind the camera are left out, as they can't be seen anyway, along with the
+/// `Hidden` ones.
+///
 /// Note that this should run after `GlobalTransform` has been updated for the current frame, and
 /// before rendering occurs.
 pub struct TransparentSortingSystem {
@@ -47,30 +53,40 @@ impl<'a> System<'a> for TransparentSortingSystem {
         Option<Fetch<'a, ActiveCamera>>,
         ReadStorage<'a, Camera>,
         ReadStorage<'a, Transparent>,
+        ReadStorage<'a, Hidden>,
         ReadStorage<'a, GlobalTransform>,
     );
 
     fn run(
         &mut self,
-        (entities, mut back_to_front, active, camera, transparent, global): Self::SystemData,
+        (
+            entities,
+            mut back_to_front,
+            active,
+            camera,
+            transparent,
+            hidden,
+            global,
+        ): Self::SystemData,
     ) {
         let origin = Point3::origin();
 
         let camera: Option<&GlobalTransform> = active
             .and_then(|a| global.get(a.entity))
             .or_else(|| (&camera, &global).join().map(|cg| cg.1).next());
+        // Cameras look down their negative z axis.
         let camera_forward = camera
-            .map(|c| c.0.z.truncate())
-            .unwrap_or(Vector3::unit_z());
+            .map(|c| -c.0.z.truncate())
+            .unwrap_or(-Vector3::unit_z());
         let camera_centroid = camera
             .map(|g| g.0.transform_point(origin))
             .unwrap_or(origin.clone());
 
         self.centroids.clear();
         self.centroids.extend(
-            (&*entities, &transparent, &global)
+            (&*entities, &transparent, &global, !&hidden)
                 .join()
-                .map(|(entity, _, global)| (entity, global.0.transform_point(origin)))
+                .map(|(entity, _, global, _)| (entity, global.0.transform_point(origin)))
                 .map(|(entity, centroid)| {
                     (
                         entity,
@@ -1,19 +1,70 @@
 //! Direct3D 11 backend types.
 
+use gfx::Factory;
+use gfx::handle::{DepthStencilView, RenderTargetView};
 use gfx_device_dx11::{self, DeferredContext};
-use gfx_window_dxgi;
+use gfx_window_dxgi as win;
+use winit::{EventsLoop, Window as WinitWindow, WindowBuilder};
 
-/// Command buffer type.
-pub type CommandBuffer = gfx_device_dx11::CommandBuffer<DeferredContext>;
+use super::{Backend, BackendContext, ColorFormat, DepthFormat, HeadlessBackendContext};
+use config::DisplayConfig;
+use error::{Error, Result};
 
-/// Graphics device type.
-pub type Device = gfx_device_dx11::Device;
+/// The Direct3D 11 backend, only available on Windows.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Direct3D11;
 
-/// Graphics factory type.
-pub type Factory = gfx_device_dx11::Factory;
+impl Backend for Direct3D11 {
+    type CommandBuffer = gfx_device_dx11::CommandBuffer<DeferredContext>;
+    type Device = gfx_device_dx11::Deferred;
+    type Factory = gfx_device_dx11::Factory;
+    type Resources = gfx_device_dx11::Resources;
+    type Window = win::Window;
+    type HeadlessContext = ();
 
-/// Graphics resource type.
-pub type Resources = gfx_device_dx11::Resources;
+    fn init(
+        wb: WindowBuilder,
+        el: &EventsLoop,
+        _config: &DisplayConfig,
+    ) -> Result<BackendContext<Self>> {
+        // FIXME: vsync + multisampling from config
+        let (window, device, mut factory, color) = win::init::<ColorFormat>(wb, el).unwrap();
+        let device = gfx_device_dx11::Deferred::from(device);
 
-/// Window type.
-pub type Window = gfx_window_dxgi::Window;
+        let size = window
+            .get_inner_size_points()
+            .ok_or(Error::WindowDestroyed)?;
+        let (w, h) = (size.0 as u16, size.1 as u16);
+        let depth = factory.create_depth_stencil_view_only::<DepthFormat>(w, h)?;
+        Ok(BackendContext {
+            window,
+            device,
+            factory,
+            color,
+            depth,
+        })
+    }
+
+    fn init_headless(_config: &DisplayConfig) -> Result<HeadlessBackendContext<Self>> {
+        Err(Error::Backend(
+            "creating a Direct3D 11 device without a window is not implemented".to_string(),
+        ))
+    }
+
+    fn winit_window(window: &Self::Window) -> &WinitWindow {
+        &*window.0
+    }
+
+    fn update_views(
+        _window: &Self::Window,
+        _color: &mut RenderTargetView<Self::Resources, ColorFormat>,
+        _depth: &mut DepthStencilView<Self::Resources, DepthFormat>,
+    ) -> Result<()> {
+        // FIXME: recreate the swapchain views
+        Err(Error::Backend(
+            "resizing the Direct3D 11 backbuffer is not implemented".to_string(),
+        ))
+    }
+
+    fn present(_window: &Self::Window) {}
+}
@@ -1,19 +1,69 @@
-//! OpenGL backend types.
+//! Metal backend types.
 
+use gfx::Factory;
+use gfx::handle::{DepthStencilView, RenderTargetView};
 use gfx_device_metal;
-use gfx_window_metal;
+use gfx_window_metal as win;
+use winit::{EventsLoop, Window as WinitWindow, WindowBuilder};
 
-/// Command buffer type.
-pub type CommandBuffer = gfx_device_metal::CommandBuffer;
+use super::{Backend, BackendContext, ColorFormat, DepthFormat, HeadlessBackendContext};
+use config::DisplayConfig;
+use error::{Error, Result};
 
-/// Graphics device type.
-pub type Device = gfx_device_metal::Device;
+/// The Metal backend, only available on macOS.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Metal;
 
-/// Graphics factory type.
-pub type Factory = gfx_device_metal::Factory;
+impl Backend for Metal {
+    type CommandBuffer = gfx_device_metal::CommandBuffer;
+    type Device = gfx_device_metal::Device;
+    type Factory = gfx_device_metal::Factory;
+    type Resources = gfx_device_metal::Resources;
+    type Window = win::MetalWindow;
+    type HeadlessContext = ();
 
-/// Graphics resource type.
-pub type Resources = gfx_device_metal::Resources;
+    fn init(
+        wb: WindowBuilder,
+        el: &EventsLoop,
+        _config: &DisplayConfig,
+    ) -> Result<BackendContext<Self>> {
+        // FIXME: vsync + multisampling from config
+        let (window, device, mut factory, color) = win::init::<ColorFormat>(wb, el).unwrap();
 
-/// Window type.
-pub type Window = gfx_window_metal::MetalWindow;
+        let size = window
+            .get_inner_size_points()
+            .ok_or(Error::WindowDestroyed)?;
+        let (w, h) = (size.0 as u16, size.1 as u16);
+        let depth = factory.create_depth_stencil_view_only::<DepthFormat>(w, h)?;
+        Ok(BackendContext {
+            window,
+            device,
+            factory,
+            color,
+            depth,
+        })
+    }
+
+    fn init_headless(_config: &DisplayConfig) -> Result<HeadlessBackendContext<Self>> {
+        Err(Error::Backend(
+            "creating a Metal device without a window is not implemented".to_string(),
+        ))
+    }
+
+    fn winit_window(window: &Self::Window) -> &WinitWindow {
+        &window.0
+    }
+
+    fn update_views(
+        _window: &Self::Window,
+        _color: &mut RenderTargetView<Self::Resources, ColorFormat>,
+        _depth: &mut DepthStencilView<Self::Resources, DepthFormat>,
+    ) -> Result<()> {
+        // FIXME: recreate the swapchain views
+        Err(Error::Backend(
+            "resizing the Metal backbuffer is not implemented".to_string(),
+        ))
+    }
+
+    fn present(_window: &Self::Window) {}
+}
@@ -1,15 +1,24 @@
 //! Compile-time graphics API types.
 
 #[cfg(all(feature = "d3d11", target_os = "windows"))]
-pub use self::d3d11::{CommandBuffer, Device, Factory, Resources, Window};
+pub use self::d3d11::Direct3D11 as DefaultBackend;
 #[cfg(all(feature = "metal", target_os = "macos"))]
-pub use self::metal::{CommandBuffer, Device, Factory, Resources, Window};
+pub use self::metal::Metal as DefaultBackend;
 #[cfg(feature = "opengl")]
-pub use self::opengl::{CommandBuffer, Device, Factory, Resources, Window};
+pub use self::opengl::OpenGL as DefaultBackend;
 #[cfg(feature = "vulkan")]
-pub use self::vulkan::{CommandBuffer, Device, Factory, Resources, Window};
+pub use self::vulkan::Vulkan;
+
+// The Vulkan backend can't create its window and swapchain yet, so it can't be
+// the one the renderer runs on.
+#[cfg(all(feature = "vulkan", not(feature = "opengl")))]
+compile_error!("the Vulkan backend is not usable yet, enable the `opengl` feature");
 
 use gfx;
+use winit::{EventsLoop, Window as WinitWindow, WindowBuilder};
+
+use config::DisplayConfig;
+use error::Result;
 
 #[cfg(all(feature = "d3d11", target_os = "windows"))]
 mod d3d11;
@@ -20,14 +29,119 @@ mod opengl;
 #[cfg(feature = "vulkan")]
 mod vulkan;
 
+/// A graphics API the renderer runs on.
+///
+/// The passes and the pipeline only use the types of this module, such as
+/// `Factory` and `Encoder`, which are those of the `DefaultBackend` selected
+/// by the cargo features. Adding a backend means implementing this trait in a
+/// new module next to `opengl`, and selecting it with a feature, without
+/// touching the passes. Their shaders still have to be compiled for it.
+pub trait Backend: Sized {
+    /// Command buffer type.
+    type CommandBuffer: gfx::CommandBuffer<Self::Resources>;
+    /// Graphics device type.
+    type Device: gfx::Device<Resources = Self::Resources, CommandBuffer = Self::CommandBuffer>;
+    /// Graphics factory type.
+    type Factory: gfx::Factory<Self::Resources> + Clone;
+    /// Graphics resource type.
+    type Resources: gfx::Resources;
+    /// Window type.
+    type Window;
+    /// Context type of a device created without a window.
+    type HeadlessContext;
+
+    /// Creates the window along with the device and its backbuffer.
+    fn init(
+        wb: WindowBuilder,
+        el: &EventsLoop,
+        config: &DisplayConfig,
+    ) -> Result<BackendContext<Self>>;
+
+    /// Creates the device without a window, drawing into offscreen targets
+    /// only.
+    ///
+    /// Returns `Error::Backend` if the backend can't create a device without
+    /// a window.
+    fn init_headless(config: &DisplayConfig) -> Result<HeadlessBackendContext<Self>>;
+
+    /// Returns the winit window wrapped in the window of the backend.
+    fn winit_window(window: &Self::Window) -> &WinitWindow;
+
+    /// Recreates the backbuffer views after the window got resized.
+    ///
+    /// Returns `Error::Backend` if the backend can't resize its backbuffer.
+    fn update_views(
+        window: &Self::Window,
+        color: &mut gfx::handle::RenderTargetView<Self::Resources, ColorFormat>,
+        depth: &mut gfx::handle::DepthStencilView<Self::Resources, DepthFormat>,
+    ) -> Result<()>;
+
+    /// Presents the backbuffer once a frame has been drawn.
+    fn present(window: &Self::Window);
+}
+
+/// Everything created along with the window by `Backend::init`.
+pub struct BackendContext<B: Backend> {
+    /// The window drawn into.
+    pub window: B::Window,
+    /// The graphics device.
+    pub device: B::Device,
+    /// The graphics factory.
+    pub factory: B::Factory,
+    /// Color view of the backbuffer.
+    pub color: gfx::handle::RenderTargetView<B::Resources, ColorFormat>,
+    /// Depth-stencil view of the backbuffer.
+    pub depth: gfx::handle::DepthStencilView<B::Resources, DepthFormat>,
+}
+
+/// Everything created without a window by `Backend::init_headless`.
+pub struct HeadlessBackendContext<B: Backend> {
+    /// The context the device draws with, which has to stay alive.
+    pub context: B::HeadlessContext,
+    /// The graphics device.
+    pub device: B::Device,
+    /// The graphics factory.
+    pub factory: B::Factory,
+}
+
+/// Command buffer type.
+pub type CommandBuffer = <DefaultBackend as Backend>::CommandBuffer;
+
+/// Graphics device type.
+pub type Device = <DefaultBackend as Backend>::Device;
+
+/// Graphics factory type.
+pub type Factory = <DefaultBackend as Backend>::Factory;
+
+/// Graphics resource type.
+pub type Resources = <DefaultBackend as Backend>::Resources;
+
+/// Window type.
+pub type Window = <DefaultBackend as Backend>::Window;
+
+/// Context type of a device created without a window.
+pub type HeadlessContext = <DefaultBackend as Backend>::HeadlessContext;
+
 // /// Handle to a typed GPU buffer.
 // pub type Buffer<V> = gfx::handle::Buffer<Resources, V>;
 
 /// Color buffer format.
 pub type SurfaceFormat = gfx::format::R8_G8_B8_A8;
+/// Color buffer channel format.
+///
+/// With the `srgb` feature, the backbuffer and the color targets store sRGB
+/// encoded values, so that lighting happens in linear space and the hardware
+/// applies the gamma correction on write.
+#[cfg(feature = "srgb")]
+pub type ChannelFormat = gfx::format::Srgb;
+/// Color buffer channel format.
+#[cfg(not(feature = "srgb"))]
 pub type ChannelFormat = gfx::format::Unorm;
 pub type ColorFormat = (SurfaceFormat, ChannelFormat);
 
+/// High dynamic range color buffer format.
+pub type HdrFormat = gfx::format::Rgba16F;
+
 /// Depth buffer format.
 #[cfg(feature = "metal")]
 pub type DepthFormat = gfx::format::Depth32F;
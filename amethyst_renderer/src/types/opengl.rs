@@ -1,19 +1,103 @@
 //! OpenGL backend types.
 
+use gfx::handle::{DepthStencilView, RenderTargetView};
 use gfx_device_gl;
-use glutin;
+use gfx_window_glutin as win;
+use glutin::{self, GlContext, GlRequest};
+use winit::{EventsLoop, Window as WinitWindow, WindowBuilder};
 
-/// Command buffer type.
-pub type CommandBuffer = gfx_device_gl::CommandBuffer;
+use super::{Backend, BackendContext, ColorFormat, DepthFormat, HeadlessBackendContext};
+use config::DisplayConfig;
+use error::{Error, Result};
 
-/// Graphics device type.
-pub type Device = gfx_device_gl::Device;
+/// The OpenGL backend, through glutin.
+///
+/// With the `gles` feature, it creates an OpenGL ES 3.0 context instead, for
+/// Android and iOS.
+///
+/// Without a window, it draws with a glutin headless context, backed by
+/// OSMesa on Linux, so no display is needed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct OpenGL;
 
-/// Graphics factory type.
-pub type Factory = gfx_device_gl::Factory;
+impl Backend for OpenGL {
+    type CommandBuffer = gfx_device_gl::CommandBuffer;
+    type Device = gfx_device_gl::Device;
+    type Factory = gfx_device_gl::Factory;
+    type Resources = gfx_device_gl::Resources;
+    type Window = glutin::GlWindow;
+    type HeadlessContext = glutin::HeadlessContext;
 
-/// Graphics resource type.
-pub type Resources = gfx_device_gl::Resources;
+    fn init(
+        wb: WindowBuilder,
+        el: &EventsLoop,
+        config: &DisplayConfig,
+    ) -> Result<BackendContext<Self>> {
+        let ctx = glutin::ContextBuilder::new()
+            .with_multisampling(config.multisampling)
+            .with_vsync(config.vsync);
+        #[cfg(not(feature = "gles"))]
+        let ctx = ctx.with_gl_profile(glutin::GlProfile::Core).with_gl(GlRequest::Latest);
+        // Android and iOS only provide OpenGL ES, version 3.0 is the first
+        // one with the texture functions and uniform blocks of the shaders.
+        #[cfg(feature = "gles")]
+        let ctx = ctx.with_gl(GlRequest::Specific(glutin::Api::OpenGlEs, (3, 0)));
 
-/// Window type.
-pub type Window = glutin::GlWindow;
+        let (window, device, factory, color, depth) =
+            win::init::<ColorFormat, DepthFormat>(wb, ctx, el);
+        Ok(BackendContext {
+            window,
+            device,
+            factory,
+            color,
+            depth,
+        })
+    }
+
+    fn init_headless(config: &DisplayConfig) -> Result<HeadlessBackendContext<Self>> {
+        // The context draws into the offscreen targets only, the size of its
+        // default framebuffer doesn't matter.
+        let (width, height) = config.dimensions.unwrap_or((1, 1));
+        let builder = glutin::HeadlessRendererBuilder::new(width.max(1), height.max(1));
+        #[cfg(not(feature = "gles"))]
+        let builder = builder
+            .with_gl_profile(glutin::GlProfile::Core)
+            .with_gl(GlRequest::Latest);
+        #[cfg(feature = "gles")]
+        let builder = builder.with_gl(GlRequest::Specific(glutin::Api::OpenGlEs, (3, 0)));
+
+        let context = builder.build().map_err(|e| {
+            Error::Backend(format!("failed to create a headless OpenGL context: {}", e))
+        })?;
+        unsafe { context.make_current() }.map_err(|e| {
+            Error::Backend(format!(
+                "failed to activate the headless OpenGL context: {}",
+                e
+            ))
+        })?;
+        let (device, factory) =
+            gfx_device_gl::create(|symbol| context.get_proc_address(symbol) as *const _);
+        Ok(HeadlessBackendContext {
+            context,
+            device,
+            factory,
+        })
+    }
+
+    fn winit_window(window: &Self::Window) -> &WinitWindow {
+        window.window()
+    }
+
+    fn update_views(
+        window: &Self::Window,
+        color: &mut RenderTargetView<Self::Resources, ColorFormat>,
+        depth: &mut DepthStencilView<Self::Resources, DepthFormat>,
+    ) -> Result<()> {
+        win::update_views(window, color, depth);
+        Ok(())
+    }
+
+    fn present(window: &Self::Window) {
+        window.swap_buffers().expect("OpenGL context has been lost");
+    }
+}
@@ -1,20 +1,59 @@
 //! Vulkan backend types.
 
-use super::ColorFormat;
+use gfx::handle::{DepthStencilView, RenderTargetView};
 use gfx_device_vulkan;
 use gfx_window_vulkan;
+use winit::{EventsLoop, Window as WinitWindow, WindowBuilder};
 
-/// Command buffer type.
-pub type CommandBuffer = gfx_device_vulkan::CommandBuffer;
+use super::{Backend, BackendContext, ColorFormat, DepthFormat, HeadlessBackendContext};
+use config::DisplayConfig;
+use error::{Error, Result};
 
-/// Graphics device type.
-pub type Device = gfx_device_vulkan::GraphicsQueue;
+/// The Vulkan backend.
+///
+/// It can't create its window yet, so it is not selectable as the
+/// `DefaultBackend`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Vulkan;
 
-/// Graphics factory type.
-pub type Factory = gfx_device_vulkan::Factory;
+impl Backend for Vulkan {
+    type CommandBuffer = gfx_device_vulkan::CommandBuffer;
+    type Device = gfx_device_vulkan::GraphicsQueue;
+    type Factory = gfx_device_vulkan::Factory;
+    type Resources = gfx_device_vulkan::Resources;
+    type Window = gfx_window_vulkan::Window<ColorFormat>;
+    type HeadlessContext = ();
 
-/// Graphics resource type.
-pub type Resources = gfx_device_vulkan::Resources;
+    fn init(
+        _wb: WindowBuilder,
+        _el: &EventsLoop,
+        _config: &DisplayConfig,
+    ) -> Result<BackendContext<Self>> {
+        // FIXME: create the window and swapchain with gfx_window_vulkan
+        Err(Error::Backend(
+            "creating a Vulkan window is not implemented".to_string(),
+        ))
+    }
 
-/// Window type.
-pub type Window = gfx_window_vulkan::Window<ColorFormat>;
+    fn init_headless(_config: &DisplayConfig) -> Result<HeadlessBackendContext<Self>> {
+        Err(Error::Backend(
+            "creating a Vulkan device without a window is not implemented".to_string(),
+        ))
+    }
+
+    fn winit_window(window: &Self::Window) -> &WinitWindow {
+        &window.0
+    }
+
+    fn update_views(
+        _window: &Self::Window,
+        _color: &mut RenderTargetView<Self::Resources, ColorFormat>,
+        _depth: &mut DepthStencilView<Self::Resources, DepthFormat>,
+    ) -> Result<()> {
+        Err(Error::Backend(
+            "resizing the Vulkan backbuffer is not implemented".to_string(),
+        ))
+    }
+
+    fn present(_window: &Self::Window) {}
+}
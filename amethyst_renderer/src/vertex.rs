@@ -217,6 +217,49 @@ impl With<TexCoord> for PosTex {
     };
 }
 
+/// Vertex format with position, RGBA8 color and UV texture coordinate attributes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PosColorTex {
+    /// Position of the vertex in 3D space.
+    pub position: [f32; 3],
+    /// RGBA color value of the vertex.
+    pub color: [f32; 4],
+    /// UV texture coordinates used by the vertex.
+    pub tex_coord: [f32; 2],
+}
+
+unsafe impl Pod for PosColorTex {}
+
+impl VertexFormat for PosColorTex {
+    const ATTRIBUTES: Attributes<'static> = &[
+        (Position::NAME, <Self as With<Position>>::FORMAT),
+        (Color::NAME, <Self as With<Color>>::FORMAT),
+        (TexCoord::NAME, <Self as With<TexCoord>>::FORMAT),
+    ];
+}
+
+impl With<Position> for PosColorTex {
+    const FORMAT: AttributeFormat = Element {
+        offset: 0,
+        format: Position::FORMAT,
+    };
+}
+
+impl With<Color> for PosColorTex {
+    const FORMAT: AttributeFormat = Element {
+        offset: Position::SIZE,
+        format: Color::FORMAT,
+    };
+}
+
+impl With<TexCoord> for PosColorTex {
+    const FORMAT: AttributeFormat = Element {
+        offset: Position::SIZE + Color::SIZE,
+        format: TexCoord::FORMAT,
+    };
+}
+
 /// Vertex format with position, normal, and UV texture coordinate attributes.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -313,6 +356,52 @@ impl With<TexCoord> for PosNormTangTex {
     };
 }
 
+/// Per instance vertex format holding the model matrix of a mesh instance.
+///
+/// The columns of the matrix are bound to the `model_0` to `model_3`
+/// attributes, and are read once per instance instead of once per vertex.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InstanceTransform {
+    /// Model matrix of the instance, column major.
+    pub model: [[f32; 4]; 4],
+}
+
+unsafe impl Pod for InstanceTransform {}
+
+impl VertexFormat for InstanceTransform {
+    const ATTRIBUTES: Attributes<'static> = &[
+        (
+            "model_0",
+            Element {
+                offset: 0,
+                format: Format(SurfaceType::R32_G32_B32_A32, ChannelType::Float),
+            },
+        ),
+        (
+            "model_1",
+            Element {
+                offset: 16,
+                format: Format(SurfaceType::R32_G32_B32_A32, ChannelType::Float),
+            },
+        ),
+        (
+            "model_2",
+            Element {
+                offset: 32,
+                format: Format(SurfaceType::R32_G32_B32_A32, ChannelType::Float),
+            },
+        ),
+        (
+            "model_3",
+            Element {
+                offset: 48,
+                format: Format(SurfaceType::R32_G32_B32_A32, ChannelType::Float),
+            },
+        ),
+    ];
+}
+
 /// Vertex combo
 pub type VertexBufferCombination = (
     Vec<Separate<Position>>,
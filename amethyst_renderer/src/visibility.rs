@@ -0,0 +1,198 @@
+//! Frustum culling of meshes.
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::GlobalTransform;
+use amethyst_core::cgmath::{EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Transform,
+                            Vector4};
+use hibitset::BitSet;
+use specs::{Component, DenseVecStorage, Entities, Entity, Fetch, FetchMut, Join, ReadStorage,
+            System, WriteStorage};
+
+use cam::{ActiveCamera, Camera};
+use mesh::{Mesh, MeshHandle};
+
+/// Sphere enclosing a mesh, in the local space of the mesh.
+///
+/// Inserted by `FrustumCullingSystem` for every entity with a loaded mesh that
+/// doesn't have one yet. Add it yourself for meshes deformed by skinning or
+/// morph targets, the bounds computed from the vertices don't account for it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundingSphere {
+    /// Center of the sphere.
+    pub center: Point3<f32>,
+    /// Radius of the sphere.
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Creates a new `BoundingSphere`.
+    pub fn new<P: Into<Point3<f32>>>(center: P, radius: f32) -> Self {
+        BoundingSphere {
+            center: center.into(),
+            radius,
+        }
+    }
+
+    /// Computes a sphere enclosing all of the given points, centered on their
+    /// axis aligned bounding box.
+    ///
+    /// Returns `None` if there are no points.
+    pub fn from_points<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Point3<f32>>,
+    {
+        let points = points.into_iter().collect::<Vec<_>>();
+        let first = match points.first() {
+            Some(first) => *first,
+            None => return None,
+        };
+        let (min, max) = points.iter().fold((first, first), |(min, max), p| {
+            (
+                Point3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                Point3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+            )
+        });
+        let center = min.midpoint(max);
+        let radius = points
+            .iter()
+            .map(|p| (*p - center).magnitude2())
+            .fold(0.0, f32::max)
+            .sqrt();
+        Some(BoundingSphere { center, radius })
+    }
+
+    /// Returns the center and radius of the sphere after applying `transform`.
+    ///
+    /// The radius is scaled by the largest scale factor of the transform.
+    pub fn transformed(&self, transform: &Matrix4<f32>) -> (Point3<f32>, f32) {
+        let scale = transform
+            .x
+            .truncate()
+            .magnitude2()
+            .max(transform.y.truncate().magnitude2())
+            .max(transform.z.truncate().magnitude2())
+            .sqrt();
+        (transform.transform_point(self.center), self.radius * scale)
+    }
+}
+
+impl Component for BoundingSphere {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Entities found inside the view frustum of the active camera.
+#[derive(Clone, Debug, Default)]
+pub struct Visibility {
+    /// Visible entities
+    pub visible: BitSet,
+}
+
+impl Visibility {
+    /// Returns `true` if the entity was found in the view frustum.
+    pub fn is_visible(&self, entity: Entity) -> bool {
+        self.visible.contains(entity.id())
+    }
+}
+
+/// Finds the meshes inside the view frustum of the active camera, or of the
+/// first camera if there's no `ActiveCamera`.
+///
+/// The drawing passes skip the entities missing from `Visibility`, except in
+/// stages with their own camera. Entities without bounds are always visible.
+///
+/// Note that this should run after `GlobalTransform` has been updated for the current frame, and
+/// before rendering occurs.
+pub struct FrustumCullingSystem;
+
+impl FrustumCullingSystem {
+    /// Create new culling system
+    pub fn new() -> Self {
+        FrustumCullingSystem
+    }
+}
+
+impl<'a> System<'a> for FrustumCullingSystem {
+    type SystemData = (
+        Entities<'a>,
+        FetchMut<'a, Visibility>,
+        Option<Fetch<'a, ActiveCamera>>,
+        Fetch<'a, AssetStorage<Mesh>>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, MeshHandle>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, BoundingSphere>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut visibility,
+            active,
+            mesh_storage,
+            camera,
+            mesh,
+            global,
+            mut bounds,
+        ): Self::SystemData,
+    ) {
+        let computed = (&*entities, &mesh, !&bounds)
+            .join()
+            .filter_map(|(entity, mesh, _)| {
+                mesh_storage
+                    .get(mesh)
+                    .and_then(Mesh::bounds)
+                    .map(|sphere| (entity, sphere.clone()))
+            })
+            .collect::<Vec<_>>();
+        for (entity, sphere) in computed {
+            bounds.insert(entity, sphere);
+        }
+
+        visibility.visible.clear();
+        let camera = active
+            .and_then(|a| match (camera.get(a.entity), global.get(a.entity)) {
+                (Some(camera), Some(global)) => Some((camera, global)),
+                _ => None,
+            })
+            .or_else(|| (&camera, &global).join().next());
+        let planes = match camera {
+            Some((camera, global)) => match global.0.invert() {
+                Some(view) => frustum_planes(&(camera.proj * view)),
+                None => return,
+            },
+            None => return,
+        };
+
+        for (entity, _, global) in (&*entities, &mesh, &global).join() {
+            let visible = match bounds.get(entity) {
+                Some(sphere) => {
+                    let (center, radius) = sphere.transformed(&global.0);
+                    let center = center.to_homogeneous();
+                    planes.iter().all(|plane| plane.dot(center) >= -radius)
+                }
+                None => true,
+            };
+            if visible {
+                visibility.visible.add(entity.id());
+            }
+        }
+    }
+}
+
+/// Extracts the normalized planes of the frustum of a view projection matrix.
+///
+/// Points inside the frustum are on the positive side of all planes.
+fn frustum_planes(m: &Matrix4<f32>) -> [Vector4<f32>; 6] {
+    let row = |i: usize| Vector4::new(m.x[i], m.y[i], m.z[i], m.w[i]);
+    let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+    let normalize = |plane: Vector4<f32>| plane / plane.truncate().magnitude();
+    [
+        normalize(w + x),
+        normalize(w - x),
+        normalize(w + y),
+        normalize(w - y),
+        normalize(w + z),
+        normalize(w - z),
+    ]
+}
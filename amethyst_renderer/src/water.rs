@@ -0,0 +1,153 @@
+//! Water planes and their reflection cameras.
+
+use amethyst_core::GlobalTransform;
+use amethyst_core::cgmath::{InnerSpace, Matrix4, Vector3};
+use specs::{Component, DenseVecStorage, Entities, Entity, Fetch, Join, ReadStorage, System,
+            WriteStorage};
+
+use cam::{ActiveCamera, Camera};
+use color::Rgba;
+use tex::TextureHandle;
+
+/// A water surface drawn by the `DrawWater` pass.
+///
+/// The surface covers the unit square centered on the entity in its local xz
+/// plane, scaled and rotated by its `GlobalTransform`, facing its local y
+/// axis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Water {
+    /// Tangent space normal map of the waves, it should be loaded with a
+    /// repeating wrap mode.
+    pub normal_map: TextureHandle,
+    /// Color of the water. Its alpha is how murky the water is: it mixes
+    /// the color over the refracted scene, or is the opacity of the surface
+    /// when the pass has no refraction target.
+    pub color: Rgba,
+    /// Number of times the normal map is repeated along each side of the
+    /// surface.
+    pub tiling: f32,
+    /// Scrolling speed of the normal map, in texture coordinates per second.
+    pub wave_velocity: [f32; 2],
+    /// Offset applied to the reflection and refraction lookups by the waves,
+    /// as a fraction of the screen.
+    pub distortion: f32,
+    /// Exponent of the fresnel term, higher values only reflect at grazing
+    /// angles.
+    pub fresnel_power: f32,
+}
+
+impl Water {
+    /// Creates calm, slightly murky blue water with the given normal map.
+    pub fn new(normal_map: TextureHandle) -> Self {
+        Water {
+            normal_map,
+            color: Rgba(0.0, 0.2, 0.3, 0.3),
+            tiling: 4.0,
+            wave_velocity: [0.02, 0.01],
+            distortion: 0.02,
+            fresnel_power: 5.0,
+        }
+    }
+}
+
+impl Component for Water {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Makes a camera entity follow the active camera mirrored below a `Water`
+/// entity.
+///
+/// A stage drawing the scene with this camera into a target provides the
+/// planar reflection read by `DrawWater::with_reflection`. The image is
+/// flipped horizontally to keep the winding of the triangles, which
+/// `DrawWater` accounts for. Geometry below the water is not clipped, so it
+/// shows up in the reflection too.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WaterReflection {
+    /// The entity with the `Water` mirroring the camera.
+    pub water: Entity,
+}
+
+impl WaterReflection {
+    /// Mirror the camera below the given water entity
+    pub fn new(water: Entity) -> Self {
+        WaterReflection { water }
+    }
+}
+
+impl Component for WaterReflection {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Updates the `Camera` and `GlobalTransform` of the `WaterReflection`
+/// entities from the active camera.
+///
+/// Note that this should run after `GlobalTransform` has been updated for the
+/// current frame, and before rendering occurs.
+pub struct WaterReflectionSystem;
+
+impl WaterReflectionSystem {
+    /// Create new water reflection system
+    pub fn new() -> Self {
+        WaterReflectionSystem
+    }
+}
+
+impl<'a> System<'a> for WaterReflectionSystem {
+    type SystemData = (
+        Entities<'a>,
+        Option<Fetch<'a, ActiveCamera>>,
+        ReadStorage<'a, Water>,
+        ReadStorage<'a, WaterReflection>,
+        WriteStorage<'a, Camera>,
+        WriteStorage<'a, GlobalTransform>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, active, water, reflection, mut camera, mut global): Self::SystemData,
+    ) {
+        let active = match active {
+            Some(active) => active.entity,
+            None => return,
+        };
+        let (proj, view) = match (camera.get(active), global.get(active)) {
+            (Some(camera), Some(global)) => (camera.proj, global.0),
+            _ => return,
+        };
+        // Mirrors the image back horizontally, so the reflection is drawn with
+        // the same winding as the scene.
+        let flip = Matrix4::from_nonuniform_scale(-1.0, 1.0, 1.0);
+
+        let mut updates = Vec::new();
+        for (entity, reflection) in (&*entities, &reflection).join() {
+            if entity == active || water.get(reflection.water).is_none() {
+                continue;
+            }
+            let plane = match global.get(reflection.water) {
+                Some(plane) => plane.0,
+                None => continue,
+            };
+            updates.push((entity, mirror(plane) * view * flip));
+        }
+        for (entity, transform) in updates {
+            if let Some(camera) = camera.get_mut(entity) {
+                camera.proj = proj;
+            }
+            global.insert(entity, GlobalTransform(transform));
+        }
+    }
+}
+
+/// Returns the reflection across the local xz plane of a transform.
+fn mirror(plane: Matrix4<f32>) -> Matrix4<f32> {
+    let normal = plane.y.truncate().normalize();
+    let distance = plane.w.truncate().dot(normal);
+    let column = |axis: Vector3<f32>, i: usize| (axis - normal * (2.0 * normal[i])).extend(0.0);
+    Matrix4::from_cols(
+        column(Vector3::unit_x(), 0),
+        column(Vector3::unit_y(), 1),
+        column(Vector3::unit_z(), 2),
+        (normal * (2.0 * distance)).extend(1.0),
+    )
+}
@@ -0,0 +1,32 @@
+//! Draw order of 2D entities.
+
+use specs::{Component, Entity, ReadStorage, VecStorage};
+
+/// Orders the drawing of an entity by the flat and sprite passes, lower values
+/// first.
+///
+/// Entities without it are drawn at `ZOrder(0)`, entities with the same order
+/// keep the order the passes would draw them in otherwise. The opaque
+/// entities and the `Transparent` ones, drawn after them, are ordered
+/// separately. An instanced `DrawFlat` also draws the entities with
+/// `CustomUniforms` after its instanced ones.
+///
+/// The drawing order decides which entity ends up on top when the depth test
+/// doesn't, so for entities at the same depth with a `LessEqual` depth test or
+/// when drawing without depth test, as is common in 2D games.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd,
+         Serialize)]
+pub struct ZOrder(pub i32);
+
+impl Component for ZOrder {
+    type Storage = VecStorage<Self>;
+}
+
+/// Sorts the items by the `ZOrder` of their entity, keeping the order of the
+/// items with the same one.
+pub(crate) fn sort_by_z_order<T, F>(items: &mut [T], z_order: &ReadStorage<ZOrder>, entity: F)
+where
+    F: Fn(&T) -> Entity,
+{
+    items.sort_by_key(|item| z_order.get(entity(item)).cloned().unwrap_or_default());
+}
@@ -44,6 +44,7 @@ where
         world.register::<UiImage>();
         world.register::<UiTransform>();
         world.register::<UiText>();
+        world.register::<Text>();
         world.register::<TextEditing>();
         world.register::<UiResize>();
         world.register::<Handle<FontAsset>>();
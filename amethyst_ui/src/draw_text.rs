@@ -0,0 +1,155 @@
+//! Screen space text, without the rest of the UI.
+
+use std::f32;
+
+use amethyst_assets::{AssetStorage, WeakHandle};
+use amethyst_renderer::{Encoder, Factory, PosTex, Resources, VertexFormat};
+use amethyst_renderer::error::Result;
+use amethyst_renderer::pipe::{Effect, NewEffect};
+use amethyst_renderer::pipe::pass::{Pass, PassData};
+use fnv::FnvHashMap as HashMap;
+use gfx::preset::blend;
+use gfx::pso::buffer::ElemStride;
+use gfx::state::ColorMask;
+use gfx_glyph::{GlyphBrush, GlyphBrushBuilder, Scale, Section};
+use specs::{Component, DenseVecStorage, Fetch, Join, ReadStorage};
+
+use super::*;
+use pass::{VertexArgs, FRAG_SRC, VERT_SRC};
+
+/// A component used to display text at a fixed position on the screen, drawn
+/// by the `DrawText` pass.
+///
+/// Unlike `UiText`, this needs no `UiTransform` and is meant for HUDs and
+/// debug overlays.
+#[derive(Clone, Debug)]
+pub struct Text {
+    /// The string rendered by this.
+    pub text: String,
+    /// The font used for rendering.
+    pub font: FontHandle,
+    /// The height of a line of text in pixels.
+    pub font_size: f32,
+    /// The color of the rendered text, using a range of 0.0 to 1.0 per channel.
+    pub color: [f32; 4],
+    /// Position of the top left corner of the text in pixels, from the top left
+    /// corner of the screen.
+    pub position: [f32; 2],
+    /// Width in pixels after which the text wraps to the next line.
+    pub max_width: Option<f32>,
+}
+
+impl Text {
+    /// Initializes a new `Text` at the top left corner of the screen.
+    ///
+    /// # Parameters
+    ///
+    /// * `font`: A handle to a `Font` asset
+    /// * `text`: the glyphs to render
+    /// * `color`: RGBA color with a maximum of 1.0 and a minimum of 0.0 for each channel
+    /// * `font_size`: a uniform scale applied to the glyphs
+    pub fn new(font: FontHandle, text: String, color: [f32; 4], font_size: f32) -> Text {
+        Text {
+            text,
+            font,
+            font_size,
+            color,
+            position: [0.0, 0.0],
+            max_width: None,
+        }
+    }
+}
+
+impl Component for Text {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Draws `Text` components.
+///
+/// Glyphs are rasterized on demand into a cache texture, with one cache per
+/// font, and all the text using the same font is drawn at once.
+pub struct DrawText {
+    glyph_brushes: HashMap<
+        u32,
+        (
+            GlyphBrush<'static, Resources, Factory>,
+            WeakHandle<FontAsset>,
+        ),
+    >,
+}
+
+impl DrawText {
+    /// Create instance of `DrawText` pass
+    pub fn new() -> Self {
+        DrawText {
+            glyph_brushes: HashMap::default(),
+        }
+    }
+}
+
+impl<'a> PassData<'a> for DrawText {
+    type Data = (Fetch<'a, AssetStorage<FontAsset>>, ReadStorage<'a, Text>);
+}
+
+impl Pass for DrawText {
+    fn compile(&mut self, effect: NewEffect) -> Result<Effect> {
+        // The glyph brushes have their own pipelines, this effect only
+        // provides the render targets.
+        use std::mem;
+        effect
+            .simple(VERT_SRC, FRAG_SRC)
+            .with_raw_constant_buffer("VertexArgs", mem::size_of::<VertexArgs>(), 1)
+            .with_raw_vertex_buffer(PosTex::ATTRIBUTES, PosTex::size() as ElemStride, 0)
+            .with_texture("albedo")
+            .with_blended_output("color", ColorMask::all(), blend::ALPHA, None)
+            .build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        factory: Factory,
+        (font_storage, text): <Self as PassData>::Data,
+    ) {
+        // Remove brushes whose fonts have been dropped.
+        self.glyph_brushes
+            .retain(|&_id, ref mut value| !value.1.is_dead());
+
+        for text in text.join() {
+            let id = text.font.id();
+            if !self.glyph_brushes.contains_key(&id) {
+                let font = match font_storage.get(&text.font) {
+                    Some(font) => font,
+                    None => continue,
+                };
+                self.glyph_brushes.insert(
+                    id,
+                    (
+                        GlyphBrushBuilder::using_font(font.0.clone()).build(factory.clone()),
+                        text.font.downgrade(),
+                    ),
+                );
+            }
+            let section = Section {
+                text: &text.text,
+                screen_position: (text.position[0], text.position[1]),
+                bounds: (text.max_width.unwrap_or(f32::INFINITY), f32::INFINITY),
+                scale: Scale::uniform(text.font_size),
+                color: text.color,
+                ..Section::default()
+            };
+            self.glyph_brushes.get_mut(&id).unwrap().0.queue(section);
+        }
+
+        let depth = match effect.data.out_depth.as_ref() {
+            Some(depth) => &depth.0,
+            None => return,
+        };
+        for &mut (ref mut brush, _) in self.glyph_brushes.values_mut() {
+            if let Err(err) = brush.draw_queued(encoder, &effect.data.out_blends[0], depth) {
+                eprintln!("Unable to draw text! Error: {:?}", err);
+            }
+        }
+    }
+}
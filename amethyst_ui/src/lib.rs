@@ -22,6 +22,7 @@ extern crate unicode_segmentation;
 extern crate winit;
 
 mod bundle;
+mod draw_text;
 mod focused;
 mod format;
 mod image;
@@ -32,6 +33,7 @@ mod transform;
 mod event;
 
 pub use self::bundle::UiBundle;
+pub use self::draw_text::{DrawText, Text};
 pub use self::event::{MouseReactive, UiEvent, UiEventType, UiMouseSystem};
 pub use self::focused::UiFocused;
 pub use self::format::{FontAsset, FontHandle, OtfFormat, TtfFormat};
@@ -23,18 +23,21 @@ use unicode_segmentation::UnicodeSegmentation;
 
 use super::*;
 
-const VERT_SRC: &[u8] = include_bytes!("shaders/vertex.glsl");
-const FRAG_SRC: &[u8] = include_bytes!("shaders/frag.glsl");
+pub(crate) const VERT_SRC: &[u8] = include_bytes!("shaders/vertex.glsl");
+pub(crate) const FRAG_SRC: &[u8] = include_bytes!("shaders/frag.glsl");
 
 #[derive(Copy, Clone, Debug)]
 #[allow(dead_code)] // This is used by the shaders
 #[repr(C)]
-struct VertexArgs {
+pub(crate) struct VertexArgs {
     proj_vec: [f32; 4],
     coord: [f32; 2],
     dimension: [f32; 2],
+    uv_transform: [f32; 4],
 }
 
+const IDENTITY_UV_TRANSFORM: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
 #[derive(Clone, Debug)]
 struct CachedDrawOrder {
     pub cached: BitSet,
@@ -231,16 +234,19 @@ impl Pass for DrawUi {
         for &(_z, entity) in &self.cached_draw_order.cache {
             // This won't panic as we guaranteed earlier these entities are present.
             let ui_transform = ui_transform.get(entity).unwrap();
+            let image = ui_image
+                .get(entity)
+                .and_then(|image| tex_storage.get(&image.texture));
             let vertex_args = VertexArgs {
                 proj_vec: proj_vec.into(),
                 coord: [ui_transform.x, ui_transform.y],
                 dimension: [ui_transform.width, ui_transform.height],
+                uv_transform: image
+                    .map(Texture::uv_transform)
+                    .unwrap_or(IDENTITY_UV_TRANSFORM),
             };
             effect.update_constant_buffer("VertexArgs", &vertex_args, encoder);
-            if let Some(image) = ui_image
-                .get(entity)
-                .and_then(|image| tex_storage.get(&image.texture))
-            {
+            if let Some(image) = image {
                 effect.data.textures.push(image.view().clone());
                 effect.data.samplers.push(image.sampler().clone());
                 effect.draw(mesh.slice(), encoder);
@@ -411,6 +417,7 @@ impl Pass for DrawUi {
                             proj_vec: proj_vec.into(),
                             coord: [pos.x, pos.y - ascent],
                             dimension: [width, height],
+                            uv_transform: IDENTITY_UV_TRANSFORM,
                         };
                         effect.update_constant_buffer("VertexArgs", &vertex_args, encoder);
                         effect.draw(mesh.slice(), encoder);
@@ -504,6 +511,7 @@ impl Pass for DrawUi {
                                 proj_vec: proj_vec.into(),
                                 coord: [x, y],
                                 dimension: [width, height],
+                                uv_transform: IDENTITY_UV_TRANSFORM,
                             };
                             effect.update_constant_buffer("VertexArgs", &vertex_args, encoder);
                             effect.draw(mesh.slice(), encoder);
@@ -538,6 +546,7 @@ fn cached_color_texture(
             let meta = TextureMetadata {
                 sampler: None,
                 mip_levels: Some(1),
+                generate_mipmaps: false,
                 size: Some((1, 1)),
                 dynamic: false,
                 format: None,
@@ -7,6 +7,7 @@ layout (std140) uniform VertexArgs {
     uniform vec4 proj_vec;
     uniform vec2 coord;
     uniform vec2 dimension;
+    uniform vec4 uv_transform;
 };
 
 in vec3 position;
@@ -23,6 +24,6 @@ void main() {
     vertex.position += vec4(coord, 0, 0);
     vertex.position *= proj_vec;
     vertex.position += vec4(-1, 1, 0, 0);
-    vertex.tex_coord = tex_coord;
+    vertex.tex_coord = tex_coord * uv_transform.zw + uv_transform.xy;
     gl_Position = vertex.position;
 }
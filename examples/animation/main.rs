@@ -2,21 +2,18 @@
 
 extern crate amethyst;
 extern crate amethyst_animation;
-extern crate genmesh;
 
 use amethyst::assets::{Handle, Loader};
 use amethyst::core::{GlobalTransform, Parent, Transform, TransformBundle};
-use amethyst::core::cgmath::{Deg, InnerSpace, Vector3};
+use amethyst::core::cgmath::Deg;
 use amethyst::ecs::{Entity, World};
 use amethyst::prelude::*;
 use amethyst::renderer::{AmbientColor, Camera, DisplayConfig, DrawShaded, ElementState, Event,
                          KeyboardInput, Light, Mesh, Pipeline, PointLight, PosNormTex, Projection,
-                         RenderBundle, Rgba, Stage, VirtualKeyCode, WindowEvent};
+                         RenderBundle, Rgba, Shape, Stage, VirtualKeyCode, WindowEvent};
 use amethyst_animation::{get_animation_set, Animation, AnimationBundle, AnimationCommand,
-                         EndControl, InterpolationFunction, Sampler, StepDirection,
-                         TransformChannel};
-use genmesh::{MapToVertices, Triangulate, Vertices};
-use genmesh::generators::SphereUV;
+                         AnimationHierarchy, EndControl, InterpolationFunction, Sampler,
+                         StepDirection, TransformChannel};
 
 const SPHERE_COLOUR: [f32; 4] = [0.0, 0.0, 1.0, 1.0]; // blue
 const AMBIENT_LIGHT_COLOUR: Rgba = Rgba(0.01, 0.01, 0.01, 1.0); // near-black
@@ -136,18 +133,6 @@ fn main() {
     }
 }
 
-fn gen_sphere(u: usize, v: usize) -> Vec<PosNormTex> {
-    SphereUV::new(u, v)
-        .vertex(|(x, y, z)| PosNormTex {
-            position: [x, y, z],
-            normal: Vector3::from([x, y, z]).normalize().into(),
-            tex_coord: [0.1, 0.1],
-        })
-        .triangulate()
-        .vertices()
-        .collect()
-}
-
 /// This function initialises a sphere and adds it to the world.
 fn initialise_sphere(world: &mut World) -> Entity {
     // Create a sphere mesh and material.
@@ -158,8 +143,15 @@ fn initialise_sphere(world: &mut World) -> Entity {
     let (mesh, material) = {
         let loader = world.read_resource::<Loader>();
 
-        let mesh: Handle<Mesh> =
-            loader.load_from_data(gen_sphere(32, 32).into(), (), &world.read_resource());
+        let sphere = Shape::Sphere {
+            segments: 32,
+            rings: 32,
+        };
+        let mesh: Handle<Mesh> = loader.load_from_data(
+            sphere.generate::<PosNormTex>(None),
+            (),
+            &world.read_resource(),
+        );
 
         let albedo = SPHERE_COLOUR.into();
 
@@ -197,11 +189,12 @@ fn initialise_sphere(world: &mut World) -> Entity {
         .with(material)
         .build();
 
-    /*let mut nodes = HashMap::default();
-    nodes.insert(0, parent_entity.clone());
-    world
-        .write()
-        .insert(parent_entity, AnimationHierarchy { nodes });*/
+    let hierarchy = AnimationHierarchy::<Transform>::from_parents(
+        parent_entity,
+        &world.entities(),
+        &world.read(),
+    );
+    world.write().insert(parent_entity, hierarchy);
     parent_entity
 }
 
@@ -258,6 +251,7 @@ fn initialise_animation(world: &mut World) -> Handle<Animation<Transform>> {
             //(0, scale_animation_handle),
             (0, TransformChannel::Rotation, rotation_animation_handle),
         ],
+        events: Vec::new(),
     };
     loader.load_from_data(animation, (), &world.read_resource())
 }
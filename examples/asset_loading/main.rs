@@ -171,7 +171,7 @@ fn initialise_lights(world: &mut World) {
     let light: Light = PointLight {
         center: [5.0, -20.0, 15.0].into(),
         intensity: 100.0,
-        radius: 1.0,
+        radius: 50.0,
         color: Rgba::white(),
         ..Default::default()
     }.into();
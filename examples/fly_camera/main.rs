@@ -78,7 +78,7 @@ fn load_assets(world: &World) -> Assets {
         ..mat_defaults.0.clone()
     };
 
-    let cube = loader.load("mesh/cube.obj", ObjFormat, (), (), &mesh_storage);
+    let cube = loader.load("mesh/cube.obj", ObjFormat, Default::default(), (), &mesh_storage);
 
     Assets { cube, red }
 }
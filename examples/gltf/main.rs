@@ -34,6 +34,9 @@ impl State for Example {
                 load_animations: true,
                 flip_v_coord: true,
                 move_to_origin: true,
+                interleaved: false,
+                compress_animations: None,
+                quantize_rotations: false,
             },
         );
 
@@ -54,6 +57,7 @@ impl State for Example {
             .with(Light::from(PointLight {
                 center: [6.0, 6.0, -6.0].into(),
                 intensity: 6.0,
+                radius: 50.0,
                 color: [0.8, 0.0, 0.0].into(),
                 ..PointLight::default()
             }))
@@ -64,6 +68,7 @@ impl State for Example {
             .with(Light::from(PointLight {
                 center: [0.0, 4.0, 4.0].into(),
                 intensity: 5.0,
+                radius: 50.0,
                 color: [0.0, 0.3, 0.7].into(),
                 ..PointLight::default()
             }))
@@ -1,22 +1,22 @@
 //! Displays spheres with physically based materials.
 
 extern crate amethyst;
-extern crate genmesh;
 
 use amethyst::assets::Loader;
-use amethyst::core::cgmath::{Deg, InnerSpace, Matrix4, Vector3};
+use amethyst::core::cgmath::{Deg, Matrix4};
 use amethyst::core::transform::GlobalTransform;
 use amethyst::prelude::*;
 use amethyst::renderer::*;
-use genmesh::{MapToVertices, Triangulate, Vertices};
-use genmesh::generators::SphereUV;
 
 struct Example;
 
 impl State for Example {
     fn on_start(&mut self, world: &mut World) {
         let mat_defaults = world.read_resource::<MaterialDefaults>().0.clone();
-        let verts = gen_sphere(32, 32).into();
+        let verts = Shape::Sphere {
+            segments: 32,
+            rings: 32,
+        }.generate::<PosNormTangTex>(None);
         let albedo = [1.0, 1.0, 1.0, 1.0].into();
 
         println!("Load mesh");
@@ -73,6 +73,7 @@ impl State for Example {
         let light1: Light = PointLight {
             center: [6.0, 6.0, -6.0].into(),
             intensity: 6.0,
+            radius: 20.0,
             color: [0.8, 0.0, 0.0].into(),
             ..PointLight::default()
         }.into();
@@ -80,6 +81,7 @@ impl State for Example {
         let light2: Light = PointLight {
             center: [6.0, -6.0, -6.0].into(),
             intensity: 5.0,
+            radius: 20.0,
             color: [0.0, 0.3, 0.7].into(),
             ..PointLight::default()
         }.into();
@@ -145,20 +147,3 @@ fn main() {
     }
 }
 
-fn gen_sphere(u: usize, v: usize) -> Vec<PosNormTangTex> {
-    SphereUV::new(u, v)
-        .vertex(|(x, y, z)| {
-            let normal = Vector3::from([x, y, z]).normalize();
-            let up = Vector3::from([0.0, 1.0, 0.0]);
-            let tangent = normal.cross(up).cross(normal);
-            PosNormTangTex {
-                position: [x, y, z],
-                normal: normal.into(),
-                tangent: tangent.into(),
-                tex_coord: [0.1, 0.1],
-            }
-        })
-        .triangulate()
-        .vertices()
-        .collect()
-}
@@ -0,0 +1,110 @@
+//! Displays a shaded sphere and its light, both loaded from a prefab.
+
+extern crate amethyst;
+
+use amethyst::assets::{AssetStorage, Handle, Loader, Prefab, PrefabLoader, PrefabLoaderSystem,
+                       RonFormat};
+use amethyst::core::cgmath::Deg;
+use amethyst::core::transform::{GlobalTransform, Transform, TransformBundle};
+use amethyst::ecs::World;
+use amethyst::prelude::*;
+use amethyst::renderer::{AmbientColor, Camera, DisplayConfig, DrawShaded, Event, KeyboardInput,
+                         Light, MaterialPrefab, MeshPrefab, Pipeline, PosNormTex, Projection,
+                         RenderBundle, Rgba, Stage, VirtualKeyCode, WindowEvent};
+
+/// The data of the entities of the prefab, any component may be missing.
+type ScenePrefab = (
+    Option<Transform>,
+    Option<MeshPrefab>,
+    Option<MaterialPrefab>,
+    Option<Light>,
+);
+
+const AMBIENT_LIGHT_COLOUR: Rgba = Rgba(0.01, 0.01, 0.01, 1.0); // near-black
+const BACKGROUND_COLOUR: [f32; 4] = [0.0, 0.0, 0.0, 0.0]; // black
+
+struct Example;
+
+impl State for Example {
+    fn on_start(&mut self, world: &mut World) {
+        // The sphere and the light are created by the `PrefabLoaderSystem` once the prefab is
+        // loaded, the entity holding the handle becoming the root of the prefab.
+        let prefab = {
+            let loader = world.read_resource::<Loader>();
+            let storage = world.read_resource::<AssetStorage<Prefab<ScenePrefab>>>();
+            PrefabLoader::new(&loader, &storage).load(
+                "prefab/sphere.ron",
+                RonFormat,
+                (),
+                (),
+            )
+        };
+        world.create_entity().with(prefab).build();
+
+        world.add_resource(AmbientColor(AMBIENT_LIGHT_COLOUR));
+        initialise_camera(world);
+    }
+
+    fn handle_event(&mut self, _: &mut World, event: Event) -> Trans {
+        match event {
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::Escape),
+                            ..
+                        },
+                    ..
+                } => Trans::Quit,
+                _ => Trans::None,
+            },
+            _ => Trans::None,
+        }
+    }
+}
+
+fn run() -> Result<(), amethyst::Error> {
+    let display_config_path = format!(
+        "{}/examples/prefab/resources/display_config.ron",
+        env!("CARGO_MANIFEST_DIR")
+    );
+
+    let resources = format!("{}/examples/assets/", env!("CARGO_MANIFEST_DIR"));
+
+    let pipe = Pipeline::build().with_stage(
+        Stage::with_backbuffer()
+            .clear_target(BACKGROUND_COLOUR, 1.0)
+            .with_pass(DrawShaded::<PosNormTex>::new()),
+    );
+
+    let config = DisplayConfig::load(&display_config_path);
+
+    let mut game = Application::build(resources, Example)?
+        .with(PrefabLoaderSystem::<ScenePrefab>::new(), "prefab_loader", &[])
+        .with_bundle(TransformBundle::new().with_dep(&["prefab_loader"]))?
+        .with_bundle(RenderBundle::new(pipe, Some(config)))?
+        .with_resource(AssetStorage::<Prefab<ScenePrefab>>::new())
+        .register::<Handle<Prefab<ScenePrefab>>>()
+        .build()?;
+    game.run();
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        println!("Failed to execute example: {}", e);
+        ::std::process::exit(1);
+    }
+}
+
+/// This function initialises a camera and adds it to the world.
+fn initialise_camera(world: &mut World) {
+    use amethyst::core::cgmath::Matrix4;
+    let transform =
+        Matrix4::from_translation([0.0, 0.0, -4.0].into()) * Matrix4::from_angle_y(Deg(180.));
+    world
+        .create_entity()
+        .with(Camera::from(Projection::perspective(1.3, Deg(60.0))))
+        .with(GlobalTransform(transform.into()))
+        .build();
+}
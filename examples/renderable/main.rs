@@ -343,11 +343,17 @@ fn load_assets(world: &World) -> Assets {
         ..mat_defaults.0.clone()
     };
 
-    let cube = loader.load("mesh/cube.obj", ObjFormat, (), (), &mesh_storage);
-    let cone = loader.load("mesh/cone.obj", ObjFormat, (), (), &mesh_storage);
-    let lid = loader.load("mesh/lid.obj", ObjFormat, (), (), &mesh_storage);
-    let teapot = loader.load("mesh/teapot.obj", ObjFormat, (), (), &mesh_storage);
-    let rectangle = loader.load("mesh/rectangle.obj", ObjFormat, (), (), &mesh_storage);
+    let cube = loader.load("mesh/cube.obj", ObjFormat, Default::default(), (), &mesh_storage);
+    let cone = loader.load("mesh/cone.obj", ObjFormat, Default::default(), (), &mesh_storage);
+    let lid = loader.load("mesh/lid.obj", ObjFormat, Default::default(), (), &mesh_storage);
+    let teapot = loader.load("mesh/teapot.obj", ObjFormat, Default::default(), (), &mesh_storage);
+    let rectangle = loader.load(
+        "mesh/rectangle.obj",
+        ObjFormat,
+        Default::default(),
+        (),
+        &mesh_storage,
+    );
     let font = loader.load("font/square.ttf", TtfFormat, (), (), &font_storage);
 
     Assets {
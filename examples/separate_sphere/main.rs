@@ -1,16 +1,13 @@
 //! Displays a shaded sphere to the user.
 
 extern crate amethyst;
-extern crate genmesh;
 
 use amethyst::assets::Loader;
-use amethyst::core::cgmath::{Deg, InnerSpace, Vector3};
+use amethyst::core::cgmath::Deg;
 use amethyst::core::transform::GlobalTransform;
 use amethyst::ecs::World;
 use amethyst::prelude::*;
 use amethyst::renderer::*;
-use genmesh::{MapToVertices, Triangulate, Vertices};
-use genmesh::generators::SphereUV;
 
 const SPHERE_COLOUR: [f32; 4] = [0.0, 0.0, 1.0, 1.0]; // blue
 const AMBIENT_LIGHT_COLOUR: Rgba = Rgba(0.01, 0.01, 0.01, 1.0); // near-black
@@ -79,29 +76,6 @@ fn main() {
     }
 }
 
-fn gen_sphere(u: usize, v: usize) -> ComboMeshCreator {
-    let positions = SphereUV::new(u, v)
-        .vertex(|(x, y, z)| [x, y, z])
-        .triangulate()
-        .vertices()
-        .collect::<Vec<_>>();
-
-    let normals = positions
-        .iter()
-        .map(|pos| Separate::<Normal>::new(Vector3::from(*pos).normalize().into()))
-        .collect::<Vec<_>>();
-    let tex_coords = positions
-        .iter()
-        .map(|_| Separate::<TexCoord>::new([0.1, 0.1]))
-        .collect::<Vec<_>>();
-    let positions = positions
-        .into_iter()
-        .map(|pos| Separate::<Position>::new(pos))
-        .collect::<Vec<_>>();
-
-    (positions, None, Some(tex_coords), Some(normals), None).into()
-}
-
 /// This function initialises a sphere and adds it to the world.
 fn initialise_sphere(world: &mut World) {
     // Create a sphere mesh and material.
@@ -112,8 +86,12 @@ fn initialise_sphere(world: &mut World) {
     let (mesh, material) = {
         let loader = world.read_resource::<Loader>();
 
+        let sphere = Shape::Sphere {
+            segments: 32,
+            rings: 32,
+        };
         let mesh: Handle<Mesh> =
-            loader.load_from_data(gen_sphere(32, 32).into(), (), &world.read_resource());
+            loader.load_from_data(sphere.generate_separate(None), (), &world.read_resource());
 
         let albedo = SPHERE_COLOUR.into();
 
@@ -682,8 +682,8 @@ impl<'a, 'b, T> ApplicationBuilder<'a, 'b, T> {
     ///
     ///         let loader = world.read_resource::<Loader>();
     ///         // Load a teapot mesh from the directory that registered above.
-    ///         let mesh = loader.load_from("teapot", ObjFormat, (), "custom_directory",
-    ///                                     (), &storage);
+    ///         let mesh = loader.load_from("teapot", ObjFormat, Default::default(),
+    ///                                     "custom_directory", (), &storage);
     ///     }
     /// }
     /// ~~~